@@ -0,0 +1,454 @@
+//! Assertions for testing graphs and datasets.
+//!
+//! The macros of this module are similar to [`assert!`] and [`assert_eq!`],
+//! but when they fail, their message describes the graph or dataset under test,
+//! which makes failing tests much easier to diagnose
+//! than a chain of `assert!(g.contains(...)?)`:
+//!
+//! * [`assert_graph_contains!`] checks that a graph contains a given triple,
+//! * [`assert_isomorphic!`] checks that two graphs (or datasets) are isomorphic,
+//!   and displays their differences otherwise,
+//! * [`assert_quad_count!`] checks the number of quads matching a pattern in a dataset.
+//!
+//! Like [`assert!`], they all accept an optional custom message.
+//!
+//! The functions of this module are used by these macros,
+//! and can also be used to write other assertions.
+//!
+//! # Example
+//! ```
+//! # use sophia_api::{assert_graph_contains, assert_isomorphic, assert_quad_count};
+//! # use sophia_api::ns::{rdf, rdfs};
+//! # use sophia_api::term::{matcher::ANY, SimpleIri};
+//! let g = vec![
+//!     [rdfs::Class, rdf::type_, rdfs::Class],
+//!     [rdfs::Resource, rdf::type_, rdfs::Class],
+//! ];
+//! assert_graph_contains!(g, rdfs::Resource, rdf::type_, rdfs::Class);
+//!
+//! let mut h = g.clone();
+//! h.reverse();
+//! assert_isomorphic!(g, h, "order does not matter");
+//!
+//! let d = vec![
+//!     ([rdfs::Class, rdf::type_, rdfs::Class], None),
+//!     ([rdfs::Resource, rdf::type_, rdfs::Class], Some(rdfs::Class)),
+//! ];
+//! assert_quad_count!(d, 2);
+//! assert_quad_count!(d, [ANY, rdf::type_, ANY, None as Option<&SimpleIri>], 1);
+//! ```
+//!
+//! [`assert!`]: https://doc.rust-lang.org/std/macro.assert.html
+//! [`assert_eq!`]: https://doc.rust-lang.org/std/macro.assert_eq.html
+//! [`assert_graph_contains!`]: ../macro.assert_graph_contains.html
+//! [`assert_isomorphic!`]: ../macro.assert_isomorphic.html
+//! [`assert_quad_count!`]: ../macro.assert_quad_count.html
+
+use crate::quad::Quad;
+use crate::term::term_to_string;
+use crate::triple::Triple;
+use std::collections::BTreeSet;
+
+/// Format `triple` as an N-Triples-like line.
+pub fn triple_to_string<T: Triple>(triple: &T) -> String {
+    format!(
+        "{} {} {} .",
+        term_to_string(triple.s()),
+        term_to_string(triple.p()),
+        term_to_string(triple.o()),
+    )
+}
+
+/// Format `quad` as an N-Quads-like line.
+pub fn quad_to_string<Q: Quad>(quad: &Q) -> String {
+    match quad.g() {
+        None => format!(
+            "{} {} {} .",
+            term_to_string(quad.s()),
+            term_to_string(quad.p()),
+            term_to_string(quad.o()),
+        ),
+        Some(g) => format!(
+            "{} {} {} {} .",
+            term_to_string(quad.s()),
+            term_to_string(quad.p()),
+            term_to_string(quad.o()),
+            term_to_string(g),
+        ),
+    }
+}
+
+/// Format the triples of a [`TripleSource`] as sorted N-Triples-like lines,
+/// e.g. the result of [`Graph::triples`].
+///
+/// [`TripleSource`]: ../triple/stream/trait.TripleSource.html
+/// [`Graph::triples`]: ../graph/trait.Graph.html#tymethod.triples
+pub fn triple_lines<I, T, E>(triples: I) -> Result<Vec<String>, E>
+where
+    I: IntoIterator<Item = Result<T, E>>,
+    T: Triple,
+{
+    let mut lines = triples
+        .into_iter()
+        .map(|t| t.map(|t| triple_to_string(&t)))
+        .collect::<Result<Vec<_>, _>>()?;
+    lines.sort();
+    Ok(lines)
+}
+
+/// Format the quads of a [`QuadSource`] as sorted N-Quads-like lines,
+/// e.g. the result of [`Dataset::quads`].
+///
+/// [`QuadSource`]: ../quad/stream/trait.QuadSource.html
+/// [`Dataset::quads`]: ../dataset/trait.Dataset.html#tymethod.quads
+pub fn quad_lines<I, Q, E>(quads: I) -> Result<Vec<String>, E>
+where
+    I: IntoIterator<Item = Result<Q, E>>,
+    Q: Quad,
+{
+    let mut lines = quads
+        .into_iter()
+        .map(|q| q.map(|q| quad_to_string(&q)))
+        .collect::<Result<Vec<_>, _>>()?;
+    lines.sort();
+    Ok(lines)
+}
+
+/// Describe the differences between two lists of lines,
+/// as produced by [`triple_lines`] or [`quad_lines`].
+///
+/// Lines only present in `left` are prefixed with `-`,
+/// lines only present in `right` are prefixed with `+`.
+///
+/// NB: blank nodes are compared by their labels,
+/// so isomorphic graphs using different labels will still have differences.
+///
+/// [`triple_lines`]: fn.triple_lines.html
+/// [`quad_lines`]: fn.quad_lines.html
+pub fn diff_lines(left: &[String], right: &[String]) -> String {
+    let left: BTreeSet<_> = left.iter().collect();
+    let right: BTreeSet<_> = right.iter().collect();
+    let mut diff = String::new();
+    for line in left.difference(&right) {
+        diff.push_str("- ");
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    for line in right.difference(&left) {
+        diff.push_str("+ ");
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    diff
+}
+
+/// The custom message of an assertion, or a default one.
+#[doc(hidden)]
+pub fn message(args: std::fmt::Arguments) -> String {
+    let msg = args.to_string();
+    if msg.is_empty() {
+        "assertion failed".to_string()
+    } else {
+        msg
+    }
+}
+
+/// Indent `lines` for inclusion in an assertion message.
+#[doc(hidden)]
+pub fn indent(lines: &[String]) -> String {
+    let mut txt = String::new();
+    for line in lines {
+        txt.push_str("    ");
+        txt.push_str(line);
+        txt.push('\n');
+    }
+    txt
+}
+
+/// Assert that a graph contains a given triple.
+///
+/// Arguments are a [`Graph`] and three terms (not references to them),
+/// optionally followed by a custom message, as in [`assert!`].
+/// On failure, the whole content of the graph is displayed.
+///
+/// See also [module `assert`](assert/index.html).
+///
+/// [`Graph`]: graph/trait.Graph.html
+/// [`assert!`]: https://doc.rust-lang.org/std/macro.assert.html
+#[macro_export]
+macro_rules! assert_graph_contains {
+    ($g:expr, $s:expr, $p:expr, $o:expr $(,)?) => {
+        $crate::assert_graph_contains!($g, $s, $p, $o, "")
+    };
+    ($g:expr, $s:expr, $p:expr, $o:expr, $($arg:tt)+) => {{
+        #[allow(unused_imports)]
+        use $crate::graph::Graph as _;
+        let (g, s, p, o) = (&$g, &$s, &$p, &$o);
+        if !g.contains(s, p, o).expect("assert_graph_contains! failed to query the graph") {
+            let lines = $crate::assert::triple_lines(g.triples())
+                .expect("assert_graph_contains! failed to read the graph");
+            panic!(
+                "{}: graph does not contain {} {} {} .\ngraph ({} triples):\n{}",
+                $crate::assert::message(format_args!($($arg)+)),
+                $crate::term::term_to_string(s),
+                $crate::term::term_to_string(p),
+                $crate::term::term_to_string(o),
+                lines.len(),
+                $crate::assert::indent(&lines),
+            );
+        }
+    }};
+}
+
+/// Assert that two graphs are isomorphic,
+/// using [`isomorphic_graphs`].
+///
+/// Arguments are two [`Graph`]s,
+/// optionally followed by a custom message, as in [`assert!`].
+/// Two [`Dataset`]s can also be compared,
+/// using [`isomorphic_datasets`], by prefixing them with `dataset`:
+/// `assert_isomorphic!(dataset d1, d2)`.
+///
+/// On failure, the [differences](assert/fn.diff_lines.html)
+/// between both graphs (resp. datasets) are displayed.
+///
+/// See also [module `assert`](assert/index.html).
+///
+/// [`isomorphic_graphs`]: graph/fn.isomorphic_graphs.html
+/// [`isomorphic_datasets`]: dataset/fn.isomorphic_datasets.html
+/// [`Graph`]: graph/trait.Graph.html
+/// [`Dataset`]: dataset/trait.Dataset.html
+/// [`assert!`]: https://doc.rust-lang.org/std/macro.assert.html
+#[macro_export]
+macro_rules! assert_isomorphic {
+    (dataset $left:expr, $right:expr $(,)?) => {
+        $crate::assert_isomorphic!(dataset $left, $right, "")
+    };
+    (dataset $left:expr, $right:expr, $($arg:tt)+) => {{
+        #[allow(unused_imports)]
+        use $crate::dataset::Dataset as _;
+        let (left, right) = (&$left, &$right);
+        if !$crate::dataset::isomorphic_datasets(left, right)
+            .expect("assert_isomorphic! failed to compare the datasets")
+        {
+            let left = $crate::assert::quad_lines(left.quads())
+                .expect("assert_isomorphic! failed to read the left dataset");
+            let right = $crate::assert::quad_lines(right.quads())
+                .expect("assert_isomorphic! failed to read the right dataset");
+            panic!(
+                "{}: datasets are not isomorphic\n(left: {} quads, right: {} quads)\n{}",
+                $crate::assert::message(format_args!($($arg)+)),
+                left.len(),
+                right.len(),
+                $crate::assert::diff_lines(&left, &right),
+            );
+        }
+    }};
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_isomorphic!($left, $right, "")
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {{
+        #[allow(unused_imports)]
+        use $crate::graph::Graph as _;
+        let (left, right) = (&$left, &$right);
+        if !$crate::graph::isomorphic_graphs(left, right)
+            .expect("assert_isomorphic! failed to compare the graphs")
+        {
+            let left = $crate::assert::triple_lines(left.triples())
+                .expect("assert_isomorphic! failed to read the left graph");
+            let right = $crate::assert::triple_lines(right.triples())
+                .expect("assert_isomorphic! failed to read the right graph");
+            panic!(
+                "{}: graphs are not isomorphic\n(left: {} triples, right: {} triples)\n{}",
+                $crate::assert::message(format_args!($($arg)+)),
+                left.len(),
+                right.len(),
+                $crate::assert::diff_lines(&left, &right),
+            );
+        }
+    }};
+}
+
+/// Assert that a dataset contains a given number of quads,
+/// possibly only counting those matching a pattern.
+///
+/// The first argument is a [`Dataset`].
+/// It is optionally followed by an array of four matchers,
+/// as accepted by [`Dataset::quads_matching`].
+/// The next argument is the expected number of quads,
+/// optionally followed by a custom message, as in [`assert!`].
+///
+/// On failure, the matching quads are displayed.
+///
+/// See also [module `assert`](assert/index.html).
+///
+/// [`Dataset`]: dataset/trait.Dataset.html
+/// [`Dataset::quads_matching`]: dataset/trait.Dataset.html#method.quads_matching
+/// [`assert!`]: https://doc.rust-lang.org/std/macro.assert.html
+#[macro_export]
+macro_rules! assert_quad_count {
+    ($d:expr, [$ms:expr, $mp:expr, $mo:expr, $mg:expr $(,)?], $n:expr $(,)?) => {
+        $crate::assert_quad_count!($d, [$ms, $mp, $mo, $mg], $n, "")
+    };
+    ($d:expr, [$ms:expr, $mp:expr, $mo:expr, $mg:expr $(,)?], $n:expr, $($arg:tt)+) => {{
+        #[allow(unused_imports)]
+        use $crate::dataset::Dataset as _;
+        let (d, ms, mp, mo, mg) = (&$d, &$ms, &$mp, &$mo, &$mg);
+        let lines = $crate::assert::quad_lines(d.quads_matching(ms, mp, mo, mg))
+            .expect("assert_quad_count! failed to read the dataset");
+        let expected: usize = $n;
+        if lines.len() != expected {
+            panic!(
+                "{}: expected {} matching quads, found {}\n{}",
+                $crate::assert::message(format_args!($($arg)+)),
+                expected,
+                lines.len(),
+                $crate::assert::indent(&lines),
+            );
+        }
+    }};
+    ($d:expr, $n:expr $(,)?) => {
+        $crate::assert_quad_count!($d, $n, "")
+    };
+    ($d:expr, $n:expr, $($arg:tt)+) => {{
+        #[allow(unused_imports)]
+        use $crate::dataset::Dataset as _;
+        let d = &$d;
+        let lines = $crate::assert::quad_lines(d.quads())
+            .expect("assert_quad_count! failed to read the dataset");
+        let expected: usize = $n;
+        if lines.len() != expected {
+            panic!(
+                "{}: expected {} quads, found {}\n{}",
+                $crate::assert::message(format_args!($($arg)+)),
+                expected,
+                lines.len(),
+                $crate::assert::indent(&lines),
+            );
+        }
+    }};
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::ns::{rdf, rdfs, xsd};
+    use crate::term::matcher::ANY;
+    use crate::term::test::TestTerm;
+    use crate::term::SimpleIri;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn panic_message<F: FnOnce()>(f: F) -> String {
+        let err = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        match err.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(err) => err.downcast_ref::<&str>().unwrap().to_string(),
+        }
+    }
+
+    fn graph() -> Vec<[TestTerm<&'static str>; 3]> {
+        vec![
+            [rdfs::Class.into(), rdf::type_.into(), rdfs::Class.into()],
+            [
+                TestTerm::bnode("b"),
+                rdfs::label.into(),
+                TestTerm::lit_dt("b", xsd::string),
+            ],
+        ]
+    }
+
+    #[test]
+    fn graph_contains() {
+        let g = graph();
+        assert_graph_contains!(g, rdfs::Class, rdf::type_, rdfs::Class);
+        assert_graph_contains!(
+            &g,
+            rdfs::Class,
+            rdf::type_,
+            rdfs::Class,
+            "with {}",
+            "message"
+        );
+
+        let msg = panic_message(|| {
+            assert_graph_contains!(g, rdfs::Class, rdf::type_, rdfs::Resource, "custom {}", 42)
+        });
+        assert!(msg.starts_with(
+            "custom 42: graph does not contain <http://www.w3.org/2000/01/rdf-schema#Class> \
+             <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \
+             <http://www.w3.org/2000/01/rdf-schema#Resource> .\n"
+        ));
+        assert!(msg.contains("graph (2 triples):\n    "));
+        assert!(msg.contains("    _:b <http://www.w3.org/2000/01/rdf-schema#label> \"b\" .\n"));
+    }
+
+    #[test]
+    fn isomorphic() {
+        let g1 = graph();
+        let mut g2 = graph();
+        g2.reverse();
+        g2[0][0] = TestTerm::bnode("c");
+        assert_isomorphic!(g1, g2);
+
+        g2[1][2] = rdfs::Resource.into();
+        let msg = panic_message(|| assert_isomorphic!(g1, g2));
+        assert!(msg.contains("(left: 2 triples, right: 2 triples)"));
+        assert!(msg.contains(
+            "\n- <http://www.w3.org/2000/01/rdf-schema#Class> \
+             <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \
+             <http://www.w3.org/2000/01/rdf-schema#Class> .\n"
+        ));
+        assert!(msg.contains(
+            "\n+ <http://www.w3.org/2000/01/rdf-schema#Class> \
+             <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \
+             <http://www.w3.org/2000/01/rdf-schema#Resource> .\n"
+        ));
+        // blank nodes are compared by their labels
+        assert!(msg.contains("\n- _:b <"));
+        assert!(msg.contains("\n+ _:c <"));
+    }
+
+    #[test]
+    fn isomorphic_datasets() {
+        let d1 = vec![
+            ([rdfs::Class, rdf::type_, rdfs::Class], None),
+            ([rdfs::Class, rdf::type_, rdfs::Class], Some(rdfs::Class)),
+        ];
+        let mut d2 = d1.clone();
+        d2.reverse();
+        assert_isomorphic!(dataset d1, d2);
+
+        d2.pop();
+        let msg = panic_message(|| assert_isomorphic!(dataset d1, d2, "oops"));
+        assert!(msg.contains("oops: datasets are not isomorphic\n(left: 2 quads, right: 1 quads)"));
+        assert!(msg.contains(
+            "\n- <http://www.w3.org/2000/01/rdf-schema#Class> \
+             <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \
+             <http://www.w3.org/2000/01/rdf-schema#Class> .\n"
+        ));
+    }
+
+    #[test]
+    fn quad_count() {
+        let d = vec![
+            ([rdfs::Class, rdf::type_, rdfs::Class], None),
+            ([rdfs::Resource, rdf::type_, rdfs::Class], Some(rdfs::Class)),
+            (
+                [rdfs::Resource, rdfs::label, rdfs::Class],
+                Some(rdfs::Class),
+            ),
+        ];
+        assert_quad_count!(d, 3);
+        assert_quad_count!(d, [ANY, rdf::type_, ANY, ANY], 2);
+        assert_quad_count!(d, [ANY, ANY, ANY, None as Option<&SimpleIri>], 1);
+
+        let msg = panic_message(|| assert_quad_count!(d, [rdfs::Resource, ANY, ANY, ANY], 1));
+        assert!(msg.starts_with("assertion failed: expected 1 matching quads, found 2"));
+        assert!(msg.contains("#label>"));
+        let msg = panic_message(|| assert_quad_count!(d, 4, "in {}", "d"));
+        assert!(msg.starts_with("in d: expected 4 quads, found 3\n"));
+    }
+}
@@ -7,7 +7,7 @@ use std::hash::Hash;
 use resiter::filter::*;
 use resiter::map::*;
 
-use crate::dataset::adapter::DatasetGraph;
+use crate::dataset::adapter::{DatasetGraph, DedupGraph};
 use crate::quad::stream::*;
 use crate::quad::streaming_mode::*;
 use crate::quad::*;
@@ -523,12 +523,34 @@ pub trait Dataset {
         DatasetGraph::new(self, graph_name)
     }
 
+    /// Borrows the union of all graphs matching `gmatcher`.
+    ///
+    /// NB: the same triple may be yielded several times by the returned graph,
+    /// if it belongs to several of the matching graphs;
+    /// see [`union_graph_dedup`](#method.union_graph_dedup) for avoiding this.
     fn union_graph<'s, T>(&'s self, gmatcher: T) -> DatasetGraph<Self, &'s Self, T>
     where
         T: GraphNameMatcher + 's,
     {
         DatasetGraph::new(self, gmatcher)
     }
+
+    /// Borrows the union of all graphs matching `gmatcher`,
+    /// ensuring that each triple is yielded only once.
+    ///
+    /// Unlike [`union_graph`](#method.union_graph),
+    /// the returned graph implements [`SetGraph`](../graph/trait.SetGraph.html),
+    /// at the expense of keeping track of already yielded triples in each iterator.
+    fn union_graph_dedup<'s, T>(
+        &'s self,
+        gmatcher: T,
+    ) -> DedupGraph<DatasetGraph<Self, &'s Self, T>>
+    where
+        T: GraphNameMatcher + 's,
+        DTerm<Self>: Clone + Eq + Hash,
+    {
+        DedupGraph::new(DatasetGraph::new(self, gmatcher))
+    }
 }
 
 /// A dataset that can be constructed from a
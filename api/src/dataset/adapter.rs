@@ -2,6 +2,8 @@
 
 mod _dataset_graph;
 pub use _dataset_graph::*;
+mod _dedup_graph;
+pub use _dedup_graph::*;
 mod _error;
 pub use _error::*;
 mod _graph_as_dataset;
@@ -3,11 +3,12 @@
 use std::borrow::{Borrow, BorrowMut};
 use std::marker::PhantomData;
 
-use resiter::Map;
+use resiter::{Filter, Map};
 
-use crate::dataset::{Dataset, MutableDataset, SetDataset};
+use crate::dataset::{DQuadSource, Dataset, MutableDataset, SetDataset};
 use crate::graph::*;
-use crate::term::matcher::{GraphNameMatcher, ANY};
+use crate::quad::Quad;
+use crate::term::matcher::{GraphNameMatcher, TermMatcher};
 use crate::term::TTerm;
 use crate::triple::streaming_mode::{FromQuad, StreamedTriple};
 
@@ -37,6 +38,31 @@ impl<D: ?Sized, E, M: GraphNameMatcher> DatasetGraph<D, E, M> {
     }
 }
 
+impl<D, E, M> DatasetGraph<D, E, M>
+where
+    D: Dataset + ?Sized,
+    E: Borrow<D>,
+    M: GraphNameMatcher,
+{
+    /// Convert quads of the underlying dataset into triples of this graph.
+    fn wrap<'s>(&'s self, quads: DQuadSource<'s, D>) -> GTripleSource<'s, Self> {
+        Box::new(quads.map_ok(StreamedTriple::from_quad))
+    }
+
+    /// Keep only the quads accepted by the graph matcher,
+    /// and convert them into triples of this graph.
+    ///
+    /// This is only used when the graph matcher is not constant;
+    /// otherwise, the graph name is passed directly to the underlying dataset.
+    fn filter_graph<'s>(&'s self, quads: DQuadSource<'s, D>) -> GTripleSource<'s, Self> {
+        Box::new(
+            quads
+                .filter_ok(move |q| self.gmatcher.matches(q.g()))
+                .map_ok(StreamedTriple::from_quad),
+        )
+    }
+}
+
 impl<D, E, M> Graph for DatasetGraph<D, E, M>
 where
     D: Dataset + ?Sized,
@@ -47,81 +73,74 @@ where
     type Error = D::Error;
 
     fn triples(&self) -> GTripleSource<Self> {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(&ANY, &ANY, &ANY, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_g(g)),
+            None => self.filter_graph(dataset.quads()),
+        }
     }
     fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
     where
         TS: TTerm + ?Sized,
     {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(s, &ANY, &ANY, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_sg(s, g)),
+            None => self.filter_graph(dataset.quads_with_s(s)),
+        }
     }
     fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
     where
         TP: TTerm + ?Sized,
     {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(&ANY, p, &ANY, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_pg(p, g)),
+            None => self.filter_graph(dataset.quads_with_p(p)),
+        }
     }
     fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
     where
         TO: TTerm + ?Sized,
     {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(&ANY, &ANY, o, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_og(o, g)),
+            None => self.filter_graph(dataset.quads_with_o(o)),
+        }
     }
     fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
     where
         TS: TTerm + ?Sized,
         TP: TTerm + ?Sized,
     {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(s, p, &ANY, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_spg(s, p, g)),
+            None => self.filter_graph(dataset.quads_with_sp(s, p)),
+        }
     }
     fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
     where
         TS: TTerm + ?Sized,
         TO: TTerm + ?Sized,
     {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(s, &ANY, o, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_sog(s, o, g)),
+            None => self.filter_graph(dataset.quads_with_so(s, o)),
+        }
     }
     fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
     where
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized,
     {
-        Box::new(
-            self.dataset
-                .borrow()
-                .quads_matching(&ANY, p, o, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
-        )
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_pog(p, o, g)),
+            None => self.filter_graph(dataset.quads_with_po(p, o)),
+        }
     }
     fn triples_with_spo<'s, TS, TP, TO>(
         &'s self,
@@ -134,11 +153,29 @@ where
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized,
     {
-        Box::new(
+        let dataset: &D = self.dataset.borrow();
+        match self.gmatcher.constant() {
+            Some(g) => self.wrap(dataset.quads_with_spog(s, p, o, g)),
+            None => self.filter_graph(dataset.quads_with_spo(s, p, o)),
+        }
+    }
+    fn triples_matching<'s, S, P, O>(
+        &'s self,
+        ms: &'s S,
+        mp: &'s P,
+        mo: &'s O,
+    ) -> GTripleSource<'s, Self>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        // delegating to quads_matching ensures that a constant graph matcher
+        // is taken into account together with the other constant matchers
+        self.wrap(
             self.dataset
                 .borrow()
-                .quads_matching(s, p, o, &self.gmatcher)
-                .map_ok(StreamedTriple::from_quad),
+                .quads_matching(ms, mp, mo, &self.gmatcher),
         )
     }
 }
@@ -184,7 +221,10 @@ mod test {
     use crate::dataset::test::*;
     use crate::dataset::MdResult;
     use crate::dataset::*;
+    use crate::graph::test::{C1, C2, P1, P2};
+    use crate::ns::{rdf, rdfs};
     use crate::quad::stream::QuadSource;
+    use crate::term::matcher::ANY;
     use crate::term::{same_graph_name, SimpleIri, TTerm};
     use crate::triple::stream::TripleSource;
     use std::collections::HashSet;
@@ -248,4 +288,21 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_graph_triples_matching() -> MdResult<MyDataset, ()> {
+        let d: MyDataset = some_quads().collect_quads().unwrap();
+        let dg = d.graph(DG.as_ref());
+        assert_eq!(dg.triples_matching(&ANY, &ANY, &ANY).count(), 4);
+        assert_eq!(dg.triples_matching(&*C1, &ANY, &ANY).count(), 1);
+        assert_eq!(dg.triples_matching(&[&*P1, &*P2], &ANY, &ANY).count(), 2);
+        let g1 = d.graph(GN1.as_ref());
+        assert_eq!(g1.triples_matching(&*C2, &ANY, &ANY).count(), 2);
+        assert_eq!(g1.triples_matching(&*P1, &rdfs::domain, &ANY).count(), 1);
+        assert_eq!(g1.triples_matching(&[&*C1, &*C2], &ANY, &ANY).count(), 3);
+        let ug = d.union_graph(ANY);
+        assert_eq!(ug.triples_matching(&*C1, &ANY, &ANY).count(), 2);
+        assert_eq!(ug.triples_matching(&ANY, &rdf::type_, &ANY).count(), 9);
+        Ok(())
+    }
 }
@@ -0,0 +1,175 @@
+// this module is transparently re-exported by its parent `adapter`
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::graph::*;
+use crate::term::matcher::TermMatcher;
+use crate::term::TTerm;
+use crate::triple::Triple;
+
+/// A [`Graph`] adapter ensuring that no triple is yielded twice
+/// by any of its iterators.
+///
+/// This adapter is returned by
+/// [`Dataset::union_graph_dedup`](../trait.Dataset.html#method.union_graph_dedup),
+/// as the union of several graphs of a dataset may contain the same triple several times.
+///
+/// Each iterator keeps track of the triples it has already yielded,
+/// by storing a copy of their terms in a `HashSet`.
+/// The memory cost of this is therefore proportional to the number of *distinct* triples,
+/// and the cost of copying terms depends on the underlying dataset
+/// (it is typically low for datasets sharing their terms through a term index,
+/// which only need to clone a reference-counted pointer).
+///
+/// [`Graph`]: ../../graph/trait.Graph.html
+pub struct DedupGraph<G>(G);
+
+impl<G> DedupGraph<G>
+where
+    G: Graph,
+    GTerm<G>: Clone + Eq + Hash,
+{
+    /// Wrap a graph in a deduplicating adapter.
+    pub fn new(graph: G) -> DedupGraph<G> {
+        DedupGraph(graph)
+    }
+
+    /// Unwrap this adapter to get the original graph.
+    pub fn unwrap(self) -> G {
+        self.0
+    }
+
+    /// Filter out the triples that were already yielded by `triples`.
+    fn dedup<'s>(&'s self, triples: GTripleSource<'s, G>) -> GTripleSource<'s, Self> {
+        let mut seen = HashSet::new();
+        Box::new(triples.filter(move |res| match res {
+            Ok(t) => seen.insert([t.s().clone(), t.p().clone(), t.o().clone()]),
+            Err(_) => true,
+        }))
+    }
+}
+
+impl<G> Graph for DedupGraph<G>
+where
+    G: Graph,
+    GTerm<G>: Clone + Eq + Hash,
+{
+    type Triple = G::Triple;
+    type Error = G::Error;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.dedup(self.0.triples())
+    }
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_s(s))
+    }
+    fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_p(p))
+    }
+    fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_o(o))
+    }
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_sp(s, p))
+    }
+    fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_so(s, o))
+    }
+    fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_po(p, o))
+    }
+    fn triples_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.dedup(self.0.triples_with_spo(s, p, o))
+    }
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        // duplicates do not matter here
+        self.0.contains(s, p, o)
+    }
+    fn triples_matching<'s, S, P, O>(
+        &'s self,
+        ms: &'s S,
+        mp: &'s P,
+        mo: &'s O,
+    ) -> GTripleSource<'s, Self>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        self.dedup(self.0.triples_matching(ms, mp, mo))
+    }
+}
+
+impl<G> SetGraph for DedupGraph<G>
+where
+    G: Graph,
+    GTerm<G>: Clone + Eq + Hash,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dataset::test::*;
+    use crate::dataset::*;
+    use crate::graph::test::C1;
+    use crate::graph::Graph;
+    use crate::quad::stream::QuadSource;
+    use crate::term::matcher::ANY;
+    use std::collections::HashSet;
+
+    type BoxTerm = crate::term::test::TestTerm<Box<str>>;
+    type MyDataset = HashSet<([BoxTerm; 3], Option<BoxTerm>)>;
+
+    #[test]
+    fn test_union_graph_dedup() -> Result<(), Box<dyn std::error::Error>> {
+        let d: MyDataset = some_quads().collect_quads()?;
+        assert_eq!(d.union_graph(ANY).triples().count(), 18);
+        assert_eq!(d.union_graph_dedup(ANY).triples().count(), 17);
+        assert_eq!(d.union_graph(ANY).triples_with_s(&*C1).count(), 2);
+        assert_eq!(d.union_graph_dedup(ANY).triples_with_s(&*C1).count(), 1);
+        assert_eq!(
+            d.union_graph_dedup(vec![DG.as_ref(), GN2.as_ref()])
+                .triples()
+                .count(),
+            11
+        );
+        Ok(())
+    }
+}
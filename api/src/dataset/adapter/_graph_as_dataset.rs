@@ -10,7 +10,8 @@ use resiter::Map;
 use crate::dataset::*;
 use crate::graph::{Graph, MutableGraph, SetGraph};
 use crate::quad::streaming_mode::{FromTriple, StreamedQuad};
-use crate::term::TTerm;
+use crate::term::matcher::{GraphNameMatcher, TermMatcher};
+use crate::term::{SimpleIri, TTerm};
 
 use super::GraphAsDatasetError;
 
@@ -252,6 +253,32 @@ where
         self.quads_with_spo(s, p, o)
     }
 
+    fn quads_matching<'s, S, P, O, M>(
+        &'s self,
+        ms: &'s S,
+        mp: &'s P,
+        mo: &'s O,
+        mg: &'s M,
+    ) -> DQuadSource<'s, Self>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        M: GraphNameMatcher + ?Sized,
+    {
+        // the wrapped graph is the default graph of this dataset,
+        // so the graph matcher is either satisfied once and for all, or never
+        if !mg.matches(None as Option<&SimpleIri>) {
+            return Box::new(empty());
+        }
+        Box::new(
+            self.0
+                .borrow()
+                .triples_matching(ms, mp, mo)
+                .map_ok(StreamedQuad::from_triple),
+        )
+    }
+
     #[inline]
     fn contains<'s, TS, TP, TO, TG>(
         &'s self,
@@ -389,6 +416,7 @@ mod test {
     use crate::dataset::{Dataset, MutableDataset};
     use crate::graph::*;
     use crate::ns::{rdf, rdfs};
+    use crate::term::matcher::ANY;
     use crate::triple::stream::TripleSource;
     use std::collections::HashSet;
     use std::convert::Infallible;
@@ -467,6 +495,23 @@ mod test {
         assert!(ret.is_err());
     }
 
+    #[test]
+    fn as_dataset_quads_matching() -> Result<(), Box<dyn Error>> {
+        let mut g = MyGraph::new();
+        <MyGraph as MutableGraph>::insert(&mut g, &rdfs::Resource, &rdf::type_, &rdfs::Class)?;
+        <MyGraph as MutableGraph>::insert(&mut g, &rdfs::Class, &rdf::type_, &rdfs::Class)?;
+
+        let d = g.as_dataset();
+        assert_eq!(d.quads_matching(&ANY, &rdf::type_, &ANY, &ANY).count(), 2);
+        assert_eq!(d.quads_matching(&rdfs::Class, &ANY, &ANY, &DG).count(), 1);
+        assert_eq!(
+            d.quads_matching(&ANY, &ANY, &ANY, &Some(&rdfs::Class))
+                .count(),
+            0
+        );
+        Ok(())
+    }
+
     /// A DatasetAsGraph wrapped as a graph so that we can test it
     type GDG =
         DatasetGraph<GraphAsDataset<MyGraph>, GraphAsDataset<MyGraph>, Option<&'static StaticTerm>>;
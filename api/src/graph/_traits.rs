@@ -181,6 +181,12 @@ pub trait Graph {
     ///
     /// See also [`triples`](#tymethod.triples).
     ///
+    /// # Note to implementors
+    /// The default implementation delegates every combination of constant matchers
+    /// to the corresponding `triples_with_*` method,
+    /// and only filters on the remaining (non-constant) matchers.
+    /// It therefore benefits from any index used by those methods.
+    ///
     /// # Usage
     ///
     /// Term references or arrays of term references are typically used as term matchers.
@@ -246,37 +252,37 @@ pub trait Graph {
         P: TermMatcher + ?Sized,
         O: TermMatcher + ?Sized,
     {
-        match (&ms.constant(), &mp.constant(), &mo.constant()) {
+        match (ms.constant(), mp.constant(), mo.constant()) {
             (None, None, None) => {
                 Box::from(self.triples().filter_ok(move |t| {
                     ms.matches(t.s()) && mp.matches(t.p()) && mo.matches(t.o())
                 }))
             }
             (Some(s), None, None) => Box::from(
-                self.triples_with_s(*s)
+                self.triples_with_s(s)
                     .filter_ok(move |t| mp.matches(t.p()) && mo.matches(t.o())),
             ),
             (None, Some(p), None) => Box::from(
-                self.triples_with_p(*p)
+                self.triples_with_p(p)
                     .filter_ok(move |t| ms.matches(t.s()) && mo.matches(t.o())),
             ),
             (None, None, Some(o)) => Box::from(
-                self.triples_with_o(*o)
+                self.triples_with_o(o)
                     .filter_ok(move |t| ms.matches(t.s()) && mp.matches(t.p())),
             ),
             (Some(s), Some(p), None) => Box::from(
-                self.triples_with_sp(*s, *p)
+                self.triples_with_sp(s, p)
                     .filter_ok(move |t| mo.matches(t.o())),
             ),
             (Some(s), None, Some(o)) => Box::from(
-                self.triples_with_so(*s, *o)
+                self.triples_with_so(s, o)
                     .filter_ok(move |t| mp.matches(t.p())),
             ),
             (None, Some(p), Some(o)) => Box::from(
-                self.triples_with_po(*p, *o)
+                self.triples_with_po(p, o)
                     .filter_ok(move |t| ms.matches(t.s())),
             ),
-            (Some(s), Some(p), Some(o)) => self.triples_with_spo(*s, *p, *o),
+            (Some(s), Some(p), Some(o)) => self.triples_with_spo(s, p, o),
         }
     }
 
@@ -32,6 +32,7 @@
 //! they will be called, respectively,
 //! the *generalized* RDF model, and the *strict* RDF model.
 
+pub mod assert;
 pub mod dataset;
 pub mod graph;
 pub mod ns;
@@ -143,7 +143,7 @@ macro_rules! namespace {
         }
     };
     ($iri_prefix:expr, $($suffix:ident),*) => {
-        namespace!($iri_prefix, $($suffix),*;);
+        $crate::namespace!($iri_prefix, $($suffix),*;);
     };
 }
 
@@ -317,40 +317,67 @@ pub mod owl {
         // Classes
         AllDifferent,
         AllDisjointClasses,
+        AllDisjointProperties,
+        Annotation,
         AnnotationProperty,
+        AsymmetricProperty,
+        Axiom,
         Class,
         DatatypeProperty,
         FunctionalProperty,
         InverseFunctionalProperty,
         IrreflexiveProperty,
+        NamedIndividual,
+        NegativePropertyAssertion,
         ObjectProperty,
+        Ontology,
+        ReflexiveProperty,
+        Restriction,
         SymmetricProperty,
         TransitiveProperty,
         // Properties
         allValuesFrom,
+        annotatedProperty,
+        annotatedSource,
+        annotatedTarget,
         assertionProperty,
+        cardinality,
         complementOf,
+        datatypeComplementOf,
         differentFrom,
+        disjointUnionOf,
         disjointWith,
         distinctMembers,
         equivalentClass,
         equivalentProperty,
+        hasKey,
+        hasSelf,
+        hasValue,
+        imports,
         intersectionOf,
         inverseOf,
         maxCardinality,
         maxQualifiedCardinality,
         members,
+        minCardinality,
+        minQualifiedCardinality,
         onClass,
+        onDataRange,
+        onDatatype,
         oneOf,
+        onProperties,
         onProperty,
         propertyChainAxiom,
         propertyDisjointWith,
+        qualifiedCardinality,
         sameAs,
         someValuesFrom,
         sourceIndividual,
         targetIndividual,
         targetValue,
-        unionOf
+        unionOf,
+        versionIRI,
+        withRestrictions
     );
 }
 
@@ -40,17 +40,61 @@ impl Location {
             Position::LiCo(line2, column2),
         )
     }
+
+    /// The position where this location starts, if known.
+    pub fn start(&self) -> Option<&Position> {
+        match self {
+            Location::Unknown => None,
+            Location::Pos(pos) | Location::Span(pos, _) => Some(pos),
+        }
+    }
+    /// The line (starting at 1) where this location starts, if known.
+    pub fn line(&self) -> Option<usize> {
+        self.start().and_then(Position::line)
+    }
+    /// The column (starting at 1) where this location starts, if known.
+    pub fn column(&self) -> Option<usize> {
+        self.start().and_then(Position::column)
+    }
+    /// The byte-offset (starting at 0) where this location starts, if known.
+    pub fn byte_offset(&self) -> Option<usize> {
+        self.start().and_then(Position::byte_offset)
+    }
 }
 
 /// A position in a parsed stream.
 #[derive(Clone, Debug)]
 pub enum Position {
-    // Byte offset (starting at 0)
+    /// Byte offset (starting at 0)
     Offset(usize),
-    // Line-Column position (both starting at 1)
+    /// Line-Column position (both starting at 1)
     LiCo(usize, usize),
 }
 
+impl Position {
+    /// The line (starting at 1) of this position, if known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Position::Offset(_) => None,
+            Position::LiCo(line, _) => Some(*line),
+        }
+    }
+    /// The column (starting at 1) of this position, if known.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            Position::Offset(_) => None,
+            Position::LiCo(_, column) => Some(*column),
+        }
+    }
+    /// The byte-offset (starting at 0) of this position, if known.
+    pub fn byte_offset(&self) -> Option<usize> {
+        match self {
+            Position::LiCo(..) => None,
+            Position::Offset(byte_offset) => Some(*byte_offset),
+        }
+    }
+}
+
 impl fmt::Display for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -63,4 +107,43 @@ impl fmt::Display for Position {
 /// This trait is meant to be implemented by errors raised by parsers.
 pub trait WithLocation {
     fn location(&self) -> Location;
+
+    /// The byte-offset (starting at 0) of the error in the parsed data, if known.
+    ///
+    /// By default, this is the byte-offset where the [location](#tymethod.location) starts;
+    /// errors located by line and column can override it to also provide the byte-offset.
+    fn byte_offset(&self) -> Option<usize> {
+        self.location().byte_offset()
+    }
+
+    /// The offending token in the parsed data, if known.
+    fn token(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accessors() {
+        let loc = Location::from_lico(3, 5);
+        assert_eq!(loc.to_string(), "3:5");
+        assert_eq!(loc.line(), Some(3));
+        assert_eq!(loc.column(), Some(5));
+        assert_eq!(loc.byte_offset(), None);
+
+        let loc = Location::from_offsets(10, 20);
+        assert_eq!(loc.line(), None);
+        assert_eq!(loc.byte_offset(), Some(10));
+
+        let loc = Location::from_lico(1, 2);
+        assert_eq!(
+            (loc.line(), loc.column(), loc.byte_offset()),
+            (Some(1), Some(2), None)
+        );
+
+        assert!(Location::Unknown.start().is_none());
+    }
 }
@@ -1,10 +1,29 @@
 //! RDF-related languages (e.g. Turtle, SPARQL) often use prefixes to shorten IRIs.
 //! This crate provides generic traits to handle prefix maps.
+//!
+//! Prefix maps can also be used to display terms in a more readable way,
+//! with [`display_with`](trait.DisplayWith.html#method.display_with):
+//! ```
+//! # use std::collections::HashMap;
+//! # use sophia_api::ns::{rdf, xsd};
+//! # use sophia_api::prefix::DisplayWith;
+//! # use sophia_api::term::SimpleIri;
+//! let mut prefixes = HashMap::new();
+//! prefixes.insert("rdf", SimpleIri::new_unchecked(rdf::PREFIX, None));
+//! assert_eq!(format!("{}", rdf::type_.display_with(&prefixes)), "rdf:type");
+//! // IRIs without a matching prefix are displayed in full
+//! assert_eq!(
+//!     format!("{}", xsd::string.display_with(&prefixes)),
+//!     "<http://www.w3.org/2001/XMLSchema#string>",
+//! );
+//! ```
 
-use crate::term::{SimpleIri, TTerm, TermKind};
+use crate::ns::xsd;
+use crate::term::{term_eq, term_format, SimpleIri, TTerm, TermKind};
 use mownstr::MownStr;
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::fmt;
 use std::hash::Hash;
 
 /// A prefix map associates prefixes (`&'a str`) to namespaces.
@@ -51,6 +70,88 @@ where
     }
 }
 
+/// Displays a term like [`term_format`](../term/fn.term_format.html),
+/// except that IRIs (including datatypes) are abbreviated as CURIEs (e.g. `foaf:name`)
+/// using a [`PrefixMap`](trait.PrefixMap.html).
+///
+/// IRIs having no matching prefix,
+/// or whose suffix would not be a valid local name, are displayed in full.
+///
+/// It is built by [`display_with`](trait.DisplayWith.html#method.display_with).
+pub struct TermDisplay<'a, T: ?Sized, P> {
+    term: &'a T,
+    prefixes: &'a P,
+}
+
+impl<'a, 'p, T, P> fmt::Display for TermDisplay<'a, T, P>
+where
+    T: TTerm + ?Sized,
+    P: PrefixMap<'p>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.term.kind() {
+            TermKind::Iri => {
+                let raw = self.term.value_raw();
+                write_iri(SimpleIri::new_unchecked(raw.0, raw.1), self.prefixes, f)
+            }
+            TermKind::Literal => {
+                write!(f, "{:?}", self.term.value_raw().0)?;
+                if let Some(tag) = self.term.language() {
+                    return write!(f, "@{}", tag);
+                }
+                let datatype = self.term.datatype().unwrap();
+                if !term_eq(&datatype, &xsd::string) {
+                    f.write_str("^^")?;
+                    write_iri(datatype, self.prefixes, f)?;
+                }
+                Ok(())
+            }
+            _ => term_format(self.term, f),
+        }
+    }
+}
+
+/// Write `iri` as a CURIE if possible, or in full otherwise.
+fn write_iri<'p, P, W>(iri: SimpleIri, prefixes: &P, w: &mut W) -> fmt::Result
+where
+    P: PrefixMap<'p>,
+    W: fmt::Write,
+{
+    match prefixes.get_prefixed_pair(&iri) {
+        Some((prefix, suffix)) if is_simple_local_name(&suffix) => {
+            write!(w, "{}:{}", prefix, suffix)
+        }
+        _ => term_format(&iri, w),
+    }
+}
+
+/// Whether `suffix` can be used as is as the local part of a CURIE.
+///
+/// This is more restrictive than `PN_LOCAL` in Turtle:
+/// it excludes the characters that would need to be escaped.
+fn is_simple_local_name(suffix: &str) -> bool {
+    let valid_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.';
+    suffix.chars().all(valid_char) && !suffix.starts_with(&['-', '.'][..]) && !suffix.ends_with('.')
+}
+
+/// Adds [`display_with`](#method.display_with) to all terms.
+pub trait DisplayWith: TTerm {
+    /// Display this term with the given prefixes.
+    ///
+    /// See [`TermDisplay`](struct.TermDisplay.html).
+    fn display_with<'a, 'p, P>(&'a self, prefixes: &'a P) -> TermDisplay<'a, Self, P>
+    where
+        P: PrefixMap<'p>,
+    {
+        TermDisplay {
+            term: self,
+            prefixes,
+        }
+    }
+}
+
+impl<T: TTerm + ?Sized> DisplayWith for T {}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -79,4 +180,42 @@ mod test {
         let iri = SimpleIri::new_unchecked(ns, sf);
         assert_eq!(map.get_prefixed_pair(&iri), expected);
     }
+
+    #[test_case("http://schema.org/Person", "s:Person"; "curie")]
+    #[test_case("http://schema.org/", "s:"; "empty suffix")]
+    #[test_case("http://schema.org/a.b-c_1", "s:a.b-c_1"; "punctuation")]
+    #[test_case("http://schema.org/a/b", "<http://schema.org/a/b>"; "slash")]
+    #[test_case("http://schema.org/a.", "<http://schema.org/a.>"; "trailing dot")]
+    #[test_case("http://schema.org/-a", "<http://schema.org/-a>"; "leading dash")]
+    #[test_case("http://example.org/x", "<http://example.org/x>"; "no prefix")]
+    fn display_iri(iri: &str, expected: &str) {
+        let mut map = HashMap::new();
+        map.insert("s", SimpleIri::new_unchecked("http://schema.org/", None));
+        let iri = SimpleIri::new_unchecked(iri, None);
+        assert_eq!(format!("{}", iri.display_with(&map)), expected);
+    }
+
+    #[test]
+    fn display_other_terms() {
+        use crate::term::test::TestTerm;
+        let mut map = HashMap::new();
+        map.insert("xsd", SimpleIri::new_unchecked(xsd::PREFIX, None));
+        let display = |t: &TestTerm<&str>| format!("{}", t.display_with(&map));
+        assert_eq!(
+            display(&TestTerm::lit_dt("42", xsd::integer)),
+            "\"42\"^^xsd:integer"
+        );
+        assert_eq!(
+            display(&TestTerm::lit_dt("a\"b", xsd::string)),
+            "\"a\\\"b\""
+        );
+        assert_eq!(display(&TestTerm::lit_lang("chat", "fr")), "\"chat\"@fr");
+        assert_eq!(display(&TestTerm::bnode("b1")), "_:b1");
+        // unsized terms can be displayed as well
+        let t: TestTerm<&str> = TestTerm::lit_dt("1", xsd::decimal);
+        assert_eq!(
+            format!("{}", t.as_dyn().display_with(&map)),
+            "\"1\"^^xsd:decimal"
+        );
+    }
 }
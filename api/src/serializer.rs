@@ -14,7 +14,10 @@
 use crate::dataset::*;
 use crate::graph::*;
 use crate::quad::stream::*;
+use crate::quad::Quad;
+use crate::term::matcher::{GraphNameMatcher, ANY};
 use crate::triple::stream::*;
+use crate::triple::Triple;
 
 /// A triple serializer writes triples according to a given format.
 pub trait TripleSerializer {
@@ -46,6 +49,50 @@ pub trait TripleSerializer {
     {
         self.serialize_triples(&mut graph.triples())
     }
+
+    /// Serialize the union of all graphs of a [`Dataset`] matching `gmatcher`,
+    /// without copying their triples into a new collection.
+    ///
+    /// See also [`Dataset::union_graph`].
+    ///
+    /// [`Dataset`]: ../dataset/trait.Dataset.html
+    /// [`Dataset::union_graph`]: ../dataset/trait.Dataset.html#method.union_graph
+    #[inline]
+    fn serialize_graph_matching<D, M>(
+        &mut self,
+        dataset: &D,
+        gmatcher: M,
+    ) -> StreamResult<&mut Self, D::Error, Self::Error>
+    where
+        D: Dataset,
+        M: GraphNameMatcher,
+        Self: Sized,
+    {
+        self.serialize_graph(&dataset.union_graph(gmatcher))
+    }
+}
+
+/// A triple sink is a [`TripleSerializer`] that can also be fed one triple at a time,
+/// and from several sources over time
+/// (through several calls to [`serialize_triples`]).
+///
+/// The serialized data may be buffered until [`flush`] is called.
+/// Once [`flush`] has returned successfully,
+/// the data written so far must be a complete document of the target format
+/// (see [`QuadSink`] for formats that can not be appended to).
+///
+/// [`TripleSerializer`]: trait.TripleSerializer.html
+/// [`serialize_triples`]: trait.TripleSerializer.html#tymethod.serialize_triples
+/// [`flush`]: #tymethod.flush
+/// [`QuadSink`]: trait.QuadSink.html
+pub trait TripleSink: TripleSerializer {
+    /// Serialize a single triple.
+    fn push_triple<T>(&mut self, triple: &T) -> Result<(), Self::Error>
+    where
+        T: Triple;
+
+    /// Write all buffered data to the target, and flush it.
+    fn flush(&mut self) -> Result<(), Self::Error>;
 }
 
 /// A quad serializer writes quads according to a given format.
@@ -81,6 +128,51 @@ pub trait QuadSerializer {
     {
         self.serialize_quads(&mut dataset.quads())
     }
+
+    /// Serialize the quads of a [`Dataset`] whose graph name matches `gmatcher`,
+    /// without copying them into a new collection.
+    ///
+    /// [`Dataset`]: ../dataset/trait.Dataset.html
+    #[inline]
+    fn serialize_dataset_matching<D, M>(
+        &mut self,
+        dataset: &D,
+        gmatcher: M,
+    ) -> StreamResult<&mut Self, D::Error, Self::Error>
+    where
+        D: Dataset,
+        M: GraphNameMatcher,
+        Self: Sized,
+    {
+        self.serialize_quads(&mut dataset.quads_matching(&ANY, &ANY, &ANY, &gmatcher))
+    }
+}
+
+/// A quad sink is a [`QuadSerializer`] that can also be fed one quad at a time,
+/// and from several sources over time
+/// (through several calls to [`serialize_quads`]).
+///
+/// The serialized data may be buffered until [`flush`] is called.
+/// Once [`flush`] has returned successfully,
+/// the data written so far must be a complete document of the target format,
+/// so that the sink can be used for log-style continuous writing.
+///
+/// Formats whose documents can not be appended to (such as XML-based formats)
+/// may end the current document on each call to [`flush`],
+/// and start a new one with the next data;
+/// the output is then a sequence of complete documents.
+///
+/// [`QuadSerializer`]: trait.QuadSerializer.html
+/// [`serialize_quads`]: trait.QuadSerializer.html#tymethod.serialize_quads
+/// [`flush`]: #tymethod.flush
+pub trait QuadSink: QuadSerializer {
+    /// Serialize a single quad.
+    fn push_quad<Q>(&mut self, quad: &Q) -> Result<(), Self::Error>
+    where
+        Q: Quad;
+
+    /// Write all buffered data to the target, and flush it.
+    fn flush(&mut self) -> Result<(), Self::Error>;
 }
 
 /// A stringifier is special kind of [`TripleSerializer`] or [`QuadSerializer`]:
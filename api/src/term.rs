@@ -11,7 +11,7 @@
 //! [Linked Data]: http://linkeddata.org/
 //! [generalized RDF]: https://docs.rs/sophia/latest/sophia/#generalized-vs-strict-rdf-model
 
-use mownstr::MownStr;
+pub use mownstr::MownStr;
 use std::cmp::Ordering;
 use std::error::Error;
 use std::hash::{Hash, Hasher};
@@ -423,6 +423,47 @@ where
     }
 }
 
+/// How IRIs are compared when checking the equality of terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IriComparison {
+    /// IRIs are equal if they are the same string of characters,
+    /// as required by RDF (this is what [`term_eq`](fn.term_eq.html) does).
+    #[default]
+    Exact,
+    /// IRIs are equal if they are the same after syntax-based normalization
+    /// (case-insensitive scheme and host, equivalent percent-encodings),
+    /// as defined by [`sophia_iri::normalize`].
+    ///
+    /// [`sophia_iri::normalize`]: https://docs.rs/sophia_iri/latest/sophia_iri/normalize/index.html
+    Normalized,
+}
+
+impl IriComparison {
+    /// Compare two terms for equality, comparing IRIs
+    /// (including the datatypes of literals) according to this mode.
+    pub fn term_eq<T1, T2>(&self, t1: &T1, t2: &T2) -> bool
+    where
+        T1: TTerm + ?Sized,
+        T2: TTerm + ?Sized,
+    {
+        if term_eq(t1, t2) {
+            return true;
+        }
+        match (self, t1.kind(), t2.kind()) {
+            (IriComparison::Normalized, Iri, Iri) => {
+                sophia_iri::normalize::normalized_eq(&t1.value(), &t2.value())
+            }
+            (IriComparison::Normalized, Literal, Literal) => {
+                t1.value_raw().0 == t2.value_raw().0
+                    && t1.language().is_none()
+                    && t2.language().is_none()
+                    && self.term_eq(&t1.datatype().unwrap(), &t2.datatype().unwrap())
+            }
+            _ => false,
+        }
+    }
+}
+
 struct TermFormater<'a, T: ?Sized>(&'a T);
 
 impl<'a, T> std::fmt::Display for TermFormater<'a, T>
@@ -139,6 +139,31 @@ where
     }
 }
 
+/// A matcher matching all terms that are equal to the inner term,
+/// modulo IRI normalization (see [`IriComparison::Normalized`]).
+///
+/// NB: this matcher has no [`constant`](trait.TermMatcher.html#tymethod.constant),
+/// as several different terms may match it.
+///
+/// [`IriComparison::Normalized`]: ../enum.IriComparison.html#variant.Normalized
+pub struct Normalized<T>(pub T);
+
+impl<U> TermMatcher for Normalized<U>
+where
+    U: TTerm,
+{
+    type Term = U;
+    fn constant(&self) -> Option<&U> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        IriComparison::Normalized.term_eq(&self.0, t)
+    }
+}
+
 impl<U> TermMatcher for U
 where
     U: TTerm + ?Sized,
@@ -404,4 +429,16 @@ mod test {
         assert!(TermMatcher::matches(&m, &t1));
         assert!(!TermMatcher::matches(&m, &t2));
     }
+
+    #[test]
+    fn test_normalized_as_matcher() {
+        let m = Normalized(SimpleIri::new("HTTP://Example.org/%7euser", None).unwrap());
+        let t1 = SimpleIri::new("http://example.org/", Some("~user")).unwrap();
+        let t2 = SimpleIri::new("http://example.org/~User", None).unwrap();
+
+        assert!(TermMatcher::constant(&m).is_none());
+        assert!(TermMatcher::matches(&m, &t1));
+        assert!(!TermMatcher::matches(&m, &t2));
+        assert!(!IriComparison::Exact.term_eq(&m.0, &t1));
+    }
 }
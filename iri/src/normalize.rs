@@ -0,0 +1,174 @@
+//! Syntax-based normalization of IRIs as per
+//! [\[RFC 3986\]](https://tools.ietf.org/html/rfc3986#section-6.2.2).
+
+use mownstr::MownStr;
+
+/// Apply case normalization and percent-encoding normalization to `txt`:
+/// * the scheme and the host are lowercased,
+/// * the hexadecimal digits of percent-encoded octets are uppercased,
+/// * percent-encoded unreserved characters (`ALPHA`, `DIGIT`, `-`, `.`, `_`, `~`) are decoded.
+///
+/// Two IRIs that are equal after normalization are considered equivalent by RFC 3986.
+/// NB: path segment normalization and scheme-based normalization (e.g. default ports)
+/// are *not* performed.
+///
+/// The returned value borrows `txt` if it is already normalized.
+pub fn normalize(txt: &str) -> MownStr<'_> {
+    let bytes = txt.as_bytes();
+    let scheme = scheme_len(bytes);
+    let host = host_range(bytes, scheme);
+    let lower = |i: usize| i < scheme || (host.0 <= i && i < host.1);
+
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%' && i + 2 < bytes.len() && is_hex(bytes[i + 1]) && is_hex(bytes[i + 2]) {
+            let decoded = hex_value(bytes[i + 1]) * 16 + hex_value(bytes[i + 2]);
+            if is_unreserved(decoded) {
+                normalized.push(if lower(i) {
+                    decoded.to_ascii_lowercase()
+                } else {
+                    decoded
+                });
+            } else {
+                normalized.push(b'%');
+                normalized.push(bytes[i + 1].to_ascii_uppercase());
+                normalized.push(bytes[i + 2].to_ascii_uppercase());
+            }
+            i += 3;
+        } else {
+            normalized.push(if lower(i) { b.to_ascii_lowercase() } else { b });
+            i += 1;
+        }
+    }
+    if normalized == bytes {
+        txt.into()
+    } else {
+        // only ASCII bytes have been changed, so the result is still valid UTF-8
+        String::from_utf8(normalized).unwrap().into()
+    }
+}
+
+/// Check whether `iri1` and `iri2` are equal after [normalization](fn.normalize.html).
+pub fn normalized_eq(iri1: &str, iri2: &str) -> bool {
+    iri1 == iri2 || *normalize(iri1) == *normalize(iri2)
+}
+
+/// Length of the scheme (without the ':'), or 0 if there is no scheme.
+fn scheme_len(bytes: &[u8]) -> usize {
+    if bytes.first().is_some_and(u8::is_ascii_alphabetic) {
+        for (i, b) in bytes.iter().enumerate().skip(1) {
+            match b {
+                b':' => return i,
+                b'+' | b'-' | b'.' => continue,
+                b if b.is_ascii_alphanumeric() => continue,
+                _ => break,
+            }
+        }
+    }
+    0
+}
+
+/// Start and end of the host, if any (or an empty range).
+fn host_range(bytes: &[u8], scheme: usize) -> (usize, usize) {
+    let start = if scheme > 0 { scheme + 1 } else { 0 };
+    if !bytes[start..].starts_with(b"//") {
+        return (0, 0);
+    }
+    let start = start + 2;
+    let end = bytes[start..]
+        .iter()
+        .position(|b| matches!(b, b'/' | b'?' | b'#'))
+        .map_or(bytes.len(), |i| start + i);
+    let host_start = bytes[start..end]
+        .iter()
+        .rposition(|b| *b == b'@')
+        .map_or(start, |i| start + i + 1);
+    let host_end = if bytes.get(host_start) == Some(&b'[') {
+        // IP literal
+        bytes[host_start..end]
+            .iter()
+            .position(|b| *b == b']')
+            .map_or(end, |i| host_start + i + 1)
+    } else {
+        bytes[host_start..end]
+            .iter()
+            .position(|b| *b == b':')
+            .map_or(end, |i| host_start + i)
+    };
+    (host_start, host_end)
+}
+
+fn is_hex(b: u8) -> bool {
+    b.is_ascii_hexdigit()
+}
+
+fn hex_value(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => b - b'A' + 10,
+    }
+}
+
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_iris() {
+        for (txt, expected) in &[
+            ("http://example.org/a/b", "http://example.org/a/b"),
+            ("HTTP://Example.ORG/A/B", "http://example.org/A/B"),
+            ("http://example.org/%7euser", "http://example.org/~user"),
+            ("http://example.org/a%2fb", "http://example.org/a%2Fb"),
+            (
+                "http://example.org/%41%42?q=%e9#%2D",
+                "http://example.org/AB?q=%E9#-",
+            ),
+            (
+                "http://User@Example.org:8080/",
+                "http://User@example.org:8080/",
+            ),
+            ("http://[FE80::1]:80/", "http://[fe80::1]:80/"),
+            ("http://%45xample.org/", "http://example.org/"),
+            ("urn:ISBN:0451450523", "urn:ISBN:0451450523"),
+            ("//Example.org/%7E", "//example.org/~"),
+            ("a/b%", "a/b%"),
+            ("http://example.org/é", "http://example.org/é"),
+        ] {
+            assert_eq!(&normalize(txt)[..], *expected, "{}", txt);
+        }
+    }
+
+    #[test]
+    fn normalize_borrows() {
+        let txt = "http://example.org/a%2Fb";
+        assert_eq!(normalize(txt).as_ptr(), txt.as_ptr());
+    }
+
+    #[test]
+    fn eq() {
+        assert!(normalized_eq(
+            "HTTP://example.ORG/%7Euser",
+            "http://example.org/~user"
+        ));
+        assert!(normalized_eq(
+            "http://example.org/a%2fb",
+            "http://example.org/a%2Fb"
+        ));
+        assert!(!normalized_eq(
+            "http://example.org/a%2Fb",
+            "http://example.org/a/b"
+        ));
+        assert!(!normalized_eq(
+            "http://example.org/A",
+            "http://example.org/a"
+        ));
+    }
+}
@@ -0,0 +1,678 @@
+//! A convenience layer for [Activity Streams 2.0],
+//! the vocabulary used by [ActivityPub] and the fediverse.
+//!
+//! [`Object`] and [`Activity`] describe the core kinds of Activity Streams objects
+//! (notes, actors, `Create`, `Follow`, `Like`...)
+//! with typed fields, and provide constructors for the most common cases.
+//! They can be written into any [`MutableGraph`], read back from any [`Graph`],
+//! or converted directly to (expanded) JSON-LD.
+//!
+//! NB: Activity Streams documents rely on the [Activity Streams context],
+//! so reading activities received as JSON-LD requires a [`JsonLdParser`]
+//! whose [`loader`] serves that context.
+//!
+//! # Example
+//! ```
+//! # use sophia_jsonld::activity::*;
+//! # use sophia_term::BoxTerm;
+//! let alice = BoxTerm::new_iri("https://example.org/alice")?;
+//! let mut note = Object::note(BoxTerm::new_iri("https://example.org/alice/1")?, "Hello world");
+//! note.attributed_to = Some(alice.clone());
+//! note.to.push(BoxTerm::new_iri(PUBLIC)?);
+//! let create = Activity::create(BoxTerm::new_iri("https://example.org/alice/1/create")?, alice, note);
+//!
+//! let json = create.to_jsonld()?;
+//! assert_eq!(json.len(), 2); // the activity and the note
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Activity Streams 2.0]: https://www.w3.org/TR/activitystreams-core/
+//! [Activity Streams context]: https://www.w3.org/ns/activitystreams
+//! [`JsonLdParser`]: ../parser/struct.JsonLdParser.html
+//! [`loader`]: ../parser/struct.JsonLdParser.html#structfield.loader
+//! [ActivityPub]: https://www.w3.org/TR/activitypub/
+//! [`Object`]: struct.Object.html
+//! [`Activity`]: struct.Activity.html
+//! [`Graph`]: https://docs.rs/sophia_api/latest/sophia_api/graph/trait.Graph.html
+//! [`MutableGraph`]: https://docs.rs/sophia_api/latest/sophia_api/graph/trait.MutableGraph.html
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use json::JsonValue;
+use sophia::description::{invalid, objects, single, text, DescriptionError};
+use sophia::graph::inmem::FastGraph;
+use sophia_api::graph::{Graph, MgResult, MutableGraph};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::serializer::QuadSerializer;
+use sophia_api::term::{term_eq, CopyTerm, SimpleIri, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::error::JsonLdError;
+use crate::serializer::Jsonifier;
+
+/// The Activity Streams namespace.
+mod as2 {
+    sophia_api::namespace!(
+        "https://www.w3.org/ns/activitystreams#",
+        // activity types
+        Create,
+        Update,
+        Delete,
+        Follow,
+        Accept,
+        Reject,
+        Like,
+        Announce,
+        Undo,
+        Block,
+        // object types
+        Note,
+        Article,
+        Image,
+        Video,
+        Person,
+        Group,
+        Organization,
+        Service,
+        Application,
+        // properties
+        actor,
+        object,
+        target,
+        name,
+        summary,
+        content,
+        attributedTo,
+        inReplyTo,
+        published,
+        url,
+        to,
+        cc
+    );
+}
+
+/// The IRI of the special collection addressing all users (`as:Public`).
+pub const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+macro_rules! type_enum {
+    ($(#[$doc:meta])* $name: ident { $($(#[$vdoc:meta])* $variant: ident,)* }) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(
+                $(#[$vdoc])*
+                $variant,
+            )*
+        }
+
+        impl $name {
+            /// All the variants of this type.
+            pub const ALL: &'static [$name] = &[$($name::$variant),*];
+
+            /// The IRI of this type.
+            pub fn iri(self) -> &'static SimpleIri<'static> {
+                match self {
+                    $($name::$variant => &as2::$variant,)*
+                }
+            }
+
+            /// The variant identified by `iri`, if any.
+            pub fn from_iri<T: TTerm + ?Sized>(iri: &T) -> Option<Self> {
+                Self::ALL.iter().copied().find(|t| term_eq(t.iri(), iri))
+            }
+        }
+    };
+}
+
+type_enum! {
+    /// The types of activities supported by this module.
+    ActivityType {
+        /// `as:Create`: the actor has created the object.
+        Create,
+        /// `as:Update`: the actor has updated the object.
+        Update,
+        /// `as:Delete`: the actor has deleted the object.
+        Delete,
+        /// `as:Follow`: the actor is following the object.
+        Follow,
+        /// `as:Accept`: the actor accepts the object (typically a `Follow`).
+        Accept,
+        /// `as:Reject`: the actor rejects the object (typically a `Follow`).
+        Reject,
+        /// `as:Like`: the actor likes the object.
+        Like,
+        /// `as:Announce`: the actor is sharing the object.
+        Announce,
+        /// `as:Undo`: the actor is undoing the object (a previous activity).
+        Undo,
+        /// `as:Block`: the actor is blocking the object.
+        Block,
+    }
+}
+
+type_enum! {
+    /// The types of (non-activity) objects supported by this module.
+    ObjectType {
+        /// `as:Note`: a short written work.
+        Note,
+        /// `as:Article`: a multi-paragraph written work.
+        Article,
+        /// `as:Image`: an image document.
+        Image,
+        /// `as:Video`: a video document.
+        Video,
+        /// `as:Person`: an individual person (actor).
+        Person,
+        /// `as:Group`: a group of persons (actor).
+        Group,
+        /// `as:Organization`: an organization (actor).
+        Organization,
+        /// `as:Service`: a service (actor).
+        Service,
+        /// `as:Application`: a software application (actor).
+        Application,
+    }
+}
+
+/// An Activity Streams object, such as a note or an actor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    /// The identifier of the object.
+    pub id: BoxTerm,
+    /// The type of the object.
+    pub kind: ObjectType,
+    /// The name of the object (`as:name`).
+    pub name: Option<String>,
+    /// A summary of the object (`as:summary`).
+    pub summary: Option<String>,
+    /// The content of the object (`as:content`), typically HTML.
+    pub content: Option<String>,
+    /// The entity to which the object is attributed (`as:attributedTo`).
+    pub attributed_to: Option<BoxTerm>,
+    /// The object that this object replies to (`as:inReplyTo`).
+    pub in_reply_to: Option<BoxTerm>,
+    /// The publication date of the object (`as:published`), as an `xsd:dateTime`.
+    pub published: Option<String>,
+    /// A link to a representation of the object (`as:url`).
+    pub url: Option<BoxTerm>,
+    /// The primary audience of the object (`as:to`).
+    pub to: Vec<BoxTerm>,
+    /// The secondary audience of the object (`as:cc`).
+    pub cc: Vec<BoxTerm>,
+}
+
+impl Object {
+    /// Build an object with the given identifier and type, and no other property.
+    pub fn new(id: BoxTerm, kind: ObjectType) -> Self {
+        Object {
+            id,
+            kind,
+            name: None,
+            summary: None,
+            content: None,
+            attributed_to: None,
+            in_reply_to: None,
+            published: None,
+            url: None,
+            to: vec![],
+            cc: vec![],
+        }
+    }
+
+    /// Build a note with the given content.
+    pub fn note<T: Into<String>>(id: BoxTerm, content: T) -> Self {
+        Object {
+            content: Some(content.into()),
+            ..Object::new(id, ObjectType::Note)
+        }
+    }
+
+    /// Build a person with the given name.
+    pub fn person<T: Into<String>>(id: BoxTerm, name: T) -> Self {
+        Object {
+            name: Some(name.into()),
+            ..Object::new(id, ObjectType::Person)
+        }
+    }
+
+    /// Insert the description of this object in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, self.kind.iri())?;
+        insert_text(graph, &self.id, &as2::name, &self.name)?;
+        insert_text(graph, &self.id, &as2::summary, &self.summary)?;
+        insert_text(graph, &self.id, &as2::content, &self.content)?;
+        insert_term(graph, &self.id, &as2::attributedTo, &self.attributed_to)?;
+        insert_term(graph, &self.id, &as2::inReplyTo, &self.in_reply_to)?;
+        insert_date(graph, &self.id, &self.published)?;
+        insert_term(graph, &self.id, &as2::url, &self.url)?;
+        insert_audience(graph, &self.id, &self.to, &self.cc)
+    }
+
+    /// Convert this object to expanded JSON-LD.
+    pub fn to_jsonld(&self) -> Result<JsonValue, JsonLdError> {
+        to_jsonld(|g| self.add_to(g))
+    }
+
+    /// Read the description of object `id` from `graph`.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> AResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let kind = types(graph, &id)?
+            .iter()
+            .find_map(ObjectType::from_iri)
+            .ok_or_else(|| invalid(&id, "no supported object type"))?;
+        let (to, cc) = audience(graph, &id)?;
+        Ok(Object {
+            kind,
+            name: text(graph, &id, &as2::name)?,
+            summary: text(graph, &id, &as2::summary)?,
+            content: text(graph, &id, &as2::content)?,
+            attributed_to: single(graph, &id, &as2::attributedTo)?,
+            in_reply_to: single(graph, &id, &as2::inReplyTo)?,
+            published: text(graph, &id, &as2::published)?,
+            url: single(graph, &id, &as2::url)?,
+            to,
+            cc,
+            id,
+        })
+    }
+}
+
+/// The object of an [`Activity`](struct.Activity.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActivityObject {
+    /// A reference to an object described elsewhere.
+    Reference(BoxTerm),
+    /// An embedded object.
+    Object(Box<Object>),
+    /// An embedded activity (e.g. the `Follow` accepted by an `Accept`).
+    Activity(Box<Activity>),
+}
+
+impl ActivityObject {
+    /// The identifier of this object.
+    pub fn id(&self) -> &BoxTerm {
+        match self {
+            ActivityObject::Reference(id) => id,
+            ActivityObject::Object(object) => &object.id,
+            ActivityObject::Activity(activity) => &activity.id,
+        }
+    }
+}
+
+/// An Activity Streams activity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Activity {
+    /// The identifier of the activity.
+    pub id: BoxTerm,
+    /// The type of the activity.
+    pub kind: ActivityType,
+    /// The actor performing the activity (`as:actor`).
+    pub actor: BoxTerm,
+    /// The object of the activity (`as:object`), if any.
+    pub object: Option<ActivityObject>,
+    /// The target of the activity (`as:target`), if any.
+    pub target: Option<BoxTerm>,
+    /// The publication date of the activity (`as:published`), as an `xsd:dateTime`.
+    pub published: Option<String>,
+    /// The primary audience of the activity (`as:to`).
+    pub to: Vec<BoxTerm>,
+    /// The secondary audience of the activity (`as:cc`).
+    pub cc: Vec<BoxTerm>,
+}
+
+impl Activity {
+    /// Build an activity with the given identifier, type and actor, and no other property.
+    pub fn new(id: BoxTerm, kind: ActivityType, actor: BoxTerm) -> Self {
+        Activity {
+            id,
+            kind,
+            actor,
+            object: None,
+            target: None,
+            published: None,
+            to: vec![],
+            cc: vec![],
+        }
+    }
+
+    /// Build a `Create` activity for the given object.
+    ///
+    /// The activity is addressed to the same audience as the object.
+    pub fn create(id: BoxTerm, actor: BoxTerm, object: Object) -> Self {
+        Activity {
+            to: object.to.clone(),
+            cc: object.cc.clone(),
+            object: Some(ActivityObject::Object(Box::new(object))),
+            ..Activity::new(id, ActivityType::Create, actor)
+        }
+    }
+
+    /// Build a `Follow` activity, where `actor` follows `followed`.
+    pub fn follow(id: BoxTerm, actor: BoxTerm, followed: BoxTerm) -> Self {
+        Activity {
+            to: vec![followed.clone()],
+            object: Some(ActivityObject::Reference(followed)),
+            ..Activity::new(id, ActivityType::Follow, actor)
+        }
+    }
+
+    /// Build a `Like` activity, where `actor` likes `object`.
+    pub fn like(id: BoxTerm, actor: BoxTerm, object: BoxTerm) -> Self {
+        Activity {
+            object: Some(ActivityObject::Reference(object)),
+            ..Activity::new(id, ActivityType::Like, actor)
+        }
+    }
+
+    /// Build an `Accept` activity, where `actor` accepts `activity` (typically a `Follow`).
+    ///
+    /// The activity is addressed to the actor of the accepted activity.
+    pub fn accept(id: BoxTerm, actor: BoxTerm, activity: Activity) -> Self {
+        Self::respond(id, ActivityType::Accept, actor, activity)
+    }
+
+    /// Build an `Undo` activity, where `actor` undoes one of its previous `activity`.
+    pub fn undo(id: BoxTerm, actor: BoxTerm, activity: Activity) -> Self {
+        Activity {
+            to: activity.to.clone(),
+            cc: activity.cc.clone(),
+            ..Self::respond(id, ActivityType::Undo, actor, activity)
+        }
+    }
+
+    fn respond(id: BoxTerm, kind: ActivityType, actor: BoxTerm, activity: Activity) -> Self {
+        Activity {
+            to: vec![activity.actor.clone()],
+            object: Some(ActivityObject::Activity(Box::new(activity))),
+            ..Activity::new(id, kind, actor)
+        }
+    }
+
+    /// Insert the description of this activity, and of its embedded object, in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, self.kind.iri())?;
+        graph.insert(&self.id, &as2::actor, &self.actor)?;
+        if let Some(object) = &self.object {
+            graph.insert(&self.id, &as2::object, object.id())?;
+            match object {
+                ActivityObject::Reference(_) => {}
+                ActivityObject::Object(object) => object.add_to(graph)?,
+                ActivityObject::Activity(activity) => activity.add_to(graph)?,
+            }
+        }
+        insert_term(graph, &self.id, &as2::target, &self.target)?;
+        insert_date(graph, &self.id, &self.published)?;
+        insert_audience(graph, &self.id, &self.to, &self.cc)
+    }
+
+    /// Convert this activity (and its embedded object) to expanded JSON-LD.
+    pub fn to_jsonld(&self) -> Result<JsonValue, JsonLdError> {
+        to_jsonld(|g| self.add_to(g))
+    }
+
+    /// Read the description of activity `id` from `graph`.
+    ///
+    /// The object of the activity is embedded if it has a supported type,
+    /// otherwise it is kept as a reference.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> AResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        Self::read(graph, BoxTerm::copy(id), &mut HashSet::new())
+    }
+
+    fn read<G: Graph>(graph: &G, id: BoxTerm, visited: &mut HashSet<BoxTerm>) -> AResult<G, Self> {
+        if !visited.insert(id.clone()) {
+            return Err(invalid(&id, "activity is its own object").into());
+        }
+        let kind = types(graph, &id)?
+            .iter()
+            .find_map(ActivityType::from_iri)
+            .ok_or_else(|| invalid(&id, "no supported activity type"))?;
+        let actor = single(graph, &id, &as2::actor)?.ok_or_else(|| invalid(&id, "no actor"))?;
+        let object = match single(graph, &id, &as2::object)? {
+            None => None,
+            Some(o) => {
+                let kinds = types(graph, &o)?;
+                Some(
+                    if kinds.iter().any(|t| ActivityType::from_iri(t).is_some()) {
+                        ActivityObject::Activity(Box::new(Self::read(graph, o, visited)?))
+                    } else if kinds.iter().any(|t| ObjectType::from_iri(t).is_some()) {
+                        ActivityObject::Object(Box::new(Object::from_graph(graph, &o)?))
+                    } else {
+                        ActivityObject::Reference(o)
+                    },
+                )
+            }
+        };
+        let (to, cc) = audience(graph, &id)?;
+        Ok(Activity {
+            kind,
+            actor,
+            object,
+            target: single(graph, &id, &as2::target)?,
+            published: text(graph, &id, &as2::published)?,
+            to,
+            cc,
+            id,
+        })
+    }
+}
+
+/// Read all the top-level activities described in `graph`, sorted by identifier.
+///
+/// Activities that are the object of another activity
+/// are embedded in that activity rather than returned separately.
+pub fn activities<G: Graph>(graph: &G) -> AResult<G, Vec<Activity>> {
+    let mut ids = vec![];
+    for kind in ActivityType::ALL {
+        for t in graph.triples_with_po(&rdf::type_, kind.iri()) {
+            let t = t.map_err(ActivityError::Graph)?;
+            if graph.triples_with_po(&as2::object, t.s()).next().is_none() {
+                ids.push(BoxTerm::copy(t.s()));
+            }
+        }
+    }
+    ids.sort_by(|a, b| a.value().cmp(&b.value()));
+    ids.dedup();
+    ids.iter()
+        .map(|id| Activity::from_graph(graph, id))
+        .collect()
+}
+
+/// The error raised when reading activities or objects.
+#[derive(Debug, Error)]
+pub enum ActivityError<E: 'static + Error> {
+    /// The graph raised an error.
+    #[error("Error in the graph: {0}")]
+    Graph(#[source] E),
+    /// A description is not well-formed, or not supported.
+    #[error("Invalid description of {resource}: {message}")]
+    Invalid {
+        /// The offending resource.
+        resource: String,
+        /// The reason why its description is invalid.
+        message: String,
+    },
+}
+
+type AResult<G, T> = Result<T, ActivityError<<G as Graph>::Error>>;
+
+impl<E: 'static + Error> From<DescriptionError<E>> for ActivityError<E> {
+    fn from(err: DescriptionError<E>) -> Self {
+        match err {
+            DescriptionError::Graph(err) => ActivityError::Graph(err),
+            DescriptionError::Invalid { resource, message } => {
+                ActivityError::Invalid { resource, message }
+            }
+        }
+    }
+}
+
+fn to_jsonld<F>(add_to: F) -> Result<JsonValue, JsonLdError>
+where
+    F: FnOnce(&mut FastGraph) -> MgResult<FastGraph, ()>,
+{
+    let mut g = FastGraph::new();
+    add_to(&mut g).unwrap_or_else(|never| match never {});
+    let mut jsonifier = Jsonifier::new_jsonifier();
+    jsonifier
+        .serialize_dataset(&g.as_dataset())
+        .map_err(|e| e.unwrap_sink_error())?;
+    Ok(jsonifier.as_json().clone())
+}
+
+fn insert_text<G, T>(graph: &mut G, s: &BoxTerm, p: &T, txt: &Option<String>) -> MgResult<G, ()>
+where
+    G: MutableGraph,
+    T: TTerm + ?Sized,
+{
+    if let Some(txt) = txt {
+        let txt = BoxTerm::new_literal_dt_unchecked(txt.as_str(), xsd::string);
+        graph.insert(s, p, &txt)?;
+    }
+    Ok(())
+}
+
+fn insert_date<G: MutableGraph>(
+    graph: &mut G,
+    s: &BoxTerm,
+    date: &Option<String>,
+) -> MgResult<G, ()> {
+    if let Some(date) = date {
+        let date = BoxTerm::new_literal_dt_unchecked(date.as_str(), xsd::dateTime);
+        graph.insert(s, &as2::published, &date)?;
+    }
+    Ok(())
+}
+
+fn insert_term<G, T>(graph: &mut G, s: &BoxTerm, p: &T, o: &Option<BoxTerm>) -> MgResult<G, ()>
+where
+    G: MutableGraph,
+    T: TTerm + ?Sized,
+{
+    if let Some(o) = o {
+        graph.insert(s, p, o)?;
+    }
+    Ok(())
+}
+
+fn insert_audience<G>(graph: &mut G, s: &BoxTerm, to: &[BoxTerm], cc: &[BoxTerm]) -> MgResult<G, ()>
+where
+    G: MutableGraph,
+{
+    for o in to {
+        graph.insert(s, &as2::to, o)?;
+    }
+    for o in cc {
+        graph.insert(s, &as2::cc, o)?;
+    }
+    Ok(())
+}
+
+fn types<G: Graph>(g: &G, s: &BoxTerm) -> AResult<G, Vec<BoxTerm>> {
+    objects(g, s, &rdf::type_).map_err(ActivityError::from)
+}
+
+/// The `as:to` and `as:cc` values of `s`, sorted by IRI.
+fn audience<G: Graph>(g: &G, s: &BoxTerm) -> AResult<G, (Vec<BoxTerm>, Vec<BoxTerm>)> {
+    let mut to = objects(g, s, &as2::to)?;
+    let mut cc = objects(g, s, &as2::cc)?;
+    to.sort_by(|a, b| a.value().cmp(&b.value()));
+    cc.sort_by(|a, b| a.value().cmp(&b.value()));
+    Ok((to, cc))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia::parser::turtle;
+    use sophia::triple::stream::TripleSource;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("https://example.org/{}", txt)).unwrap()
+    }
+
+    #[test]
+    fn create_note() -> Res {
+        let mut note = Object::note(iri("alice/1"), "<p>Hello</p>");
+        note.attributed_to = Some(iri("alice"));
+        note.published = Some("2020-11-06T12:00:00Z".to_string());
+        note.to.push(BoxTerm::new_iri(PUBLIC)?);
+        note.cc.push(iri("alice/followers"));
+        let create = Activity::create(iri("alice/1/create"), iri("alice"), note);
+        assert_eq!(create.to, [BoxTerm::new_iri(PUBLIC)?]);
+
+        let mut g = FastGraph::new();
+        create.add_to(&mut g)?;
+        assert_eq!(activities(&g)?, std::slice::from_ref(&create));
+
+        let json = create.to_jsonld()?;
+        let node = json
+            .members()
+            .find(|n| n["@id"] == "https://example.org/alice/1")
+            .unwrap();
+        assert_eq!(
+            node["@type"][0],
+            "https://www.w3.org/ns/activitystreams#Note"
+        );
+        assert_eq!(
+            node["https://www.w3.org/ns/activitystreams#content"][0]["@value"],
+            "<p>Hello</p>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn follow_accept_undo() -> Res {
+        let follow = Activity::follow(iri("bob/f"), iri("bob"), iri("alice"));
+        let accept = Activity::accept(iri("alice/a"), iri("alice"), follow.clone());
+        assert_eq!(accept.to, [iri("bob")]);
+        let undo = Activity::undo(iri("bob/u"), iri("bob"), follow.clone());
+        assert_eq!(undo.to, [iri("alice")]);
+
+        let mut g = FastGraph::new();
+        accept.add_to(&mut g)?;
+        undo.add_to(&mut g)?;
+        // the follow activity is embedded in both others
+        assert_eq!(activities(&g)?, [accept, undo]);
+        assert_eq!(Activity::from_graph(&g, &iri("bob/f"))?, follow);
+        Ok(())
+    }
+
+    #[test]
+    fn read_from_turtle() -> Res {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            @prefix as: <https://www.w3.org/ns/activitystreams#>.
+            @prefix : <https://example.org/>.
+
+            :like a as:Like; as:actor :bob; as:object :alice-post.
+            :alice a as:Person; as:name "Alice", "Alicia"@es.
+            :broken a as:Announce.
+            "#,
+        )
+        .collect_triples()?;
+        let like = Activity::from_graph(&g, &iri("like"))?;
+        assert_eq!(like.kind, ActivityType::Like);
+        assert_eq!(
+            like.object,
+            Some(ActivityObject::Reference(iri("alice-post")))
+        );
+        let alice = Object::from_graph(&g, &iri("alice"))?;
+        assert_eq!(alice, Object::person(iri("alice"), "Alice"));
+        assert!(Activity::from_graph(&g, &iri("broken")).is_err());
+        assert!(Activity::from_graph(&g, &iri("alice")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! Automatic generation of JSON-LD contexts.
+//!
+//! [`generate_context`] inspects a graph, and builds a [context] for compacting it:
+//!
+//! * a term is defined for each property whose IRI can be shortened with the given prefix map,
+//!   named after the suffix of the IRI (or the full compact IRI, if several properties share the same suffix);
+//! * if all the objects of a property are IRIs or blank nodes,
+//!   its values are coerced to `@id`;
+//! * if all the objects of a property are literals with the same datatype
+//!   (other than `xsd:string` and `rdf:langString`),
+//!   its values are coerced to that datatype;
+//! * if a property has several values for the same subject,
+//!   it gets a `@set` container, so that its values are always compacted as arrays;
+//! * the prefixes used by the terms, datatypes and classes of the graph are also defined.
+//!
+//! NB: as this crate only produces [expanded] JSON-LD,
+//! the generated context must be passed to another JSON-LD processor
+//! to actually compact the output.
+//!
+//! # Example
+//! ```
+//! # use std::collections::HashMap;
+//! # use sophia::graph::{inmem::FastGraph, MutableGraph};
+//! # use sophia_api::ns::{xsd, Namespace};
+//! # use sophia_api::term::SimpleIri;
+//! # use sophia_term::BoxTerm;
+//! # use sophia_jsonld::context::generate_context;
+//! let ex = Namespace::new("http://example.org/")?;
+//! let mut g = FastGraph::new();
+//! g.insert(&ex.get("alice")?, &ex.get("knows")?, &ex.get("bob")?)?;
+//! g.insert(&ex.get("alice")?, &ex.get("knows")?, &ex.get("charlie")?)?;
+//! g.insert(&ex.get("alice")?, &ex.get("name")?, &BoxTerm::from("Alice".to_string()))?;
+//!
+//! let mut prefixes = HashMap::new();
+//! prefixes.insert("ex", SimpleIri::new("http://example.org/", None)?);
+//! let context = generate_context(&g, &prefixes)?;
+//! assert_eq!(context, json::object! {
+//!     "ex": "http://example.org/",
+//!     "knows": { "@id": "ex:knows", "@type": "@id", "@container": "@set" },
+//!     "name": "ex:name",
+//! });
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`generate_context`]: fn.generate_context.html
+//! [context]: https://www.w3.org/TR/json-ld11/#the-context
+//! [expanded]: https://www.w3.org/TR/json-ld11/#expanded-document-form
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use json::JsonValue;
+use sophia_api::graph::Graph;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::prefix::PrefixMap;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// Generate a context for compacting `graph`, using `prefixes`.
+///
+/// The returned value is the context itself,
+/// i.e. the value of the `@context` entry of a JSON-LD document.
+/// See [module documentation](index.html) for details.
+pub fn generate_context<'p, G, P>(graph: &G, prefixes: &P) -> Result<JsonValue, G::Error>
+where
+    G: Graph,
+    P: PrefixMap<'p>,
+{
+    let mut properties: BTreeMap<String, PropertyStats> = BTreeMap::new();
+    let mut classes: HashSet<BoxTerm> = HashSet::new();
+    for t in graph.triples() {
+        let t = t?;
+        if term_eq(t.p(), &rdf::type_) {
+            if t.o().kind() == TermKind::Iri {
+                classes.insert(BoxTerm::copy(t.o()));
+            }
+            continue;
+        }
+        if t.p().kind() != TermKind::Iri {
+            continue;
+        }
+        let stats = properties
+            .entry(t.p().value().to_string())
+            .or_insert_with(|| PropertyStats {
+                property: BoxTerm::copy(t.p()),
+                objects: Objects::Unknown,
+                subjects: HashSet::new(),
+                multiple: false,
+            });
+        stats.objects.merge(t.o());
+        if !stats.multiple && !stats.subjects.insert(BoxTerm::copy(t.s())) {
+            stats.multiple = true;
+            stats.subjects = HashSet::new(); // no longer needed
+        }
+    }
+
+    let mut used_prefixes = BTreeMap::new();
+    let mut use_prefix = |iri: &BoxTerm| -> Option<String> {
+        let (prefix, suffix) = prefixes.get_prefixed_pair(iri)?;
+        let ns = prefixes.get_namespace(prefix)?;
+        used_prefixes.insert(prefix.to_string(), ns.value().to_string());
+        Some(format!("{}:{}", prefix, suffix))
+    };
+
+    let mut suffixes: HashMap<String, usize> = HashMap::new();
+    let mut terms = vec![];
+    for stats in properties.values() {
+        let curie = use_prefix(&stats.property);
+        let suffix = curie
+            .as_ref()
+            .map(|c| c[c.find(':').unwrap() + 1..].to_string())
+            .filter(|s| is_simple_term(s));
+        if let Some(suffix) = &suffix {
+            *suffixes.entry(suffix.clone()).or_default() += 1;
+        }
+        let datatype = match &stats.objects {
+            Objects::Literals(dt) => Some(use_prefix(dt).unwrap_or_else(|| dt.value().to_string())),
+            _ => None,
+        };
+        terms.push((stats, curie, suffix, datatype));
+    }
+    for c in &classes {
+        use_prefix(c);
+    }
+
+    let mut context = JsonValue::new_object();
+    for (prefix, ns) in &used_prefixes {
+        context[prefix.as_str()] = ns.as_str().into();
+    }
+    let mut definitions = BTreeMap::new();
+    for (stats, curie, suffix, datatype) in terms {
+        let curie = match curie {
+            Some(curie) => curie,
+            None => continue, // no way to shorten this IRI
+        };
+        // a suffix can not be used as a term if it is ambiguous,
+        // or if it would override a prefix
+        let term = match suffix {
+            Some(suffix) if suffixes[&suffix] == 1 && !used_prefixes.contains_key(&suffix) => {
+                suffix
+            }
+            _ => curie.clone(),
+        };
+        let mut definition = json::object! { "@id": curie.clone() };
+        if let Some(datatype) = datatype {
+            definition["@type"] = datatype.into();
+        } else if let Objects::Ids = stats.objects {
+            definition["@type"] = "@id".into();
+        }
+        if stats.multiple {
+            definition["@container"] = "@set".into();
+        }
+        if definition.len() == 1 {
+            if term == curie {
+                continue; // the prefix is enough
+            }
+            definitions.insert(term, JsonValue::from(curie));
+        } else {
+            definitions.insert(term, definition);
+        }
+    }
+    for (term, definition) in definitions {
+        context[term] = definition;
+    }
+    Ok(context)
+}
+
+/// What is known about the usage of a property.
+struct PropertyStats {
+    property: BoxTerm,
+    objects: Objects,
+    /// The subjects using the property (only maintained until `multiple` is true).
+    subjects: HashSet<BoxTerm>,
+    /// Whether the property has several values for the same subject.
+    multiple: bool,
+}
+
+/// The kind of objects of a property.
+enum Objects {
+    /// No object seen yet.
+    Unknown,
+    /// Only IRIs and blank nodes.
+    Ids,
+    /// Only literals with the given datatype.
+    Literals(BoxTerm),
+    /// Only plain or language-tagged strings, or a mix of anything.
+    Mixed,
+}
+
+impl Objects {
+    /// Take into account another object `o`.
+    fn merge<T: TTerm + ?Sized>(&mut self, o: &T) {
+        let datatype = o.datatype();
+        let plain = datatype
+            .as_ref()
+            .map(|dt| term_eq(dt, &xsd::string) || term_eq(dt, &rdf::langString))
+            .unwrap_or(false);
+        *self = match (std::mem::replace(self, Objects::Mixed), o.kind(), datatype) {
+            (Objects::Unknown, TermKind::Iri, _) | (Objects::Unknown, TermKind::BlankNode, _) => {
+                Objects::Ids
+            }
+            (Objects::Unknown, TermKind::Literal, Some(dt)) if !plain => {
+                Objects::Literals(BoxTerm::copy(&dt))
+            }
+            (Objects::Ids, TermKind::Iri, _) | (Objects::Ids, TermKind::BlankNode, _) => {
+                Objects::Ids
+            }
+            (Objects::Literals(dt1), TermKind::Literal, Some(dt2)) if term_eq(&dt1, &dt2) => {
+                Objects::Literals(dt1)
+            }
+            _ => Objects::Mixed,
+        }
+    }
+}
+
+/// Whether `txt` can be used as a term without being confused with an IRI or a keyword.
+fn is_simple_term(txt: &str) -> bool {
+    let mut chars = txt.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => (),
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia::graph::inmem::FastGraph;
+    use sophia::graph::MutableGraph;
+    use sophia_api::term::SimpleIri;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(txt).unwrap()
+    }
+
+    fn plain(txt: &str) -> BoxTerm {
+        BoxTerm::from(txt.to_string())
+    }
+
+    fn prefixes() -> HashMap<&'static str, SimpleIri<'static>> {
+        let mut prefixes = HashMap::new();
+        for (prefix, ns) in &[
+            ("ex", "http://example.org/"),
+            ("foaf", "http://xmlns.com/foaf/0.1/"),
+            ("schema", "http://schema.org/"),
+            ("xsd", xsd::PREFIX),
+            ("unused", "http://unused.example.org/"),
+        ] {
+            prefixes.insert(*prefix, SimpleIri::new_unchecked(ns, None));
+        }
+        prefixes
+    }
+
+    #[test]
+    fn coercions() -> Res {
+        let mut g = FastGraph::new();
+        let alice = iri("http://example.org/alice");
+        let age = iri("http://example.org/age");
+        let name = iri("http://example.org/name");
+        let other = iri("http://example.org/other");
+        let mixed = iri("http://example.org/mixed");
+        g.insert(
+            &alice,
+            &rdf::type_,
+            &iri("http://xmlns.com/foaf/0.1/Person"),
+        )?;
+        g.insert(&alice, &age, &BoxTerm::new_literal_dt("42", xsd::integer)?)?;
+        g.insert(&alice, &name, &BoxTerm::new_literal_lang("Alice", "en")?)?;
+        g.insert(&alice, &other, &BoxTerm::new_bnode("b")?)?;
+        g.insert(&alice, &mixed, &BoxTerm::new_literal_dt("1", xsd::integer)?)?;
+        g.insert(
+            &alice,
+            &mixed,
+            &BoxTerm::new_literal_dt("1.5", xsd::decimal)?,
+        )?;
+        g.insert(&iri("http://example.org/bob"), &mixed, &alice)?;
+        g.insert(&alice, &iri("http://elsewhere.org/p"), &alice)?;
+
+        let context = generate_context(&g, &prefixes())?;
+        assert_eq!(
+            context,
+            json::object! {
+                "ex": "http://example.org/",
+                "foaf": "http://xmlns.com/foaf/0.1/",
+                "xsd": xsd::PREFIX,
+                "age": { "@id": "ex:age", "@type": "xsd:integer" },
+                "mixed": { "@id": "ex:mixed", "@container": "@set" },
+                "name": "ex:name",
+                "other": { "@id": "ex:other", "@type": "@id" },
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn conflicting_suffixes() -> Res {
+        let mut g = FastGraph::new();
+        let alice = iri("http://example.org/alice");
+        g.insert(
+            &alice,
+            &iri("http://xmlns.com/foaf/0.1/name"),
+            &plain("Alice"),
+        )?;
+        g.insert(&alice, &iri("http://schema.org/name"), &plain("Alice"))?;
+        g.insert(&alice, &iri("http://schema.org/knows"), &alice)?;
+        g.insert(&alice, &iri("http://example.org/1st"), &plain("first"))?;
+        g.insert(&alice, &iri("http://example.org/1st"), &plain("premier"))?;
+        g.insert(&alice, &iri("http://example.org/foaf"), &plain("Alice"))?;
+        g.insert(&alice, &iri("http://example.org/schema"), &alice)?;
+
+        let context = generate_context(&g, &prefixes())?;
+        assert_eq!(
+            context,
+            json::object! {
+                "ex": "http://example.org/",
+                "foaf": "http://xmlns.com/foaf/0.1/",
+                "schema": "http://schema.org/",
+                "ex:1st": { "@id": "ex:1st", "@container": "@set" },
+                "ex:schema": { "@id": "ex:schema", "@type": "@id" },
+                "knows": { "@id": "schema:knows", "@type": "@id" },
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn simple_terms() {
+        assert!(is_simple_term("name"));
+        assert!(is_simple_term("_first-name.2"));
+        assert!(!is_simple_term(""));
+        assert!(!is_simple_term("1st"));
+        assert!(!is_simple_term("@id"));
+        assert!(!is_simple_term("a/b"));
+        assert!(!is_simple_term("a:b"));
+    }
+}
@@ -1,6 +1,8 @@
 //! JSON-LD errors.
 
 use crate::config::JsonLdSpecVersion;
+use crate::loader::LoaderError;
+use sophia::parser::config::LimitExceeded;
 
 /// JSON-LD error
 #[derive(Debug, thiserror::Error)]
@@ -14,6 +16,30 @@ pub enum JsonLdError {
     #[error("IO Error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// A document which is not in expanded form, or not valid JSON-LD.
+    #[error("invalid expanded JSON-LD document: {0}")]
+    NotExpanded(String),
+
+    /// A context which is not valid, or uses unsupported features.
+    #[error("invalid or unsupported JSON-LD context: {0}")]
+    InvalidContext(String),
+
+    /// A remote context that could not be loaded.
+    #[error("failed to load context: {0}")]
+    Loader(#[from] LoaderError),
+
+    /// An invalid base IRI.
+    #[error("invalid base IRI <{0}>")]
+    InvalidBaseIri(String),
+
+    /// A relative IRI-reference that can not be resolved.
+    #[error("relative IRI <{0}> with no base IRI")]
+    RelativeIri(String),
+
+    /// A limit of the [parser](../parser/struct.JsonLdParser.html) exceeded by the document.
+    #[error("limit exceeded: {0}")]
+    LimitExceeded(#[from] LimitExceeded),
+
     /// An unsupported JSON-LD version
     #[error("unsupported version: {0:?}")]
     UnsupportedVersion(JsonLdSpecVersion),
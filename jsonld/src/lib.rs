@@ -1,21 +1,29 @@
 //! Serializer and parser for the [JSON-LD] concrete syntax of RDF.
 //!
-//! NB: this implementation only supports the [expanded document form] of [JSON-LD].
+//! NB: the serializer only produces the [expanded document form] of [JSON-LD],
+//! and the parser only supports the most common features of contexts.
 //!
-//! NB2: currently, only the serializer part is implemented.
+//! It provides a [parser](parser/index.html) and a [serializer](serializer/index.html).
 //!
 //! [JSON-LD]: https://www.w3.org/TR/json-ld11/
 //! [expanded document form]: https://www.w3.org/TR/json-ld11/#expanded-document-form
 
 #![deny(missing_docs)]
 
+pub mod activity;
 pub mod config;
 pub use config::*;
+pub mod context;
 pub mod error;
 pub use error::*;
+pub mod loader;
+pub mod parser;
+pub use parser::*;
 pub mod serializer;
 pub use serializer::*;
 mod util_traits;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
 #[cfg(any(test, feature = "test_util"))]
 pub mod test_util;
@@ -0,0 +1,508 @@
+//! Loading remote JSON-LD contexts.
+//!
+//! JSON-LD documents may refer to their `@context` by IRI,
+//! which must then be dereferenced to process the document.
+//! The [`ContextLoader`] trait (and its async counterpart [`AsyncContextLoader`])
+//! lets users control how this is done,
+//! e.g. to avoid network access in tests, or to enforce a policy.
+//! The [`JsonLdParser`] uses the loader set in its [`loader`] field.
+//!
+//! This module provides the following implementations:
+//! * [`NoLoader`] refuses to load any context;
+//! * [`StaticLoader`] serves a fixed set of contexts, stored in memory;
+//! * [`HttpLoader`] dereferences contexts with an [`HttpClient`];
+//! * [`CachingLoader`] wraps another loader,
+//!   and keeps the loaded contexts in memory and (optionally) in a directory,
+//!   which can also be populated beforehand to work offline.
+//!
+//! # Example
+//! ```
+//! # use sophia_jsonld::loader::*;
+//! let mut contexts = StaticLoader::new();
+//! contexts.insert("https://example.org/context", json::object! {
+//!     "@context": { "name": "https://schema.org/name" }
+//! });
+//! let loader = CachingLoader::new(contexts);
+//! let doc = loader.load_context("https://example.org/context")?;
+//! assert_eq!(doc.document["@context"]["name"], "https://schema.org/name");
+//! assert!(loader.load_context("https://example.org/other").is_err());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`ContextLoader`]: trait.ContextLoader.html
+//! [`AsyncContextLoader`]: trait.AsyncContextLoader.html
+//! [`NoLoader`]: struct.NoLoader.html
+//! [`StaticLoader`]: struct.StaticLoader.html
+//! [`HttpLoader`]: struct.HttpLoader.html
+//! [`CachingLoader`]: struct.CachingLoader.html
+//! [`JsonLdParser`]: ../parser/struct.JsonLdParser.html
+//! [`loader`]: ../parser/struct.JsonLdParser.html#structfield.loader
+//! [`HttpClient`]: https://docs.rs/sophia/latest/sophia/fetch/trait.HttpClient.html
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use json::JsonValue;
+use sophia::fetch::HttpClient;
+use thiserror::Error;
+
+/// The `Accept` header sent by [`HttpLoader`](struct.HttpLoader.html).
+pub const ACCEPT: &str = "application/ld+json, application/json;q=0.9";
+
+/// A JSON document retrieved by a [`ContextLoader`](trait.ContextLoader.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteDocument {
+    /// The final URL of the document, after redirections.
+    pub url: String,
+    /// The media type of the document, if known.
+    pub content_type: Option<String>,
+    /// The parsed document.
+    pub document: JsonValue,
+}
+
+/// Loads the documents referenced by `@context` IRIs.
+///
+/// Methods take `&self`, so that a loader can be shared between threads.
+///
+/// See [module documentation](index.html).
+pub trait ContextLoader {
+    /// The error raised when a context can not be loaded.
+    type Error: 'static + Error;
+
+    /// Load the context document identified by `iri`.
+    fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error>;
+}
+
+impl<L: ContextLoader + ?Sized> ContextLoader for &L {
+    type Error = L::Error;
+    fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error> {
+        (*self).load_context(iri)
+    }
+}
+
+/// A [`ContextLoader`](trait.ContextLoader.html) that can be shared by several parsers,
+/// possibly in different threads.
+pub type SharedLoader = Arc<dyn ContextLoader<Error = LoaderError> + Send + Sync>;
+
+/// The future returned by [`AsyncContextLoader::load_context_async`](trait.AsyncContextLoader.html#tymethod.load_context_async).
+pub type LoadFuture<'a, E> = Pin<Box<dyn Future<Output = Result<RemoteDocument, E>> + Send + 'a>>;
+
+/// The asynchronous version of [`ContextLoader`](trait.ContextLoader.html).
+///
+/// It is implemented by all (thread-safe) synchronous loaders,
+/// whose futures are ready immediately
+/// (i.e. they block the task polling them while loading).
+pub trait AsyncContextLoader {
+    /// The error raised when a context can not be loaded.
+    type Error: 'static + Error;
+
+    /// Load the context document identified by `iri`.
+    fn load_context_async<'a>(&'a self, iri: &'a str) -> LoadFuture<'a, Self::Error>;
+}
+
+impl<L> AsyncContextLoader for L
+where
+    L: ContextLoader + Sync + ?Sized,
+    L::Error: Send,
+{
+    type Error = L::Error;
+    fn load_context_async<'a>(&'a self, iri: &'a str) -> LoadFuture<'a, Self::Error> {
+        Box::pin(std::future::ready(self.load_context(iri)))
+    }
+}
+
+/// A [`ContextLoader`](trait.ContextLoader.html) refusing to load any context.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoLoader;
+
+impl ContextLoader for NoLoader {
+    type Error = LoaderError;
+    fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error> {
+        Err(LoaderError::NotAvailable(iri.to_string()))
+    }
+}
+
+/// A [`ContextLoader`](trait.ContextLoader.html) serving a fixed set of documents.
+#[derive(Clone, Debug, Default)]
+pub struct StaticLoader {
+    documents: HashMap<String, JsonValue>,
+}
+
+impl StaticLoader {
+    /// Build a loader serving no document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve `document` for `iri`, replacing any previous document.
+    pub fn insert<T: Into<String>>(&mut self, iri: T, document: JsonValue) {
+        self.documents.insert(iri.into(), document);
+    }
+}
+
+impl ContextLoader for StaticLoader {
+    type Error = LoaderError;
+    fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error> {
+        match self.documents.get(iri) {
+            Some(document) => Ok(RemoteDocument {
+                url: iri.to_string(),
+                content_type: Some("application/ld+json".to_string()),
+                document: document.clone(),
+            }),
+            None => Err(LoaderError::NotAvailable(iri.to_string())),
+        }
+    }
+}
+
+/// A [`ContextLoader`](trait.ContextLoader.html) dereferencing IRIs with an
+/// [`HttpClient`](https://docs.rs/sophia/latest/sophia/fetch/trait.HttpClient.html).
+///
+/// Only JSON responses (`application/json`, `application/ld+json` or any `+json` media type)
+/// are accepted.
+#[derive(Clone, Debug)]
+pub struct HttpLoader<C> {
+    /// The underlying HTTP client.
+    pub client: C,
+}
+
+impl<C: HttpClient> HttpLoader<C> {
+    /// Build a loader using `client`.
+    pub fn new(client: C) -> Self {
+        HttpLoader { client }
+    }
+}
+
+impl<C: HttpClient> ContextLoader for HttpLoader<C> {
+    type Error = LoaderError;
+    fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error> {
+        let response = self
+            .client
+            .get(iri, &[("Accept", ACCEPT)])
+            .map_err(|err| LoaderError::Client(err.to_string()))?;
+        if !response.is_success() {
+            return Err(LoaderError::Status(response.status));
+        }
+        let content_type = response.media_type();
+        match content_type.as_deref() {
+            Some(mt) if mt == "application/json" || mt.ends_with("+json") => {}
+            _ => return Err(LoaderError::UnsupportedMediaType(content_type)),
+        }
+        let txt = std::str::from_utf8(&response.body)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(RemoteDocument {
+            url: response.url,
+            content_type,
+            document: json::parse(txt)?,
+        })
+    }
+}
+
+/// A [`ContextLoader`](trait.ContextLoader.html) caching the documents loaded by another one.
+///
+/// Documents are kept in memory, and optionally stored in a directory,
+/// in a file whose name is the percent-encoded IRI followed by `.jsonld`
+/// (e.g. `https%3A%2F%2Fexample.org%2Fcontext.jsonld`).
+/// The directory can be shared by several loaders, and persists across runs;
+/// it can also be populated by hand, so that the loader works offline
+/// (with a [`NoLoader`](struct.NoLoader.html) as the underlying loader).
+///
+/// Failing to store a document is not reported, as the cache is only an optimization;
+/// but a stored document that is not valid JSON is reported as an error.
+#[derive(Debug)]
+pub struct CachingLoader<L> {
+    inner: L,
+    dir: Option<PathBuf>,
+    memory: Mutex<HashMap<String, RemoteDocument>>,
+}
+
+impl<L> CachingLoader<L>
+where
+    L: ContextLoader<Error = LoaderError>,
+{
+    /// Build a loader caching in memory the documents loaded by `inner`.
+    pub fn new(inner: L) -> Self {
+        CachingLoader {
+            inner,
+            dir: None,
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build a loader caching in memory and in `dir` (created if necessary)
+    /// the documents loaded by `inner`.
+    pub fn with_dir<P: Into<PathBuf>>(inner: L, dir: P) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(CachingLoader {
+            dir: Some(dir),
+            ..Self::new(inner)
+        })
+    }
+
+    /// The directory where this loader stores documents, if any.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// The path of the file where the document for `iri` is (or would be) stored, if any.
+    pub fn path(&self, iri: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(file_name(iri)))
+    }
+
+    fn read(&self, iri: &str) -> Result<Option<RemoteDocument>, LoaderError> {
+        let path = match self.path(iri) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let txt = match fs::read_to_string(path) {
+            Ok(txt) => txt,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(RemoteDocument {
+            url: iri.to_string(),
+            content_type: Some("application/ld+json".to_string()),
+            document: json::parse(&txt)?,
+        }))
+    }
+
+    fn write(&self, iri: &str, document: &RemoteDocument) -> io::Result<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = match self.path(iri) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result =
+            fs::write(&tmp, document.document.dump()).and_then(|_| fs::rename(&tmp, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+}
+
+impl<L> ContextLoader for CachingLoader<L>
+where
+    L: ContextLoader<Error = LoaderError>,
+{
+    type Error = LoaderError;
+    fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error> {
+        if let Some(document) = self.memory.lock().unwrap().get(iri) {
+            return Ok(document.clone());
+        }
+        let document = match self.read(iri)? {
+            Some(document) => document,
+            None => {
+                let document = self.inner.load_context(iri)?;
+                let _ = self.write(iri, &document);
+                document
+            }
+        };
+        self.memory
+            .lock()
+            .unwrap()
+            .insert(iri.to_string(), document.clone());
+        Ok(document)
+    }
+}
+
+/// Percent-encode `iri` into a file name.
+fn file_name(iri: &str) -> String {
+    let mut name = String::with_capacity(iri.len() + 7);
+    for b in iri.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_' {
+            name.push(b as char);
+        } else {
+            write!(name, "%{:02X}", b).unwrap();
+        }
+    }
+    name.push_str(".jsonld");
+    name
+}
+
+/// The error raised by the loaders of this module.
+#[derive(Debug, Error)]
+pub enum LoaderError {
+    /// The loader can not provide the requested context.
+    #[error("Context not available: {0}")]
+    NotAvailable(String),
+    /// The HTTP client failed.
+    #[error("HTTP client error: {0}")]
+    Client(String),
+    /// The server responded with an error status.
+    #[error("HTTP status {0}")]
+    Status(u16),
+    /// The media type of the response is not JSON.
+    #[error("Unsupported media type {0:?}")]
+    UnsupportedMediaType(Option<String>),
+    /// The document could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The document is not valid JSON.
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] json::Error),
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia::fetch::Response;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    fn context() -> JsonValue {
+        json::object! { "@context": { "name": "https://schema.org/name" } }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sophia-jsonld-{}-{}", name, std::process::id()))
+    }
+
+    /// A loader counting how many documents it loads.
+    #[derive(Default)]
+    struct CountingLoader(StaticLoader, AtomicUsize);
+
+    impl ContextLoader for CountingLoader {
+        type Error = LoaderError;
+        fn load_context(&self, iri: &str) -> Result<RemoteDocument, Self::Error> {
+            self.1.fetch_add(1, Ordering::Relaxed);
+            self.0.load_context(iri)
+        }
+    }
+
+    struct MockClient(u16, &'static str, &'static str);
+
+    impl HttpClient for MockClient {
+        type Error = io::Error;
+        fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Self::Error> {
+            assert_eq!(headers, &[("Accept", ACCEPT)]);
+            Ok(Response {
+                status: self.0,
+                url: format!("{}.jsonld", url),
+                headers: vec![("Content-Type".to_string(), self.1.to_string())],
+                body: self.2.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn static_loader() {
+        let mut loader = StaticLoader::new();
+        loader.insert("https://example.org/ctx", context());
+        let doc = loader.load_context("https://example.org/ctx").unwrap();
+        assert_eq!(doc.url, "https://example.org/ctx");
+        assert_eq!(doc.document, context());
+        assert!(matches!(
+            loader.load_context("https://example.org/other"),
+            Err(LoaderError::NotAvailable(_))
+        ));
+        assert!(NoLoader.load_context("https://example.org/ctx").is_err());
+    }
+
+    #[test]
+    fn http_loader() {
+        let body = r#"{"@context": {"name": "https://schema.org/name"}}"#;
+        let loader = HttpLoader::new(MockClient(200, "application/ld+json; charset=utf-8", body));
+        let doc = loader.load_context("https://example.org/ctx").unwrap();
+        assert_eq!(doc.url, "https://example.org/ctx.jsonld");
+        assert_eq!(doc.content_type.as_deref(), Some("application/ld+json"));
+        assert_eq!(doc.document, context());
+
+        let loader = HttpLoader::new(MockClient(200, "text/html", "<html/>"));
+        assert!(matches!(
+            loader.load_context("https://example.org/ctx"),
+            Err(LoaderError::UnsupportedMediaType(Some(_)))
+        ));
+        let loader = HttpLoader::new(MockClient(404, "application/json", "{}"));
+        assert!(matches!(
+            loader.load_context("https://example.org/ctx"),
+            Err(LoaderError::Status(404))
+        ));
+        let loader = HttpLoader::new(MockClient(200, "application/json", "{"));
+        assert!(matches!(
+            loader.load_context("https://example.org/ctx"),
+            Err(LoaderError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn caching_loader() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = temp_dir("caching_loader");
+        let _ = fs::remove_dir_all(&dir);
+        let mut inner = CountingLoader::default();
+        inner.0.insert("https://example.org/ctx", context());
+        let loader = CachingLoader::with_dir(inner, &dir)?;
+        for _ in 0..3 {
+            assert_eq!(
+                loader.load_context("https://example.org/ctx")?.document,
+                context()
+            );
+        }
+        assert_eq!(loader.inner.1.load(Ordering::Relaxed), 1);
+        let path = dir.join("https%3A%2F%2Fexample.org%2Fctx.jsonld");
+        assert_eq!(loader.path("https://example.org/ctx"), Some(path.clone()));
+        assert!(path.exists());
+        assert!(loader.load_context("https://example.org/other").is_err());
+        assert_eq!(loader.inner.1.load(Ordering::Relaxed), 2);
+
+        // another loader, working offline from the same directory
+        let offline = CachingLoader::with_dir(NoLoader, &dir)?;
+        assert_eq!(
+            offline.load_context("https://example.org/ctx")?.document,
+            context()
+        );
+        fs::write(dir.join("https%3A%2F%2Fexample.org%2Fbad.jsonld"), "{")?;
+        assert!(matches!(
+            offline.load_context("https://example.org/bad"),
+            Err(LoaderError::Json(_))
+        ));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn async_loader() {
+        fn block_on<F: Future>(f: F) -> F::Output {
+            fn noop_raw_waker() -> RawWaker {
+                fn clone(_: *const ()) -> RawWaker {
+                    noop_raw_waker()
+                }
+                fn noop(_: *const ()) {}
+                static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+                RawWaker::new(std::ptr::null(), &VTABLE)
+            }
+            let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+            let mut cx = Context::from_waker(&waker);
+            let mut f = Box::pin(f);
+            loop {
+                if let Poll::Ready(output) = f.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+        }
+
+        let mut loader = StaticLoader::new();
+        loader.insert("https://example.org/ctx", context());
+        let loader = CachingLoader::new(loader);
+        let doc = block_on(loader.load_context_async("https://example.org/ctx")).unwrap();
+        assert_eq!(doc.document, context());
+        assert!(block_on(loader.load_context_async("https://example.org/other")).is_err());
+    }
+}
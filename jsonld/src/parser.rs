@@ -0,0 +1,908 @@
+//! A JSON-LD parser implementing the
+//! [`Deserialize JSON-LD to RDF Algorithm`].
+//!
+//! Node objects containing a `@context` are first converted to the [expanded document form],
+//! loading remote contexts with the [`loader`] of the parser.
+//! Only the most common features of contexts are supported:
+//! term definitions with `@id`, `@type` and `@container` (`@list` or `@set`),
+//! keyword aliases, compact IRIs, `@vocab` and `@language`.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::inmem::FastDataset;
+//! # use sophia::dataset::Dataset;
+//! # use sophia::quad::stream::QuadSource;
+//! # use sophia_api::parser::QuadParser;
+//! # use sophia_jsonld::parser::JsonLdParser;
+//! let jsonld = r#"[{
+//!     "@id": "alice",
+//!     "http://schema.org/name": [{"@value": "Alice"}],
+//!     "http://schema.org/knows": [{"@id": "bob"}]
+//! }]"#;
+//! let parser = JsonLdParser {
+//!     base: Some("http://example.org/".into()),
+//!     ..JsonLdParser::default()
+//! };
+//! let d: FastDataset = parser.parse_str(jsonld).collect_quads()?;
+//! assert_eq!(d.quads().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Deserialize JSON-LD to RDF Algorithm`]: https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm
+//! [expanded document form]: https://www.w3.org/TR/json-ld11/#expanded-document-form
+//! [`loader`]: struct.JsonLdParser.html#structfield.loader
+
+use crate::config::*;
+use crate::error::*;
+use crate::loader::SharedLoader;
+use json::object::Object;
+use json::JsonValue;
+use sophia::ns::{rdf, xsd};
+use sophia::parser::config::LimitExceeded;
+use sophia::quad::TupleQuad;
+use sophia_api::parser::QuadParser;
+use sophia_api::term::CopyTerm;
+use sophia_iri::is_absolute_iri_ref;
+use sophia_iri::resolve::{IriParsed, Resolve};
+use sophia_term::BoxTerm;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+
+mod expand;
+use expand::ActiveContext;
+
+/// JSON-LD parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Default)]
+pub struct JsonLdParser {
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// in node identifiers and types
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    ///
+    /// If it is not a valid absolute IRI, the source produced by this parser fails immediately.
+    pub base: Option<String>,
+    /// Whether relative IRI-references are errors when they can not be resolved.
+    /// Otherwise (the default), the quads containing them are ignored,
+    /// as required by the JSON-LD API.
+    pub reject_relative: bool,
+    /// How value objects containing a base direction are transformed to RDF
+    /// (see [`JsonLdConfig::rdf_direction`]).
+    /// If `None` (the default), the base direction is ignored.
+    ///
+    /// [`JsonLdConfig::rdf_direction`]: ../config/struct.JsonLdConfig.html#structfield.rdf_direction
+    pub rdf_direction: Option<RdfDirectionMode>,
+    /// The maximum nesting depth of node objects and lists, if any (default: none).
+    ///
+    /// Each node object or list is one level deeper than the node object or list containing it;
+    /// value objects are not counted.
+    /// Deeply nested documents may exhaust the stack of this (recursive) parser,
+    /// so this limit should be set when parsing untrusted data.
+    pub max_depth: Option<usize>,
+    /// The maximum length, in bytes, of the lexical form of literals, if any (default: none).
+    ///
+    /// The length is that of the unescaped string,
+    /// or of the canonical serialization of JSON literals.
+    pub max_literal_len: Option<usize>,
+    /// The loader used to dereference the IRIs of remote contexts, if any (default: none).
+    ///
+    /// Without a loader, documents referring to a remote context fail
+    /// with [`LoaderError::NotAvailable`].
+    ///
+    /// [`LoaderError::NotAvailable`]: ../loader/enum.LoaderError.html#variant.NotAvailable
+    pub loader: Option<SharedLoader>,
+}
+
+impl fmt::Debug for JsonLdParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLdParser")
+            .field("base", &self.base)
+            .field("reject_relative", &self.reject_relative)
+            .field("rdf_direction", &self.rdf_direction)
+            .field("max_depth", &self.max_depth)
+            .field("max_literal_len", &self.max_literal_len)
+            .field("loader", &self.loader.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl JsonLdParser {
+    /// Convert the JSON-LD document `doc`, already parsed as JSON, to RDF.
+    pub fn parse_json(&self, doc: &JsonValue) -> JsonLdSource {
+        let mut quads = vec![];
+        let res = self.base().and_then(|base| {
+            let mut converter = Converter {
+                parser: self,
+                base,
+                bnodes: HashMap::new(),
+                next_bnode: 0,
+                depth: 0,
+                quads: &mut quads,
+            };
+            converter.document(doc)
+        });
+        let mut items: Vec<_> = quads.into_iter().map(Ok).collect();
+        if let Err(err) = res {
+            items.push(Err(err));
+        }
+        items.into_iter()
+    }
+
+    fn base(&self) -> Result<Option<IriParsed<'_>>, JsonLdError> {
+        match &self.base {
+            None => Ok(None),
+            Some(base) => match IriParsed::new(base) {
+                Ok(parsed) if parsed.is_absolute() => Ok(Some(parsed)),
+                _ => Err(JsonLdError::InvalidBaseIri(base.clone())),
+            },
+        }
+    }
+}
+
+impl<B: BufRead> QuadParser<B> for JsonLdParser {
+    type Source = JsonLdSource;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        if let Err(err) = data.read_to_string(&mut txt) {
+            return vec![Err(err.into())].into_iter();
+        }
+        match json::parse(&txt) {
+            Ok(doc) => self.parse_json(&doc),
+            Err(err) => vec![Err(err.into())].into_iter(),
+        }
+    }
+}
+
+/// The source produced by [`JsonLdParser`](struct.JsonLdParser.html).
+pub type JsonLdSource = std::vec::IntoIter<Result<TupleQuad<BoxTerm>, JsonLdError>>;
+
+sophia_api::def_mod_functions_for_bufread_parser!(JsonLdParser, QuadParser);
+
+/// The state of the conversion of a document.
+struct Converter<'a> {
+    parser: &'a JsonLdParser,
+    base: Option<IriParsed<'a>>,
+    /// Maps the blank node identifiers of the document to the generated ones.
+    bnodes: HashMap<String, BoxTerm>,
+    next_bnode: usize,
+    /// The number of node objects and lists enclosing the one being converted.
+    depth: usize,
+    quads: &'a mut Vec<TupleQuad<BoxTerm>>,
+}
+
+impl<'a> Converter<'a> {
+    fn document(&mut self, doc: &JsonValue) -> Result<(), JsonLdError> {
+        let expanded;
+        let doc = match doc {
+            JsonValue::Object(obj) if obj.get("@context").is_some() => {
+                expanded = self.expand_node(obj, &ActiveContext::default())?;
+                &expanded
+            }
+            _ => doc,
+        };
+        let nodes = match doc {
+            JsonValue::Object(obj) if obj.len() == 1 && obj.get("@graph").is_some() => {
+                &obj["@graph"]
+            }
+            JsonValue::Object(_) | JsonValue::Array(_) => doc,
+            _ => return Err(not_expanded("document is not an object nor an array")),
+        };
+        for node in members(nodes) {
+            self.node(node, &None)?;
+        }
+        Ok(())
+    }
+
+    /// Convert a node object in `graph`, and return its identifier
+    /// (`None` if it is not a valid IRI).
+    fn node(
+        &mut self,
+        node: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        self.nested(|this| this.node_object(node, graph))
+    }
+
+    fn node_object(
+        &mut self,
+        node: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        let obj = as_object(node)?;
+        if obj.get("@context").is_some() {
+            let expanded = self.expand_node(obj, &ActiveContext::default())?;
+            return self.node_object(&expanded, graph);
+        }
+        let id = match obj.get("@id") {
+            None => Some(self.fresh_bnode()),
+            Some(id) => self.node_id(as_str(id, "@id")?)?,
+        };
+        for (key, value) in obj.iter() {
+            match key {
+                "@id" | "@index" => (),
+                "@type" => {
+                    for typ in members(value) {
+                        let typ = self.node_id(as_str(typ, "@type")?)?;
+                        if let (Some(s), Some(o)) = (&id, typ) {
+                            self.push(s.clone(), BoxTerm::copy(&rdf::type_), o, graph);
+                        }
+                    }
+                }
+                // graphs whose name is not a valid IRI are ignored
+                "@graph" if id.is_some() => {
+                    for child in members(value) {
+                        self.node(child, &id)?;
+                    }
+                }
+                "@graph" => (),
+                "@included" => {
+                    for child in members(value) {
+                        self.node(child, graph)?;
+                    }
+                }
+                "@reverse" => {
+                    for (property, values) in as_object(value)?.iter() {
+                        let p = self.property(property)?;
+                        for value in members(values) {
+                            let s = self.node(value, graph)?;
+                            if let (Some(s), Some(p), Some(o)) = (s, &p, &id) {
+                                self.push(s, p.clone(), o.clone(), graph);
+                            }
+                        }
+                    }
+                }
+                _ if key.starts_with('@') => {
+                    return Err(not_expanded(&format!("unexpected keyword {}", key)));
+                }
+                _ => {
+                    let p = self.property(key)?;
+                    for value in members(value) {
+                        let o = self.object(value, graph)?;
+                        if let (Some(s), Some(p), Some(o)) = (&id, &p, o) {
+                            self.push(s.clone(), p.clone(), o, graph);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(id)
+    }
+
+    /// Convert a value, list or node object in `graph`, and return the corresponding term
+    /// (`None` if it can not be represented in RDF).
+    fn object(
+        &mut self,
+        value: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        let obj = as_object(value)?;
+        if obj.get("@value").is_some() {
+            self.literal(obj, graph)
+        } else if let Some(items) = obj.get("@list") {
+            self.list(items, graph)
+        } else {
+            self.node(value, graph)
+        }
+    }
+
+    fn literal(
+        &mut self,
+        obj: &Object,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        let value = &obj["@value"];
+        let datatype = match obj.get("@type") {
+            None => None,
+            Some(dt) if dt == "@json" => {
+                let lex = canonical_json(value);
+                self.check_literal_len(&lex)?;
+                return Ok(Some(BoxTerm::new_literal_dt_unchecked(lex, rdf::JSON)));
+            }
+            Some(dt) => match self.iri(as_str(dt, "@type")?, false)? {
+                None => return Ok(None),
+                dt => dt,
+            },
+        };
+        let (lex, default_dt) = match value {
+            JsonValue::Null => return Ok(None),
+            JsonValue::Boolean(b) => (b.to_string(), xsd::boolean),
+            JsonValue::Number(n) => {
+                let f = f64::from(*n);
+                let is_double = matches!(&datatype, Some(dt) if dt == &xsd::double);
+                if is_double || f.fract() != 0.0 || f.abs() >= 1e21 {
+                    (canonical_double(f), xsd::double)
+                } else {
+                    (format!("{:.0}", f), xsd::integer)
+                }
+            }
+            JsonValue::Short(_) | JsonValue::String(_) => {
+                let txt = value.as_str().unwrap().to_string();
+                self.check_literal_len(&txt)?;
+                if datatype.is_none() {
+                    return self.string(txt, obj, graph);
+                }
+                (txt, xsd::string)
+            }
+            _ => return Err(not_expanded("@value is an object or an array")),
+        };
+        Ok(match datatype {
+            Some(dt) => BoxTerm::new_literal_dt(lex, &dt).ok(),
+            None => Some(BoxTerm::new_literal_dt_unchecked(lex, default_dt)),
+        })
+    }
+
+    /// Convert a string value with no datatype,
+    /// taking into account its language and base direction, if any.
+    fn string(
+        &mut self,
+        txt: String,
+        obj: &Object,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        let language = obj.get("@language").map(|l| as_str(l, "@language"));
+        let language = language.transpose()?;
+        let direction = obj.get("@direction").map(|d| as_str(d, "@direction"));
+        let direction = direction.transpose()?;
+        Ok(match (direction, self.parser.rdf_direction) {
+            (Some(dir), Some(RdfDirectionMode::I18nDatatype)) => {
+                let lang = language.unwrap_or("").to_lowercase();
+                let dt = format!("https://www.w3.org/ns/i18n#{}_{}", lang, dir);
+                let dt = BoxTerm::new_iri(dt).ok();
+                dt.and_then(|dt| BoxTerm::new_literal_dt(txt, &dt).ok())
+            }
+            (Some(dir), Some(RdfDirectionMode::CompoundLiteral)) => {
+                let b = self.fresh_bnode();
+                let string = |txt: String| BoxTerm::new_literal_dt_unchecked(txt, xsd::string);
+                self.push(b.clone(), BoxTerm::copy(&rdf::value), string(txt), graph);
+                if let Some(lang) = language {
+                    let lang = string(lang.to_lowercase());
+                    self.push(b.clone(), BoxTerm::copy(&rdf::language), lang, graph);
+                }
+                let dir = string(dir.to_string());
+                self.push(b.clone(), BoxTerm::copy(&rdf::direction), dir, graph);
+                Some(b)
+            }
+            _ => match language {
+                Some(lang) => BoxTerm::new_literal_lang(txt, lang).ok(),
+                None => Some(BoxTerm::new_literal_dt_unchecked(txt, xsd::string)),
+            },
+        })
+    }
+
+    fn list(
+        &mut self,
+        items: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        self.nested(|this| this.list_items(items, graph))
+    }
+
+    fn list_items(
+        &mut self,
+        items: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        let items: Vec<&JsonValue> = members(items).collect();
+        let nodes: Vec<BoxTerm> = items.iter().map(|_| self.fresh_bnode()).collect();
+        for (i, item) in items.into_iter().enumerate() {
+            if let Some(o) = self.object(item, graph)? {
+                self.push(nodes[i].clone(), BoxTerm::copy(&rdf::first), o, graph);
+            }
+            let rest = match nodes.get(i + 1) {
+                Some(next) => next.clone(),
+                None => BoxTerm::copy(&rdf::nil),
+            };
+            self.push(nodes[i].clone(), BoxTerm::copy(&rdf::rest), rest, graph);
+        }
+        Ok(Some(match nodes.into_iter().next() {
+            Some(head) => head,
+            None => BoxTerm::copy(&rdf::nil),
+        }))
+    }
+
+    /// Convert a node identifier (or type), which may be a blank node identifier,
+    /// or an IRI-reference to resolve against the base IRI.
+    fn node_id(&mut self, id: &str) -> Result<Option<BoxTerm>, JsonLdError> {
+        if id.starts_with("_:") {
+            if let Some(b) = self.bnodes.get(id) {
+                return Ok(Some(b.clone()));
+            }
+            let b = self.fresh_bnode();
+            self.bnodes.insert(id.to_string(), b.clone());
+            Ok(Some(b))
+        } else {
+            self.iri(id, true)
+        }
+    }
+
+    /// Convert a property, which must be an absolute IRI
+    /// (blank nodes as predicates are not supported).
+    fn property(&self, property: &str) -> Result<Option<BoxTerm>, JsonLdError> {
+        if property.starts_with("_:") {
+            Ok(None)
+        } else {
+            self.iri(property, false)
+        }
+    }
+
+    /// Convert `iri`, resolving it against the base IRI if `resolve` is true.
+    ///
+    /// Relative IRI-references that can not be resolved are either ignored or errors,
+    /// depending on [`JsonLdParser::reject_relative`](struct.JsonLdParser.html#structfield.reject_relative).
+    fn iri(&self, iri: &str, resolve: bool) -> Result<Option<BoxTerm>, JsonLdError> {
+        let resolved = match &self.base {
+            Some(base) if resolve && !is_absolute_iri_ref(iri) => {
+                base.resolve(iri).ok().map(|iri| iri.to_string())
+            }
+            _ => Some(iri.to_string()),
+        };
+        match resolved {
+            Some(iri) if is_absolute_iri_ref(&iri) => Ok(BoxTerm::new_iri(iri).ok()),
+            _ if self.parser.reject_relative => Err(JsonLdError::RelativeIri(iri.to_string())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Call `f` one level deeper, failing if this exceeds
+    /// [`JsonLdParser::max_depth`](struct.JsonLdParser.html#structfield.max_depth).
+    fn nested<T, F>(&mut self, f: F) -> Result<T, JsonLdError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, JsonLdError>,
+    {
+        match self.parser.max_depth {
+            Some(max) if self.depth >= max => Err(LimitExceeded::Depth(max).into()),
+            _ => {
+                self.depth += 1;
+                let res = f(self);
+                self.depth -= 1;
+                res
+            }
+        }
+    }
+
+    /// Fail if `lex` exceeds
+    /// [`JsonLdParser::max_literal_len`](struct.JsonLdParser.html#structfield.max_literal_len).
+    fn check_literal_len(&self, lex: &str) -> Result<(), JsonLdError> {
+        match self.parser.max_literal_len {
+            Some(max) if lex.len() > max => Err(LimitExceeded::LiteralLength(max).into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn fresh_bnode(&mut self) -> BoxTerm {
+        self.next_bnode += 1;
+        BoxTerm::new_bnode(format!("b{}", self.next_bnode - 1)).unwrap()
+    }
+
+    fn push(&mut self, s: BoxTerm, p: BoxTerm, o: BoxTerm, graph: &Option<BoxTerm>) {
+        self.quads.push(([s, p, o], graph.clone()));
+    }
+}
+
+/// Iterate over the members of `value` if it is an array, or over `value` itself otherwise.
+fn members(value: &JsonValue) -> std::slice::Iter<'_, JsonValue> {
+    match value {
+        JsonValue::Array(items) => items.iter(),
+        _ => std::slice::from_ref(value).iter(),
+    }
+}
+
+fn as_object(value: &JsonValue) -> Result<&Object, JsonLdError> {
+    match value {
+        JsonValue::Object(obj) => Ok(obj),
+        _ => Err(not_expanded(&format!(
+            "expected an object, found {}",
+            value
+        ))),
+    }
+}
+
+fn as_str<'v>(value: &'v JsonValue, key: &str) -> Result<&'v str, JsonLdError> {
+    value
+        .as_str()
+        .ok_or_else(|| not_expanded(&format!("{} must be a string", key)))
+}
+
+fn not_expanded(msg: &str) -> JsonLdError {
+    JsonLdError::NotExpanded(msg.to_string())
+}
+
+/// The canonical lexical form of an `xsd:double`, as required by JSON-LD (e.g. `1.1E0`).
+fn canonical_double(f: f64) -> String {
+    let txt = format!("{:E}", f);
+    match txt.find('E') {
+        Some(e) if !txt[..e].contains('.') => format!("{}.0{}", &txt[..e], &txt[e..]),
+        _ => txt,
+    }
+}
+
+/// A canonical serialization of `value`, with the keys of objects in lexicographical order.
+fn canonical_json(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Object(obj) => {
+            let mut entries: Vec<(&str, &JsonValue)> = obj.iter().collect();
+            entries.sort_by_key(|(k, _)| *k);
+            let entries: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| format!("{}:{}", json::stringify(k), canonical_json(v)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        JsonValue::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        _ => json::stringify(value.clone()),
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::loader::{LoaderError, StaticLoader};
+    use sophia::dataset::inmem::FastDataset;
+    use sophia::dataset::Dataset;
+    use sophia::quad::stream::QuadSource;
+    use sophia::quad::Quad;
+    use sophia_api::term::matcher::ANY;
+    use sophia_api::term::TTerm;
+    use std::sync::Arc;
+
+    fn ex(suffix: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    fn parse(parser: &JsonLdParser, jsonld: &str) -> Result<FastDataset, JsonLdError> {
+        parser
+            .parse_str(jsonld)
+            .collect_quads()
+            .map_err(|e| e.unwrap_source_error())
+    }
+
+    #[test]
+    fn nodes_and_values() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"[{
+            "@id": "http://example.org/alice",
+            "@type": ["http://example.org/Person"],
+            "http://example.org/name": [{"@value": "Alice", "@language": "en"}],
+            "http://example.org/age": [{"@value": 42}],
+            "http://example.org/height": [{"@value": 1.5}],
+            "http://example.org/married": [{"@value": false}],
+            "http://example.org/birth": [{"@value": "2000-01-01", "@type": "http://www.w3.org/2001/XMLSchema#date"}],
+            "http://example.org/knows": [{"http://example.org/name": [{"@value": "Bob"}]}],
+            "http://example.org/likes": [{"@list": [{"@value": "a"}, {"@id": "http://example.org/b"}]}],
+            "@reverse": {"http://example.org/parent": [{"@id": "http://example.org/carol"}]}
+        }]"#;
+        let d = parse(&JsonLdParser::default(), jsonld)?;
+        let alice = ex("alice");
+        assert_eq!(d.quads().count(), 14);
+        let value = |p: &str| -> BoxTerm {
+            let p = ex(p);
+            let q = d.quads_with_sp(&alice, &p).next().unwrap().unwrap();
+            BoxTerm::copy(q.o())
+        };
+        assert_eq!(value("name"), BoxTerm::new_literal_lang("Alice", "en")?);
+        assert_eq!(value("age"), BoxTerm::new_literal_dt("42", xsd::integer)?);
+        assert_eq!(
+            value("height"),
+            BoxTerm::new_literal_dt("1.5E0", xsd::double)?
+        );
+        assert_eq!(
+            value("married"),
+            BoxTerm::new_literal_dt("false", xsd::boolean)?
+        );
+        assert_eq!(
+            value("birth"),
+            BoxTerm::new_literal_dt("2000-01-01", xsd::date)?
+        );
+        assert!(d
+            .quads_with_spo(&alice, &rdf::type_, &ex("Person"))
+            .next()
+            .is_some());
+        assert!(d
+            .quads_with_spo(&ex("carol"), &ex("parent"), &alice)
+            .next()
+            .is_some());
+        let bob = value("knows");
+        assert!(bob.value().starts_with('b'));
+        assert_eq!(d.quads_with_s(&bob).count(), 1);
+        let list = value("likes");
+        assert_eq!(d.quads_with_s(&list).count(), 2);
+        assert_eq!(
+            d.quads_matching(&ANY, &rdf::rest, &rdf::nil, &ANY).count(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn named_graphs_and_bnodes() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"{"@graph": [
+            {"@id": "_:b0", "http://example.org/p": [{"@id": "_:b0"}]},
+            {"@id": "http://example.org/g", "@graph": [
+                {"@id": "_:b0", "http://example.org/p": [{"@value": "x"}]}
+            ]}
+        ]}"#;
+        let d = parse(&JsonLdParser::default(), jsonld)?;
+        assert_eq!(d.quads().count(), 2);
+        let g = ex("g");
+        let q = d.quads_with_g(Some(&g)).next().unwrap()?;
+        let b = BoxTerm::copy(q.s());
+        // document blank nodes are consistently relabeled, across graphs
+        assert!(d.quads_with_spo(&b, &ex("p"), &b).next().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn base_iri() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"[{"@id": "a", "@type": "T", "http://example.org/p": [{"@id": "../c"}]}]"#;
+        let parser = JsonLdParser {
+            base: Some("http://example.org/x/y".into()),
+            ..JsonLdParser::default()
+        };
+        let d = parse(&parser, jsonld)?;
+        assert_eq!(d.quads().count(), 2);
+        let a = BoxTerm::new_iri("http://example.org/x/a")?;
+        let t = BoxTerm::new_iri("http://example.org/x/T")?;
+        assert!(d.quads_with_spo(&a, &rdf::type_, &t).next().is_some());
+        assert!(d.quads_with_spo(&a, &ex("p"), &ex("c")).next().is_some());
+
+        // without base, relative IRIs are ignored, or rejected
+        let d = parse(&JsonLdParser::default(), jsonld)?;
+        assert_eq!(d.quads().count(), 0);
+        let strict = JsonLdParser {
+            reject_relative: true,
+            ..JsonLdParser::default()
+        };
+        assert!(matches!(
+            parse(&strict, jsonld),
+            Err(JsonLdError::RelativeIri(_))
+        ));
+
+        // so are graphs with a relative name, along with their contents
+        let jsonld = r#"[{"@id": "g", "@graph": [
+            {"@id": "http://example.org/s", "http://example.org/p": [{"@id": "http://example.org/o"}]}
+        ]}]"#;
+        let d = parse(&JsonLdParser::default(), jsonld)?;
+        assert_eq!(d.quads().count(), 0);
+        assert!(matches!(
+            parse(&strict, jsonld),
+            Err(JsonLdError::RelativeIri(_))
+        ));
+
+        let invalid = JsonLdParser {
+            base: Some("not/absolute".into()),
+            ..JsonLdParser::default()
+        };
+        assert!(matches!(
+            parse(&invalid, jsonld),
+            Err(JsonLdError::InvalidBaseIri(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn direction() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"[{"@id": "http://example.org/s", "http://example.org/p": [
+            {"@value": "hello", "@language": "en-US", "@direction": "ltr"}
+        ]}]"#;
+        let d = parse(&JsonLdParser::default(), jsonld)?;
+        let q = d.quads().next().unwrap()?;
+        assert_eq!(
+            BoxTerm::copy(q.o()),
+            BoxTerm::new_literal_lang("hello", "en-US")?
+        );
+
+        let parser = JsonLdParser {
+            rdf_direction: Some(RdfDirectionMode::I18nDatatype),
+            ..JsonLdParser::default()
+        };
+        let d = parse(&parser, jsonld)?;
+        let q = d.quads().next().unwrap()?;
+        let dt = BoxTerm::new_iri("https://www.w3.org/ns/i18n#en-us_ltr")?;
+        assert_eq!(BoxTerm::copy(q.o()), BoxTerm::new_literal_dt("hello", &dt)?);
+
+        let parser = JsonLdParser {
+            rdf_direction: Some(RdfDirectionMode::CompoundLiteral),
+            ..JsonLdParser::default()
+        };
+        let d = parse(&parser, jsonld)?;
+        assert_eq!(d.quads().count(), 4);
+        assert_eq!(d.quads_with_p(&rdf::direction).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn json_literals() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"[{"@id": "http://example.org/s", "http://example.org/p": [
+            {"@value": {"b": [1, true], "a": null}, "@type": "@json"}
+        ]}]"#;
+        let d = parse(&JsonLdParser::default(), jsonld)?;
+        let q = d.quads().next().unwrap()?;
+        let expected = BoxTerm::new_literal_dt(r#"{"a":null,"b":[1,true]}"#, rdf::JSON)?;
+        assert_eq!(BoxTerm::copy(q.o()), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let parser = JsonLdParser::default();
+        assert!(matches!(
+            parse(&parser, "[{"),
+            Err(JsonLdError::InvalidJsonLiteral(_))
+        ));
+        assert!(matches!(
+            parse(
+                &parser,
+                r#"{"@context": "http://example.org/ctx", "@id": "http://example.org/s"}"#
+            ),
+            Err(JsonLdError::Loader(LoaderError::NotAvailable(_)))
+        ));
+        assert!(matches!(
+            parse(&parser, r#"[{"http://example.org/p": ["not expanded"]}]"#),
+            Err(JsonLdError::NotExpanded(_))
+        ));
+    }
+
+    fn loader() -> SharedLoader {
+        let mut loader = StaticLoader::new();
+        loader.insert(
+            "http://example.org/ctx",
+            json::object! { "@context": {
+                "@vocab": "http://schema.org/",
+                "id": "@id",
+                "type": "@type",
+                "knows": { "@type": "@id" },
+                "birthDate": { "@type": "xsd:date" },
+                "friends": { "@id": "ex:friends", "@type": "@id", "@container": "@list" },
+                "nameMap": { "@id": "name", "@container": "@language" },
+            }},
+        );
+        loader.insert(
+            "http://example.org/loop",
+            json::object! { "@context": ["http://example.org/ctx", "http://example.org/loop"] },
+        );
+        Arc::new(loader)
+    }
+
+    #[test]
+    fn contexts() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"{
+            "@context": [
+                "ctx",
+                {"ex": "http://example.org/", "xsd": "http://www.w3.org/2001/XMLSchema#"}
+            ],
+            "id": "ex:alice",
+            "type": "Person",
+            "name": "Alice",
+            "knows": "bob",
+            "birthDate": "2000-01-01",
+            "friends": ["ex:bob", "ex:carol"],
+            "ex:age": 42,
+            "ex:partner": {
+                "@context": {"@language": "en"},
+                "@id": "ex:dan",
+                "name": "Dan"
+            }
+        }"#;
+        let parser = JsonLdParser {
+            base: Some("http://example.org/".into()),
+            loader: Some(loader()),
+            ..JsonLdParser::default()
+        };
+        let d = parse(&parser, jsonld)?;
+        assert_eq!(d.quads().count(), 12);
+        let schema = |suffix: &str| BoxTerm::new_iri(format!("http://schema.org/{}", suffix));
+        let alice = ex("alice");
+        let dg = None as Option<&BoxTerm>;
+        assert!(d.contains(&alice, &rdf::type_, &schema("Person")?, dg)?);
+        let name = BoxTerm::new_literal_dt("Alice", xsd::string)?;
+        assert!(d.contains(&alice, &schema("name")?, &name, dg)?);
+        assert!(d.contains(&alice, &schema("knows")?, &ex("bob"), dg)?);
+        let birth = BoxTerm::new_literal_dt("2000-01-01", xsd::date)?;
+        assert!(d.contains(&alice, &schema("birthDate")?, &birth, dg)?);
+        let age = BoxTerm::new_literal_dt("42", xsd::integer)?;
+        assert!(d.contains(&alice, &ex("age"), &age, dg)?);
+        let dan = BoxTerm::new_literal_lang("Dan", "en")?;
+        assert!(d.contains(&ex("dan"), &schema("name")?, &dan, dg)?);
+        let friends = ex("friends");
+        let list = d.quads_with_sp(&alice, &friends).next().unwrap()?;
+        assert!(d.contains(list.o(), &rdf::first, &ex("bob"), dg)?);
+        Ok(())
+    }
+
+    #[test]
+    fn context_errors() {
+        let parser = JsonLdParser {
+            loader: Some(loader()),
+            ..JsonLdParser::default()
+        };
+        let jsonld = r#"{"@context": "http://example.org/ctx", "nameMap": {"en": "Alice"}}"#;
+        assert!(matches!(
+            parse(&parser, jsonld),
+            Err(JsonLdError::InvalidContext(_))
+        ));
+        let jsonld = r#"{"@context": "http://example.org/loop", "name": "Alice"}"#;
+        assert!(matches!(
+            parse(&parser, jsonld),
+            Err(JsonLdError::InvalidContext(_))
+        ));
+        let jsonld = r#"{"@context": "http://example.org/other", "name": "Alice"}"#;
+        assert!(matches!(
+            parse(&parser, jsonld),
+            Err(JsonLdError::Loader(LoaderError::NotAvailable(_)))
+        ));
+        let jsonld = r#"{"@context": {"@import": "http://example.org/ctx"}}"#;
+        assert!(matches!(
+            parse(&parser, jsonld),
+            Err(JsonLdError::InvalidContext(_))
+        ));
+    }
+
+    #[test]
+    fn limits() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonld = r#"[{"@id": "http://example.org/s", "http://example.org/p": [
+            {"@list": [{"http://example.org/q": [{"@value": "abcd"}]}]},
+            {"@value": [1, 2], "@type": "@json"}
+        ]}]"#;
+        let parser = JsonLdParser {
+            max_depth: Some(3),
+            max_literal_len: Some(5),
+            ..JsonLdParser::default()
+        };
+        assert_eq!(parse(&parser, jsonld)?.quads().count(), 5);
+
+        let parser = JsonLdParser {
+            max_depth: Some(2),
+            ..JsonLdParser::default()
+        };
+        assert!(matches!(
+            parse(&parser, jsonld),
+            Err(JsonLdError::LimitExceeded(LimitExceeded::Depth(2)))
+        ));
+
+        let parser = JsonLdParser {
+            max_literal_len: Some(3),
+            ..JsonLdParser::default()
+        };
+        assert!(matches!(
+            parse(&parser, jsonld),
+            Err(JsonLdError::LimitExceeded(LimitExceeded::LiteralLength(3)))
+        ));
+
+        // the limits also apply to JSON literals
+        let json = r#"[{"http://example.org/p": [{"@value": [1, 2], "@type": "@json"}]}]"#;
+        assert!(matches!(
+            parse(&parser, json),
+            Err(JsonLdError::LimitExceeded(LimitExceeded::LiteralLength(3)))
+        ));
+
+        // deeply nested data is rejected before exhausting the stack
+        let deep = format!(
+            "{}{{}}{}",
+            r#"{"http://example.org/p": ["#.repeat(200),
+            "]}".repeat(200)
+        );
+        let parser = JsonLdParser {
+            max_depth: Some(100),
+            ..JsonLdParser::default()
+        };
+        assert!(matches!(
+            parse(&parser, &deep),
+            Err(JsonLdError::LimitExceeded(LimitExceeded::Depth(100)))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn double_lexical_form() {
+        assert_eq!(canonical_double(1.5), "1.5E0");
+        assert_eq!(canonical_double(10.0), "1.0E1");
+        assert_eq!(canonical_double(-0.00125), "-1.25E-3");
+    }
+}
@@ -0,0 +1,437 @@
+//! Expansion of the node objects containing a [context],
+//! into the [expanded document form] converted to RDF by the parser.
+//!
+//! Only the most common features of contexts are supported:
+//! term definitions with `@id`, `@type` and `@container` (`@list` or `@set`),
+//! keyword aliases, compact IRIs, `@vocab`, `@language`,
+//! and remote contexts, loaded with [`JsonLdParser::loader`].
+//! Using a term whose definition relies on any other feature is an error.
+//!
+//! [context]: https://www.w3.org/TR/json-ld11/#the-context
+//! [expanded document form]: https://www.w3.org/TR/json-ld11/#expanded-document-form
+//! [`JsonLdParser::loader`]: ../struct.JsonLdParser.html#structfield.loader
+
+use super::*;
+use crate::loader::LoaderError;
+use sophia_api::term::TTerm;
+
+/// The maximum number of nested remote contexts.
+const MAX_REMOTE_CONTEXTS: usize = 32;
+
+/// The term definitions in scope for a node object.
+#[derive(Clone, Debug, Default)]
+pub(super) struct ActiveContext {
+    /// The definition of each term, `None` if the term is explicitly mapped to `null`.
+    terms: HashMap<String, Option<TermDefinition>>,
+    vocab: Option<String>,
+    language: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct TermDefinition {
+    /// The IRI, compact IRI, term or keyword that the term stands for.
+    id: String,
+    /// The type to which the values of the term are coerced, as written in the context.
+    coercion: Option<String>,
+    /// Whether the values of the term are a list.
+    list: bool,
+    /// The unsupported feature used by this definition, if any.
+    unsupported: Option<String>,
+}
+
+impl ActiveContext {
+    /// Update this context with the entries of the local context `obj`.
+    fn define(&mut self, obj: &Object) -> Result<(), JsonLdError> {
+        // keywords first, as they affect the expansion of terms
+        for (key, value) in obj.iter() {
+            match key {
+                "@version" | "@protected" => (),
+                "@vocab" => {
+                    self.vocab = match value {
+                        JsonValue::Null => None,
+                        _ => self.expand_iri(as_context_str(value, key)?, true)?,
+                    }
+                }
+                "@language" => {
+                    self.language = match value {
+                        JsonValue::Null => None,
+                        _ => Some(as_context_str(value, key)?.to_string()),
+                    }
+                }
+                _ if key.starts_with('@') => {
+                    return Err(invalid_context(&format!("{} is not supported", key)));
+                }
+                _ => (),
+            }
+        }
+        for (key, value) in obj.iter() {
+            if key.starts_with('@') {
+                continue;
+            }
+            let definition = match value {
+                JsonValue::Null => None,
+                JsonValue::Object(def) => term_definition(key, def)?,
+                _ => Some(TermDefinition {
+                    id: as_context_str(value, key)?.to_string(),
+                    coercion: None,
+                    list: false,
+                    unsupported: None,
+                }),
+            };
+            self.terms.insert(key.to_string(), definition);
+        }
+        Ok(())
+    }
+
+    /// The definition of `term`, failing if it relies on an unsupported feature.
+    fn definition(&self, term: &str) -> Result<Option<&TermDefinition>, JsonLdError> {
+        match self.terms.get(term) {
+            Some(Some(def)) => match &def.unsupported {
+                Some(feature) => Err(invalid_context(&format!(
+                    "term {} uses {}, which is not supported",
+                    term, feature
+                ))),
+                None => Ok(Some(def)),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Expand `value` to an IRI, a blank node identifier or a keyword.
+    ///
+    /// If `vocab` is true, `value` may be a term, or be relative to `@vocab`;
+    /// otherwise, relative IRI-references are kept, to be resolved against the base IRI.
+    /// Return `None` if `value` is a term mapped to `null`.
+    fn expand_iri(&self, value: &str, vocab: bool) -> Result<Option<String>, JsonLdError> {
+        self.expand_iri_with_depth(value, vocab, 0)
+    }
+
+    fn expand_iri_with_depth(
+        &self,
+        value: &str,
+        vocab: bool,
+        depth: usize,
+    ) -> Result<Option<String>, JsonLdError> {
+        if depth > self.terms.len() {
+            return Err(invalid_context(&format!("cyclic definition of {}", value)));
+        }
+        if value.starts_with('@') {
+            return Ok(Some(value.to_string()));
+        }
+        if vocab {
+            match self.terms.get(value) {
+                Some(None) => return Ok(None),
+                Some(Some(def)) if def.id != value => {
+                    return self.expand_iri_with_depth(&def.id, true, depth + 1);
+                }
+                _ => (),
+            }
+        }
+        if let Some(colon) = value.find(':') {
+            let (prefix, suffix) = (&value[..colon], &value[colon + 1..]);
+            if prefix == "_" || suffix.starts_with("//") {
+                return Ok(Some(value.to_string()));
+            }
+            if let Some(Some(def)) = self.terms.get(prefix) {
+                if def.id != prefix {
+                    let ns = self.expand_iri_with_depth(&def.id, true, depth + 1)?;
+                    return Ok(ns.map(|ns| format!("{}{}", ns, suffix)));
+                }
+            }
+            return Ok(Some(value.to_string()));
+        }
+        Ok(match &self.vocab {
+            Some(ns) if vocab => Some(format!("{}{}", ns, value)),
+            _ => Some(value.to_string()),
+        })
+    }
+}
+
+/// Parse the expanded term definition `obj` of `term`
+/// (`None` if `term` is mapped to `null`).
+fn term_definition(term: &str, obj: &Object) -> Result<Option<TermDefinition>, JsonLdError> {
+    let mut def = TermDefinition {
+        id: term.to_string(),
+        coercion: None,
+        list: false,
+        unsupported: None,
+    };
+    for (key, value) in obj.iter() {
+        match key {
+            "@id" if value.is_null() => return Ok(None),
+            "@id" => def.id = as_context_str(value, key)?.to_string(),
+            "@type" => def.coercion = Some(as_context_str(value, key)?.to_string()),
+            "@container" => {
+                for container in members(value) {
+                    match as_context_str(container, key)? {
+                        "@list" => def.list = true,
+                        "@set" => (),
+                        other => def.unsupported = Some(format!("@container {}", other)),
+                    }
+                }
+            }
+            "@protected" => (),
+            _ => def.unsupported = Some(key.to_string()),
+        }
+    }
+    Ok(Some(def))
+}
+
+impl<'a> Converter<'a> {
+    /// Expand the node object `node`, in the scope of `active` updated by its own `@context`.
+    pub(super) fn expand_node(
+        &mut self,
+        node: &Object,
+        active: &ActiveContext,
+    ) -> Result<JsonValue, JsonLdError> {
+        self.nested(|this| this.expand_node_object(node, active))
+    }
+
+    fn expand_node_object(
+        &mut self,
+        node: &Object,
+        active: &ActiveContext,
+    ) -> Result<JsonValue, JsonLdError> {
+        let local;
+        let active = match node.get("@context") {
+            Some(context) => {
+                let mut updated = active.clone();
+                self.process_context(&mut updated, context, &mut vec![])?;
+                local = updated;
+                &local
+            }
+            None => active,
+        };
+        let mut expanded = JsonValue::new_object();
+        for (key, value) in node.iter() {
+            if key == "@context" {
+                continue;
+            }
+            let property = match active.expand_iri(key, true)? {
+                Some(property) => property,
+                None => continue,
+            };
+            let values = match property.as_str() {
+                "@id" => match active.expand_iri(as_str(value, key)?, false)? {
+                    Some(id) => {
+                        expanded["@id"] = id.into();
+                        continue;
+                    }
+                    None => continue,
+                },
+                "@type" => {
+                    let mut types = vec![];
+                    for typ in members(value) {
+                        if let Some(typ) = active.expand_iri(as_str(typ, key)?, true)? {
+                            types.push(typ.into());
+                        }
+                    }
+                    types
+                }
+                "@graph" | "@included" => {
+                    let mut nodes = vec![];
+                    for child in members(value) {
+                        nodes.push(self.expand_node(as_object(child)?, active)?);
+                    }
+                    nodes
+                }
+                "@reverse" => {
+                    let mut reverse = JsonValue::new_object();
+                    for (key, value) in as_object(value)?.iter() {
+                        if let Some(property) = active.expand_iri(key, true)? {
+                            let def = active.definition(key)?;
+                            let values = self.expand_values(value, def, active)?;
+                            append(&mut reverse, &property, values);
+                        }
+                    }
+                    expanded["@reverse"] = reverse;
+                    continue;
+                }
+                _ if property.starts_with('@') => {
+                    expanded[property.as_str()] = value.clone();
+                    continue;
+                }
+                _ => {
+                    let def = active.definition(key)?;
+                    self.expand_values(value, def, active)?
+                }
+            };
+            append(&mut expanded, &property, values);
+        }
+        Ok(expanded)
+    }
+
+    /// Update `active` with the local context `context`.
+    ///
+    /// `remote` holds the IRIs of the remote contexts being processed,
+    /// to detect recursive inclusions.
+    fn process_context(
+        &self,
+        active: &mut ActiveContext,
+        context: &JsonValue,
+        remote: &mut Vec<String>,
+    ) -> Result<(), JsonLdError> {
+        match context {
+            JsonValue::Null => *active = ActiveContext::default(),
+            JsonValue::Array(contexts) => {
+                for context in contexts {
+                    self.process_context(active, context, remote)?;
+                }
+            }
+            JsonValue::Object(obj) => active.define(obj)?,
+            _ => {
+                let iri = as_context_str(context, "@context")?;
+                let iri = match self.iri(iri, true)? {
+                    Some(iri) => iri.value().to_string(),
+                    None => return Err(JsonLdError::RelativeIri(iri.to_string())),
+                };
+                if remote.contains(&iri) || remote.len() >= MAX_REMOTE_CONTEXTS {
+                    return Err(invalid_context(&format!(
+                        "recursive inclusion of <{}>",
+                        iri
+                    )));
+                }
+                let loader = match &self.parser.loader {
+                    Some(loader) => loader,
+                    None => return Err(LoaderError::NotAvailable(iri).into()),
+                };
+                let document = loader.load_context(&iri)?.document;
+                let context = match &document {
+                    JsonValue::Object(obj) => obj.get("@context"),
+                    _ => None,
+                };
+                let context = context.ok_or_else(|| {
+                    invalid_context(&format!("<{}> does not contain a @context", iri))
+                })?;
+                remote.push(iri);
+                self.process_context(active, context, remote)?;
+                remote.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Expand the value(s) of a property defined by `def`.
+    fn expand_values(
+        &mut self,
+        value: &JsonValue,
+        def: Option<&TermDefinition>,
+        active: &ActiveContext,
+    ) -> Result<Vec<JsonValue>, JsonLdError> {
+        let mut values = vec![];
+        for item in members(value) {
+            self.expand_value(item, def, active, &mut values)?;
+        }
+        let is_list = matches!(value, JsonValue::Object(obj) if obj.get("@list").is_some());
+        if matches!(def, Some(def) if def.list) && !is_list {
+            values = vec![json::object! { "@list": values }];
+        }
+        Ok(values)
+    }
+
+    /// Expand `item`, a single value of a property defined by `def`, into `values`.
+    fn expand_value(
+        &mut self,
+        item: &JsonValue,
+        def: Option<&TermDefinition>,
+        active: &ActiveContext,
+        values: &mut Vec<JsonValue>,
+    ) -> Result<(), JsonLdError> {
+        let coercion = def.and_then(|def| def.coercion.as_deref());
+        if coercion == Some("@json") {
+            values.push(json::object! { "@value": item.clone(), "@type": "@json" });
+            return Ok(());
+        }
+        let obj = match item {
+            JsonValue::Null => return Ok(()),
+            JsonValue::Array(_) => return Err(not_expanded("nested arrays are not supported")),
+            JsonValue::Object(obj) => obj,
+            _ => {
+                values.extend(self.expand_scalar(item, coercion, active)?);
+                return Ok(());
+            }
+        };
+        // value, list and set objects may use keyword aliases
+        let mut keywords = JsonValue::new_object();
+        for (key, value) in obj.iter() {
+            match active.expand_iri(key, true)? {
+                Some(keyword) if keyword.starts_with('@') => {
+                    keywords[keyword.as_str()] = value.clone()
+                }
+                _ => (),
+            }
+        }
+        if !keywords["@value"].is_null() {
+            let mut value = json::object! { "@value": keywords["@value"].clone() };
+            if let Some(dt) = keywords["@type"].as_str() {
+                match active.expand_iri(dt, true)? {
+                    Some(dt) => value["@type"] = dt.into(),
+                    None => return Ok(()),
+                }
+            }
+            for key in &["@language", "@direction"] {
+                if !keywords[*key].is_null() {
+                    value[*key] = keywords[*key].clone();
+                }
+            }
+            values.push(value);
+        } else if !keywords["@list"].is_null() {
+            let mut items = vec![];
+            for item in members(&keywords["@list"]) {
+                self.expand_value(item, def, active, &mut items)?;
+            }
+            values.push(json::object! { "@list": items });
+        } else if !keywords["@set"].is_null() {
+            for item in members(&keywords["@set"]) {
+                self.expand_value(item, def, active, values)?;
+            }
+        } else {
+            values.push(self.expand_node(obj, active)?);
+        }
+        Ok(())
+    }
+
+    /// Expand a string, number or boolean, coerced to `coercion` if any
+    /// (`None` if it is coerced to a term mapped to `null`).
+    fn expand_scalar(
+        &self,
+        item: &JsonValue,
+        coercion: Option<&str>,
+        active: &ActiveContext,
+    ) -> Result<Option<JsonValue>, JsonLdError> {
+        Ok(match (coercion, item.as_str()) {
+            (Some("@id"), Some(id)) => active
+                .expand_iri(id, false)?
+                .map(|id| json::object! { "@id": id }),
+            (Some("@vocab"), Some(id)) => active
+                .expand_iri(id, true)?
+                .map(|id| json::object! { "@id": id }),
+            (Some(dt), _) if !dt.starts_with('@') => active
+                .expand_iri(dt, true)?
+                .map(|dt| json::object! { "@value": item.clone(), "@type": dt }),
+            (_, Some(_)) if active.language.is_some() => {
+                let lang = active.language.clone();
+                Some(json::object! { "@value": item.clone(), "@language": lang })
+            }
+            _ => Some(json::object! { "@value": item.clone() }),
+        })
+    }
+}
+
+/// Append `values` to the entry `key` of `obj`.
+fn append(obj: &mut JsonValue, key: &str, values: Vec<JsonValue>) {
+    match &mut obj[key] {
+        JsonValue::Array(existing) => existing.extend(values),
+        entry => *entry = JsonValue::Array(values),
+    }
+}
+
+fn as_context_str<'v>(value: &'v JsonValue, key: &str) -> Result<&'v str, JsonLdError> {
+    value
+        .as_str()
+        .ok_or_else(|| invalid_context(&format!("{} must be a string", key)))
+}
+
+fn invalid_context(msg: &str) -> JsonLdError {
+    JsonLdError::InvalidContext(msg.to_string())
+}
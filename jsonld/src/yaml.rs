@@ -0,0 +1,281 @@
+//! Support for [YAML-LD], the YAML serialization of JSON-LD.
+//!
+//! YAML-LD documents are processed by converting them to the
+//! [internal representation](https://www.w3.org/TR/json-ld11-api/#dfn-internal-representation)
+//! of JSON-LD, i.e. to a [`JsonValue`], which [`to_json`] does.
+//! Only the JSON-compatible subset of YAML is supported:
+//! mapping keys must be strings,
+//! and numbers must be finite (`.inf` and `.nan` are rejected).
+//! Anchors and aliases are resolved.
+//!
+//! [`YamlLdParser`] then converts the resulting JSON to RDF with a [`JsonLdParser`],
+//! so it has the same limitations (only the most common features of contexts are supported).
+//!
+//! This module requires the `yaml` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::quad::stream::QuadSource;
+//! # use sophia_api::parser::QuadParser;
+//! # use sophia_jsonld::yaml::{to_json, YamlLdParser};
+//! let yaml = r#"
+//! - "@id": https://example.org/alice
+//!   https://schema.org/name:
+//!     - "@value": Alice
+//! "#;
+//! let doc = to_json(yaml)?;
+//! assert_eq!(doc[0]["https://schema.org/name"][0]["@value"], "Alice");
+//!
+//! let d: FastDataset = YamlLdParser::default().parse_str(yaml).collect_quads()?;
+//! assert_eq!(d.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [YAML-LD]: https://json-ld.github.io/yaml-ld/spec/
+//! [`JsonValue`]: https://docs.rs/json/0.12/json/enum.JsonValue.html
+//! [`to_json`]: fn.to_json.html
+//! [`YamlLdParser`]: struct.YamlLdParser.html
+//! [`JsonLdParser`]: ../parser/struct.JsonLdParser.html
+
+use crate::error::JsonLdError;
+use crate::parser::JsonLdParser;
+use json::object::Object;
+use json::JsonValue;
+use sophia::quad::TupleQuad;
+use sophia_api::parser::QuadParser;
+use sophia_term::BoxTerm;
+use std::io::BufRead;
+use thiserror::Error;
+use yaml_rust::{Yaml, YamlLoader};
+
+/// The media type of YAML-LD.
+pub const MEDIA_TYPE: &str = "application/ld+yaml";
+
+/// Convert the YAML-LD document `txt` to JSON.
+///
+/// `txt` must contain exactly one YAML document.
+///
+/// # Errors
+/// Fails if `txt` is not valid YAML,
+/// or if it can not be represented in JSON.
+pub fn to_json(txt: &str) -> Result<JsonValue, YamlLdError> {
+    let mut documents = YamlLoader::load_from_str(txt)?;
+    if documents.len() != 1 {
+        return Err(YamlLdError::DocumentCount(documents.len()));
+    }
+    convert(documents.pop().unwrap())
+}
+
+/// YAML-LD parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct YamlLdParser {
+    /// The JSON-LD parser used to convert documents to RDF, once converted to JSON.
+    pub jsonld: JsonLdParser,
+}
+
+impl<B: BufRead> QuadParser<B> for YamlLdParser {
+    type Source = YamlLdSource;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        if let Err(err) = data.read_to_string(&mut txt) {
+            return vec![Err(JsonLdError::from(err).into())].into_iter();
+        }
+        match to_json(&txt) {
+            Ok(doc) => self
+                .jsonld
+                .parse_json(&doc)
+                .map(|res| res.map_err(YamlLdError::from))
+                .collect::<Vec<_>>()
+                .into_iter(),
+            Err(err) => vec![Err(err)].into_iter(),
+        }
+    }
+}
+
+/// The source produced by [`YamlLdParser`](struct.YamlLdParser.html).
+pub type YamlLdSource = std::vec::IntoIter<Result<TupleQuad<BoxTerm>, YamlLdError>>;
+
+sophia_api::def_mod_functions_for_bufread_parser!(YamlLdParser, QuadParser);
+
+/// Convert a YAML node to JSON.
+fn convert(yaml: Yaml) -> Result<JsonValue, YamlLdError> {
+    Ok(match yaml {
+        Yaml::Null => JsonValue::Null,
+        Yaml::Boolean(b) => JsonValue::Boolean(b),
+        Yaml::Integer(i) => i.into(),
+        Yaml::Real(txt) => match txt.parse::<f64>() {
+            Ok(f) if f.is_finite() => f.into(),
+            _ => return Err(YamlLdError::InvalidNumber(txt)),
+        },
+        Yaml::String(s) => s.into(),
+        Yaml::Array(items) => JsonValue::Array(
+            items
+                .into_iter()
+                .map(convert)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Yaml::Hash(entries) => {
+            let mut object = Object::with_capacity(entries.len());
+            for (key, value) in entries {
+                match key {
+                    Yaml::String(key) => object.insert(&key, convert(value)?),
+                    key => return Err(YamlLdError::InvalidKey(format!("{:?}", key))),
+                }
+            }
+            JsonValue::Object(object)
+        }
+        Yaml::Alias(_) | Yaml::BadValue => return Err(YamlLdError::InvalidNode),
+    })
+}
+
+/// The error raised by [`to_json`](fn.to_json.html) and [`YamlLdParser`](struct.YamlLdParser.html).
+#[derive(Debug, Error)]
+pub enum YamlLdError {
+    /// The document, once converted to JSON, is not valid JSON-LD
+    /// (or it could not be read).
+    #[error("{0}")]
+    JsonLd(#[from] JsonLdError),
+    /// The document is not valid YAML.
+    #[error("invalid YAML: {0}")]
+    Yaml(#[from] yaml_rust::ScanError),
+    /// The YAML stream does not contain exactly one document.
+    #[error("expected exactly one YAML document, found {0}")]
+    DocumentCount(usize),
+    /// A mapping key is not a string.
+    #[error("mapping keys must be strings, found {0}")]
+    InvalidKey(String),
+    /// A number can not be represented in JSON.
+    #[error("invalid number: {0}")]
+    InvalidNumber(String),
+    /// A node could not be resolved (e.g. an unknown alias).
+    #[error("invalid YAML node")]
+    InvalidNode,
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia::dataset::inmem::FastDataset;
+    use sophia::graph::inmem::FastGraph;
+    use sophia::graph::Graph;
+    use sophia::ns::rdf;
+    use sophia::quad::stream::QuadSource;
+    use sophia::quad::Quad;
+    use sophia::triple::stream::TripleSource;
+    use sophia_api::term::CopyTerm;
+
+    #[test]
+    fn scalars_and_collections() -> Result<(), YamlLdError> {
+        let doc = to_json(
+            r#"
+"@id": https://example.org/alice
+"@type": [Person, Agent]
+age: 42
+height: 1.68
+married: false
+spouse: ~
+knows:
+  - "@id": https://example.org/bob
+"#,
+        )?;
+        let expected = json::object! {
+            "@id": "https://example.org/alice",
+            "@type": ["Person", "Agent"],
+            "age": 42,
+            "height": 1.68,
+            "married": false,
+            "spouse": null,
+            "knows": [{ "@id": "https://example.org/bob" }],
+        };
+        assert_eq!(doc, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn anchors() -> Result<(), YamlLdError> {
+        let doc = to_json("a: &x {b: 1}\nc: *x\n")?;
+        assert_eq!(doc["c"]["b"], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_into_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let yaml = r#"
+- "@id": alice
+  "@type": [https://schema.org/Person]
+  https://schema.org/name:
+    - "@value": Alice
+  https://schema.org/knows:
+    - "@id": bob
+      https://schema.org/name:
+        - {"@value": Bob, "@language": en}
+"#;
+        let parser = YamlLdParser {
+            jsonld: JsonLdParser {
+                base: Some("https://example.org/".into()),
+                ..JsonLdParser::default()
+            },
+        };
+        let g: FastGraph = parser
+            .parse_str(yaml)
+            .map_quads(|q| {
+                [
+                    BoxTerm::copy(q.s()),
+                    BoxTerm::copy(q.p()),
+                    BoxTerm::copy(q.o()),
+                ]
+            })
+            .collect_triples()?;
+        assert_eq!(g.triples().count(), 4);
+        let alice = BoxTerm::new_iri("https://example.org/alice")?;
+        let bob = BoxTerm::new_iri("https://example.org/bob")?;
+        let schema = |suffix: &str| BoxTerm::new_iri(format!("https://schema.org/{}", suffix));
+        assert!(g.contains(&alice, &rdf::type_, &schema("Person")?)?);
+        assert!(g.contains(&alice, &schema("knows")?, &bob)?);
+        assert!(g.contains(
+            &bob,
+            &schema("name")?,
+            &BoxTerm::new_literal_lang("Bob", "en")?
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        let res = parse_str("a: [1").collect_quads::<FastDataset>();
+        assert!(matches!(
+            res.map_err(|e| e.unwrap_source_error()),
+            Err(YamlLdError::Yaml(_))
+        ));
+        let res =
+            parse_str("\"@context\": https://example.org/ctx\n").collect_quads::<FastDataset>();
+        assert!(matches!(
+            res.map_err(|e| e.unwrap_source_error()),
+            Err(YamlLdError::JsonLd(JsonLdError::Loader(_)))
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(to_json("a: [1"), Err(YamlLdError::Yaml(_))));
+        assert!(matches!(
+            to_json("a: 1\n---\nb: 2\n"),
+            Err(YamlLdError::DocumentCount(2))
+        ));
+        assert!(matches!(
+            to_json("1: one\n"),
+            Err(YamlLdError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            to_json("a: .inf\n"),
+            Err(YamlLdError::InvalidNumber(_))
+        ));
+    }
+}
@@ -1,4 +1,9 @@
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use sophia::dataset::inmem::FastDataset;
+use sophia::generator::lubm::{queries, Lubm};
+use sophia::generator::Generator;
+use sophia::graph::inmem::FastGraph;
+use sophia::quad::stream::QuadSource;
 use sophia::triple::stream::TripleSource;
 
 fn parse_nt(c: &mut Criterion) {
@@ -11,6 +16,19 @@ fn parse_nt(c: &mut Criterion) {
     group.finish();
 }
 
+fn parse_nt_borrowed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse NT borrowed");
+    group.throughput(Throughput::Bytes(NT.len() as u64));
+    group.bench_with_input("parse NT borrowed", &NT, |b, &data| {
+        b.iter(|| -> FastGraph {
+            sophia::parser::nt::parse_borrowed(data)
+                .collect_triples()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
 fn parse_ttl(c: &mut Criterion) {
     let mut group = c.benchmark_group("parse TTL");
     group.throughput(Throughput::Bytes(TTL.len() as u64));
@@ -21,7 +39,42 @@ fn parse_ttl(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, parse_nt, parse_ttl);
+fn insert_generated(c: &mut Criterion) {
+    let generator = Generator {
+        quads: 10_000,
+        nodes: 1_000,
+        graphs: 10,
+        ..Generator::default()
+    };
+    let mut group = c.benchmark_group("insert generated");
+    group.throughput(Throughput::Elements(generator.quads as u64));
+    group.bench_with_input("insert generated", &generator, |b, generator| {
+        b.iter(|| -> FastDataset { generator.quads().collect_quads().unwrap() })
+    });
+    group.finish();
+}
+
+fn lubm(c: &mut Criterion) {
+    let lubm = Lubm {
+        departments: Some(5),
+        ..Lubm::default()
+    };
+    let g: FastGraph = lubm.triples().collect_triples().unwrap();
+    let mut group = c.benchmark_group("LUBM");
+    for (name, mut query) in queries() {
+        group.bench_function(name, |b| b.iter(|| query.process(&g).count()));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    parse_nt,
+    parse_nt_borrowed,
+    parse_ttl,
+    insert_generated,
+    lubm
+);
 criterion_main!(benches);
 
 const TTL: &str = r#"
@@ -0,0 +1,923 @@
+//! Create and query [Web Annotations].
+//!
+//! An [`Annotation`] links one or several *bodies* (typically a comment or a tag)
+//! to one or several *targets* (the annotated resources).
+//! A target can be a whole resource,
+//! or a part of it, described by a [`SpecificResource`] and its [`Selector`]s
+//! (e.g. a quotation in a text, or a region in an image).
+//!
+//! Annotations can be written into any [`MutableGraph`], and read back from any [`Graph`].
+//! [`annotations_on`] retrieves all the annotations of a given resource.
+//!
+//! NB: the `oa:bodyValue` shortcut, the selectors other than
+//! `oa:TextQuoteSelector`, `oa:TextPositionSelector` and `oa:FragmentSelector`,
+//! as well as refinements of selectors, are not supported.
+//!
+//! # Example
+//! ```
+//! # use sophia::annotation::*;
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::term::BoxTerm;
+//! let page = BoxTerm::new_iri("http://example.org/page")?;
+//! let target = SpecificResource::new(
+//!     BoxTerm::new_iri("http://example.org/anno1#target")?,
+//!     page.clone(),
+//!     Selector::text_quote(BoxTerm::new_iri("http://example.org/anno1#quote")?, "Sophia"),
+//! );
+//! let body = TextualBody::new(BoxTerm::new_iri("http://example.org/anno1#body")?, "Nice name!");
+//! let anno = Annotation::comment(BoxTerm::new_iri("http://example.org/anno1")?, target, body);
+//!
+//! let mut g = FastGraph::new();
+//! anno.add_to(&mut g)?;
+//! let found = annotations_on(&g, &page)?;
+//! assert_eq!(found, vec![anno]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Web Annotations]: https://www.w3.org/TR/annotation-model/
+//! [`Annotation`]: struct.Annotation.html
+//! [`SpecificResource`]: struct.SpecificResource.html
+//! [`Selector`]: enum.Selector.html
+//! [`annotations_on`]: fn.annotations_on.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::description::{invalid, objects, single, text, DescriptionError};
+use sophia_api::graph::{Graph, MgResult, MutableGraph};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{term_eq, CopyTerm, SimpleIri, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The Web Annotation namespace.
+mod oa {
+    sophia_api::namespace!(
+        "http://www.w3.org/ns/oa#",
+        Annotation,
+        SpecificResource,
+        TextualBody,
+        TextQuoteSelector,
+        TextPositionSelector,
+        FragmentSelector,
+        hasBody,
+        hasTarget,
+        hasSource,
+        hasSelector,
+        motivatedBy,
+        exact,
+        prefix,
+        suffix,
+        start,
+        end,
+        // motivations
+        assessing,
+        bookmarking,
+        classifying,
+        commenting,
+        describing,
+        editing,
+        highlighting,
+        identifying,
+        linking,
+        moderating,
+        questioning,
+        replying,
+        tagging
+    );
+}
+
+/// The Dublin Core elements namespace.
+mod dc {
+    sophia_api::namespace!("http://purl.org/dc/elements/1.1/", format, language);
+}
+
+/// The Dublin Core terms namespace.
+mod dct {
+    sophia_api::namespace!("http://purl.org/dc/terms/", creator, created, conformsTo);
+}
+
+/// The reasons why an annotation is created, as defined by the Web Annotation vocabulary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Motivation {
+    /// `oa:assessing`: to assess the target.
+    Assessing,
+    /// `oa:bookmarking`: to create a bookmark to the target.
+    Bookmarking,
+    /// `oa:classifying`: to classify the target.
+    Classifying,
+    /// `oa:commenting`: to comment about the target.
+    Commenting,
+    /// `oa:describing`: to describe the target.
+    Describing,
+    /// `oa:editing`: to request a change or edit to the target.
+    Editing,
+    /// `oa:highlighting`: to highlight the target.
+    Highlighting,
+    /// `oa:identifying`: to assign an identity to the target.
+    Identifying,
+    /// `oa:linking`: to link to a resource related to the target.
+    Linking,
+    /// `oa:moderating`: to assign some value or quality to the target.
+    Moderating,
+    /// `oa:questioning`: to ask a question about the target.
+    Questioning,
+    /// `oa:replying`: to reply to a previous statement.
+    Replying,
+    /// `oa:tagging`: to associate a tag with the target.
+    Tagging,
+}
+
+impl Motivation {
+    /// All the motivations.
+    pub const ALL: &'static [Motivation] = &[
+        Motivation::Assessing,
+        Motivation::Bookmarking,
+        Motivation::Classifying,
+        Motivation::Commenting,
+        Motivation::Describing,
+        Motivation::Editing,
+        Motivation::Highlighting,
+        Motivation::Identifying,
+        Motivation::Linking,
+        Motivation::Moderating,
+        Motivation::Questioning,
+        Motivation::Replying,
+        Motivation::Tagging,
+    ];
+
+    /// The IRI of this motivation.
+    pub fn iri(self) -> &'static SimpleIri<'static> {
+        match self {
+            Motivation::Assessing => &oa::assessing,
+            Motivation::Bookmarking => &oa::bookmarking,
+            Motivation::Classifying => &oa::classifying,
+            Motivation::Commenting => &oa::commenting,
+            Motivation::Describing => &oa::describing,
+            Motivation::Editing => &oa::editing,
+            Motivation::Highlighting => &oa::highlighting,
+            Motivation::Identifying => &oa::identifying,
+            Motivation::Linking => &oa::linking,
+            Motivation::Moderating => &oa::moderating,
+            Motivation::Questioning => &oa::questioning,
+            Motivation::Replying => &oa::replying,
+            Motivation::Tagging => &oa::tagging,
+        }
+    }
+
+    /// The motivation identified by `iri`, if any.
+    pub fn from_iri<T: TTerm + ?Sized>(iri: &T) -> Option<Self> {
+        Self::ALL.iter().copied().find(|m| term_eq(m.iri(), iri))
+    }
+}
+
+/// A Web Annotation (`oa:Annotation`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// The resource describing the annotation.
+    pub id: BoxTerm,
+    /// The reason why the annotation was created (`oa:motivatedBy`).
+    pub motivation: Option<Motivation>,
+    /// The bodies of the annotation (`oa:hasBody`).
+    pub bodies: Vec<Body>,
+    /// The targets of the annotation (`oa:hasTarget`); there must be at least one.
+    pub targets: Vec<Target>,
+    /// The creator of the annotation (`dct:creator`).
+    pub creator: Option<BoxTerm>,
+    /// The creation date of the annotation (`dct:created`), as an `xsd:dateTime`.
+    pub created: Option<String>,
+}
+
+impl Annotation {
+    /// Build an annotation of `target`, without body.
+    pub fn new<T: Into<Target>>(id: BoxTerm, target: T) -> Self {
+        Annotation {
+            id,
+            motivation: None,
+            bodies: vec![],
+            targets: vec![target.into()],
+            creator: None,
+            created: None,
+        }
+    }
+
+    /// Build an annotation commenting `target` with a textual `body`.
+    pub fn comment<T: Into<Target>>(id: BoxTerm, target: T, body: TextualBody) -> Self {
+        Annotation {
+            motivation: Some(Motivation::Commenting),
+            bodies: vec![Body::Text(body)],
+            ..Annotation::new(id, target)
+        }
+    }
+
+    /// Build an annotation highlighting `target`.
+    pub fn highlight<T: Into<Target>>(id: BoxTerm, target: T) -> Self {
+        Annotation {
+            motivation: Some(Motivation::Highlighting),
+            ..Annotation::new(id, target)
+        }
+    }
+
+    /// Build an annotation tagging `target` with the textual `tag`.
+    pub fn tag<T: Into<Target>>(id: BoxTerm, target: T, tag: TextualBody) -> Self {
+        Annotation {
+            motivation: Some(Motivation::Tagging),
+            bodies: vec![Body::Text(tag)],
+            ..Annotation::new(id, target)
+        }
+    }
+
+    /// Whether this annotation targets `resource`,
+    /// either as a whole or through a specific resource.
+    pub fn annotates<T: TTerm + ?Sized>(&self, resource: &T) -> bool {
+        self.targets.iter().any(|t| term_eq(t.source(), resource))
+    }
+
+    /// Insert the description of this annotation, of its bodies and of its targets, in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &oa::Annotation)?;
+        if let Some(motivation) = self.motivation {
+            graph.insert(&self.id, &oa::motivatedBy, motivation.iri())?;
+        }
+        for body in &self.bodies {
+            graph.insert(&self.id, &oa::hasBody, body.id())?;
+            if let Body::Text(body) = body {
+                body.add_to(graph)?;
+            }
+        }
+        for target in &self.targets {
+            graph.insert(&self.id, &oa::hasTarget, target.id())?;
+            if let Target::Specific(target) = target {
+                target.add_to(graph)?;
+            }
+        }
+        if let Some(creator) = &self.creator {
+            graph.insert(&self.id, &dct::creator, creator)?;
+        }
+        if let Some(created) = &self.created {
+            let created = BoxTerm::new_literal_dt_unchecked(created.as_str(), xsd::dateTime);
+            graph.insert(&self.id, &dct::created, &created)?;
+        }
+        Ok(())
+    }
+
+    /// Read the description of annotation `id` from `graph`.
+    ///
+    /// Bodies and targets are sorted by identifier.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> AnResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let motivation = match single(graph, &id, &oa::motivatedBy)? {
+            Some(m) => Some(
+                Motivation::from_iri(&m)
+                    .ok_or_else(|| invalid(&id, format!("unknown motivation {}", m)))?,
+            ),
+            None => None,
+        };
+        let mut bodies = objects(graph, &id, &oa::hasBody)?
+            .into_iter()
+            .map(|b| Body::from_graph(graph, b))
+            .collect::<Result<Vec<_>, _>>()?;
+        bodies.sort_by(|a, b| a.id().value().cmp(&b.id().value()));
+        let mut targets = objects(graph, &id, &oa::hasTarget)?
+            .into_iter()
+            .map(|t| Target::from_graph(graph, t))
+            .collect::<Result<Vec<_>, _>>()?;
+        if targets.is_empty() {
+            return Err(invalid(&id, "no target").into());
+        }
+        targets.sort_by(|a, b| a.id().value().cmp(&b.id().value()));
+        Ok(Annotation {
+            motivation,
+            bodies,
+            targets,
+            creator: single(graph, &id, &dct::creator)?,
+            created: text(graph, &id, &dct::created)?,
+            id,
+        })
+    }
+}
+
+/// The body of an [`Annotation`](struct.Annotation.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Body {
+    /// A resource described elsewhere (e.g. a web page, or a concept from a thesaurus).
+    Resource(BoxTerm),
+    /// An embedded textual body.
+    Text(TextualBody),
+}
+
+impl Body {
+    /// The identifier of this body.
+    pub fn id(&self) -> &BoxTerm {
+        match self {
+            Body::Resource(id) => id,
+            Body::Text(body) => &body.id,
+        }
+    }
+
+    fn from_graph<G: Graph>(graph: &G, id: BoxTerm) -> AnResult<G, Self> {
+        if is_a(graph, &id, &oa::TextualBody)? || !objects(graph, &id, &rdf::value)?.is_empty() {
+            TextualBody::from_graph(graph, &id).map(Body::Text)
+        } else {
+            Ok(Body::Resource(id))
+        }
+    }
+}
+
+impl From<BoxTerm> for Body {
+    fn from(id: BoxTerm) -> Self {
+        Body::Resource(id)
+    }
+}
+
+impl From<TextualBody> for Body {
+    fn from(body: TextualBody) -> Self {
+        Body::Text(body)
+    }
+}
+
+/// A textual body (`oa:TextualBody`), such as a comment or a tag.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextualBody {
+    /// The resource describing the body.
+    pub id: BoxTerm,
+    /// The text of the body (`rdf:value`).
+    pub value: String,
+    /// The media type of the text (`dc:format`), e.g. `"text/plain"` or `"text/html"`.
+    pub format: Option<String>,
+    /// The language of the text (`dc:language`), e.g. `"en"`.
+    pub language: Option<String>,
+}
+
+impl TextualBody {
+    /// Build a textual body with the given text.
+    pub fn new<T: Into<String>>(id: BoxTerm, value: T) -> Self {
+        TextualBody {
+            id,
+            value: value.into(),
+            format: None,
+            language: None,
+        }
+    }
+
+    /// Insert the description of this body in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &oa::TextualBody)?;
+        graph.insert(&self.id, &rdf::value, &string_literal(&self.value))?;
+        insert_text(graph, &self.id, &dc::format, &self.format)?;
+        insert_text(graph, &self.id, &dc::language, &self.language)
+    }
+
+    /// Read the description of body `id` from `graph`.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> AnResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        Ok(TextualBody {
+            value: text(graph, &id, &rdf::value)?.ok_or_else(|| invalid(&id, "no value"))?,
+            format: text(graph, &id, &dc::format)?,
+            language: text(graph, &id, &dc::language)?,
+            id,
+        })
+    }
+}
+
+/// The target of an [`Annotation`](struct.Annotation.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// A whole resource.
+    Resource(BoxTerm),
+    /// A part of a resource.
+    Specific(SpecificResource),
+}
+
+impl Target {
+    /// The identifier of this target.
+    pub fn id(&self) -> &BoxTerm {
+        match self {
+            Target::Resource(id) => id,
+            Target::Specific(target) => &target.id,
+        }
+    }
+
+    /// The annotated resource, i.e. this target itself,
+    /// or the source of the specific resource.
+    pub fn source(&self) -> &BoxTerm {
+        match self {
+            Target::Resource(id) => id,
+            Target::Specific(target) => &target.source,
+        }
+    }
+
+    fn from_graph<G: Graph>(graph: &G, id: BoxTerm) -> AnResult<G, Self> {
+        if objects(graph, &id, &oa::hasSource)?.is_empty() {
+            Ok(Target::Resource(id))
+        } else {
+            SpecificResource::from_graph(graph, &id).map(Target::Specific)
+        }
+    }
+}
+
+impl From<BoxTerm> for Target {
+    fn from(id: BoxTerm) -> Self {
+        Target::Resource(id)
+    }
+}
+
+impl From<SpecificResource> for Target {
+    fn from(target: SpecificResource) -> Self {
+        Target::Specific(target)
+    }
+}
+
+/// A part of a resource (`oa:SpecificResource`), identified by selectors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecificResource {
+    /// The resource describing this part.
+    pub id: BoxTerm,
+    /// The resource containing this part (`oa:hasSource`).
+    pub source: BoxTerm,
+    /// The ways to identify this part in its source (`oa:hasSelector`).
+    ///
+    /// If there are several selectors, they identify the same part.
+    pub selectors: Vec<Selector>,
+}
+
+impl SpecificResource {
+    /// Build the part of `source` identified by `selector`.
+    pub fn new(id: BoxTerm, source: BoxTerm, selector: Selector) -> Self {
+        SpecificResource {
+            id,
+            source,
+            selectors: vec![selector],
+        }
+    }
+
+    /// Insert the description of this resource, and of its selectors, in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &oa::SpecificResource)?;
+        graph.insert(&self.id, &oa::hasSource, &self.source)?;
+        for selector in &self.selectors {
+            graph.insert(&self.id, &oa::hasSelector, selector.id())?;
+            selector.add_to(graph)?;
+        }
+        Ok(())
+    }
+
+    /// Read the description of resource `id` from `graph`.
+    ///
+    /// Selectors are sorted by identifier.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> AnResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let source =
+            single(graph, &id, &oa::hasSource)?.ok_or_else(|| invalid(&id, "no source"))?;
+        let mut selectors = objects(graph, &id, &oa::hasSelector)?
+            .iter()
+            .map(|s| Selector::from_graph(graph, s))
+            .collect::<Result<Vec<_>, _>>()?;
+        selectors.sort_by(|a, b| a.id().value().cmp(&b.id().value()));
+        Ok(SpecificResource {
+            id,
+            source,
+            selectors,
+        })
+    }
+}
+
+/// A way to identify a part of a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    /// A quotation of the selected text (`oa:TextQuoteSelector`).
+    TextQuote {
+        /// The resource describing the selector.
+        id: BoxTerm,
+        /// The selected text (`oa:exact`).
+        exact: String,
+        /// The text immediately before the selection (`oa:prefix`).
+        prefix: Option<String>,
+        /// The text immediately after the selection (`oa:suffix`).
+        suffix: Option<String>,
+    },
+    /// The position of the selected text (`oa:TextPositionSelector`),
+    /// in characters from the start of the source.
+    TextPosition {
+        /// The resource describing the selector.
+        id: BoxTerm,
+        /// The position of the first selected character (`oa:start`).
+        start: u64,
+        /// The position following the last selected character (`oa:end`).
+        end: u64,
+    },
+    /// A fragment identifier of the selection (`oa:FragmentSelector`),
+    /// e.g. `xywh=10,10,100,100` for a region of an image.
+    Fragment {
+        /// The resource describing the selector.
+        id: BoxTerm,
+        /// The fragment identifier (`rdf:value`).
+        value: String,
+        /// The specification defining the syntax of the fragment (`dct:conformsTo`).
+        conforms_to: Option<BoxTerm>,
+    },
+}
+
+impl Selector {
+    /// Build a selector quoting `exact`.
+    pub fn text_quote<T: Into<String>>(id: BoxTerm, exact: T) -> Self {
+        Selector::TextQuote {
+            id,
+            exact: exact.into(),
+            prefix: None,
+            suffix: None,
+        }
+    }
+
+    /// Build a selector of the characters between `start` (included) and `end` (excluded).
+    pub fn text_position(id: BoxTerm, start: u64, end: u64) -> Self {
+        Selector::TextPosition { id, start, end }
+    }
+
+    /// Build a selector with the fragment identifier `value`.
+    pub fn fragment<T: Into<String>>(id: BoxTerm, value: T) -> Self {
+        Selector::Fragment {
+            id,
+            value: value.into(),
+            conforms_to: None,
+        }
+    }
+
+    /// The identifier of this selector.
+    pub fn id(&self) -> &BoxTerm {
+        match self {
+            Selector::TextQuote { id, .. }
+            | Selector::TextPosition { id, .. }
+            | Selector::Fragment { id, .. } => id,
+        }
+    }
+
+    /// Insert the description of this selector in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        match self {
+            Selector::TextQuote {
+                id,
+                exact,
+                prefix,
+                suffix,
+            } => {
+                graph.insert(id, &rdf::type_, &oa::TextQuoteSelector)?;
+                graph.insert(id, &oa::exact, &string_literal(exact))?;
+                insert_text(graph, id, &oa::prefix, prefix)?;
+                insert_text(graph, id, &oa::suffix, suffix)?;
+            }
+            Selector::TextPosition { id, start, end } => {
+                graph.insert(id, &rdf::type_, &oa::TextPositionSelector)?;
+                graph.insert(id, &oa::start, &integer_literal(*start))?;
+                graph.insert(id, &oa::end, &integer_literal(*end))?;
+            }
+            Selector::Fragment {
+                id,
+                value,
+                conforms_to,
+            } => {
+                graph.insert(id, &rdf::type_, &oa::FragmentSelector)?;
+                graph.insert(id, &rdf::value, &string_literal(value))?;
+                if let Some(spec) = conforms_to {
+                    graph.insert(id, &dct::conformsTo, spec)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the description of selector `id` from `graph`.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> AnResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        if is_a(graph, &id, &oa::TextQuoteSelector)? {
+            Ok(Selector::TextQuote {
+                exact: text(graph, &id, &oa::exact)?
+                    .ok_or_else(|| invalid(&id, "no exact text"))?,
+                prefix: text(graph, &id, &oa::prefix)?,
+                suffix: text(graph, &id, &oa::suffix)?,
+                id,
+            })
+        } else if is_a(graph, &id, &oa::TextPositionSelector)? {
+            Ok(Selector::TextPosition {
+                start: integer(graph, &id, &oa::start)?,
+                end: integer(graph, &id, &oa::end)?,
+                id,
+            })
+        } else if is_a(graph, &id, &oa::FragmentSelector)? {
+            Ok(Selector::Fragment {
+                value: text(graph, &id, &rdf::value)?.ok_or_else(|| invalid(&id, "no value"))?,
+                conforms_to: single(graph, &id, &dct::conformsTo)?,
+                id,
+            })
+        } else {
+            Err(invalid(&id, "no supported selector type").into())
+        }
+    }
+}
+
+/// The error raised when reading annotations.
+#[derive(Debug, Error)]
+pub enum AnnotationError<E: 'static + Error> {
+    /// The graph raised an error.
+    #[error("Error in the graph: {0}")]
+    Graph(#[source] E),
+    /// A description is not well-formed, or not supported.
+    #[error("Invalid description of {resource}: {message}")]
+    Invalid {
+        /// The offending resource.
+        resource: String,
+        /// The reason why its description is invalid.
+        message: String,
+    },
+}
+
+type AnResult<G, T> = Result<T, AnnotationError<<G as Graph>::Error>>;
+
+impl<E: 'static + Error> From<DescriptionError<E>> for AnnotationError<E> {
+    fn from(err: DescriptionError<E>) -> Self {
+        match err {
+            DescriptionError::Graph(err) => AnnotationError::Graph(err),
+            DescriptionError::Invalid { resource, message } => {
+                AnnotationError::Invalid { resource, message }
+            }
+        }
+    }
+}
+
+/// Read all the annotations (instances of `oa:Annotation`) described in `graph`,
+/// sorted by identifier.
+pub fn annotations<G: Graph>(graph: &G) -> AnResult<G, Vec<Annotation>> {
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    for t in graph.triples_with_po(&rdf::type_, &oa::Annotation) {
+        let id = BoxTerm::copy(t.map_err(AnnotationError::Graph)?.s());
+        if seen.insert(id.clone()) {
+            ids.push(id);
+        }
+    }
+    ids.sort_by(|a, b| a.value().cmp(&b.value()));
+    ids.iter()
+        .map(|id| Annotation::from_graph(graph, id))
+        .collect()
+}
+
+/// Read all the annotations described in `graph` that target `resource`,
+/// either as a whole or through a specific resource, sorted by identifier.
+pub fn annotations_on<G, T>(graph: &G, resource: &T) -> AnResult<G, Vec<Annotation>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    let mut targets = vec![BoxTerm::copy(resource)];
+    for t in graph.triples_with_po(&oa::hasSource, resource) {
+        targets.push(BoxTerm::copy(t.map_err(AnnotationError::Graph)?.s()));
+    }
+    for target in &targets {
+        for t in graph.triples_with_po(&oa::hasTarget, target) {
+            let id = BoxTerm::copy(t.map_err(AnnotationError::Graph)?.s());
+            if seen.insert(id.clone()) {
+                ids.push(id);
+            }
+        }
+    }
+    ids.sort_by(|a, b| a.value().cmp(&b.value()));
+    ids.iter()
+        .map(|id| Annotation::from_graph(graph, id))
+        .collect()
+}
+
+fn string_literal(txt: &str) -> BoxTerm {
+    BoxTerm::new_literal_dt_unchecked(txt, xsd::string)
+}
+
+fn integer_literal(value: u64) -> BoxTerm {
+    BoxTerm::new_literal_dt_unchecked(value.to_string(), xsd::nonNegativeInteger)
+}
+
+fn insert_text<G, T>(graph: &mut G, s: &BoxTerm, p: &T, txt: &Option<String>) -> MgResult<G, ()>
+where
+    G: MutableGraph,
+    T: TTerm + ?Sized,
+{
+    if let Some(txt) = txt {
+        graph.insert(s, p, &string_literal(txt))?;
+    }
+    Ok(())
+}
+
+/// Whether `s` has type `class`.
+fn is_a<G, T>(g: &G, s: &BoxTerm, class: &T) -> AnResult<G, bool>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    g.contains(s, &rdf::type_, class)
+        .map_err(AnnotationError::Graph)
+}
+
+/// The (mandatory) integer value of `p` for `s`.
+fn integer<G, T>(g: &G, s: &BoxTerm, p: &T) -> AnResult<G, u64>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    let value = single(g, s, p)?
+        .filter(|v| v.kind() == TermKind::Literal)
+        .ok_or_else(|| invalid(s, format!("no integer value for <{}>", p.value())))?;
+    let parsed = value
+        .value()
+        .parse()
+        .map_err(|_| invalid(s, format!("invalid integer {}", value)).into());
+    parsed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::triple::stream::TripleSource;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", txt)).unwrap()
+    }
+
+    fn example() -> Annotation {
+        let target = SpecificResource {
+            id: iri("anno-target"),
+            source: iri("text"),
+            selectors: vec![
+                Selector::TextQuote {
+                    id: iri("anno-quote"),
+                    exact: "ipsum".to_string(),
+                    prefix: Some("Lorem ".to_string()),
+                    suffix: None,
+                },
+                Selector::text_position(iri("anno-range"), 6, 11),
+            ],
+        };
+        let body = TextualBody {
+            format: Some("text/plain".to_string()),
+            language: Some("la".to_string()),
+            ..TextualBody::new(iri("anno-body"), "sic")
+        };
+        Annotation {
+            motivation: Some(Motivation::Commenting),
+            bodies: vec![Body::Text(body), Body::Resource(iri("concept"))],
+            creator: Some(iri("me")),
+            created: Some("2020-01-01T00:00:00Z".to_string()),
+            ..Annotation::new(iri("anno"), target)
+        }
+    }
+
+    #[test]
+    fn write() -> Res {
+        let mut g = FastGraph::new();
+        example().add_to(&mut g)?;
+        let expected: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix oa: <http://www.w3.org/ns/oa#>.
+            @prefix dc: <http://purl.org/dc/elements/1.1/>.
+            @prefix dct: <http://purl.org/dc/terms/>.
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+            @prefix : <http://example.org/>.
+
+            :anno a oa:Annotation;
+                oa:motivatedBy oa:commenting;
+                oa:hasBody :anno-body, :concept;
+                oa:hasTarget :anno-target;
+                dct:creator :me;
+                dct:created "2020-01-01T00:00:00Z"^^xsd:dateTime.
+
+            :anno-body a oa:TextualBody;
+                rdf:value "sic";
+                dc:format "text/plain";
+                dc:language "la".
+
+            :anno-target a oa:SpecificResource;
+                oa:hasSource :text;
+                oa:hasSelector :anno-quote, :anno-range.
+
+            :anno-quote a oa:TextQuoteSelector;
+                oa:exact "ipsum";
+                oa:prefix "Lorem ".
+
+            :anno-range a oa:TextPositionSelector;
+                oa:start "6"^^xsd:nonNegativeInteger;
+                oa:end "11"^^xsd:nonNegativeInteger.
+            "#,
+        )
+        .collect_triples()?;
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Res {
+        let mut g = FastGraph::new();
+        let anno = example();
+        anno.add_to(&mut g)?;
+        assert_eq!(Annotation::from_graph(&g, &anno.id)?, anno);
+        assert_eq!(annotations(&g)?, vec![anno]);
+        Ok(())
+    }
+
+    #[test]
+    fn query() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix oa: <http://www.w3.org/ns/oa#>.
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+            @prefix : <http://example.org/>.
+
+            :a1 a oa:Annotation;
+                oa:motivatedBy oa:highlighting;
+                oa:hasTarget [
+                    oa:hasSource :image;
+                    oa:hasSelector [
+                        a oa:FragmentSelector;
+                        rdf:value "xywh=10,10,50,50"
+                    ]
+                ].
+            :a2 a oa:Annotation;
+                oa:motivatedBy oa:tagging;
+                oa:hasBody [ a oa:TextualBody; rdf:value "cat" ];
+                oa:hasTarget :image.
+            :a3 a oa:Annotation;
+                oa:hasTarget :other.
+            "#,
+        )
+        .collect_triples()?;
+        assert_eq!(annotations(&g)?.len(), 3);
+
+        let found = annotations_on(&g, &iri("image"))?;
+        let ids: Vec<_> = found.iter().map(|a| a.id.value().to_string()).collect();
+        assert_eq!(ids, ["http://example.org/a1", "http://example.org/a2"]);
+        assert!(found.iter().all(|a| a.annotates(&iri("image"))));
+
+        let a1 = &found[0];
+        assert_eq!(a1.motivation, Some(Motivation::Highlighting));
+        match &a1.targets[0] {
+            Target::Specific(target) => match &target.selectors[..] {
+                [Selector::Fragment { value, .. }] => assert_eq!(value, "xywh=10,10,50,50"),
+                other => panic!("unexpected selectors {:?}", other),
+            },
+            other => panic!("unexpected target {:?}", other),
+        }
+
+        let a2 = &found[1];
+        assert_eq!(a2.motivation, Some(Motivation::Tagging));
+        match &a2.bodies[..] {
+            [Body::Text(body)] => assert_eq!(body.value, "cat"),
+            other => panic!("unexpected bodies {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_annotations() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix oa: <http://www.w3.org/ns/oa#>.
+            @prefix : <http://example.org/>.
+
+            :no-target a oa:Annotation.
+            :bad-motivation a oa:Annotation;
+                oa:motivatedBy :fun;
+                oa:hasTarget :x.
+            :bad-selector a oa:Annotation;
+                oa:hasTarget [ oa:hasSource :x; oa:hasSelector :s ].
+            "#,
+        )
+        .collect_triples()?;
+        assert!(Annotation::from_graph(&g, &iri("no-target")).is_err());
+        assert!(Annotation::from_graph(&g, &iri("bad-motivation")).is_err());
+        assert!(Annotation::from_graph(&g, &iri("bad-selector")).is_err());
+        assert!(annotations(&g).is_err());
+        Ok(())
+    }
+}
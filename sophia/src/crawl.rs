@@ -0,0 +1,409 @@
+//! A Linked Data crawler.
+//!
+//! A [`Crawler`] starts from some seed IRIs, dereferences them,
+//! and follows selected *link predicates* to other documents,
+//! up to a maximum depth and a maximum number of documents (the *budget*).
+//! Each document is fetched at most once,
+//! and the harvested triples are streamed as quads
+//! whose graph name is the IRI of the document they come from.
+//!
+//! Paginated resources are supported:
+//! the next page of a document (indicated by `hydra:next` or `as:next`)
+//! is considered as part of the same resource,
+//! so it is fetched at the same depth as the document linking to it
+//! (but still counts towards the budget).
+//!
+//! This crate does not include an HTTP client:
+//! dereferencing is delegated to a [`Fetcher`],
+//! which will typically perform content negotiation and parse the response
+//! with one of the [parsers](../parser/index.html).
+//!
+//! # Example
+//! ```
+//! # use sophia::crawl::*;
+//! # use sophia::term::BoxTerm;
+//! # use sophia::triple::stream::TripleSource;
+//! # use std::collections::HashMap;
+//! /// A fetcher serving documents from memory
+//! struct InMemory(HashMap<&'static str, &'static str>);
+//!
+//! impl Fetcher for InMemory {
+//!     type Error = std::io::Error;
+//!     fn fetch(&mut self, iri: &str) -> Result<Document, Self::Error> {
+//!         let turtle = self.0.get(iri).ok_or(std::io::ErrorKind::NotFound)?;
+//!         let triples = sophia::parser::turtle::parse_str(turtle)
+//!             .collect_triples()
+//!             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+//!         Ok(Document { iri: iri.to_string(), triples })
+//!     }
+//! }
+//!
+//! let mut docs = HashMap::new();
+//! docs.insert("http://example.org/a", "<http://example.org/a#it> <http://www.w3.org/2000/01/rdf-schema#seeAlso> <http://example.org/b#it>.");
+//! docs.insert("http://example.org/b", "<http://example.org/b#it> a <http://example.org/Thing>.");
+//! let mut crawler = Crawler::new(InMemory(docs));
+//! crawler.add_link_predicate(&sophia::ns::rdfs::seeAlso).set_max_depth(1);
+//! let quads = crawler
+//!     .crawl(&["http://example.org/a"])
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(quads.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Crawler`]: struct.Crawler.html
+//! [`Fetcher`]: trait.Fetcher.html
+
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The IRIs of the predicates indicating the next page of a paginated resource.
+pub const NEXT_PAGE_PREDICATES: &[&str] = &[
+    "http://www.w3.org/ns/hydra/core#next",
+    "https://www.w3.org/ns/activitystreams#next",
+];
+
+/// A document retrieved by a [`Fetcher`](trait.Fetcher.html).
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    /// The IRI of the document, after redirections.
+    pub iri: String,
+    /// The triples of the document.
+    pub triples: Vec<[BoxTerm; 3]>,
+}
+
+/// The fetch layer used by a [`Crawler`](struct.Crawler.html) to dereference IRIs.
+pub trait Fetcher {
+    /// The error raised when a document can not be fetched or parsed.
+    type Error: 'static + Error;
+
+    /// Dereference `iri` (which has no fragment identifier) and return the retrieved document.
+    fn fetch(&mut self, iri: &str) -> Result<Document, Self::Error>;
+}
+
+/// The error yielded by a crawl when a document can not be fetched.
+#[derive(Debug, Error)]
+#[error("Could not fetch <{iri}>: {source}")]
+pub struct CrawlError<E: 'static + Error> {
+    /// The IRI of the document.
+    pub iri: String,
+    /// The error raised by the fetcher.
+    pub source: E,
+}
+
+/// A Linked Data crawler.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Crawler<F> {
+    fetcher: F,
+    link_predicates: Vec<BoxTerm>,
+    max_depth: usize,
+    max_documents: usize,
+    pagination: bool,
+}
+
+impl<F: Fetcher> Crawler<F> {
+    /// Build a crawler using `fetcher`, following no link predicate,
+    /// with no limit on the number of documents, and following pagination.
+    pub fn new(fetcher: F) -> Self {
+        Crawler {
+            fetcher,
+            link_predicates: vec![],
+            max_depth: 0,
+            max_documents: usize::MAX,
+            pagination: true,
+        }
+    }
+
+    /// Follow `predicate` from harvested documents to other documents.
+    pub fn add_link_predicate<T>(&mut self, predicate: &T) -> &mut Self
+    where
+        T: TTerm + ?Sized,
+    {
+        self.link_predicates.push(BoxTerm::copy(predicate));
+        self
+    }
+
+    /// Set how many links can be followed from the seeds (0 by default).
+    pub fn set_max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the maximum number of documents fetched by a crawl (unlimited by default).
+    pub fn set_max_documents(&mut self, max_documents: usize) -> &mut Self {
+        self.max_documents = max_documents;
+        self
+    }
+
+    /// Set whether the next pages of paginated resources are fetched (true by default).
+    pub fn set_pagination(&mut self, pagination: bool) -> &mut Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// The predicates followed by this crawler.
+    pub fn link_predicates(&self) -> &[BoxTerm] {
+        &self.link_predicates
+    }
+
+    /// The maximum number of links followed from the seeds.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// The maximum number of documents fetched by a crawl.
+    pub fn max_documents(&self) -> usize {
+        self.max_documents
+    }
+
+    /// Whether the next pages of paginated resources are fetched.
+    pub fn pagination(&self) -> bool {
+        self.pagination
+    }
+
+    /// Start crawling from `seeds`.
+    ///
+    /// Documents are fetched lazily, breadth-first, as the returned iterator is consumed.
+    /// A document that can not be fetched yields an error,
+    /// after which the crawl can be resumed by calling `next` again.
+    pub fn crawl<I>(&mut self, seeds: I) -> Crawl<'_, F>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut crawl = Crawl {
+            crawler: self,
+            queue: VecDeque::new(),
+            visited: HashSet::new(),
+            fetched: 0,
+            buffer: vec![].into_iter(),
+        };
+        for seed in seeds {
+            crawl.enqueue(seed.as_ref(), 0, false);
+        }
+        crawl
+    }
+}
+
+/// An ongoing crawl, iterating over the harvested quads.
+///
+/// It is a [`QuadSource`](../quad/stream/trait.QuadSource.html).
+pub struct Crawl<'a, F: Fetcher> {
+    crawler: &'a mut Crawler<F>,
+    queue: VecDeque<(String, usize)>,
+    visited: HashSet<String>,
+    fetched: usize,
+    buffer: std::vec::IntoIter<TupleQuad<BoxTerm>>,
+}
+
+impl<'a, F: Fetcher> Crawl<'a, F> {
+    /// The number of documents fetched so far (including failed attempts).
+    pub fn fetched(&self) -> usize {
+        self.fetched
+    }
+
+    fn enqueue(&mut self, iri: &str, depth: usize, front: bool) {
+        let iri = iri.split('#').next().unwrap();
+        if self.visited.insert(iri.to_string()) {
+            if front {
+                self.queue.push_front((iri.to_string(), depth));
+            } else {
+                self.queue.push_back((iri.to_string(), depth));
+            }
+        }
+    }
+
+    fn harvest(&mut self, doc: Document, depth: usize) {
+        self.visited.insert(doc.iri.clone());
+        let graph_name = BoxTerm::new_iri_unchecked(doc.iri);
+        for t in &doc.triples {
+            if t[2].kind() != TermKind::Iri {
+                continue;
+            }
+            let is_next_page = self.crawler.pagination
+                && NEXT_PAGE_PREDICATES.iter().any(|p| &t[1].value()[..] == *p);
+            if is_next_page {
+                self.enqueue(&t[2].value(), depth, true);
+            } else if depth < self.crawler.max_depth
+                && self
+                    .crawler
+                    .link_predicates
+                    .iter()
+                    .any(|p| term_eq(p, &t[1]))
+            {
+                self.enqueue(&t[2].value(), depth + 1, false);
+            }
+        }
+        let quads: Vec<_> = doc
+            .triples
+            .into_iter()
+            .map(|t| (t, Some(graph_name.clone())))
+            .collect();
+        self.buffer = quads.into_iter();
+    }
+}
+
+impl<'a, F: Fetcher> Iterator for Crawl<'a, F> {
+    type Item = Result<TupleQuad<BoxTerm>, CrawlError<F::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(quad) = self.buffer.next() {
+                return Some(Ok(quad));
+            }
+            if self.fetched >= self.crawler.max_documents {
+                return None;
+            }
+            let (iri, depth) = self.queue.pop_front()?;
+            self.fetched += 1;
+            match self.crawler.fetcher.fetch(&iri) {
+                Ok(doc) => self.harvest(doc, depth),
+                Err(source) => return Some(Err(CrawlError { iri, source })),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ns::rdfs;
+    use crate::triple::stream::TripleSource;
+    use std::collections::HashMap;
+    use std::io;
+
+    #[derive(Default)]
+    struct InMemory {
+        docs: HashMap<String, String>,
+        log: Vec<String>,
+    }
+
+    impl InMemory {
+        fn add(&mut self, path: &str, body: &str) {
+            self.docs
+                .insert(format!("http://ex.org/{}", path), body.to_string());
+        }
+    }
+
+    impl Fetcher for InMemory {
+        type Error = io::Error;
+        fn fetch(&mut self, iri: &str) -> Result<Document, io::Error> {
+            self.log.push(iri.to_string());
+            let body = self.docs.get(iri).ok_or(io::ErrorKind::NotFound)?;
+            let prefixed = format!("@prefix : <http://ex.org/>. {}", body);
+            let triples = crate::parser::turtle::parse_str(&prefixed)
+                .collect_triples()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok(Document {
+                iri: iri.to_string(),
+                triples,
+            })
+        }
+    }
+
+    /// a -> b -> c -> a, a -> d (missing), b is paginated (b, b2, b3)
+    fn make_fetcher() -> InMemory {
+        let mut f = InMemory::default();
+        f.add("a", ":a rdfs:seeAlso <http://ex.org/b#it>, :d; :label 'a'.");
+        f.add(
+            "b",
+            ":b rdfs:seeAlso :c; <http://www.w3.org/ns/hydra/core#next> :b2.",
+        );
+        f.add(
+            "b2",
+            ":b rdfs:member 2; <http://www.w3.org/ns/hydra/core#next> :b3.",
+        );
+        f.add("b3", ":b rdfs:member 3.");
+        f.add("c", ":c rdfs:seeAlso :a.");
+        for body in f.docs.values_mut() {
+            body.insert_str(0, "@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>. ");
+        }
+        f
+    }
+
+    fn crawled(crawler: &mut Crawler<InMemory>) -> Vec<String> {
+        let mut graphs = vec![];
+        for res in crawler.crawl(&["http://ex.org/a"]) {
+            if let Ok((_, Some(g))) = res {
+                let g = g.value().to_string();
+                if !graphs.contains(&g) {
+                    graphs.push(g);
+                }
+            }
+        }
+        graphs
+    }
+
+    #[test]
+    fn depth() {
+        let mut crawler = Crawler::new(make_fetcher());
+        crawler.add_link_predicate(&rdfs::seeAlso);
+        assert_eq!(crawled(&mut crawler), vec!["http://ex.org/a"]);
+
+        crawler.fetcher.log.clear();
+        crawler.set_max_depth(1);
+        // pages are fetched before the other links of the same depth
+        assert_eq!(
+            crawled(&mut crawler),
+            vec![
+                "http://ex.org/a",
+                "http://ex.org/b",
+                "http://ex.org/b2",
+                "http://ex.org/b3",
+            ]
+        );
+        assert_eq!(
+            crawler.fetcher.log,
+            vec![
+                "http://ex.org/a",
+                "http://ex.org/b",
+                "http://ex.org/b2",
+                "http://ex.org/b3",
+                "http://ex.org/d",
+            ]
+        );
+
+        crawler.fetcher.log.clear();
+        crawler.set_max_depth(10);
+        assert_eq!(crawled(&mut crawler).len(), 5);
+        // a is not fetched again, although c links to it
+        assert_eq!(crawler.fetcher.log.len(), 6);
+    }
+
+    #[test]
+    fn budget_and_pagination() {
+        let mut crawler = Crawler::new(make_fetcher());
+        crawler
+            .add_link_predicate(&rdfs::seeAlso)
+            .set_max_depth(10)
+            .set_max_documents(2);
+        assert_eq!(
+            crawled(&mut crawler),
+            vec!["http://ex.org/a", "http://ex.org/b"]
+        );
+
+        crawler.set_max_documents(usize::MAX).set_pagination(false);
+        assert_eq!(
+            crawled(&mut crawler),
+            vec!["http://ex.org/a", "http://ex.org/b", "http://ex.org/c"]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut crawler = Crawler::new(make_fetcher());
+        let mut crawl = crawler.crawl(&["http://ex.org/d#it", "http://ex.org/c"]);
+        let err = crawl.next().unwrap().unwrap_err();
+        assert_eq!(err.iri, "http://ex.org/d");
+        // the crawl can be resumed
+        let quads: Vec<_> = crawl.collect::<Result<_, _>>().unwrap();
+        assert_eq!(quads.len(), 1);
+        assert_eq!(&quads[0].1.as_ref().unwrap().value()[..], "http://ex.org/c");
+    }
+}
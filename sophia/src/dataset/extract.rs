@@ -0,0 +1,161 @@
+//! Extract the neighbourhood of some resources from a dataset.
+//!
+//! This is similar to a SPARQL `CONSTRUCT` query describing a few resources,
+//! and is useful to build test fixtures from a larger dataset,
+//! or to ship only the relevant slice of a dataset to a client.
+
+use std::collections::{HashSet, VecDeque};
+
+use sophia_api::dataset::{Dataset, MutableDataset};
+use sophia_api::quad::Quad;
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_term::BoxTerm;
+
+/// Extract from `dataset` the neighbourhood of the given `seeds`, in all graphs.
+///
+/// The neighbourhood of a seed is made of all the quads having this seed as their subject,
+/// and, recursively, the neighbourhood of their objects,
+/// up to `depth` hops away from the seed
+/// (so a `depth` of 0 only extracts the quads about the seeds themselves).
+/// Blank nodes are always followed, regardless of `depth`,
+/// so that the extracted descriptions are self-contained
+/// (as in a [Concise Bounded Description]).
+///
+/// Only quads whose predicate matches `predicate_filter` are extracted (and followed);
+/// use [`ANY`] to extract them all.
+///
+/// # Example
+/// ```
+/// # use sophia::dataset::extract::extract;
+/// # use sophia::dataset::inmem::FastDataset;
+/// # use sophia::ns::{rdf, rdfs};
+/// # use sophia::term::{matcher::ANY, BoxTerm};
+/// # fn f(d: &FastDataset) -> Result<(), Box<dyn std::error::Error>> {
+/// let alice = BoxTerm::new_iri("http://example.org/alice")?;
+/// // the description of alice and of the resources she is linked to
+/// let slice: FastDataset = extract(d, &[alice.clone()], 1, &ANY)?;
+/// // the class hierarchy above alice
+/// let classes: FastDataset = extract(d, &[alice], usize::MAX, &[&rdf::type_, &rdfs::subClassOf])?;
+/// # Ok(()) }
+/// ```
+///
+/// [Concise Bounded Description]: https://www.w3.org/Submission/CBD/
+/// [`ANY`]: ../../term/matcher/constant.ANY.html
+pub fn extract<D, O, T, M>(
+    dataset: &D,
+    seeds: &[T],
+    depth: usize,
+    predicate_filter: &M,
+) -> StreamResult<O, D::Error, O::MutationError>
+where
+    D: Dataset,
+    O: MutableDataset + Default,
+    T: TTerm,
+    M: TermMatcher + ?Sized,
+{
+    let mut output = O::default();
+    let mut visited: HashSet<BoxTerm> = HashSet::new();
+    // nodes to visit, with their distance to the closest seed;
+    // blank nodes are pushed at the front, so that nodes are visited by increasing distance
+    let mut todo: VecDeque<(BoxTerm, usize)> =
+        seeds.iter().map(|t| (BoxTerm::copy(t), 0)).collect();
+    while let Some((node, distance)) = todo.pop_front() {
+        if visited.contains(&node) {
+            continue;
+        }
+        for q in dataset.quads_with_s(&node) {
+            let q = q.map_err(SourceError)?;
+            if !predicate_filter.matches(q.p()) {
+                continue;
+            }
+            output
+                .insert(q.s(), q.p(), q.o(), q.g())
+                .map_err(SinkError)?;
+            let o = q.o();
+            match o.kind() {
+                TermKind::BlankNode => todo.push_front((BoxTerm::copy(o), distance)),
+                TermKind::Literal => {}
+                _ if distance < depth => todo.push_back((BoxTerm::copy(o), distance + 1)),
+                _ => {}
+            }
+        }
+        visited.insert(node);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::ns::{rdf, rdfs, Namespace};
+    use sophia_api::term::matcher::ANY;
+
+    /// a -> b -> c -> d, where b is also described by a blank node
+    fn make_dataset() -> FastDataset {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let a = ex.get("a").unwrap();
+        let b = ex.get("b").unwrap();
+        let c = ex.get("c").unwrap();
+        let d = ex.get("d").unwrap();
+        let g1 = ex.get("g1").unwrap();
+        let bn = BoxTerm::new_bnode("x").unwrap();
+        let lit = BoxTerm::new_literal_lang_unchecked("label", "en");
+        let mut ds = FastDataset::new();
+        ds.insert(&a, &rdf::value, &b, None as Option<&BoxTerm>)
+            .unwrap();
+        ds.insert(&a, &rdfs::label, &lit, None as Option<&BoxTerm>)
+            .unwrap();
+        ds.insert(&b, &rdf::value, &c, Some(&g1)).unwrap();
+        ds.insert(&b, &rdfs::seeAlso, &bn, Some(&g1)).unwrap();
+        ds.insert(&bn, &rdf::value, &d, Some(&g1)).unwrap();
+        ds.insert(&c, &rdf::value, &d, None as Option<&BoxTerm>)
+            .unwrap();
+        ds.insert(&d, &rdf::value, &a, None as Option<&BoxTerm>)
+            .unwrap();
+        ds
+    }
+
+    #[test]
+    fn depth() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let ds = make_dataset();
+        let e0: FastDataset = extract(&ds, &[ex.get("a")?], 0, &ANY)?;
+        assert_eq!(e0.quads().count(), 2);
+        let e1: FastDataset = extract(&ds, &[ex.get("a")?], 1, &ANY)?;
+        // b's description includes the blank node's
+        assert_eq!(e1.quads().count(), 5);
+        assert!(e1.quads_with_s(&ex.get("c")?).next().is_none());
+        let e3: FastDataset = extract(&ds, &[ex.get("a")?], 3, &ANY)?;
+        assert_eq!(e3.quads().count(), 7);
+        // cycles are not a problem
+        let e9: FastDataset = extract(&ds, &[ex.get("a")?], 9, &ANY)?;
+        assert_eq!(e9.quads().count(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn several_seeds() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let ds = make_dataset();
+        let e: FastDataset = extract(&ds, &[ex.get("c")?, ex.get("d")?], 0, &ANY)?;
+        assert_eq!(e.quads().count(), 2);
+        let e: FastDataset = extract(&ds, &[ex.get("unknown")?], 3, &ANY)?;
+        assert_eq!(e.quads().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn predicate_filter() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let ds = make_dataset();
+        let e: FastDataset = extract(&ds, &[ex.get("a")?], 1, &rdf::value)?;
+        assert_eq!(e.quads().count(), 2);
+        assert!(e.quads_with_p(&rdfs::label).next().is_none());
+        let e: FastDataset = extract(&ds, &[ex.get("b")?], 0, &[&rdf::value, &rdfs::seeAlso])?;
+        assert_eq!(e.quads().count(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+//! A dataset wrapper injecting errors, for robustness testing.
+//!
+//! A [`FlakyDataset`] behaves like the dataset it wraps,
+//! except that some of the quads it yields are replaced by errors,
+//! and some of its mutations fail (without changing the wrapped dataset),
+//! according to the [`Faults`] it is configured with.
+//! It can be used to check that code consuming datasets
+//! correctly propagates errors (e.g. through [`StreamError`]).
+//!
+//! Faults are injected deterministically:
+//! the same sequence of operations on the same dataset always fails at the same points.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{Dataset, CollectibleDataset, inmem::FastDataset};
+//! # use sophia::dataset::flaky::{Faults, FlakyDataset};
+//! # use sophia::quad::stream::StreamError;
+//! # fn test(d: FastDataset) {
+//! let flaky = FlakyDataset::new(d, Faults::At(vec![2]), Faults::Never);
+//! match FastDataset::from_quad_source(flaky.quads()) {
+//!     Err(StreamError::SourceError(_)) => (),
+//!     _ => panic!("the injected error should be propagated"),
+//! }
+//! # }
+//! ```
+//!
+//! [`FlakyDataset`]: struct.FlakyDataset.html
+//! [`Faults`]: enum.Faults.html
+//! [`StreamError`]: ../../triple/stream/enum.StreamError.html
+
+use std::error::Error;
+use std::sync::Mutex;
+
+use resiter::map::*;
+use sophia_api::dataset::{DQuadSource, Dataset, MdResult, MutableDataset, SetDataset};
+use sophia_api::term::TTerm;
+use thiserror::Error;
+
+use crate::generator::SplitMix64;
+
+/// Where a [`FlakyDataset`](struct.FlakyDataset.html) injects faults.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Faults {
+    /// No fault is injected.
+    #[default]
+    Never,
+    /// Faults are injected at the given positions
+    /// (the first operation being at position 0).
+    At(Vec<usize>),
+    /// Each operation fails with probability `rate` (between 0 and 1);
+    /// `seed` determines which operations fail.
+    Random {
+        /// The probability of failure of each operation.
+        rate: f64,
+        /// The seed of the pseudo-random number generator.
+        seed: u64,
+    },
+}
+
+/// Decides which operations fail.
+#[derive(Debug)]
+struct Injector {
+    faults: Faults,
+    count: usize,
+    rng: SplitMix64,
+}
+
+impl Injector {
+    fn new(faults: Faults) -> Self {
+        let seed = match faults {
+            Faults::Random { seed, .. } => seed,
+            _ => 0,
+        };
+        Injector {
+            faults,
+            count: 0,
+            rng: SplitMix64(seed),
+        }
+    }
+
+    /// Whether the next operation must fail.
+    fn next(&mut self) -> bool {
+        let position = self.count;
+        self.count += 1;
+        match &self.faults {
+            Faults::Never => false,
+            Faults::At(positions) => positions.contains(&position),
+            Faults::Random { rate, .. } => self.rng.next_f64() < *rate,
+        }
+    }
+}
+
+/// A [`Dataset`](../trait.Dataset.html) wrapper injecting errors.
+///
+/// *Reads* are the quads yielded by the dataset, through any of its methods;
+/// when a read fails, the quad is replaced by an error, and the iteration goes on.
+/// *Writes* are the calls to [`insert`] and [`remove`];
+/// when a write fails, the wrapped dataset is not modified.
+///
+/// See [module documentation](index.html).
+///
+/// [`insert`]: ../trait.MutableDataset.html#tymethod.insert
+/// [`remove`]: ../trait.MutableDataset.html#tymethod.remove
+#[derive(Debug)]
+pub struct FlakyDataset<D> {
+    inner: D,
+    reads: Mutex<Injector>,
+    writes: Injector,
+}
+
+impl<D> FlakyDataset<D> {
+    /// Wrap `inner`, injecting faults in reads and writes
+    /// according to `read_faults` and `write_faults`, respectively.
+    pub fn new(inner: D, read_faults: Faults, write_faults: Faults) -> Self {
+        FlakyDataset {
+            inner,
+            reads: Mutex::new(Injector::new(read_faults)),
+            writes: Injector::new(write_faults),
+        }
+    }
+
+    /// The wrapped dataset.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwrap this dataset.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// The number of reads so far (including failed ones).
+    pub fn reads(&self) -> usize {
+        self.reads.lock().unwrap().count
+    }
+
+    /// The number of writes so far (including failed ones).
+    pub fn writes(&self) -> usize {
+        self.writes.count
+    }
+}
+
+impl<D: Dataset> FlakyDataset<D> {
+    fn wrap<'s>(&'s self, quads: DQuadSource<'s, D>) -> DQuadSource<'s, Self> {
+        Box::new(quads.map_err(FlakyError::Inner).map(move |q| match q {
+            Ok(_) if self.reads.lock().unwrap().next() => Err(FlakyError::Injected),
+            q => q,
+        }))
+    }
+}
+
+impl<D: Dataset> Dataset for FlakyDataset<D> {
+    type Quad = D::Quad;
+    type Error = FlakyError<D::Error>;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.wrap(self.inner.quads())
+    }
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_s(s))
+    }
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_p(p))
+    }
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_o(o))
+    }
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_g(g))
+    }
+    fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_sp(s, p))
+    }
+    fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_so(s, o))
+    }
+    fn quads_with_sg<'s, TS, TG>(&'s self, s: &'s TS, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_sg(s, g))
+    }
+    fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_po(p, o))
+    }
+    fn quads_with_pg<'s, TP, TG>(&'s self, p: &'s TP, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_pg(p, g))
+    }
+    fn quads_with_og<'s, TO, TG>(&'s self, o: &'s TO, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_og(o, g))
+    }
+    fn quads_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_spo(s, p, o))
+    }
+    fn quads_with_spg<'s, TS, TP, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_spg(s, p, g))
+    }
+    fn quads_with_sog<'s, TS, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_sog(s, o, g))
+    }
+    fn quads_with_pog<'s, TP, TO, TG>(
+        &'s self,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_pog(p, o, g))
+    }
+    fn quads_with_spog<'s, TS, TP, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.wrap(self.inner.quads_with_spog(s, p, o, g))
+    }
+}
+
+impl<D: MutableDataset> MutableDataset for FlakyDataset<D> {
+    type MutationError = FlakyError<D::MutationError>;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        if self.writes.next() {
+            return Err(FlakyError::Injected);
+        }
+        self.inner.insert(s, p, o, g).map_err(FlakyError::Inner)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        if self.writes.next() {
+            return Err(FlakyError::Injected);
+        }
+        self.inner.remove(s, p, o, g).map_err(FlakyError::Inner)
+    }
+}
+
+impl<D: SetDataset> SetDataset for FlakyDataset<D> {}
+
+/// The error raised by [`FlakyDataset`](struct.FlakyDataset.html).
+#[derive(Debug, Error)]
+pub enum FlakyError<E: 'static + Error> {
+    /// An injected fault.
+    #[error("Injected fault")]
+    Injected,
+    /// An error raised by the wrapped dataset.
+    #[error("{0}")]
+    Inner(#[source] E),
+}
+
+impl<E: 'static + Error> FlakyError<E> {
+    /// Whether this error is an injected fault.
+    pub fn is_injected(&self) -> bool {
+        matches!(self, FlakyError::Injected)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::generator::Generator;
+    use sophia_api::dataset::CollectibleDataset;
+    use sophia_api::quad::stream::{QuadSource, StreamError};
+    use sophia_term::BoxTerm;
+
+    fn dataset() -> FastDataset {
+        Generator {
+            quads: 20,
+            ..Generator::default()
+        }
+        .quads()
+        .collect_quads()
+        .unwrap()
+    }
+
+    #[test]
+    fn never() -> Result<(), Box<dyn std::error::Error>> {
+        let d = dataset();
+        let n = d.quads().count();
+        let flaky = FlakyDataset::new(d, Faults::Never, Faults::default());
+        let copy = FastDataset::from_quad_source(flaky.quads())?;
+        assert_eq!(copy.quads().count(), n);
+        assert_eq!(flaky.reads(), n);
+        Ok(())
+    }
+
+    #[test]
+    fn injected_reads() {
+        let d = dataset();
+        let n = d.quads().count();
+        let flaky = FlakyDataset::new(d, Faults::At(vec![1, 3]), Faults::Never);
+        let results: Vec<_> = flaky.quads().collect();
+        assert_eq!(results.len(), n);
+        let failed: Vec<usize> = (0..n).filter(|i| results[*i].is_err()).collect();
+        assert_eq!(failed, vec![1, 3]);
+        assert!(results[1].as_ref().err().unwrap().is_injected());
+        // positions are counted across all reads
+        assert!(flaky.quads().all(|q| q.is_ok()));
+        assert_eq!(flaky.reads(), 2 * n);
+
+        // errors propagate through the provided methods of Dataset...
+        let flaky = FlakyDataset::new(dataset(), Faults::At(vec![0]), Faults::Never);
+        assert!(flaky.subjects().unwrap_err().is_injected());
+        // ... and through streams
+        let flaky = FlakyDataset::new(dataset(), Faults::At(vec![5]), Faults::Never);
+        match FastDataset::from_quad_source(flaky.quads()) {
+            Err(StreamError::SourceError(err)) => assert!(err.is_injected()),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn injected_writes() {
+        let source = dataset();
+        let mut flaky = FlakyDataset::new(FastDataset::new(), Faults::Never, Faults::At(vec![3]));
+        match flaky.insert_all(source.quads()) {
+            Err(StreamError::SinkError(err)) => assert!(err.is_injected()),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        assert_eq!(flaky.writes(), 4);
+        assert_eq!(flaky.inner().quads().count(), 3);
+
+        let x = BoxTerm::new_iri("http://example.org/x").unwrap();
+        assert!(flaky.insert(&x, &x, &x, None as Option<&BoxTerm>).is_ok());
+        assert_eq!(flaky.into_inner().quads().count(), 4);
+    }
+
+    #[test]
+    fn random() {
+        let faults = Faults::Random {
+            rate: 0.25,
+            seed: 42,
+        };
+        let run = || -> Vec<bool> {
+            let flaky = FlakyDataset::new(dataset(), faults.clone(), Faults::Never);
+            let mut results = vec![];
+            for _ in 0..10 {
+                results.extend(flaky.quads().map(|q| q.is_err()));
+            }
+            results
+        };
+        let results = run();
+        assert_eq!(results, run());
+        let failures = results.iter().filter(|f| **f).count();
+        assert!(
+            results.len() / 8 < failures && failures < results.len() * 3 / 8,
+            "{} / {}",
+            failures,
+            results.len()
+        );
+    }
+}
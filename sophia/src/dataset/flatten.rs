@@ -0,0 +1,163 @@
+//! Materialize all the quads of a dataset into a single graph.
+//!
+//! Naively taking the union of all graphs of a dataset
+//! (as done by [`Dataset::union_graph`])
+//! may accidentally merge blank nodes that happen to have the same label in different graphs,
+//! while they are, semantically, distinct nodes.
+//! The [`Flattener`] defined here avoids this by renaming blank nodes
+//! according to the graph they come from.
+//!
+//! [`Dataset::union_graph`]: ../trait.Dataset.html#method.union_graph
+//! [`Flattener`]: struct.Flattener.html
+
+use std::collections::HashMap;
+
+use sophia_api::dataset::Dataset;
+use sophia_api::graph::MutableGraph;
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_term::BoxTerm;
+
+/// How blank nodes are handled by a [`Flattener`](struct.Flattener.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BnodeRenaming {
+    /// Blank nodes are kept as is,
+    /// so that blank nodes with the same label in different graphs are merged.
+    Keep,
+    /// Blank nodes are renamed with a prefix specific to their graph
+    /// (`g0_` for the default graph, `g1_`, `g2_`... for named graphs,
+    /// in the order in which they are encountered),
+    /// so that blank nodes from different graphs are never merged.
+    #[default]
+    PerGraph,
+}
+
+/// Flattens a dataset into a graph, by inserting the triple part of each quad.
+///
+/// # Example
+/// ```
+/// # use sophia::dataset::flatten::{BnodeRenaming, Flattener};
+/// # use sophia::dataset::inmem::FastDataset;
+/// # use sophia::graph::inmem::FastGraph;
+/// # fn f(d: &FastDataset) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut g = FastGraph::new();
+/// Flattener { bnodes: BnodeRenaming::PerGraph }.flatten_into(d, &mut g)?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Flattener {
+    /// How blank nodes are handled.
+    pub bnodes: BnodeRenaming,
+}
+
+impl Flattener {
+    /// Insert all the quads of `dataset` as triples into `graph`,
+    /// and return the number of triples actually inserted.
+    pub fn flatten_into<D, G>(
+        &self,
+        dataset: &D,
+        graph: &mut G,
+    ) -> StreamResult<usize, D::Error, G::MutationError>
+    where
+        D: Dataset,
+        G: MutableGraph,
+    {
+        let mut graph_ids: HashMap<BoxTerm, usize> = HashMap::new();
+        let mut count = 0;
+        for q in dataset.quads() {
+            let q = q.map_err(SourceError)?;
+            let inserted = if self.bnodes == BnodeRenaming::Keep {
+                graph.insert(q.s(), q.p(), q.o())
+            } else {
+                let gid = match q.g() {
+                    None => 0,
+                    Some(g) => {
+                        let next = graph_ids.len() + 1;
+                        *graph_ids.entry(BoxTerm::copy(g)).or_insert(next)
+                    }
+                };
+                let s = rename(q.s(), gid);
+                let o = rename(q.o(), gid);
+                graph.insert(
+                    s.as_ref()
+                        .map(|t| t.as_dyn())
+                        .unwrap_or_else(|| q.s().as_dyn()),
+                    q.p(),
+                    o.as_ref()
+                        .map(|t| t.as_dyn())
+                        .unwrap_or_else(|| q.o().as_dyn()),
+                )
+            }
+            .map_err(SinkError)?;
+            if inserted {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Return a renamed copy of `t` if it is a blank node, `None` otherwise.
+///
+/// NB: only subjects and objects are renamed,
+/// as predicates can not be blank nodes in standard RDF.
+fn rename<T>(t: &T, gid: usize) -> Option<BoxTerm>
+where
+    T: TTerm + ?Sized,
+{
+    if t.kind() == TermKind::BlankNode {
+        Some(BoxTerm::new_bnode_unchecked(format!(
+            "g{}_{}",
+            gid,
+            t.value()
+        )))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::ns::rdf;
+    use sophia_api::dataset::MutableDataset;
+    use sophia_api::graph::Graph;
+
+    fn make_dataset() -> FastDataset {
+        let b1 = BoxTerm::new_bnode("b1").unwrap();
+        let g1 = BoxTerm::new_iri("http://example.org/g1").unwrap();
+        let mut d = FastDataset::new();
+        d.insert(&b1, &rdf::type_, &rdf::Property, None as Option<&BoxTerm>)
+            .unwrap();
+        d.insert(&b1, &rdf::type_, &rdf::Property, Some(&g1))
+            .unwrap();
+        d.insert(&b1, &rdf::value, &b1, Some(&g1)).unwrap();
+        d
+    }
+
+    #[test]
+    fn keep_bnodes() -> Result<(), Box<dyn std::error::Error>> {
+        let d = make_dataset();
+        let mut g = FastGraph::new();
+        let flattener = Flattener {
+            bnodes: BnodeRenaming::Keep,
+        };
+        assert_eq!(flattener.flatten_into(&d, &mut g)?, 2);
+        assert_eq!(g.subjects()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn rename_bnodes_per_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let d = make_dataset();
+        let mut g = FastGraph::new();
+        assert_eq!(Flattener::default().flatten_into(&d, &mut g)?, 3);
+        assert_eq!(g.subjects()?.len(), 2);
+        let b = BoxTerm::new_bnode_unchecked("g1_b1");
+        assert!(g.contains(&b, &rdf::value, &b)?);
+        Ok(())
+    }
+}
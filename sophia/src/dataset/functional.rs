@@ -0,0 +1,556 @@
+//! A dataset wrapper enforcing functional properties.
+//!
+//! A property is *functional* if each subject has at most one value for it
+//! (as declared by `owl:FunctionalProperty`).
+//! A [`FunctionalDataset`] checks every inserted quad whose predicate is a functional property:
+//! if its subject already has another value for that property in the same graph,
+//! the insertion is handled according to a [`FunctionalPolicy`]:
+//! the old value is replaced, the insertion is rejected with an error,
+//! or it is ignored and the [`Conflict`] is recorded for later review.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{Dataset, MutableDataset, inmem::FastDataset};
+//! # use sophia::dataset::functional::{FunctionalDataset, FunctionalPolicy};
+//! # use sophia::term::BoxTerm;
+//! let alice = BoxTerm::new_iri("http://example.org/alice")?;
+//! let age = BoxTerm::new_iri("http://example.org/age")?;
+//! let mut d = FunctionalDataset::new(FastDataset::new(), FunctionalPolicy::Replace);
+//! d.add_property(&age);
+//! d.insert(&alice, &age, &BoxTerm::from("41".to_string()), None as Option<&BoxTerm>)?;
+//! d.insert(&alice, &age, &BoxTerm::from("42".to_string()), None as Option<&BoxTerm>)?;
+//! assert_eq!(d.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`FunctionalDataset`]: struct.FunctionalDataset.html
+//! [`FunctionalPolicy`]: enum.FunctionalPolicy.html
+//! [`Conflict`]: struct.Conflict.html
+
+use std::error::Error;
+use std::fmt;
+
+use sophia_api::dataset::{DQuadSource, Dataset, MdResult, MutableDataset, SetDataset};
+use sophia_api::graph::Graph;
+use sophia_api::ns::{owl, rdf};
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_eq, CopyTerm, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// How a [`FunctionalDataset`](struct.FunctionalDataset.html) handles
+/// the insertion of a second value for a functional property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FunctionalPolicy {
+    /// The previous value(s) are removed, and the new value is inserted.
+    Replace,
+    /// The insertion fails with [`FunctionalError::Conflict`](enum.FunctionalError.html),
+    /// and the dataset is not modified.
+    #[default]
+    Reject,
+    /// The new value is not inserted, and the conflict is recorded
+    /// (see [`conflicts`](struct.FunctionalDataset.html#method.conflicts)).
+    Record,
+}
+
+/// An attempt to give a second value to a functional property.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// The graph in which the value was inserted (`None` for the default graph).
+    pub graph: Option<BoxTerm>,
+    /// The subject.
+    pub subject: BoxTerm,
+    /// The functional property.
+    pub property: BoxTerm,
+    /// The value(s) that the subject already had.
+    pub existing: Vec<BoxTerm>,
+    /// The value that could not be inserted.
+    pub value: BoxTerm,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} already has a value for {}: ",
+            self.subject, self.property
+        )?;
+        for (i, o) in self.existing.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", o)?;
+        }
+        write!(f, " (can not insert {}", self.value)?;
+        if let Some(g) = &self.graph {
+            write!(f, " in graph {}", g)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// A [`MutableDataset`](../trait.MutableDataset.html) wrapper enforcing functional properties.
+///
+/// Reading this dataset is the same as reading the wrapped dataset;
+/// only [`insert`] checks the functional properties.
+/// Values already present in the wrapped dataset are not checked.
+///
+/// See [module documentation](index.html).
+///
+/// [`insert`]: ../trait.MutableDataset.html#tymethod.insert
+#[derive(Clone, Debug)]
+pub struct FunctionalDataset<D> {
+    inner: D,
+    properties: Vec<BoxTerm>,
+    policy: FunctionalPolicy,
+    conflicts: Vec<Conflict>,
+}
+
+impl<D> FunctionalDataset<D> {
+    /// Wrap `inner`, with no functional property yet,
+    /// handling conflicts according to `policy`.
+    pub fn new(inner: D, policy: FunctionalPolicy) -> Self {
+        FunctionalDataset {
+            inner,
+            properties: vec![],
+            policy,
+            conflicts: vec![],
+        }
+    }
+
+    /// Declare `property` as functional.
+    pub fn add_property<T: TTerm + ?Sized>(&mut self, property: &T) -> &mut Self {
+        if !self.is_functional(property) {
+            self.properties.push(BoxTerm::copy(property));
+        }
+        self
+    }
+
+    /// Declare as functional all the properties declared as `owl:FunctionalProperty` in `ontology`.
+    pub fn add_declared_properties<G: Graph>(
+        &mut self,
+        ontology: &G,
+    ) -> Result<&mut Self, G::Error> {
+        for t in ontology.triples_with_po(&rdf::type_, &owl::FunctionalProperty) {
+            self.add_property(t?.s());
+        }
+        Ok(self)
+    }
+
+    /// The functional properties.
+    pub fn properties(&self) -> &[BoxTerm] {
+        &self.properties
+    }
+
+    /// Whether `property` is functional.
+    pub fn is_functional<T: TTerm + ?Sized>(&self, property: &T) -> bool {
+        self.properties.iter().any(|p| term_eq(p, property))
+    }
+
+    /// How conflicts are handled.
+    pub fn policy(&self) -> FunctionalPolicy {
+        self.policy
+    }
+
+    /// Set how conflicts are handled.
+    pub fn set_policy(&mut self, policy: FunctionalPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The conflicts recorded so far, with [`FunctionalPolicy::Record`](enum.FunctionalPolicy.html#variant.Record).
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// Remove and return the conflicts recorded so far.
+    pub fn take_conflicts(&mut self) -> Vec<Conflict> {
+        std::mem::take(&mut self.conflicts)
+    }
+
+    /// The wrapped dataset.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwrap this dataset.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Dataset> Dataset for FunctionalDataset<D> {
+    type Quad = D::Quad;
+    type Error = D::Error;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.inner.quads()
+    }
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.inner.quads_with_s(s)
+    }
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.inner.quads_with_p(p)
+    }
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_o(o)
+    }
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_g(g)
+    }
+    fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.inner.quads_with_sp(s, p)
+    }
+    fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_so(s, o)
+    }
+    fn quads_with_sg<'s, TS, TG>(&'s self, s: &'s TS, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_sg(s, g)
+    }
+    fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_po(p, o)
+    }
+    fn quads_with_pg<'s, TP, TG>(&'s self, p: &'s TP, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_pg(p, g)
+    }
+    fn quads_with_og<'s, TO, TG>(&'s self, o: &'s TO, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_og(o, g)
+    }
+    fn quads_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_spo(s, p, o)
+    }
+    fn quads_with_spg<'s, TS, TP, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_spg(s, p, g)
+    }
+    fn quads_with_sog<'s, TS, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_sog(s, o, g)
+    }
+    fn quads_with_pog<'s, TP, TO, TG>(
+        &'s self,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_pog(p, o, g)
+    }
+    fn quads_with_spog<'s, TS, TP, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_spog(s, p, o, g)
+    }
+}
+
+impl<D: MutableDataset> MutableDataset for FunctionalDataset<D> {
+    type MutationError = FunctionalError<D::Error, D::MutationError>;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        if self.is_functional(p) {
+            let mut existing = vec![];
+            for q in self.inner.quads_with_spg(s, p, g) {
+                let q = q.map_err(FunctionalError::Dataset)?;
+                if term_eq(q.o(), o) {
+                    return Ok(false);
+                }
+                existing.push(BoxTerm::copy(q.o()));
+            }
+            if !existing.is_empty() {
+                match self.policy {
+                    FunctionalPolicy::Replace => {
+                        for old in &existing {
+                            self.inner
+                                .remove(s, p, old, g)
+                                .map_err(FunctionalError::Mutation)?;
+                        }
+                    }
+                    policy => {
+                        let conflict = Conflict {
+                            graph: g.map(BoxTerm::copy),
+                            subject: BoxTerm::copy(s),
+                            property: BoxTerm::copy(p),
+                            existing,
+                            value: BoxTerm::copy(o),
+                        };
+                        if policy == FunctionalPolicy::Reject {
+                            return Err(FunctionalError::Conflict(Box::new(conflict)));
+                        }
+                        self.conflicts.push(conflict);
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        self.inner
+            .insert(s, p, o, g)
+            .map_err(FunctionalError::Mutation)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner
+            .remove(s, p, o, g)
+            .map_err(FunctionalError::Mutation)
+    }
+}
+
+impl<D: SetDataset> SetDataset for FunctionalDataset<D> {}
+
+/// The error raised by [`FunctionalDataset`](struct.FunctionalDataset.html) on insertion.
+#[derive(Debug, Error)]
+pub enum FunctionalError<E: 'static + Error, M: 'static + Error> {
+    /// The wrapped dataset could not be read.
+    #[error("Error in the dataset: {0}")]
+    Dataset(#[source] E),
+    /// The wrapped dataset could not be modified.
+    #[error("{0}")]
+    Mutation(#[source] M),
+    /// A second value was inserted for a functional property,
+    /// with [`FunctionalPolicy::Reject`](enum.FunctionalPolicy.html#variant.Reject).
+    #[error("Functional property violated: {0}")]
+    Conflict(Box<Conflict>),
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::graph::MutableGraph;
+    use sophia_api::ns::Namespace;
+    use sophia_api::term::CopiableTerm;
+
+    fn dataset(policy: FunctionalPolicy) -> FunctionalDataset<FastDataset> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let x_alice = ex.get("alice").unwrap();
+        let x_bob = ex.get("bob").unwrap();
+        let x_mother = ex.get("mother").unwrap();
+        let x_friend = ex.get("friend").unwrap();
+
+        let mut d = FunctionalDataset::new(FastDataset::new(), policy);
+        d.add_property(&x_mother).add_property(&x_mother);
+        assert_eq!(d.properties().len(), 1);
+        d.insert(&x_bob, &x_mother, &x_alice, None as Option<&BoxTerm>)
+            .unwrap();
+        d.insert(&x_bob, &x_friend, &x_alice, None as Option<&BoxTerm>)
+            .unwrap();
+        d
+    }
+
+    fn objects(d: &FunctionalDataset<FastDataset>, p: &str, g: Option<&BoxTerm>) -> Vec<BoxTerm> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        d.quads_with_spg(&ex.get("bob").unwrap(), &ex.get(p).unwrap(), g)
+            .map(|q| BoxTerm::copy(q.unwrap().o()))
+            .collect()
+    }
+
+    #[test]
+    fn replace() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let mut d = dataset(FunctionalPolicy::Replace);
+        assert!(d.insert(
+            &ex.get("bob")?,
+            &ex.get("mother")?,
+            &ex.get("carol")?,
+            None as Option<&BoxTerm>
+        )?);
+        assert_eq!(objects(&d, "mother", None), vec![ex.get("carol")?]);
+        // inserting the current value changes nothing
+        assert!(!d.insert(
+            &ex.get("bob")?,
+            &ex.get("mother")?,
+            &ex.get("carol")?,
+            None as Option<&BoxTerm>
+        )?);
+        // other properties are not functional
+        assert!(d.insert(
+            &ex.get("bob")?,
+            &ex.get("friend")?,
+            &ex.get("carol")?,
+            None as Option<&BoxTerm>
+        )?);
+        assert_eq!(objects(&d, "friend", None).len(), 2);
+        // other graphs are independent
+        let g: BoxTerm = ex.get("g")?.copied();
+        assert!(d.insert(
+            &ex.get("bob")?,
+            &ex.get("mother")?,
+            &ex.get("dave")?,
+            Some(&g)
+        )?);
+        assert_eq!(objects(&d, "mother", Some(&g)), vec![ex.get("dave")?]);
+        assert_eq!(objects(&d, "mother", None), vec![ex.get("carol")?]);
+        Ok(())
+    }
+
+    #[test]
+    fn reject() {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let mut d = dataset(FunctionalPolicy::Reject);
+        match d.insert(
+            &ex.get("bob").unwrap(),
+            &ex.get("mother").unwrap(),
+            &ex.get("carol").unwrap(),
+            None as Option<&BoxTerm>,
+        ) {
+            Err(FunctionalError::Conflict(c)) => {
+                assert_eq!(c.existing, vec![ex.get("alice").unwrap()]);
+                assert_eq!(c.value, ex.get("carol").unwrap());
+                assert_eq!(c.graph, None);
+                assert_eq!(
+                    c.to_string(),
+                    "<http://example.org/bob> already has a value for <http://example.org/mother>: \
+                     <http://example.org/alice> (can not insert <http://example.org/carol>)"
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(objects(&d, "mother", None), vec![ex.get("alice").unwrap()]);
+        assert!(d.conflicts().is_empty());
+    }
+
+    #[test]
+    fn record() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let mut d = dataset(FunctionalPolicy::Record);
+        assert!(!d.insert(
+            &ex.get("bob")?,
+            &ex.get("mother")?,
+            &ex.get("carol")?,
+            None as Option<&BoxTerm>
+        )?);
+        assert!(!d.insert(
+            &ex.get("bob")?,
+            &ex.get("mother")?,
+            &ex.get("dave")?,
+            None as Option<&BoxTerm>
+        )?);
+        assert_eq!(objects(&d, "mother", None), vec![ex.get("alice")?]);
+        let values: Vec<_> = d.conflicts().iter().map(|c| c.value.clone()).collect();
+        assert_eq!(values, vec![ex.get("carol")?, ex.get("dave")?]);
+        assert_eq!(d.take_conflicts().len(), 2);
+        assert!(d.conflicts().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn declared_properties() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let mut ontology = FastGraph::new();
+        ontology.insert(&ex.get("mother")?, &rdf::type_, &owl::FunctionalProperty)?;
+        ontology.insert(&ex.get("friend")?, &rdf::type_, &owl::ObjectProperty)?;
+        let mut d = FunctionalDataset::new(FastDataset::new(), FunctionalPolicy::default());
+        d.add_declared_properties(&ontology)?;
+        assert!(d.is_functional(&ex.get("mother")?));
+        assert!(!d.is_functional(&ex.get("friend")?));
+        assert_eq!(d.policy(), FunctionalPolicy::Reject);
+        Ok(())
+    }
+}
@@ -1,10 +1,21 @@
 //! A utility trait for building datasets using indexed terms.
 
+use std::collections::HashMap;
 use std::hash::Hash;
 
-use sophia_api::term::TTerm;
+use crate::graph::inmem::Unsigned;
+use sophia_api::dataset::Dataset;
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm};
 use sophia_term::*;
 
+/// A quad whose indices were changed by [`IndexedDataset::rewrite_terms`],
+/// as its old indices and its new ones
+/// (`None` if the new quad was already in the dataset, and the old one was simply removed).
+///
+/// [`IndexedDataset::rewrite_terms`]: trait.IndexedDataset.html#method.rewrite_terms
+pub type RewrittenQuad<I> = ([I; 4], Option<[I; 4]>);
+
 /// A utility trait for implementing [`Dataset`] and [`MutableDataset`]
 /// based on an internal [`TermIndexMap`] for efficient storage.
 ///
@@ -17,6 +28,17 @@ use sophia_term::*;
 /// [`TermIndexMap`]: ../../term/index_map/trait.TermIndexMap.html
 /// [`impl_mutable_dataset_for_indexed_dataset!`]: ../../macro.impl_mutable_dataset_for_indexed_dataset.html
 ///
+/// # Node identifiers
+///
+/// When indices are unsigned integers (as in all the datasets of [`inmem`]),
+/// [`node_id`], [`term_of`] and [`quad_ids`] expose them as `usize`,
+/// like the corresponding methods of [`IndexedGraph`].
+///
+/// [`inmem`]: ../inmem/index.html
+/// [`node_id`]: #method.node_id
+/// [`term_of`]: #method.term_of
+/// [`quad_ids`]: #method.quad_ids
+/// [`IndexedGraph`]: ../../graph/indexed/trait.IndexedGraph.html#node-identifiers
 pub trait IndexedDataset {
     /// The type used to represent terms internally.
     type Index: Copy + Eq + Hash;
@@ -30,6 +52,21 @@ pub trait IndexedDataset {
     /// Shrink the memory consumption of the dataset as much as possible.
     fn shrink_to_fit(&mut self);
 
+    /// Renumber the indices of the terms, so that they are contiguous,
+    /// then [shrink](#tymethod.shrink_to_fit) the dataset.
+    ///
+    /// The indices freed by removed quads are reused by later insertions,
+    /// but the memory they hold is never released;
+    /// after a heavy delete workload, this method reclaims it,
+    /// at the cost of rewriting every quad index.
+    ///
+    /// NB: the indices (and [node identifiers](#node-identifiers)) of terms may change.
+    ///
+    /// The default implementation only shrinks the dataset.
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+
     /// Return the index for the given term, if it exists.
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -53,6 +90,72 @@ pub trait IndexedDataset {
     #[allow(clippy::option_option)]
     fn get_graph_name(&self, i: Self::Index) -> Option<Option<&Term<Self::TermData>>>;
 
+    /// Iterate over the quads of this dataset, as tuples of indices.
+    ///
+    /// The last index is the graph name (see [`get_graph_name`](#tymethod.get_graph_name)).
+    ///
+    /// The default implementation looks up the indices of each quad of the dataset,
+    /// skipping the quads that can not be read.
+    fn indexed_quads(&self) -> Box<dyn Iterator<Item = [Self::Index; 4]> + '_>
+    where
+        Self: Dataset,
+    {
+        Box::new(self.quads().filter_map(move |q| {
+            let q = q.ok()?;
+            Some([
+                self.get_index(q.s())?,
+                self.get_index(q.p())?,
+                self.get_index(q.o())?,
+                self.get_index_for_graph_name(q.g())?,
+            ])
+        }))
+    }
+
+    /// Return the identifier of the given term, if it is used in this dataset.
+    ///
+    /// See [Node identifiers](#node-identifiers).
+    fn node_id<T>(&self, t: &T) -> Option<usize>
+    where
+        T: TTerm + ?Sized,
+        Self::Index: Unsigned,
+    {
+        self.get_index(t).map(|i| i.as_usize())
+    }
+
+    /// Return the term with the given identifier, if any.
+    ///
+    /// See [Node identifiers](#node-identifiers).
+    fn term_of(&self, id: usize) -> Option<&Term<Self::TermData>>
+    where
+        Self::Index: Unsigned,
+    {
+        let i = Self::Index::from_usize(id);
+        if i.as_usize() == id {
+            self.get_term(i)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the quads of this dataset,
+    /// as identifiers of their subject, predicate and object,
+    /// and of their graph name (`None` for the default graph).
+    ///
+    /// See [Node identifiers](#node-identifiers).
+    fn quad_ids(&self) -> Box<dyn Iterator<Item = ([usize; 3], Option<usize>)> + '_>
+    where
+        Self: Dataset,
+        Self::Index: Unsigned,
+    {
+        Box::new(self.indexed_quads().map(move |[s, p, o, g]| {
+            let g = match self.get_graph_name(g) {
+                Some(Some(_)) => Some(g.as_usize()),
+                _ => None,
+            };
+            ([s.as_usize(), p.as_usize(), o.as_usize()], g)
+        }))
+    }
+
     /// Insert a triple in this Dataset,
     /// and return the corresponding tuple of indices.
     fn insert_indexed<TS, TP, TO, TG>(
@@ -82,6 +185,81 @@ pub trait IndexedDataset {
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized,
         TG: TTerm + ?Sized;
+
+    /// Replace, in place, every term for which `rewrite` returns `Some(new_term)`.
+    ///
+    /// The term index is updated once per distinct term,
+    /// so this is much faster than removing and re-inserting every affected quad
+    /// (e.g. when migrating a vocabulary to a new namespace).
+    /// `rewrite` is always called with the original terms,
+    /// and it is the responsibility of the caller to produce terms
+    /// that are valid in the positions where the original terms are used.
+    ///
+    /// Quads only need to be touched when a term is rewritten into a term already in use;
+    /// those are returned as [`RewrittenQuad`]s.
+    ///
+    /// The default implementation removes and re-inserts every affected quad,
+    /// and returns all of them.
+    ///
+    /// [`RewrittenQuad`]: type.RewrittenQuad.html
+    fn rewrite_terms<U, R>(&mut self, mut rewrite: R) -> Vec<RewrittenQuad<Self::Index>>
+    where
+        Self: Dataset,
+        U: TTerm,
+        R: FnMut(&Term<Self::TermData>) -> Option<U>,
+    {
+        let mut cache: HashMap<Self::Index, Option<BoxTerm>> = HashMap::new();
+        let mut affected = Vec::new();
+        let indexed: Vec<_> = self.indexed_quads().collect();
+        for old in indexed {
+            let mut terms: [Option<BoxTerm>; 4] = Default::default();
+            let mut new_terms: [Option<BoxTerm>; 4] = Default::default();
+            let mut changed = false;
+            for (pos, i) in old.iter().enumerate() {
+                let term = if pos < 3 {
+                    self.get_term(*i)
+                } else {
+                    self.get_graph_name(*i).flatten()
+                };
+                // only the default graph has no term
+                let term = match term {
+                    Some(term) => term,
+                    None => continue,
+                };
+                let new = cache
+                    .entry(*i)
+                    .or_insert_with(|| rewrite(term).map(|u| BoxTerm::copy(&u)));
+                changed |= new.is_some();
+                terms[pos] = Some(BoxTerm::copy(term));
+                new_terms[pos] = new.clone().or_else(|| terms[pos].clone());
+            }
+            if changed {
+                affected.push((old, terms, new_terms));
+            }
+        }
+        // remove all the affected quads before inserting any,
+        // so that a rewritten quad is not removed as the original of another one
+        for (_, [s, p, o, g], _) in &affected {
+            self.remove_indexed(
+                s.as_ref().unwrap(),
+                p.as_ref().unwrap(),
+                o.as_ref().unwrap(),
+                g.as_ref(),
+            );
+        }
+        affected
+            .into_iter()
+            .map(|(old, _, [s, p, o, g])| {
+                let new = self.insert_indexed(
+                    s.as_ref().unwrap(),
+                    p.as_ref().unwrap(),
+                    o.as_ref().unwrap(),
+                    g.as_ref(),
+                );
+                (old, new)
+            })
+            .collect()
+    }
 }
 
 /// Defines the implementation of [`CollectibleDataset`] for [`IndexedDataset`].
@@ -162,5 +340,272 @@ macro_rules! impl_mutable_dataset_for_indexed_dataset {
 
 #[cfg(test)]
 mod test {
-    // Nothing really worth testing here
+    use super::*;
+    use crate::dataset::inmem::{FastDataset, LightDataset};
+    use sophia_api::dataset::{DQuadSource, Dataset, MutableDataset};
+    use sophia_api::term::TermKind;
+
+    /// A dataset relying on the default implementations of [`IndexedDataset`].
+    #[derive(Default)]
+    struct DefaultDataset(LightDataset);
+
+    impl IndexedDataset for DefaultDataset {
+        type Index = <LightDataset as IndexedDataset>::Index;
+        type TermData = <LightDataset as IndexedDataset>::TermData;
+
+        fn with_capacity(capacity: usize) -> Self {
+            DefaultDataset(LightDataset::with_capacity(capacity))
+        }
+        fn shrink_to_fit(&mut self) {
+            self.0.shrink_to_fit()
+        }
+        fn get_index<T: TTerm + ?Sized>(&self, t: &T) -> Option<Self::Index> {
+            self.0.get_index(t)
+        }
+        fn get_index_for_graph_name<T: TTerm + ?Sized>(
+            &self,
+            g: Option<&T>,
+        ) -> Option<Self::Index> {
+            self.0.get_index_for_graph_name(g)
+        }
+        fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>> {
+            self.0.get_term(i)
+        }
+        fn get_graph_name(&self, i: Self::Index) -> Option<Option<&Term<Self::TermData>>> {
+            self.0.get_graph_name(i)
+        }
+        fn insert_indexed<TS, TP, TO, TG>(
+            &mut self,
+            s: &TS,
+            p: &TP,
+            o: &TO,
+            g: Option<&TG>,
+        ) -> Option<[Self::Index; 4]>
+        where
+            TS: TTerm + ?Sized,
+            TP: TTerm + ?Sized,
+            TO: TTerm + ?Sized,
+            TG: TTerm + ?Sized,
+        {
+            self.0.insert_indexed(s, p, o, g)
+        }
+        fn remove_indexed<TS, TP, TO, TG>(
+            &mut self,
+            s: &TS,
+            p: &TP,
+            o: &TO,
+            g: Option<&TG>,
+        ) -> Option<[Self::Index; 4]>
+        where
+            TS: TTerm + ?Sized,
+            TP: TTerm + ?Sized,
+            TO: TTerm + ?Sized,
+            TG: TTerm + ?Sized,
+        {
+            self.0.remove_indexed(s, p, o, g)
+        }
+    }
+
+    impl Dataset for DefaultDataset {
+        type Quad = <LightDataset as Dataset>::Quad;
+        type Error = <LightDataset as Dataset>::Error;
+
+        fn quads(&self) -> DQuadSource<'_, Self> {
+            self.0.quads()
+        }
+    }
+
+    #[test]
+    fn node_ids() -> Result<(), Box<dyn std::error::Error>> {
+        let alice = BoxTerm::new_iri("http://example.org/alice")?;
+        let bob = BoxTerm::new_iri("http://example.org/bob")?;
+        let knows = BoxTerm::new_iri("http://example.org/knows")?;
+        let mut d = FastDataset::new();
+        d.insert(&alice, &knows, &bob, None as Option<&BoxTerm>)?;
+        d.insert(&bob, &knows, &alice, Some(&alice))?;
+
+        let a = d.node_id(&alice).unwrap();
+        let b = d.node_id(&bob).unwrap();
+        let k = d.node_id(&knows).unwrap();
+        assert!(d.term_of(k).unwrap() == &knows);
+        assert!(d.term_of(0).is_none());
+
+        let mut ids: Vec<_> = d.quad_ids().collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![([a, k, b], None), ([b, k, a], Some(a))]);
+        Ok(())
+    }
+
+    #[test]
+    fn compact() -> Result<(), Box<dyn std::error::Error>> {
+        let p = BoxTerm::new_iri("http://example.org/p")?;
+        let g = BoxTerm::new_iri("http://example.org/g")?;
+        let terms = (0..10)
+            .map(|i| BoxTerm::new_iri(format!("http://example.org/{}", i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut d = FastDataset::new();
+        for t in &terms {
+            d.insert(t, &p, t, Some(&g))?;
+        }
+        for t in &terms[..8] {
+            d.remove(t, &p, t, Some(&g))?;
+        }
+
+        d.compact();
+        let mut ids: Vec<_> = d
+            .quad_ids()
+            .flat_map(|([s, p, o], g)| vec![s, p, o, g.unwrap()])
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(d.quads_with_s(&terms[9]).count(), 1);
+        assert_eq!(d.quads_with_g(Some(&g)).count(), 2);
+        assert_eq!(d.quads_with_o(&terms[8]).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_terms() -> Result<(), Box<dyn std::error::Error>> {
+        let old = |s: &str| BoxTerm::new_iri(format!("http://old.example/{}", s)).unwrap();
+        let new = |s: &str| BoxTerm::new_iri(format!("http://example.org/{}", s)).unwrap();
+        let mut d = FastDataset::new();
+        d.insert(
+            &old("alice"),
+            &old("knows"),
+            &old("bob"),
+            None as Option<&BoxTerm>,
+        )?;
+        d.insert(&old("bob"), &old("knows"), &old("alice"), Some(&old("g")))?;
+        d.insert(
+            &old("alice"),
+            &new("knows"),
+            &new("bob"),
+            None as Option<&BoxTerm>,
+        )?;
+        let alice = d.node_id(&old("alice")).unwrap();
+        let knows = d.node_id(&new("knows")).unwrap();
+
+        let rewritten = d.rewrite_terms(|t| {
+            let value = t.value();
+            if t.kind() == TermKind::Iri && value.starts_with("http://old.example/") {
+                Some(new(&value["http://old.example/".len()..]))
+            } else {
+                None
+            }
+        });
+        // knows and bob collided with existing terms, so quads using them were changed;
+        // the first one now duplicates the third one
+        assert_eq!(rewritten.len(), 2);
+        assert_eq!(rewritten.iter().filter(|(_, q)| q.is_none()).count(), 1);
+
+        assert_eq!(d.quads().count(), 2);
+        assert!(d.contains(
+            &new("alice"),
+            &new("knows"),
+            &new("bob"),
+            None as Option<&BoxTerm>
+        )?);
+        assert!(d.contains(&new("bob"), &new("knows"), &new("alice"), Some(&new("g")))?);
+        assert!(d.get_index(&old("alice")).is_none());
+        // a term that did not collide kept its index
+        assert_eq!(d.node_id(&new("alice")), Some(alice));
+        assert_eq!(d.node_id(&new("knows")), Some(knows));
+        // indexes of the wrappers are up to date
+        assert_eq!(d.quads_with_s(&new("alice")).count(), 1);
+        assert_eq!(d.quads_with_o(&new("alice")).count(), 1);
+        assert_eq!(d.quads_with_g(Some(&new("g"))).count(), 1);
+
+        // rewritten terms can be swapped
+        d.rewrite_terms(|t| {
+            if t == &new("alice") {
+                Some(new("bob"))
+            } else if t == &new("bob") {
+                Some(new("alice"))
+            } else {
+                None
+            }
+        });
+        assert_eq!(d.quads().count(), 2);
+        assert!(d.contains(
+            &new("bob"),
+            &new("knows"),
+            &new("alice"),
+            None as Option<&BoxTerm>
+        )?);
+        assert!(d.contains(&new("alice"), &new("knows"), &new("bob"), Some(&new("g")))?);
+        Ok(())
+    }
+
+    #[test]
+    fn default_rewrite_terms() -> Result<(), Box<dyn std::error::Error>> {
+        let old = |s: &str| BoxTerm::new_iri(format!("http://old.example/{}", s)).unwrap();
+        let new = |s: &str| BoxTerm::new_iri(format!("http://example.org/{}", s)).unwrap();
+        let mut d = DefaultDataset::default();
+        d.insert_indexed(
+            &old("alice"),
+            &old("knows"),
+            &old("bob"),
+            None as Option<&BoxTerm>,
+        );
+        d.insert_indexed(&old("bob"), &old("knows"), &old("alice"), Some(&old("g")));
+        d.insert_indexed(
+            &new("alice"),
+            &new("knows"),
+            &new("bob"),
+            None as Option<&BoxTerm>,
+        );
+        d.insert_indexed(
+            &new("carol"),
+            &new("knows"),
+            &new("dan"),
+            None as Option<&BoxTerm>,
+        );
+        assert_eq!(d.indexed_quads().count(), 4);
+
+        let rewritten = d.rewrite_terms(|t| {
+            let value = t.value();
+            if t.kind() == TermKind::Iri && value.starts_with("http://old.example/") {
+                Some(new(&value["http://old.example/".len()..]))
+            } else {
+                None
+            }
+        });
+        // every affected quad is returned; the first one now duplicates the third one
+        assert_eq!(rewritten.len(), 2);
+        assert_eq!(rewritten.iter().filter(|(_, q)| q.is_none()).count(), 1);
+        assert_eq!(d.quads().count(), 3);
+        assert!(d.get_index(&old("alice")).is_none());
+        assert!(d.contains(&new("bob"), &new("knows"), &new("alice"), Some(&new("g")))?);
+
+        // chained rewrites are applied to the original terms
+        let rewritten = d.rewrite_terms(|t| {
+            if t == &new("alice") {
+                Some(new("bob"))
+            } else if t == &new("bob") {
+                Some(new("carol"))
+            } else {
+                None
+            }
+        });
+        assert_eq!(rewritten.len(), 2);
+        assert_eq!(d.quads().count(), 3);
+        assert!(d.contains(
+            &new("bob"),
+            &new("knows"),
+            &new("carol"),
+            None as Option<&BoxTerm>
+        )?);
+        assert!(d.contains(&new("carol"), &new("knows"), &new("bob"), Some(&new("g")))?);
+        assert!(d.contains(
+            &new("carol"),
+            &new("knows"),
+            &new("dan"),
+            None as Option<&BoxTerm>
+        )?);
+
+        d.compact();
+        assert_eq!(d.indexed_quads().count(), 3);
+        Ok(())
+    }
 }
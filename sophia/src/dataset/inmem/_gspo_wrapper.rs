@@ -125,11 +125,12 @@ where
                     let g = self.wrapped.get_graph_name(gi).unwrap();
                     let s = self.wrapped.get_term(si).unwrap();
                     let p = self.wrapped.get_term(pi).unwrap();
-                    let ois = self.gsp2o.get(&[gi, si, pi]).unwrap();
-                    return Box::new(ois.iter().map(move |oi| {
-                        let o = self.wrapped.get_term(*oi).unwrap();
-                        Ok(StreamedQuad::by_term_refs(s, p, o, g))
-                    }));
+                    if let Some(ois) = self.gsp2o.get(&[gi, si, pi]) {
+                        return Box::new(ois.iter().map(move |oi| {
+                            let o = self.wrapped.get_term(*oi).unwrap();
+                            Ok(StreamedQuad::by_term_refs(s, p, o, g))
+                        }));
+                    }
                 }
             }
         }
@@ -231,4 +232,34 @@ where
 #[cfg(all(test, feature = "all_tests"))]
 type GspoDataset = GspoWrapper<LightDataset>;
 #[cfg(all(test, feature = "all_tests"))]
-sophia_api::test_dataset_impl!(GspoDataset);
+sophia_api::test_dataset_impl!(test_gspod, GspoDataset);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::MutableDataset;
+    use sophia_term::BoxTerm;
+
+    #[test]
+    fn quads_with_spg_unknown_pair() -> Result<(), Box<dyn std::error::Error>> {
+        let a = BoxTerm::new_iri("http://example.org/a")?;
+        let b = BoxTerm::new_iri("http://example.org/b")?;
+        let mut d = GspoWrapper::<LightDataset>::new();
+        d.insert(&a, &b, &a, None as Option<&BoxTerm>)?;
+        d.insert(&b, &a, &b, None as Option<&BoxTerm>)?;
+        // a and b are both known, but never as subject and predicate of the same quad
+        assert_eq!(
+            d.quads_with_spg(&a, &a, None as Option<&BoxTerm>).count(),
+            0
+        );
+        assert_eq!(
+            d.quads_with_spg(&a, &b, None as Option<&BoxTerm>).count(),
+            1
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
 // this module is transparently re-exported by its parent `dataset::inmem`
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::hash::Hash;
 
-use crate::dataset::indexed::IndexedDataset;
+use crate::dataset::indexed::{IndexedDataset, RewrittenQuad};
 use crate::dataset::*;
 use sophia_api::quad::stream::QuadSource;
 use sophia_api::quad::streaming_mode::{ByTermRefs, StreamedQuad};
@@ -81,6 +81,19 @@ where
         self.quads.shrink_to_fit();
     }
 
+    fn compact(&mut self) {
+        let moved: HashMap<_, _> = self.terms.compact().into_iter().collect();
+        if !moved.is_empty() {
+            let map = |i| *moved.get(&i).unwrap_or(&i);
+            let quads = std::mem::take(&mut self.quads);
+            self.quads = quads
+                .into_iter()
+                .map(|[s, p, o, g]| [map(s), map(p), map(o), map(g)])
+                .collect();
+        }
+        self.shrink_to_fit();
+    }
+
     #[inline]
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -108,6 +121,11 @@ where
         self.terms.get_graph_name(i)
     }
 
+    #[inline]
+    fn indexed_quads(&self) -> Box<dyn Iterator<Item = [Self::Index; 4]> + '_> {
+        Box::new(self.quads.iter().copied())
+    }
+
     fn insert_indexed<TS, TP, TO, TG>(
         &mut self,
         s: &TS,
@@ -168,6 +186,38 @@ where
         }
         None
     }
+
+    fn rewrite_terms<U, R>(&mut self, rewrite: R) -> Vec<RewrittenQuad<Self::Index>>
+    where
+        U: TTerm,
+        R: FnMut(&Term<Self::TermData>) -> Option<U>,
+    {
+        let merged: HashMap<_, _> = self.terms.rewrite_terms(rewrite).into_iter().collect();
+        if merged.is_empty() {
+            return Vec::new();
+        }
+        let old: Vec<_> = self
+            .quads
+            .iter()
+            .filter(|q| q.iter().any(|i| merged.contains_key(i)))
+            .copied()
+            .collect();
+        let map = |i| *merged.get(&i).unwrap_or(&i);
+        let mut rewritten = Vec::with_capacity(old.len());
+        for q in old {
+            self.quads.remove(&q);
+            let new = [map(q[0]), map(q[1]), map(q[2]), map(q[3])];
+            if self.quads.insert(new) {
+                rewritten.push((q, Some(new)));
+            } else {
+                for i in new.iter() {
+                    self.terms.dec_ref(*i);
+                }
+                rewritten.push((q, None));
+            }
+        }
+        rewritten
+    }
 }
 
 impl<I> Dataset for HashDataset<I>
@@ -626,6 +626,18 @@ macro_rules! impl_indexed_dataset_for_wrapper {
             self.idw_hook_shrink_to_fit();
         }
 
+        fn compact(&mut self) {
+            self.get_wrapped_mut().compact();
+            // indices may have changed, so the indexes of the wrapper are rebuilt
+            let wrapped = std::mem::replace(self.get_wrapped_mut(), T::with_capacity(0));
+            *self = Self::idw_wrap_empty(wrapped);
+            let quads: Vec<_> = self.get_wrapped().indexed_quads().collect();
+            for q in quads {
+                self.idw_hook_insert_indexed(&Some(q));
+            }
+            self.idw_hook_shrink_to_fit();
+        }
+
         #[inline]
         fn get_index<U>(&self, t: &U) -> Option<Self::Index>
         where
@@ -652,6 +664,11 @@ macro_rules! impl_indexed_dataset_for_wrapper {
             self.get_wrapped().get_graph_name(i)
         }
 
+        #[inline]
+        fn indexed_quads(&self) -> Box<dyn Iterator<Item = [Self::Index; 4]> + '_> {
+            self.get_wrapped().indexed_quads()
+        }
+
         fn insert_indexed<TS, TP, TO, TG>(
             &mut self,
             s: &TS,
@@ -687,6 +704,22 @@ macro_rules! impl_indexed_dataset_for_wrapper {
             self.idw_hook_remove_indexed(&modified);
             modified
         }
+
+        fn rewrite_terms<U, R>(
+            &mut self,
+            rewrite: R,
+        ) -> Vec<$crate::dataset::indexed::RewrittenQuad<Self::Index>>
+        where
+            U: sophia_api::term::TTerm,
+            R: FnMut(&Term<Self::TermData>) -> Option<U>,
+        {
+            let rewritten = self.get_wrapped_mut().rewrite_terms(rewrite);
+            for (old, new) in &rewritten {
+                self.idw_hook_remove_indexed(&Some(*old));
+                self.idw_hook_insert_indexed(new);
+            }
+            rewritten
+        }
     };
 }
 
@@ -0,0 +1,391 @@
+//! A read-only view layering several datasets with different priorities.
+//!
+//! A [`LayeredDataset`] exposes the quads of all its layers,
+//! except that, for the configured *functional* properties,
+//! the values of a layer shadow those of all the layers below it:
+//! if a layer contains a value for a given subject, functional property and graph,
+//! all the values of lower layers for the same subject, property and graph are hidden.
+//! This is the usual "defaults + overrides" configuration pattern, expressed over RDF.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{Dataset, MutableDataset, inmem::FastDataset};
+//! # use sophia::dataset::layered::LayeredDataset;
+//! # use sophia::term::BoxTerm;
+//! let server = BoxTerm::new_iri("http://example.org/server")?;
+//! let port = BoxTerm::new_iri("http://example.org/port")?;
+//! let mut defaults = FastDataset::new();
+//! defaults.insert(&server, &port, &BoxTerm::from("80".to_string()), None as Option<&BoxTerm>)?;
+//! let mut overrides = FastDataset::new();
+//! overrides.insert(&server, &port, &BoxTerm::from("8080".to_string()), None as Option<&BoxTerm>)?;
+//!
+//! let mut config = LayeredDataset::new();
+//! config.push_layer(defaults).push_layer(overrides).add_property(&port);
+//! assert_eq!(config.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`LayeredDataset`]: struct.LayeredDataset.html
+
+use sophia_api::dataset::{DQuadSource, Dataset};
+use sophia_api::graph::Graph;
+use sophia_api::ns::{owl, rdf};
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_eq, CopyTerm, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// A read-only union of datasets (layers),
+/// where higher layers shadow lower ones for functional properties.
+///
+/// Quads with a non-functional property are yielded by every layer containing them,
+/// so the same quad may be yielded several times.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct LayeredDataset<D> {
+    layers: Vec<D>,
+    properties: Vec<BoxTerm>,
+}
+
+impl<D> LayeredDataset<D> {
+    /// Build a dataset with no layer and no functional property yet.
+    pub fn new() -> Self {
+        LayeredDataset {
+            layers: vec![],
+            properties: vec![],
+        }
+    }
+
+    /// Add `layer` on top of the existing ones, i.e. with the highest priority.
+    pub fn push_layer(&mut self, layer: D) -> &mut Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// The layers, from the lowest to the highest priority.
+    pub fn layers(&self) -> &[D] {
+        &self.layers
+    }
+
+    /// Unwrap the layers, from the lowest to the highest priority.
+    pub fn into_layers(self) -> Vec<D> {
+        self.layers
+    }
+
+    /// Declare `property` as functional.
+    pub fn add_property<T: TTerm + ?Sized>(&mut self, property: &T) -> &mut Self {
+        if !self.is_functional(property) {
+            self.properties.push(BoxTerm::copy(property));
+        }
+        self
+    }
+
+    /// Declare as functional all the properties declared as `owl:FunctionalProperty` in `ontology`.
+    pub fn add_declared_properties<G: Graph>(
+        &mut self,
+        ontology: &G,
+    ) -> Result<&mut Self, G::Error> {
+        for t in ontology.triples_with_po(&rdf::type_, &owl::FunctionalProperty) {
+            self.add_property(t?.s());
+        }
+        Ok(self)
+    }
+
+    /// The functional properties.
+    pub fn properties(&self) -> &[BoxTerm] {
+        &self.properties
+    }
+
+    /// Whether `property` is functional.
+    pub fn is_functional<T: TTerm + ?Sized>(&self, property: &T) -> bool {
+        self.properties.iter().any(|p| term_eq(p, property))
+    }
+}
+
+impl<D> Default for LayeredDataset<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: Dataset> LayeredDataset<D> {
+    /// Whether quad `q` of layer `i` is hidden by a higher layer.
+    fn is_shadowed<Q: Quad>(&self, i: usize, q: &Q) -> Result<bool, D::Error> {
+        if !self.is_functional(q.p()) {
+            return Ok(false);
+        }
+        for layer in &self.layers[i + 1..] {
+            if let Some(res) = layer.quads_with_spg(q.s(), q.p(), q.g()).next() {
+                res?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Chain the quads selected by `select` in each layer, filtering out shadowed ones.
+    fn layered<'s, F>(&'s self, select: F) -> DQuadSource<'s, Self>
+    where
+        F: Fn(&'s D) -> DQuadSource<'s, D> + 's,
+    {
+        Box::new(self.layers.iter().enumerate().flat_map(move |(i, layer)| {
+            select(layer).filter_map(move |res| match res {
+                Ok(q) => match self.is_shadowed(i, &q) {
+                    Ok(true) => None,
+                    Ok(false) => Some(Ok(q)),
+                    Err(err) => Some(Err(err)),
+                },
+                Err(err) => Some(Err(err)),
+            })
+        }))
+    }
+}
+
+impl<D: Dataset> Dataset for LayeredDataset<D> {
+    type Quad = D::Quad;
+    type Error = D::Error;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.layered(|d| d.quads())
+    }
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_s(s))
+    }
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_p(p))
+    }
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_o(o))
+    }
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_g(g))
+    }
+    fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_sp(s, p))
+    }
+    fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_so(s, o))
+    }
+    fn quads_with_sg<'s, TS, TG>(&'s self, s: &'s TS, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_sg(s, g))
+    }
+    fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_po(p, o))
+    }
+    fn quads_with_pg<'s, TP, TG>(&'s self, p: &'s TP, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_pg(p, g))
+    }
+    fn quads_with_og<'s, TO, TG>(&'s self, o: &'s TO, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_og(o, g))
+    }
+    fn quads_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_spo(s, p, o))
+    }
+    fn quads_with_spg<'s, TS, TP, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_spg(s, p, g))
+    }
+    fn quads_with_sog<'s, TS, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_sog(s, o, g))
+    }
+    fn quads_with_pog<'s, TP, TO, TG>(
+        &'s self,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_pog(p, o, g))
+    }
+    fn quads_with_spog<'s, TS, TP, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.layered(move |d| d.quads_with_spog(s, p, o, g))
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::dataset::MutableDataset;
+    use sophia_api::graph::MutableGraph;
+    use sophia_api::ns::Namespace;
+
+    fn layer(quads: &[(&str, &str, Option<&str>)]) -> FastDataset {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let mut d = FastDataset::new();
+        for (p, o, g) in quads {
+            let g = g.map(|g| ex.get(g).unwrap());
+            d.insert(
+                &ex.get("server").unwrap(),
+                &ex.get(p).unwrap(),
+                &ex.get(o).unwrap(),
+                g.as_ref(),
+            )
+            .unwrap();
+        }
+        d
+    }
+
+    fn objects(d: &LayeredDataset<FastDataset>, p: &str, g: Option<&BoxTerm>) -> Vec<BoxTerm> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let mut objects: Vec<_> = d
+            .quads_with_spg(&ex.get("server").unwrap(), &ex.get(p).unwrap(), g)
+            .map(|q| BoxTerm::copy(q.unwrap().o()))
+            .collect();
+        objects.sort_by_key(|t| t.value().to_string());
+        objects
+    }
+
+    fn layered() -> LayeredDataset<FastDataset> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let mut d = LayeredDataset::new();
+        d.push_layer(layer(&[
+            ("port", "p80", None),
+            ("host", "localhost", None),
+            ("tag", "a", None),
+            ("port", "p80", Some("test")),
+        ]))
+        .push_layer(layer(&[("port", "p8080", None), ("tag", "b", None)]))
+        .add_property(&ex.get("port").unwrap())
+        .add_property(&ex.get("host").unwrap());
+        d
+    }
+
+    #[test]
+    fn shadowing() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let d = layered();
+        assert_eq!(d.layers().len(), 2);
+        assert_eq!(objects(&d, "port", None), vec![ex.get("p8080")?]);
+        assert_eq!(objects(&d, "host", None), vec![ex.get("localhost")?]);
+        assert_eq!(objects(&d, "tag", None), vec![ex.get("a")?, ex.get("b")?]);
+        // shadowing is per graph
+        assert_eq!(
+            objects(&d, "port", Some(&BoxTerm::copy(&ex.get("test")?))),
+            vec![ex.get("p80")?]
+        );
+        assert_eq!(d.quads().count(), 5);
+        assert_eq!(d.quads_with_p(&ex.get("port")?).count(), 2);
+        assert!(!d.contains(
+            &ex.get("server")?,
+            &ex.get("port")?,
+            &ex.get("p80")?,
+            None as Option<&BoxTerm>
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn non_functional() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let mut d = LayeredDataset::new();
+        d.push_layer(layer(&[("port", "p80", None)]))
+            .push_layer(layer(&[("port", "p8080", None)]));
+        assert_eq!(
+            objects(&d, "port", None),
+            vec![ex.get("p80")?, ex.get("p8080")?]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn declared_properties() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let mut ontology = FastGraph::new();
+        ontology.insert(&ex.get("port")?, &rdf::type_, &owl::FunctionalProperty)?;
+        let mut d = LayeredDataset::new();
+        d.push_layer(layer(&[("port", "p80", None)]))
+            .push_layer(layer(&[("port", "p8080", None)]))
+            .add_declared_properties(&ontology)?;
+        assert!(d.is_functional(&ex.get("port")?));
+        assert_eq!(objects(&d, "port", None), vec![ex.get("p8080")?]);
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Metadata about the graphs of a dataset.
+//!
+//! When a dataset is loaded from several files (typically one file per named graph),
+//! information such as the base IRI or the prefixes declared in each file
+//! is lost once the quads are inserted in the dataset.
+//! [`DatasetMetadata`] keeps track of this information on a per-graph basis,
+//! so that it can be populated when loading each file,
+//! and consulted when serializing each graph back.
+//!
+//! The Turtle and TriG parsers populate it with their `load_into` methods
+//! (see [`TurtleParser::load_into`] and [`TriGParser::load_into`]).
+//! Serializers supporting prefixes consume it
+//! through [`SerializerConfig::add_prefixes_from`]
+//! or [`TrigSerializer::serialize_dataset_with_metadata`].
+//!
+//! [`DatasetMetadata`]: struct.DatasetMetadata.html
+//! [`TurtleParser::load_into`]: ../../parser/turtle/struct.TurtleParser.html#method.load_into
+//! [`TriGParser::load_into`]: ../../parser/trig/struct.TriGParser.html#method.load_into
+//! [`SerializerConfig::add_prefixes_from`]: ../../serializer/config/struct.SerializerConfig.html#method.add_prefixes_from
+//! [`TrigSerializer::serialize_dataset_with_metadata`]: ../../serializer/trig/struct.TrigSerializer.html#method.serialize_dataset_with_metadata
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::time::SystemTime;
+
+use sophia_api::term::{CopyTerm, SimpleIri, TTerm};
+use sophia_term::BoxTerm;
+
+/// Metadata about a single graph of a dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphMetadata {
+    /// The base IRI that was used to parse this graph, if any.
+    pub base: Option<String>,
+    /// The prefixes declared in the source of this graph, as (prefix, namespace) pairs.
+    pub prefixes: Vec<(String, String)>,
+    /// The URL from which this graph was loaded, if any.
+    pub source: Option<String>,
+    /// The last modification time of the source of this graph, if known.
+    pub last_modified: Option<SystemTime>,
+}
+
+impl GraphMetadata {
+    /// Add a prefix declaration, replacing any previous declaration of the same prefix.
+    pub fn add_prefix<P, N>(&mut self, prefix: P, namespace: N)
+    where
+        P: Into<String>,
+        N: Into<String>,
+    {
+        let prefix = prefix.into();
+        let namespace = namespace.into();
+        match self.prefixes.iter_mut().find(|(p, _)| *p == prefix) {
+            Some(pair) => pair.1 = namespace,
+            None => self.prefixes.push((prefix, namespace)),
+        }
+    }
+
+    /// Build a [`PrefixMap`] from the prefixes of this graph,
+    /// suitable for serializers supporting prefixes.
+    ///
+    /// [`PrefixMap`]: ../../prefix/trait.PrefixMap.html
+    pub fn prefix_map(&self) -> HashMap<&str, SimpleIri<'_>> {
+        self.prefixes
+            .iter()
+            .map(|(p, ns)| (p.as_str(), SimpleIri::new_unchecked(ns, None)))
+            .collect()
+    }
+}
+
+/// Metadata about the graphs of a dataset, indexed by graph name.
+///
+/// The default graph is identified by the graph name `None`.
+#[derive(Clone, Debug, Default)]
+pub struct DatasetMetadata {
+    graphs: HashMap<Option<BoxTerm>, GraphMetadata>,
+}
+
+impl DatasetMetadata {
+    /// Build an empty `DatasetMetadata`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the metadata of the graph named `graph_name`, if any.
+    pub fn get<T>(&self, graph_name: Option<&T>) -> Option<&GraphMetadata>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.graphs.get(&graph_name.map(BoxTerm::copy))
+    }
+
+    /// Return the metadata of the graph named `graph_name`,
+    /// inserting default metadata if none was present.
+    pub fn get_or_default<T>(&mut self, graph_name: Option<&T>) -> &mut GraphMetadata
+    where
+        T: TTerm + ?Sized,
+    {
+        self.graphs
+            .entry(graph_name.map(BoxTerm::copy))
+            .or_default()
+    }
+
+    /// Set the metadata of the graph named `graph_name`,
+    /// and return its previous metadata, if any.
+    pub fn set<T>(
+        &mut self,
+        graph_name: Option<&T>,
+        metadata: GraphMetadata,
+    ) -> Option<GraphMetadata>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.graphs.insert(graph_name.map(BoxTerm::copy), metadata)
+    }
+
+    /// Remove the metadata of the graph named `graph_name`,
+    /// and return it, if any.
+    pub fn remove<T>(&mut self, graph_name: Option<&T>) -> Option<GraphMetadata>
+    where
+        T: TTerm + ?Sized,
+    {
+        match self.graphs.entry(graph_name.map(BoxTerm::copy)) {
+            Entry::Occupied(e) => Some(e.remove()),
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    /// Iterate over all graph names and their metadata.
+    pub fn iter(&self) -> impl Iterator<Item = (Option<&BoxTerm>, &GraphMetadata)> {
+        self.graphs.iter().map(|(g, m)| (g.as_ref(), m))
+    }
+
+    /// The number of graphs having metadata.
+    pub fn len(&self) -> usize {
+        self.graphs.len()
+    }
+
+    /// Whether no graph has metadata.
+    pub fn is_empty(&self) -> bool {
+        self.graphs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::prefix::PrefixMap;
+
+    #[test]
+    fn per_graph_metadata() {
+        let g1 = BoxTerm::new_iri("http://example.org/g1").unwrap();
+        let mut md = DatasetMetadata::new();
+        assert!(md.get(None as Option<&BoxTerm>).is_none());
+
+        let gmd = md.get_or_default(Some(&g1));
+        gmd.base = Some("http://example.org/g1".into());
+        gmd.add_prefix("ex", "http://example.org/");
+        gmd.add_prefix("ex", "http://example.org/ns#");
+        md.get_or_default(None as Option<&BoxTerm>).source = Some("file:default.ttl".into());
+        assert_eq!(md.len(), 2);
+
+        let gmd = md.get(Some(&g1)).unwrap();
+        assert_eq!(gmd.prefixes.len(), 1);
+        let pm = gmd.prefix_map();
+        let iri = SimpleIri::new_unchecked("http://example.org/ns#foo", None);
+        assert_eq!(pm.get_prefixed_pair(&iri), Some(("ex", "foo".into())));
+
+        assert!(md.remove(Some(&g1)).is_some());
+        assert!(md.get(Some(&g1)).is_none());
+        assert_eq!(md.len(), 1);
+    }
+}
@@ -0,0 +1,517 @@
+//! A mock dataset, for unit-testing code built on the [`Dataset`] trait.
+//!
+//! A [`MockDataset`] records every request it receives
+//! (as a [`Pattern`] of the constant terms passed to [`quads`] or one of the `quads_with_*` methods),
+//! so that tests can check *how* a dataset is queried, and not only the result.
+//! By default, requests are answered from the quads of the mock,
+//! but the answer to a given pattern can be scripted,
+//! either as a fixed list of quads, or as an error,
+//! so that error paths can be tested without a real store.
+//!
+//! NB: the provided methods of [`Dataset`] (e.g. [`contains`] or [`quads_matching`])
+//! are implemented on top of [`quads`] and the `quads_with_*` methods,
+//! so the patterns recorded for them are those of the underlying calls.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::Dataset;
+//! # use sophia::dataset::mock::{MockDataset, Pattern};
+//! # use sophia::ns::rdf;
+//! # use sophia::term::{BoxTerm, CopyTerm};
+//! let alice = BoxTerm::new_iri("http://example.org/alice")?;
+//! let mut d = MockDataset::default();
+//! d.script_error(Pattern { s: Some(alice.clone()), ..Pattern::default() }, 0, "connection lost");
+//!
+//! assert_eq!(d.quads_with_p(&rdf::type_).count(), 0);
+//! assert!(d.quads_with_s(&alice).next().unwrap().is_err());
+//! assert_eq!(d.requests(), vec![
+//!     Pattern { p: Some(BoxTerm::copy(&rdf::type_)), ..Pattern::default() },
+//!     Pattern { s: Some(alice), ..Pattern::default() },
+//! ]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Dataset`]: ../trait.Dataset.html
+//! [`MockDataset`]: struct.MockDataset.html
+//! [`Pattern`]: struct.Pattern.html
+//! [`quads`]: ../trait.Dataset.html#tymethod.quads
+//! [`contains`]: ../trait.Dataset.html#method.contains
+//! [`quads_matching`]: ../trait.Dataset.html#method.quads_matching
+
+use std::sync::Mutex;
+
+use sophia_api::dataset::{DQuadSource, Dataset};
+use sophia_api::quad::streaming_mode::{ByRef, StreamedQuad};
+use sophia_api::quad::Quad;
+use sophia_api::term::{same_graph_name, term_eq, CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The type of quads stored in a [`MockDataset`](struct.MockDataset.html).
+pub type MockQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// A request received by a [`MockDataset`](struct.MockDataset.html).
+///
+/// Each field is `None` if the corresponding position was not constrained;
+/// in particular, `g` is `Some(None)` for a request on the default graph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pattern {
+    /// The requested subject, if any.
+    pub s: Option<BoxTerm>,
+    /// The requested predicate, if any.
+    pub p: Option<BoxTerm>,
+    /// The requested object, if any.
+    pub o: Option<BoxTerm>,
+    /// The requested graph name, if any.
+    pub g: Option<Option<BoxTerm>>,
+}
+
+impl Pattern {
+    /// Whether `quad` matches this pattern.
+    pub fn matches<Q: Quad>(&self, quad: &Q) -> bool {
+        self.s.as_ref().is_none_or(|s| term_eq(s, quad.s()))
+            && self.p.as_ref().is_none_or(|p| term_eq(p, quad.p()))
+            && self.o.as_ref().is_none_or(|o| term_eq(o, quad.o()))
+            && self
+                .g
+                .as_ref()
+                .is_none_or(|g| same_graph_name(g.as_ref(), quad.g()))
+    }
+}
+
+/// The scripted answer to a pattern.
+#[derive(Clone, Debug)]
+enum Script {
+    Quads(Vec<MockQuad>),
+    Error { after: usize, message: String },
+}
+
+/// A [`Dataset`](../trait.Dataset.html) recording the requests it receives,
+/// and returning scripted answers.
+///
+/// See [module documentation](index.html).
+#[derive(Debug, Default)]
+pub struct MockDataset {
+    /// The quads of this dataset, used to answer requests that are not scripted.
+    pub quads: Vec<MockQuad>,
+    scripts: Vec<(Pattern, Script)>,
+    requests: Mutex<Vec<Pattern>>,
+}
+
+impl MockDataset {
+    /// Build a mock dataset containing a copy of `quads`.
+    pub fn new<I, Q>(quads: I) -> Self
+    where
+        I: IntoIterator<Item = Q>,
+        Q: Quad,
+    {
+        let quads = quads
+            .into_iter()
+            .map(|q| {
+                (
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                )
+            })
+            .collect();
+        MockDataset {
+            quads,
+            ..Self::default()
+        }
+    }
+
+    /// Answer requests exactly matching `pattern` with `quads`,
+    /// regardless of the quads of this dataset.
+    ///
+    /// This replaces any previous script for `pattern`.
+    pub fn script_quads(&mut self, pattern: Pattern, quads: Vec<MockQuad>) -> &mut Self {
+        self.script(pattern, Script::Quads(quads))
+    }
+
+    /// Answer requests exactly matching `pattern` with an error,
+    /// raised after yielding (at most) `after` matching quads.
+    ///
+    /// This replaces any previous script for `pattern`.
+    pub fn script_error<M: Into<String>>(
+        &mut self,
+        pattern: Pattern,
+        after: usize,
+        message: M,
+    ) -> &mut Self {
+        let message = message.into();
+        self.script(pattern, Script::Error { after, message })
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<Pattern> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Forget the requests received so far.
+    pub fn clear_requests(&self) {
+        self.requests.lock().unwrap().clear();
+    }
+
+    fn script(&mut self, pattern: Pattern, script: Script) -> &mut Self {
+        self.scripts.retain(|(p, _)| p != &pattern);
+        self.scripts.push((pattern, script));
+        self
+    }
+
+    fn answer(&self, pattern: Pattern) -> DQuadSource<'_, Self> {
+        self.requests.lock().unwrap().push(pattern.clone());
+        let script = self
+            .scripts
+            .iter()
+            .find(|(p, _)| p == &pattern)
+            .map(|(_, script)| script);
+        let matching = self.quads.iter().filter(move |q| pattern.matches(*q));
+        match script {
+            None => Box::new(matching.map(|q| Ok(StreamedQuad::by_ref(q)))),
+            Some(Script::Quads(quads)) => {
+                Box::new(quads.iter().map(|q| Ok(StreamedQuad::by_ref(q))))
+            }
+            Some(Script::Error { after, message }) => Box::new(
+                matching
+                    .take(*after)
+                    .map(|q| Ok(StreamedQuad::by_ref(q)))
+                    .chain(std::iter::once(Err(MockError(message.clone())))),
+            ),
+        }
+    }
+}
+
+fn copy<T: TTerm + ?Sized>(t: &T) -> Option<BoxTerm> {
+    Some(BoxTerm::copy(t))
+}
+
+fn copy_g<T: TTerm + ?Sized>(g: Option<&T>) -> Option<Option<BoxTerm>> {
+    Some(g.map(BoxTerm::copy))
+}
+
+impl Dataset for MockDataset {
+    type Quad = ByRef<MockQuad>;
+    type Error = MockError;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.answer(Pattern::default())
+    }
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            p: copy(p),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            o: copy(o),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            g: copy_g(g),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            p: copy(p),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            o: copy(o),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_sg<'s, TS, TG>(&'s self, s: &'s TS, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            g: copy_g(g),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            p: copy(p),
+            o: copy(o),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_pg<'s, TP, TG>(&'s self, p: &'s TP, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            p: copy(p),
+            g: copy_g(g),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_og<'s, TO, TG>(&'s self, o: &'s TO, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            o: copy(o),
+            g: copy_g(g),
+            ..Pattern::default()
+        })
+    }
+    fn quads_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            p: copy(p),
+            o: copy(o),
+            g: None,
+        })
+    }
+    fn quads_with_spg<'s, TS, TP, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            p: copy(p),
+            o: None,
+            g: copy_g(g),
+        })
+    }
+    fn quads_with_sog<'s, TS, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            p: None,
+            o: copy(o),
+            g: copy_g(g),
+        })
+    }
+    fn quads_with_pog<'s, TP, TO, TG>(
+        &'s self,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: None,
+            p: copy(p),
+            o: copy(o),
+            g: copy_g(g),
+        })
+    }
+    fn quads_with_spog<'s, TS, TP, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.answer(Pattern {
+            s: copy(s),
+            p: copy(p),
+            o: copy(o),
+            g: copy_g(g),
+        })
+    }
+}
+
+/// The error raised by [`MockDataset`](struct.MockDataset.html) when scripted to.
+#[derive(Clone, Debug, Error, PartialEq)]
+#[error("{0}")]
+pub struct MockError(pub String);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ns::{rdf, rdfs};
+    use sophia_api::term::matcher::ANY;
+
+    fn ex(suffix: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    fn dataset() -> MockDataset {
+        MockDataset::new(vec![
+            ([ex("a"), BoxTerm::copy(&rdf::type_), ex("C")], None),
+            (
+                [ex("b"), BoxTerm::copy(&rdf::type_), ex("C")],
+                Some(ex("g")),
+            ),
+            (
+                [
+                    ex("a"),
+                    BoxTerm::copy(&rdfs::label),
+                    BoxTerm::from("a".to_string()),
+                ],
+                Some(ex("g")),
+            ),
+        ])
+    }
+
+    #[test]
+    fn records_requests() -> Result<(), MockError> {
+        let d = dataset();
+        assert_eq!(d.quads().count(), 3);
+        assert_eq!(d.quads_with_s(&ex("a")).count(), 2);
+        assert_eq!(d.quads_with_pg(&rdf::type_, Some(&ex("g"))).count(), 1);
+        assert!(d.contains(&ex("a"), &rdf::type_, &ex("C"), None as Option<&BoxTerm>)?);
+        assert_eq!(
+            d.quads_matching(&ANY, &[&ex("a"), &ex("b")], &ANY, &ANY)
+                .count(),
+            0
+        );
+        assert_eq!(
+            d.requests(),
+            vec![
+                Pattern::default(),
+                Pattern {
+                    s: Some(ex("a")),
+                    ..Pattern::default()
+                },
+                Pattern {
+                    p: Some(BoxTerm::copy(&rdf::type_)),
+                    g: Some(Some(ex("g"))),
+                    ..Pattern::default()
+                },
+                Pattern {
+                    s: Some(ex("a")),
+                    p: Some(BoxTerm::copy(&rdf::type_)),
+                    o: Some(ex("C")),
+                    g: Some(None),
+                },
+                Pattern::default(),
+            ]
+        );
+        d.clear_requests();
+        assert!(d.requests().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn scripted_quads() {
+        let mut d = dataset();
+        let pattern = Pattern {
+            o: Some(ex("C")),
+            ..Pattern::default()
+        };
+        d.script_quads(pattern.clone(), vec![([ex("x"), ex("y"), ex("z")], None)]);
+        let c = ex("C");
+        let quads: Vec<_> = d.quads_with_o(&c).map(Result::unwrap).collect();
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].s(), &ex("x"));
+        // other patterns are not affected
+        assert_eq!(d.quads_with_o(&ex("D")).count(), 0);
+        assert_eq!(d.quads_with_p(&rdf::type_).count(), 2);
+        // scripts can be replaced
+        d.script_quads(pattern, vec![]);
+        assert_eq!(d.quads_with_o(&ex("C")).count(), 0);
+    }
+
+    #[test]
+    fn scripted_error() {
+        let mut d = dataset();
+        d.script_error(Pattern::default(), 2, "boom");
+        let results: Vec<_> = d.quads().collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert_eq!(results[2].as_ref().err(), Some(&MockError("boom".into())));
+
+        // errors propagate through provided methods
+        d.script_error(
+            Pattern {
+                s: Some(ex("a")),
+                ..Pattern::default()
+            },
+            0,
+            "unavailable",
+        );
+        assert!(d.subjects().is_err());
+        assert!(d.quads_with_s(&ex("a")).next().unwrap().is_err());
+        assert_eq!(d.quads_with_s(&ex("b")).count(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+//! A content-addressed store of datasets.
+//!
+//! Each dataset is identified by a [`DatasetHash`],
+//! the SHA-256 hash of its [canonical N-Quads](../../serializer/nq/fn.canonical_nquads.html) serialization.
+//! Storing the same dataset twice (even with different blank node labels)
+//! therefore only stores it once,
+//! and a document referring to a dataset by its hash
+//! can check that the dataset it retrieves is the one it refers to.
+//!
+//! NB: the canonical form relies on [`BnodeLabeler::canonical`],
+//! which may fail to give the same labels to isomorphic datasets
+//! in some highly symmetrical cases;
+//! such datasets would then be stored twice, under different hashes.
+//!
+//! This module requires the `store` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::inmem::FastDataset;
+//! # use sophia::dataset::store::{canonical_hash, ContentStore};
+//! # fn f(d: &FastDataset) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut store: ContentStore = ContentStore::new();
+//! let hash = store.put(d)?;
+//! println!("stored as {}", hash);
+//! assert_eq!(canonical_hash(store.get(&hash).unwrap())?, hash);
+//! # Ok(()) }
+//! ```
+//!
+//! [`DatasetHash`]: struct.DatasetHash.html
+//! [`BnodeLabeler::canonical`]: ../../serializer/labeling/struct.BnodeLabeler.html#method.canonical
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+use sophia_api::dataset::{Dataset, MutableDataset};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use thiserror::Error;
+
+use crate::dataset::inmem::FastDataset;
+use crate::serializer::nq::{canonical_lines, canonical_quads};
+
+pub use crate::serializer::nq::canonical_nquads;
+
+/// The SHA-256 hash of the [canonical N-Quads](../../serializer/nq/fn.canonical_nquads.html) of `dataset`.
+pub fn canonical_hash<D: Dataset>(dataset: &D) -> Result<DatasetHash, D::Error> {
+    Ok(DatasetHash::of(&canonical_lines(&canonical_quads(
+        dataset,
+    )?)))
+}
+
+/// The hash identifying a dataset in a [`ContentStore`](struct.ContentStore.html).
+///
+/// Hashes are displayed, and parsed from, lowercase hexadecimal strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DatasetHash(pub [u8; 32]);
+
+impl DatasetHash {
+    fn of(bytes: &[u8]) -> Self {
+        let mut hash = [0; 32];
+        hash.copy_from_slice(&Sha256::digest(bytes));
+        DatasetHash(hash)
+    }
+}
+
+impl fmt::Display for DatasetHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in &self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DatasetHash {
+    type Err = InvalidHash;
+
+    fn from_str(txt: &str) -> Result<Self, InvalidHash> {
+        if txt.len() != 64 || !txt.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(InvalidHash(txt.to_string()));
+        }
+        let mut hash = [0; 32];
+        for (i, b) in hash.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&txt[2 * i..2 * i + 2], 16).unwrap();
+        }
+        Ok(DatasetHash(hash))
+    }
+}
+
+/// The error raised when parsing an invalid [`DatasetHash`](struct.DatasetHash.html).
+#[derive(Debug, Error)]
+#[error("Invalid dataset hash {0:?}")]
+pub struct InvalidHash(pub String);
+
+/// A content-addressed store of datasets.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct ContentStore<D = FastDataset> {
+    datasets: HashMap<DatasetHash, D>,
+}
+
+impl<D> Default for ContentStore<D> {
+    fn default() -> Self {
+        ContentStore {
+            datasets: HashMap::new(),
+        }
+    }
+}
+
+impl<D: MutableDataset + Default> ContentStore<D> {
+    /// Build an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a copy of `dataset` (with canonical blank node labels), and return its hash.
+    ///
+    /// If an identical dataset is already stored, it is not stored again.
+    pub fn put<S: Dataset>(
+        &mut self,
+        dataset: &S,
+    ) -> StreamResult<DatasetHash, S::Error, D::MutationError> {
+        let quads = canonical_quads(dataset).map_err(SourceError)?;
+        let hash = DatasetHash::of(&canonical_lines(&quads));
+        if let Entry::Vacant(entry) = self.datasets.entry(hash) {
+            let mut copy = D::default();
+            for ([s, p, o], g) in &quads {
+                copy.insert(s, p, o, g.as_ref()).map_err(SinkError)?;
+            }
+            entry.insert(copy);
+        }
+        Ok(hash)
+    }
+
+    /// The dataset stored with the given hash, if any.
+    pub fn get(&self, hash: &DatasetHash) -> Option<&D> {
+        self.datasets.get(hash)
+    }
+
+    /// Whether a dataset is stored with the given hash.
+    pub fn contains(&self, hash: &DatasetHash) -> bool {
+        self.datasets.contains_key(hash)
+    }
+
+    /// Remove the dataset stored with the given hash, and return it.
+    pub fn remove(&mut self, hash: &DatasetHash) -> Option<D> {
+        self.datasets.remove(hash)
+    }
+
+    /// The hashes of the datasets in this store, in no particular order.
+    pub fn hashes(&self) -> impl Iterator<Item = &DatasetHash> + '_ {
+        self.datasets.keys()
+    }
+
+    /// The number of datasets in this store.
+    pub fn len(&self) -> usize {
+        self.datasets.len()
+    }
+
+    /// Whether this store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.datasets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::trig;
+    use crate::quad::stream::QuadSource;
+    use sophia_api::dataset::isomorphic_datasets;
+
+    fn parse(trig: &str) -> FastDataset {
+        trig::parse_str(trig).collect_quads().unwrap()
+    }
+
+    const D1: &str = r#"
+        @prefix : <http://example.org/> .
+        :alice :knows [ :name "Bob" ], _:x .
+        _:x :name "Charlie" .
+        :g { _:x :age 42 . }
+    "#;
+
+    // D1 with different blank node labels and statement order
+    const D2: &str = r#"
+        @prefix : <http://example.org/> .
+        :g { _:y :age 42 . }
+        _:z :name "Bob" .
+        :alice :knows _:y, _:z .
+        _:y :name "Charlie" .
+    "#;
+
+    #[test]
+    fn canonical_form() -> Result<(), Box<dyn std::error::Error>> {
+        let (d1, d2) = (parse(D1), parse(D2));
+        let nq = canonical_nquads(&d1)?;
+        assert_eq!(nq, canonical_nquads(&d2)?);
+        assert_eq!(nq.lines().count(), 5);
+        assert!(nq.contains("_:c0"));
+        let mut lines: Vec<&str> = nq.lines().collect();
+        lines.sort_unstable();
+        assert_eq!(lines, nq.lines().collect::<Vec<_>>());
+
+        assert_eq!(canonical_hash(&d1)?, canonical_hash(&d2)?);
+        let d3 = parse(&D1.replace("Bob", "Bobby"));
+        assert_ne!(canonical_hash(&d1)?, canonical_hash(&d3)?);
+        let d4 = parse(&D1.replace(":g {", ":h {"));
+        assert_ne!(canonical_hash(&d1)?, canonical_hash(&d4)?);
+        Ok(())
+    }
+
+    #[test]
+    fn hash_strings() {
+        let hash = canonical_hash(&FastDataset::new()).unwrap();
+        // SHA-256 of the empty string
+        let txt = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(hash.to_string(), txt);
+        assert_eq!(txt.parse::<DatasetHash>().unwrap(), hash);
+        assert!("e3b0".parse::<DatasetHash>().is_err());
+        assert!(txt.replace('e', "g").parse::<DatasetHash>().is_err());
+    }
+
+    #[test]
+    fn store() -> Result<(), Box<dyn std::error::Error>> {
+        let mut store: ContentStore = ContentStore::new();
+        let h1 = store.put(&parse(D1))?;
+        let h2 = store.put(&parse(D2))?;
+        assert_eq!(h1, h2);
+        assert_eq!(store.len(), 1);
+        let h3 = store.put(&parse(&D1.replace("Bob", "Bobby")))?;
+        assert_ne!(h1, h3);
+        assert_eq!(store.len(), 2);
+
+        let d1 = store.get(&h1).unwrap();
+        assert!(isomorphic_datasets(d1, &parse(D1))?);
+        assert_eq!(canonical_hash(d1)?, h1);
+        assert!(store.contains(&h3));
+        assert!(store.remove(&h3).is_some());
+        assert!(!store.contains(&h3));
+        assert!(store.get(&h3).is_none());
+        assert_eq!(store.hashes().collect::<Vec<_>>(), vec![&h1]);
+        Ok(())
+    }
+}
@@ -0,0 +1,549 @@
+//! A dataset wrapper keeping track of its revisions, for incremental export.
+//!
+//! A [`VersionedDataset`] records every change (added or deleted quad)
+//! made to the wrapped dataset.
+//! Changes are grouped into revisions by calling [`commit`].
+//! The dataset can then be exported as [RDF Patch],
+//! either fully ([`write_snapshot`]) or as the changes since a given revision ([`write_patch`]),
+//! so that mirrors can be kept in sync without transferring the whole dataset every time.
+//! Each revision is written as one transaction (`TX` ... `TC`).
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{MutableDataset, inmem::FastDataset};
+//! # use sophia::dataset::versioned::VersionedDataset;
+//! # use sophia::term::BoxTerm;
+//! let s = BoxTerm::new_iri("http://example.org/s")?;
+//! let mut d = VersionedDataset::new(FastDataset::new());
+//! d.insert(&s, &s, &s, None as Option<&BoxTerm>)?;
+//! let mirrored = d.commit();
+//! d.remove(&s, &s, &s, None as Option<&BoxTerm>)?;
+//! d.commit();
+//!
+//! let mut patch = Vec::new();
+//! d.write_patch(&mut patch, mirrored)?;
+//! assert_eq!(
+//!     std::str::from_utf8(&patch)?,
+//!     "TX .\nD <http://example.org/s> <http://example.org/s> <http://example.org/s> .\nTC .\n"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`VersionedDataset`]: struct.VersionedDataset.html
+//! [`commit`]: struct.VersionedDataset.html#method.commit
+//! [`write_snapshot`]: struct.VersionedDataset.html#method.write_snapshot
+//! [`write_patch`]: struct.VersionedDataset.html#method.write_patch
+//! [RDF Patch]: https://afs.github.io/rdf-patch/
+
+use std::io;
+
+use sophia_api::dataset::{DQuadSource, Dataset, MdResult, MutableDataset, SetDataset};
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_term::BoxTerm;
+
+use crate::serializer::nt::write_term;
+
+type BoxQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// A revision number.
+///
+/// Revision 0 is the state of the wrapped dataset when it was wrapped.
+pub type Revision = u64;
+
+/// A change recorded by a [`VersionedDataset`](struct.VersionedDataset.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// The quad was added.
+    Add(BoxQuad),
+    /// The quad was deleted.
+    Delete(BoxQuad),
+}
+
+/// A dataset wrapper recording all changes, grouped in revisions.
+///
+/// Only actual changes are recorded,
+/// i.e. insertions and removals for which the wrapped dataset returns `true`;
+/// the wrapped dataset should therefore be a [`SetDataset`](../trait.SetDataset.html).
+///
+/// NB: all changes are kept in memory,
+/// see [`forget_before`](#method.forget_before) to discard the oldest ones.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct VersionedDataset<D> {
+    inner: D,
+    revision: Revision,
+    oldest: Revision,
+    log: Vec<(Revision, Change)>,
+    // revision is the last committed revision;
+    // changes made since then belong to revision+1, until commit is called.
+    // oldest is the oldest revision from which a patch can still be computed
+    // (see forget_before).
+}
+
+impl<D> VersionedDataset<D> {
+    /// Wrap `inner`, starting at revision 0.
+    pub fn new(inner: D) -> Self {
+        VersionedDataset {
+            inner,
+            revision: 0,
+            oldest: 0,
+            log: vec![],
+        }
+    }
+
+    /// The last committed revision.
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// Whether some changes have been made since the last commit.
+    pub fn has_pending_changes(&self) -> bool {
+        matches!(self.log.last(), Some((r, _)) if *r > self.revision)
+    }
+
+    /// Group all changes made since the last commit into a new revision, and return it.
+    ///
+    /// If no change was made since the last commit, no revision is created,
+    /// and the last committed revision is returned.
+    pub fn commit(&mut self) -> Revision {
+        if self.has_pending_changes() {
+            self.revision += 1;
+        }
+        self.revision
+    }
+
+    /// The committed changes made after revision `since`, with their revision.
+    ///
+    /// Return `None` if the changes of revisions up to `since` are no longer available
+    /// (see [`forget_before`](#method.forget_before)).
+    pub fn changes_since(&self, since: Revision) -> Option<&[(Revision, Change)]> {
+        if since < self.oldest {
+            return None;
+        }
+        let start = self.log_index_after(since);
+        let end = self.log_index_after(self.revision);
+        Some(&self.log[start..end.max(start)])
+    }
+
+    /// Discard the changes of all revisions up to `revision`,
+    /// so that patches can only be computed from `revision` onwards.
+    pub fn forget_before(&mut self, revision: Revision) {
+        let revision = revision.min(self.revision);
+        if revision > self.oldest {
+            let end = self.log_index_after(revision);
+            self.log.drain(..end);
+            self.oldest = revision;
+        }
+    }
+
+    /// Write the committed changes made after revision `since` as an [RDF Patch],
+    /// with one transaction per revision.
+    ///
+    /// Fail with [`io::ErrorKind::NotFound`] if the changes since `since` are no longer available
+    /// (see [`forget_before`](#method.forget_before)).
+    ///
+    /// [RDF Patch]: https://afs.github.io/rdf-patch/
+    /// [`io::ErrorKind::NotFound`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.NotFound
+    pub fn write_patch<W: io::Write>(&self, w: &mut W, since: Revision) -> io::Result<()> {
+        let changes = self.changes_since(since).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("changes since revision {} were discarded", since),
+            )
+        })?;
+        let mut current = None;
+        for (revision, change) in changes {
+            if current != Some(*revision) {
+                if current.is_some() {
+                    w.write_all(b"TC .\n")?;
+                }
+                w.write_all(b"TX .\n")?;
+                current = Some(*revision);
+            }
+            match change {
+                Change::Add(quad) => write_row(w, b"A", quad)?,
+                Change::Delete(quad) => write_row(w, b"D", quad)?,
+            }
+        }
+        if current.is_some() {
+            w.write_all(b"TC .\n")?;
+        }
+        Ok(())
+    }
+
+    /// The wrapped dataset.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwrap this dataset, discarding its history.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// The index of the first change of the log made after `revision`.
+    ///
+    /// The log is sorted by revision, so this is a binary search.
+    fn log_index_after(&self, revision: Revision) -> usize {
+        self.log.partition_point(|(r, _)| *r <= revision)
+    }
+
+    fn record(&mut self, change: Change) {
+        self.log.push((self.revision + 1, change));
+    }
+}
+
+impl<D: Dataset> VersionedDataset<D> {
+    /// Write the whole dataset as an [RDF Patch] with a single transaction adding all its quads.
+    ///
+    /// Applied to an empty dataset, this patch brings it to the state of this dataset,
+    /// including changes not committed yet;
+    /// so this should be called right after [`commit`](#method.commit),
+    /// and mirrors can then be updated with [`write_patch`](#method.write_patch)
+    /// since the returned [`revision`](#method.revision).
+    ///
+    /// [RDF Patch]: https://afs.github.io/rdf-patch/
+    pub fn write_snapshot<W: io::Write>(&self, w: &mut W) -> StreamResult<(), D::Error, io::Error> {
+        w.write_all(b"TX .\n").map_err(SinkError)?;
+        for q in self.inner.quads() {
+            let q = q.map_err(SourceError)?;
+            write_row(w, b"A", &copy_quad(&q)).map_err(SinkError)?;
+        }
+        w.write_all(b"TC .\n").map_err(SinkError)
+    }
+}
+
+impl<D: Dataset> Dataset for VersionedDataset<D> {
+    type Quad = D::Quad;
+    type Error = D::Error;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.inner.quads()
+    }
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.inner.quads_with_s(s)
+    }
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.inner.quads_with_p(p)
+    }
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_o(o)
+    }
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_g(g)
+    }
+    fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.inner.quads_with_sp(s, p)
+    }
+    fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_so(s, o)
+    }
+    fn quads_with_sg<'s, TS, TG>(&'s self, s: &'s TS, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_sg(s, g)
+    }
+    fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_po(p, o)
+    }
+    fn quads_with_pg<'s, TP, TG>(&'s self, p: &'s TP, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_pg(p, g)
+    }
+    fn quads_with_og<'s, TO, TG>(&'s self, o: &'s TO, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_og(o, g)
+    }
+    fn quads_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_spo(s, p, o)
+    }
+    fn quads_with_spg<'s, TS, TP, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_spg(s, p, g)
+    }
+    fn quads_with_sog<'s, TS, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_sog(s, o, g)
+    }
+    fn quads_with_pog<'s, TP, TO, TG>(
+        &'s self,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_pog(p, o, g)
+    }
+    fn quads_with_spog<'s, TS, TP, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_spog(s, p, o, g)
+    }
+}
+
+impl<D: MutableDataset> MutableDataset for VersionedDataset<D> {
+    type MutationError = D::MutationError;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let changed = self.inner.insert(s, p, o, g)?;
+        if changed {
+            self.record(Change::Add(copy_terms(s, p, o, g)));
+        }
+        Ok(changed)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let changed = self.inner.remove(s, p, o, g)?;
+        if changed {
+            self.record(Change::Delete(copy_terms(s, p, o, g)));
+        }
+        Ok(changed)
+    }
+}
+
+impl<D: SetDataset> SetDataset for VersionedDataset<D> {}
+
+fn copy_terms<TS, TP, TO, TG>(s: &TS, p: &TP, o: &TO, g: Option<&TG>) -> BoxQuad
+where
+    TS: TTerm + ?Sized,
+    TP: TTerm + ?Sized,
+    TO: TTerm + ?Sized,
+    TG: TTerm + ?Sized,
+{
+    (
+        [BoxTerm::copy(s), BoxTerm::copy(p), BoxTerm::copy(o)],
+        g.map(BoxTerm::copy),
+    )
+}
+
+fn copy_quad<Q: Quad>(q: &Q) -> BoxQuad {
+    copy_terms(q.s(), q.p(), q.o(), q.g())
+}
+
+/// Write an RDF Patch row, e.g. `A <s> <p> <o> <g> .`
+fn write_row<W: io::Write>(w: &mut W, op: &[u8], ([s, p, o], g): &BoxQuad) -> io::Result<()> {
+    w.write_all(op)?;
+    for t in [s, p, o].iter().copied().chain(g.as_ref()) {
+        w.write_all(b" ")?;
+        write_term(w, t)?;
+    }
+    w.write_all(b" .\n")
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use sophia_api::ns::Namespace;
+    use sophia_api::term::CopiableTerm;
+
+    fn patch(d: &VersionedDataset<FastDataset>, since: Revision) -> String {
+        let mut buffer = Vec::new();
+        d.write_patch(&mut buffer, since).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn dataset() -> VersionedDataset<FastDataset> {
+        let tag = Namespace::new("tag:").unwrap();
+        let t_a = tag.get("a").unwrap();
+        let t_b = tag.get("b").unwrap();
+        let t_c = tag.get("c").unwrap();
+        let t_g = tag.get("g").unwrap();
+        let t_p = tag.get("p").unwrap();
+        let t_z = tag.get("z").unwrap();
+
+        let mut d = VersionedDataset::new(FastDataset::new());
+        let none = None as Option<&BoxTerm>;
+        d.insert(&t_a, &t_p, &t_b, none).unwrap();
+        d.insert(&t_b, &t_p, &t_c, none).unwrap();
+        assert_eq!(d.commit(), 1);
+        d.remove(&t_a, &t_p, &t_b, none).unwrap();
+        d.remove(&t_a, &t_p, &t_b, none).unwrap(); // no-op
+        d.insert(&t_a, &t_p, &t_c, Some(&t_g)).unwrap();
+        assert_eq!(d.commit(), 2);
+        assert_eq!(d.commit(), 2);
+        d.insert(&t_z, &t_p, &t_z, none).unwrap();
+        d
+    }
+
+    #[test]
+    fn changes() -> Result<(), Box<dyn std::error::Error>> {
+        let tag = Namespace::new("tag:")?;
+        let t_a: BoxTerm = tag.get("a")?.copied();
+        let t_b: BoxTerm = tag.get("b")?.copied();
+        let t_c: BoxTerm = tag.get("c")?.copied();
+        let t_g: BoxTerm = tag.get("g")?.copied();
+        let t_p: BoxTerm = tag.get("p")?.copied();
+
+        let d = dataset();
+        assert_eq!(d.revision(), 2);
+        assert!(d.has_pending_changes());
+        assert_eq!(d.changes_since(0).unwrap().len(), 4);
+        assert_eq!(
+            d.changes_since(1).unwrap(),
+            &[
+                (2, Change::Delete(([t_a.clone(), t_p.clone(), t_b], None))),
+                (2, Change::Add(([t_a, t_p, t_c], Some(t_g)))),
+            ][..]
+        );
+        assert!(d.changes_since(2).unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn write_patch() {
+        let d = dataset();
+        assert_eq!(
+            patch(&d, 0),
+            "TX .\n\
+             A <tag:a> <tag:p> <tag:b> .\n\
+             A <tag:b> <tag:p> <tag:c> .\n\
+             TC .\n\
+             TX .\n\
+             D <tag:a> <tag:p> <tag:b> .\n\
+             A <tag:a> <tag:p> <tag:c> <tag:g> .\n\
+             TC .\n"
+        );
+        assert_eq!(patch(&d, 2), "");
+    }
+
+    #[test]
+    fn forget_before() {
+        let mut d = dataset();
+        d.forget_before(1);
+        assert!(d.changes_since(0).is_none());
+        assert!(d.write_patch(&mut Vec::<u8>::new(), 0).is_err());
+        assert_eq!(d.changes_since(1).unwrap().len(), 2);
+        // pending changes are kept
+        d.forget_before(5);
+        assert!(d.changes_since(1).is_none());
+        assert_eq!(d.commit(), 3);
+        assert_eq!(patch(&d, 2), "TX .\nA <tag:z> <tag:p> <tag:z> .\nTC .\n");
+    }
+
+    #[test]
+    fn write_snapshot() {
+        let mut d = dataset();
+        d.commit();
+        let mut buffer = Vec::new();
+        d.write_snapshot(&mut buffer).unwrap();
+        let snapshot = String::from_utf8(buffer).unwrap();
+        let mut lines: Vec<_> = snapshot.lines().collect();
+        assert_eq!(lines.remove(0), "TX .");
+        assert_eq!(lines.pop(), Some("TC ."));
+        lines.sort_unstable();
+        assert_eq!(
+            lines,
+            vec![
+                "A <tag:a> <tag:p> <tag:c> <tag:g> .",
+                "A <tag:b> <tag:p> <tag:c> .",
+                "A <tag:z> <tag:p> <tag:z> .",
+            ]
+        );
+    }
+}
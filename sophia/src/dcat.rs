@@ -0,0 +1,526 @@
+//! Build and read [DCAT] catalogs.
+//!
+//! [`Catalog`], [`Dataset`] and [`Distribution`] are plain descriptions,
+//! which can be written into any [`MutableGraph`],
+//! and read back from any [`Graph`].
+//! Only the most commonly used DCAT and Dublin Core properties are supported.
+//!
+//! Media types are written as `dcat:mediaType`,
+//! using the IRIs of the [IANA media types registry]
+//! (e.g. `<https://www.iana.org/assignments/media-types/text/turtle>`).
+//!
+//! # Example
+//! ```
+//! # use sophia::dcat::*;
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::term::BoxTerm;
+//! let turtle = Distribution {
+//!     access_url: Some(BoxTerm::new_iri("http://example.org/data.ttl")?),
+//!     media_type: Some("text/turtle".to_string()),
+//!     ..Distribution::new(BoxTerm::new_iri("http://example.org/data#ttl")?)
+//! };
+//! let dataset = Dataset {
+//!     title: Some("Some data".to_string()),
+//!     distributions: vec![turtle],
+//!     ..Dataset::new(BoxTerm::new_iri("http://example.org/data")?)
+//! };
+//! let catalog = Catalog {
+//!     datasets: vec![dataset],
+//!     ..Catalog::new(BoxTerm::new_iri("http://example.org/catalog")?)
+//! };
+//!
+//! let mut g = FastGraph::new();
+//! catalog.add_to(&mut g)?;
+//! let catalogs = catalogs(&g)?;
+//! assert_eq!(catalogs.len(), 1);
+//! assert_eq!(catalogs[0].datasets[0].title.as_deref(), Some("Some data"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [DCAT]: https://www.w3.org/TR/vocab-dcat-2/
+//! [IANA media types registry]: https://www.iana.org/assignments/media-types/
+//! [`Catalog`]: struct.Catalog.html
+//! [`Dataset`]: struct.Dataset.html
+//! [`Distribution`]: struct.Distribution.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::description::{invalid, objects, single, text, DescriptionError};
+use sophia_api::graph::{Graph, MgResult, MutableGraph};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The DCAT namespace.
+mod dcat_ns {
+    sophia_api::namespace!(
+        "http://www.w3.org/ns/dcat#",
+        Catalog,
+        Dataset,
+        Distribution,
+        dataset,
+        distribution,
+        accessURL,
+        downloadURL,
+        mediaType,
+        byteSize,
+        keyword,
+        landingPage
+    );
+}
+
+/// The Dublin Core terms namespace.
+mod dct {
+    sophia_api::namespace!(
+        "http://purl.org/dc/terms/",
+        title,
+        description,
+        publisher,
+        license
+    );
+}
+
+/// The prefix of the IRIs identifying media types.
+pub const MEDIA_TYPE_PREFIX: &str = "https://www.iana.org/assignments/media-types/";
+
+/// A DCAT catalog (`dcat:Catalog`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+    /// The resource describing the catalog.
+    pub id: BoxTerm,
+    /// The title of the catalog (`dct:title`).
+    pub title: Option<String>,
+    /// The description of the catalog (`dct:description`).
+    pub description: Option<String>,
+    /// The publisher of the catalog (`dct:publisher`).
+    pub publisher: Option<BoxTerm>,
+    /// The datasets of the catalog (`dcat:dataset`).
+    pub datasets: Vec<Dataset>,
+}
+
+impl Catalog {
+    /// Build an empty catalog described by `id`.
+    pub fn new(id: BoxTerm) -> Self {
+        Catalog {
+            id,
+            title: None,
+            description: None,
+            publisher: None,
+            datasets: vec![],
+        }
+    }
+
+    /// Insert the description of this catalog, and of all its datasets, in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &dcat_ns::Catalog)?;
+        insert_text(graph, &self.id, &dct::title, &self.title)?;
+        insert_text(graph, &self.id, &dct::description, &self.description)?;
+        if let Some(publisher) = &self.publisher {
+            graph.insert(&self.id, &dct::publisher, publisher)?;
+        }
+        for dataset in &self.datasets {
+            graph.insert(&self.id, &dcat_ns::dataset, &dataset.id)?;
+            dataset.add_to(graph)?;
+        }
+        Ok(())
+    }
+
+    /// Read the description of catalog `id` from `graph`.
+    ///
+    /// Datasets are sorted by IRI.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> DResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let mut datasets = objects(graph, &id, &dcat_ns::dataset)?
+            .iter()
+            .map(|d| Dataset::from_graph(graph, d))
+            .collect::<Result<Vec<_>, _>>()?;
+        datasets.sort_by(|a, b| a.id.value().cmp(&b.id.value()));
+        Ok(Catalog {
+            title: text(graph, &id, &dct::title)?,
+            description: text(graph, &id, &dct::description)?,
+            publisher: single(graph, &id, &dct::publisher)?,
+            datasets,
+            id,
+        })
+    }
+}
+
+/// A DCAT dataset (`dcat:Dataset`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset {
+    /// The resource describing the dataset.
+    pub id: BoxTerm,
+    /// The title of the dataset (`dct:title`).
+    pub title: Option<String>,
+    /// The description of the dataset (`dct:description`).
+    pub description: Option<String>,
+    /// The keywords of the dataset (`dcat:keyword`).
+    pub keywords: Vec<String>,
+    /// The web page of the dataset (`dcat:landingPage`).
+    pub landing_page: Option<BoxTerm>,
+    /// The license of the dataset (`dct:license`).
+    pub license: Option<BoxTerm>,
+    /// The distributions of the dataset (`dcat:distribution`).
+    pub distributions: Vec<Distribution>,
+}
+
+impl Dataset {
+    /// Build an empty dataset described by `id`.
+    pub fn new(id: BoxTerm) -> Self {
+        Dataset {
+            id,
+            title: None,
+            description: None,
+            keywords: vec![],
+            landing_page: None,
+            license: None,
+            distributions: vec![],
+        }
+    }
+
+    /// Insert the description of this dataset, and of all its distributions, in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &dcat_ns::Dataset)?;
+        insert_text(graph, &self.id, &dct::title, &self.title)?;
+        insert_text(graph, &self.id, &dct::description, &self.description)?;
+        for keyword in &self.keywords {
+            graph.insert(&self.id, &dcat_ns::keyword, &string_literal(keyword))?;
+        }
+        if let Some(page) = &self.landing_page {
+            graph.insert(&self.id, &dcat_ns::landingPage, page)?;
+        }
+        if let Some(license) = &self.license {
+            graph.insert(&self.id, &dct::license, license)?;
+        }
+        for distribution in &self.distributions {
+            graph.insert(&self.id, &dcat_ns::distribution, &distribution.id)?;
+            distribution.add_to(graph)?;
+        }
+        Ok(())
+    }
+
+    /// Read the description of dataset `id` from `graph`.
+    ///
+    /// Keywords are sorted alphabetically, and distributions by IRI.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> DResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let mut keywords = objects(graph, &id, &dcat_ns::keyword)?
+            .iter()
+            .map(|k| literal_value(&id, k))
+            .collect::<Result<Vec<_>, _>>()?;
+        keywords.sort();
+        let mut distributions = objects(graph, &id, &dcat_ns::distribution)?
+            .iter()
+            .map(|d| Distribution::from_graph(graph, d))
+            .collect::<Result<Vec<_>, _>>()?;
+        distributions.sort_by(|a, b| a.id.value().cmp(&b.id.value()));
+        Ok(Dataset {
+            title: text(graph, &id, &dct::title)?,
+            description: text(graph, &id, &dct::description)?,
+            keywords,
+            landing_page: single(graph, &id, &dcat_ns::landingPage)?,
+            license: single(graph, &id, &dct::license)?,
+            distributions,
+            id,
+        })
+    }
+}
+
+/// A DCAT distribution (`dcat:Distribution`), i.e. a way to access a dataset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    /// The resource describing the distribution.
+    pub id: BoxTerm,
+    /// The title of the distribution (`dct:title`).
+    pub title: Option<String>,
+    /// The URL giving access to the distribution (`dcat:accessURL`).
+    pub access_url: Option<BoxTerm>,
+    /// The URL of a downloadable file of the distribution (`dcat:downloadURL`).
+    pub download_url: Option<BoxTerm>,
+    /// The media type of the distribution (`dcat:mediaType`), e.g. `"text/turtle"`.
+    pub media_type: Option<String>,
+    /// The size of the distribution in bytes (`dcat:byteSize`).
+    pub byte_size: Option<u64>,
+}
+
+impl Distribution {
+    /// Build an empty distribution described by `id`.
+    pub fn new(id: BoxTerm) -> Self {
+        Distribution {
+            id,
+            title: None,
+            access_url: None,
+            download_url: None,
+            media_type: None,
+            byte_size: None,
+        }
+    }
+
+    /// Insert the description of this distribution in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &dcat_ns::Distribution)?;
+        insert_text(graph, &self.id, &dct::title, &self.title)?;
+        if let Some(url) = &self.access_url {
+            graph.insert(&self.id, &dcat_ns::accessURL, url)?;
+        }
+        if let Some(url) = &self.download_url {
+            graph.insert(&self.id, &dcat_ns::downloadURL, url)?;
+        }
+        if let Some(media_type) = &self.media_type {
+            let iri = BoxTerm::new_iri_suffixed_unchecked(MEDIA_TYPE_PREFIX, media_type.as_str());
+            graph.insert(&self.id, &dcat_ns::mediaType, &iri)?;
+        }
+        if let Some(size) = self.byte_size {
+            let size = BoxTerm::new_literal_dt_unchecked(size.to_string(), xsd::nonNegativeInteger);
+            graph.insert(&self.id, &dcat_ns::byteSize, &size)?;
+        }
+        Ok(())
+    }
+
+    /// Read the description of distribution `id` from `graph`.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> DResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let media_type = match single(graph, &id, &dcat_ns::mediaType)? {
+            Some(t) => Some(media_type(&id, &t)?),
+            None => None,
+        };
+        let byte_size = match single(graph, &id, &dcat_ns::byteSize)? {
+            Some(t) => {
+                let value = literal_value(&id, &t)?;
+                // DCAT 1 used xsd:decimal, so "1234.0" is also accepted
+                let integer = value.strip_suffix(".0").unwrap_or(&value);
+                Some(
+                    integer
+                        .parse()
+                        .map_err(|_| invalid(&id, format!("invalid byte size {}", t)))?,
+                )
+            }
+            None => None,
+        };
+        Ok(Distribution {
+            title: text(graph, &id, &dct::title)?,
+            access_url: single(graph, &id, &dcat_ns::accessURL)?,
+            download_url: single(graph, &id, &dcat_ns::downloadURL)?,
+            media_type,
+            byte_size,
+            id,
+        })
+    }
+}
+
+/// The error raised when reading DCAT descriptions.
+#[derive(Debug, Error)]
+pub enum DcatError<E: 'static + Error> {
+    /// The graph raised an error.
+    #[error("Error in the graph: {0}")]
+    Graph(#[source] E),
+    /// A description is not well-formed.
+    #[error("Invalid description of {resource}: {message}")]
+    Invalid {
+        /// The offending resource.
+        resource: String,
+        /// The reason why its description is invalid.
+        message: String,
+    },
+}
+
+type DResult<G, T> = Result<T, DcatError<<G as Graph>::Error>>;
+
+impl<E: 'static + Error> From<DescriptionError<E>> for DcatError<E> {
+    fn from(err: DescriptionError<E>) -> Self {
+        match err {
+            DescriptionError::Graph(err) => DcatError::Graph(err),
+            DescriptionError::Invalid { resource, message } => {
+                DcatError::Invalid { resource, message }
+            }
+        }
+    }
+}
+
+/// Read all the catalogs (instances of `dcat:Catalog`) described in `graph`,
+/// sorted by IRI.
+pub fn catalogs<G: Graph>(graph: &G) -> DResult<G, Vec<Catalog>> {
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    for t in graph.triples_with_po(&rdf::type_, &dcat_ns::Catalog) {
+        let id = BoxTerm::copy(t.map_err(DcatError::Graph)?.s());
+        if seen.insert(id.clone()) {
+            ids.push(id);
+        }
+    }
+    ids.sort_by(|a, b| a.value().cmp(&b.value()));
+    ids.iter()
+        .map(|id| Catalog::from_graph(graph, id))
+        .collect()
+}
+
+fn string_literal(txt: &str) -> BoxTerm {
+    BoxTerm::new_literal_dt_unchecked(txt, xsd::string)
+}
+
+fn insert_text<G, T>(graph: &mut G, s: &BoxTerm, p: &T, txt: &Option<String>) -> MgResult<G, ()>
+where
+    G: MutableGraph,
+    T: TTerm + ?Sized,
+{
+    if let Some(txt) = txt {
+        graph.insert(s, p, &string_literal(txt))?;
+    }
+    Ok(())
+}
+
+fn literal_value<E: 'static + Error>(s: &BoxTerm, t: &BoxTerm) -> Result<String, DcatError<E>> {
+    match t.kind() {
+        TermKind::Literal => Ok(t.value().to_string()),
+        _ => Err(invalid(s, format!("expected a literal, got {}", t)).into()),
+    }
+}
+
+/// The media type identified by `t`, either as an IRI or a literal.
+fn media_type<E: 'static + Error>(s: &BoxTerm, t: &BoxTerm) -> Result<String, DcatError<E>> {
+    let value = t.value();
+    match t.kind() {
+        TermKind::Literal => Ok(value.to_string()),
+        TermKind::Iri => value
+            .strip_prefix(MEDIA_TYPE_PREFIX)
+            .or_else(|| value.strip_prefix("http://www.iana.org/assignments/media-types/"))
+            .map(str::to_string)
+            .ok_or_else(|| invalid(s, format!("unknown media type {}", t)).into()),
+        _ => Err(invalid(s, format!("invalid media type {}", t)).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::triple::stream::TripleSource;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", txt)).unwrap()
+    }
+
+    fn example() -> Catalog {
+        let ttl = Distribution {
+            title: Some("Turtle dump".to_string()),
+            download_url: Some(iri("dump.ttl")),
+            media_type: Some("text/turtle".to_string()),
+            byte_size: Some(1234),
+            ..Distribution::new(iri("dump-ttl"))
+        };
+        let sparql = Distribution {
+            access_url: Some(iri("sparql")),
+            ..Distribution::new(iri("dump-sparql"))
+        };
+        let dataset = Dataset {
+            title: Some("Dump".to_string()),
+            description: Some("All the data".to_string()),
+            keywords: vec!["linked data".to_string(), "rdf".to_string()],
+            landing_page: Some(iri("")),
+            license: Some(BoxTerm::new_iri("http://creativecommons.org/licenses/by/4.0/").unwrap()),
+            distributions: vec![sparql, ttl],
+            ..Dataset::new(iri("dump"))
+        };
+        Catalog {
+            title: Some("Example catalog".to_string()),
+            publisher: Some(iri("me")),
+            datasets: vec![dataset],
+            ..Catalog::new(iri("catalog"))
+        }
+    }
+
+    #[test]
+    fn write() -> Res {
+        let mut g = FastGraph::new();
+        example().add_to(&mut g)?;
+        let expected: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#>.
+            @prefix dct: <http://purl.org/dc/terms/>.
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            @prefix : <http://example.org/>.
+
+            :catalog a dcat:Catalog;
+                dct:title "Example catalog";
+                dct:publisher :me;
+                dcat:dataset :dump.
+
+            :dump a dcat:Dataset;
+                dct:title "Dump";
+                dct:description "All the data";
+                dcat:keyword "linked data", "rdf";
+                dcat:landingPage :;
+                dct:license <http://creativecommons.org/licenses/by/4.0/>;
+                dcat:distribution :dump-ttl, :dump-sparql.
+
+            :dump-ttl a dcat:Distribution;
+                dct:title "Turtle dump";
+                dcat:downloadURL <http://example.org/dump.ttl>;
+                dcat:mediaType <https://www.iana.org/assignments/media-types/text/turtle>;
+                dcat:byteSize "1234"^^xsd:nonNegativeInteger.
+
+            :dump-sparql a dcat:Distribution;
+                dcat:accessURL :sparql.
+            "#,
+        )
+        .collect_triples()?;
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Res {
+        let mut g = FastGraph::new();
+        let catalog = example();
+        catalog.add_to(&mut g)?;
+        assert_eq!(Catalog::from_graph(&g, &catalog.id)?, catalog);
+        assert_eq!(catalogs(&g)?, vec![catalog]);
+        Ok(())
+    }
+
+    #[test]
+    fn read_legacy() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#>.
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            @prefix : <http://example.org/>.
+
+            :d dcat:mediaType "application/n-triples";
+                dcat:byteSize "42.0"^^xsd:decimal.
+            :e dcat:mediaType <http://www.iana.org/assignments/media-types/application/ld+json>.
+            :f dcat:mediaType <http://example.org/turtle>.
+            :g dcat:byteSize "big".
+            "#,
+        )
+        .collect_triples()?;
+        let d = Distribution::from_graph(&g, &iri("d"))?;
+        assert_eq!(d.media_type.as_deref(), Some("application/n-triples"));
+        assert_eq!(d.byte_size, Some(42));
+        let e = Distribution::from_graph(&g, &iri("e"))?;
+        assert_eq!(e.media_type.as_deref(), Some("application/ld+json"));
+        assert!(Distribution::from_graph(&g, &iri("f")).is_err());
+        assert!(Distribution::from_graph(&g, &iri("g")).is_err());
+        Ok(())
+    }
+}
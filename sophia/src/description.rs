@@ -0,0 +1,84 @@
+//! Helpers for reading the description of resources in a graph,
+//! shared by the modules dedicated to a vocabulary (SHACL, DCAT, Web Annotation...).
+//!
+//! Each of these modules has its own error type,
+//! which is built from [`DescriptionError`] by the `?` operator.
+//!
+//! This module is public only so that the other crates of Sophia
+//! (e.g. `sophia_jsonld`) can use it; it is not part of the stable API.
+//!
+//! [`DescriptionError`]: enum.DescriptionError.html
+
+use sophia_api::graph::Graph;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// An error raised while reading a description.
+#[derive(Debug)]
+pub enum DescriptionError<E> {
+    /// The graph raised an error.
+    Graph(E),
+    /// A description is not well-formed.
+    Invalid {
+        /// The offending resource.
+        resource: String,
+        /// The reason why its description is invalid.
+        message: String,
+    },
+}
+
+/// The result of reading a description in a graph of type `G`.
+pub type DescResult<G, T> = Result<T, DescriptionError<<G as Graph>::Error>>;
+
+/// The error reporting that the description of `resource` is invalid.
+pub fn invalid<E, M: Into<String>>(resource: &BoxTerm, message: M) -> DescriptionError<E> {
+    DescriptionError::Invalid {
+        resource: resource.to_string(),
+        message: message.into(),
+    }
+}
+
+/// All the values of `p` for `s`.
+pub fn objects<G, T>(g: &G, s: &BoxTerm, p: &T) -> DescResult<G, Vec<BoxTerm>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    g.triples_with_sp(s, p)
+        .map(|t| {
+            t.map(|t| BoxTerm::copy(t.o()))
+                .map_err(DescriptionError::Graph)
+        })
+        .collect()
+}
+
+/// The value of `p` for `s`, which must be unique if present.
+pub fn single<G, T>(g: &G, s: &BoxTerm, p: &T) -> DescResult<G, Option<BoxTerm>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    let mut values = objects(g, s, p)?;
+    if values.len() > 1 {
+        return Err(invalid(s, format!("several values for <{}>", p.value())));
+    }
+    Ok(values.pop())
+}
+
+/// The textual value of `p` for `s`, if any.
+///
+/// If there are several values (e.g. in different languages),
+/// the one without a language tag is preferred.
+pub fn text<G, T>(g: &G, s: &BoxTerm, p: &T) -> DescResult<G, Option<String>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    let values = objects(g, s, p)?;
+    let value = values
+        .iter()
+        .filter(|v| v.kind() == TermKind::Literal)
+        .min_by_key(|v| (v.language().is_some(), v.value().to_string()));
+    Ok(value.map(|v| v.value().to_string()))
+}
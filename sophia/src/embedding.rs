@@ -0,0 +1,400 @@
+//! Export datasets for knowledge-graph embedding toolkits.
+//!
+//! Knowledge-graph embedding models (TransE, DistMult, RotatE...)
+//! are trained on *(head, relation, tail)* triples,
+//! where entities and relations are replaced by integer identifiers.
+//! An [`Exporter`] builds such triples from a [`Dataset`],
+//! along with the dictionaries mapping identifiers back to terms,
+//! and splits them into a training, a validation and a test set.
+//! The resulting [`Embedding`] can then be written in the file layout
+//! expected by [OpenKE] or [DGL-KE] (which is also accepted by [PyKEEN]).
+//!
+//! Entities and relations are named after their N-Triples representation.
+//! Graph names are ignored, and a triple present in several graphs is exported only once.
+//!
+//! # Example
+//! ```
+//! # use sophia::embedding::Exporter;
+//! # use sophia::term::BoxTerm;
+//! let ex = |s: &str| BoxTerm::new_iri(format!("http://example.org/{}", s));
+//! let d = vec![
+//!     ([ex("alice")?, ex("knows")?, ex("bob")?], None),
+//!     ([ex("bob")?, ex("knows")?, ex("carol")?], Some(ex("g")?)),
+//!     ([ex("carol")?, ex("name")?, BoxTerm::from("Carol".to_string())], None),
+//! ];
+//! let exporter = Exporter {
+//!     valid_ratio: 0.0,
+//!     test_ratio: 0.5,
+//!     ..Exporter::default()
+//! };
+//! let embedding = exporter.export(&d)?;
+//! assert_eq!(embedding.entities.len(), 3); // literals are ignored by default
+//! assert_eq!(embedding.relations.len(), 1);
+//! assert_eq!(embedding.train.len() + embedding.test.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Exporter`]: struct.Exporter.html
+//! [`Embedding`]: struct.Embedding.html
+//! [`Dataset`]: ../dataset/trait.Dataset.html
+//! [OpenKE]: https://github.com/thunlp/OpenKE
+//! [DGL-KE]: https://aws-dglke.readthedocs.io/en/latest/train_user_data.html
+//! [PyKEEN]: https://pykeen.readthedocs.io/
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use sophia_api::dataset::Dataset;
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+
+use crate::generator::SplitMix64;
+use crate::serializer::nt::write_term;
+
+/// The options for exporting a dataset as integer triples.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Exporter {
+    /// The seed of the pseudo-random number generator used to split the triples.
+    pub seed: u64,
+    /// The proportion (between 0 and 1) of triples that go to the validation set.
+    pub valid_ratio: f64,
+    /// The proportion (between 0 and 1) of triples that go to the test set.
+    pub test_ratio: f64,
+    /// Whether triples whose object is a literal are exported
+    /// (literals are then considered as entities).
+    pub literals: bool,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Exporter {
+            seed: 0,
+            valid_ratio: 0.1,
+            test_ratio: 0.1,
+            literals: false,
+        }
+    }
+}
+
+impl Exporter {
+    /// Build the integer triples of `dataset`, and split them.
+    ///
+    /// Identifiers are assigned in the order in which terms are encountered,
+    /// so for a given seed, the result only depends on the order of the quads of `dataset`.
+    ///
+    /// # Panics
+    ///
+    /// If `valid_ratio` or `test_ratio` is negative,
+    /// or if their sum is greater than 1.
+    pub fn export<D: Dataset>(&self, dataset: &D) -> Result<Embedding, D::Error> {
+        assert!(
+            self.valid_ratio >= 0.0
+                && self.test_ratio >= 0.0
+                && self.valid_ratio + self.test_ratio <= 1.0,
+            "invalid ratios"
+        );
+        let mut entities = Dictionary::default();
+        let mut relations = Dictionary::default();
+        let mut seen = HashSet::new();
+        let mut triples = vec![];
+        for q in dataset.quads() {
+            let q = q?;
+            if !self.literals && q.o().kind() == TermKind::Literal {
+                continue;
+            }
+            let triple = [entities.id(q.s()), relations.id(q.p()), entities.id(q.o())];
+            if seen.insert(triple) {
+                triples.push(triple);
+            }
+        }
+
+        // Fisher-Yates shuffle
+        let mut rng = SplitMix64(self.seed);
+        for i in (1..triples.len()).rev() {
+            triples.swap(i, rng.below(i + 1));
+        }
+        let len = triples.len() as f64;
+        let valid_len = (len * self.valid_ratio).round() as usize;
+        let test_len = ((len * self.test_ratio).round() as usize).min(triples.len() - valid_len);
+        let test = triples.split_off(triples.len() - test_len);
+        let valid = triples.split_off(triples.len() - valid_len);
+        Ok(Embedding {
+            entities: entities.terms,
+            relations: relations.terms,
+            train: triples,
+            valid,
+            test,
+        })
+    }
+}
+
+/// Integer triples produced by an [`Exporter`](struct.Exporter.html).
+///
+/// Each triple is a `[head, relation, tail]` array of identifiers,
+/// which are indices in `entities` and `relations`.
+///
+/// NB: some entities or relations of the validation and test sets
+/// may not appear in the training set.
+#[derive(Clone, Debug)]
+pub struct Embedding {
+    /// The term of each entity identifier.
+    pub entities: Vec<BoxTerm>,
+    /// The term of each relation identifier.
+    pub relations: Vec<BoxTerm>,
+    /// The training set.
+    pub train: Vec<[usize; 3]>,
+    /// The validation set.
+    pub valid: Vec<[usize; 3]>,
+    /// The test set.
+    pub test: Vec<[usize; 3]>,
+}
+
+impl Embedding {
+    /// Write this embedding in directory `dir` (which must exist), in the [OpenKE] layout.
+    ///
+    /// The files `entity2id.txt` and `relation2id.txt` contain the number of entries,
+    /// then one `name<TAB>id` line per entry.
+    /// The files `train2id.txt`, `valid2id.txt` and `test2id.txt`
+    /// contain the number of triples, then one `head tail relation` line per triple
+    /// (NB: OpenKE puts the relation last).
+    ///
+    /// [OpenKE]: https://github.com/thunlp/OpenKE
+    pub fn write_openke<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        for (file, terms) in &[
+            ("entity2id.txt", &self.entities),
+            ("relation2id.txt", &self.relations),
+        ] {
+            let mut w = BufWriter::new(File::create(dir.join(file))?);
+            writeln!(w, "{}", terms.len())?;
+            for (id, t) in terms.iter().enumerate() {
+                writeln!(w, "{}\t{}", name(t)?, id)?;
+            }
+            w.flush()?;
+        }
+        for (file, triples) in &[
+            ("train2id.txt", &self.train),
+            ("valid2id.txt", &self.valid),
+            ("test2id.txt", &self.test),
+        ] {
+            let mut w = BufWriter::new(File::create(dir.join(file))?);
+            writeln!(w, "{}", triples.len())?;
+            for [h, r, t] in triples.iter() {
+                writeln!(w, "{} {} {}", h, t, r)?;
+            }
+            w.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write this embedding in directory `dir` (which must exist), in the [DGL-KE] layout
+    /// for user-defined datasets with raw identifiers (`udd_hrt`).
+    ///
+    /// The files `entities.dict` and `relations.dict` contain one `id<TAB>name` line per entry.
+    /// The files `train.tsv`, `valid.tsv` and `test.tsv`
+    /// contain one `head<TAB>relation<TAB>tail` line per triple.
+    ///
+    /// [DGL-KE]: https://aws-dglke.readthedocs.io/en/latest/train_user_data.html
+    pub fn write_dglke<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        for (file, terms) in &[
+            ("entities.dict", &self.entities),
+            ("relations.dict", &self.relations),
+        ] {
+            let mut w = BufWriter::new(File::create(dir.join(file))?);
+            for (id, t) in terms.iter().enumerate() {
+                writeln!(w, "{}\t{}", id, name(t)?)?;
+            }
+            w.flush()?;
+        }
+        for (file, triples) in &[
+            ("train.tsv", &self.train),
+            ("valid.tsv", &self.valid),
+            ("test.tsv", &self.test),
+        ] {
+            let mut w = BufWriter::new(File::create(dir.join(file))?);
+            for [h, r, t] in triples.iter() {
+                writeln!(w, "{}\t{}\t{}", h, r, t)?;
+            }
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Assigns consecutive identifiers to terms.
+#[derive(Default)]
+struct Dictionary {
+    ids: HashMap<BoxTerm, usize>,
+    terms: Vec<BoxTerm>,
+}
+
+impl Dictionary {
+    fn id<T: TTerm + ?Sized>(&mut self, t: &T) -> usize {
+        let t = BoxTerm::copy(t);
+        if let Some(id) = self.ids.get(&t) {
+            return *id;
+        }
+        let id = self.terms.len();
+        self.terms.push(t.clone());
+        self.ids.insert(t, id);
+        id
+    }
+}
+
+/// The N-Triples representation of `t`, without any tab.
+fn name(t: &BoxTerm) -> io::Result<String> {
+    let mut buf = vec![];
+    write_term(&mut buf, t)?;
+    // tabs are only possible in literals, where N-Triples allows to escape them
+    Ok(String::from_utf8(buf).unwrap().replace('\t', "\\t"))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::Namespace;
+    use std::fs;
+
+    fn some_dataset() -> Vec<([BoxTerm; 3], Option<BoxTerm>)> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let x = |suffix: &str| BoxTerm::copy(&ex.get(suffix).unwrap());
+        let mut d: Vec<_> = (0..100)
+            .map(|i| {
+                (
+                    [x(&format!("n{}", i)), x("next"), x(&format!("n{}", i + 1))],
+                    None,
+                )
+            })
+            .collect();
+        d.push(([x("n0"), x("next"), x("n1")], Some(x("g"))));
+        d.push((
+            [x("n0"), x("label"), BoxTerm::from("zero\tnull".to_string())],
+            None,
+        ));
+        d
+    }
+
+    #[test]
+    fn split() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let next = ex.get("next")?;
+        let d = some_dataset();
+        let exporter = Exporter {
+            valid_ratio: 0.2,
+            test_ratio: 0.15,
+            ..Exporter::default()
+        };
+        let e = exporter.export(&d)?;
+        assert_eq!(e.entities.len(), 101);
+        assert_eq!(e.relations.len(), 1);
+        assert_eq!(e.train.len(), 65);
+        assert_eq!(e.valid.len(), 20);
+        assert_eq!(e.test.len(), 15);
+        let mut all: Vec<_> = e.train.iter().chain(&e.valid).chain(&e.test).collect();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 100);
+        for [h, r, t] in all {
+            assert!(e.relations[*r] == next);
+            assert_eq!(
+                e.entities[*t]
+                    .value()
+                    .trim_start_matches("http://example.org/n"),
+                (e.entities[*h]
+                    .value()
+                    .trim_start_matches("http://example.org/n")
+                    .parse::<usize>()?
+                    + 1)
+                .to_string()
+            );
+        }
+
+        // deterministic for a given seed
+        assert_eq!(exporter.export(&d)?.test, e.test);
+        let other = Exporter {
+            seed: 42,
+            ..exporter
+        };
+        assert_ne!(other.export(&d)?.test, e.test);
+        Ok(())
+    }
+
+    #[test]
+    fn literals() -> Result<(), Box<dyn std::error::Error>> {
+        let exporter = Exporter {
+            valid_ratio: 0.0,
+            test_ratio: 0.0,
+            literals: true,
+            ..Exporter::default()
+        };
+        let e = exporter.export(&some_dataset())?;
+        assert_eq!(e.entities.len(), 102);
+        assert_eq!(e.relations.len(), 2);
+        assert_eq!(e.train.len(), 101);
+        assert!(e.valid.is_empty() && e.test.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_ratios() {
+        let exporter = Exporter {
+            valid_ratio: 0.6,
+            test_ratio: 0.6,
+            ..Exporter::default()
+        };
+        let _ = exporter.export(&some_dataset());
+    }
+
+    #[test]
+    fn files() -> Result<(), Box<dyn std::error::Error>> {
+        let exporter = Exporter {
+            literals: true,
+            ..Exporter::default()
+        };
+        let e = exporter.export(&some_dataset())?;
+        let dir = std::env::temp_dir().join(format!("sophia_embedding_{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+
+        e.write_openke(&dir)?;
+        let entities = fs::read_to_string(dir.join("entity2id.txt"))?;
+        let mut lines = entities.lines();
+        assert_eq!(lines.next(), Some("102"));
+        assert_eq!(lines.next(), Some("<http://example.org/n0>\t0"));
+        assert!(entities.contains("\"zero\\tnull\"\t"));
+        let train = fs::read_to_string(dir.join("train2id.txt"))?;
+        assert_eq!(train.lines().count(), e.train.len() + 1);
+        let [h, r, t] = e.train[0];
+        assert_eq!(
+            train.lines().nth(1),
+            Some(&format!("{} {} {}", h, t, r)[..])
+        );
+
+        e.write_dglke(&dir)?;
+        let relations = fs::read_to_string(dir.join("relations.dict"))?;
+        assert_eq!(
+            relations,
+            "0\t<http://example.org/next>\n1\t<http://example.org/label>\n"
+        );
+        let test = fs::read_to_string(dir.join("test.tsv"))?;
+        let [h, r, t] = e.test[0];
+        assert_eq!(
+            test.lines().next(),
+            Some(&format!("{}\t{}\t{}", h, r, t)[..])
+        );
+        assert_eq!(test.lines().count(), e.test.len());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
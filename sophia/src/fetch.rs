@@ -0,0 +1,256 @@
+//! The fetch layer, dereferencing IRIs over HTTP.
+//!
+//! This crate does not include an HTTP client;
+//! instead, the [`HttpClient`] trait can be implemented on top of any client library.
+//! Clients can then be wrapped in a [`PoliteClient`],
+//! which enforces a [politeness policy](polite/index.html)
+//! (robots.txt, per-host delays and parallelism, retries, conditional requests),
+//! and turned into a [`Fetcher`] for the [crawler](../crawl/index.html)
+//! with [`HttpFetcher`].
+//!
+//! [`HttpClient`]: trait.HttpClient.html
+//! [`PoliteClient`]: polite/struct.PoliteClient.html
+//! [`Fetcher`]: ../crawl/trait.Fetcher.html
+//! [`HttpFetcher`]: struct.HttpFetcher.html
+
+use std::error::Error;
+
+use sophia_api::parser::TripleParser;
+use sophia_api::triple::stream::TripleSource;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::crawl::{Document, Fetcher};
+
+pub mod cache;
+pub mod polite;
+
+/// The `Accept` header sent by [`HttpFetcher`](struct.HttpFetcher.html).
+pub const ACCEPT: &str = if cfg!(feature = "xml") {
+    "text/turtle, application/n-triples;q=0.9, application/rdf+xml;q=0.8"
+} else {
+    "text/turtle, application/n-triples;q=0.9"
+};
+
+/// The response to an HTTP request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Response {
+    /// The status code of the response.
+    pub status: u16,
+    /// The URL of the response, after redirections.
+    pub url: String,
+    /// The headers of the response.
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The value of header `name` (case-insensitive), if any.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the status code of this response is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The media type of the response (lowercased, without parameters), if any.
+    pub fn media_type(&self) -> Option<String> {
+        self.header("content-type")
+            .map(|ct| ct.split(';').next().unwrap().trim().to_ascii_lowercase())
+    }
+}
+
+/// An HTTP client, as used by the fetch layer.
+///
+/// Methods take `&self`, so that a client can be shared between threads.
+pub trait HttpClient {
+    /// The error raised when a request fails (excluding HTTP error statuses).
+    type Error: 'static + Error;
+
+    /// Perform a GET request on `url` with the given extra `headers`,
+    /// following redirections.
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Self::Error>;
+}
+
+impl<C: HttpClient + ?Sized> HttpClient for &C {
+    type Error = C::Error;
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Self::Error> {
+        (*self).get(url, headers)
+    }
+}
+
+/// A [`Fetcher`](../crawl/trait.Fetcher.html) dereferencing IRIs with an [`HttpClient`],
+/// and parsing the responses according to their media type.
+///
+/// [`HttpClient`]: trait.HttpClient.html
+#[derive(Clone, Debug)]
+pub struct HttpFetcher<C> {
+    /// The underlying HTTP client.
+    pub client: C,
+}
+
+impl<C: HttpClient> HttpFetcher<C> {
+    /// Build a fetcher using `client`.
+    pub fn new(client: C) -> Self {
+        HttpFetcher { client }
+    }
+}
+
+impl<C: HttpClient> Fetcher for HttpFetcher<C> {
+    type Error = FetchError<C::Error>;
+
+    fn fetch(&mut self, iri: &str) -> Result<Document, Self::Error> {
+        let response = self
+            .client
+            .get(iri, &[("Accept", ACCEPT)])
+            .map_err(FetchError::Client)?;
+        if !response.is_success() {
+            return Err(FetchError::Status(response.status));
+        }
+        let base = Some(response.url.clone());
+        let body = &response.body[..];
+        let triples = match response.media_type().as_deref() {
+            Some("text/turtle") => {
+                collect(crate::parser::turtle::TurtleParser { base }.parse(body))
+            }
+            Some("application/n-triples") => collect(crate::parser::nt::parse_bufread(body)),
+            #[cfg(feature = "xml")]
+            Some("application/rdf+xml") => {
+                collect(crate::parser::xml::RdfXmlParser { base }.parse(body))
+            }
+            other => return Err(FetchError::UnsupportedMediaType(other.map(str::to_string))),
+        }?;
+        Ok(Document {
+            iri: response.url,
+            triples,
+        })
+    }
+}
+
+fn collect<TS, E>(triples: TS) -> Result<Vec<[BoxTerm; 3]>, FetchError<E>>
+where
+    TS: TripleSource,
+    E: 'static + Error,
+{
+    triples
+        .collect_triples()
+        .map_err(|e| FetchError::Parse(e.to_string()))
+}
+
+/// The error raised by [`HttpFetcher`](struct.HttpFetcher.html).
+#[derive(Debug, Error)]
+pub enum FetchError<E: 'static + Error> {
+    /// The HTTP client failed.
+    #[error("HTTP client error: {0}")]
+    Client(#[source] E),
+    /// The server responded with an error status.
+    #[error("HTTP status {0}")]
+    Status(u16),
+    /// The media type of the response is not supported.
+    #[error("Unsupported media type {0:?}")]
+    UnsupportedMediaType(Option<String>),
+    /// The response could not be parsed.
+    #[error("Parse error: {0}")]
+    Parse(String),
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use sophia_api::term::TTerm;
+    use std::collections::HashMap;
+    use std::io;
+    use std::sync::Mutex;
+
+    /// A request received by [`MockClient`]: its URL and headers.
+    pub type LoggedRequest = (String, Vec<(String, String)>);
+
+    /// A client serving canned responses, and logging the requests it receives.
+    #[derive(Debug, Default)]
+    pub struct MockClient {
+        pub responses: Mutex<HashMap<String, Vec<Response>>>,
+        pub log: Mutex<Vec<LoggedRequest>>,
+    }
+
+    impl MockClient {
+        /// Add a response for `url`; several responses for the same URL are served in order,
+        /// and the last one is repeated.
+        pub fn add(&self, url: &str, status: u16, headers: &[(&str, &str)], body: &str) {
+            let response = Response {
+                status,
+                url: url.to_string(),
+                headers: headers
+                    .iter()
+                    .map(|(n, v)| (n.to_string(), v.to_string()))
+                    .collect(),
+                body: body.as_bytes().to_vec(),
+            };
+            let mut responses = self.responses.lock().unwrap();
+            responses.entry(url.to_string()).or_default().push(response);
+        }
+
+        pub fn requested(&self) -> Vec<String> {
+            let log = self.log.lock().unwrap();
+            log.iter().map(|(url, _)| url.clone()).collect()
+        }
+    }
+
+    impl HttpClient for MockClient {
+        type Error = io::Error;
+        fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, io::Error> {
+            let headers = headers
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect();
+            self.log.lock().unwrap().push((url.to_string(), headers));
+            let mut responses = self.responses.lock().unwrap();
+            let queue = responses
+                .get_mut(url)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, url.to_string()))?;
+            if queue.len() > 1 {
+                Ok(queue.remove(0))
+            } else {
+                Ok(queue[0].clone())
+            }
+        }
+    }
+
+    #[test]
+    fn http_fetcher() {
+        let client = MockClient::default();
+        client.add(
+            "http://ex.org/a",
+            200,
+            &[("Content-Type", "text/turtle; charset=utf-8")],
+            "<#it> a <b>.",
+        );
+        client.add("http://ex.org/b", 200, &[("Content-Type", "text/html")], "");
+        client.add("http://ex.org/c", 404, &[], "");
+        let mut fetcher = HttpFetcher::new(&client);
+
+        let doc = fetcher.fetch("http://ex.org/a").unwrap();
+        assert_eq!(doc.triples.len(), 1);
+        assert_eq!(&doc.triples[0][0].value()[..], "http://ex.org/a#it");
+        assert_eq!(&doc.triples[0][2].value()[..], "http://ex.org/b");
+        assert_eq!(client.log.lock().unwrap()[0].1[0].1, ACCEPT);
+
+        assert!(matches!(
+            fetcher.fetch("http://ex.org/b"),
+            Err(FetchError::UnsupportedMediaType(Some(_)))
+        ));
+        assert!(matches!(
+            fetcher.fetch("http://ex.org/c"),
+            Err(FetchError::Status(404))
+        ));
+        assert!(matches!(
+            fetcher.fetch("http://ex.org/d"),
+            Err(FetchError::Client(_))
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+//! Caches of HTTP responses, used for conditional requests.
+//!
+//! A [`PoliteClient`](../polite/struct.PoliteClient.html) stores in its cache
+//! the responses carrying a *validator* (`ETag` or `Last-Modified` header),
+//! and revalidates them with `If-None-Match` or `If-Modified-Since`
+//! when the same URL is requested again.
+//!
+//! A [`DiskCache`] persists responses across runs,
+//! and can be shared by several clients (or processes) dereferencing the same documents,
+//! so that they are not downloaded again as long as they have not changed.
+//!
+//! [`DiskCache`]: struct.DiskCache.html
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::Response;
+
+/// A store of HTTP responses, indexed by URL.
+///
+/// Methods take `&self`, so that a cache can be shared between threads.
+pub trait ResponseCache {
+    /// The response previously stored for `url`, if any.
+    fn get(&self, url: &str) -> Option<Response>;
+
+    /// Store `response` for `url`, replacing any previous one.
+    fn put(&self, url: &str, response: &Response);
+}
+
+/// A [`ResponseCache`](trait.ResponseCache.html) storing nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoCache;
+
+impl ResponseCache for NoCache {
+    fn get(&self, _url: &str) -> Option<Response> {
+        None
+    }
+
+    fn put(&self, _url: &str, _response: &Response) {}
+}
+
+/// A [`ResponseCache`](trait.ResponseCache.html) storing responses in memory.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    responses: Mutex<HashMap<String, Response>>,
+}
+
+impl MemoryCache {
+    /// Build an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of responses in this cache.
+    pub fn len(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+
+    /// Whether this cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for MemoryCache {
+    fn get(&self, url: &str) -> Option<Response> {
+        self.responses.lock().unwrap().get(url).cloned()
+    }
+
+    fn put(&self, url: &str, response: &Response) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), response.clone());
+    }
+}
+
+/// A [`ResponseCache`](trait.ResponseCache.html) storing responses as files in a directory.
+///
+/// Each response is stored in a file named after a hash of its URL,
+/// containing the URL, the status, the headers and the body of the response.
+/// Files are written atomically,
+/// so the same directory can safely be used by several caches at the same time.
+///
+/// Storage errors are not reported, as a cache is only an optimization:
+/// a response that can not be stored or read back is simply fetched again.
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Build a cache storing its files in `dir`, creating it if necessary.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskCache { dir })
+    }
+
+    /// The directory where this cache stores its files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Remove the response stored for `url`, if any.
+    pub fn remove(&self, url: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(url)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove all the responses stored in this cache.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.{}", fnv1a(url.as_bytes()), EXTENSION))
+    }
+
+    fn read(&self, url: &str) -> io::Result<Option<Response>> {
+        let file = match fs::File::open(self.path(url)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut reader = io::BufReader::new(file);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line.trim_end_matches('\n') != url {
+            // hash collision
+            return Ok(None);
+        }
+        line.clear();
+        reader.read_line(&mut line)?;
+        let status = line.trim_end().parse().map_err(invalid_data)?;
+        let mut headers = vec![];
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let header = line.trim_end_matches('\n');
+            if header.is_empty() {
+                break;
+            }
+            let mut parts = header.splitn(2, ": ");
+            let name = parts.next().unwrap().to_string();
+            let value = parts
+                .next()
+                .ok_or_else(|| invalid_data(header))?
+                .to_string();
+            headers.push((name, value));
+        }
+        let mut body = vec![];
+        reader.read_to_end(&mut body)?;
+        Ok(Some(Response {
+            status,
+            url: url.to_string(),
+            headers,
+            body,
+        }))
+    }
+
+    fn write(&self, url: &str, response: &Response) -> io::Result<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = self.path(url);
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = write_response(&tmp, url, response).and_then(|_| fs::rename(&tmp, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+}
+
+impl ResponseCache for DiskCache {
+    fn get(&self, url: &str) -> Option<Response> {
+        self.read(url).ok().flatten()
+    }
+
+    fn put(&self, url: &str, response: &Response) {
+        // URLs and header values can not contain line breaks, except if forged
+        let forged = |txt: &str| txt.contains(&['\n', '\r'][..]);
+        if forged(url)
+            || response
+                .headers
+                .iter()
+                .any(|(n, v)| forged(n) || forged(v) || n.contains(": "))
+        {
+            return;
+        }
+        let _ = self.write(url, response);
+    }
+}
+
+fn write_response(path: &Path, url: &str, response: &Response) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(file, "{}", url)?;
+    writeln!(file, "{}", response.status)?;
+    for (name, value) in &response.headers {
+        writeln!(file, "{}: {}", name, value)?;
+    }
+    writeln!(file)?;
+    file.write_all(&response.body)?;
+    file.flush()
+}
+
+const EXTENSION: &str = "response";
+
+/// The 64-bit FNV-1a hash, which (unlike `std`'s hashers) is stable across builds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+impl<C: ResponseCache + ?Sized> ResponseCache for &C {
+    fn get(&self, url: &str) -> Option<Response> {
+        (*self).get(url)
+    }
+
+    fn put(&self, url: &str, response: &Response) {
+        (*self).put(url, response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_cache() {
+        let cache = MemoryCache::new();
+        assert!(cache.get("http://ex.org/").is_none());
+        let response = Response {
+            status: 200,
+            url: "http://ex.org/".into(),
+            headers: vec![("ETag".into(), "\"1\"".into())],
+            body: b"hello".to_vec(),
+        };
+        cache.put("http://ex.org/", &response);
+        assert_eq!(cache.get("http://ex.org/"), Some(response));
+        assert_eq!(cache.len(), 1);
+        assert!(NoCache.get("http://ex.org/").is_none());
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sophia-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn disk_cache() -> io::Result<()> {
+        let dir = temp_dir("disk_cache");
+        let cache = DiskCache::new(&dir)?;
+        assert!(cache.get("http://ex.org/").is_none());
+        let response = Response {
+            status: 200,
+            url: "http://ex.org/".into(),
+            headers: vec![
+                ("ETag".into(), "\"1\"".into()),
+                ("Content-Type".into(), "text/turtle".into()),
+            ],
+            body: b"<a> <b> <c>.\n\n<d> <e> <f>.".to_vec(),
+        };
+        cache.put("http://ex.org/", &response);
+        assert_eq!(cache.get("http://ex.org/"), Some(response.clone()));
+
+        // the stored responses survive the cache
+        let cache = DiskCache::new(&dir)?;
+        assert_eq!(cache.get("http://ex.org/"), Some(response.clone()));
+        cache.remove("http://ex.org/")?;
+        assert!(cache.get("http://ex.org/").is_none());
+        cache.remove("http://ex.org/")?;
+
+        // forged headers are not stored
+        let mut forged = response.clone();
+        forged.headers.push(("X".into(), "a\r\nb".into()));
+        cache.put("http://ex.org/forged", &forged);
+        assert!(cache.get("http://ex.org/forged").is_none());
+
+        cache.put("http://ex.org/", &response);
+        cache.put("http://ex.org/other", &Response::default());
+        assert_eq!(fs::read_dir(&dir)?.count(), 2);
+        cache.clear()?;
+        assert_eq!(fs::read_dir(&dir)?.count(), 0);
+        fs::remove_dir(&dir)
+    }
+}
@@ -0,0 +1,847 @@
+//! A politeness policy for fetching documents on the open web.
+//!
+//! A [`PoliteClient`] wraps an [`HttpClient`] and, for each host,
+//! * honours the rules of its `robots.txt` file (including `Crawl-delay`),
+//! * waits a minimum delay between two successive requests,
+//! * limits the number of parallel requests,
+//! * retries failed requests (and `429` or `503` responses) with an exponential backoff,
+//!   honouring the `Retry-After` header,
+//! * revalidates the responses stored in its [cache](../cache/index.html)
+//!   with conditional requests (`If-None-Match` and `If-Modified-Since`).
+//!
+//! [`PoliteClient`]: struct.PoliteClient.html
+//! [`HttpClient`]: ../trait.HttpClient.html
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use super::cache::{MemoryCache, ResponseCache};
+use super::{HttpClient, Response};
+
+/// The configuration of a [`PoliteClient`](struct.PoliteClient.html).
+#[derive(Clone, Debug)]
+pub struct PolitenessPolicy {
+    user_agent: String,
+    min_delay: Duration,
+    respect_robots: bool,
+    max_parallel: usize,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_retry_after: Duration,
+}
+
+impl Default for PolitenessPolicy {
+    fn default() -> Self {
+        PolitenessPolicy {
+            user_agent: format!("sophia/{}", env!("CARGO_PKG_VERSION")),
+            min_delay: Duration::from_secs(1),
+            respect_robots: true,
+            max_parallel: 1,
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_retry_after: Duration::from_secs(120),
+        }
+    }
+}
+
+impl PolitenessPolicy {
+    /// Set the `User-Agent` header sent with every request,
+    /// and used to select the rules of `robots.txt` (`sophia/<version>` by default).
+    pub fn set_user_agent<T: Into<String>>(&mut self, user_agent: T) -> &mut Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Set the minimum delay between two requests to the same host (1s by default).
+    ///
+    /// A longer `Crawl-delay` in `robots.txt` takes precedence.
+    pub fn set_min_delay(&mut self, min_delay: Duration) -> &mut Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Set whether `robots.txt` is honoured (true by default).
+    pub fn set_respect_robots(&mut self, respect_robots: bool) -> &mut Self {
+        self.respect_robots = respect_robots;
+        self
+    }
+
+    /// Set the maximum number of parallel requests to the same host (1 by default).
+    ///
+    /// # Panics
+    /// If `max_parallel` is 0.
+    pub fn set_max_parallel(&mut self, max_parallel: usize) -> &mut Self {
+        assert!(max_parallel > 0, "max_parallel must be positive");
+        self.max_parallel = max_parallel;
+        self
+    }
+
+    /// Set how many times a failed request is retried (3 by default).
+    pub fn set_max_retries(&mut self, max_retries: u32) -> &mut Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry (1s by default);
+    /// it is doubled for each subsequent retry.
+    pub fn set_initial_backoff(&mut self, initial_backoff: Duration) -> &mut Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the longest delay requested by a `Retry-After` header that is honoured (120s by default).
+    ///
+    /// Above it, the regular backoff applies instead.
+    pub fn set_max_retry_after(&mut self, max_retry_after: Duration) -> &mut Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    /// The `User-Agent` of this policy.
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// The minimum delay between two requests to the same host.
+    pub fn min_delay(&self) -> Duration {
+        self.min_delay
+    }
+
+    /// Whether `robots.txt` is honoured.
+    pub fn respect_robots(&self) -> bool {
+        self.respect_robots
+    }
+
+    /// The maximum number of parallel requests to the same host.
+    pub fn max_parallel(&self) -> usize {
+        self.max_parallel
+    }
+
+    /// How many times a failed request is retried.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// The delay before the first retry.
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// The longest delay requested by a `Retry-After` header that is honoured.
+    pub fn max_retry_after(&self) -> Duration {
+        self.max_retry_after
+    }
+}
+
+/// The rules of a `robots.txt` file applying to a given user agent,
+/// as specified by [RFC 9309](https://www.rfc-editor.org/rfc/rfc9309).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RobotsTxt {
+    /// (allow, pattern)
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsTxt {
+    /// Rules allowing everything.
+    pub fn allow_all() -> Self {
+        RobotsTxt::default()
+    }
+
+    /// Rules disallowing everything.
+    pub fn disallow_all() -> Self {
+        RobotsTxt {
+            rules: vec![(false, "/".to_string())],
+            crawl_delay: None,
+        }
+    }
+
+    /// Parse the content of a `robots.txt` file,
+    /// keeping the rules applying to `user_agent`.
+    ///
+    /// The groups naming the product token of `user_agent` (case-insensitively) are used if any,
+    /// otherwise the groups for `*`.
+    pub fn parse(txt: &str, user_agent: &str) -> Self {
+        let token = user_agent
+            .split(|c: char| c == '/' || c.is_whitespace())
+            .next()
+            .unwrap()
+            .to_ascii_lowercase();
+        let mut specific = RobotsTxt::default();
+        let mut wildcard = RobotsTxt::default();
+        let mut found_specific = false;
+        // which of the above the current group applies to
+        let (mut for_specific, mut for_wildcard) = (false, false);
+        let mut in_rules = false;
+        for line in txt.lines() {
+            let line = line.split('#').next().unwrap();
+            let mut kv = line.splitn(2, ':');
+            let key = kv.next().unwrap().trim().to_ascii_lowercase();
+            let value = match kv.next() {
+                Some(value) => value.trim(),
+                None => continue,
+            };
+            match &key[..] {
+                "user-agent" => {
+                    if in_rules {
+                        for_specific = false;
+                        for_wildcard = false;
+                        in_rules = false;
+                    }
+                    if value == "*" {
+                        for_wildcard = true;
+                    } else if value.eq_ignore_ascii_case(&token) {
+                        for_specific = true;
+                        found_specific = true;
+                    }
+                }
+                "allow" | "disallow" | "crawl-delay" => {
+                    in_rules = true;
+                    if for_specific {
+                        specific.add_line(&key, value);
+                    }
+                    if for_wildcard {
+                        wildcard.add_line(&key, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if found_specific {
+            specific
+        } else {
+            wildcard
+        }
+    }
+
+    fn add_line(&mut self, key: &str, value: &str) {
+        match key {
+            "crawl-delay" => {
+                if let Ok(secs) = value.parse::<f64>() {
+                    if let Ok(delay) = Duration::try_from_secs_f64(secs) {
+                        self.crawl_delay = Some(delay);
+                    }
+                }
+            }
+            // an empty Disallow rule allows everything, i.e. it is not a rule
+            _ if value.is_empty() => {}
+            _ => self.rules.push((key == "allow", value.to_string())),
+        }
+    }
+
+    /// Whether these rules allow fetching `path` (including the query string, if any).
+    ///
+    /// The longest matching rule wins; in case of a tie, `Allow` rules win.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        let mut best: Option<(usize, bool)> = None;
+        for (allow, pattern) in &self.rules {
+            if pattern_matches(pattern, path) {
+                let candidate = (pattern.len(), *allow);
+                if best.is_none_or(|b| candidate > b) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best.map(|(_, allow)| allow).unwrap_or(true)
+    }
+
+    /// The `Crawl-delay` of these rules, if any.
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+/// Match `path` against a `robots.txt` pattern,
+/// where `*` matches any sequence of characters and a final `$` anchors the pattern.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if !path.starts_with(parts[0]) {
+        return false;
+    }
+    if parts.len() == 1 {
+        return !anchored || path.len() == parts[0].len();
+    }
+    let mut pos = parts[0].len();
+    let last = parts.len() - 1;
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        if i == last && anchored {
+            return path.len() >= pos + part.len() && path.ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(j) => pos += j + part.len(),
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Split an absolute HTTP(S) URL into its origin and its path (with query, without fragment).
+fn split_url(url: &str) -> Option<(&str, &str)> {
+    let scheme_end = url.find("://")?;
+    let authority_start = scheme_end + 3;
+    let path_start = url[authority_start..]
+        .find(&['/', '?', '#'][..])
+        .map_or(url.len(), |i| authority_start + i);
+    let path = url[path_start..].split('#').next().unwrap();
+    let path = if path.is_empty() || path.starts_with('?') {
+        "/"
+    } else {
+        path
+    };
+    Some((&url[..path_start], path))
+}
+
+#[derive(Debug)]
+struct HostState {
+    /// None until robots.txt has been successfully retrieved
+    robots: Option<Arc<RobotsTxt>>,
+    /// Whether a thread is currently retrieving robots.txt
+    fetching_robots: bool,
+    next_request: Instant,
+    active: usize,
+}
+
+impl HostState {
+    fn new() -> Self {
+        HostState {
+            robots: None,
+            fetching_robots: false,
+            next_request: Instant::now(),
+            active: 0,
+        }
+    }
+}
+
+/// An [`HttpClient`](../trait.HttpClient.html) wrapping another one,
+/// and enforcing a [`PolitenessPolicy`](struct.PolitenessPolicy.html).
+///
+/// See [module documentation](index.html).
+///
+/// A `PoliteClient` can be shared between threads;
+/// delays and parallelism limits then apply to all of them.
+#[derive(Debug)]
+pub struct PoliteClient<C, S = MemoryCache> {
+    client: C,
+    policy: PolitenessPolicy,
+    cache: S,
+    hosts: Mutex<HashMap<String, HostState>>,
+    available: Condvar,
+}
+
+impl<C: HttpClient> PoliteClient<C> {
+    /// Wrap `client`, enforcing `policy`, and caching responses in memory.
+    pub fn new(client: C, policy: PolitenessPolicy) -> Self {
+        PoliteClient::with_cache(client, policy, MemoryCache::new())
+    }
+}
+
+impl<C: HttpClient, S: ResponseCache> PoliteClient<C, S> {
+    /// Wrap `client`, enforcing `policy`, and caching responses in `cache`
+    /// (for example a [`DiskCache`](../cache/struct.DiskCache.html), to reuse responses across runs).
+    pub fn with_cache(client: C, policy: PolitenessPolicy, cache: S) -> Self {
+        PoliteClient {
+            client,
+            policy,
+            cache,
+            hosts: Mutex::new(HashMap::new()),
+            available: Condvar::new(),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// The policy enforced by this client.
+    pub fn policy(&self) -> &PolitenessPolicy {
+        &self.policy
+    }
+
+    /// The cache of this client.
+    pub fn cache(&self) -> &S {
+        &self.cache
+    }
+
+    /// The `robots.txt` rules of `origin`, retrieving them if necessary.
+    ///
+    /// If `robots.txt` does not exist (4xx status), everything is allowed.
+    /// If it can not be retrieved (5xx status or client error), everything is disallowed,
+    /// and retrieval will be attempted again on the next request.
+    ///
+    /// Only one thread at a time retrieves `robots.txt` for a given host;
+    /// the other ones wait for its result.
+    fn robots(&self, origin: &str) -> Arc<RobotsTxt> {
+        let mut hosts = self.hosts.lock().unwrap();
+        loop {
+            let state = hosts
+                .entry(origin.to_string())
+                .or_insert_with(HostState::new);
+            if let Some(robots) = &state.robots {
+                return robots.clone();
+            }
+            if !state.fetching_robots {
+                state.fetching_robots = true;
+                break;
+            }
+            hosts = self.available.wait(hosts).unwrap();
+        }
+        drop(hosts);
+        let url = format!("{}/robots.txt", origin);
+        let headers = [("User-Agent", self.policy.user_agent())];
+        let response = self.throttled(origin, self.policy.min_delay, || {
+            self.client.get(&url, &headers)
+        });
+        let robots = match response {
+            Ok(r) if r.is_success() => Some(RobotsTxt::parse(
+                &String::from_utf8_lossy(&r.body),
+                self.policy.user_agent(),
+            )),
+            Ok(r) if (400..500).contains(&r.status) => Some(RobotsTxt::allow_all()),
+            _ => None,
+        };
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts
+            .entry(origin.to_string())
+            .or_insert_with(HostState::new);
+        state.fetching_robots = false;
+        let robots = match robots {
+            Some(robots) => {
+                let robots = Arc::new(robots);
+                state.robots = Some(robots.clone());
+                robots
+            }
+            None => Arc::new(RobotsTxt::disallow_all()),
+        };
+        drop(hosts);
+        self.available.notify_all();
+        robots
+    }
+
+    /// Run `request` on `host` once the policy allows it,
+    /// and prevent other requests on `host` for `delay`.
+    fn throttled<T, F>(&self, host: &str, delay: Duration, request: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        let mut hosts = self.hosts.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let state = hosts.entry(host.to_string()).or_insert_with(HostState::new);
+            if state.active >= self.policy.max_parallel {
+                hosts = self.available.wait(hosts).unwrap();
+            } else if state.next_request > now {
+                let wait = state.next_request - now;
+                hosts = self.available.wait_timeout(hosts, wait).unwrap().0;
+            } else {
+                state.active += 1;
+                if let Some(next_request) = now.checked_add(delay) {
+                    state.next_request = next_request;
+                }
+                break;
+            }
+        }
+        drop(hosts);
+        // releases the slot even if `request` panics
+        let _active = ActiveSlot { client: self, host };
+        request()
+    }
+
+    /// Prevent any request on `host` for `wait`.
+    fn defer(&self, host: &str, wait: Duration) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_insert_with(HostState::new);
+        if let Some(until) = Instant::now().checked_add(wait) {
+            if state.next_request < until {
+                state.next_request = until;
+            }
+        }
+    }
+}
+
+/// A parallel request slot taken on a host by [`PoliteClient::throttled`],
+/// released when dropped.
+struct ActiveSlot<'a, C, S> {
+    client: &'a PoliteClient<C, S>,
+    host: &'a str,
+}
+
+impl<C, S> Drop for ActiveSlot<'_, C, S> {
+    fn drop(&mut self) {
+        let mut hosts = self
+            .client
+            .hosts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(state) = hosts.get_mut(self.host) {
+            state.active -= 1;
+        }
+        drop(hosts);
+        self.client.available.notify_all();
+    }
+}
+
+impl<C: HttpClient, S: ResponseCache> HttpClient for PoliteClient<C, S> {
+    type Error = PoliteError<C::Error>;
+
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Self::Error> {
+        let (host, path) = split_url(url).unwrap_or(("", url));
+        let mut delay = self.policy.min_delay;
+        if self.policy.respect_robots && !host.is_empty() {
+            let robots = self.robots(host);
+            if !robots.is_allowed(path) {
+                return Err(PoliteError::Disallowed(url.to_string()));
+            }
+            if let Some(crawl_delay) = robots.crawl_delay() {
+                delay = delay.max(crawl_delay);
+            }
+        }
+
+        let cached = self.cache.get(url);
+        let mut all_headers = vec![("User-Agent", self.policy.user_agent())];
+        all_headers.extend_from_slice(headers);
+        if let Some(cached) = &cached {
+            if let Some(etag) = cached.header("etag") {
+                all_headers.push(("If-None-Match", etag));
+            }
+            if let Some(last_modified) = cached.header("last-modified") {
+                all_headers.push(("If-Modified-Since", last_modified));
+            }
+        }
+
+        let mut backoff = self.policy.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let result = self.throttled(host, delay, || self.client.get(url, &all_headers));
+            if retries < self.policy.max_retries {
+                let wait = match &result {
+                    Ok(r) if r.status == 429 || r.status == 503 => Some(
+                        retry_after(r)
+                            .filter(|wait| *wait <= self.policy.max_retry_after)
+                            .unwrap_or(backoff),
+                    ),
+                    Err(_) => Some(backoff),
+                    Ok(_) => None,
+                };
+                if let Some(wait) = wait {
+                    self.defer(host, wait);
+                    retries += 1;
+                    backoff = backoff.saturating_mul(2);
+                    continue;
+                }
+            }
+            let response = result.map_err(PoliteError::Client)?;
+            if response.status == 304 {
+                if let Some(cached) = &cached {
+                    return Ok(cached.clone());
+                }
+            }
+            if response.is_success()
+                && (response.header("etag").is_some() || response.header("last-modified").is_some())
+            {
+                self.cache.put(url, &response);
+            }
+            return Ok(response);
+        }
+    }
+}
+
+/// The delay requested by the `Retry-After` header of `response`, if any
+/// (only the delay-seconds form is supported).
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .header("retry-after")
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// The error raised by [`PoliteClient`](struct.PoliteClient.html).
+#[derive(Debug, Error)]
+pub enum PoliteError<E: 'static + Error> {
+    /// The wrapped client failed (after all retries).
+    #[error("HTTP client error: {0}")]
+    Client(#[source] E),
+    /// The URL is disallowed by the `robots.txt` of its host.
+    #[error("Disallowed by robots.txt: {0}")]
+    Disallowed(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fetch::test::MockClient;
+    use test_case::test_case;
+
+    const ROBOTS: &str = "# comment
+User-agent: otherbot
+Disallow: /
+
+User-agent: *
+User-agent: sophia
+Disallow: /private/ # trailing comment
+Allow: /private/public
+Disallow: /*.json$
+Crawl-delay: 2
+
+User-agent: *
+Disallow: /tmp
+";
+
+    #[test_case("/", true; "root")]
+    #[test_case("/robots.txt", true; "robots")]
+    #[test_case("/private/x", false; "disallowed")]
+    #[test_case("/private/public/x", true; "longest match")]
+    #[test_case("/data.json", false; "anchored wildcard")]
+    #[test_case("/data.json?x", true; "anchored wildcard no match")]
+    #[test_case("/tmp/x", true; "wildcard only group")]
+    fn robots_txt(path: &str, allowed: bool) {
+        let robots = RobotsTxt::parse(ROBOTS, "Sophia/0.6");
+        assert_eq!(robots.is_allowed(path), allowed);
+        assert_eq!(robots.crawl_delay(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn robots_txt_groups() {
+        let robots = RobotsTxt::parse(ROBOTS, "otherbot");
+        assert!(!robots.is_allowed("/"));
+        assert_eq!(robots.crawl_delay(), None);
+        let robots = RobotsTxt::parse(ROBOTS, "unknownbot");
+        assert!(!robots.is_allowed("/tmp"));
+        assert!(!robots.is_allowed("/private/"));
+        let robots = RobotsTxt::parse("Disallow: /\n", "unknownbot");
+        assert!(robots.is_allowed("/"));
+    }
+
+    #[test_case("/a*b", "/axxb", true)]
+    #[test_case("/a*b", "/axxbyy", true)]
+    #[test_case("/a*b$", "/axxbyy", false)]
+    #[test_case("/a*b$", "/abab", true)]
+    #[test_case("/a$", "/a", true)]
+    #[test_case("/a$", "/ab", false)]
+    #[test_case("/*/b*c", "/x/b/yc", true)]
+    #[test_case("/*/b*c", "/xb/c", false)]
+    fn pattern(pattern: &str, path: &str, expected: bool) {
+        assert_eq!(pattern_matches(pattern, path), expected);
+    }
+
+    #[test_case("http://ex.org", "http://ex.org", "/")]
+    #[test_case("http://ex.org/a/b?c#d", "http://ex.org", "/a/b?c")]
+    #[test_case("https://ex.org:8080?q", "https://ex.org:8080", "/")]
+    fn split(url: &str, origin: &str, path: &str) {
+        assert_eq!(split_url(url), Some((origin, path)));
+    }
+
+    fn policy() -> PolitenessPolicy {
+        let mut policy = PolitenessPolicy::default();
+        policy
+            .set_user_agent("testbot")
+            .set_min_delay(Duration::from_millis(0))
+            .set_initial_backoff(Duration::from_millis(1));
+        policy
+    }
+
+    #[test]
+    fn robots_disallow() {
+        let client = MockClient::default();
+        client.add(
+            "http://ex.org/robots.txt",
+            200,
+            &[],
+            "User-agent: *\nDisallow: /no",
+        );
+        client.add("http://ex.org/yes", 200, &[], "");
+        client.add("http://ex.org/no", 200, &[], "");
+        client.add("http://other.org/robots.txt", 404, &[], "");
+        client.add("http://other.org/no", 200, &[], "");
+        let polite = PoliteClient::new(&client, policy());
+        assert!(polite.get("http://ex.org/yes", &[]).is_ok());
+        assert!(matches!(
+            polite.get("http://ex.org/no", &[]),
+            Err(PoliteError::Disallowed(_))
+        ));
+        assert!(polite.get("http://other.org/no", &[]).is_ok());
+        // robots.txt is only retrieved once per host
+        assert_eq!(
+            client.requested(),
+            vec![
+                "http://ex.org/robots.txt",
+                "http://ex.org/yes",
+                "http://other.org/robots.txt",
+                "http://other.org/no",
+            ]
+        );
+        let log = client.log.lock().unwrap();
+        assert_eq!(
+            log[1].1[0],
+            ("User-Agent".to_string(), "testbot".to_string())
+        );
+    }
+
+    #[test]
+    fn robots_unreachable() {
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 500, &[], "");
+        client.add("http://ex.org/robots.txt", 200, &[], "");
+        client.add("http://ex.org/a", 200, &[], "");
+        let polite = PoliteClient::new(&client, policy());
+        assert!(matches!(
+            polite.get("http://ex.org/a", &[]),
+            Err(PoliteError::Disallowed(_))
+        ));
+        assert!(polite.get("http://ex.org/a", &[]).is_ok());
+
+        let mut policy = policy();
+        policy.set_respect_robots(false);
+        let polite = PoliteClient::new(&client, policy);
+        assert!(polite.get("http://unknown.org/robots.txt", &[]).is_err());
+    }
+
+    #[test]
+    fn retry() {
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 404, &[], "");
+        client.add("http://ex.org/a", 503, &[], "");
+        client.add("http://ex.org/a", 429, &[("Retry-After", "0")], "");
+        client.add("http://ex.org/a", 200, &[], "ok");
+        client.add("http://ex.org/b", 503, &[], "");
+        let polite = PoliteClient::new(&client, policy());
+        assert_eq!(polite.get("http://ex.org/a", &[]).unwrap().body, b"ok");
+        assert_eq!(polite.get("http://ex.org/b", &[]).unwrap().status, 503);
+        assert!(matches!(
+            polite.get("http://ex.org/c", &[]),
+            Err(PoliteError::Client(_))
+        ));
+        let requested = client.requested();
+        let count = |url: &str| requested.iter().filter(|u| *u == url).count();
+        assert_eq!(count("http://ex.org/a"), 3);
+        assert_eq!(count("http://ex.org/b"), 4);
+        assert_eq!(count("http://ex.org/c"), 4);
+    }
+
+    #[test]
+    fn retry_after_too_long() {
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 404, &[], "");
+        client.add(
+            "http://ex.org/a",
+            503,
+            &[("Retry-After", "18446744073709551615")],
+            "",
+        );
+        client.add("http://ex.org/a", 429, &[("Retry-After", "3600")], "");
+        client.add("http://ex.org/a", 200, &[], "ok");
+        let mut policy = policy();
+        policy.set_max_retry_after(Duration::from_secs(60));
+        let polite = PoliteClient::new(&client, policy);
+        let start = Instant::now();
+        assert_eq!(polite.get("http://ex.org/a", &[]).unwrap().body, b"ok");
+        // both Retry-After are above the cap, so the (short) backoff applies instead
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn client_panic() {
+        struct PanickingClient(MockClient);
+        impl HttpClient for PanickingClient {
+            type Error = std::io::Error;
+            fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Self::Error> {
+                if url.ends_with("/panic") {
+                    panic!("client panicked");
+                }
+                self.0.get(url, headers)
+            }
+        }
+
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 404, &[], "");
+        client.add("http://ex.org/a", 200, &[], "ok");
+        let polite = Arc::new(PoliteClient::new(PanickingClient(client), policy()));
+        let polite2 = polite.clone();
+        let panicked = std::thread::spawn(move || polite2.get("http://ex.org/panic", &[]));
+        assert!(panicked.join().is_err());
+        // the slot taken by the panicking request was released
+        assert_eq!(polite.hosts.lock().unwrap()["http://ex.org"].active, 0);
+        assert_eq!(polite.get("http://ex.org/a", &[]).unwrap().body, b"ok");
+    }
+
+    #[test]
+    fn conditional_requests() {
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 404, &[], "");
+        let validators = [
+            ("ETag", "\"v1\""),
+            ("Last-Modified", "Sat, 01 Jan 2000 00:00:00 GMT"),
+        ];
+        client.add("http://ex.org/a", 200, &validators, "content");
+        client.add("http://ex.org/a", 304, &[], "");
+        let polite = PoliteClient::new(&client, policy());
+        let first = polite.get("http://ex.org/a", &[]).unwrap();
+        assert_eq!(polite.cache().len(), 1);
+        let second = polite
+            .get("http://ex.org/a", &[("Accept", "text/turtle")])
+            .unwrap();
+        assert_eq!(first, second);
+        let log = client.log.lock().unwrap();
+        let headers = &log[2].1;
+        assert!(headers.contains(&("Accept".to_string(), "text/turtle".to_string())));
+        assert!(headers.contains(&("If-None-Match".to_string(), "\"v1\"".to_string())));
+        assert!(headers.contains(&(
+            "If-Modified-Since".to_string(),
+            "Sat, 01 Jan 2000 00:00:00 GMT".to_string()
+        )));
+    }
+
+    #[test]
+    fn delay() {
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 404, &[], "");
+        client.add("http://ex.org/a", 200, &[], "");
+        client.add("http://other.org/robots.txt", 404, &[], "");
+        client.add("http://other.org/a", 200, &[], "");
+        let mut policy = policy();
+        policy.set_min_delay(Duration::from_millis(50));
+        let polite = PoliteClient::new(&client, policy);
+        let start = Instant::now();
+        polite.get("http://ex.org/a", &[]).unwrap();
+        polite.get("http://ex.org/a", &[]).unwrap();
+        // robots.txt, then 2 requests, each 50ms apart
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        let start = Instant::now();
+        polite.get("http://other.org/a", &[]).unwrap();
+        // other hosts are not delayed by the requests above
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn parallel() {
+        let client = MockClient::default();
+        client.add("http://ex.org/robots.txt", 404, &[], "");
+        client.add("http://ex.org/a", 200, &[], "");
+        let mut policy = policy();
+        policy.set_max_parallel(2);
+        let polite = Arc::new(PoliteClient::new(client, policy));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let polite = polite.clone();
+                std::thread::spawn(move || polite.get("http://ex.org/a", &[]).unwrap())
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        // robots.txt is retrieved only once, although all threads need it at the same time
+        assert_eq!(polite.client().requested().len(), 5);
+        assert_eq!(polite.hosts.lock().unwrap()["http://ex.org"].active, 0);
+    }
+}
@@ -0,0 +1,330 @@
+//! Well-known test data, and golden-file testing.
+//!
+//! The [`Fixture`]s are small graphs and datasets bundled with this crate,
+//! covering the most common features of RDF
+//! (blank nodes, language tags, datatypes, lists, named graphs...).
+//! They are meant to be used in tests, e.g. of parsers, serializers or datasets.
+//!
+//! A [`GoldenFile`] compares the output of a serializer to an expected file,
+//! checked in along with the tests.
+//! Both are parsed and [canonicalized](../serializer/nq/fn.canonical_nquads.html) before comparison,
+//! so that they only differ if they do not describe the same dataset
+//! (regardless of the order of the statements, of prefixes, of blank node labels...).
+//! If the environment variable `SOPHIA_UPDATE_GOLDEN` is set,
+//! or if the expected file does not exist,
+//! the output of the serializer is written to the expected file instead.
+//!
+//! # Example
+//! ```
+//! # use sophia::fixtures::{Fixture, GoldenFile};
+//! # use sophia::parser::any::Format;
+//! # use sophia::serializer::{TripleSerializer, Stringifier};
+//! # use sophia::serializer::nt::NtSerializer;
+//! # let dir = std::env::temp_dir().join("sophia_fixtures_doc");
+//! # std::fs::create_dir_all(&dir)?;
+//! let mut graph = Fixture::Foaf.graph();
+//! let mut nt = NtSerializer::new_stringifier();
+//! let output = nt.serialize_graph(&mut graph)?.as_str();
+//! GoldenFile::new(dir.join("foaf.nt"), Format::NTriples).assert(output);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Fixture`]: enum.Fixture.html
+//! [`GoldenFile`]: struct.GoldenFile.html
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sophia_api::graph::MutableGraph;
+use sophia_api::quad::stream::QuadSource;
+use thiserror::Error;
+
+use crate::dataset::inmem::FastDataset;
+use crate::graph::inmem::FastGraph;
+use crate::parser::any::{AnyError, AnyParser, Format};
+use crate::serializer::nq::canonical_nquads;
+
+/// The environment variable requesting [`GoldenFile`](struct.GoldenFile.html)s to be updated.
+pub const UPDATE_GOLDEN_VAR: &str = "SOPHIA_UPDATE_GOLDEN";
+
+/// A small graph or dataset bundled with this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Fixture {
+    /// A few people and organizations, described with [FOAF](http://xmlns.com/foaf/spec/)
+    /// (26 triples, including 2 blank nodes).
+    Foaf,
+    /// An [OWL](https://www.w3.org/TR/owl2-overview/) ontology snippet,
+    /// with class restrictions and an RDF list (36 triples).
+    Ontology,
+    /// A dataset with two named graphs,
+    /// described in the default graph (10 quads).
+    Graphs,
+}
+
+impl Fixture {
+    /// All the fixtures.
+    pub fn all() -> &'static [Fixture] {
+        &[Fixture::Foaf, Fixture::Ontology, Fixture::Graphs]
+    }
+
+    /// The name of this fixture (also the stem of its file name).
+    pub fn name(self) -> &'static str {
+        match self {
+            Fixture::Foaf => "foaf",
+            Fixture::Ontology => "ontology",
+            Fixture::Graphs => "graphs",
+        }
+    }
+
+    /// The format of [`text`](#method.text).
+    pub fn format(self) -> Format {
+        match self {
+            Fixture::Foaf | Fixture::Ontology => Format::Turtle,
+            Fixture::Graphs => Format::TriG,
+        }
+    }
+
+    /// The source text of this fixture.
+    pub fn text(self) -> &'static str {
+        match self {
+            Fixture::Foaf => include_str!("../fixtures/foaf.ttl"),
+            Fixture::Ontology => include_str!("../fixtures/ontology.ttl"),
+            Fixture::Graphs => include_str!("../fixtures/graphs.trig"),
+        }
+    }
+
+    /// This fixture as a dataset.
+    pub fn dataset(self) -> FastDataset {
+        let source = AnyParser::new(self.format()).parse_str(self.text());
+        // bundled fixtures are known to be valid
+        source.unwrap().collect_quads().unwrap()
+    }
+
+    /// The default graph of this fixture.
+    pub fn graph(self) -> FastGraph {
+        let source = AnyParser::new(self.format()).parse_str(self.text());
+        let mut g = FastGraph::new();
+        for q in source.unwrap() {
+            let ([s, p, o], gn) = q.unwrap();
+            if gn.is_none() {
+                g.insert(&s, &p, &o).unwrap();
+            }
+        }
+        g
+    }
+}
+
+/// A file containing the expected output of a serializer.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct GoldenFile {
+    /// The path of the expected file.
+    pub path: PathBuf,
+    /// The format of the expected file, and of the output compared to it.
+    pub format: Format,
+    /// Whether to write the output to the expected file, instead of comparing them.
+    pub update: bool,
+}
+
+impl GoldenFile {
+    /// A golden file at `path`, in the given format.
+    ///
+    /// It is [updated](#structfield.update) if the `SOPHIA_UPDATE_GOLDEN` environment variable is set.
+    pub fn new<P: AsRef<Path>>(path: P, format: Format) -> Self {
+        GoldenFile {
+            path: path.as_ref().to_path_buf(),
+            format,
+            update: std::env::var_os(UPDATE_GOLDEN_VAR).is_some(),
+        }
+    }
+
+    /// Compare `actual` to the content of the expected file.
+    ///
+    /// If the file must be [updated](#structfield.update), or does not exist,
+    /// `actual` is written to it (after checking that it can be parsed).
+    ///
+    /// # Errors
+    /// Fails if the file can not be read or written,
+    /// if `actual` or the expected file can not be parsed,
+    /// or if they do not describe the same dataset.
+    pub fn check(&self, actual: &str) -> Result<(), GoldenError> {
+        let actual_lines = self.canonical_lines(actual, "actual output")?;
+        if self.update || !self.path.exists() {
+            fs::write(&self.path, actual)?;
+            return Ok(());
+        }
+        let expected = fs::read_to_string(&self.path)?;
+        let expected_lines = self.canonical_lines(&expected, "expected file")?;
+        if actual_lines == expected_lines {
+            return Ok(());
+        }
+        Err(GoldenError::Mismatch {
+            path: self.path.clone(),
+            missing: expected_lines.difference(&actual_lines).cloned().collect(),
+            unexpected: actual_lines.difference(&expected_lines).cloned().collect(),
+        })
+    }
+
+    /// Same as [`check`](#method.check), but panics on error.
+    ///
+    /// This is convenient in tests.
+    pub fn assert(&self, actual: &str) {
+        if let Err(err) = self.check(actual) {
+            panic!("{}", err);
+        }
+    }
+
+    /// The canonical N-Quads of `txt`, as a set of lines.
+    fn canonical_lines(
+        &self,
+        txt: &str,
+        what: &'static str,
+    ) -> Result<BTreeSet<String>, GoldenError> {
+        let parse = |txt: &str| -> Result<FastDataset, AnyError> {
+            let source = AnyParser::new(self.format).parse_str(txt)?;
+            // inserting in a FastDataset never fails
+            source
+                .collect_quads()
+                .map_err(|err| err.unwrap_source_error())
+        };
+        let d = parse(txt).map_err(|source| GoldenError::Parse { what, source })?;
+        let nq = canonical_nquads(&d).unwrap(); // reading a FastDataset never fails
+        Ok(nq.lines().map(str::to_string).collect())
+    }
+}
+
+/// The error raised by [`GoldenFile::check`](struct.GoldenFile.html#method.check).
+#[derive(Debug, Error)]
+pub enum GoldenError {
+    /// The expected file could not be read or written.
+    #[error("Could not access golden file: {0}")]
+    Io(#[from] io::Error),
+    /// The actual output, or the expected file, could not be parsed.
+    #[error("Could not parse {what}: {source}")]
+    Parse {
+        /// What could not be parsed.
+        what: &'static str,
+        /// The parse error.
+        source: AnyError,
+    },
+    /// The actual output does not describe the same dataset as the expected file.
+    #[error(
+        "Output differs from {}:\n{}{}",
+        path.display(),
+        diff_lines('-', missing),
+        diff_lines('+', unexpected)
+    )]
+    Mismatch {
+        /// The path of the expected file.
+        path: PathBuf,
+        /// The canonical N-Quads lines of the expected file missing in the output.
+        missing: Vec<String>,
+        /// The canonical N-Quads lines of the output missing in the expected file.
+        unexpected: Vec<String>,
+    },
+}
+
+fn diff_lines(prefix: char, lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| format!("{} {}\n", prefix, line))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::Dataset;
+    use crate::graph::Graph;
+    use crate::serializer::nq::NqSerializer;
+    use crate::serializer::turtle::TurtleSerializer;
+    use crate::serializer::{QuadSerializer, Stringifier, TripleSerializer};
+
+    fn golden(name: &str, format: Format) -> GoldenFile {
+        let dir = std::env::temp_dir().join(format!("sophia_golden_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        GoldenFile {
+            path,
+            format,
+            update: false,
+        }
+    }
+
+    #[test]
+    fn fixtures() {
+        let counts: Vec<_> = Fixture::all()
+            .iter()
+            .map(|f| {
+                (
+                    f.name(),
+                    f.dataset().quads().count(),
+                    f.graph().triples().count(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            vec![("foaf", 26, 26), ("ontology", 36, 36), ("graphs", 10, 4)]
+        );
+    }
+
+    #[test]
+    fn golden_turtle() -> Result<(), Box<dyn std::error::Error>> {
+        let g = Fixture::Foaf.graph();
+        let mut ser = TurtleSerializer::new_stringifier();
+        let output = ser.serialize_graph(&g)?.as_str().to_string();
+        let golden = golden("foaf.ttl", Format::Turtle);
+        // the file does not exist yet, so it is written
+        golden.check(&output)?;
+        assert_eq!(fs::read_to_string(&golden.path)?, output);
+        // the original text describes the same graph
+        golden.check(Fixture::Foaf.text())?;
+
+        let modified = output.replace("Charlie", "Charly");
+        match golden.check(&modified) {
+            Err(GoldenError::Mismatch {
+                missing,
+                unexpected,
+                ..
+            }) => {
+                assert_eq!(missing.len(), 1);
+                assert!(missing[0].contains("\"Charlie\""));
+                assert_eq!(unexpected.len(), 1);
+                assert!(unexpected[0].contains("\"Charly\""));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match golden.check("not turtle") {
+            Err(GoldenError::Parse { what, .. }) => assert_eq!(what, "actual output"),
+            other => panic!("unexpected {:?}", other),
+        }
+        fs::remove_file(&golden.path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn golden_update() -> Result<(), Box<dyn std::error::Error>> {
+        let d = Fixture::Graphs.dataset();
+        let mut ser = NqSerializer::new_stringifier();
+        let output = ser.serialize_dataset(&d)?.as_str().to_string();
+        let mut golden = golden("graphs.nq", Format::NQuads);
+        golden.check(&output)?;
+        golden.check(Fixture::Graphs.text()).unwrap_err(); // not N-Quads
+        golden.update = true;
+        let other = Fixture::Foaf.dataset();
+        let other = canonical_nquads(&other)?;
+        golden.check(&other)?;
+        assert_eq!(fs::read_to_string(&golden.path)?, other);
+        fs::remove_file(&golden.path)?;
+        Ok(())
+    }
+}
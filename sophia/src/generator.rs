@@ -0,0 +1,291 @@
+//! Generate synthetic datasets, e.g. for benchmarks and examples.
+//!
+//! A [`Generator`] describes the shape of the dataset to produce
+//! (number of quads, nodes, predicates and graphs, proportion of literals and blank nodes...).
+//! Its [`quads`](struct.Generator.html#method.quads) method returns a [`QuadSource`].
+//!
+//! Generation is deterministic:
+//! a given generator always produces the same quads, in the same order,
+//! regardless of the platform or of the version of its dependencies.
+//! Different datasets can be obtained by changing the
+//! [`seed`](struct.Generator.html#structfield.seed).
+//!
+//! The [`lubm`](lubm/index.html) submodule generates data for a standard benchmark.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::generator::{Distribution, Generator};
+//! # use sophia::quad::stream::QuadSource;
+//! let generator = Generator {
+//!     quads: 500,
+//!     predicates: 20,
+//!     predicate_distribution: Distribution::Zipf(1.2),
+//!     graphs: 3,
+//!     ..Generator::default()
+//! };
+//! let d: FastDataset = generator.quads().collect_quads()?;
+//! assert!(d.quads().count() <= 500); // duplicate quads are possible
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Generator`]: struct.Generator.html
+//! [`QuadSource`]: ../quad/stream/trait.QuadSource.html
+
+use std::convert::Infallible;
+
+use sophia_api::ns::xsd;
+use sophia_api::quad::TupleQuad;
+use sophia_term::BoxTerm;
+
+pub mod lubm;
+
+/// The description of a synthetic dataset.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Generator {
+    /// The seed of the pseudo-random number generator.
+    pub seed: u64,
+    /// The number of quads to generate.
+    pub quads: usize,
+    /// The number of distinct subjects and non-literal objects.
+    pub nodes: usize,
+    /// The number of distinct predicates.
+    pub predicates: usize,
+    /// How predicates are distributed among quads.
+    pub predicate_distribution: Distribution,
+    /// The proportion (between 0 and 1) of objects that are literals.
+    pub literal_ratio: f64,
+    /// The proportion (between 0 and 1) of nodes that are blank nodes.
+    pub bnode_ratio: f64,
+    /// The number of named graphs; if 0, all quads are in the default graph.
+    pub graphs: usize,
+    /// The namespace of all generated IRIs.
+    pub namespace: String,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator {
+            seed: 0,
+            quads: 1000,
+            nodes: 100,
+            predicates: 10,
+            predicate_distribution: Distribution::Uniform,
+            literal_ratio: 0.3,
+            bnode_ratio: 0.0,
+            graphs: 0,
+            namespace: "http://example.org/".into(),
+        }
+    }
+}
+
+impl Generator {
+    /// Generate the quads described by this generator.
+    ///
+    /// The returned iterator is a [`QuadSource`](../quad/stream/trait.QuadSource.html),
+    /// that never fails.
+    ///
+    /// # Panics
+    /// If `nodes` or `predicates` is 0.
+    pub fn quads(&self) -> GeneratedQuads {
+        assert!(self.nodes > 0, "Generator needs at least one node");
+        assert!(
+            self.predicates > 0,
+            "Generator needs at least one predicate"
+        );
+        let predicate_weights = self
+            .predicate_distribution
+            .cumulative_weights(self.predicates);
+        GeneratedQuads {
+            config: self.clone(),
+            rng: SplitMix64(self.seed),
+            predicate_weights,
+            remaining: self.quads,
+        }
+    }
+
+    fn node(&self, i: usize) -> BoxTerm {
+        // the first nodes are blank nodes, so that the set of blank nodes does not vary
+        if (i as f64) < self.bnode_ratio * self.nodes as f64 {
+            BoxTerm::new_bnode_unchecked(format!("b{}", i))
+        } else {
+            BoxTerm::new_iri_unchecked(format!("{}node{}", self.namespace, i))
+        }
+    }
+}
+
+/// A distribution of values among a finite set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    /// All values are equally likely.
+    Uniform,
+    /// The k-th value has a probability proportional to 1/k^s,
+    /// where s is the given exponent ([Zipf's law](https://en.wikipedia.org/wiki/Zipf%27s_law)).
+    Zipf(f64),
+}
+
+impl Distribution {
+    /// The cumulative weights of `n` values, the last one being 1.
+    fn cumulative_weights(self, n: usize) -> Vec<f64> {
+        let weights: Vec<f64> = match self {
+            Distribution::Uniform => vec![1.0; n],
+            Distribution::Zipf(s) => (1..=n).map(|k| 1.0 / (k as f64).powf(s)).collect(),
+        };
+        let total: f64 = weights.iter().sum();
+        let mut acc = 0.0;
+        weights
+            .into_iter()
+            .map(|w| {
+                acc += w / total;
+                acc
+            })
+            .collect()
+    }
+}
+
+/// The [`QuadSource`](../quad/stream/trait.QuadSource.html)
+/// returned by [`Generator::quads`](struct.Generator.html#method.quads).
+pub struct GeneratedQuads {
+    config: Generator,
+    rng: SplitMix64,
+    predicate_weights: Vec<f64>,
+    remaining: usize,
+}
+
+impl Iterator for GeneratedQuads {
+    type Item = Result<TupleQuad<BoxTerm>, Infallible>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let config = &self.config;
+        let s = config.node(self.rng.below(config.nodes));
+        let x = self.rng.next_f64();
+        let p = self
+            .predicate_weights
+            .iter()
+            .position(|w| x < *w)
+            .unwrap_or(config.predicates - 1);
+        let p = BoxTerm::new_iri_unchecked(format!("{}p{}", config.namespace, p));
+        let o = if self.rng.next_f64() < config.literal_ratio {
+            let n = self.rng.below(config.nodes);
+            if n.is_multiple_of(2) {
+                BoxTerm::new_literal_dt_unchecked(n.to_string(), xsd::integer)
+            } else {
+                BoxTerm::new_literal_dt_unchecked(format!("literal {}", n), xsd::string)
+            }
+        } else {
+            config.node(self.rng.below(config.nodes))
+        };
+        let g = if config.graphs == 0 {
+            None
+        } else {
+            let i = self.rng.below(config.graphs);
+            Some(BoxTerm::new_iri_unchecked(format!(
+                "{}graph{}",
+                config.namespace, i
+            )))
+        };
+        Some(Ok(([s, p, o], g)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// The [SplitMix64](https://prng.di.unimi.it/splitmix64.c) pseudo-random number generator,
+/// chosen for its simplicity and stability.
+#[derive(Clone, Debug)]
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in [0, 1).
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A number in [0, n).
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::quad::Quad;
+    use sophia_api::term::{TTerm, TermKind};
+
+    fn generate(generator: &Generator) -> Vec<TupleQuad<BoxTerm>> {
+        generator.quads().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn deterministic() {
+        let generator = Generator::default();
+        let q1 = generate(&generator);
+        assert_eq!(q1.len(), 1000);
+        assert_eq!(q1, generate(&generator));
+        let q2 = generate(&Generator {
+            seed: 42,
+            ..generator
+        });
+        assert_ne!(q1, q2);
+    }
+
+    #[test]
+    fn shape() {
+        let generator = Generator {
+            quads: 2000,
+            nodes: 50,
+            predicates: 5,
+            predicate_distribution: Distribution::Zipf(2.0),
+            literal_ratio: 0.5,
+            bnode_ratio: 0.2,
+            graphs: 4,
+            ..Generator::default()
+        };
+        let quads = generate(&generator);
+        let count = |f: &dyn Fn(&TupleQuad<BoxTerm>) -> bool| quads.iter().filter(|q| f(q)).count();
+
+        let literals = count(&|q| q.o().kind() == TermKind::Literal);
+        assert!(800 < literals && literals < 1200, "{}", literals);
+        let bnodes = count(&|q| q.s().kind() == TermKind::BlankNode);
+        assert!(200 < bnodes && bnodes < 600, "{}", bnodes);
+        assert_eq!(count(&|q| q.g().is_none()), 0);
+        let graphs: std::collections::HashSet<_> = quads.iter().map(|q| q.g().unwrap()).collect();
+        assert_eq!(graphs.len(), 4);
+
+        // with Zipf(2), the first predicate accounts for 1/(1+1/4+1/9+1/16+1/25) ≈ 68% of quads
+        let p0 = count(&|q| q.p().value() == "http://example.org/p0");
+        assert!(1200 < p0 && p0 < 1500, "{}", p0);
+        let p4 = count(&|q| q.p().value() == "http://example.org/p4");
+        assert!(p4 < 100, "{}", p4);
+    }
+
+    #[test]
+    fn distributions() {
+        let weights = Distribution::Uniform.cumulative_weights(4);
+        assert_eq!(weights, vec![0.25, 0.5, 0.75, 1.0]);
+        let weights = Distribution::Zipf(1.0).cumulative_weights(3);
+        assert!((weights[0] - 6.0 / 11.0).abs() < 1e-9);
+        assert!((weights[2] - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,594 @@
+//! Data and queries of the [Lehigh University Benchmark](http://swat.cse.lehigh.edu/projects/lubm/) (LUBM).
+//!
+//! [`Lubm`] generates data in the style of the original UBA generator:
+//! universities with departments, faculty, students, courses, research groups and publications,
+//! described with the [univ-bench](http://swat.cse.lehigh.edu/onto/univ-bench.owl) ontology.
+//! The numbers of entities are drawn from the same ranges as UBA,
+//! but the data is not identical to what UBA would produce.
+//!
+//! [`queries`] returns the 14 standard LUBM queries, as [`Query`]s.
+//! Many of them rely on inferences (e.g. every `ub:FullProfessor` is a `ub:Professor`).
+//! As Sophia does not perform reasoning,
+//! [`Lubm`] materializes the relevant inferences by default,
+//! so that all queries return their expected answers.
+//!
+//! # Example
+//! ```no_run
+//! # use sophia::generator::lubm::{queries, Lubm};
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::triple::stream::TripleSource;
+//! let lubm = Lubm {
+//!     departments: Some(1),
+//!     ..Lubm::default()
+//! };
+//! let g: FastGraph = lubm.triples().collect_triples()?;
+//! for (name, mut query) in queries() {
+//!     let results = query.process(&g).count();
+//!     println!("{}: {} results", name, results);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Lubm`]: struct.Lubm.html
+//! [`queries`]: fn.queries.html
+//! [`Query`]: ../../query/enum.Query.html
+
+use std::convert::Infallible;
+
+use sophia_api::ns::rdf;
+use sophia_api::term::{CopyTerm, SimpleIri, TTerm};
+use sophia_term::{BoxTerm, RcTerm};
+
+use super::SplitMix64;
+use crate::query::Query;
+
+/// A kind of faculty member: its name, its class,
+/// and the ranges of its number per department and of its number of publications.
+type FacultyKind = (
+    &'static str,
+    &'static SimpleIri<'static>,
+    (usize, usize),
+    (usize, usize),
+);
+
+/// The univ-bench namespace.
+mod ub {
+    sophia_api::namespace!(
+        "http://swat.cse.lehigh.edu/onto/univ-bench.owl#",
+        // classes
+        AssistantProfessor,
+        AssociateProfessor,
+        Chair,
+        Course,
+        Department,
+        Employee,
+        Faculty,
+        FullProfessor,
+        GraduateCourse,
+        GraduateStudent,
+        Lecturer,
+        Organization,
+        Person,
+        Professor,
+        Publication,
+        ResearchAssistant,
+        ResearchGroup,
+        Student,
+        TeachingAssistant,
+        UndergraduateStudent,
+        University,
+        // properties
+        advisor,
+        degreeFrom,
+        doctoralDegreeFrom,
+        emailAddress,
+        hasAlumnus,
+        headOf,
+        mastersDegreeFrom,
+        memberOf,
+        name,
+        publicationAuthor,
+        researchInterest,
+        subOrganizationOf,
+        takesCourse,
+        teacherOf,
+        teachingAssistantOf,
+        telephone,
+        undergraduateDegreeFrom,
+        worksFor
+    );
+}
+
+/// A generator of LUBM data.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Lubm {
+    /// The seed of the pseudo-random number generator.
+    pub seed: u64,
+    /// The number of universities.
+    pub universities: usize,
+    /// The number of departments per university;
+    /// if `None` (the default), it is drawn between 15 and 25 for each university, as in UBA.
+    pub departments: Option<usize>,
+    /// Whether to add the triples inferred from the univ-bench ontology
+    /// that are required to answer the LUBM queries (the default).
+    pub materialize: bool,
+}
+
+impl Default for Lubm {
+    fn default() -> Self {
+        Lubm {
+            seed: 0,
+            universities: 1,
+            departments: None,
+            materialize: true,
+        }
+    }
+}
+
+impl Lubm {
+    /// Generate the triples described by this generator.
+    ///
+    /// The returned iterator is a [`TripleSource`](../../triple/stream/trait.TripleSource.html),
+    /// that never fails.
+    pub fn triples(&self) -> impl Iterator<Item = Result<[BoxTerm; 3], Infallible>> {
+        let mut builder = Builder {
+            config: self,
+            rng: SplitMix64(self.seed),
+            triples: vec![],
+        };
+        for u in 0..self.universities {
+            builder.university(u);
+        }
+        builder.triples.into_iter().map(Ok)
+    }
+}
+
+struct Builder<'a> {
+    config: &'a Lubm,
+    rng: SplitMix64,
+    triples: Vec<[BoxTerm; 3]>,
+}
+
+impl<'a> Builder<'a> {
+    fn add<S, P, O>(&mut self, s: &S, p: &P, o: &O)
+    where
+        S: TTerm + ?Sized,
+        P: TTerm + ?Sized,
+        O: TTerm + ?Sized,
+    {
+        self.triples
+            .push([BoxTerm::copy(s), BoxTerm::copy(p), BoxTerm::copy(o)]);
+    }
+
+    fn add_type<S, C>(&mut self, s: &S, class: &C)
+    where
+        S: TTerm + ?Sized,
+        C: TTerm + ?Sized,
+    {
+        self.add(s, &rdf::type_, class);
+    }
+
+    /// Add inferred types, if `materialize` is set.
+    fn add_inferred_types<S: TTerm + ?Sized>(&mut self, s: &S, classes: &[&dyn TTerm]) {
+        if self.config.materialize {
+            for class in classes {
+                self.add_type(s, *class);
+            }
+        }
+    }
+
+    fn add_literal<S, P>(&mut self, s: &S, p: &P, txt: String)
+    where
+        S: TTerm + ?Sized,
+        P: TTerm + ?Sized,
+    {
+        self.add(s, p, &BoxTerm::from(txt));
+    }
+
+    /// A number between `min` and `max` (both included).
+    fn between(&mut self, (min, max): (usize, usize)) -> usize {
+        min + self.rng.below(max - min + 1)
+    }
+
+    /// A random university (among the generated ones).
+    fn any_university(&mut self) -> BoxTerm {
+        university_iri(self.rng.below(self.config.universities))
+    }
+
+    /// Add `person` `p` `univ`, where `p` is a sub-property of `ub:degreeFrom`.
+    fn add_degree<P: TTerm + ?Sized>(&mut self, person: &BoxTerm, p: &P) {
+        let univ = self.any_university();
+        self.add(person, p, &univ);
+        if self.config.materialize {
+            self.add(person, &ub::degreeFrom, &univ);
+            self.add(&univ, &ub::hasAlumnus, person);
+        }
+    }
+
+    fn university(&mut self, u: usize) {
+        let univ = university_iri(u);
+        self.add_type(&univ, &ub::University);
+        self.add_inferred_types(&univ, &[&ub::Organization]);
+        self.add_literal(&univ, &ub::name, format!("University{}", u));
+        let departments = match self.config.departments {
+            Some(n) => n,
+            None => self.between((15, 25)),
+        };
+        for d in 0..departments {
+            self.department(u, d, &univ);
+        }
+    }
+
+    fn department(&mut self, u: usize, d: usize, univ: &BoxTerm) {
+        let domain = format!("Department{}.University{}.edu", d, u);
+        let dept = BoxTerm::new_iri_unchecked(format!("http://www.{}", domain));
+        let iri =
+            |local: &str| BoxTerm::new_iri_unchecked(format!("http://www.{}/{}", domain, local));
+        self.add_type(&dept, &ub::Department);
+        self.add_inferred_types(&dept, &[&ub::Organization]);
+        self.add_literal(&dept, &ub::name, format!("Department{}", d));
+        self.add(&dept, &ub::subOrganizationOf, univ);
+
+        for i in 0..self.between((10, 20)) {
+            let group = iri(&format!("ResearchGroup{}", i));
+            self.add_type(&group, &ub::ResearchGroup);
+            self.add_inferred_types(&group, &[&ub::Organization]);
+            self.add(&group, &ub::subOrganizationOf, &dept);
+            if self.config.materialize {
+                self.add(&group, &ub::subOrganizationOf, univ);
+            }
+        }
+
+        // faculty (with their number per department, and their number of publications),
+        // and the courses they teach
+        let kinds: [FacultyKind; 4] = [
+            ("FullProfessor", &ub::FullProfessor, (7, 10), (15, 20)),
+            (
+                "AssociateProfessor",
+                &ub::AssociateProfessor,
+                (10, 14),
+                (10, 18),
+            ),
+            (
+                "AssistantProfessor",
+                &ub::AssistantProfessor,
+                (8, 11),
+                (5, 10),
+            ),
+            ("Lecturer", &ub::Lecturer, (5, 7), (0, 5)),
+        ];
+        let mut faculty = 0;
+        let mut professors = vec![];
+        let mut courses = vec![];
+        let mut graduate_courses = vec![];
+        for (kind, class, count, publications) in kinds.iter().copied() {
+            let is_professor = kind != "Lecturer";
+            for i in 0..self.between(count) {
+                faculty += 1;
+                let name = format!("{}{}", kind, i);
+                let person = iri(&name);
+                self.add_type(&person, class);
+                if is_professor {
+                    self.add_inferred_types(&person, &[&ub::Professor]);
+                    professors.push(person.clone());
+                }
+                self.add_inferred_types(&person, &[&ub::Faculty, &ub::Employee, &ub::Person]);
+                self.person(&person, &name, &domain);
+                self.add_degree(&person, &ub::undergraduateDegreeFrom);
+                self.add_degree(&person, &ub::mastersDegreeFrom);
+                self.add_degree(&person, &ub::doctoralDegreeFrom);
+                self.add(&person, &ub::worksFor, &dept);
+                if self.config.materialize {
+                    self.add(&person, &ub::memberOf, &dept);
+                }
+                if kind == "FullProfessor" && i == 0 {
+                    self.add(&person, &ub::headOf, &dept);
+                    self.add_inferred_types(&person, &[&ub::Chair]);
+                }
+                let interest = self.rng.below(100);
+                self.add_literal(
+                    &person,
+                    &ub::researchInterest,
+                    format!("Research{}", interest),
+                );
+                for _ in 0..self.between((1, 2)) {
+                    let name = format!("Course{}", courses.len());
+                    let course = iri(&name);
+                    self.add_type(&course, &ub::Course);
+                    self.add_literal(&course, &ub::name, name);
+                    self.add(&person, &ub::teacherOf, &course);
+                    courses.push(course);
+                }
+                if is_professor {
+                    for _ in 0..self.between((1, 2)) {
+                        let name = format!("GraduateCourse{}", graduate_courses.len());
+                        let course = iri(&name);
+                        self.add_type(&course, &ub::GraduateCourse);
+                        self.add_inferred_types(&course, &[&ub::Course]);
+                        self.add_literal(&course, &ub::name, name);
+                        self.add(&person, &ub::teacherOf, &course);
+                        graduate_courses.push(course);
+                    }
+                }
+                for j in 0..self.between(publications) {
+                    let publication = iri(&format!("{}/Publication{}", name, j));
+                    self.add_type(&publication, &ub::Publication);
+                    self.add_literal(&publication, &ub::name, format!("Publication{}", j));
+                    self.add(&publication, &ub::publicationAuthor, &person);
+                }
+            }
+        }
+
+        // students
+        for i in 0..faculty * self.between((8, 14)) {
+            let name = format!("UndergraduateStudent{}", i);
+            let person = iri(&name);
+            self.add_type(&person, &ub::UndergraduateStudent);
+            self.add_inferred_types(&person, &[&ub::Student, &ub::Person]);
+            self.person(&person, &name, &domain);
+            self.add(&person, &ub::memberOf, &dept);
+            for _ in 0..self.between((2, 4)) {
+                let course = &courses[self.rng.below(courses.len())];
+                self.add(&person, &ub::takesCourse, course);
+            }
+            if self.rng.below(5) == 0 {
+                let advisor = &professors[self.rng.below(professors.len())];
+                self.add(&person, &ub::advisor, advisor);
+            }
+        }
+        for i in 0..faculty * self.between((3, 4)) {
+            let name = format!("GraduateStudent{}", i);
+            let person = iri(&name);
+            self.add_type(&person, &ub::GraduateStudent);
+            // graduate students are students because they take courses
+            self.add_inferred_types(&person, &[&ub::Student, &ub::Person]);
+            self.person(&person, &name, &domain);
+            self.add(&person, &ub::memberOf, &dept);
+            self.add_degree(&person, &ub::undergraduateDegreeFrom);
+            for _ in 0..self.between((1, 3)) {
+                let course = &graduate_courses[self.rng.below(graduate_courses.len())];
+                self.add(&person, &ub::takesCourse, course);
+            }
+            let advisor = &professors[self.rng.below(professors.len())];
+            self.add(&person, &ub::advisor, advisor);
+            match self.rng.below(4) {
+                0 => {
+                    self.add_type(&person, &ub::TeachingAssistant);
+                    let course = &courses[self.rng.below(courses.len())];
+                    self.add(&person, &ub::teachingAssistantOf, course);
+                }
+                1 => self.add_type(&person, &ub::ResearchAssistant),
+                _ => (),
+            }
+        }
+    }
+
+    /// Add the name, email address and telephone of `person`.
+    fn person(&mut self, person: &BoxTerm, name: &str, domain: &str) {
+        self.add_literal(person, &ub::name, name.to_string());
+        self.add_literal(person, &ub::emailAddress, format!("{}@{}", name, domain));
+        self.add_literal(person, &ub::telephone, "xxx-xxx-xxxx".to_string());
+    }
+}
+
+fn university_iri(u: usize) -> BoxTerm {
+    BoxTerm::new_iri_unchecked(format!("http://www.University{}.edu", u))
+}
+
+/// The IRI of the univ-bench term `local`.
+fn ub_term(local: &str) -> RcTerm {
+    RcTerm::new_iri_unchecked(format!(
+        "http://swat.cse.lehigh.edu/onto/univ-bench.owl#{}",
+        local
+    ))
+}
+
+/// The 14 LUBM queries, with their names (`"Q1"` to `"Q14"`).
+///
+/// The queries only differ from the original ones
+/// in that they are expressed as basic graph patterns rather than SPARQL.
+pub fn queries() -> Vec<(&'static str, Query)> {
+    let x = RcTerm::new_variable("X").unwrap();
+    let y = RcTerm::new_variable("Y").unwrap();
+    let z = RcTerm::new_variable("Z").unwrap();
+    let type_ = RcTerm::copy(&rdf::type_);
+    let ub = ub_term;
+    let iri = |txt: &str| RcTerm::new_iri_unchecked(format!("http://www.{}", txt));
+    let univ0 = iri("University0.edu");
+    let dept0 = iri("Department0.University0.edu");
+    let gcourse0 = iri("Department0.University0.edu/GraduateCourse0");
+    let t = |s: &RcTerm, p: RcTerm, o: &RcTerm| [s.clone(), p, o.clone()];
+    vec![
+        (
+            "Q1",
+            vec![
+                t(&x, type_.clone(), &ub("GraduateStudent")),
+                t(&x, ub("takesCourse"), &gcourse0),
+            ],
+        ),
+        (
+            "Q2",
+            vec![
+                t(&x, type_.clone(), &ub("GraduateStudent")),
+                t(&y, type_.clone(), &ub("University")),
+                t(&z, type_.clone(), &ub("Department")),
+                t(&x, ub("memberOf"), &z),
+                t(&z, ub("subOrganizationOf"), &y),
+                t(&x, ub("undergraduateDegreeFrom"), &y),
+            ],
+        ),
+        (
+            "Q3",
+            vec![
+                t(&x, type_.clone(), &ub("Publication")),
+                t(
+                    &x,
+                    ub("publicationAuthor"),
+                    &iri("Department0.University0.edu/AssistantProfessor0"),
+                ),
+            ],
+        ),
+        (
+            "Q4",
+            vec![
+                t(&x, type_.clone(), &ub("Professor")),
+                t(&x, ub("worksFor"), &dept0),
+                t(&x, ub("name"), &RcTerm::new_variable("Y1").unwrap()),
+                t(&x, ub("emailAddress"), &RcTerm::new_variable("Y2").unwrap()),
+                t(&x, ub("telephone"), &RcTerm::new_variable("Y3").unwrap()),
+            ],
+        ),
+        (
+            "Q5",
+            vec![
+                t(&x, type_.clone(), &ub("Person")),
+                t(&x, ub("memberOf"), &dept0),
+            ],
+        ),
+        ("Q6", vec![t(&x, type_.clone(), &ub("Student"))]),
+        (
+            "Q7",
+            vec![
+                t(&x, type_.clone(), &ub("Student")),
+                t(&y, type_.clone(), &ub("Course")),
+                t(&x, ub("takesCourse"), &y),
+                t(
+                    &iri("Department0.University0.edu/AssociateProfessor0"),
+                    ub("teacherOf"),
+                    &y,
+                ),
+            ],
+        ),
+        (
+            "Q8",
+            vec![
+                t(&x, type_.clone(), &ub("Student")),
+                t(&y, type_.clone(), &ub("Department")),
+                t(&x, ub("memberOf"), &y),
+                t(&y, ub("subOrganizationOf"), &univ0),
+                t(&x, ub("emailAddress"), &z),
+            ],
+        ),
+        (
+            "Q9",
+            vec![
+                t(&x, type_.clone(), &ub("Student")),
+                t(&y, type_.clone(), &ub("Faculty")),
+                t(&z, type_.clone(), &ub("Course")),
+                t(&x, ub("advisor"), &y),
+                t(&y, ub("teacherOf"), &z),
+                t(&x, ub("takesCourse"), &z),
+            ],
+        ),
+        (
+            "Q10",
+            vec![
+                t(&x, type_.clone(), &ub("Student")),
+                t(&x, ub("takesCourse"), &gcourse0),
+            ],
+        ),
+        (
+            "Q11",
+            vec![
+                t(&x, type_.clone(), &ub("ResearchGroup")),
+                t(&x, ub("subOrganizationOf"), &univ0),
+            ],
+        ),
+        (
+            "Q12",
+            vec![
+                t(&x, type_.clone(), &ub("Chair")),
+                t(&y, type_.clone(), &ub("Department")),
+                t(&x, ub("worksFor"), &y),
+                t(&y, ub("subOrganizationOf"), &univ0),
+            ],
+        ),
+        (
+            "Q13",
+            vec![
+                t(&x, type_.clone(), &ub("Person")),
+                t(&univ0, ub("hasAlumnus"), &x),
+            ],
+        ),
+        ("Q14", vec![t(&x, type_, &ub("UndergraduateStudent"))]),
+    ]
+    .into_iter()
+    .map(|(name, triples)| (name, Query::Triples(triples)))
+    .collect()
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::triple::stream::TripleSource;
+    use std::collections::HashMap;
+
+    fn results(lubm: &Lubm) -> HashMap<&'static str, usize> {
+        let g: FastGraph = lubm.triples().collect_triples().unwrap();
+        queries()
+            .into_iter()
+            .map(|(name, mut query)| (name, query.process(&g).count()))
+            .collect()
+    }
+
+    // With materialized types, Q9 starts by combining all the faculty members with all the courses
+    // (Query orders triple patterns by their number of matches only),
+    // which takes several minutes in a debug build.
+    // Run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn queries_with_materialization() {
+        let lubm = Lubm {
+            departments: Some(1),
+            ..Lubm::default()
+        };
+        let results = results(&lubm);
+        assert_eq!(results.len(), 14);
+        for name in &["Q2", "Q4", "Q5", "Q8", "Q13"] {
+            assert!(results[name] > 0, "{}", name);
+        }
+        assert!((5..=10).contains(&results["Q3"]));
+        assert!(results["Q6"] > results["Q14"]);
+        assert!((10..=20).contains(&results["Q11"]));
+        assert_eq!(results["Q12"], 1);
+    }
+
+    #[test]
+    fn queries_without_materialization() {
+        let lubm = Lubm {
+            departments: Some(1),
+            materialize: false,
+            ..Lubm::default()
+        };
+        let results = results(&lubm);
+        assert!((5..=10).contains(&results["Q3"]));
+        for name in &["Q4", "Q5", "Q6", "Q12", "Q13"] {
+            assert_eq!(results[name], 0, "{}", name);
+        }
+        assert!(results["Q14"] > 0);
+    }
+
+    #[test]
+    fn deterministic() {
+        let lubm = Lubm {
+            universities: 2,
+            departments: Some(1),
+            ..Lubm::default()
+        };
+        let t1: Vec<_> = lubm.triples().map(Result::unwrap).collect();
+        let t2: Vec<_> = lubm.triples().map(Result::unwrap).collect();
+        assert_eq!(t1, t2);
+        let t3: Vec<_> = Lubm { seed: 1, ..lubm }
+            .triples()
+            .map(Result::unwrap)
+            .collect();
+        assert_ne!(t1, t3);
+    }
+}
@@ -0,0 +1,187 @@
+// this module defines the graph! macro
+
+/// Build a graph from a Turtle-like description.
+///
+/// The macro expands to a block creating an empty graph (with [`Default`])
+/// and [inserting](graph/trait.MutableGraph.html#tymethod.insert) each triple in it,
+/// so the type of the graph must be known from the context.
+/// Prefixed names refer to terms of [namespace modules](macro.namespace.html),
+/// e.g. `rdfs:label` is `rdfs::label`, which must be in scope.
+///
+/// The following terms are supported:
+/// * `prefix:suffix` for a term of a namespace module,
+/// * `a` for `rdf:type`,
+/// * `_:id` for a blank node,
+/// * `"text"@en`, `"text"@"en-US"` and `"text"^^prefix:suffix`
+///   for language-tagged and typed literals,
+/// * other Rust literals (e.g. `"text"`, `42`, `true`),
+///   converted to RDF literals with [`AsLiteral`](term/literal/convert/trait.AsLiteral.html),
+/// * `(expr)` for any Rust expression evaluating to a term.
+///
+/// As in Turtle, triples are terminated with `.`,
+/// and triples sharing the same subject (resp. subject and predicate)
+/// can be abbreviated with `;` (resp. `,`).
+///
+/// # Panics
+/// The generated code panics if the graph rejects an insertion,
+/// or if a language tag is invalid.
+///
+/// # Note
+/// Each term increases the depth of macro expansion,
+/// so large graphs may require to increase the `recursion_limit` of the crate.
+///
+/// # Example
+/// ```
+/// # use sophia::graph;
+/// # use sophia::graph::{inmem::FastGraph, Graph};
+/// # use sophia::ns::{rdfs, xsd};
+/// # use sophia::term::BoxTerm;
+/// mod foaf {
+///     sophia_api::namespace!("http://xmlns.com/foaf/0.1/", Person, knows, name, age);
+/// }
+/// mod ex {
+///     sophia_api::namespace!("http://example.org/", alice, bob);
+/// }
+/// let charlie = BoxTerm::new_iri("http://example.org/charlie")?;
+/// let g: FastGraph = graph! {
+///     ex:alice a foaf:Person;
+///         foaf:name "Alice", "Alicia"@es;
+///         foaf:age "42"^^xsd:integer;
+///         foaf:knows ex:bob, (charlie), _:someone.
+///     _:someone rdfs:label "someone".
+/// };
+/// assert_eq!(g.triples().count(), 8);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[macro_export]
+macro_rules! graph {
+    // parsing a subject
+    (@s $g:ident;) => {};
+    (@s $g:ident; $($rest:tt)+) => {
+        $crate::graph!(@term [@p $g;] $($rest)+)
+    };
+    // parsing a predicate
+    (@p $g:ident; [$($s:tt)*] $($rest:tt)+) => {
+        $crate::graph!(@term [@o $g; [$($s)*]] $($rest)+)
+    };
+    // parsing an object
+    (@o $g:ident; [$($s:tt)*] [$($p:tt)*] $($rest:tt)+) => {
+        $crate::graph!(@term [@sep $g; [$($s)*] [$($p)*]] $($rest)+)
+    };
+    // inserting a triple, and parsing the separator after it
+    (@sep $g:ident; [$($s:tt)*] [$($p:tt)*] [$($o:tt)*] $($rest:tt)*) => {
+        $crate::graph::MutableGraph::insert(&mut $g, &($($s)*), &($($p)*), &($($o)*))
+            .expect("graph! failed to insert a triple");
+        $crate::graph!(@next $g; [$($s)*] [$($p)*] $($rest)*);
+    };
+    (@next $g:ident; [$($s:tt)*] [$($p:tt)*]) => {};
+    (@next $g:ident; [$($s:tt)*] [$($p:tt)*] , $($rest:tt)+) => {
+        $crate::graph!(@o $g; [$($s)*] [$($p)*] $($rest)+)
+    };
+    (@next $g:ident; [$($s:tt)*] [$($p:tt)*] ; . $($rest:tt)*) => {
+        $crate::graph!(@s $g; $($rest)*)
+    };
+    (@next $g:ident; [$($s:tt)*] [$($p:tt)*] ; $($rest:tt)+) => {
+        $crate::graph!(@p $g; [$($s)*] $($rest)+)
+    };
+    (@next $g:ident; [$($s:tt)*] [$($p:tt)*] . $($rest:tt)*) => {
+        $crate::graph!(@s $g; $($rest)*)
+    };
+    // parsing a term, then passing it to the continuation $k
+    (@term [$($k:tt)*] $ns:ident : $suffix:ident $($rest:tt)*) => {
+        $crate::graph!($($k)* [$ns::$suffix] $($rest)*)
+    };
+    (@term [$($k:tt)*] a $($rest:tt)*) => {
+        $crate::graph!($($k)* [$crate::ns::rdf::type_] $($rest)*)
+    };
+    (@term [$($k:tt)*] _ : $id:ident $($rest:tt)*) => {
+        $crate::graph!($($k)* [$crate::term::BoxTerm::new_bnode_unchecked(stringify!($id))] $($rest)*)
+    };
+    (@term [$($k:tt)*] $txt:literal ^^ $ns:ident : $suffix:ident $($rest:tt)*) => {
+        $crate::graph!($($k)* [
+            $crate::term::BoxTerm::new_literal_dt($txt, $ns::$suffix.clone()).unwrap()
+        ] $($rest)*)
+    };
+    (@term [$($k:tt)*] $txt:literal @ $lang:ident $($rest:tt)*) => {
+        $crate::graph!($($k)* [
+            $crate::term::BoxTerm::new_literal_lang($txt, stringify!($lang))
+                .expect("graph! invalid language tag")
+        ] $($rest)*)
+    };
+    (@term [$($k:tt)*] $txt:literal @ $lang:literal $($rest:tt)*) => {
+        $crate::graph!($($k)* [
+            $crate::term::BoxTerm::new_literal_lang($txt, $lang).expect("graph! invalid language tag")
+        ] $($rest)*)
+    };
+    (@term [$($k:tt)*] $lit:literal $($rest:tt)*) => {
+        $crate::graph!($($k)* [
+            <$crate::term::BoxTerm as $crate::term::CopyTerm>::copy(
+                &$crate::term::literal::convert::AsLiteral::as_literal(&$lit)
+            )
+        ] $($rest)*)
+    };
+    (@term [$($k:tt)*] ($e:expr) $($rest:tt)*) => {
+        $crate::graph!($($k)* [$e] $($rest)*)
+    };
+    // entry point
+    ($($triples:tt)*) => {{
+        #[allow(unused_mut)] // an empty graph! is never mutated
+        let mut g = ::std::default::Default::default();
+        $crate::graph!(@s g; $($triples)*);
+        g
+    }};
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::{isomorphic_graphs, Graph};
+    use crate::ns::{rdf, rdfs, xsd};
+    use crate::term::BoxTerm;
+    use crate::triple::stream::TripleSource;
+
+    mod ex {
+        sophia_api::namespace!("http://example.org/", alice, bob, knows, name, age);
+    }
+
+    #[test]
+    fn same_as_turtle() -> Result<(), Box<dyn std::error::Error>> {
+        let carol = BoxTerm::new_iri("http://example.org/carol")?;
+        let g: FastGraph = graph! {
+            ex:alice a rdfs:Resource;
+                ex:name "Alice", "Alicia"@es, "Alice"@"en-US";
+                ex:age "42"^^xsd:integer;
+                ex:knows ex:bob, (carol), _:b1;
+            .
+            _:b1 ex:age 7; rdf:value true, 2.5.
+        };
+        let expected: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix ex: <http://example.org/>.
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            ex:alice a rdfs:Resource;
+                ex:name "Alice", "Alicia"@es, "Alice"@en-US;
+                ex:age 42;
+                ex:knows ex:bob, ex:carol, _:x.
+            _:x ex:age "7"^^xsd:int; rdf:value true, "2.5"^^xsd:double.
+            "#,
+        )
+        .collect_triples()?;
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn other_graph_types() {
+        let g: Vec<[BoxTerm; 3]> = graph! { ex:alice ex:knows ex:bob };
+        assert_eq!(g.len(), 1);
+        let g: FastGraph = graph! {};
+        assert_eq!(g.triples().count(), 0);
+    }
+}
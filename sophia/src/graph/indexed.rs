@@ -4,6 +4,7 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::hash::Hash;
 
+use super::inmem::Unsigned;
 use sophia_api::term::TTerm;
 use sophia_term::*;
 
@@ -19,6 +20,21 @@ use sophia_term::*;
 /// [`TermIndexMap`]: ../../term/index_map/trait.TermIndexMap.html
 /// [`impl_mutable_graph_for_indexed_graph!`]: ../../macro.impl_mutable_graph_for_indexed_graph.html
 ///
+/// # Node identifiers
+///
+/// When indices are unsigned integers (as in all the graphs of [`inmem`]),
+/// [`node_id`], [`term_of`] and [`triple_ids`] expose them as `usize`,
+/// for graph algorithms that work on integer identifiers
+/// (e.g. to build a [petgraph] graph, or to index a `Vec` of PageRank scores).
+/// The identifier of a term is stable as long as that term is used in the graph;
+/// once all the triples using it are removed, its identifier may be reused for another term.
+/// Identifiers are never 0, and are kept small by reusing free identifiers.
+///
+/// [`inmem`]: ../inmem/index.html
+/// [`node_id`]: #method.node_id
+/// [`term_of`]: #method.term_of
+/// [`triple_ids`]: #method.triple_ids
+/// [petgraph]: https://docs.rs/petgraph/
 pub trait IndexedGraph {
     /// The type used to represent terms internally.
     type Index: Copy + Eq + Hash;
@@ -32,6 +48,17 @@ pub trait IndexedGraph {
     /// Shrink the memory consumption of the graph as much as possible.
     fn shrink_to_fit(&mut self);
 
+    /// Renumber the indices of the terms, so that they are contiguous,
+    /// then [shrink](#tymethod.shrink_to_fit) the graph.
+    ///
+    /// The indices freed by removed triples are reused by later insertions,
+    /// but the memory they hold is never released;
+    /// after a heavy delete workload, this method reclaims it,
+    /// at the cost of rewriting every triple index.
+    ///
+    /// NB: the indices (and [node identifiers](#node-identifiers)) of terms may change.
+    fn compact(&mut self);
+
     /// Return the index for the given term, if it exists.
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -40,6 +67,48 @@ pub trait IndexedGraph {
     /// Return the term for the given index, if it exists.
     fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>>;
 
+    /// Iterate over the triples of this graph, as tuples of indices.
+    fn indexed_triples(&self) -> Box<dyn Iterator<Item = [Self::Index; 3]> + '_>;
+
+    /// Return the identifier of the given term, if it is used in this graph.
+    ///
+    /// See [Node identifiers](#node-identifiers).
+    fn node_id<T>(&self, t: &T) -> Option<usize>
+    where
+        T: TTerm + ?Sized,
+        Self::Index: Unsigned,
+    {
+        self.get_index(t).map(|i| i.as_usize())
+    }
+
+    /// Return the term with the given identifier, if any.
+    ///
+    /// See [Node identifiers](#node-identifiers).
+    fn term_of(&self, id: usize) -> Option<&Term<Self::TermData>>
+    where
+        Self::Index: Unsigned,
+    {
+        let i = Self::Index::from_usize(id);
+        if i.as_usize() == id {
+            self.get_term(i)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the triples of this graph, as identifiers of their subject, predicate and object.
+    ///
+    /// See [Node identifiers](#node-identifiers).
+    fn triple_ids(&self) -> Box<dyn Iterator<Item = [usize; 3]> + '_>
+    where
+        Self::Index: Unsigned,
+    {
+        Box::new(
+            self.indexed_triples()
+                .map(|[s, p, o]| [s.as_usize(), p.as_usize(), o.as_usize()]),
+        )
+    }
+
     /// Insert a triple in this Graph,
     /// and return the corresponding tuple of indices.
     fn insert_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[Self::Index; 3]>
@@ -193,5 +262,98 @@ where
 
 #[cfg(test)]
 mod test {
-    // Nothing really worth testing here
+    use super::*;
+    use crate::graph::inmem::{FastGraph, LightGraph};
+    use sophia_api::graph::MutableGraph;
+    use sophia_api::ns::rdf;
+
+    fn node_ids<G: IndexedGraph + MutableGraph>() -> Result<(), Box<dyn std::error::Error>>
+    where
+        G::Index: Unsigned,
+    {
+        let alice = BoxTerm::new_iri("http://example.org/alice")?;
+        let bob = BoxTerm::new_iri("http://example.org/bob")?;
+        let knows = BoxTerm::new_iri("http://example.org/knows")?;
+        let mut g = G::with_capacity(2);
+        g.insert(&alice, &knows, &bob).unwrap();
+        g.insert(&bob, &knows, &alice).unwrap();
+
+        let a = g.node_id(&alice).unwrap();
+        let b = g.node_id(&bob).unwrap();
+        let k = g.node_id(&knows).unwrap();
+        assert!(a != 0 && b != 0 && k != 0);
+        assert_eq!(g.node_id(&rdf::type_), None);
+        assert!(g.term_of(a).unwrap() == &alice);
+        assert!(g.term_of(b).unwrap() == &bob);
+        assert!(g.term_of(0).is_none());
+        assert!(g.term_of(usize::MAX).is_none());
+
+        let mut ids: Vec<_> = g.triple_ids().collect();
+        ids.sort_unstable();
+        let mut expected = vec![[a, k, b], [b, k, a]];
+        expected.sort_unstable();
+        assert_eq!(ids, expected);
+
+        // ids are stable as long as the terms are used
+        g.remove(&alice, &knows, &bob).unwrap();
+        assert_eq!(g.node_id(&alice), Some(a));
+        assert_eq!(g.triple_ids().collect::<Vec<_>>(), vec![[b, k, a]]);
+        g.remove(&bob, &knows, &alice).unwrap();
+        assert!(g.term_of(a).is_none());
+        assert_eq!(g.triple_ids().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn node_ids_fast() -> Result<(), Box<dyn std::error::Error>> {
+        node_ids::<FastGraph>()
+    }
+
+    #[test]
+    fn node_ids_light() -> Result<(), Box<dyn std::error::Error>> {
+        node_ids::<LightGraph>()
+    }
+
+    fn compact<G: IndexedGraph + MutableGraph>() -> Result<(), Box<dyn std::error::Error>>
+    where
+        G::Index: Unsigned,
+    {
+        let terms = (0..10)
+            .map(|i| BoxTerm::new_iri(format!("http://example.org/{}", i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let p = BoxTerm::new_iri("http://example.org/p")?;
+        let mut g = G::with_capacity(10);
+        for t in &terms {
+            g.insert(t, &p, t).unwrap();
+        }
+        for t in &terms[..8] {
+            g.remove(t, &p, t).unwrap();
+        }
+        assert_eq!(g.node_id(&terms[9]), Some(11));
+
+        g.compact();
+        let mut ids: Vec<_> = g.triple_ids().flat_map(|t| t.to_vec()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!(g.term_of(1).unwrap() == &p);
+        assert_eq!(g.triples_with_s(&terms[9]).count(), 1);
+        assert_eq!(g.triples_with_o(&terms[8]).count(), 1);
+        assert_eq!(g.triples_with_p(&p).count(), 2);
+
+        g.insert(&terms[0], &p, &terms[0]).unwrap();
+        assert_eq!(g.node_id(&terms[0]), Some(4));
+        assert_eq!(g.triples().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn compact_fast() -> Result<(), Box<dyn std::error::Error>> {
+        compact::<FastGraph>()
+    }
+
+    #[test]
+    fn compact_light() -> Result<(), Box<dyn std::error::Error>> {
+        compact::<LightGraph>()
+    }
 }
@@ -48,6 +48,8 @@ mod _ops_wrapper;
 pub use self::_ops_wrapper::*;
 mod _term_index_map_u;
 pub use self::_term_index_map_u::*;
+mod _frozen_term_index_u;
+pub use self::_frozen_term_index_u::*;
 
 /// A generic in-memory graph.
 ///
@@ -0,0 +1,255 @@
+// this module is transparently re-exported by its parent `graph::inmem`
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+use sophia_api::term::{term_eq, term_hash, TTerm};
+use sophia_term::factory::{FTerm, TermFactory};
+
+use super::Unsigned;
+
+/// An immutable, read-optimized version of [`TermIndexMapU`](struct.TermIndexMapU.html),
+/// obtained with [`TermIndexMapU::freeze`](struct.TermIndexMapU.html#method.freeze).
+///
+/// Every term keeps the index it had in the original map,
+/// so indices stored elsewhere remain valid.
+/// Term lookup relies on a minimal perfect hash function
+/// rather than on a hash map,
+/// which costs a few bytes per term instead of a full copy of each term.
+pub struct FrozenTermIndexU<I, F>
+where
+    F: TermFactory,
+{
+    i2t: Vec<Option<FTerm<F>>>,
+    seed: u64,
+    pilots: Vec<u32>,
+    slots: Vec<I>,
+    // i2t (index to term) is taken as is from the original TermIndexMapU
+    // seed, pilots and slots implement a "hash and displace" perfect hash:
+    // - each term is hashed (with seed) and assigned to a bucket,
+    // - each bucket has a pilot, chosen so that, combined with their hash,
+    //   it sends all the terms of that bucket to distinct slots,
+    // - there are exactly as many slots as terms,
+    //   and each slot contains the index of the term that lands in it.
+}
+
+impl<I, F> FrozenTermIndexU<I, F>
+where
+    I: Unsigned,
+    F: TermFactory,
+{
+    pub(super) fn new(i2t: Vec<Option<FTerm<F>>>) -> Self {
+        let mut seed = 0;
+        loop {
+            // building only fails if two terms have the same hash,
+            // which a different seed will most certainly fix
+            if let Some((pilots, slots)) = build_perfect_hash::<I, F>(&i2t, seed) {
+                return FrozenTermIndexU {
+                    i2t,
+                    seed,
+                    pilots,
+                    slots,
+                };
+            }
+            seed += 1;
+        }
+    }
+
+    /// The number of terms in this index.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether this index contains no term.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Return the index associated to the given term, if it exists.
+    pub fn get_index<T>(&self, t: &T) -> Option<I>
+    where
+        T: TTerm + ?Sized,
+    {
+        if self.slots.is_empty() {
+            return None;
+        }
+        let h = hash(t, self.seed);
+        let pilot = self.pilots[bucket_of(h, self.pilots.len())];
+        let i = self.slots[slot_of(h, pilot, self.slots.len())];
+        match &self.i2t[i.as_usize()] {
+            Some(u) if term_eq(u, t) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Return the term associated to the given index, if it exists.
+    pub fn get_term(&self, i: I) -> Option<&FTerm<F>> {
+        let i = i.as_usize();
+        if i < self.i2t.len() {
+            self.i2t[i].as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all the terms of this index, with their index.
+    pub fn iter(&self) -> impl Iterator<Item = (I, &FTerm<F>)> {
+        self.i2t
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| t.as_ref().map(|t| (I::from_usize(i), t)))
+    }
+}
+
+/// Average number of terms per bucket of the perfect hash function.
+const BUCKET_SIZE: usize = 4;
+
+/// Try to build the pilots and slots of a perfect hash function for all the terms in `i2t`.
+///
+/// Fail if a bucket can not be placed (which only happens on hash collisions).
+fn build_perfect_hash<I, F>(i2t: &[Option<FTerm<F>>], seed: u64) -> Option<(Vec<u32>, Vec<I>)>
+where
+    I: Unsigned,
+    F: TermFactory,
+{
+    let n = i2t.iter().filter(|t| t.is_some()).count();
+    let bucket_count = n / BUCKET_SIZE + 1;
+    let mut buckets = vec![Vec::new(); bucket_count];
+    for (i, t) in i2t.iter().enumerate() {
+        if let Some(t) = t {
+            let h = hash(t, seed);
+            buckets[bucket_of(h, bucket_count)].push((i, h));
+        }
+    }
+    // placing big buckets first, while most slots are still free,
+    // makes it much easier to find a pilot for every bucket
+    let mut order: Vec<usize> = (0..bucket_count).collect();
+    order.sort_by_key(|b| Reverse(buckets[*b].len()));
+
+    let max_pilot = (n as u64 * 64 + 1024).min(u32::MAX as u64) as u32;
+    let mut pilots = vec![0; bucket_count];
+    // NB: as 0 is the null index for TermIndexMapU, it denotes free slots
+    let mut slots = vec![I::ZERO; n];
+    let mut positions = Vec::with_capacity(BUCKET_SIZE);
+    for b in order {
+        let bucket = &buckets[b];
+        if bucket.is_empty() {
+            break;
+        }
+        let pilot = (0..max_pilot).find(|pilot| {
+            positions.clear();
+            for (_, h) in bucket {
+                let pos = slot_of(*h, *pilot, n);
+                if slots[pos] != I::ZERO || positions.contains(&pos) {
+                    return false;
+                }
+                positions.push(pos);
+            }
+            true
+        })?;
+        pilots[b] = pilot;
+        for ((i, _), pos) in bucket.iter().zip(&positions) {
+            slots[*pos] = I::from_usize(*i);
+        }
+    }
+    Some((pilots, slots))
+}
+
+fn hash<T>(t: &T, seed: u64) -> u64
+where
+    T: TTerm + ?Sized,
+{
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(seed);
+    term_hash(t, &mut hasher);
+    hasher.finish()
+}
+
+/// The finalizer of SplitMix64, ensuring that every bit of the input affects the output.
+#[inline]
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[inline]
+fn bucket_of(h: u64, bucket_count: usize) -> usize {
+    (mix(h) % bucket_count as u64) as usize
+}
+
+#[inline]
+fn slot_of(h: u64, pilot: u32, slot_count: usize) -> usize {
+    let displacement = (pilot as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    (mix(h ^ displacement) % slot_count as u64) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::TermIndexMapU;
+    use sophia_api::ns::{rdf, xsd};
+    use sophia_term::factory::RcTermFactory;
+    use sophia_term::index_map::TermIndexMap;
+    use sophia_term::*;
+
+    #[test]
+    fn freeze_empty() {
+        let ti = TermIndexMapU::<u16, RcTermFactory>::default();
+        let frozen = ti.freeze();
+        assert!(frozen.is_empty());
+        assert_eq!(frozen.get_index(&rdf::type_), None);
+        assert_eq!(frozen.get_term(0), None);
+        assert_eq!(frozen.get_term(1), None);
+    }
+
+    #[test]
+    fn freeze_preserves_indices() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+        let s = ti.make_index(&rdf::subject.into());
+        let p = ti.make_index(&rdf::predicate.into());
+        let o = ti.make_index(&rdf::object.into());
+        let lit = RefTerm::new_literal_lang_unchecked("chat", "en-US");
+        let l = ti.make_index(&lit);
+        ti.dec_ref(p);
+
+        let frozen = ti.freeze();
+        assert_eq!(frozen.len(), 3);
+        assert_eq!(frozen.get_index(&rdf::subject), Some(s));
+        assert_eq!(frozen.get_index(&rdf::predicate), None);
+        assert_eq!(frozen.get_index(&rdf::object), Some(o));
+        assert_eq!(frozen.get_index(&lit), Some(l));
+        // language tags are case insensitive
+        let lit2 = RefTerm::new_literal_lang_unchecked("chat", "en-us");
+        assert_eq!(frozen.get_index(&lit2), Some(l));
+        assert_eq!(frozen.get_index(&xsd::string), None);
+
+        assert_eq!(frozen.get_term(0), None);
+        assert_eq!(frozen.get_term(p), None);
+        assert!(frozen.get_term(s).unwrap() == &rdf::subject);
+        assert!(frozen.get_term(o).unwrap() == &rdf::object);
+        assert_eq!(frozen.get_term(99), None);
+
+        let indices: Vec<_> = frozen.iter().map(|(i, _)| i).collect();
+        assert_eq!(indices, vec![s, o, l]);
+    }
+
+    #[test]
+    fn freeze_many() {
+        let mut ti = TermIndexMapU::<u32, RcTermFactory>::default();
+        let terms: Vec<_> = (0..5000)
+            .map(|i| RcTerm::new_iri(format!("http://example.org/{}", i)).unwrap())
+            .collect();
+        let indices: Vec<_> = terms
+            .iter()
+            .map(|t| ti.make_index(&t.as_ref_str()))
+            .collect();
+
+        let frozen = ti.freeze();
+        assert_eq!(frozen.len(), terms.len());
+        for (t, i) in terms.iter().zip(indices) {
+            assert_eq!(frozen.get_index(t), Some(i));
+        }
+        let other = RcTerm::new_iri("http://example.org/other").unwrap();
+        assert_eq!(frozen.get_index(&other), None);
+    }
+}
@@ -1,6 +1,6 @@
 // this module is transparently re-exported by its parent `graph::inmem`
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::hash::Hash;
 
@@ -80,6 +80,19 @@ where
         self.triples.shrink_to_fit();
     }
 
+    fn compact(&mut self) {
+        let moved: HashMap<_, _> = self.terms.compact().into_iter().collect();
+        if !moved.is_empty() {
+            let map = |i| *moved.get(&i).unwrap_or(&i);
+            let triples = std::mem::take(&mut self.triples);
+            self.triples = triples
+                .into_iter()
+                .map(|[s, p, o]| [map(s), map(p), map(o)])
+                .collect();
+        }
+        self.shrink_to_fit();
+    }
+
     #[inline]
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -93,6 +106,11 @@ where
         self.terms.get_term(i)
     }
 
+    #[inline]
+    fn indexed_triples(&self) -> Box<dyn Iterator<Item = [Self::Index; 3]> + '_> {
+        Box::new(self.triples.iter().copied())
+    }
+
     fn insert_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[Self::Index; 3]>
     where
         TS: TTerm + ?Sized,
@@ -2,10 +2,13 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
 
+use sophia_api::term::TTerm;
 use sophia_term::factory::{FTerm, TermFactory};
 use sophia_term::index_map::TermIndexMap;
 use sophia_term::*;
 
+use super::FrozenTermIndexU;
+
 /// An in-memory implementation of [`TermIndexMap`](../../term/index_map/trait.TermIndexMap.html)
 /// with unsigned integers as indices.
 pub struct TermIndexMapU<I, F>
@@ -47,6 +50,15 @@ where
     pub fn new() -> TermIndexMapU<I, F> {
         Self::default()
     }
+
+    /// Convert this map into an immutable [`FrozenTermIndexU`](struct.FrozenTermIndexU.html).
+    ///
+    /// All terms keep their index,
+    /// but lookups are faster and the memory footprint is smaller.
+    /// This is well suited for data that is loaded once and queried many times.
+    pub fn freeze(self) -> FrozenTermIndexU<I, F> {
+        FrozenTermIndexU::new(self.i2t)
+    }
 }
 
 impl<I, F> Default for TermIndexMapU<I, F>
@@ -155,6 +167,72 @@ where
         }
     }
 
+    fn compact(&mut self) -> Vec<(T, T)> {
+        let mut moved = Vec::new();
+        let mut next = 1;
+        for i in 1..self.i2t.len() {
+            if self.i2t[i].is_none() {
+                continue;
+            }
+            if i != next {
+                let t = self.i2t[i].take().unwrap();
+                let new = T::from_usize(next);
+                *self.t2i.get_mut(unsafe { &fake_static(&t) }).unwrap() = new;
+                self.i2t[next] = Some(t);
+                self.i2c[next] = self.i2c[i];
+                moved.push((T::from_usize(i), new));
+            }
+            next += 1;
+        }
+        self.i2t.truncate(next);
+        self.i2c.truncate(next);
+        self.set_next_free(T::from_usize(next));
+        self.shrink_to_fit();
+        moved
+    }
+
+    fn rewrite_terms<U, R>(&mut self, mut rewrite: R) -> Vec<(T, T)>
+    where
+        U: TTerm,
+        R: FnMut(&FTerm<F>) -> Option<U>,
+    {
+        let mut changed = Vec::new();
+        for (i, t) in self.i2t.iter().enumerate() {
+            if let Some(t) = t {
+                if let Some(u) = rewrite(t) {
+                    let u = self.factory.clone_term(&u);
+                    if u != *t {
+                        changed.push((i, u));
+                    }
+                }
+            }
+        }
+        // unregister all old terms first, so that terms can be swapped
+        for (i, _) in &changed {
+            let t = self.i2t[*i].as_ref().unwrap();
+            self.t2i.remove(unsafe { &fake_static(t) });
+        }
+        let mut merged = Vec::new();
+        for (i, u) in changed {
+            let ru = unsafe { fake_static(&u) };
+            match self.t2i.get(&ru).copied() {
+                Some(j) => {
+                    let count = self.i2c[j.as_usize()].as_usize() + self.i2c[i].as_usize();
+                    self.i2c[j.as_usize()] = T::from_usize(count);
+                    self.i2t[i] = None;
+                    self.i2c[i] = self.next_free();
+                    self.set_next_free(T::from_usize(i));
+                    merged.push((T::from_usize(i), j));
+                }
+                None => {
+                    self.t2i.insert(ru, T::from_usize(i));
+                    self.i2t[i] = Some(u);
+                }
+            }
+        }
+        merged
+    }
+
     fn shrink_to_fit(&mut self) {
         self.factory.shrink_to_fit();
         self.i2c.shrink_to_fit();
@@ -311,4 +389,65 @@ mod test {
         assert_eq!(ti.i2t.len(), 5);
         assert_eq!(ti.next_free(), 5);
     }
+
+    #[test]
+    fn test_rewrite_terms() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+
+        use sophia_api::ns::rdf;
+
+        let s = ti.make_index(&rdf::subject.into());
+        let p = ti.make_index(&rdf::predicate.into());
+        let o = ti.make_index(&rdf::object.into());
+        ti.inc_ref(o);
+
+        // swap subject and predicate, merge object into predicate
+        let merged = ti.rewrite_terms(|t| {
+            if t == &rdf::subject {
+                Some(rdf::predicate)
+            } else if t == &rdf::predicate || t == &rdf::object {
+                Some(rdf::subject)
+            } else {
+                None
+            }
+        });
+        assert_eq!(merged, vec![(o, p)]);
+        assert_eq!(ti.get_index(&rdf::predicate.into()), Some(s));
+        assert_eq!(ti.get_index(&rdf::subject.into()), Some(p));
+        assert_eq!(ti.get_index(&rdf::object.into()), None);
+        assert_eq!(ti.i2c[s as usize], 1);
+        assert_eq!(ti.i2c[p as usize], 3);
+        assert_eq!(ti.next_free(), o);
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+
+        use sophia_api::ns::rdf;
+
+        let terms = [rdf::subject, rdf::predicate, rdf::object, rdf::type_];
+        let idx: Vec<_> = terms
+            .iter()
+            .copied()
+            .map(|t| ti.make_index(&t.into()))
+            .collect();
+        ti.inc_ref(idx[3]);
+        ti.dec_ref(idx[0]);
+        ti.dec_ref(idx[2]);
+        assert_eq!(ti.i2t.len(), 5);
+
+        let moved = ti.compact();
+        assert_eq!(moved, vec![(idx[1], 1), (idx[3], 2)]);
+        assert_eq!(ti.i2t.len(), 3);
+        assert_eq!(ti.next_free(), 3);
+        assert_eq!(ti.get_index(&rdf::predicate.into()), Some(1));
+        assert_eq!(ti.get_index(&rdf::type_.into()), Some(2));
+        assert!(ti.get_term(2).unwrap() == &rdf::type_);
+        assert_eq!(ti.i2c[2], 2);
+        assert!(ti.compact().is_empty());
+
+        assert_eq!(ti.make_index(&rdf::subject.into()), 3);
+        assert_term_index_map_works(&mut ti);
+    }
 }
@@ -430,6 +430,18 @@ macro_rules! impl_indexed_graph_for_wrapper {
             self.igw_hook_shrink_to_fit();
         }
 
+        fn compact(&mut self) {
+            self.get_wrapped_mut().compact();
+            // indices may have changed, so the indexes of the wrapper are rebuilt
+            let wrapped = std::mem::replace(self.get_wrapped_mut(), T::with_capacity(0));
+            *self = Self::igw_wrap_empty(wrapped);
+            let triples: Vec<_> = self.get_wrapped().indexed_triples().collect();
+            for t in triples {
+                self.igw_hook_insert_indexed(&Some(t));
+            }
+            self.igw_hook_shrink_to_fit();
+        }
+
         #[inline]
         fn get_index<U_>(&self, t: &U_) -> Option<Self::Index>
         where
@@ -443,6 +455,11 @@ macro_rules! impl_indexed_graph_for_wrapper {
             self.get_wrapped().get_term(i)
         }
 
+        #[inline]
+        fn indexed_triples(&self) -> Box<dyn Iterator<Item = [Self::Index; 3]> + '_> {
+            self.get_wrapped().indexed_triples()
+        }
+
         fn insert_indexed<TS_, TP_, TO_>(
             &mut self,
             s: &TS_,
@@ -0,0 +1,330 @@
+//! A view of a multilingual graph, restricted to preferred languages.
+//!
+//! [`LangFilteredGraph`] wraps a graph, and hides the language-tagged literals
+//! that do not match a list of preferred [language ranges]
+//! (e.g. `["fr", "en"]`, where `"en"` also matches `"en-GB"`).
+//! For each subject and predicate, only the literals in the most preferred available language are kept;
+//! the other triples (including literals without a language tag) are left untouched.
+//! The special range `"*"` matches any language,
+//! so it can be used as a last resort, to keep the literals in any language
+//! when none of the preferred languages is available.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::Graph;
+//! # use sophia::graph::lang::LangFilteredGraph;
+//! # use sophia::term::{BoxTerm, CopyTerm, TTerm};
+//! # use sophia::triple::Triple;
+//! # use sophia::ns::rdfs;
+//! let s = BoxTerm::new_iri("http://example.org/cat")?;
+//! let label = BoxTerm::copy(&rdfs::label);
+//! let g = vec![
+//!     [s.clone(), label.clone(), BoxTerm::new_literal_lang("chat", "fr")?],
+//!     [s.clone(), label.clone(), BoxTerm::new_literal_lang("cat", "en")?],
+//!     [s.clone(), label.clone(), BoxTerm::new_literal_lang("Katze", "de")?],
+//! ];
+//! let view = LangFilteredGraph::new(g, vec!["en-US", "en", "fr"]);
+//! let labels: Vec<_> = view
+//!     .triples_with_sp(&s, &rdfs::label)
+//!     .map(|t| t.map(|t| t.o().value().to_string()))
+//!     .collect::<Result<_, _>>()?;
+//! assert_eq!(labels, vec!["cat"]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`LangFilteredGraph`]: struct.LangFilteredGraph.html
+//! [language ranges]: https://tools.ietf.org/html/rfc4647#section-2.1
+
+use sophia_api::graph::*;
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::TTerm;
+use sophia_api::triple::Triple;
+
+/// A [`Graph`] adapter hiding the literals that are not in the preferred languages.
+///
+/// See [module documentation](index.html).
+///
+/// NB: for each language-tagged literal that is not in the most preferred language,
+/// this adapter needs to check the other values of the same subject and predicate
+/// in the underlying graph; this is efficient only if the underlying graph is indexed.
+///
+/// [`Graph`]: ../trait.Graph.html
+pub struct LangFilteredGraph<G> {
+    graph: G,
+    languages: Vec<String>,
+}
+
+impl<G: Graph> LangFilteredGraph<G> {
+    /// Wrap `graph`, keeping only the literals in the given `languages`,
+    /// sorted by decreasing preference.
+    ///
+    /// Each language is a basic [language range](https://tools.ietf.org/html/rfc4647#section-2.1),
+    /// matched case-insensitively; `"*"` matches any language.
+    pub fn new<I>(graph: G, languages: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        LangFilteredGraph {
+            graph,
+            languages: languages.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The preferred languages of this view.
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// Borrow the underlying graph.
+    pub fn inner(&self) -> &G {
+        &self.graph
+    }
+
+    /// Unwrap this adapter to get the original graph.
+    pub fn unwrap(self) -> G {
+        self.graph
+    }
+
+    /// The index of the first preferred language matching `tag`, if any.
+    fn rank(&self, tag: &str) -> Option<usize> {
+        self.languages
+            .iter()
+            .position(|range| lang_matches(range, tag))
+    }
+
+    /// Whether the triple `(s, p, o)` of the underlying graph is visible in this view.
+    fn keep<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<G, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let rank = match o.language() {
+            None => return Ok(true),
+            Some(tag) => match self.rank(tag) {
+                None => return Ok(false),
+                Some(rank) => rank,
+            },
+        };
+        if rank > 0 {
+            for t in self.graph.triples_with_sp(s, p) {
+                let t = t?;
+                if let Some(other) = t.o().language().and_then(|tag| self.rank(tag)) {
+                    if other < rank {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Filter out the triples of `triples` that are not visible in this view.
+    fn filter<'s>(&'s self, triples: GTripleSource<'s, G>) -> GTripleSource<'s, Self> {
+        Box::new(triples.filter_map(move |res| {
+            let t = match res {
+                Ok(t) => t,
+                Err(err) => return Some(Err(err)),
+            };
+            match self.keep(t.s(), t.p(), t.o()) {
+                Ok(true) => Some(Ok(t)),
+                Ok(false) => None,
+                Err(err) => Some(Err(err)),
+            }
+        }))
+    }
+}
+
+impl<G: Graph> Graph for LangFilteredGraph<G> {
+    type Triple = G::Triple;
+    type Error = G::Error;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.filter(self.graph.triples())
+    }
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_s(s))
+    }
+    fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_p(p))
+    }
+    fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_o(o))
+    }
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_sp(s, p))
+    }
+    fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_so(s, o))
+    }
+    fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_po(p, o))
+    }
+    fn triples_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.filter(self.graph.triples_with_spo(s, p, o))
+    }
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Ok(self.graph.contains(s, p, o)? && self.keep(s, p, o)?)
+    }
+    fn triples_matching<'s, S, P, O>(
+        &'s self,
+        ms: &'s S,
+        mp: &'s P,
+        mo: &'s O,
+    ) -> GTripleSource<'s, Self>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        self.filter(self.graph.triples_matching(ms, mp, mo))
+    }
+}
+
+impl<G: SetGraph> SetGraph for LangFilteredGraph<G> {}
+
+/// Whether the language `tag` matches the basic language `range`
+/// (see [RFC 4647](https://tools.ietf.org/html/rfc4647#section-3.3.1)).
+fn lang_matches(range: &str, tag: &str) -> bool {
+    if range == "*" {
+        return true;
+    }
+    match tag.get(..range.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(range) => {
+            tag.len() == range.len() || tag.as_bytes()[range.len()] == b'-'
+        }
+        _ => false,
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::{rdfs, Namespace};
+    use sophia_api::term::CopyTerm;
+    use sophia_term::BoxTerm;
+    use test_case::test_case;
+
+    fn lang(txt: &str, tag: &str) -> BoxTerm {
+        BoxTerm::new_literal_lang(txt, tag).unwrap()
+    }
+
+    fn some_graph() -> Vec<[BoxTerm; 3]> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let label = BoxTerm::copy(&rdfs::label);
+        let cat = BoxTerm::copy(&ex.get("cat").unwrap());
+        let dog = BoxTerm::copy(&ex.get("dog").unwrap());
+        let hound = BoxTerm::copy(&ex.get("hound").unwrap());
+        let colour = BoxTerm::copy(&ex.get("colour").unwrap());
+        let chases = BoxTerm::copy(&ex.get("chases").unwrap());
+        vec![
+            [cat.clone(), label.clone(), lang("chat", "fr")],
+            [cat.clone(), label.clone(), lang("cat", "en")],
+            [cat.clone(), label.clone(), lang("Katze", "de")],
+            [
+                cat.clone(),
+                label.clone(),
+                BoxTerm::from("felis".to_string()),
+            ],
+            [cat.clone(), colour.clone(), lang("grey", "en-US")],
+            [cat.clone(), colour, lang("gray", "EN-gb")],
+            [cat, chases, dog.clone()],
+            [dog, label.clone(), lang("dog", "en")],
+            [hound, label, lang("Hund", "de")],
+        ]
+    }
+
+    fn objects<T, E>(triples: impl Iterator<Item = Result<T, E>>) -> Vec<String>
+    where
+        T: Triple,
+        E: std::fmt::Debug,
+    {
+        let mut v: Vec<_> = triples
+            .map(|t| t.unwrap().o().value().to_string())
+            .collect();
+        v.sort();
+        v
+    }
+
+    #[test_case("*", "fr", true ; "wildcard")]
+    #[test_case("en", "en", true ; "same")]
+    #[test_case("en", "EN-gb", true ; "prefix")]
+    #[test_case("EN-GB", "en-gb", true ; "case")]
+    #[test_case("en-GB", "en", false ; "more specific range")]
+    #[test_case("en", "eng", false ; "partial subtag")]
+    #[test_case("fr", "en", false ; "other")]
+    fn matching(range: &str, tag: &str, expected: bool) {
+        assert_eq!(lang_matches(range, tag), expected);
+    }
+
+    #[test]
+    fn preferred_languages() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let cat = ex.get("cat")?;
+        let view = LangFilteredGraph::new(some_graph(), vec!["fr", "en"]);
+        assert_eq!(view.triples().count(), 6);
+        assert_eq!(
+            objects(view.triples_with_s(&cat)),
+            vec!["chat", "felis", "gray", "grey", "http://example.org/dog"]
+        );
+        assert_eq!(objects(view.triples_with_s(&ex.get("dog")?)), vec!["dog"]);
+        assert_eq!(view.triples_with_s(&ex.get("hound")?).count(), 0);
+        assert!(view.contains(&cat, &rdfs::label, &lang("chat", "fr"))?);
+        assert!(!view.contains(&cat, &rdfs::label, &lang("cat", "en"))?);
+        assert!(!view.contains(&cat, &rdfs::label, &lang("chien", "fr"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn fallback() -> Result<(), Box<dyn std::error::Error>> {
+        let view = LangFilteredGraph::new(some_graph(), vec!["en", "*"]);
+        assert_eq!(
+            objects(view.triples_with_p(&rdfs::label)),
+            vec!["Hund", "cat", "dog", "felis"]
+        );
+        assert_eq!(view.languages(), &["en".to_string(), "*".to_string()]);
+        assert_eq!(view.unwrap().len(), 9);
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+//! Conversions between RDF graphs and [petgraph] graphs.
+//!
+//! This gives access to the graph algorithms of the petgraph ecosystem
+//! (shortest paths, connected components, topological sort...) over RDF data.
+//! The subjects and objects of the triples become the nodes,
+//! and each triple becomes an edge, labeled by its predicate.
+//! A [`TermMatcher`] restricts the conversion to some predicates
+//! (use [`ANY`] to keep all of them).
+//!
+//! * [`to_petgraph`] converts any [`Graph`] into a `petgraph::Graph` whose nodes and edges are terms;
+//! * [`to_csr`] converts an [`IndexedGraph`] into a compact [`Csr`]
+//!   whose nodes and edges are the [node identifiers] of the terms;
+//! * [`from_petgraph`] inserts the edges of a `petgraph::Graph` whose nodes and edges are terms
+//!   into a [`MutableGraph`].
+//!
+//! This module is only available with the `petgraph` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::petgraph::to_petgraph;
+//! # use sophia::term::BoxTerm;
+//! # use sophia::term::matcher::ANY;
+//! # use petgraph::algo::has_path_connecting;
+//! let ex = |s: &str| BoxTerm::new_iri(format!("http://example.org/{}", s));
+//! let g = vec![
+//!     [ex("a")?, ex("knows")?, ex("b")?],
+//!     [ex("b")?, ex("knows")?, ex("c")?],
+//!     [ex("d")?, ex("hates")?, ex("a")?],
+//! ];
+//! let (pg, nodes) = to_petgraph(&g, &ex("knows")?)?;
+//! assert_eq!(pg.edge_count(), 2);
+//! assert!(has_path_connecting(&pg, nodes[&ex("a")?], nodes[&ex("c")?], None));
+//! assert!(!nodes.contains_key(&ex("d")?));
+//! # let _ = to_petgraph(&g, &ANY)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [petgraph]: https://docs.rs/petgraph/
+//! [`TermMatcher`]: ../../term/matcher/trait.TermMatcher.html
+//! [`ANY`]: ../../term/matcher/constant.ANY.html
+//! [`to_petgraph`]: fn.to_petgraph.html
+//! [`to_csr`]: fn.to_csr.html
+//! [`from_petgraph`]: fn.from_petgraph.html
+//! [`Graph`]: ../trait.Graph.html
+//! [`MutableGraph`]: ../trait.MutableGraph.html
+//! [`IndexedGraph`]: ../indexed/trait.IndexedGraph.html
+//! [node identifiers]: ../indexed/trait.IndexedGraph.html#node-identifiers
+//! [`Csr`]: https://docs.rs/petgraph/0.5/petgraph/csr/struct.Csr.html
+
+use std::collections::HashMap;
+
+use petgraph::csr::Csr;
+use petgraph::graph::{IndexType, NodeIndex};
+use petgraph::{Directed, EdgeType};
+use sophia_api::graph::{GResult, Graph, MgResult, MutableGraph};
+use sophia_api::term::matcher::{TermMatcher, ANY};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+use super::indexed::IndexedGraph;
+use super::inmem::Unsigned;
+
+/// A petgraph graph whose nodes and edges are terms.
+pub type TermPetGraph = petgraph::Graph<BoxTerm, BoxTerm>;
+
+/// A compressed sparse row graph whose nodes and edges are [node identifiers].
+///
+/// Node weights are unused; edge weights are the identifiers of the predicates.
+///
+/// [node identifiers]: ../indexed/trait.IndexedGraph.html#node-identifiers
+pub type IdCsr = Csr<(), usize, Directed, usize>;
+
+/// Convert the triples of `graph` whose predicate matches `predicates` into a petgraph graph.
+///
+/// Return the petgraph graph, and the index of the node of each term.
+pub fn to_petgraph<G, M>(
+    graph: &G,
+    predicates: &M,
+) -> GResult<G, (TermPetGraph, HashMap<BoxTerm, NodeIndex>)>
+where
+    G: Graph,
+    M: TermMatcher + ?Sized,
+{
+    let mut pg = TermPetGraph::new();
+    let mut nodes = HashMap::new();
+    for t in graph.triples_matching(&ANY, predicates, &ANY) {
+        let t = t?;
+        let s = get_or_add_node(&mut pg, &mut nodes, t.s());
+        let o = get_or_add_node(&mut pg, &mut nodes, t.o());
+        pg.add_edge(s, o, BoxTerm::copy(t.p()));
+    }
+    Ok((pg, nodes))
+}
+
+fn get_or_add_node<T>(
+    pg: &mut TermPetGraph,
+    nodes: &mut HashMap<BoxTerm, NodeIndex>,
+    t: &T,
+) -> NodeIndex
+where
+    T: TTerm + ?Sized,
+{
+    let t = BoxTerm::copy(t);
+    match nodes.get(&t) {
+        Some(i) => *i,
+        None => {
+            let i = pg.add_node(t.clone());
+            nodes.insert(t, i);
+            i
+        }
+    }
+}
+
+/// Convert the triples of `graph` whose predicate matches `predicates`
+/// into a compressed sparse row graph of [node identifiers].
+///
+/// The index of each node in the returned graph is the identifier of the corresponding term,
+/// which can be retrieved with [`IndexedGraph::term_of`].
+/// As identifiers are not contiguous,
+/// the returned graph may contain isolated nodes that do not correspond to any subject or object.
+///
+/// [node identifiers]: ../indexed/trait.IndexedGraph.html#node-identifiers
+/// [`IndexedGraph::term_of`]: ../indexed/trait.IndexedGraph.html#method.term_of
+pub fn to_csr<G, M>(graph: &G, predicates: &M) -> IdCsr
+where
+    G: IndexedGraph,
+    G::Index: Unsigned,
+    M: TermMatcher + ?Sized,
+{
+    let mut matching: HashMap<usize, bool> = HashMap::new();
+    let mut edges: Vec<(usize, usize, usize)> = graph
+        .triple_ids()
+        .filter(|[_, p, _]| {
+            *matching.entry(*p).or_insert_with(|| {
+                graph
+                    .term_of(*p)
+                    .map(|t| predicates.matches(t))
+                    .unwrap_or(false)
+            })
+        })
+        .map(|[s, p, o]| (s, o, p))
+        .collect();
+    edges.sort_unstable();
+    IdCsr::from_sorted_edges(&edges).expect("edges are sorted")
+}
+
+/// Insert into `graph` a triple for each edge of `pg`,
+/// whose subject and object are the weights of its source and target nodes,
+/// and whose predicate is its weight.
+///
+/// Return the number of triples actually inserted.
+pub fn from_petgraph<N, E, Ty, Ix, G>(
+    pg: &petgraph::Graph<N, E, Ty, Ix>,
+    graph: &mut G,
+) -> MgResult<G, usize>
+where
+    N: TTerm,
+    E: TTerm,
+    Ty: EdgeType,
+    Ix: IndexType,
+    G: MutableGraph,
+{
+    let mut inserted = 0;
+    for e in pg.raw_edges() {
+        if graph.insert(&pg[e.source()], &e.weight, &pg[e.target()])? {
+            inserted += 1;
+        }
+    }
+    Ok(inserted)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::{FastGraph, LightGraph};
+    use petgraph::algo::{connected_components, dijkstra};
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::ns::Namespace;
+
+    fn some_graph() -> Vec<[BoxTerm; 3]> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let x = |suffix| BoxTerm::copy(&ex.get(suffix).unwrap());
+        vec![
+            [x("a"), x("next"), x("b")],
+            [x("b"), x("next"), x("c")],
+            [x("c"), x("next"), x("d")],
+            [x("a"), x("shortcut"), x("d")],
+            [x("e"), x("next"), x("f")],
+            [x("a"), x("label"), BoxTerm::from("A".to_string())],
+        ]
+    }
+
+    #[test]
+    fn terms() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let a = BoxTerm::copy(&ex.get("a")?);
+        let d = BoxTerm::copy(&ex.get("d")?);
+        let next = BoxTerm::copy(&ex.get("next")?);
+
+        let g = some_graph();
+        let (pg, nodes) = to_petgraph(&g, &ANY)?;
+        assert_eq!(pg.node_count(), 7);
+        assert_eq!(pg.edge_count(), 6);
+        assert_eq!(connected_components(&pg), 2);
+        let distances = dijkstra(&pg, nodes[&a], None, |_| 1);
+        assert_eq!(distances[&nodes[&d]], 1);
+
+        let (pg, nodes) = to_petgraph(&g, &next)?;
+        assert_eq!(pg.node_count(), 6);
+        assert_eq!(pg.edge_count(), 4);
+        let distances = dijkstra(&pg, nodes[&a], None, |_| 1);
+        assert_eq!(distances[&nodes[&d]], 3);
+        assert!(pg.edge_references().all(|e| e.weight() == &next));
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph();
+        let (pg, _) = to_petgraph(&g, &ANY)?;
+        let mut g2 = FastGraph::new();
+        assert_eq!(from_petgraph(&pg, &mut g2)?, 6);
+        assert!(isomorphic_graphs(&g, &g2)?);
+        assert_eq!(from_petgraph(&pg, &mut g2)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn ids() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = LightGraph::new();
+        g.insert_all(some_graph().triples())?;
+        let ex = Namespace::new("http://example.org/")?;
+        let csr = to_csr(&g, &[&ex.get("next")?, &ex.get("shortcut")?]);
+        assert_eq!(csr.edge_count(), 5);
+        let a = g.node_id(&ex.get("a")?).unwrap();
+        let d = g.node_id(&ex.get("d")?).unwrap();
+        let distances = dijkstra(&csr, a, None, |_| 1);
+        assert_eq!(distances[&d], 1);
+        for id in 0..csr.node_count() {
+            for (o, p) in csr.neighbors_slice(id).iter().zip(csr.edges_slice(id)) {
+                assert!(g.contains(
+                    g.term_of(id).unwrap(),
+                    g.term_of(*p).unwrap(),
+                    g.term_of(*o).unwrap()
+                )?);
+            }
+        }
+        assert_eq!(to_csr(&g, &ex.get("other")?).edge_count(), 0);
+        Ok(())
+    }
+}
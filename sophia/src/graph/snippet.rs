@@ -0,0 +1,180 @@
+//! Inserting Turtle snippets into existing graphs.
+//!
+//! The [`InsertTurtle`] trait, implemented by all [`MutableGraph`]s,
+//! parses a piece of Turtle and inserts the resulting triples in the graph,
+//! possibly using the base IRI and prefixes of the graph,
+//! as recorded in its [`GraphMetadata`].
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::metadata::GraphMetadata;
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::graph::snippet::InsertTurtle;
+//! let mut g = FastGraph::new();
+//! let mut metadata = GraphMetadata::default();
+//! metadata.base = Some("http://example.org/".into());
+//! metadata.add_prefix("ex", "http://example.org/ns#");
+//!
+//! g.insert_turtle_with("<alice> ex:knows <bob>; ex:name \"Alice\".", &metadata)?;
+//! g.insert_turtle("<http://example.org/bob> a <http://example.org/ns#Person>.")?;
+//! assert_eq!(g.triples().count(), 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`InsertTurtle`]: trait.InsertTurtle.html
+//! [`MutableGraph`]: ../trait.MutableGraph.html
+//! [`GraphMetadata`]: ../../dataset/metadata/struct.GraphMetadata.html
+
+use std::error::Error;
+
+use sophia_api::graph::MutableGraph;
+use sophia_api::parser::QuadParser;
+use thiserror::Error;
+
+use crate::dataset::metadata::GraphMetadata;
+use crate::parser::n3::{N3Error, N3Parser};
+
+/// Parse Turtle snippets into a graph.
+///
+/// Snippets are parsed with the [N3 parser](../../parser/n3/index.html),
+/// which accepts a superset of Turtle;
+/// snippets containing N3 formulas are rejected.
+/// The whole snippet is parsed before any triple is inserted,
+/// so that nothing is inserted if it is invalid.
+///
+/// See [module documentation](index.html).
+pub trait InsertTurtle: MutableGraph {
+    /// Parse `snippet`, and insert its triples in this graph.
+    ///
+    /// The snippet must declare the prefixes it uses,
+    /// and must not contain relative IRIs unless it declares a base IRI.
+    ///
+    /// Return the number of triples actually inserted
+    /// (i.e. not already present in this graph).
+    fn insert_turtle(&mut self, snippet: &str) -> SnippetResult<usize, Self::MutationError> {
+        self.insert_turtle_with(snippet, &GraphMetadata::default())
+    }
+
+    /// Parse `snippet`, using the base IRI and prefixes of `metadata`,
+    /// and insert its triples in this graph.
+    ///
+    /// The snippet can still declare its own prefixes and base IRI,
+    /// which take precedence over those of `metadata`.
+    ///
+    /// Return the number of triples actually inserted
+    /// (i.e. not already present in this graph).
+    fn insert_turtle_with(
+        &mut self,
+        snippet: &str,
+        metadata: &GraphMetadata,
+    ) -> SnippetResult<usize, Self::MutationError> {
+        let parser = N3Parser {
+            base: metadata.base.clone(),
+            reject_relative: true,
+            prefixes: metadata.prefixes.clone(),
+        };
+        let mut triples = vec![];
+        for quad in parser.parse_str(snippet) {
+            let ([s, p, o], g) = quad?;
+            if g.is_some() {
+                return Err(SnippetError::Formula);
+            }
+            triples.push([s, p, o]);
+        }
+        let mut inserted = 0;
+        for [s, p, o] in &triples {
+            if self.insert(s, p, o).map_err(SnippetError::Graph)? {
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+}
+
+impl<G: MutableGraph + ?Sized> InsertTurtle for G {}
+
+/// The error raised by [`InsertTurtle`](trait.InsertTurtle.html).
+#[derive(Debug, Error)]
+pub enum SnippetError<E: 'static + Error> {
+    /// The snippet is not valid.
+    #[error("Invalid snippet: {0}")]
+    Parse(#[from] N3Error),
+    /// The snippet contains N3 formulas.
+    #[error("Formulas are not allowed in Turtle snippets")]
+    Formula,
+    /// The graph raised an error.
+    #[error("Error in the graph: {0}")]
+    Graph(#[source] E),
+}
+
+/// The result type of [`InsertTurtle`](trait.InsertTurtle.html) methods.
+pub type SnippetResult<T, E> = Result<T, SnippetError<E>>;
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use sophia_term::BoxTerm;
+
+    fn ex(suffix: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    fn metadata() -> GraphMetadata {
+        let mut metadata = GraphMetadata {
+            base: Some("http://example.org/".into()),
+            ..GraphMetadata::default()
+        };
+        metadata.add_prefix("ex", "http://example.org/");
+        metadata
+    }
+
+    #[test]
+    fn insert() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        let n = g.insert_turtle_with("ex:a ex:p <b>, <c>. <b> ex:p ex:c.", &metadata())?;
+        assert_eq!(n, 3);
+        assert!(g.contains(&ex("a"), &ex("p"), &ex("c"))?);
+        assert!(g.contains(&ex("b"), &ex("p"), &ex("c"))?);
+
+        // already present triples are not counted
+        let n = g.insert_turtle_with("ex:a ex:p ex:b, ex:d.", &metadata())?;
+        assert_eq!(n, 1);
+
+        // the snippet's own declarations take precedence
+        let snippet =
+            "@prefix ex: <http://example.org/x/>. @base <http://example.org/y/>. ex:a ex:p <b>.";
+        g.insert_turtle_with(snippet, &metadata())?;
+        assert!(g.contains(&ex("x/a"), &ex("x/p"), &ex("y/b"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let mut g = FastGraph::new();
+        // unknown prefix
+        let err = g.insert_turtle("ex:a ex:p ex:b.").unwrap_err();
+        assert!(matches!(err, SnippetError::Parse(_)));
+        // relative IRI without base
+        let err = g
+            .insert_turtle("<a> <http://example.org/p> <b>.")
+            .unwrap_err();
+        assert!(matches!(err, SnippetError::Parse(_)));
+        // formula
+        let err = g
+            .insert_turtle_with("ex:a ex:p { ex:b ex:p ex:c }.", &metadata())
+            .unwrap_err();
+        assert!(matches!(err, SnippetError::Formula));
+        // syntax error, located in the snippet
+        match g.insert_turtle_with("ex:a ex:p ex:b.\nex:c ex:p .", &metadata()) {
+            Err(SnippetError::Parse(N3Error::Syntax { line, .. })) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(g.triples().count(), 0);
+    }
+}
@@ -0,0 +1,420 @@
+//! Structural summaries of graphs.
+//!
+//! A [`GraphSummary`] describes how properties are used in a graph:
+//!
+//! * its [characteristic sets], i.e. the distinct combinations of properties
+//!   used by the subjects of the graph, with the number of subjects using each of them;
+//!   they provide accurate estimates of the cardinality of star-shaped patterns
+//!   (see [`subjects_with`]);
+//! * its [class profiles], i.e. for each class,
+//!   the number of its instances and the properties used by them;
+//! * the overall usage of each property.
+//!
+//! The class profiles and property usages can be exported as a [VoID] description
+//! (with [`add_to`]), to be consumed by other tools.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::graph::snippet::InsertTurtle;
+//! # use sophia::graph::summary::GraphSummary;
+//! # use sophia::term::BoxTerm;
+//! let mut g = FastGraph::new();
+//! g.insert_turtle(r#"
+//!     @prefix : <http://example.org/>.
+//!     :alice a :Person; :name "Alice"; :knows :bob, :charlie.
+//!     :bob a :Person; :name "Bob".
+//! "#)?;
+//! let summary = GraphSummary::new(&g)?;
+//! assert_eq!(summary.characteristic_sets.len(), 2);
+//! let name = BoxTerm::new_iri("http://example.org/name")?;
+//! assert_eq!(summary.subjects_with(&[&name]), 2);
+//! let person = BoxTerm::new_iri("http://example.org/Person")?;
+//! assert_eq!(summary.class(&person).unwrap().instances, 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`GraphSummary`]: struct.GraphSummary.html
+//! [characteristic sets]: struct.CharacteristicSet.html
+//! [`subjects_with`]: struct.GraphSummary.html#method.subjects_with
+//! [class profiles]: struct.ClassProfile.html
+//! [VoID]: https://www.w3.org/TR/void/
+//! [`add_to`]: struct.GraphSummary.html#method.add_to
+
+use std::collections::HashMap;
+
+use sophia_api::graph::{Graph, MgResult, MutableGraph};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{term_cmp, term_eq, CopyTerm, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// The VoID namespace.
+mod void {
+    sophia_api::namespace!(
+        "http://rdfs.org/ns/void#",
+        Dataset,
+        class,
+        classPartition,
+        distinctSubjects,
+        entities,
+        property,
+        propertyPartition,
+        triples
+    );
+}
+
+/// The usage of a property, by all the subjects of a graph,
+/// or by the instances of a class.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyUsage {
+    /// The property.
+    pub property: BoxTerm,
+    /// The number of distinct subjects using the property.
+    pub subjects: usize,
+    /// The number of triples using the property.
+    pub triples: usize,
+}
+
+/// A distinct combination of properties, used by one or several subjects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharacteristicSet {
+    /// The properties of this set, sorted.
+    ///
+    /// Each subject counted in this set uses exactly these properties.
+    pub properties: Vec<BoxTerm>,
+    /// The number of subjects using exactly these properties.
+    pub subjects: usize,
+    /// The number of triples using each property (in the same order as `properties`).
+    pub triples: Vec<usize>,
+}
+
+impl CharacteristicSet {
+    /// Whether this set contains all the given properties.
+    pub fn contains_all<T: TTerm + ?Sized>(&self, properties: &[&T]) -> bool {
+        properties
+            .iter()
+            .all(|p| self.properties.iter().any(|q| term_eq(q, *p)))
+    }
+}
+
+/// The instances of a class, and the properties they use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassProfile {
+    /// The class.
+    pub class: BoxTerm,
+    /// The number of distinct instances of the class (subjects of `rdf:type`).
+    pub instances: usize,
+    /// The properties used by the instances of the class, sorted.
+    pub properties: Vec<PropertyUsage>,
+}
+
+/// A structural summary of a graph.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphSummary {
+    /// The number of triples in the graph.
+    pub triples: usize,
+    /// The characteristic sets of the graph,
+    /// sorted by decreasing number of subjects.
+    pub characteristic_sets: Vec<CharacteristicSet>,
+    /// The profiles of the classes of the graph, sorted.
+    pub classes: Vec<ClassProfile>,
+    /// The usage of all the properties of the graph, sorted.
+    pub properties: Vec<PropertyUsage>,
+}
+
+impl GraphSummary {
+    /// Compute the summary of `graph`.
+    ///
+    /// This requires a single pass over the triples of `graph`,
+    /// but keeps in memory the properties used by each subject.
+    pub fn new<G: Graph>(graph: &G) -> Result<Self, G::Error> {
+        let mut triples = 0;
+        let mut by_subject: HashMap<BoxTerm, Vec<(BoxTerm, usize)>> = HashMap::new();
+        let mut types: HashMap<BoxTerm, Vec<BoxTerm>> = HashMap::new();
+        for t in graph.triples() {
+            let t = t?;
+            triples += 1;
+            let s = BoxTerm::copy(t.s());
+            if term_eq(t.p(), &rdf::type_) {
+                types
+                    .entry(s.clone())
+                    .or_default()
+                    .push(BoxTerm::copy(t.o()));
+            }
+            let props = by_subject.entry(s).or_default();
+            match props.iter_mut().find(|(p, _)| term_eq(p, t.p())) {
+                Some((_, n)) => *n += 1,
+                None => props.push((BoxTerm::copy(t.p()), 1)),
+            }
+        }
+
+        let mut sets: HashMap<Vec<BoxTerm>, (usize, Vec<usize>)> = HashMap::new();
+        let mut classes: HashMap<BoxTerm, (usize, Usages)> = HashMap::new();
+        let mut properties = Usages::new();
+        for (s, mut props) in by_subject {
+            props.sort_by(|(p1, _), (p2, _)| term_cmp(p1, p2));
+            let (key, counts): (Vec<_>, Vec<_>) = props.iter().cloned().unzip();
+            let entry = sets
+                .entry(key)
+                .or_insert_with(|| (0, vec![0; counts.len()]));
+            entry.0 += 1;
+            for (total, n) in entry.1.iter_mut().zip(counts) {
+                *total += n;
+            }
+            for c in types.get(&s).into_iter().flatten() {
+                let entry = classes
+                    .entry(c.clone())
+                    .or_insert_with(|| (0, Usages::new()));
+                entry.0 += 1;
+                entry.1.add(&props);
+            }
+            properties.add(&props);
+        }
+
+        let mut characteristic_sets: Vec<_> = sets
+            .into_iter()
+            .map(|(properties, (subjects, triples))| CharacteristicSet {
+                properties,
+                subjects,
+                triples,
+            })
+            .collect();
+        characteristic_sets.sort_by(|cs1, cs2| {
+            cs2.subjects
+                .cmp(&cs1.subjects)
+                .then_with(|| cmp_terms(&cs1.properties, &cs2.properties))
+        });
+        let mut classes: Vec<_> = classes
+            .into_iter()
+            .map(|(class, (instances, usages))| ClassProfile {
+                class,
+                instances,
+                properties: usages.into_sorted_vec(),
+            })
+            .collect();
+        classes.sort_by(|c1, c2| term_cmp(&c1.class, &c2.class));
+        Ok(GraphSummary {
+            triples,
+            characteristic_sets,
+            classes,
+            properties: properties.into_sorted_vec(),
+        })
+    }
+
+    /// The number of subjects using (at least) all the given properties.
+    ///
+    /// This is the exact cardinality of the subject variable
+    /// in a star-shaped pattern using these properties.
+    pub fn subjects_with<T: TTerm + ?Sized>(&self, properties: &[&T]) -> usize {
+        self.characteristic_sets
+            .iter()
+            .filter(|cs| cs.contains_all(properties))
+            .map(|cs| cs.subjects)
+            .sum()
+    }
+
+    /// The profile of the given class, if it has any instance.
+    pub fn class<T: TTerm + ?Sized>(&self, class: &T) -> Option<&ClassProfile> {
+        self.classes.iter().find(|c| term_eq(&c.class, class))
+    }
+
+    /// The usage of the given property, if it is used in the graph.
+    pub fn property<T: TTerm + ?Sized>(&self, property: &T) -> Option<&PropertyUsage> {
+        self.properties
+            .iter()
+            .find(|u| term_eq(&u.property, property))
+    }
+
+    /// Insert a [VoID](https://www.w3.org/TR/void/) description of this summary in `graph`,
+    /// as a `void:Dataset` identified by `dataset`.
+    ///
+    /// The class profiles are described as class partitions,
+    /// and property usages as property partitions.
+    /// Characteristic sets have no VoID counterpart, and are not described.
+    ///
+    /// Partitions are identified by blank nodes labelled `summary_N`,
+    /// which must therefore not be used in `graph`.
+    pub fn add_to<G, T>(&self, graph: &mut G, dataset: &T) -> MgResult<G, ()>
+    where
+        G: MutableGraph,
+        T: TTerm + ?Sized,
+    {
+        let mut bnodes = 0;
+        let mut bnode = || {
+            bnodes += 1;
+            BoxTerm::new_bnode_unchecked(format!("summary_{}", bnodes))
+        };
+        graph.insert(dataset, &rdf::type_, &void::Dataset)?;
+        graph.insert(dataset, &void::triples, &integer(self.triples))?;
+        for c in &self.classes {
+            let partition = bnode();
+            graph.insert(dataset, &void::classPartition, &partition)?;
+            graph.insert(&partition, &void::class, &c.class)?;
+            graph.insert(&partition, &void::entities, &integer(c.instances))?;
+            for u in &c.properties {
+                let pp = bnode();
+                graph.insert(&partition, &void::propertyPartition, &pp)?;
+                add_usage(graph, &pp, u)?;
+            }
+        }
+        for u in &self.properties {
+            let pp = bnode();
+            graph.insert(dataset, &void::propertyPartition, &pp)?;
+            add_usage(graph, &pp, u)?;
+        }
+        Ok(())
+    }
+}
+
+/// Property usages, accumulated over several subjects.
+struct Usages(HashMap<BoxTerm, (usize, usize)>);
+
+impl Usages {
+    fn new() -> Self {
+        Usages(HashMap::new())
+    }
+
+    /// Add the properties of one subject, with their number of triples.
+    fn add(&mut self, props: &[(BoxTerm, usize)]) {
+        for (p, n) in props {
+            let entry = self.0.entry(p.clone()).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += n;
+        }
+    }
+
+    fn into_sorted_vec(self) -> Vec<PropertyUsage> {
+        let mut v: Vec<_> = self
+            .0
+            .into_iter()
+            .map(|(property, (subjects, triples))| PropertyUsage {
+                property,
+                subjects,
+                triples,
+            })
+            .collect();
+        v.sort_by(|u1, u2| term_cmp(&u1.property, &u2.property));
+        v
+    }
+}
+
+fn cmp_terms(t1: &[BoxTerm], t2: &[BoxTerm]) -> std::cmp::Ordering {
+    t1.iter()
+        .zip(t2)
+        .map(|(a, b)| term_cmp(a, b))
+        .find(|o| *o != std::cmp::Ordering::Equal)
+        .unwrap_or_else(|| t1.len().cmp(&t2.len()))
+}
+
+fn integer(n: usize) -> BoxTerm {
+    BoxTerm::new_literal_dt_unchecked(n.to_string(), xsd::integer)
+}
+
+fn add_usage<G: MutableGraph>(graph: &mut G, pp: &BoxTerm, u: &PropertyUsage) -> MgResult<G, ()> {
+    graph.insert(pp, &void::property, &u.property)?;
+    graph.insert(pp, &void::triples, &integer(u.triples))?;
+    graph.insert(pp, &void::distinctSubjects, &integer(u.subjects))?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::snippet::InsertTurtle;
+
+    const DATA: &str = r#"
+        @prefix : <http://example.org/>.
+        :alice a :Person, :Agent; :name "Alice"; :knows :bob, :charlie.
+        :bob a :Person; :name "Bob"; :knows :alice.
+        :charlie a :Person; :name "Charlie".
+        :acme a :Agent; :name "ACME".
+        :dave :name "Dave".
+    "#;
+
+    fn ex(suffix: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    fn summary() -> GraphSummary {
+        let mut g = FastGraph::new();
+        g.insert_turtle(DATA).unwrap();
+        GraphSummary::new(&g).unwrap()
+    }
+
+    #[test]
+    fn characteristic_sets() {
+        let s = summary();
+        assert_eq!(s.triples, 13);
+        let sets: Vec<_> = s
+            .characteristic_sets
+            .iter()
+            .map(|cs| (cs.properties.len(), cs.subjects, cs.triples.clone()))
+            .collect();
+        // sorted by decreasing number of subjects, then by properties:
+        // {knows, name, type}, {name, type}, {name}
+        assert_eq!(
+            sets,
+            vec![(3, 2, vec![3, 2, 3]), (2, 2, vec![2, 2]), (1, 1, vec![1])]
+        );
+        let (knows, name, type_) = (ex("knows"), ex("name"), BoxTerm::copy(&rdf::type_));
+        assert_eq!(
+            s.characteristic_sets[0].properties,
+            vec![knows.clone(), name.clone(), type_.clone()]
+        );
+        assert_eq!(s.subjects_with(&[&name]), 5);
+        assert_eq!(s.subjects_with(&[&name, &type_]), 4);
+        assert_eq!(s.subjects_with(&[&knows]), 2);
+        assert_eq!(s.subjects_with(&[&ex("age")]), 0);
+        assert_eq!(s.subjects_with::<BoxTerm>(&[]), 5);
+    }
+
+    #[test]
+    fn classes_and_properties() {
+        let s = summary();
+        let classes: Vec<_> = s
+            .classes
+            .iter()
+            .map(|c| (c.class.clone(), c.instances))
+            .collect();
+        assert_eq!(classes, vec![(ex("Agent"), 2), (ex("Person"), 3)]);
+        let person = s.class(&ex("Person")).unwrap();
+        let knows = &person.properties[0];
+        assert_eq!(
+            (&knows.property, knows.subjects, knows.triples),
+            (&ex("knows"), 2, 3)
+        );
+        assert_eq!(person.properties.len(), 3);
+        assert!(s.class(&ex("Thing")).is_none());
+
+        let name = s.property(&ex("name")).unwrap();
+        assert_eq!((name.subjects, name.triples), (5, 5));
+        let type_ = s.property(&rdf::type_).unwrap();
+        assert_eq!((type_.subjects, type_.triples), (4, 5));
+    }
+
+    #[test]
+    fn void_description() -> Result<(), Box<dyn std::error::Error>> {
+        let s = summary();
+        let mut g = FastGraph::new();
+        let dataset = ex("dataset");
+        s.add_to(&mut g, &dataset)?;
+        let partitions: Vec<_> = g
+            .triples_with_sp(&dataset, &void::classPartition)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(partitions.len(), 2);
+        // 2 triples for the dataset, 2 class partitions (3 triples each),
+        // and 3 + 3 + 3 property partitions (4 triples each)
+        let expected = 2 + 2 * 3 + (3 + 3 + 3) * 4;
+        assert_eq!(g.triples().count(), expected);
+        assert!(g.contains(&dataset, &void::triples, &integer(13))?);
+        Ok(())
+    }
+}
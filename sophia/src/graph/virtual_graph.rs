@@ -0,0 +1,407 @@
+//! A graph adapter synthesizing virtual triples on read.
+//!
+//! [`VirtualGraph`] wraps a graph, and exposes, in addition to its triples,
+//! triples that are *implied* by a few simple rules,
+//! without storing them in the underlying graph:
+//!
+//! * **inverse properties**: if `p` and `q` are declared inverse of each other
+//!   (e.g. with `owl:inverseOf`), then each triple `(s, p, o)` implies `(o, q, s)`,
+//!   and vice-versa;
+//! * **default values**: each instance of a class (according to `rdf:type`)
+//!   that has no value for a given property gets a default value for it.
+//!
+//! Virtual triples that are already present in the underlying graph are not repeated.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::Graph;
+//! # use sophia::graph::virtual_graph::VirtualGraph;
+//! # use sophia::term::BoxTerm;
+//! let alice = BoxTerm::new_iri("http://example.org/alice")?;
+//! let bob = BoxTerm::new_iri("http://example.org/bob")?;
+//! let parent = BoxTerm::new_iri("http://example.org/parent")?;
+//! let child = BoxTerm::new_iri("http://example.org/child")?;
+//! let g = vec![[bob.clone(), parent.clone(), alice.clone()]];
+//! let mut view = VirtualGraph::new(g);
+//! view.add_inverse(&parent, &child);
+//! assert!(view.contains(&alice, &child, &bob)?);
+//! assert_eq!(view.triples().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`VirtualGraph`]: struct.VirtualGraph.html
+
+use sophia_api::graph::*;
+use sophia_api::ns::{owl, rdf};
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::streaming_mode::{ByValue, StreamedTriple};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// A [`Graph`] adapter adding virtual inverse and default-value triples to another graph.
+///
+/// See [module documentation](index.html).
+///
+/// NB: virtual triples are computed by querying the underlying graph on every read,
+/// so this is efficient only if the underlying graph is indexed.
+/// Also, a virtual triple may be yielded more than once
+/// if several rules imply it (e.g. two classes of a resource providing the same default).
+///
+/// [`Graph`]: ../trait.Graph.html
+pub struct VirtualGraph<G> {
+    graph: G,
+    /// pairs `(a, b)` such that `(x, b, y)` implies `(y, a, x)`
+    inverses: Vec<(BoxTerm, BoxTerm)>,
+    /// triples `(class, property, value)`
+    defaults: Vec<(BoxTerm, BoxTerm, BoxTerm)>,
+}
+
+impl<G: Graph> VirtualGraph<G> {
+    /// Wrap `graph`, with no rule yet.
+    pub fn new(graph: G) -> Self {
+        VirtualGraph {
+            graph,
+            inverses: vec![],
+            defaults: vec![],
+        }
+    }
+
+    /// Declare `p` and `q` as inverse properties.
+    ///
+    /// A property can be declared as its own inverse, making it symmetric.
+    pub fn add_inverse<TP, TQ>(&mut self, p: &TP, q: &TQ) -> &mut Self
+    where
+        TP: TTerm + ?Sized,
+        TQ: TTerm + ?Sized,
+    {
+        if !self.is_inverse(p, q) {
+            self.inverses.push((BoxTerm::copy(p), BoxTerm::copy(q)));
+            if !term_eq(p, q) {
+                self.inverses.push((BoxTerm::copy(q), BoxTerm::copy(p)));
+            }
+        }
+        self
+    }
+
+    /// Declare as inverse all the pairs of properties linked by `owl:inverseOf` in `ontology`.
+    pub fn add_declared_inverses<O: Graph>(&mut self, ontology: &O) -> Result<&mut Self, O::Error> {
+        for t in ontology.triples_with_p(&owl::inverseOf) {
+            let t = t?;
+            self.add_inverse(t.s(), t.o());
+        }
+        Ok(self)
+    }
+
+    /// Whether `p` and `q` have been declared as inverse properties.
+    pub fn is_inverse<TP, TQ>(&self, p: &TP, q: &TQ) -> bool
+    where
+        TP: TTerm + ?Sized,
+        TQ: TTerm + ?Sized,
+    {
+        self.inverses
+            .iter()
+            .any(|(a, b)| term_eq(a, p) && term_eq(b, q))
+    }
+
+    /// Give `value` as the default value of `property`
+    /// for all the instances of `class`.
+    pub fn add_default<TC, TP, TV>(&mut self, class: &TC, property: &TP, value: &TV) -> &mut Self
+    where
+        TC: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TV: TTerm + ?Sized,
+    {
+        self.defaults.push((
+            BoxTerm::copy(class),
+            BoxTerm::copy(property),
+            BoxTerm::copy(value),
+        ));
+        self
+    }
+
+    /// Borrow the underlying graph.
+    pub fn inner(&self) -> &G {
+        &self.graph
+    }
+
+    /// Unwrap this adapter to get the original graph.
+    pub fn unwrap(self) -> G {
+        self.graph
+    }
+
+    /// The virtual triple `(y, a, x)` implied by the triple `(x, b, y)` of the underlying graph,
+    /// unless it is already present in the underlying graph.
+    fn inverse<T: Triple>(
+        &self,
+        res: GResult<G, T>,
+        a: &BoxTerm,
+    ) -> Option<GResult<G, [BoxTerm; 3]>> {
+        let t = match res {
+            Ok(t) => t,
+            Err(err) => return Some(Err(err)),
+        };
+        if t.o().kind() == TermKind::Literal {
+            return None;
+        }
+        match self.graph.contains(t.o(), a, t.s()) {
+            Ok(true) => None,
+            Ok(false) => Some(Ok([BoxTerm::copy(t.o()), a.clone(), BoxTerm::copy(t.s())])),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    /// The virtual triple `(s, property, value)`, unless `s` already has a value for `property`
+    /// (either in the underlying graph, or through an inverse property).
+    fn default_value<T>(
+        &self,
+        s: &T,
+        property: &BoxTerm,
+        value: &BoxTerm,
+    ) -> Option<GResult<G, [BoxTerm; 3]>>
+    where
+        T: TTerm + ?Sized,
+    {
+        match self.has_value(s, property) {
+            Ok(true) => None,
+            Ok(false) => Some(Ok([BoxTerm::copy(s), property.clone(), value.clone()])),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    /// Whether `s` has a value for `property`, ignoring default values.
+    fn has_value<T>(&self, s: &T, property: &BoxTerm) -> GResult<G, bool>
+    where
+        T: TTerm + ?Sized,
+    {
+        if let Some(res) = self.graph.triples_with_sp(s, property).next() {
+            res?;
+            return Ok(true);
+        }
+        for (_, b) in self.inverses.iter().filter(|(a, _)| term_eq(a, property)) {
+            if let Some(res) = self.graph.triples_with_po(b, s).next() {
+                res?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl<G: Graph> Graph for VirtualGraph<G> {
+    type Triple = ByValue<[BoxTerm; 3]>;
+    type Error = G::Error;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        let stored = self.graph.triples().map(|res| res.map(copy_triple));
+        let inverses = self.inverses.iter().flat_map(move |(a, b)| {
+            self.graph
+                .triples_with_p(b)
+                .filter_map(move |res| self.inverse(res, a))
+        });
+        let defaults = self
+            .defaults
+            .iter()
+            .flat_map(move |(class, property, value)| {
+                self.graph
+                    .triples_with_po(&rdf::type_, class)
+                    .filter_map(move |res| match res {
+                        Ok(t) => self.default_value(t.s(), property, value),
+                        Err(err) => Some(Err(err)),
+                    })
+            });
+        Box::new(
+            stored
+                .chain(inverses)
+                .chain(defaults)
+                .map(|res| res.map(StreamedTriple::by_value)),
+        )
+    }
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        let stored = self.graph.triples_with_s(s).map(|res| res.map(copy_triple));
+        let inverses = self.inverses.iter().flat_map(move |(a, b)| {
+            self.graph
+                .triples_with_po(b, s)
+                .filter_map(move |res| self.inverse(res, a))
+        });
+        let defaults = self
+            .defaults
+            .iter()
+            .filter_map(move |(class, property, value)| {
+                match self.graph.contains(s, &rdf::type_, class) {
+                    Ok(true) => self.default_value(s, property, value),
+                    Ok(false) => None,
+                    Err(err) => Some(Err(err)),
+                }
+            });
+        Box::new(
+            stored
+                .chain(inverses)
+                .chain(defaults)
+                .map(|res| res.map(StreamedTriple::by_value)),
+        )
+    }
+}
+
+/// Copy the terms of `t` into an owned triple.
+fn copy_triple<T: Triple>(t: T) -> [BoxTerm; 3] {
+    [
+        BoxTerm::copy(t.s()),
+        BoxTerm::copy(t.p()),
+        BoxTerm::copy(t.o()),
+    ]
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::Namespace;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn some_graph() -> Vec<[BoxTerm; 3]> {
+        let ex = Namespace::new("http://example.org/").unwrap();
+        let x_alice = BoxTerm::copy(&ex.get("alice").unwrap());
+        let x_bob = BoxTerm::copy(&ex.get("bob").unwrap());
+        let x_carol = BoxTerm::copy(&ex.get("carol").unwrap());
+        let x_person = BoxTerm::copy(&ex.get("Person").unwrap());
+        let x_parent = BoxTerm::copy(&ex.get("parent").unwrap());
+        let x_child = BoxTerm::copy(&ex.get("child").unwrap());
+        let x_knows = BoxTerm::copy(&ex.get("knows").unwrap());
+        let x_status = BoxTerm::copy(&ex.get("status").unwrap());
+        let type_ = BoxTerm::copy(&rdf::type_);
+        vec![
+            [x_alice.clone(), type_.clone(), x_person.clone()],
+            [x_bob.clone(), type_.clone(), x_person.clone()],
+            [x_carol.clone(), type_, x_person],
+            [x_bob.clone(), x_parent.clone(), x_alice.clone()],
+            [x_alice.clone(), x_child, x_bob.clone()],
+            [x_carol.clone(), x_parent.clone(), x_alice.clone()],
+            [x_alice.clone(), x_knows, x_carol],
+            [x_bob, x_status, BoxTerm::from("busy".to_string())],
+            [x_alice, x_parent, BoxTerm::from("unknown".to_string())],
+        ]
+    }
+
+    fn sorted<T, E>(triples: impl Iterator<Item = Result<T, E>>) -> Vec<String>
+    where
+        T: Triple,
+        E: std::fmt::Debug,
+    {
+        let mut v: Vec<_> = triples
+            .map(|t| {
+                let t = t.unwrap();
+                format!(
+                    "{} {} {}",
+                    BoxTerm::copy(t.s()),
+                    BoxTerm::copy(t.p()),
+                    BoxTerm::copy(t.o())
+                )
+            })
+            .collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn no_rule() {
+        let view = VirtualGraph::new(some_graph());
+        assert_eq!(sorted(view.triples()), sorted(some_graph().triples()));
+    }
+
+    #[test]
+    fn inverses() -> Res {
+        let ex = Namespace::new("http://example.org/")?;
+        let x_alice = ex.get("alice")?;
+        let x_carol = ex.get("carol")?;
+        let x_parent = ex.get("parent")?;
+        let x_child = ex.get("child")?;
+        let x_knows = ex.get("knows")?;
+
+        let mut view = VirtualGraph::new(some_graph());
+        view.add_inverse(&x_parent, &x_child)
+            .add_inverse(&x_knows, &x_knows)
+            .add_inverse(&x_child, &x_parent);
+        assert!(view.is_inverse(&x_child, &x_parent));
+        assert!(!view.is_inverse(&x_parent, &x_knows));
+        assert_eq!(view.inverses.len(), 3);
+
+        // (alice child carol) and (carol knows alice) are new;
+        // (alice child bob) is already stored;
+        // (alice parent "unknown") has no inverse
+        assert_eq!(view.triples().count(), 11);
+        assert!(view.contains(&x_alice, &x_child, &x_carol)?);
+        assert!(view.contains(&x_carol, &x_knows, &x_alice)?);
+        assert_eq!(
+            sorted(view.triples_with_s(&x_alice)),
+            vec![
+                "<http://example.org/alice> <http://example.org/child> <http://example.org/bob>",
+                "<http://example.org/alice> <http://example.org/child> <http://example.org/carol>",
+                "<http://example.org/alice> <http://example.org/knows> <http://example.org/carol>",
+                "<http://example.org/alice> <http://example.org/parent> \"unknown\"",
+                "<http://example.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Person>",
+            ]
+        );
+        assert_eq!(view.triples_with_p(&x_child).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn declared_inverses() -> Res {
+        let ex = Namespace::new("http://example.org/")?;
+        let x_alice = ex.get("alice")?;
+        let x_carol = ex.get("carol")?;
+        let x_parent = ex.get("parent")?;
+        let x_child = ex.get("child")?;
+
+        let ontology = vec![[x_parent, owl::inverseOf, x_child]];
+        let mut view = VirtualGraph::new(some_graph());
+        view.add_declared_inverses(&ontology)?;
+        assert!(view.is_inverse(&x_parent, &x_child));
+        assert!(view.is_inverse(&x_child, &x_parent));
+        assert!(view.contains(&x_alice, &x_child, &x_carol)?);
+        Ok(())
+    }
+
+    #[test]
+    fn defaults() -> Res {
+        let ex = Namespace::new("http://example.org/")?;
+        let x_alice = ex.get("alice")?;
+        let x_bob = ex.get("bob")?;
+        let x_carol = ex.get("carol")?;
+        let x_nobody = ex.get("nobody")?;
+        let x_person = ex.get("Person")?;
+        let x_parent = ex.get("parent")?;
+        let x_child = ex.get("child")?;
+        let x_status = ex.get("status")?;
+
+        let available = BoxTerm::from("available".to_string());
+        let mut view = VirtualGraph::new(some_graph());
+        view.add_default(&x_person, &x_status, &available)
+            .add_default(&x_person, &x_child, &x_nobody)
+            .add_inverse(&x_parent, &x_child);
+        assert_eq!(
+            sorted(view.triples_with_p(&x_status)),
+            vec![
+                "<http://example.org/alice> <http://example.org/status> \"available\"",
+                "<http://example.org/bob> <http://example.org/status> \"busy\"",
+                "<http://example.org/carol> <http://example.org/status> \"available\"",
+            ]
+        );
+        // alice has children, stored or virtual; carol only has a default child
+        assert!(!view.contains(&x_alice, &x_child, &x_nobody)?);
+        assert!(view.contains(&x_carol, &x_child, &x_nobody)?);
+        assert!(view.contains(&x_bob, &x_child, &x_nobody)?);
+        assert!(!view.contains(&x_bob, &x_status, &available)?);
+        assert_eq!(view.triples_with_s(&x_carol).count(), 4);
+        assert_eq!(view.triples_with_o(&x_nobody).count(), 2);
+        assert_eq!(view.unwrap().len(), 9);
+        Ok(())
+    }
+}
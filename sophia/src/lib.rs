@@ -65,23 +65,55 @@
 
 #![deny(missing_docs)]
 
+pub mod annotation;
+pub mod crawl;
+pub mod dcat;
+#[doc(hidden)]
+pub mod description;
+pub mod embedding;
+pub mod fetch;
+pub mod fixtures;
+pub mod generator;
+pub mod pipeline;
+pub mod quantity;
 pub mod query;
+pub mod rewrite;
+pub mod shape;
+#[cfg(feature = "time")]
+pub mod time;
 
 /// This module re-exports symbols from
 /// [`sophia_api::dataset`](https://docs.rs/sophia_api/latest/sophia_api/dataset/),
 /// and also provides some implementations of its traits.
 pub mod dataset {
     pub use sophia_api::dataset::*;
+    pub mod extract;
+    pub mod flaky;
+    pub mod flatten;
+    pub mod functional;
     pub mod indexed;
     pub mod inmem;
+    pub mod layered;
+    pub mod metadata;
+    pub mod mock;
+    #[cfg(feature = "store")]
+    pub mod store;
+    pub mod versioned;
 }
 /// This module re-exports symbols from
 /// [`sophia_api::graph`](https://docs.rs/sophia_api/latest/sophia_api/graph/),
 /// and also provides some implementations of its traits.
 pub mod graph {
     pub use sophia_api::graph::*;
+    mod _macro;
     pub mod indexed;
     pub mod inmem;
+    pub mod lang;
+    #[cfg(feature = "petgraph")]
+    pub mod petgraph;
+    pub mod snippet;
+    pub mod summary;
+    pub mod virtual_graph;
 }
 /// This module re-exports symbols from
 /// [`sophia_api::ns`](https://docs.rs/sophia_api/latest/sophia_api/ns/).
@@ -93,11 +125,31 @@ pub mod ns {
 /// and also provides some implementations of its traits.
 pub mod parser {
     pub use sophia_api::parser::*;
+    pub mod any;
+    #[cfg(feature = "async")]
+    pub mod asynchronous;
+    pub mod checkpoint;
+    pub mod config;
+    #[cfg(feature = "csvw")]
+    pub mod csvw;
+    pub mod decompress;
     pub mod gtrig;
+    pub mod gturtle;
+    pub mod hdt;
+    pub mod lenient;
+    pub mod n3;
     pub mod nq;
     pub mod nt;
+    pub mod ofn;
+    #[cfg(feature = "parallel")]
+    pub mod parallel;
+    #[cfg(feature = "json")]
+    pub mod rdf_json;
     pub mod rio_common;
+    pub mod thrift;
     pub mod trig;
+    #[cfg(feature = "xml")]
+    pub mod trix;
     pub mod turtle;
     #[cfg(feature = "xml")]
     pub mod xml;
@@ -105,6 +157,11 @@ pub mod parser {
     pub mod xml_legacy;
 }
 /// This module re-exports symbols from
+/// [`sophia_api::prefix`](https://docs.rs/sophia_api/latest/sophia_api/prefix/).
+pub mod prefix {
+    pub use sophia_api::prefix::*;
+}
+/// This module re-exports symbols from
 /// [`sophia_api::quad`](https://docs.rs/sophia_api/latest/sophia_api/quad/).
 pub mod quad {
     pub use sophia_api::quad::*;
@@ -114,9 +171,20 @@ pub mod quad {
 /// and also provides some implementations of its traits.
 pub mod serializer {
     pub use sophia_api::serializer::*;
+    pub mod any;
+    #[cfg(feature = "async")]
+    pub mod asynchronous;
+    pub mod config;
+    pub mod labeling;
     pub mod nq;
     pub mod nt;
+    mod parallel;
+    #[cfg(feature = "json")]
+    pub mod rdf_json;
     pub mod rio_common;
+    pub mod trig;
+    #[cfg(feature = "xml")]
+    pub mod trix;
     pub mod turtle;
     #[cfg(feature = "xml")]
     pub mod xml;
@@ -126,7 +194,10 @@ pub mod serializer {
 pub mod sparql {
     pub use sophia_api::sparql::*;
 }
-/// This module re-exports symbols from
+/// Utilities for the concrete syntaxes of RDF.
+pub mod syntax {
+    pub mod escape;
+}
 /// This module re-exports symbols from
 /// [`sophia_api::term`](https://docs.rs/sophia_api/latest/sophia_api/term/)
 /// and
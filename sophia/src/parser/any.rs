@@ -0,0 +1,883 @@
+//! Parsing data in any supported format,
+//! selected from a media type, a file extension, or the data itself.
+//!
+//! [`Format`] describes the formats supported by Sophia's parsers,
+//! with their media types and usual file extensions,
+//! and can guess the format of some data by sniffing its first bytes.
+//! [`AnyParser`] dispatches to the corresponding parser,
+//! and returns a boxed quad source,
+//! where triple-based formats produce quads in the default graph.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::parser::any::{self, Format};
+//! # use sophia::quad::stream::QuadSource;
+//! let format = Format::from_media_type("text/turtle; charset=utf-8").unwrap();
+//! assert_eq!(format, Format::Turtle);
+//! let d: FastDataset = any::parse_str("@prefix : <tag:>. :s :p :o.", format)?.collect_quads()?;
+//! assert_eq!(d.quads().count(), 1);
+//!
+//! // or let the format be guessed from the data
+//! let nq = "<tag:s> <tag:p> <tag:o> <tag:g>.\n";
+//! let d: FastDataset = any::parse_auto(nq.as_bytes())?.collect_quads()?;
+//! assert_eq!(d.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Format`]: enum.Format.html
+//! [`AnyParser`]: struct.AnyParser.html
+
+use std::cell::Cell;
+use std::error::Error;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::rc::Rc;
+
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::{Quad, TupleQuad};
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use sophia_term::iri::is_absolute_iri_ref;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::config::{
+    ByteCounter, LimitExceeded, LimitReader, ParserConfig, ProgressSource,
+};
+use crate::parser::decompress;
+use crate::parser::gtrig::GTriGParser;
+use crate::parser::gturtle::GTurtleParser;
+use crate::parser::n3::N3Parser;
+use crate::parser::trig::TriGParser;
+use crate::parser::turtle::TurtleParser;
+
+/// The RDF formats supported by this module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// [N-Triples](https://www.w3.org/TR/n-triples/)
+    NTriples,
+    /// [N-Quads](https://www.w3.org/TR/n-quads/)
+    NQuads,
+    /// [Turtle](https://www.w3.org/TR/turtle/)
+    Turtle,
+    /// [TriG](https://www.w3.org/TR/trig/)
+    TriG,
+    /// [Notation3](https://www.w3.org/TeamSubmission/n3/)
+    N3,
+    /// [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/) (requires the `xml` feature)
+    RdfXml,
+    /// [TriX](https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html) (requires the `xml` feature)
+    TriX,
+    /// [RDF/JSON](https://www.w3.org/TR/rdf-json/)
+    RdfJson,
+    /// [HDT](https://www.rdfhdt.org/)
+    Hdt,
+    /// [RDF Thrift](https://jena.apache.org/documentation/io/rdf-binary.html)
+    RdfThrift,
+}
+
+impl Format {
+    /// All formats.
+    pub const ALL: [Format; 10] = [
+        Format::NTriples,
+        Format::NQuads,
+        Format::Turtle,
+        Format::TriG,
+        Format::N3,
+        Format::RdfXml,
+        Format::TriX,
+        Format::RdfJson,
+        Format::Hdt,
+        Format::RdfThrift,
+    ];
+
+    /// The media types of this format, the first one being the canonical one.
+    pub fn media_types(self) -> &'static [&'static str] {
+        match self {
+            Format::NTriples => &["application/n-triples"],
+            Format::NQuads => &["application/n-quads", "text/x-nquads"],
+            Format::Turtle => &["text/turtle", "application/x-turtle"],
+            Format::TriG => &["application/trig", "application/x-trig"],
+            Format::N3 => &["text/n3", "text/rdf+n3"],
+            Format::RdfXml => &["application/rdf+xml"],
+            Format::TriX => &["application/trix"],
+            Format::RdfJson => &["application/rdf+json"],
+            Format::Hdt => &["application/vnd.hdt"],
+            Format::RdfThrift => &[crate::parser::thrift::MEDIA_TYPE],
+        }
+    }
+
+    /// The canonical media type of this format.
+    pub fn media_type(self) -> &'static str {
+        self.media_types()[0]
+    }
+
+    /// The usual file extensions (without dot) of this format, the first one being the preferred one.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::NTriples => &["nt"],
+            Format::NQuads => &["nq"],
+            Format::Turtle => &["ttl"],
+            Format::TriG => &["trig"],
+            Format::N3 => &["n3"],
+            Format::RdfXml => &["rdf", "owl"],
+            Format::TriX => &["trix"],
+            Format::RdfJson => &["rj"],
+            Format::Hdt => &["hdt"],
+            Format::RdfThrift => &["trdf", "rt"],
+        }
+    }
+
+    /// Whether this format is supported by the enabled features.
+    pub fn is_supported(self) -> bool {
+        // the arms are constant for a given set of features
+        #[allow(clippy::match_like_matches_macro)]
+        match self {
+            Format::RdfXml | Format::TriX => cfg!(feature = "xml"),
+            Format::RdfJson => cfg!(feature = "json"),
+            _ => true,
+        }
+    }
+
+    /// The format having the given media type, ignoring case and parameters (e.g. `charset`).
+    pub fn from_media_type(media_type: &str) -> Option<Format> {
+        let media_type = media_type.split(';').next()?.trim().to_ascii_lowercase();
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|f| f.media_types().contains(&media_type.as_str()))
+    }
+
+    /// The format having the given file extension (with or without leading dot), ignoring case.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|f| f.extensions().contains(&extension.as_str()))
+    }
+
+    /// The format of the file at `path`, based on its extension.
+    ///
+    /// The extension of a compressed file (e.g. `.gz` in `dump.nt.gz`) is ignored.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Format> {
+        let path = path.as_ref();
+        let extension = path.extension()?.to_str()?;
+        match extension {
+            "gz" | "bz2" | "xz" => Format::from_path(path.file_stem()?),
+            _ => Format::from_extension(extension),
+        }
+    }
+
+    /// Guess the format of some data, based on its first bytes.
+    ///
+    /// This is a heuristic, which should only be used when no media type or file name is available.
+    /// N-Triples data with no statement having a graph name is recognized as N-Triples,
+    /// even if it could be the beginning of an N-Quads document.
+    pub fn sniff(header: &[u8]) -> Option<Format> {
+        if header.starts_with(b"$HDT") {
+            return Some(Format::Hdt);
+        }
+        let txt = String::from_utf8_lossy(header);
+        let txt = txt.trim_start_matches('\u{feff}').trim_start();
+        if ["<?xml", "<!", "<rdf:RDF", "<TriX"]
+            .iter()
+            .any(|prefix| txt.starts_with(prefix))
+        {
+            return if txt.contains("<TriX") {
+                Some(Format::TriX)
+            } else if txt.contains("http://www.w3.org/1999/02/22-rdf-syntax-ns#") {
+                Some(Format::RdfXml)
+            } else {
+                None
+            };
+        }
+        if txt.starts_with('{') {
+            if txt.contains("\"@context\"") || txt.contains("\"@id\"") {
+                return None; // JSON-LD
+            }
+            return Some(Format::RdfJson);
+        }
+        sniff_text(txt)
+    }
+}
+
+/// Text formats, from the most specific to the most general.
+const TEXT_FORMATS: [Format; 4] = [
+    Format::NTriples,
+    Format::NQuads,
+    Format::Turtle,
+    Format::TriG,
+];
+
+/// Sniff text-based formats, line by line,
+/// keeping the most general format required by any line.
+fn sniff_text(txt: &str) -> Option<Format> {
+    // ignore the last line if it is (probably) truncated
+    let complete = match txt.rfind('\n') {
+        Some(i) => &txt[..i],
+        None => txt,
+    };
+    let mut rank = None;
+    for line in complete.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with("@keywords")
+            || line.starts_with("@forAll")
+            || line.starts_with("@forSome")
+            || line.contains("=>")
+        {
+            return Some(Format::N3);
+        }
+        let line_rank = match count_terms(line) {
+            Some(3) => 0,
+            Some(4) => 1,
+            _ if line.contains('{') || line.to_ascii_lowercase().starts_with("graph ") => 3,
+            _ => 2,
+        };
+        rank = rank.max(Some(line_rank));
+    }
+    rank.map(|r| TEXT_FORMATS[r])
+}
+
+/// Count the terms of an N-Triples/N-Quads statement,
+/// or return `None` if `line` is not such a statement.
+fn count_terms(line: &str) -> Option<usize> {
+    let mut rest = line.trim_start();
+    let mut count = 0;
+    loop {
+        rest = rest.trim_start();
+        if rest.starts_with('<') {
+            rest = &rest[rest.find('>')? + 1..];
+        } else if rest.starts_with("_:") {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            rest = &rest[rest[..end].trim_end_matches('.').len()..];
+        } else if rest.starts_with('"') {
+            let mut escaped = false;
+            let end = rest[1..].find(|c: char| {
+                let end = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                end
+            })?;
+            rest = &rest[end + 2..];
+            if rest.starts_with('@') {
+                let end = rest[1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+                    .map_or(rest.len(), |i| i + 1);
+                rest = &rest[end..];
+            } else if rest.starts_with("^^<") {
+                rest = &rest[rest.find('>')? + 1..];
+            }
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let after = after.trim_start();
+            return if after.is_empty() || after.starts_with('#') {
+                Some(count)
+            } else {
+                None
+            };
+        } else {
+            return None;
+        }
+        count += 1;
+    }
+}
+
+/// A boxed quad source, as returned by the functions of this module.
+///
+/// Triple-based formats produce quads in the default graph.
+pub type AnySource<'a> = Box<dyn Iterator<Item = Result<TupleQuad<BoxTerm>, AnyError>> + 'a>;
+
+/// A parser for any supported [format](enum.Format.html).
+///
+/// The [configuration](../config/struct.ParserConfig.html) is applied to every format:
+/// format-specific options are passed to the underlying parser,
+/// while IRI validation, blank node prefixing and graph naming are applied to the parsed quads,
+/// and progress is reported while they are parsed.
+#[derive(Clone, Debug)]
+pub struct AnyParser {
+    /// The format of the data to parse.
+    pub format: Format,
+    /// The configuration of this parser.
+    pub config: ParserConfig,
+}
+
+impl AnyParser {
+    /// Build a parser for the given format, with the default configuration.
+    pub fn new(format: Format) -> Self {
+        Self::with_config(format, ParserConfig::default())
+    }
+
+    /// Build a parser for the given format, with the given configuration.
+    pub fn with_config(format: Format, config: ParserConfig) -> Self {
+        AnyParser { format, config }
+    }
+
+    /// Parse `data`.
+    ///
+    /// # Errors
+    /// Fails if the format is not supported by the enabled features.
+    pub fn parse<'a, B: BufRead + 'a>(&self, data: B) -> Result<AnySource<'a>, AnyError> {
+        match self.config.progress() {
+            Some(reporter) => {
+                let bytes = Rc::new(Cell::new(0));
+                let source = self.parse_unreported(ByteCounter::new(data, bytes.clone()))?;
+                Ok(Box::new(ProgressSource::new(
+                    source,
+                    bytes,
+                    reporter.clone(),
+                )))
+            }
+            None => self.parse_unreported(data),
+        }
+    }
+
+    /// Parse `data`, applying every option of the configuration except progress reporting.
+    fn parse_unreported<'a, B: BufRead + 'a>(&self, data: B) -> Result<AnySource<'a>, AnyError> {
+        let config = &self.config;
+        let graph = match config.graph_naming() {
+            Some(naming) => {
+                let document = config.base().ok_or(AnyError::NoDocumentIri)?;
+                let name = naming.name(document);
+                match BoxTerm::new_iri(name.clone()) {
+                    Ok(graph) => Some(graph),
+                    Err(_) => return Err(AnyError::InvalidIri(name)),
+                }
+            }
+            None => None,
+        };
+        let source: AnySource<'a> = match (self.format, config.limits()) {
+            (Format::NTriples, Some(limits))
+            | (Format::NQuads, Some(limits))
+            | (Format::Turtle, Some(limits))
+            | (Format::TriG, Some(limits))
+            | (Format::N3, Some(limits)) => {
+                let exceeded = Rc::new(Cell::new(None));
+                let source = self.parse_format(LimitReader::new(
+                    data,
+                    limits,
+                    self.format == Format::N3,
+                    exceeded.clone(),
+                ))?;
+                // the underlying parser hides the exceeded limit in its own error type,
+                // and would keep retrying the failing reader: stop after reporting it
+                Box::new(source.scan(false, move |stopped, res| {
+                    if *stopped {
+                        return None;
+                    }
+                    Some(res.map_err(|err| match exceeded.get() {
+                        Some(limit) => {
+                            *stopped = true;
+                            AnyError::LimitExceeded(limit)
+                        }
+                        None => err,
+                    }))
+                }))
+            }
+            _ => self.parse_format(data)?,
+        };
+        let source: AnySource<'a> = match graph {
+            Some(graph) => Box::new(
+                source.map(move |res| res.map(|(spo, g)| (spo, g.or_else(|| Some(graph.clone()))))),
+            ),
+            None => source,
+        };
+        if !config.validate_iris() && config.bnode_prefix().is_none() {
+            return Ok(source);
+        }
+        let config = config.clone();
+        Ok(Box::new(source.map(move |res| {
+            res.and_then(|([s, p, o], g)| {
+                Ok((
+                    [
+                        postprocess(s, &config)?,
+                        postprocess(p, &config)?,
+                        postprocess(o, &config)?,
+                    ],
+                    match g {
+                        Some(g) => Some(postprocess(g, &config)?),
+                        None => None,
+                    },
+                ))
+            })
+        })))
+    }
+
+    /// Parse `txt`.
+    ///
+    /// # Errors
+    /// Fails if the format is not supported by the enabled features.
+    pub fn parse_str<'a>(&self, txt: &'a str) -> Result<AnySource<'a>, AnyError> {
+        self.parse(txt.as_bytes())
+    }
+
+    fn parse_format<'a, B: BufRead + 'a>(&self, data: B) -> Result<AnySource<'a>, AnyError> {
+        let config = &self.config;
+        Ok(match self.format {
+            Format::NTriples => triples(crate::parser::nt::NTriplesParser {}.parse(data)),
+            Format::NQuads => quads(crate::parser::nq::NQuadsParser {}.parse(data)),
+            Format::Turtle if config.strict() => triples(TurtleParser::from(config).parse(data)),
+            Format::Turtle => triples(GTurtleParser::from(config).parse(data)),
+            Format::TriG if config.strict() => quads(TriGParser::from(config).parse(data)),
+            Format::TriG => quads(GTriGParser::from(config).parse(data)),
+            Format::N3 => quads(N3Parser::from(config).parse(data)),
+            #[cfg(feature = "xml")]
+            Format::RdfXml => triples(crate::parser::xml::RdfXmlParser::from(config).parse(data)),
+            #[cfg(feature = "xml")]
+            Format::TriX => quads(crate::parser::trix::TrixParser {}.parse(data)),
+            #[cfg(feature = "json")]
+            Format::RdfJson => triples(crate::parser::rdf_json::RdfJsonParser {}.parse(data)),
+            Format::Hdt => triples(crate::parser::hdt::HdtParser {}.parse(data)),
+            Format::RdfThrift => quads(crate::parser::thrift::ThriftParser {}.parse(data)),
+            #[allow(unreachable_patterns)]
+            format => return Err(AnyError::Unsupported(format)),
+        })
+    }
+}
+
+/// Apply the options of `config` that do not depend on the format to a parsed term.
+fn postprocess(t: BoxTerm, config: &ParserConfig) -> Result<BoxTerm, AnyError> {
+    match t.kind() {
+        TermKind::Iri if config.validate_iris() => {
+            if is_absolute_iri_ref(&t.value()) {
+                Ok(t)
+            } else {
+                Err(AnyError::InvalidIri(t.value().to_string()))
+            }
+        }
+        TermKind::BlankNode => match config.bnode_prefix() {
+            Some(prefix) => Ok(BoxTerm::new_bnode_unchecked(format!(
+                "{}{}",
+                prefix,
+                t.value()
+            ))),
+            None => Ok(t),
+        },
+        _ => Ok(t),
+    }
+}
+
+fn triples<'a, S: TripleSource + 'a>(source: S) -> AnySource<'a> {
+    Box::new(
+        source
+            .map_triples(|t| -> TupleQuad<BoxTerm> {
+                (
+                    [
+                        BoxTerm::copy(t.s()),
+                        BoxTerm::copy(t.p()),
+                        BoxTerm::copy(t.o()),
+                    ],
+                    None,
+                )
+            })
+            .into_iter()
+            .map(|res| res.map_err(AnyError::parse)),
+    )
+}
+
+fn quads<'a, S: QuadSource + 'a>(source: S) -> AnySource<'a> {
+    Box::new(
+        source
+            .map_quads(|q| -> TupleQuad<BoxTerm> {
+                (
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                )
+            })
+            .into_iter()
+            .map(|res| res.map_err(AnyError::parse)),
+    )
+}
+
+/// Parse `data` in the given format.
+///
+/// See [`AnyParser::parse`](struct.AnyParser.html#method.parse).
+pub fn parse_bufread<'a, B: BufRead + 'a>(
+    data: B,
+    format: Format,
+) -> Result<AnySource<'a>, AnyError> {
+    AnyParser::new(format).parse(data)
+}
+
+/// Parse `txt` in the given format.
+///
+/// See [`AnyParser::parse`](struct.AnyParser.html#method.parse).
+pub fn parse_str(txt: &str, format: Format) -> Result<AnySource<'_>, AnyError> {
+    AnyParser::new(format).parse_str(txt)
+}
+
+/// Parse `data`, guessing its format with [`Format::sniff`](enum.Format.html#method.sniff).
+///
+/// Compressed data is [transparently decompressed](../decompress/index.html) beforehand.
+///
+/// # Errors
+/// Fails if the format can not be guessed, or is not supported.
+pub fn parse_auto<'a, B: BufRead + 'a>(data: B) -> Result<AnySource<'a>, AnyError> {
+    let mut data = decompress::auto(data)?;
+    let format = Format::sniff(data.fill_buf()?).ok_or(AnyError::UnknownFormat)?;
+    parse_bufread(data, format)
+}
+
+/// Open and parse the file at `path`,
+/// guessing its format from its extension (see [`Format::from_path`]),
+/// or by sniffing its content (see [`Format::sniff`]).
+///
+/// Compressed files are [transparently decompressed](../decompress/index.html).
+///
+/// [`Format::from_path`]: enum.Format.html#method.from_path
+/// [`Format::sniff`]: enum.Format.html#method.sniff
+pub fn open<P: AsRef<Path>>(path: P) -> Result<AnySource<'static>, AnyError> {
+    let path = path.as_ref();
+    let data = decompress::open(path)?;
+    match Format::from_path(path) {
+        Some(format) => parse_bufread(data, format),
+        None => parse_auto(data),
+    }
+}
+
+/// The error raised by [`AnySource`](type.AnySource.html) and the functions of this module.
+#[derive(Debug, Error)]
+pub enum AnyError {
+    /// The format could not be guessed.
+    #[error("Unknown format")]
+    UnknownFormat,
+    /// The format is not supported by the enabled features.
+    #[error("{0:?} is not supported by the enabled features")]
+    Unsupported(Format),
+    /// The data could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// An invalid IRI was found, while [IRI validation](../config/struct.ParserConfig.html#method.set_validate_iris) is enabled.
+    #[error("Invalid IRI <{0}>")]
+    InvalidIri(String),
+    /// A [graph naming](../config/struct.ParserConfig.html#method.set_graph_naming) is set,
+    /// but no base IRI identifies the document.
+    #[error("No base IRI to name the graph of the document")]
+    NoDocumentIri,
+    /// A limit of the [configuration](../config/struct.ParserConfig.html) was exceeded.
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(LimitExceeded),
+    /// The underlying parser raised an error.
+    #[error("{0}")]
+    Parse(#[source] Box<dyn Error>),
+}
+
+impl AnyError {
+    fn parse<E: Error + 'static>(err: E) -> Self {
+        AnyError::Parse(Box::new(err))
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use sophia_api::dataset::isomorphic_datasets;
+
+    #[test]
+    fn media_types_and_extensions() {
+        for format in Format::ALL.iter().copied() {
+            assert_eq!(Format::from_media_type(format.media_type()), Some(format));
+            for ext in format.extensions() {
+                assert_eq!(Format::from_extension(ext), Some(format));
+            }
+        }
+        assert_eq!(
+            Format::from_media_type("Text/Turtle ; charset=UTF-8"),
+            Some(Format::Turtle)
+        );
+        assert_eq!(Format::from_media_type("text/html"), None);
+        assert_eq!(Format::from_extension(".NQ"), Some(Format::NQuads));
+        assert_eq!(
+            Format::from_path("/data/dump.nt.gz"),
+            Some(Format::NTriples)
+        );
+        assert_eq!(Format::from_path("onto.owl"), Some(Format::RdfXml));
+        assert_eq!(Format::from_path("README"), None);
+        assert_eq!(Format::from_path("data.gz"), None);
+    }
+
+    #[test]
+    fn sniff() {
+        let cases: &[(&str, Option<Format>)] = &[
+            ("# a comment\n<tag:s> <tag:p> \"o\\\"<\"@en .\n", Some(Format::NTriples)),
+            ("<tag:s> <tag:p> _:o .\n<tag:s> <tag:p> <tag:o> <tag:g> .\n", Some(Format::NQuads)),
+            ("<tag:s> <tag:p> <tag:o> ;\n <tag:q> 42 .\n", Some(Format::Turtle)),
+            ("@prefix : <tag:>.\n:s :p :o.\n", Some(Format::Turtle)),
+            ("PREFIX : <tag:>\n:g {\n:s :p :o.\n}\n", Some(Format::TriG)),
+            ("@prefix : <tag:>.\n{ :s :p ?o } => { :s :q ?o }.\n", Some(Format::N3)),
+            ("<?xml version=\"1.0\"?>\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">", Some(Format::RdfXml)),
+            ("<TriX xmlns=\"http://www.w3.org/2004/03/trix/trix-1/\">", Some(Format::TriX)),
+            ("{ \"tag:s\": { \"tag:p\": [] } }", Some(Format::RdfJson)),
+            ("{ \"@context\": {} }", None),
+            ("$HDT\x01", Some(Format::Hdt)),
+            ("\u{feff}  \n", None),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(Format::sniff(data.as_bytes()), *expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn parse_all_formats() -> Result<(), Box<dyn std::error::Error>> {
+        // the third member is the sniffed format, i.e. the most specific format parsing the data:
+        // as they contain only a triple in the default graph,
+        // the N-Quads (resp. N3) data is also valid N-Triples (resp. Turtle)
+        let mut cases = vec![
+            (
+                Format::NTriples,
+                "<tag:s> <tag:p> \"o\" .\n".to_string(),
+                Format::NTriples,
+            ),
+            (
+                Format::NQuads,
+                "<tag:s> <tag:p> \"o\" .\n".to_string(),
+                Format::NTriples,
+            ),
+            (
+                Format::Turtle,
+                "@prefix : <tag:>. :s :p \"o\".".to_string(),
+                Format::Turtle,
+            ),
+            (
+                Format::TriG,
+                "@prefix : <tag:>. { :s :p \"o\" }".to_string(),
+                Format::TriG,
+            ),
+            (
+                Format::N3,
+                "@prefix : <tag:>. :s :p \"o\".".to_string(),
+                Format::Turtle,
+            ),
+        ];
+        if cfg!(feature = "json") {
+            cases.push((
+                Format::RdfJson,
+                r#"{ "tag:s": { "tag:p": [{ "type": "literal", "value": "o" }] } }"#.to_string(),
+                Format::RdfJson,
+            ));
+        }
+        if cfg!(feature = "xml") {
+            cases.push((
+                Format::RdfXml,
+                r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                    <rdf:Description rdf:about="tag:s"><p xmlns="tag:">o</p></rdf:Description>
+                </rdf:RDF>"#
+                    .to_string(),
+                Format::RdfXml,
+            ));
+        }
+        let expected: FastDataset = parse_str(&cases[0].1, Format::NTriples)?.collect_quads()?;
+        assert_eq!(expected.quads().count(), 1);
+        for (format, data, sniffed) in &cases {
+            let d: FastDataset = parse_str(data, *format)?.collect_quads()?;
+            assert!(isomorphic_datasets(&d, &expected)?, "{:?}", format);
+            assert_eq!(
+                Format::sniff(data.as_bytes()),
+                Some(*sniffed),
+                "{:?}",
+                format
+            );
+        }
+        // with a graph name, N-Quads data is sniffed as such
+        let data = "<tag:s> <tag:p> \"o\" <tag:g> .\n";
+        assert_eq!(Format::sniff(data.as_bytes()), Some(Format::NQuads));
+        assert_eq!(parse_str(data, Format::NQuads)?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_auto_and_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let d: FastDataset =
+            parse_auto("<tag:s> <tag:p> <tag:o> <tag:g> .\n".as_bytes())?.collect_quads()?;
+        assert_eq!(d.quads().count(), 1);
+
+        assert!(matches!(parse_auto(&b""[..]), Err(AnyError::UnknownFormat)));
+
+        let errors: Vec<_> = parse_str("<tag:s> <tag:p> .", Format::NTriples)?
+            .filter_map(Result::err)
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], AnyError::Parse(_)));
+
+        let xml = parse_str("", Format::RdfXml);
+        assert_eq!(xml.is_ok(), cfg!(feature = "xml"));
+        Ok(())
+    }
+
+    #[test]
+    fn config() -> Result<(), Box<dyn std::error::Error>> {
+        let parse = |format, config: &ParserConfig, txt| -> Result<Vec<_>, AnyError> {
+            AnyParser::with_config(format, config.clone())
+                .parse_str(txt)?
+                .collect()
+        };
+        let mut config = ParserConfig::new();
+
+        let generalized = "\"s\" <tag:p> <tag:o>.";
+        assert!(parse(Format::Turtle, &config, generalized).is_err());
+        assert!(parse(Format::TriG, &config, generalized).is_err());
+        config.set_strict(false);
+        assert_eq!(parse(Format::Turtle, &config, generalized)?.len(), 1);
+        assert_eq!(parse(Format::TriG, &config, generalized)?.len(), 1);
+
+        let relative = "<s> <p> <o>.";
+        assert_eq!(parse(Format::N3, &config, relative)?.len(), 1);
+        config.set_validate_iris(true);
+        assert!(matches!(
+            parse(Format::N3, &config, relative),
+            Err(AnyError::InvalidIri(iri)) if iri == "s"
+        ));
+        config.set_base(Some("tag:".into()));
+        assert_eq!(parse(Format::N3, &config, relative)?.len(), 1);
+
+        config.set_bnode_prefix(Some("x_".into()));
+        let d = parse(Format::Turtle, &config, "_:a <p> [ <q> _:b ].")?;
+        let mut bnodes: Vec<_> = d
+            .iter()
+            .flat_map(|q| vec![q.s().value().to_string(), q.o().value().to_string()])
+            .filter(|v| !v.starts_with("tag:"))
+            .collect();
+        bnodes.sort();
+        bnodes.dedup();
+        assert_eq!(bnodes.len(), 3);
+        assert!(bnodes.iter().all(|b| b.starts_with("x_")), "{:?}", bnodes);
+
+        let nested = "<s> <p> [ <p> [ <p> 1 ] ].";
+        config.set_max_depth(Some(2));
+        assert_eq!(parse(Format::Turtle, &config, nested)?.len(), 3);
+        config.set_max_depth(Some(1));
+        assert!(parse(Format::Turtle, &config, nested).is_err());
+        assert!(parse(Format::N3, &config, nested).is_err());
+        let escaped = "@prefix ex: <tag:>. ex:s ex:p ex:a\\#b, [ ex:p [ ex:p [] ] ].";
+        assert!(parse(Format::Turtle, &config, escaped).is_err());
+        let escaped = "@prefix ex: <tag:>. ex:s ex:p ex:a\\(\\(b, <=((>.";
+        assert_eq!(parse(Format::Turtle, &config, escaped)?.len(), 2);
+        config.set_max_depth(None);
+
+        config.set_max_bnode_nesting(Some(1));
+        assert!(matches!(
+            parse(Format::TriG, &config, "{ <s> <p> [ <p> [] ] }"),
+            Err(AnyError::LimitExceeded(LimitExceeded::BnodeNesting(1)))
+        ));
+        assert_eq!(parse(Format::TriG, &config, "{ <s> <p> [], [] }")?.len(), 2);
+        config.set_max_collection_depth(Some(1));
+        assert!(matches!(
+            parse(Format::Turtle, &config, "<s> <p> ( 1 ( 2 ) )."),
+            Err(AnyError::LimitExceeded(LimitExceeded::CollectionDepth(1)))
+        ));
+        config.set_max_literal_len(Some(3));
+        assert_eq!(parse(Format::Turtle, &config, "<s> <p> 'abc'.")?.len(), 1);
+        assert!(matches!(
+            parse(Format::Turtle, &config, "<s> <p> 'abcd'."),
+            Err(AnyError::LimitExceeded(LimitExceeded::LiteralLength(3)))
+        ));
+        assert!(matches!(
+            parse(Format::NTriples, &config, "<tag:s> <tag:p> \"abcd\" .\n"),
+            Err(AnyError::LimitExceeded(LimitExceeded::LiteralLength(3)))
+        ));
+
+        // the source ends after reporting the exceeded limit
+        let data = "<tag:s> <tag:p> \"a\" .\n<tag:s> <tag:p> \"abcd\" .\n<tag:s> <tag:p> \"b\" .\n";
+        for format in &[Format::NTriples, Format::Turtle, Format::TriG, Format::N3] {
+            let parser = AnyParser::with_config(*format, config.clone());
+            let results: Vec<_> = parser.parse_str(data)?.collect();
+            assert!(
+                matches!(
+                    results.last(),
+                    Some(Err(AnyError::LimitExceeded(LimitExceeded::LiteralLength(
+                        3
+                    ))))
+                ),
+                "{:?}",
+                format
+            );
+            assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn graph_naming() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::config::GraphNaming;
+
+        let mut config = ParserConfig::new();
+        config.set_graph_naming(Some(GraphNaming::document()));
+        let parser = AnyParser::with_config(Format::TriG, config.clone());
+        assert!(matches!(
+            parser.parse_str("<tag:s> <tag:p> <tag:o>."),
+            Err(AnyError::NoDocumentIri)
+        ));
+
+        config.set_base(Some("http://example.org/doc".into()));
+        let parser = AnyParser::with_config(Format::TriG, config.clone());
+        let quads: Vec<_> = parser
+            .parse_str("<#s> <#p> <#o>. <#g> { <#s> <#p> <#o> }")?
+            .collect::<Result<_, _>>()?;
+        let graphs: Vec<_> = quads
+            .iter()
+            .map(|q| q.g().unwrap().value().to_string())
+            .collect();
+        assert_eq!(
+            graphs,
+            vec!["http://example.org/doc", "http://example.org/doc#g"]
+        );
+
+        config.set_graph_naming(Some(GraphNaming::new(|doc| format!("{}#graph", doc))));
+        let parser = AnyParser::with_config(Format::NTriples, config.clone());
+        let quads: Vec<_> = parser
+            .parse_str("<tag:s> <tag:p> <tag:o> .\n")?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(
+            quads[0].g().unwrap().value().to_string(),
+            "http://example.org/doc#graph"
+        );
+
+        config.set_graph_naming(Some(GraphNaming::new(|_| "not an IRI".into())));
+        let parser = AnyParser::with_config(Format::NTriples, config);
+        assert!(matches!(
+            parser.parse_str(""),
+            Err(AnyError::InvalidIri(iri)) if iri == "not an IRI"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn progress() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::config::{Progress, ProgressReporter};
+        use std::sync::{Arc, Mutex};
+
+        let data: String = (0..5)
+            .map(|i| format!("<tag:s> <tag:p> \"{}\" .\n", i))
+            .collect();
+        let reported = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let r2 = reported.clone();
+        let mut config = ParserConfig::new();
+        config.set_progress(Some(ProgressReporter::new(2, move |p| {
+            r2.lock().unwrap().push(p)
+        })));
+        let parser = AnyParser::with_config(Format::NTriples, config);
+        let reader = io::BufReader::with_capacity(16, data.as_bytes());
+        assert_eq!(parser.parse(reader)?.count(), 5);
+
+        let reported = reported.lock().unwrap();
+        let quads: Vec<_> = reported.iter().map(|p| (p.quads, p.done)).collect();
+        assert_eq!(quads, vec![(2, false), (4, false), (5, true)]);
+        assert!(reported[0].bytes > 0);
+        assert!(reported.windows(2).all(|w| w[0].bytes <= w[1].bytes));
+        assert_eq!(reported[2].bytes, data.len() as u64);
+        Ok(())
+    }
+}
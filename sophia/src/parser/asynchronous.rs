@@ -0,0 +1,324 @@
+//! Asynchronous parsers, reading from [tokio]'s [`AsyncBufRead`].
+//!
+//! [N-Triples] and [N-Quads] are parsed one line at a time,
+//! as the data becomes available, into an [`AsyncQuads`] stream;
+//! the executor is never blocked waiting for the data.
+//! Other formats can not be parsed incrementally:
+//! [`parse_any`] reads the whole data asynchronously,
+//! then parses it with an [`AnyParser`].
+//!
+//! The returned sources implement [`Stream`], and yield owned quads.
+//! They also provide a `next_quad` method,
+//! so that they can be used without any stream combinator library.
+//!
+//! This module requires the `async` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::parser::asynchronous::parse_nq;
+//! # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+//! let body: &[u8] = b"<tag:s> <tag:p> <tag:o>.\n<tag:s> <tag:p> <tag:o> <tag:g>.\n";
+//! let mut quads = parse_nq(body);
+//! let mut count = 0;
+//! while let Some(quad) = quads.next_quad().await {
+//!     let ([_s, _p, _o], _g) = quad?;
+//!     count += 1;
+//! }
+//! assert_eq!(count, 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! # })?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [tokio]: https://docs.rs/tokio/
+//! [`AsyncBufRead`]: https://docs.rs/tokio/1/tokio/io/trait.AsyncBufRead.html
+//! [`Stream`]: https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html
+//! [N-Triples]: https://www.w3.org/TR/n-triples/
+//! [N-Quads]: https://www.w3.org/TR/n-quads/
+//! [`AsyncQuads`]: struct.AsyncQuads.html
+//! [`parse_any`]: fn.parse_any.html
+//! [`AnyParser`]: ../any/struct.AnyParser.html
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec;
+
+use futures_core::Stream;
+use memchr::memchr;
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::CopyTerm;
+use sophia_term::BoxTerm;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt};
+
+use crate::parser::any::{AnyError, AnyParser};
+use crate::parser::nq::{parse_line, NQuadsError};
+
+/// Parse the N-Quads in `data`.
+pub fn parse_nq<R: AsyncBufRead + Unpin>(data: R) -> AsyncQuads<R> {
+    AsyncQuads::new(data, false)
+}
+
+/// Parse the N-Triples in `data`.
+///
+/// The quads produced have no graph name,
+/// and lines with a graph name are reported as syntax errors.
+pub fn parse_nt<R: AsyncBufRead + Unpin>(data: R) -> AsyncQuads<R> {
+    AsyncQuads::new(data, true)
+}
+
+/// Read all of `data`, then parse it with `parser`.
+///
+/// Reading is asynchronous, but parsing is not:
+/// the whole data is parsed (and kept in memory) before this function returns.
+///
+/// # Errors
+/// Fails if `data` can not be read,
+/// or if the format of `parser` is not supported by the enabled features.
+/// Syntax errors are yielded by the returned stream.
+pub async fn parse_any<R: AsyncRead + Unpin>(
+    mut data: R,
+    parser: &AnyParser,
+) -> Result<BufferedQuads, AnyError> {
+    let mut buffer = Vec::new();
+    data.read_to_end(&mut buffer).await?;
+    let quads: Vec<_> = parser.parse(&buffer[..])?.collect();
+    Ok(BufferedQuads(quads.into_iter()))
+}
+
+/// The stream returned by [`parse_nq`](fn.parse_nq.html) and [`parse_nt`](fn.parse_nt.html).
+///
+/// Like [`BorrowedQuads`](../nq/struct.BorrowedQuads.html),
+/// syntax errors are reported once per invalid line,
+/// after which parsing resumes at the next line.
+/// I/O errors (including invalid UTF-8) end the stream.
+pub struct AsyncQuads<R> {
+    data: R,
+    /// The line being read.
+    line: Vec<u8>,
+    /// Unescaped term data, when the line contains escape sequences.
+    scratch: String,
+    line_number: usize,
+    /// The byte offset of the next line in the data.
+    offset: usize,
+    triples_only: bool,
+    done: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncQuads<R> {
+    fn new(data: R, triples_only: bool) -> Self {
+        AsyncQuads {
+            data,
+            line: Vec::new(),
+            scratch: String::new(),
+            line_number: 0,
+            offset: 0,
+            triples_only,
+            done: false,
+        }
+    }
+
+    /// The number of lines read so far.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The next quad of this stream, if any.
+    pub async fn next_quad(&mut self) -> Option<Result<TupleQuad<BoxTerm>, NQuadsError>> {
+        NextItem(self).await
+    }
+
+    /// Complete `self.line` until the end of the line or of the data.
+    ///
+    /// Returns `false` if there is no more line to parse.
+    fn poll_line(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        loop {
+            let available = match Pin::new(&mut self.data).poll_fill_buf(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Ready(Ok(available)) => available,
+            };
+            if available.is_empty() {
+                return Poll::Ready(Ok(!self.line.is_empty()));
+            }
+            let (len, complete) = match memchr(b'\n', available) {
+                Some(i) => (i + 1, true),
+                None => (available.len(), false),
+            };
+            self.line.extend_from_slice(&available[..len]);
+            Pin::new(&mut self.data).consume(len);
+            if complete {
+                return Poll::Ready(Ok(true));
+            }
+        }
+    }
+
+    /// Parse the line read by [`poll_line`](#method.poll_line).
+    fn parse_current_line(
+        &mut self,
+        line_offset: usize,
+    ) -> Result<Option<TupleQuad<BoxTerm>>, NQuadsError> {
+        let txt = match std::str::from_utf8(&self.line) {
+            Ok(txt) => txt.trim_end_matches(&['\n', '\r'][..]),
+            Err(err) => {
+                self.done = true;
+                return Err(NQuadsError::Io {
+                    source: io::Error::new(io::ErrorKind::InvalidData, err),
+                    line: self.line_number,
+                });
+            }
+        };
+        self.scratch.clear();
+        let quad = parse_line(
+            txt,
+            self.line_number,
+            line_offset,
+            &mut self.scratch,
+            self.triples_only,
+        )?;
+        Ok(quad.map(|([s, p, o], g)| {
+            (
+                [BoxTerm::copy(&s), BoxTerm::copy(&p), BoxTerm::copy(&o)],
+                g.as_ref().map(BoxTerm::copy),
+            )
+        }))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncQuads<R> {
+    type Item = Result<TupleQuad<BoxTerm>, NQuadsError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.done {
+            match this.poll_line(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(source)) => {
+                    this.done = true;
+                    let line = this.line_number + 1;
+                    return Poll::Ready(Some(Err(NQuadsError::Io { source, line })));
+                }
+                Poll::Ready(Ok(false)) => this.done = true,
+                Poll::Ready(Ok(true)) => {
+                    this.line_number += 1;
+                    let line_offset = this.offset;
+                    this.offset += this.line.len();
+                    let res = this.parse_current_line(line_offset);
+                    this.line.clear();
+                    match res {
+                        Ok(None) => continue,
+                        Ok(Some(quad)) => return Poll::Ready(Some(Ok(quad))),
+                        Err(err) => return Poll::Ready(Some(Err(err))),
+                    }
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// The stream returned by [`parse_any`](fn.parse_any.html).
+///
+/// It is always ready, as the data has already been parsed.
+pub struct BufferedQuads(vec::IntoIter<Result<TupleQuad<BoxTerm>, AnyError>>);
+
+impl BufferedQuads {
+    /// The next quad of this stream, if any.
+    pub async fn next_quad(&mut self) -> Option<Result<TupleQuad<BoxTerm>, AnyError>> {
+        self.0.next()
+    }
+}
+
+impl Stream for BufferedQuads {
+    type Item = Result<TupleQuad<BoxTerm>, AnyError>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().0.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// The future returned by `next_quad`.
+struct NextItem<'a, S>(&'a mut S);
+
+impl<S: Stream + Unpin> Future for NextItem<'_, S> {
+    type Output = Option<S::Item>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().0).poll_next(cx)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::any::Format;
+    use sophia_api::parser::WithLocation;
+    use sophia_api::quad::Quad;
+    use sophia_api::term::TTerm;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn nq() {
+        let nq = concat!(
+            "<tag:s> <tag:p> \"a\\tb\" <tag:g>.\n",
+            "# a comment\n",
+            "<tag:s> \"not a predicate\" <tag:o> .\n",
+            "_:b <tag:p> \"chat\"@fr .\r\n",
+            "<tag:s> <tag:p> <tag:o>.",
+        );
+        // a tiny buffer, so that lines are read in several steps
+        let mut quads = parse_nq(BufReader::with_capacity(4, nq.as_bytes()));
+        let mut results = vec![];
+        while let Some(res) = quads.next_quad().await {
+            results.push(res);
+        }
+        assert_eq!(quads.line_number(), 5);
+        assert_eq!(results.len(), 4);
+        let q = results[0].as_ref().unwrap();
+        assert_eq!(&*q.o().value(), "a\tb");
+        assert_eq!(&*q.g().unwrap().value(), "tag:g");
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.location().to_string(), "3:9");
+        assert_eq!(results[2].as_ref().unwrap().o().language(), Some("fr"));
+        assert!(results[3].is_ok());
+    }
+
+    #[tokio::test]
+    async fn nt() {
+        let nt: &[u8] = b"<tag:s> <tag:p> <tag:o>.\n<tag:s> <tag:p> <tag:o> <tag:g>.\n\xff\n";
+        let mut quads = parse_nt(nt);
+        let q = quads.next_quad().await.unwrap().unwrap();
+        assert!(q.g().is_none());
+        assert!(matches!(
+            quads.next_quad().await,
+            Some(Err(NQuadsError::Syntax { line: 2, .. }))
+        ));
+        assert!(matches!(
+            quads.next_quad().await,
+            Some(Err(NQuadsError::Io { line: 3, .. }))
+        ));
+        assert!(quads.next_quad().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn any() -> Result<(), AnyError> {
+        let ttl: &[u8] = b"@prefix : <tag:>. :s :p :o1, :o2.";
+        let mut quads = parse_any(ttl, &AnyParser::new(Format::Turtle)).await?;
+        let mut count = 0;
+        while let Some(quad) = quads.next_quad().await {
+            assert_eq!(&*quad?.s().value(), "tag:s");
+            count += 1;
+        }
+        assert_eq!(count, 2);
+        Ok(())
+    }
+}
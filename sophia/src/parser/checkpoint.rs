@@ -0,0 +1,354 @@
+//! Resumable loading of large N-Quads (or N-Triples) files.
+//!
+//! A [`Checkpointer`] inserts the quads of a file into a dataset,
+//! and periodically records a [`Checkpoint`] in a file:
+//! the number of quads inserted so far,
+//! and the position in the data where parsing must resume
+//! (as given by [`NQuadsSource::offset`]).
+//! If the load is interrupted (crash, I/O error, failed insertion...),
+//! running it again with the same checkpoint file resumes it from the last checkpoint,
+//! instead of restarting from zero.
+//!
+//! NB: resuming only makes sense if the quads inserted before the checkpoint
+//! have been durably stored.
+//! [`Checkpointer::load_with`] accepts a `commit` callback,
+//! called before each checkpoint is recorded, to that effect.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::parser::checkpoint::Checkpointer;
+//! # use std::io::Cursor;
+//! # let path = std::env::temp_dir().join(format!("sophia-doctest-{}.checkpoint", std::process::id()));
+//! let data = Cursor::new("<tag:s> <tag:p> <tag:o> <tag:g> .\n<tag:s> <tag:p> \"o\" .\n");
+//! let mut d = FastDataset::new();
+//! let checkpoint = Checkpointer::new(&path).load(data, &mut d)?;
+//! assert_eq!(checkpoint.quads, 2);
+//! assert_eq!(d.quads().count(), 2);
+//! # std::fs::remove_file(&path)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Checkpointer`]: struct.Checkpointer.html
+//! [`Checkpoint`]: struct.Checkpoint.html
+//! [`NQuadsSource::offset`]: ../nq/struct.NQuadsSource.html#method.offset
+//! [`Checkpointer::load_with`]: struct.Checkpointer.html#method.load_with
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sophia_api::dataset::MutableDataset;
+use sophia_api::quad::stream::{QuadSource, SinkError, SourceError};
+use sophia_api::quad::Quad;
+use thiserror::Error;
+
+use crate::parser::nq::{NQuadsError, NQuadsParser};
+
+/// The progress of a load.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The number of quads inserted before this checkpoint.
+    pub quads: usize,
+    /// The number of lines read before this checkpoint.
+    pub lines: usize,
+    /// The byte offset in the data where parsing must resume.
+    pub offset: usize,
+}
+
+impl Checkpoint {
+    /// Read the checkpoint stored in the file at `path`,
+    /// or return `None` if that file does not exist.
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Option<Checkpoint>> {
+        match fs::read_to_string(path) {
+            Ok(txt) => txt
+                .parse()
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Store this checkpoint in the file at `path`.
+    ///
+    /// The file is replaced atomically,
+    /// so that an interruption can not leave a corrupted checkpoint behind.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = path.as_ref();
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = fs::write(&tmp, self.to_string()).and_then(|_| fs::rename(&tmp, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "quads={}", self.quads)?;
+        writeln!(f, "lines={}", self.lines)?;
+        writeln!(f, "offset={}", self.offset)
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = InvalidCheckpoint;
+
+    fn from_str(txt: &str) -> Result<Checkpoint, InvalidCheckpoint> {
+        let mut fields: [Option<usize>; 3] = [None; 3];
+        for line in txt.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut kv = line.splitn(2, '=');
+            let i = match kv.next() {
+                Some("quads") => 0,
+                Some("lines") => 1,
+                Some("offset") => 2,
+                _ => return Err(InvalidCheckpoint(line.to_string())),
+            };
+            let value = kv.next().and_then(|v| v.trim().parse().ok());
+            fields[i] = Some(value.ok_or_else(|| InvalidCheckpoint(line.to_string()))?);
+        }
+        match fields {
+            [Some(quads), Some(lines), Some(offset)] => Ok(Checkpoint {
+                quads,
+                lines,
+                offset,
+            }),
+            _ => Err(InvalidCheckpoint(txt.to_string())),
+        }
+    }
+}
+
+/// The error raised when parsing an invalid [`Checkpoint`](struct.Checkpoint.html).
+#[derive(Clone, Debug, Error)]
+#[error("Invalid checkpoint: {0:?}")]
+pub struct InvalidCheckpoint(pub String);
+
+/// Loads N-Quads into datasets, recording checkpoints in a file.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Checkpointer {
+    /// The file where checkpoints are recorded.
+    pub path: PathBuf,
+    /// The number of quads inserted between two checkpoints.
+    pub interval: usize,
+}
+
+impl Checkpointer {
+    /// Build a checkpointer recording checkpoints in the file at `path`,
+    /// every 100,000 quads.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Checkpointer {
+            path: path.into(),
+            interval: 100_000,
+        }
+    }
+
+    /// The last recorded checkpoint, if any.
+    pub fn checkpoint(&self) -> io::Result<Option<Checkpoint>> {
+        Checkpoint::read(&self.path)
+    }
+
+    /// Forget the last recorded checkpoint, so that the next load starts from the beginning.
+    pub fn reset(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+
+    /// Insert the quads of `data` into `dataset`,
+    /// resuming from the last recorded checkpoint, if any.
+    ///
+    /// See [`load_with`](#method.load_with).
+    pub fn load<R, D>(
+        &self,
+        data: R,
+        dataset: &mut D,
+    ) -> Result<Checkpoint, CheckpointError<D::MutationError>>
+    where
+        R: BufRead + Seek,
+        D: MutableDataset,
+    {
+        self.load_with(data, dataset, |_| Ok(()))
+    }
+
+    /// Insert the quads of `data` into `dataset`,
+    /// resuming from the last recorded checkpoint, if any.
+    ///
+    /// Before each checkpoint is recorded, `commit` is called with the dataset,
+    /// to make the quads inserted so far durable;
+    /// if it fails, the load stops without recording the checkpoint.
+    ///
+    /// When the load stops (successfully or not),
+    /// a last checkpoint is recorded after the last inserted quad, and returned on success.
+    /// Hence, loading the same data again is a no-op,
+    /// unless the checkpoint is [`reset`](#method.reset).
+    pub fn load_with<R, D, F>(
+        &self,
+        mut data: R,
+        dataset: &mut D,
+        mut commit: F,
+    ) -> Result<Checkpoint, CheckpointError<D::MutationError>>
+    where
+        R: BufRead + Seek,
+        D: MutableDataset,
+        F: FnMut(&mut D) -> io::Result<()>,
+    {
+        let mut current = self.checkpoint()?.unwrap_or_default();
+        data.seek(SeekFrom::Start(current.offset as u64))?;
+        let mut source = NQuadsParser {}.parse_from(data, current.lines, current.offset);
+        let mut recorded = current.quads;
+        let result = loop {
+            let res = source
+                .try_for_some_quad(&mut |q| dataset.insert(q.s(), q.p(), q.o(), q.g()).map(|_| ()));
+            match res {
+                Ok(true) => {
+                    current = Checkpoint {
+                        quads: current.quads + 1,
+                        lines: source.line_number(),
+                        offset: source.offset(),
+                    };
+                    if current.quads - recorded >= self.interval {
+                        commit(dataset)?;
+                        current.write(&self.path)?;
+                        recorded = current.quads;
+                    }
+                }
+                Ok(false) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+        if current.quads != recorded || result.is_ok() {
+            commit(dataset)?;
+            current.write(&self.path)?;
+        }
+        match result {
+            Ok(()) => Ok(current),
+            Err(SourceError(err)) => Err(CheckpointError::Parse(err)),
+            Err(SinkError(err)) => Err(CheckpointError::Insert(err)),
+        }
+    }
+}
+
+/// The error raised by [`Checkpointer`](struct.Checkpointer.html).
+#[derive(Debug, Error)]
+pub enum CheckpointError<E: Error + 'static> {
+    /// The checkpoint could not be read or recorded, or the data could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data could not be parsed.
+    #[error("{0}")]
+    Parse(#[source] NQuadsError),
+    /// A quad could not be inserted in the dataset.
+    #[error("{0}")]
+    Insert(#[source] E),
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::flaky::{Faults, FlakyDataset};
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use sophia_api::parser::WithLocation;
+    use std::io::Cursor;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sophia-{}-{}.checkpoint", name, std::process::id()))
+    }
+
+    fn data(n: usize) -> Cursor<String> {
+        let mut txt = String::from("# some quads\n");
+        for i in 0..n {
+            txt.push_str(&format!("<tag:s{}> <tag:p> \"{}\" <tag:g> .\n\n", i, i));
+        }
+        Cursor::new(txt)
+    }
+
+    #[test]
+    fn checkpoint_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let path = temp_path("round-trip");
+        assert_eq!(Checkpoint::read(&path)?, None);
+        let checkpoint = Checkpoint {
+            quads: 3,
+            lines: 7,
+            offset: 42,
+        };
+        checkpoint.write(&path)?;
+        assert_eq!(Checkpoint::read(&path)?, Some(checkpoint));
+        fs::write(&path, "quads=3\nlines=x\n")?;
+        assert!(Checkpoint::read(&path).is_err());
+        fs::remove_file(&path)?;
+        assert!("quads=1\nlines=2\n".parse::<Checkpoint>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resume() -> Result<(), Box<dyn std::error::Error>> {
+        let path = temp_path("resume");
+        let mut checkpointer = Checkpointer::new(&path);
+        checkpointer.reset()?;
+        checkpointer.interval = 3;
+
+        // the 8th insertion fails
+        let mut flaky = FlakyDataset::new(FastDataset::new(), Faults::Never, Faults::At(vec![7]));
+        let mut commits = 0;
+        let res = checkpointer.load_with(data(10), &mut flaky, |_| {
+            commits += 1;
+            Ok(())
+        });
+        assert!(matches!(res, Err(CheckpointError::Insert(err)) if err.is_injected()));
+        assert_eq!(commits, 3); // after 3 and 6 quads, then after the 7th on failure
+        let checkpoint = checkpointer.checkpoint()?.unwrap();
+        assert_eq!(checkpoint.quads, 7);
+        assert_eq!(checkpoint.lines, 14);
+
+        let mut d = flaky.into_inner();
+        assert_eq!(d.quads().count(), 7);
+        let checkpoint = checkpointer.load(data(10), &mut d)?;
+        assert_eq!(checkpoint.quads, 10);
+        assert_eq!(d.quads().count(), 10);
+
+        // loading again is a no-op
+        assert_eq!(checkpointer.load(data(10), &mut d)?, checkpoint);
+        // unless the checkpoint is reset
+        checkpointer.reset()?;
+        assert_eq!(checkpointer.load(data(10), &mut d)?.quads, 10);
+        checkpointer.reset()?;
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error() -> Result<(), Box<dyn std::error::Error>> {
+        let path = temp_path("parse-error");
+        let checkpointer = Checkpointer::new(&path);
+        checkpointer.reset()?;
+        let data = Cursor::new("<tag:s> <tag:p> <tag:o> .\n<tag:s> <tag:p> .\n");
+        let mut d = FastDataset::new();
+        match checkpointer.load(data, &mut d) {
+            Err(CheckpointError::Parse(err)) => {
+                assert_eq!(err.location().line(), Some(2))
+            }
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        assert_eq!(checkpointer.checkpoint()?.map(|c| c.quads), Some(1));
+        checkpointer.reset()?;
+        Ok(())
+    }
+}
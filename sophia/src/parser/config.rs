@@ -0,0 +1,967 @@
+//! Configuration shared by all parsers.
+//!
+//! Each parser has its own configuration fields
+//! (e.g. [`TurtleParser::base`], [`N3Parser::reject_relative`]),
+//! but applications usually want to apply the same policy to whatever format they receive.
+//! A [`ParserConfig`] describes such a policy once;
+//! it can be converted into the corresponding parser for a given format
+//! (e.g. `TurtleParser::from(&config)`),
+//! or given to [`AnyParser`], which also applies the options that are not specific to a format
+//! (IRI validation, blank node prefix, graph naming, progress reporting).
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::parser::any::{AnyParser, Format};
+//! # use sophia::parser::config::ParserConfig;
+//! # use sophia::quad::stream::QuadSource;
+//! let mut config = ParserConfig::new();
+//! config
+//!     .set_base(Some("http://example.org/".into()))
+//!     .set_bnode_prefix(Some("doc1_".into()))
+//!     .set_max_depth(Some(32));
+//! let parser = AnyParser::with_config(Format::Turtle, config);
+//! let d: FastDataset = parser.parse_str("<s> <p> [ <q> <o> ].")?.collect_quads()?;
+//! assert_eq!(d.quads().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TurtleParser::base`]: ../turtle/struct.TurtleParser.html#structfield.base
+//! [`N3Parser::reject_relative`]: ../n3/struct.N3Parser.html#structfield.reject_relative
+//! [`ParserConfig`]: struct.ParserConfig.html
+//! [`AnyParser`]: ../any/struct.AnyParser.html
+
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::parser::gtrig::GTriGParser;
+use crate::parser::gturtle::GTurtleParser;
+use crate::parser::n3::N3Parser;
+use crate::parser::trig::TriGParser;
+use crate::parser::turtle::TurtleParser;
+#[cfg(feature = "xml")]
+use crate::parser::xml::RdfXmlParser;
+
+/// Configuration options common to all parsers.
+///
+/// Options that make no sense for a given format are ignored by the corresponding parser.
+#[derive(Clone, Debug)]
+pub struct ParserConfig {
+    strict: bool,
+    validate_iris: bool,
+    base: Option<String>,
+    bnode_prefix: Option<String>,
+    max_depth: Option<usize>,
+    max_bnode_nesting: Option<usize>,
+    max_collection_depth: Option<usize>,
+    max_literal_len: Option<usize>,
+    graph_naming: Option<GraphNaming>,
+    progress: Option<ProgressReporter>,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        ParserConfig {
+            strict: true,
+            validate_iris: false,
+            base: None,
+            bnode_prefix: None,
+            max_depth: None,
+            max_bnode_nesting: None,
+            max_collection_depth: None,
+            max_literal_len: None,
+            graph_naming: None,
+            progress: None,
+        }
+    }
+}
+
+impl ParserConfig {
+    /// Build a new default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether parsers stick to the specification of their format.
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+    /// Set the strictness of parsers (default: true).
+    ///
+    /// If false, Turtle and TriG parsers accept
+    /// [generalized RDF](https://www.w3.org/TR/rdf11-concepts/#section-generalized-rdf)
+    /// and variables,
+    /// and the N3 parser keeps relative IRI-references that it can not resolve;
+    /// otherwise, they are errors.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether every parsed IRI is checked to be a valid absolute IRI.
+    pub fn validate_iris(&self) -> bool {
+        self.validate_iris
+    }
+    /// Set the IRI validation configuration (default: false).
+    ///
+    /// If true, [`AnyParser`] checks every IRI it produces,
+    /// regardless of the format.
+    /// Note that some parsers (e.g. those based on RIO) always validate IRIs,
+    /// so setting this to false does not disable their own validation.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_validate_iris(&mut self, validate_iris: bool) -> &mut Self {
+        self.validate_iris = validate_iris;
+        self
+    }
+
+    /// The base IRI used to resolve relative IRI-references, if any.
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+    /// Set the base IRI used to resolve relative IRI-references (default: none).
+    ///
+    /// It is used by the formats that allow relative IRI-references
+    /// (Turtle, TriG, N3 and RDF/XML).
+    pub fn set_base(&mut self, base: Option<String>) -> &mut Self {
+        self.base = base;
+        self
+    }
+
+    /// The prefix added to the label of every blank node, if any.
+    pub fn bnode_prefix(&self) -> Option<&str> {
+        self.bnode_prefix.as_deref()
+    }
+    /// Set the prefix added by [`AnyParser`] to the label of every blank node (default: none).
+    ///
+    /// This is useful when loading several documents in the same dataset,
+    /// to prevent blank nodes with the same label in different documents from being merged.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_bnode_prefix(&mut self, bnode_prefix: Option<String>) -> &mut Self {
+        self.bnode_prefix = bnode_prefix;
+        self
+    }
+
+    /// The maximum nesting depth, if any.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+    /// Set the maximum nesting depth of the data (default: none).
+    ///
+    /// The nesting depth is the number of enclosing brackets
+    /// (blank node property lists, collections, graphs or formulae).
+    /// Deeply nested data may exhaust the stack of recursive parsers,
+    /// so this limit should be set when parsing untrusted data.
+    /// It is enforced by [`AnyParser`] for Turtle, TriG and N3;
+    /// the other formats are either not nested, or parsed without recursion.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// The maximum nesting of blank node property lists, if any.
+    pub fn max_bnode_nesting(&self) -> Option<usize> {
+        self.max_bnode_nesting
+    }
+    /// Set the maximum nesting of blank node property lists (`[ ... ]`) (default: none).
+    ///
+    /// Like [`set_max_depth`](#method.set_max_depth),
+    /// it is enforced by [`AnyParser`] for Turtle, TriG and N3,
+    /// but only counts blank node property lists.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_max_bnode_nesting(&mut self, max_bnode_nesting: Option<usize>) -> &mut Self {
+        self.max_bnode_nesting = max_bnode_nesting;
+        self
+    }
+
+    /// The maximum nesting of collections, if any.
+    pub fn max_collection_depth(&self) -> Option<usize> {
+        self.max_collection_depth
+    }
+    /// Set the maximum nesting of collections (`( ... )`) (default: none).
+    ///
+    /// Like [`set_max_depth`](#method.set_max_depth),
+    /// it is enforced by [`AnyParser`] for Turtle, TriG and N3,
+    /// but only counts collections.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_max_collection_depth(&mut self, max_collection_depth: Option<usize>) -> &mut Self {
+        self.max_collection_depth = max_collection_depth;
+        self
+    }
+
+    /// The maximum length of literals, if any.
+    pub fn max_literal_len(&self) -> Option<usize> {
+        self.max_literal_len
+    }
+    /// Set the maximum length, in bytes, of the strings of literals (default: none).
+    ///
+    /// The length is that of the string as written in the data,
+    /// escape sequences included and quotes excluded.
+    /// It is enforced by [`AnyParser`] for N-Triples, N-Quads, Turtle, TriG and N3,
+    /// before the parser buffers the whole literal.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_max_literal_len(&mut self, max_literal_len: Option<usize>) -> &mut Self {
+        self.max_literal_len = max_literal_len;
+        self
+    }
+
+    /// The limits to enforce while reading Turtle-like data, if any.
+    pub(crate) fn limits(&self) -> Option<Limits> {
+        let limits = Limits {
+            depth: self.max_depth,
+            bnode_nesting: self.max_bnode_nesting,
+            collection_depth: self.max_collection_depth,
+            literal_len: self.max_literal_len,
+        };
+        if limits == Limits::default() {
+            None
+        } else {
+            Some(limits)
+        }
+    }
+
+    /// How the graph receiving the triples of the document is named, if at all.
+    pub fn graph_naming(&self) -> Option<&GraphNaming> {
+        self.graph_naming.as_ref()
+    }
+    /// Set how [`AnyParser`] names the graph receiving the triples of the document (default: none).
+    ///
+    /// If set, quads parsed in the default graph are put in a named graph,
+    /// whose name is derived from the IRI of the document, i.e. the [base IRI](#method.set_base),
+    /// which must then be set.
+    /// Quads parsed in a named graph (e.g. in TriG) are left unchanged.
+    ///
+    /// This is useful when loading several documents in the same dataset,
+    /// to keep track of the document each triple comes from.
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_graph_naming(&mut self, graph_naming: Option<GraphNaming>) -> &mut Self {
+        self.graph_naming = graph_naming;
+        self
+    }
+
+    /// The progress reporter, if any.
+    pub fn progress(&self) -> Option<&ProgressReporter> {
+        self.progress.as_ref()
+    }
+    /// Set the progress reporter called by [`AnyParser`] during parsing (default: none).
+    ///
+    /// [`AnyParser`]: ../any/struct.AnyParser.html
+    pub fn set_progress(&mut self, progress: Option<ProgressReporter>) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+}
+
+/// The progress of a parser, as reported to a [`ProgressReporter`](struct.ProgressReporter.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of bytes consumed by the parser so far.
+    ///
+    /// Parsers may read ahead of the quads they have emitted.
+    pub bytes: u64,
+    /// The number of quads emitted by the parser so far.
+    pub quads: u64,
+    /// Whether all the data has been parsed.
+    pub done: bool,
+}
+
+/// A callback periodically informed of the [`Progress`](struct.Progress.html) of a parser.
+///
+/// The callback is called every `every` quads,
+/// and once more when the parser is done (i.e. after its last quad).
+/// Errors are not reported, as they are returned by the parser anyway.
+///
+/// # Example
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use sophia::parser::any::{AnyParser, Format};
+/// # use sophia::parser::config::{ParserConfig, ProgressReporter};
+/// let reported = Arc::new(Mutex::new(vec![]));
+/// let r2 = reported.clone();
+/// let mut config = ParserConfig::new();
+/// config.set_progress(Some(ProgressReporter::new(2, move |p| {
+///     r2.lock().unwrap().push(p.quads)
+/// })));
+/// let parser = AnyParser::with_config(Format::Turtle, config);
+/// assert_eq!(parser.parse_str("<tag:s> <tag:p> 1, 2, 3.")?.count(), 3);
+/// assert_eq!(*reported.lock().unwrap(), vec![2, 3]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct ProgressReporter {
+    every: u64,
+    callback: Arc<dyn Fn(Progress) + Send + Sync>,
+}
+
+impl ProgressReporter {
+    /// Build a reporter calling `callback` every `every` quads.
+    ///
+    /// If `every` is 0, `callback` is only called when the parser is done.
+    pub fn new<F>(every: u64, callback: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        ProgressReporter {
+            every,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// The number of quads between two calls of the callback.
+    pub fn every(&self) -> u64 {
+        self.every
+    }
+
+    /// Call the callback.
+    pub fn report(&self, progress: Progress) {
+        (self.callback)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("every", &self.every)
+            .finish()
+    }
+}
+
+/// A function deriving, from the IRI of a document,
+/// the name of the graph receiving its triples
+/// (see [`ParserConfig::set_graph_naming`](struct.ParserConfig.html#method.set_graph_naming)).
+///
+/// # Example
+/// ```
+/// # use sophia::dataset::{inmem::FastDataset, Dataset, MutableDataset};
+/// # use sophia::parser::any::{AnyParser, Format};
+/// # use sophia::parser::config::{GraphNaming, ParserConfig};
+/// let docs = [
+///     ("http://example.org/a.ttl", "<#s> <#p> <#o>."),
+///     ("http://example.org/b.ttl", "<#s> <#p> <#o>."),
+/// ];
+/// let mut d = FastDataset::new();
+/// for (iri, txt) in docs.iter() {
+///     let mut config = ParserConfig::new();
+///     config
+///         .set_base(Some(iri.to_string()))
+///         .set_graph_naming(Some(GraphNaming::document()));
+///     d.insert_all(AnyParser::with_config(Format::Turtle, config).parse_str(txt)?)?;
+/// }
+/// assert_eq!(d.graph_names()?.len(), 2);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct GraphNaming {
+    naming: Arc<dyn Fn(&str) -> String + Send + Sync>,
+}
+
+impl GraphNaming {
+    /// Name the graph after the document itself.
+    pub fn document() -> Self {
+        GraphNaming::new(|document| document.to_string())
+    }
+
+    /// Name the graph with the IRI returned by `naming` for the IRI of the document.
+    pub fn new<F>(naming: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        GraphNaming {
+            naming: Arc::new(naming),
+        }
+    }
+
+    /// The name of the graph receiving the triples of `document`.
+    pub fn name(&self, document: &str) -> String {
+        (self.naming)(document)
+    }
+}
+
+impl fmt::Debug for GraphNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphNaming").finish()
+    }
+}
+
+impl From<&ParserConfig> for TurtleParser {
+    fn from(config: &ParserConfig) -> Self {
+        TurtleParser {
+            base: config.base.clone(),
+        }
+    }
+}
+
+impl From<&ParserConfig> for GTurtleParser {
+    fn from(config: &ParserConfig) -> Self {
+        GTurtleParser {
+            base: config.base.clone(),
+        }
+    }
+}
+
+impl From<&ParserConfig> for TriGParser {
+    fn from(config: &ParserConfig) -> Self {
+        TriGParser {
+            base: config.base.clone(),
+        }
+    }
+}
+
+impl From<&ParserConfig> for GTriGParser {
+    fn from(config: &ParserConfig) -> Self {
+        GTriGParser {
+            base: config.base.clone(),
+        }
+    }
+}
+
+impl From<&ParserConfig> for N3Parser {
+    fn from(config: &ParserConfig) -> Self {
+        N3Parser {
+            base: config.base.clone(),
+            reject_relative: config.strict,
+            ..N3Parser::default()
+        }
+    }
+}
+
+#[cfg(feature = "xml")]
+impl From<&ParserConfig> for RdfXmlParser {
+    fn from(config: &ParserConfig) -> Self {
+        RdfXmlParser {
+            base: config.base.clone(),
+        }
+    }
+}
+
+/// A limit of [`ParserConfig`](struct.ParserConfig.html) exceeded by the parsed data.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum LimitExceeded {
+    /// The nesting depth exceeds the [maximum depth](struct.ParserConfig.html#method.set_max_depth).
+    #[error("nesting depth exceeds {0}")]
+    Depth(usize),
+    /// The nesting of blank node property lists exceeds the
+    /// [maximum](struct.ParserConfig.html#method.set_max_bnode_nesting).
+    #[error("blank node property list nesting exceeds {0}")]
+    BnodeNesting(usize),
+    /// The nesting of collections exceeds the
+    /// [maximum](struct.ParserConfig.html#method.set_max_collection_depth).
+    #[error("collection depth exceeds {0}")]
+    CollectionDepth(usize),
+    /// A literal exceeds the [maximum length](struct.ParserConfig.html#method.set_max_literal_len).
+    #[error("literal length exceeds {0} bytes")]
+    LiteralLength(usize),
+}
+
+/// The limits enforced by a [`LimitReader`](struct.LimitReader.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Limits {
+    pub depth: Option<usize>,
+    pub bnode_nesting: Option<usize>,
+    pub collection_depth: Option<usize>,
+    pub literal_len: Option<usize>,
+}
+
+/// A reader failing as soon as the Turtle-like data it reads exceeds some [`Limits`].
+///
+/// Brackets inside IRIs, strings and comments are ignored,
+/// as well as characters escaped with a backslash in local names.
+/// The exceeded limit is also stored in a shared cell,
+/// so that it can be reported even if the parser hides the I/O error.
+pub(crate) struct LimitReader<R> {
+    inner: R,
+    scanner: LimitScanner,
+    /// The number of bytes at the start of the inner buffer that have already been scanned.
+    scanned: usize,
+    exceeded: Rc<Cell<Option<LimitExceeded>>>,
+}
+
+impl<R> LimitReader<R> {
+    /// Build a reader enforcing `limits` on `inner`;
+    /// `n3` tells whether `<=` must be recognized as an operator rather than the start of an IRI.
+    pub(crate) fn new(
+        inner: R,
+        limits: Limits,
+        n3: bool,
+        exceeded: Rc<Cell<Option<LimitExceeded>>>,
+    ) -> Self {
+        LimitReader {
+            inner,
+            scanner: LimitScanner {
+                limits,
+                n3,
+                depth: 0,
+                bnodes: 0,
+                collections: 0,
+                state: State::Normal,
+            },
+            scanned: 0,
+            exceeded,
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for LimitReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let LimitReader {
+            inner,
+            scanner,
+            scanned,
+            exceeded,
+        } = self;
+        if let Some(limit) = exceeded.get() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, limit));
+        }
+        let buf = inner.fill_buf()?;
+        for b in &buf[*scanned..] {
+            if let Err(limit) = scanner.scan(*b) {
+                exceeded.set(Some(limit));
+                return Err(io::Error::new(io::ErrorKind::InvalidData, limit));
+            }
+        }
+        *scanned = buf.len();
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.scanned = self.scanned.saturating_sub(amt);
+        self.inner.consume(amt);
+    }
+}
+
+impl<R: BufRead> Read for LimitReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let buf = self.fill_buf()?;
+        let n = buf.len().min(out.len());
+        out[..n].copy_from_slice(&buf[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+/// A reader counting the bytes consumed from it.
+pub(crate) struct ByteCounter<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R> ByteCounter<R> {
+    pub(crate) fn new(inner: R, count: Rc<Cell<u64>>) -> Self {
+        ByteCounter { inner, count }
+    }
+}
+
+impl<R: BufRead> BufRead for ByteCounter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count.set(self.count.get() + amt as u64);
+        self.inner.consume(amt);
+    }
+}
+
+impl<R: BufRead> Read for ByteCounter<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(out)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// A result iterator reporting its progress to a [`ProgressReporter`](struct.ProgressReporter.html),
+/// the number of bytes being counted by a [`ByteCounter`](struct.ByteCounter.html).
+pub(crate) struct ProgressSource<I> {
+    inner: I,
+    bytes: Rc<Cell<u64>>,
+    reporter: ProgressReporter,
+    quads: u64,
+    done: bool,
+}
+
+impl<I> ProgressSource<I> {
+    pub(crate) fn new(inner: I, bytes: Rc<Cell<u64>>, reporter: ProgressReporter) -> Self {
+        ProgressSource {
+            inner,
+            bytes,
+            reporter,
+            quads: 0,
+            done: false,
+        }
+    }
+
+    fn report(&self) {
+        self.reporter.report(Progress {
+            bytes: self.bytes.get(),
+            quads: self.quads,
+            done: self.done,
+        });
+    }
+}
+
+impl<I, T, E> Iterator for ProgressSource<I>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.inner.next() {
+            Some(Ok(quad)) => {
+                self.quads += 1;
+                if self.reporter.every > 0 && self.quads.is_multiple_of(self.reporter.every) {
+                    self.report();
+                }
+                Some(Ok(quad))
+            }
+            Some(Err(err)) => Some(Err(err)),
+            None => {
+                self.done = true;
+                self.report();
+                None
+            }
+        }
+    }
+}
+
+/// The lexical state of a [`LimitScanner`](struct.LimitScanner.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Normal,
+    /// After a backslash escaping the next character of a local name.
+    Escape,
+    Comment,
+    /// After `<`, which may start an IRI or the N3 operator `<=`.
+    LessThan,
+    Iri,
+    /// After `n` opening quotes.
+    Opening(u8, u8),
+    /// Inside a string; `quotes` is the number of consecutive closing quotes seen so far,
+    /// and `len` the number of bytes of the string before them.
+    Str {
+        quote: u8,
+        long: bool,
+        escape: bool,
+        quotes: u8,
+        len: usize,
+    },
+}
+
+#[derive(Debug)]
+struct LimitScanner {
+    limits: Limits,
+    n3: bool,
+    depth: usize,
+    bnodes: usize,
+    collections: usize,
+    state: State,
+}
+
+impl LimitScanner {
+    /// Scan one more byte; fail if a limit is exceeded.
+    fn scan(&mut self, b: u8) -> Result<(), LimitExceeded> {
+        match self.state {
+            State::Normal => match b {
+                b'\\' => self.state = State::Escape,
+                b'#' => self.state = State::Comment,
+                b'<' if self.n3 => self.state = State::LessThan,
+                b'<' => self.state = State::Iri,
+                b'"' | b'\'' => self.state = State::Opening(b, 1),
+                b'[' | b'(' | b'{' => {
+                    self.depth += 1;
+                    check(self.depth, self.limits.depth, LimitExceeded::Depth)?;
+                    if b == b'[' {
+                        self.bnodes += 1;
+                        check(
+                            self.bnodes,
+                            self.limits.bnode_nesting,
+                            LimitExceeded::BnodeNesting,
+                        )?;
+                    } else if b == b'(' {
+                        self.collections += 1;
+                        check(
+                            self.collections,
+                            self.limits.collection_depth,
+                            LimitExceeded::CollectionDepth,
+                        )?;
+                    }
+                }
+                b']' | b')' | b'}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if b == b']' {
+                        self.bnodes = self.bnodes.saturating_sub(1);
+                    } else if b == b')' {
+                        self.collections = self.collections.saturating_sub(1);
+                    }
+                }
+                _ => (),
+            },
+            State::Escape => self.state = State::Normal,
+            State::Comment => {
+                if b == b'\n' || b == b'\r' {
+                    self.state = State::Normal;
+                }
+            }
+            State::LessThan => {
+                if b == b'=' {
+                    self.state = State::Normal;
+                } else {
+                    self.state = State::Iri;
+                    return self.scan(b);
+                }
+            }
+            State::Iri => {
+                if b == b'>' {
+                    self.state = State::Normal;
+                }
+            }
+            State::Opening(quote, n) => {
+                if b == quote && n == 1 {
+                    self.state = State::Opening(quote, 2);
+                } else if b == quote {
+                    self.state = State::Str {
+                        quote,
+                        long: true,
+                        escape: false,
+                        quotes: 0,
+                        len: 0,
+                    };
+                } else if n == 2 {
+                    // empty string
+                    self.state = State::Normal;
+                    return self.scan(b);
+                } else {
+                    self.state = State::Str {
+                        quote,
+                        long: false,
+                        escape: false,
+                        quotes: 0,
+                        len: 0,
+                    };
+                    return self.scan(b);
+                }
+            }
+            State::Str {
+                quote,
+                long,
+                escape,
+                quotes,
+                len,
+            } => {
+                if !escape && b == quote {
+                    if !long || quotes == 2 {
+                        self.state = State::Normal;
+                    } else {
+                        self.state = State::Str {
+                            quote,
+                            long,
+                            escape: false,
+                            quotes: quotes + 1,
+                            len,
+                        };
+                    }
+                } else {
+                    // the pending quotes turn out to be part of the string
+                    let len = len + quotes as usize + 1;
+                    check(len, self.limits.literal_len, LimitExceeded::LiteralLength)?;
+                    self.state = State::Str {
+                        quote,
+                        long,
+                        escape: !escape && b == b'\\',
+                        quotes: 0,
+                        len,
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fail with `err` if `value` exceeds `max`.
+fn check(
+    value: usize,
+    max: Option<usize>,
+    err: fn(usize) -> LimitExceeded,
+) -> Result<(), LimitExceeded> {
+    match max {
+        Some(max) if value > max => Err(err(max)),
+        _ => Ok(()),
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Read `data` through a `LimitReader`, and return the exceeded limit if any.
+    fn exceeded<R: BufRead>(data: R, limits: Limits) -> Option<LimitExceeded> {
+        exceeded_with(data, limits, false)
+    }
+
+    fn exceeded_with<R: BufRead>(data: R, limits: Limits, n3: bool) -> Option<LimitExceeded> {
+        let cell = Rc::new(Cell::new(None));
+        let mut out = String::new();
+        let res = LimitReader::new(data, limits, n3, cell.clone()).read_to_string(&mut out);
+        assert_eq!(res.is_err(), cell.get().is_some());
+        cell.get()
+    }
+
+    fn depth_limits(max_depth: usize) -> Limits {
+        Limits {
+            depth: Some(max_depth),
+            ..Limits::default()
+        }
+    }
+
+    fn depth_ok(txt: &str, max_depth: usize) -> bool {
+        exceeded(txt.as_bytes(), depth_limits(max_depth)).is_none()
+    }
+
+    fn n3_depth_ok(txt: &str, max_depth: usize) -> bool {
+        exceeded_with(txt.as_bytes(), depth_limits(max_depth), true).is_none()
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = "<tag:s> <tag:p> [ <tag:q> ( [ <tag:r> 1 ] ) ].";
+        assert!(depth_ok(nested, 3));
+        assert!(!depth_ok(nested, 2));
+        assert!(depth_ok("[][][][]", 1));
+
+        // brackets in IRIs, strings and comments are ignored
+        assert!(depth_ok("<http://[::1]/(> <tag:p> '[('.", 0));
+        assert!(depth_ok(
+            "<tag:s> <tag:p> \"\"\"[\"\" \\\"\"\"\" ; <tag:q> \"\".",
+            0
+        ));
+        assert!(depth_ok("# [[[\n<tag:s> <tag:p> <tag:o>.", 0));
+        assert!(!depth_ok("'''(''' [ <tag:p> ''\n]", 0));
+        // N3 operator
+        assert!(n3_depth_ok("{ ?x <= ?y } => { ?y }.", 1));
+        assert!(!n3_depth_ok("{ ?x <= { ?y } }.", 1));
+        // ... which is the start of an IRI in other formats
+        assert!(depth_ok("<tag:s> <tag:p> <=[[[>.", 0));
+    }
+
+    #[test]
+    fn escaped_local_names() {
+        let nested = "[ ex:p [ ex:p [] ] ].";
+        for esc in &["\\#", "\\'", "\\.", "\\("] {
+            let txt = format!("ex:s ex:p ex:a{}b, {}", esc, nested);
+            assert!(depth_ok(&txt, 3), "{}", txt);
+            assert!(!depth_ok(&txt, 2), "{}", txt);
+        }
+        assert!(depth_ok("ex:s ex:p ex:a\\(\\(\\(b.", 0));
+        assert!(depth_ok("ex:s ex:p ex:a\\[\\]\\)b.", 0));
+        assert_eq!(
+            exceeded(
+                "ex:s ex:p ex:a\\'b, 'abc'.".as_bytes(),
+                Limits {
+                    literal_len: Some(2),
+                    ..Limits::default()
+                }
+            ),
+            Some(LimitExceeded::LiteralLength(2))
+        );
+    }
+
+    #[test]
+    fn depth_limit_across_buffers() {
+        let mut data = String::new();
+        for _ in 0..100 {
+            data.push_str("<tag:s> <tag:p> \"[[[[\" .\n");
+        }
+        data.push_str("<tag:s> <tag:p> [ <tag:p> [] ].");
+        let limits = |max| Limits {
+            depth: Some(max),
+            ..Limits::default()
+        };
+        let reader = io::BufReader::with_capacity(7, data.as_bytes());
+        assert_eq!(exceeded(reader, limits(2)), None);
+        let reader = io::BufReader::with_capacity(7, data.as_bytes());
+        assert_eq!(exceeded(reader, limits(1)), Some(LimitExceeded::Depth(1)));
+    }
+
+    #[test]
+    fn nesting_limits() {
+        let nested = "<tag:s> <tag:p> [ <tag:q> ( [ <tag:r> ( 1 ) ] ) ], [], ().";
+        let bnodes = |max| Limits {
+            bnode_nesting: Some(max),
+            ..Limits::default()
+        };
+        assert_eq!(exceeded(nested.as_bytes(), bnodes(2)), None);
+        assert_eq!(
+            exceeded(nested.as_bytes(), bnodes(1)),
+            Some(LimitExceeded::BnodeNesting(1))
+        );
+        let collections = |max| Limits {
+            collection_depth: Some(max),
+            ..Limits::default()
+        };
+        assert_eq!(exceeded(nested.as_bytes(), collections(2)), None);
+        assert_eq!(
+            exceeded(nested.as_bytes(), collections(1)),
+            Some(LimitExceeded::CollectionDepth(1))
+        );
+        // brackets in strings are ignored
+        assert_eq!(exceeded("'[[(('".as_bytes(), bnodes(0)), None);
+        assert_eq!(exceeded("'[[(('".as_bytes(), collections(0)), None);
+    }
+
+    #[test]
+    fn literal_len_limit() {
+        let len = |max| Limits {
+            literal_len: Some(max),
+            ..Limits::default()
+        };
+        let cases: &[(&str, usize)] = &[
+            ("<tag:s> <tag:p> \"abcd\".", 4),
+            ("<tag:s> <tag:p> 'ab\\'d'.", 5),
+            ("<tag:s> <tag:p> '''ab''c'''.", 5),
+            ("<tag:s> <tag:p> \"\"\"a\"b\"\"c\"\"\".", 6),
+            ("<tag:looooooooooong> <tag:p> 'a'. # looooooooooong", 1),
+        ];
+        for (txt, l) in cases {
+            assert_eq!(exceeded(txt.as_bytes(), len(*l)), None, "{}", txt);
+            assert_eq!(
+                exceeded(txt.as_bytes(), len(l - 1)),
+                Some(LimitExceeded::LiteralLength(l - 1)),
+                "{}",
+                txt
+            );
+        }
+        assert_eq!(exceeded("<tag:s> <tag:p> \"\".".as_bytes(), len(0)), None);
+    }
+
+    #[test]
+    fn into_parsers() {
+        let mut config = ParserConfig::new();
+        config.set_base(Some("tag:".into())).set_strict(false);
+        assert_eq!(TurtleParser::from(&config).base.as_deref(), Some("tag:"));
+        assert_eq!(GTurtleParser::from(&config).base.as_deref(), Some("tag:"));
+        assert!(!N3Parser::from(&config).reject_relative);
+        assert!(N3Parser::from(&ParserConfig::new()).reject_relative);
+        assert_eq!(TriGParser::from(&config).base.as_deref(), Some("tag:"));
+    }
+}
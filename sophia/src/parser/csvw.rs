@@ -0,0 +1,1027 @@
+//! Conversion of tabular data to RDF, per [CSV on the Web] (CSVW).
+//!
+//! A [`CsvwParser`] is built from a [metadata] descriptor (a JSON document
+//! describing the columns of a CSV file, and how to map them to RDF),
+//! and then parses CSV files into triples, as described in [csv2rdf].
+//! Rows are converted one at a time, so large files can be streamed.
+//!
+//! Both the *standard* mode (the default),
+//! which describes the table and its rows with the [CSVW vocabulary],
+//! and the *minimal* mode, which only produces the triples derived from cells,
+//! are supported.
+//!
+//! NB: the following features of the recommendation are not supported:
+//! * tables groups with more than one table (as a parser processes a single CSV file);
+//! * validation and normalization of cell values against their datatype
+//!   (the `format` of datatypes, `required`, `minimum`, etc. are ignored);
+//! * ordered lists (`ordered: true`) and foreign keys;
+//! * JSON-LD contexts other than the CSVW one (`@context` is ignored).
+//!
+//! This module requires the `csvw` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::parser::csvw::CsvwParser;
+//! # use sophia::parser::TripleParser;
+//! # use sophia::triple::stream::TripleSource;
+//! # fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let metadata = r##"{
+//!   "@context": "http://www.w3.org/ns/csvw",
+//!   "url": "people.csv",
+//!   "tableSchema": {
+//!     "aboutUrl": "#person-{id}",
+//!     "columns": [
+//!       { "name": "id", "titles": "id", "suppressOutput": true },
+//!       { "name": "name", "titles": "name", "propertyUrl": "foaf:name" },
+//!       { "name": "age", "titles": "age", "datatype": "integer" }
+//!     ]
+//!   }
+//! }"##;
+//! let csv = "id,name,age\n1,Alice,42\n2,Bob,39\n";
+//! let mut parser = CsvwParser::new(metadata, "http://example.org/people.csv-metadata.json")?;
+//! parser.set_minimal(true);
+//! let g: FastGraph = parser.parse_str(csv).collect_triples()?;
+//! assert_eq!(g.triples().count(), 4);
+//! # Ok(()) }
+//! ```
+//!
+//! [CSV on the Web]: https://www.w3.org/TR/tabular-data-primer/
+//! [`CsvwParser`]: struct.CsvwParser.html
+//! [metadata]: https://www.w3.org/TR/tabular-metadata/
+//! [csv2rdf]: https://www.w3.org/TR/csv2rdf/
+//! [CSVW vocabulary]: https://www.w3.org/ns/csvw
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+
+use csv::StringRecord;
+use json::JsonValue;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::TripleParser;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The CSVW namespace.
+mod csvw_ns {
+    sophia_api::namespace!(
+        "http://www.w3.org/ns/csvw#",
+        Table,
+        TableGroup,
+        Row,
+        table,
+        row,
+        rownum,
+        url,
+        describes
+    );
+}
+
+/// The prefixes that can be used in `aboutUrl`, `propertyUrl` and `valueUrl`
+/// (a subset of the [RDFa initial context](https://www.w3.org/2011/rdfa-context/rdfa-1.1)).
+const PREFIXES: &[(&str, &str)] = &[
+    ("as", "https://www.w3.org/ns/activitystreams#"),
+    ("csvw", "http://www.w3.org/ns/csvw#"),
+    ("dc", "http://purl.org/dc/terms/"),
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("dcterms", "http://purl.org/dc/terms/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("schema", "http://schema.org/"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+/// CSVW parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct CsvwParser {
+    url: String,
+    dialect: Dialect,
+    schema: Inherited,
+    columns: Vec<Column>,
+    minimal: bool,
+}
+
+impl CsvwParser {
+    /// Build a parser from a metadata descriptor,
+    /// located at `metadata_url` (against which the URL of the table is resolved).
+    ///
+    /// The descriptor must describe a single table,
+    /// either directly or as the only table of a table group.
+    pub fn new(metadata: &str, metadata_url: &str) -> Result<Self, CsvwError> {
+        let doc = json::parse(metadata)?;
+        if !doc.is_object() {
+            return metadata_error("descriptor must be an object");
+        }
+        let (table, group) = if doc["tables"].is_null() {
+            (&doc, Inherited::default())
+        } else if doc["tables"].len() == 1 {
+            (
+                &doc["tables"][0],
+                Inherited::read(&doc, &Inherited::default())?,
+            )
+        } else {
+            return metadata_error("only table groups with a single table are supported");
+        };
+        let url = match string_prop(table, "url")? {
+            Some(url) => resolve(metadata_url, &url).map_err(CsvwError::Metadata)?,
+            None => return metadata_error("table has no url"),
+        };
+        let dialect = if table["dialect"].is_null() {
+            Dialect::read(&doc["dialect"])?
+        } else {
+            Dialect::read(&table["dialect"])?
+        };
+        let schema = &table["tableSchema"];
+        if !(schema.is_null() || schema.is_object()) {
+            return metadata_error("tableSchema must be an object");
+        }
+        let schema_props = Inherited::read(schema, &Inherited::read(table, &group)?)?;
+        let columns = schema["columns"]
+            .members()
+            .enumerate()
+            .map(|(i, column)| Column::read(column, i, &schema_props))
+            .collect::<Result<_, _>>()?;
+        Ok(CsvwParser {
+            url,
+            dialect,
+            schema: schema_props,
+            columns,
+            minimal: false,
+        })
+    }
+
+    /// The URL of the table described by this parser.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether this parser uses the minimal mode.
+    pub fn minimal(&self) -> bool {
+        self.minimal
+    }
+
+    /// Set whether this parser uses the minimal mode
+    /// (only producing the triples derived from cells)
+    /// rather than the standard mode (also describing the table and its rows).
+    pub fn set_minimal(&mut self, minimal: bool) -> &mut Self {
+        self.minimal = minimal;
+        self
+    }
+
+    /// Expand `template` with `vars`, and resolve it against the URL of the table.
+    fn template_iri<F>(&self, template: &str, vars: F) -> Result<BoxTerm, String>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let expanded = expand_prefix(&expand_template(template, vars)?);
+        let iri = resolve(&self.url, &expanded)?;
+        BoxTerm::new_iri(iri).map_err(|e| e.to_string())
+    }
+
+    /// Push the triples derived from the cells of `record` into `triples`,
+    /// and return the subjects of these triples.
+    fn convert_row(
+        &self,
+        record: &StringRecord,
+        row: usize,
+        source_row: usize,
+        default_subject: &BoxTerm,
+        triples: &mut VecDeque<Result<[BoxTerm; 3], CsvwError>>,
+    ) -> Vec<BoxTerm> {
+        let columns = &self.columns;
+        // the values of non-virtual columns, or None for null cells
+        let cells: Vec<Option<&str>> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                if column.is_virtual {
+                    return None;
+                }
+                let cell = record.get(i).unwrap_or("");
+                if column.props.null.iter().any(|null| null == cell) {
+                    None
+                } else {
+                    Some(cell)
+                }
+            })
+            .collect();
+        let mut vars: HashMap<&str, String> = columns
+            .iter()
+            .zip(&cells)
+            .filter_map(|(column, cell)| Some((&column.name[..], (*cell)?.to_string())))
+            .collect();
+        vars.insert("_row", row.to_string());
+        vars.insert("_sourceRow", source_row.to_string());
+
+        let mut subjects: Vec<BoxTerm> = vec![];
+        for (i, (column, cell)) in columns.iter().zip(&cells).enumerate() {
+            if column.suppress_output || (cell.is_none() && !column.is_virtual) {
+                continue;
+            }
+            let column_vars = |name: &str| match name {
+                "_column" | "_sourceColumn" => Some((i + 1).to_string()),
+                "_name" => Some(column.name.clone()),
+                _ => vars.get(name).cloned(),
+            };
+            let result = self.convert_cell(column, *cell, default_subject, column_vars);
+            match result {
+                Ok((s, p, objects)) => {
+                    if !subjects.contains(&s) {
+                        subjects.push(s.clone());
+                    }
+                    for o in objects {
+                        triples.push_back(Ok([s.clone(), p.clone(), o]));
+                    }
+                }
+                Err(message) => triples.push_back(Err(CsvwError::Cell {
+                    row: source_row,
+                    column: i + 1,
+                    message,
+                })),
+            }
+        }
+        subjects
+    }
+
+    /// The subject, predicate and objects of the triples derived from a cell.
+    fn convert_cell<F>(
+        &self,
+        column: &Column,
+        cell: Option<&str>,
+        default_subject: &BoxTerm,
+        vars: F,
+    ) -> Result<(BoxTerm, BoxTerm, Vec<BoxTerm>), String>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let props = &column.props;
+        let s = match &props.about_url {
+            Some(template) => self.template_iri(template, &vars)?,
+            None => default_subject.clone(),
+        };
+        let p = match &props.property_url {
+            Some(template) => self.template_iri(template, &vars)?,
+            None => self.template_iri("{#_name}", &vars)?,
+        };
+        let objects = match (&props.value_url, cell) {
+            (Some(template), _) => vec![self.template_iri(template, &vars)?],
+            (None, Some(cell)) => match &props.separator {
+                Some(separator) => cell
+                    .split(&separator[..])
+                    .filter(|value| !props.null.iter().any(|null| null == value))
+                    .map(|value| props.literal(value))
+                    .collect::<Result<_, _>>()?,
+                None => vec![props.literal(cell)?],
+            },
+            // virtual column without valueUrl
+            (None, None) => vec![],
+        };
+        Ok((s, p, objects))
+    }
+}
+
+impl<R: Read> TripleParser<R> for CsvwParser {
+    type Source = CsvwSource<R>;
+    fn parse(&self, data: R) -> Self::Source {
+        let dialect = &self.dialect;
+        let mut builder = csv::ReaderBuilder::new();
+        builder
+            .delimiter(dialect.delimiter)
+            .double_quote(dialect.double_quote)
+            .comment(dialect.comment_prefix)
+            .has_headers(false)
+            .flexible(true)
+            .trim(if dialect.trim {
+                csv::Trim::All
+            } else {
+                csv::Trim::None
+            });
+        match dialect.quote_char {
+            Some(quote) => builder.quote(quote),
+            None => builder.quoting(false),
+        };
+        let mut source = CsvwSource {
+            parser: self.clone(),
+            records: builder.from_reader(data).into_records(),
+            read: 0,
+            row: 0,
+            bnodes: 0,
+            table: None,
+            pending: VecDeque::new(),
+            done: false,
+        };
+        if !self.minimal {
+            let group = source.new_bnode();
+            let table = source.new_bnode();
+            let url =
+                BoxTerm::new_iri(&self.url[..]).map_err(|e| CsvwError::Metadata(e.to_string()));
+            source.pending.extend(vec![
+                Ok([
+                    group.clone(),
+                    BoxTerm::copy(&rdf::type_),
+                    BoxTerm::copy(&csvw_ns::TableGroup),
+                ]),
+                Ok([group, BoxTerm::copy(&csvw_ns::table), table.clone()]),
+                Ok([
+                    table.clone(),
+                    BoxTerm::copy(&rdf::type_),
+                    BoxTerm::copy(&csvw_ns::Table),
+                ]),
+                url.map(|url| [table.clone(), BoxTerm::copy(&csvw_ns::url), url]),
+            ]);
+            source.table = Some(table);
+        }
+        source
+    }
+}
+
+/// The triple source produced by [`CsvwParser`](struct.CsvwParser.html).
+pub struct CsvwSource<R> {
+    parser: CsvwParser,
+    records: csv::StringRecordsIntoIter<R>,
+    /// The number of records read so far (including header and skipped rows).
+    read: usize,
+    /// The number of rows converted so far.
+    row: usize,
+    bnodes: usize,
+    /// The node representing the table (in standard mode).
+    table: Option<BoxTerm>,
+    pending: VecDeque<Result<[BoxTerm; 3], CsvwError>>,
+    done: bool,
+}
+
+impl<R: Read> CsvwSource<R> {
+    fn new_bnode(&mut self) -> BoxTerm {
+        self.bnodes += 1;
+        BoxTerm::new_bnode_unchecked(format!("csvw{}", self.bnodes))
+    }
+
+    fn process(&mut self, record: StringRecord) {
+        self.read += 1;
+        let dialect = &self.parser.dialect;
+        let source_row = record
+            .position()
+            .map_or(self.read, |position| position.line() as usize);
+        if self.read <= dialect.skip_rows {
+            return;
+        }
+        if self.read <= dialect.skip_rows + dialect.header_row_count {
+            // the last header row provides the titles of the columns, if the metadata has none
+            if self.read == dialect.skip_rows + dialect.header_row_count
+                && self.parser.columns.is_empty()
+            {
+                self.parser.columns = columns_from_titles(record.iter(), &self.parser.schema);
+            }
+            return;
+        }
+        if dialect.skip_blank_rows && record.iter().all(str::is_empty) {
+            return;
+        }
+        if self.parser.columns.is_empty() {
+            // no metadata nor header: columns are named after their position
+            self.parser.columns =
+                columns_from_titles(record.iter().map(|_| ""), &self.parser.schema);
+        }
+        self.row += 1;
+        let row = self.row;
+
+        let default_subject = self.new_bnode();
+        let row_node = match self.table.clone() {
+            None => None,
+            Some(table) => {
+                let row_node = self.new_bnode();
+                let url = resolve(&self.parser.url, &format!("#row={}", source_row))
+                    .and_then(|url| BoxTerm::new_iri(url).map_err(|e| e.to_string()));
+                let rownum = BoxTerm::new_literal_dt_unchecked(row.to_string(), xsd::integer);
+                self.pending.extend(vec![
+                    Ok([table, BoxTerm::copy(&csvw_ns::row), row_node.clone()]),
+                    Ok([
+                        row_node.clone(),
+                        BoxTerm::copy(&rdf::type_),
+                        BoxTerm::copy(&csvw_ns::Row),
+                    ]),
+                    Ok([row_node.clone(), BoxTerm::copy(&csvw_ns::rownum), rownum]),
+                ]);
+                match url {
+                    Ok(url) => self.pending.push_back(Ok([
+                        row_node.clone(),
+                        BoxTerm::copy(&csvw_ns::url),
+                        url,
+                    ])),
+                    Err(message) => self.pending.push_back(Err(CsvwError::Cell {
+                        row: source_row,
+                        column: 0,
+                        message,
+                    })),
+                }
+                Some(row_node)
+            }
+        };
+        let subjects = self.parser.convert_row(
+            &record,
+            row,
+            source_row,
+            &default_subject,
+            &mut self.pending,
+        );
+        if let Some(row_node) = row_node {
+            for s in subjects {
+                self.pending.push_back(Ok([
+                    row_node.clone(),
+                    BoxTerm::copy(&csvw_ns::describes),
+                    s,
+                ]));
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for CsvwSource<R> {
+    type Item = Result<[BoxTerm; 3], CsvwError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(triple) = self.pending.pop_front() {
+                return Some(triple);
+            }
+            if self.done {
+                return None;
+            }
+            match self.records.next() {
+                None => self.done = true,
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err.into()));
+                }
+                Some(Ok(record)) => self.process(record),
+            }
+        }
+    }
+}
+
+/// Errors raised by the [`CsvwParser`](struct.CsvwParser.html).
+#[derive(Debug, Error)]
+pub enum CsvwError {
+    /// The metadata descriptor is not valid JSON.
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] json::Error),
+    /// The metadata descriptor is valid JSON, but not a valid (or supported) CSVW descriptor.
+    #[error("Invalid metadata: {0}")]
+    Metadata(String),
+    /// The CSV data could not be read.
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    /// A cell could not be converted
+    /// (typically because a URI template produced an invalid IRI).
+    #[error("Invalid cell at row {row}, column {column}: {message}")]
+    Cell {
+        /// The row of the cell in the CSV data (starting at 1).
+        row: usize,
+        /// The column of the cell (starting at 1).
+        column: usize,
+        /// A description of the error.
+        message: String,
+    },
+}
+
+fn metadata_error<T>(message: &str) -> Result<T, CsvwError> {
+    Err(CsvwError::Metadata(message.to_string()))
+}
+
+/// The value of the string property `key` of `desc`, if any.
+fn string_prop(desc: &JsonValue, key: &str) -> Result<Option<String>, CsvwError> {
+    let value = &desc[key];
+    if value.is_null() {
+        return Ok(None);
+    }
+    match value.as_str() {
+        Some(txt) => Ok(Some(txt.to_string())),
+        None => Err(CsvwError::Metadata(format!("{} must be a string", key))),
+    }
+}
+
+/// The value of the boolean property `key` of `desc`, or `default`.
+fn bool_prop(desc: &JsonValue, key: &str, default: bool) -> Result<bool, CsvwError> {
+    let value = &desc[key];
+    if value.is_null() {
+        return Ok(default);
+    }
+    value
+        .as_bool()
+        .ok_or_else(|| CsvwError::Metadata(format!("{} must be a boolean", key)))
+}
+
+/// The properties that are inherited by columns from their schema, table and table group.
+#[derive(Clone, Debug)]
+struct Inherited {
+    about_url: Option<String>,
+    property_url: Option<String>,
+    value_url: Option<String>,
+    /// The IRI of the datatype.
+    datatype: Option<String>,
+    lang: Option<String>,
+    null: Vec<String>,
+    separator: Option<String>,
+}
+
+impl Default for Inherited {
+    fn default() -> Self {
+        Inherited {
+            about_url: None,
+            property_url: None,
+            value_url: None,
+            datatype: None,
+            lang: None,
+            null: vec![String::new()],
+            separator: None,
+        }
+    }
+}
+
+impl Inherited {
+    /// The properties of `desc`, or those of `parent` if `desc` does not override them.
+    fn read(desc: &JsonValue, parent: &Inherited) -> Result<Self, CsvwError> {
+        let mut props = parent.clone();
+        for (key, prop) in [
+            ("aboutUrl", &mut props.about_url),
+            ("propertyUrl", &mut props.property_url),
+            ("valueUrl", &mut props.value_url),
+            ("lang", &mut props.lang),
+            ("separator", &mut props.separator),
+        ] {
+            if let Some(value) = string_prop(desc, key)? {
+                *prop = Some(value);
+            }
+        }
+        let null = &desc["null"];
+        if let Some(null) = null.as_str() {
+            props.null = vec![null.to_string()];
+        } else if null.is_array() {
+            props.null = null
+                .members()
+                .map(|null| null.as_str().map(str::to_string))
+                .collect::<Option<_>>()
+                .ok_or_else(|| CsvwError::Metadata("null must contain strings".to_string()))?;
+        } else if !null.is_null() {
+            return metadata_error("null must be a string or an array");
+        }
+        let datatype = &desc["datatype"];
+        if !datatype.is_null() {
+            props.datatype = Some(datatype_iri(datatype)?);
+        }
+        Ok(props)
+    }
+
+    fn literal(&self, value: &str) -> Result<BoxTerm, String> {
+        let literal = match &self.datatype {
+            Some(datatype) if datatype != &*xsd::string.value() => {
+                // values of other datatypes are not whitespace-sensitive
+                BoxTerm::new_iri(&datatype[..])
+                    .and_then(|datatype| BoxTerm::new_literal_dt(value.trim(), datatype))
+            }
+            _ => match &self.lang {
+                Some(lang) if lang != "und" => BoxTerm::new_literal_lang(value, &lang[..]),
+                _ => BoxTerm::new_literal_dt(value, xsd::string),
+            },
+        };
+        literal.map_err(|e| e.to_string())
+    }
+}
+
+/// The IRI of the datatype described by `desc`
+/// (either the name of a built-in datatype, or an object with a `base` or an `@id`).
+fn datatype_iri(desc: &JsonValue) -> Result<String, CsvwError> {
+    let name = if desc.is_object() {
+        if let Some(id) = string_prop(desc, "@id")? {
+            return Ok(expand_prefix(&id));
+        }
+        string_prop(desc, "base")?.unwrap_or_else(|| "string".to_string())
+    } else {
+        match desc.as_str() {
+            Some(name) => name.to_string(),
+            None => return metadata_error("datatype must be a string or an object"),
+        }
+    };
+    let xsd_name = match &name[..] {
+        "any" => "anyAtomicType",
+        "binary" => "base64Binary",
+        "datetime" => "dateTime",
+        "number" => "double",
+        "json" => return Ok("http://www.w3.org/ns/csvw#JSON".to_string()),
+        "html" => return Ok(rdf::HTML.value().to_string()),
+        "xml" => return Ok(rdf::XMLLiteral.value().to_string()),
+        name if name.chars().all(|c| c.is_ascii_alphanumeric()) => name,
+        name => return Err(CsvwError::Metadata(format!("unknown datatype {}", name))),
+    };
+    Ok(format!("http://www.w3.org/2001/XMLSchema#{}", xsd_name))
+}
+
+/// A column of a table.
+#[derive(Clone, Debug)]
+struct Column {
+    name: String,
+    is_virtual: bool,
+    suppress_output: bool,
+    props: Inherited,
+}
+
+impl Column {
+    fn read(desc: &JsonValue, index: usize, schema: &Inherited) -> Result<Self, CsvwError> {
+        let titles = &desc["titles"];
+        let title = if titles.is_object() {
+            // titles by language
+            titles.entries().next().map(|(_, titles)| titles)
+        } else {
+            Some(titles)
+        }
+        .and_then(|titles| {
+            if titles.is_array() {
+                titles[0].as_str()
+            } else {
+                titles.as_str()
+            }
+        });
+        let name = match (string_prop(desc, "name")?, title) {
+            (Some(name), _) => name,
+            (None, Some(title)) => encode(title, false),
+            (None, None) => format!("_col.{}", index + 1),
+        };
+        Ok(Column {
+            name,
+            is_virtual: bool_prop(desc, "virtual", false)?,
+            suppress_output: bool_prop(desc, "suppressOutput", false)?,
+            props: Inherited::read(desc, schema)?,
+        })
+    }
+}
+
+/// Columns named after `titles` (or after their position for empty titles).
+fn columns_from_titles<'a, I>(titles: I, schema: &Inherited) -> Vec<Column>
+where
+    I: Iterator<Item = &'a str>,
+{
+    titles
+        .enumerate()
+        .map(|(i, title)| Column {
+            name: if title.is_empty() {
+                format!("_col.{}", i + 1)
+            } else {
+                encode(title, false)
+            },
+            is_virtual: false,
+            suppress_output: false,
+            props: schema.clone(),
+        })
+        .collect()
+}
+
+/// The dialect of a CSV file.
+#[derive(Clone, Debug)]
+struct Dialect {
+    delimiter: u8,
+    quote_char: Option<u8>,
+    double_quote: bool,
+    comment_prefix: Option<u8>,
+    header_row_count: usize,
+    skip_rows: usize,
+    skip_blank_rows: bool,
+    trim: bool,
+}
+
+impl Dialect {
+    fn read(desc: &JsonValue) -> Result<Self, CsvwError> {
+        if !(desc.is_null() || desc.is_object()) {
+            return metadata_error("dialect must be an object");
+        }
+        // a single ASCII character, which may be explicitly disabled with null
+        let byte = |key: &str, default: u8| -> Result<Option<u8>, CsvwError> {
+            let value = &desc[key];
+            if value.is_null() {
+                return Ok(if desc.has_key(key) {
+                    None
+                } else {
+                    Some(default)
+                });
+            }
+            match value.as_str().map(str::as_bytes) {
+                Some([b]) if b.is_ascii() => Ok(Some(*b)),
+                _ => Err(CsvwError::Metadata(format!(
+                    "{} must be a single ASCII character",
+                    key
+                ))),
+            }
+        };
+        let count = |key: &str, default: usize| -> Result<usize, CsvwError> {
+            let value = &desc[key];
+            if value.is_null() {
+                Ok(default)
+            } else {
+                value.as_usize().ok_or_else(|| {
+                    CsvwError::Metadata(format!("{} must be a non-negative integer", key))
+                })
+            }
+        };
+        let header = bool_prop(desc, "header", true)?;
+        let trim = &desc["trim"];
+        Ok(Dialect {
+            delimiter: byte("delimiter", b',')?.unwrap_or(b','),
+            quote_char: byte("quoteChar", b'"')?,
+            double_quote: bool_prop(desc, "doubleQuote", true)?,
+            comment_prefix: byte("commentPrefix", b'#')?,
+            header_row_count: count("headerRowCount", if header { 1 } else { 0 })?,
+            skip_rows: count("skipRows", 0)?,
+            skip_blank_rows: bool_prop(desc, "skipBlankRows", false)?,
+            // trim may also be "start" or "end", which are approximated by trimming both
+            trim: trim
+                .as_bool()
+                .unwrap_or_else(|| trim.is_null() || trim.is_string()),
+        })
+    }
+}
+
+/// Resolve `iri` against `base`.
+fn resolve(base: &str, iri: &str) -> Result<String, String> {
+    oxiri::Iri::parse(base)
+        .and_then(|base| base.resolve(iri))
+        .map(oxiri::Iri::into_inner)
+        .map_err(|e| format!("{} ({})", e, iri))
+}
+
+/// Expand `txt` if it is a prefixed name using one of the known [prefixes](constant.PREFIXES.html).
+fn expand_prefix(txt: &str) -> String {
+    if let Some(colon) = txt.find(':') {
+        let (prefix, suffix) = (&txt[..colon], &txt[colon + 1..]);
+        if !suffix.starts_with("//") {
+            if let Some((_, ns)) = PREFIXES.iter().find(|(p, _)| *p == prefix) {
+                return format!("{}{}", ns, suffix);
+            }
+        }
+    }
+    txt.to_string()
+}
+
+/// Expand a URI template ([RFC 6570], up to level 3), getting the value of variables from `vars`.
+///
+/// [RFC 6570]: https://tools.ietf.org/html/rfc6570
+fn expand_template<F>(template: &str, vars: F) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => return Err(format!("unclosed expression in template {}", template)),
+        };
+        let expression = &rest[start + 1..end];
+        // (operator, first, separator, named, allow reserved characters)
+        let (operator, first, separator, named, reserved) = match expression.chars().next() {
+            Some('+') => (true, "", ",", false, true),
+            Some('#') => (true, "#", ",", false, true),
+            Some('.') => (true, ".", ".", false, false),
+            Some('/') => (true, "/", "/", false, false),
+            Some(';') => (true, ";", ";", true, false),
+            Some('?') => (true, "?", "&", true, false),
+            Some('&') => (true, "&", "&", true, false),
+            _ => (false, "", ",", false, false),
+        };
+        let names = if operator {
+            &expression[1..]
+        } else {
+            expression
+        };
+        let values: Vec<String> = names
+            .split(',')
+            .filter_map(|name| {
+                let value = encode(&vars(name)?, reserved);
+                Some(match (named, first) {
+                    (false, _) => value,
+                    (true, ";") if value.is_empty() => name.to_string(),
+                    (true, _) => format!("{}={}", name, value),
+                })
+            })
+            .collect();
+        if !values.is_empty() {
+            expanded.push_str(first);
+            expanded.push_str(&values.join(separator));
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Percent-encode all characters of `txt` except unreserved ones
+/// (and reserved ones and percent-encoded triplets if `reserved` is true).
+fn encode(txt: &str, reserved: bool) -> String {
+    let mut encoded = String::with_capacity(txt.len());
+    let bytes = txt.as_bytes();
+    for (i, c) in txt.char_indices() {
+        let keep = c.is_ascii_alphanumeric()
+            || "-._~".contains(c)
+            || (reserved && ":/?#[]@!$&'()*+,;=".contains(c))
+            || (reserved
+                && c == '%'
+                && bytes.len() > i + 2
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit());
+        if keep {
+            encoded.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    encoded
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::triple::stream::TripleSource;
+    use sophia_api::graph::isomorphic_graphs;
+    use test_case::test_case;
+
+    const METADATA_URL: &str = "http://example.org/tree-ops.csv-metadata.json";
+
+    const CSV: &str = "GID,On Street,Species,Trim Cycle,Inventory Date\n\
+                       1,ADDISON AV,Celtis australis,Large Tree Routine Prune,10/18/2010\n\
+                       2,EMERSON ST,Liquidambar styraciflua,Large Tree Routine Prune,6/2/2010\n";
+
+    fn parse(
+        metadata: &str,
+        csv: &str,
+        minimal: bool,
+    ) -> Result<FastGraph, Box<dyn std::error::Error>> {
+        let mut parser = CsvwParser::new(metadata, METADATA_URL)?;
+        parser.set_minimal(minimal);
+        Ok(parser.parse_str(csv).collect_triples()?)
+    }
+
+    fn turtle(ttl: &str) -> FastGraph {
+        crate::parser::turtle::parse_str(ttl)
+            .collect_triples()
+            .unwrap()
+    }
+
+    #[test]
+    fn minimal_without_schema() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(r#"{"url": "tree-ops.csv"}"#, CSV, true)?;
+        let expected = turtle(
+            r#"
+            @base <http://example.org/tree-ops.csv> .
+            [ <#GID> "1"; <#On%20Street> "ADDISON AV"; <#Species> "Celtis australis";
+              <#Trim%20Cycle> "Large Tree Routine Prune"; <#Inventory%20Date> "10/18/2010" ].
+            [ <#GID> "2"; <#On%20Street> "EMERSON ST"; <#Species> "Liquidambar styraciflua";
+              <#Trim%20Cycle> "Large Tree Routine Prune"; <#Inventory%20Date> "6/2/2010" ].
+        "#,
+        );
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    const METADATA: &str = r##"{
+        "@context": ["http://www.w3.org/ns/csvw", {"@language": "en"}],
+        "url": "tree-ops.csv",
+        "dialect": {"trim": true},
+        "tableSchema": {
+            "columns": [
+                {"name": "GID", "titles": "GID", "datatype": "string", "suppressOutput": true},
+                {"name": "on_street", "titles": "On Street", "propertyUrl": "schema:streetAddress"},
+                {"name": "species", "titles": "Species", "lang": "en"},
+                {"name": "trim_cycle", "titles": "Trim Cycle", "null": "Large Tree Routine Prune"},
+                {"name": "inventory_date", "titles": "Inventory Date", "datatype": "date"},
+                {"virtual": true, "propertyUrl": "rdf:type", "valueUrl": "schema:Place"}
+            ],
+            "aboutUrl": "#gid-{GID}"
+        }
+    }"##;
+
+    #[test]
+    fn minimal_with_schema() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(METADATA, CSV, true)?;
+        let expected = turtle(
+            r#"
+            @base <http://example.org/tree-ops.csv> .
+            @prefix schema: <http://schema.org/> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+            <#gid-1> a schema:Place; schema:streetAddress "ADDISON AV";
+                <#species> "Celtis australis"@en; <#inventory_date> "10/18/2010"^^xsd:date.
+            <#gid-2> a schema:Place; schema:streetAddress "EMERSON ST";
+                <#species> "Liquidambar styraciflua"@en; <#inventory_date> "6/2/2010"^^xsd:date.
+        "#,
+        );
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn standard() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(METADATA, CSV, false)?;
+        let expected = turtle(
+            r#"
+            @base <http://example.org/tree-ops.csv> .
+            @prefix csvw: <http://www.w3.org/ns/csvw#> .
+            @prefix schema: <http://schema.org/> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+            [] a csvw:TableGroup; csvw:table [
+                a csvw:Table; csvw:url <>;
+                csvw:row
+                    [ a csvw:Row; csvw:rownum 1; csvw:url <#row=2>; csvw:describes <#gid-1> ],
+                    [ a csvw:Row; csvw:rownum 2; csvw:url <#row=3>; csvw:describes <#gid-2> ]
+            ].
+            <#gid-1> a schema:Place; schema:streetAddress "ADDISON AV";
+                <#species> "Celtis australis"@en; <#inventory_date> "10/18/2010"^^xsd:date.
+            <#gid-2> a schema:Place; schema:streetAddress "EMERSON ST";
+                <#species> "Liquidambar styraciflua"@en; <#inventory_date> "6/2/2010"^^xsd:date.
+        "#,
+        );
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn dialect_and_separator() -> Result<(), Box<dyn std::error::Error>> {
+        let metadata = r#"{
+            "tables": [{
+                "url": "http://example.org/data.tsv",
+                "tableSchema": {
+                    "columns": [
+                        {"name": "id", "suppressOutput": true},
+                        {"name": "tags", "separator": " "}
+                    ],
+                    "aboutUrl": "{?id}",
+                    "datatype": "integer"
+                }
+            }],
+            "dialect": {"delimiter": "\t", "header": false, "skipRows": 1}
+        }"#;
+        let g = parse(metadata, "skipped\n1\t2 3 \n4\t\n", true)?;
+        let expected = turtle(
+            r#"
+            <http://example.org/data.tsv?id=1> <http://example.org/data.tsv#tags> 2, 3.
+        "#,
+        );
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test_case("{var}", "a%20b%2Fc" ; "simple")]
+    #[test_case("{+var}", "a%20b/c" ; "reserved")]
+    #[test_case("{#var}", "#a%20b/c" ; "fragment")]
+    #[test_case("x{?var,n}", "x?var=a%20b%2Fc&n=42" ; "query")]
+    #[test_case("{/n,undef}", "/42" ; "path")]
+    #[test_case("{undef}x{#undef}", "x" ; "undefined")]
+    fn templates(template: &str, expected: &str) {
+        let vars = |name: &str| match name {
+            "var" => Some("a b/c".to_string()),
+            "n" => Some("42".to_string()),
+            _ => None,
+        };
+        assert_eq!(expand_template(template, vars).unwrap(), expected);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(CsvwParser::new("{", METADATA_URL).is_err());
+        assert!(CsvwParser::new("[]", METADATA_URL).is_err());
+        assert!(CsvwParser::new("{}", METADATA_URL).is_err());
+        assert!(CsvwParser::new(
+            r#"{"url": "x.csv", "dialect": {"delimiter": ";;"}}"#,
+            METADATA_URL
+        )
+        .is_err());
+        assert!(CsvwParser::new(
+            r#"{"url": "x.csv", "tableSchema": {"datatype": 42}}"#,
+            METADATA_URL
+        )
+        .is_err());
+
+        let parser =
+            CsvwParser::new(r##"{"url": "x.csv", "aboutUrl": "#{x"}"##, METADATA_URL).unwrap();
+        let results: Vec<_> = parser.parse_str("x\n1\n").collect();
+        assert!(results.iter().any(|r| matches!(
+            r,
+            Err(CsvwError::Cell {
+                row: 2,
+                column: 1,
+                ..
+            })
+        )));
+    }
+}
@@ -0,0 +1,288 @@
+//! Transparent decompression of the data to parse.
+//!
+//! RDF dumps are often distributed compressed (e.g. `dump.nt.gz`).
+//! This module provides readers decompressing such data on the fly,
+//! which can be passed directly to any parser expecting a [`BufRead`].
+//!
+//! Each compression format is enabled by a cargo feature:
+//! `gz` for gzip, `bz2` for bzip2 and `xz` for xz
+//! (`compression` enables all of them).
+//! Without any of those features, [`auto`] and [`open`] still work,
+//! but only accept uncompressed data.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::parser::{decompress, nt};
+//! # use sophia::triple::stream::TripleSource;
+//! // the compression format (if any) is detected from the data itself
+//! let data = decompress::auto(&b"<tag:s> <tag:p> <tag:o>.\n"[..])?;
+//! let g: FastGraph = nt::parse_bufread(data).collect_triples()?;
+//! assert_eq!(g.triples().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
+//! [`auto`]: fn.auto.html
+//! [`open`]: fn.open.html
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// The compression formats recognized by this module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Uncompressed data.
+    None,
+    /// gzip (`.gz`).
+    Gzip,
+    /// bzip2 (`.bz2`).
+    Bzip2,
+    /// xz (`.xz`).
+    Xz,
+}
+
+impl Compression {
+    /// Detect the compression format of some data, based on its first bytes.
+    ///
+    /// Data not starting with a known magic number is considered uncompressed.
+    pub fn sniff(header: &[u8]) -> Compression {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if header.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Whether this compression format is supported by the enabled features.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::None => true,
+            Compression::Gzip => cfg!(feature = "gz"),
+            Compression::Bzip2 => cfg!(feature = "bz2"),
+            Compression::Xz => cfg!(feature = "xz"),
+        }
+    }
+}
+
+/// A reader decompressing data on the fly, as returned by [`auto`](fn.auto.html).
+pub enum Decompressed<R> {
+    /// Uncompressed data.
+    Plain(R),
+    /// gzip compressed data.
+    #[cfg(feature = "gz")]
+    Gzip(BufReader<flate2::bufread::MultiGzDecoder<R>>),
+    /// bzip2 compressed data.
+    #[cfg(feature = "bz2")]
+    Bzip2(BufReader<bzip2::bufread::MultiBzDecoder<R>>),
+    /// xz compressed data.
+    #[cfg(feature = "xz")]
+    Xz(BufReader<xz2::bufread::XzDecoder<R>>),
+}
+
+impl<R> Decompressed<R> {
+    /// The compression format of the underlying data.
+    pub fn compression(&self) -> Compression {
+        match self {
+            Decompressed::Plain(_) => Compression::None,
+            #[cfg(feature = "gz")]
+            Decompressed::Gzip(_) => Compression::Gzip,
+            #[cfg(feature = "bz2")]
+            Decompressed::Bzip2(_) => Compression::Bzip2,
+            #[cfg(feature = "xz")]
+            Decompressed::Xz(_) => Compression::Xz,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Decompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Decompressed::Plain(r) => r.read(buf),
+            #[cfg(feature = "gz")]
+            Decompressed::Gzip(r) => r.read(buf),
+            #[cfg(feature = "bz2")]
+            Decompressed::Bzip2(r) => r.read(buf),
+            #[cfg(feature = "xz")]
+            Decompressed::Xz(r) => r.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for Decompressed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Decompressed::Plain(r) => r.fill_buf(),
+            #[cfg(feature = "gz")]
+            Decompressed::Gzip(r) => r.fill_buf(),
+            #[cfg(feature = "bz2")]
+            Decompressed::Bzip2(r) => r.fill_buf(),
+            #[cfg(feature = "xz")]
+            Decompressed::Xz(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Decompressed::Plain(r) => r.consume(amt),
+            #[cfg(feature = "gz")]
+            Decompressed::Gzip(r) => r.consume(amt),
+            #[cfg(feature = "bz2")]
+            Decompressed::Bzip2(r) => r.consume(amt),
+            #[cfg(feature = "xz")]
+            Decompressed::Xz(r) => r.consume(amt),
+        }
+    }
+}
+
+/// Decompress `data` according to `compression`.
+///
+/// # Errors
+/// Fails if `compression` is not supported by the enabled features.
+pub fn with<R: BufRead>(data: R, compression: Compression) -> io::Result<Decompressed<R>> {
+    match compression {
+        Compression::None => Ok(Decompressed::Plain(data)),
+        #[cfg(feature = "gz")]
+        Compression::Gzip => Ok(Decompressed::Gzip(gz(data))),
+        #[cfg(feature = "bz2")]
+        Compression::Bzip2 => Ok(Decompressed::Bzip2(bz2(data))),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Decompressed::Xz(xz(data))),
+        #[allow(unreachable_patterns)]
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{:?} compression is not supported", compression),
+        )),
+    }
+}
+
+/// Decompress `data`, detecting its compression format from its first bytes.
+///
+/// Uncompressed data is passed through unchanged.
+///
+/// # Errors
+/// Fails if `data` can not be read,
+/// or if its compression format is not supported by the enabled features.
+pub fn auto<R: BufRead>(mut data: R) -> io::Result<Decompressed<R>> {
+    let compression = Compression::sniff(data.fill_buf()?);
+    with(data, compression)
+}
+
+/// Open the file at `path`, and decompress it as needed (see [`auto`](fn.auto.html)).
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Decompressed<BufReader<File>>> {
+    auto(BufReader::new(File::open(path)?))
+}
+
+/// Decompress gzip `data`.
+///
+/// Files made of several concatenated gzip members are supported.
+#[cfg(feature = "gz")]
+pub fn gz<R: BufRead>(data: R) -> BufReader<flate2::bufread::MultiGzDecoder<R>> {
+    BufReader::new(flate2::bufread::MultiGzDecoder::new(data))
+}
+
+/// Decompress bzip2 `data`.
+///
+/// Files made of several concatenated bzip2 streams are supported.
+#[cfg(feature = "bz2")]
+pub fn bz2<R: BufRead>(data: R) -> BufReader<bzip2::bufread::MultiBzDecoder<R>> {
+    BufReader::new(bzip2::bufread::MultiBzDecoder::new(data))
+}
+
+/// Decompress xz `data`.
+///
+/// Files made of several concatenated xz streams are supported.
+#[cfg(feature = "xz")]
+pub fn xz<R: BufRead>(data: R) -> BufReader<xz2::bufread::XzDecoder<R>> {
+    BufReader::new(xz2::bufread::XzDecoder::new_multi_decoder(data))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::parser::nq;
+    use crate::quad::stream::QuadSource;
+
+    const NQ: &str = "<tag:s> <tag:p> <tag:o>.\n<tag:s> <tag:p> <tag:o> <tag:g>.\n";
+
+    #[test]
+    fn sniff() {
+        assert_eq!(Compression::sniff(b"\x1f\x8b\x08\x00"), Compression::Gzip);
+        assert_eq!(Compression::sniff(b"BZh91AY&SY"), Compression::Bzip2);
+        assert_eq!(Compression::sniff(b"\xfd7zXZ\x00\x00\x04"), Compression::Xz);
+        assert_eq!(Compression::sniff(b"<tag:s>"), Compression::None);
+        assert_eq!(Compression::sniff(b""), Compression::None);
+    }
+
+    #[test]
+    fn plain() -> Result<(), Box<dyn std::error::Error>> {
+        let data = auto(NQ.as_bytes())?;
+        assert_eq!(data.compression(), Compression::None);
+        let d: FastDataset = nq::parse_bufread(data).collect_quads()?;
+        assert_eq!(d.quads().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported() {
+        for compression in &[Compression::Gzip, Compression::Bzip2, Compression::Xz] {
+            assert_eq!(
+                with(&b""[..], *compression).is_ok(),
+                compression.is_supported()
+            );
+        }
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn gz_data() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(NQ.as_bytes())?;
+        let compressed = encoder.finish()?;
+        let data = auto(&compressed[..])?;
+        assert_eq!(data.compression(), Compression::Gzip);
+        let d: FastDataset = nq::parse_bufread(data).collect_quads()?;
+        assert_eq!(d.quads().count(), 2);
+        Ok(())
+    }
+
+    #[cfg(feature = "bz2")]
+    #[test]
+    fn bz2_data() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+        encoder.write_all(NQ.as_bytes())?;
+        let compressed = encoder.finish()?;
+        let data = auto(&compressed[..])?;
+        assert_eq!(data.compression(), Compression::Bzip2);
+        let d: FastDataset = nq::parse_bufread(data).collect_quads()?;
+        assert_eq!(d.quads().count(), 2);
+        Ok(())
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn xz_data() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+        encoder.write_all(NQ.as_bytes())?;
+        let compressed = encoder.finish()?;
+        let data = auto(&compressed[..])?;
+        assert_eq!(data.compression(), Compression::Xz);
+        let d: FastDataset = nq::parse_bufread(data).collect_quads()?;
+        assert_eq!(d.quads().count(), 2);
+        Ok(())
+    }
+}
@@ -8,16 +8,22 @@ use std::io::BufRead;
 /// TriG parser based on RIO.
 #[derive(Clone, Debug, Default)]
 pub struct GTriGParser {
-    /// The base IRI used by this parser to resolve relative IRI-references.
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    ///
+    /// If it is not a valid absolute IRI, the source produced by this parser fails immediately.
+    /// If it is `None`, relative IRI-references are errors,
+    /// unless a base IRI is declared in the data.
     pub base: Option<String>,
 }
 
 impl<B: BufRead> QuadParser<B> for GTriGParser {
     type Source = GeneralizedRioSource<RioGTriGParser<B>, TurtleError>;
     fn parse(&self, data: B) -> Self::Source {
-        // TODO issue TurtleError if base can not be parsed
-        let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        GeneralizedRioSource::Parser(RioGTriGParser::new(data, base))
+        parse_base(&self.base)
+            .map(|base| RioGTriGParser::new(data, base))
+            .map_err(TurtleError::from)
+            .into()
     }
 }
 
@@ -0,0 +1,98 @@
+//! Adapter for the Generalized TriG parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/gtrig.rs),
+//! restricted to the default graph.
+//!
+//! This parser accepts Turtle extended to
+//! [generalized RDF](https://www.w3.org/TR/rdf11-concepts/#section-generalized-rdf)
+//! (e.g. literals as subjects, blank nodes as predicates)
+//! and variables (`?x`), as [`GTriGParser`](../gtrig/struct.GTriGParser.html) does.
+//! Graph blocks are errors.
+
+use crate::parser::rio_common::*;
+use rio_turtle::{GTriGParser as RioGTriGParser, TurtleError};
+use sophia_api::parser::TripleParser;
+use std::io::BufRead;
+
+/// Generalized Turtle parser based on RIO.
+#[derive(Clone, Debug, Default)]
+pub struct GTurtleParser {
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    ///
+    /// If it is not a valid absolute IRI, the source produced by this parser fails immediately.
+    /// If it is `None`, relative IRI-references are errors,
+    /// unless a base IRI is declared in the data.
+    pub base: Option<String>,
+}
+
+impl<B: BufRead> TripleParser<B> for GTurtleParser {
+    type Source = GeneralizedRioTripleSource<RioGTriGParser<B>, TurtleError>;
+    fn parse(&self, data: B) -> Self::Source {
+        parse_base(&self.base)
+            .map(|base| RioGTriGParser::new(data, base))
+            .map_err(TurtleError::from)
+            .into()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(GTurtleParser, TripleParser);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::turtle::TurtleParser;
+    use crate::triple::stream::TripleSource;
+    use sophia_api::ns::xsd;
+    use sophia_api::term::{TTerm, TermKind};
+    use sophia_term::{BoxTerm, StaticTerm};
+
+    #[test]
+    fn generalized() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let ttl = r#"
+            @prefix : <http://example.org/ns/> .
+            "lit" _:p ?x.
+            :s ?p :o, "o".
+        "#;
+        let res: Result<Vec<[BoxTerm; 3]>, _> =
+            TurtleParser::default().parse_str(ttl).collect_triples();
+        assert!(res.is_err());
+
+        let p = GTurtleParser::default();
+        let triples: Vec<[BoxTerm; 3]> = p.parse_str(ttl).collect_triples()?;
+        assert_eq!(triples.len(), 3);
+        assert_eq!(
+            triples[0][0],
+            StaticTerm::new_literal_dt("lit", xsd::string)?
+        );
+        assert_eq!(triples[0][1].kind(), TermKind::BlankNode);
+        assert_eq!(triples[0][2], StaticTerm::new_variable("x")?);
+        assert_eq!(triples[1][1], StaticTerm::new_variable("p")?);
+
+        // graphs are not allowed
+        let res: Result<Vec<[BoxTerm; 3]>, _> = p
+            .parse_str("<tag:g> { <tag:s> <tag:p> <tag:o> }")
+            .collect_triples();
+        assert!(res.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn base_iri() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let p = GTurtleParser {
+            base: Some("http://example.org/x/y".into()),
+        };
+        let triples: Vec<[BoxTerm; 3]> = p.parse_str("<a> <b> <../c> .").collect_triples()?;
+        assert_eq!(triples[0][2], StaticTerm::new_iri("http://example.org/c")?);
+
+        let p = GTurtleParser {
+            base: Some("not an IRI".into()),
+        };
+        let res: Result<Vec<[BoxTerm; 3]>, _> =
+            p.parse_str("<tag:a> <tag:b> <tag:c>.").collect_triples();
+        assert!(res.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,745 @@
+//! Reader for the [HDT] (Header, Dictionary, Triples) binary format.
+//!
+//! HDT is a compressed, indexed format, designed to publish and query large RDF graphs.
+//! An HDT file can be read in two ways:
+//! * with [`HdtParser`], as a [`TripleSource`], like any other parser;
+//! * with [`Hdt::read`], as a [`Graph`] whose triples are decoded lazily,
+//!   and which uses the compressed structures of the file as an index
+//!   for triples with a given subject (and predicate).
+//!
+//! In both cases, the file is loaded in memory in its compressed form,
+//! which is usually much smaller than any in-memory graph.
+//!
+//! NB: only the most common flavour of HDT is supported,
+//! i.e. files with a four-section dictionary using Plain Front Coding,
+//! and bitmap triples in SPO order
+//! (which is what the reference implementation produces by default).
+//! Checksums are not verified.
+//!
+//! # Example
+//! ```no_run
+//! # use sophia::graph::Graph;
+//! # use sophia::parser::hdt::Hdt;
+//! # use sophia::triple::Triple;
+//! # use sophia_api::term::matcher::ANY;
+//! # use std::{fs::File, io::BufReader};
+//! # fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let hdt = Hdt::read(BufReader::new(File::open("dump.hdt")?))?;
+//! println!("{} triples", hdt.len());
+//! for t in hdt.triples_matching(&ANY, &ANY, &ANY).take(10) {
+//!     println!("{:?}", t?.s());
+//! }
+//! # Ok(()) }
+//! ```
+//!
+//! [HDT]: https://www.rdfhdt.org/hdt-binary-format/
+//! [`HdtParser`]: struct.HdtParser.html
+//! [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+//! [`Hdt::read`]: struct.Hdt.html#method.read
+//! [`Graph`]: ../../graph/trait.Graph.html
+
+use std::io::{self, BufRead};
+
+use sophia_api::graph::{GTripleSource, Graph};
+use sophia_api::parser::TripleParser;
+use sophia_api::term::TTerm;
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::streaming_mode::{ByValue, StreamedTriple};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::nt;
+
+mod _bits;
+use self::_bits::*;
+mod _dictionary;
+use self::_dictionary::*;
+
+/// The format of four-section dictionaries.
+const DICTIONARY_FOUR: &str = "<http://purl.org/HDT/hdt#dictionaryFour>";
+/// The format of bitmap triples.
+const TRIPLES_BITMAP: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+
+/// HDT parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct HdtParser {}
+
+impl<B: BufRead> TripleParser<B> for HdtParser {
+    type Source = HdtSource;
+    fn parse(&self, data: B) -> Self::Source {
+        match Hdt::read(data) {
+            Ok(hdt) => HdtSource {
+                cursor: hdt.cursor(),
+                hdt: Some(hdt),
+                error: None,
+            },
+            Err(err) => HdtSource {
+                hdt: None,
+                error: Some(err),
+                cursor: Cursor::default(),
+            },
+        }
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(HdtParser, TripleParser);
+
+/// The triple source produced by [`HdtParser`](struct.HdtParser.html).
+#[derive(Debug)]
+pub struct HdtSource {
+    hdt: Option<Hdt>,
+    error: Option<HdtError>,
+    cursor: Cursor,
+}
+
+impl Iterator for HdtSource {
+    type Item = Result<[BoxTerm; 3], HdtError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        self.hdt.as_ref()?.next_triple(&mut self.cursor)
+    }
+}
+
+/// Errors raised when reading HDT files.
+#[derive(Debug, Error)]
+pub enum HdtError {
+    /// The data could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data is not valid HDT.
+    #[error("Invalid HDT: {0}")]
+    Format(String),
+    /// The data uses a flavour of HDT that is not supported.
+    #[error("Unsupported HDT: {0}")]
+    Unsupported(String),
+}
+
+/// The control information preceding each part of an HDT file.
+struct ControlInfo {
+    format: String,
+    properties: String,
+}
+
+impl ControlInfo {
+    fn read<R: BufRead>(r: &mut R, kind: u8) -> Result<Self, HdtError> {
+        if read_bytes(r, 4)? != b"$HDT" {
+            return Err(HdtError::Format("missing $HDT cookie".to_string()));
+        }
+        let found = read_u8(r)?;
+        if found != kind {
+            return Err(HdtError::Format(format!(
+                "expected control information of type {}, found {}",
+                kind, found
+            )));
+        }
+        let format = read_cstring(r)?;
+        let properties = read_cstring(r)?;
+        read_bytes(r, 2)?; // CRC16
+        Ok(ControlInfo { format, properties })
+    }
+
+    /// The value of property `key`, if any (properties are encoded as `key=value;` pairs).
+    fn property(&self, key: &str) -> Option<&str> {
+        self.properties.split(';').find_map(|pair| {
+            let mut split = pair.splitn(2, '=');
+            if split.next()? == key {
+                split.next()
+            } else {
+                None
+            }
+        })
+    }
+
+    fn check_format(&self, expected: &str) -> Result<(), HdtError> {
+        if self.format == expected {
+            Ok(())
+        } else {
+            Err(HdtError::Unsupported(format!("format {}", self.format)))
+        }
+    }
+}
+
+/// The contents of an HDT file, usable as a [`Graph`](../../graph/trait.Graph.html).
+///
+/// Triples are stored in their compressed form, and decoded when iterated over.
+/// Triples with a given subject, or a given subject and predicate,
+/// are found without scanning the whole graph.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Hdt {
+    header: Vec<[BoxTerm; 3]>,
+    dictionary: Dictionary,
+    bitmap_y: Bitmap,
+    bitmap_z: Bitmap,
+    seq_y: Sequence,
+    seq_z: Sequence,
+}
+
+impl Hdt {
+    /// Read an HDT file.
+    pub fn read<R: BufRead>(mut r: R) -> Result<Self, HdtError> {
+        ControlInfo::read(&mut r, 1)?;
+
+        let info = ControlInfo::read(&mut r, 2)?;
+        info.check_format("ntriples")?;
+        let length = info
+            .property("length")
+            .and_then(|length| length.parse().ok())
+            .ok_or_else(|| HdtError::Format("missing header length".to_string()))?;
+        let header = String::from_utf8(read_bytes(&mut r, length)?)
+            .map_err(|_| HdtError::Format("invalid UTF-8 in header".to_string()))?;
+        let header = nt::parse_str(&header)
+            .collect_triples::<Vec<[BoxTerm; 3]>>()
+            .map_err(|err| HdtError::Format(format!("header: {}", err.unwrap_source_error())))?;
+
+        ControlInfo::read(&mut r, 3)?.check_format(DICTIONARY_FOUR)?;
+        let dictionary = Dictionary::read(&mut r)?;
+
+        let info = ControlInfo::read(&mut r, 4)?;
+        info.check_format(TRIPLES_BITMAP)?;
+        match info.property("order") {
+            None | Some("1") => {}
+            Some(order) => return Err(HdtError::Unsupported(format!("triple order {}", order))),
+        }
+        let bitmap_y = Bitmap::read(&mut r)?;
+        let bitmap_z = Bitmap::read(&mut r)?;
+        let seq_y = Sequence::read(&mut r)?;
+        let seq_z = Sequence::read(&mut r)?;
+        let (len_y, len_z) = (seq_y.len(), seq_z.len());
+        if bitmap_y.len() != len_y
+            || bitmap_z.len() != len_z
+            || bitmap_z.ones() != len_y
+            || (len_y > 0 && !(bitmap_y.get(len_y - 1) && bitmap_z.get(len_z - 1)))
+        {
+            return Err(HdtError::Format("inconsistent triples".to_string()));
+        }
+
+        Ok(Hdt {
+            header,
+            dictionary,
+            bitmap_y,
+            bitmap_z,
+            seq_y,
+            seq_z,
+        })
+    }
+
+    /// The triples of the header of this file,
+    /// describing the dataset (provenance, statistics...).
+    pub fn header(&self) -> &[[BoxTerm; 3]] {
+        &self.header
+    }
+
+    /// The number of triples in this file.
+    pub fn len(&self) -> usize {
+        self.seq_z.len()
+    }
+
+    /// Whether this file contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.seq_z.len() == 0
+    }
+
+    /// A cursor over all triples.
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            s: 1,
+            y: 0,
+            z: 0,
+            z_end: self.seq_z.len(),
+        }
+    }
+
+    /// The range of `seq_y` describing the predicates of subject `s`.
+    fn y_range(&self, s: usize) -> Option<(usize, usize)> {
+        let start = match s.checked_sub(2) {
+            Some(previous) => self.bitmap_y.select1(previous)? + 1,
+            None => 0,
+        };
+        let end = self.bitmap_y.select1(s.checked_sub(1)?)? + 1;
+        Some((start, end))
+    }
+
+    /// The position in `seq_z` of the first object of the `y`-th (subject, predicate) pair.
+    fn z_start(&self, y: usize) -> usize {
+        match y.checked_sub(1) {
+            // z_start is never called with y > seq_y.len() == bitmap_z.ones()
+            Some(previous) => self.bitmap_z.select1(previous).unwrap() + 1,
+            None => 0,
+        }
+    }
+
+    /// A cursor over the triples with subject `s`.
+    fn s_cursor<TS: TTerm + ?Sized>(&self, s: &TS) -> Option<Cursor> {
+        let s = self.dictionary.subject_id(s)?;
+        let (start, end) = self.y_range(s)?;
+        Some(Cursor {
+            s,
+            y: start,
+            z: self.z_start(start),
+            z_end: self.z_start(end),
+        })
+    }
+
+    /// A cursor over the triples with subject `s` and predicate `p`.
+    fn sp_cursor<TS, TP>(&self, s: &TS, p: &TP) -> Option<Cursor>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        let s = self.dictionary.subject_id(s)?;
+        let p = self.dictionary.predicate_id(p)?;
+        let (start, end) = self.y_range(s)?;
+        let y = self.seq_y.search(start, end, p)?;
+        Some(Cursor {
+            s,
+            y,
+            z: self.z_start(y),
+            z_end: self.z_start(y + 1),
+        })
+    }
+
+    /// Decode the triple at `cursor`, and move it to the next one.
+    fn next_triple(&self, cursor: &mut Cursor) -> Option<Result<[BoxTerm; 3], HdtError>> {
+        if cursor.z >= cursor.z_end {
+            return None;
+        }
+        let (s, p, o) = (cursor.s, self.seq_y.get(cursor.y), self.seq_z.get(cursor.z));
+        if self.bitmap_z.get(cursor.z) {
+            if self.bitmap_y.get(cursor.y) {
+                cursor.s += 1;
+            }
+            cursor.y += 1;
+        }
+        cursor.z += 1;
+        Some(self.decode(s, p, o))
+    }
+
+    fn decode(&self, s: usize, p: usize, o: usize) -> Result<[BoxTerm; 3], HdtError> {
+        Ok([
+            self.dictionary.subject(s)?,
+            self.dictionary.predicate(p)?,
+            self.dictionary.object(o)?,
+        ])
+    }
+
+    fn source(&self, cursor: Option<Cursor>) -> GTripleSource<'_, Self> {
+        let mut cursor = cursor.unwrap_or_default();
+        Box::new(
+            std::iter::from_fn(move || self.next_triple(&mut cursor))
+                .map(|t| t.map(StreamedTriple::by_value)),
+        )
+    }
+}
+
+impl Graph for Hdt {
+    type Triple = ByValue<[BoxTerm; 3]>;
+    type Error = HdtError;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.source(Some(self.cursor()))
+    }
+
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.source(self.s_cursor(s))
+    }
+
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.source(self.sp_cursor(s, p))
+    }
+}
+
+/// A position in the triples of an HDT file.
+///
+/// Subject identifiers are implicit: they are incremented each time `y` reaches the end of a subject.
+/// The default cursor is empty.
+#[derive(Clone, Debug, Default)]
+struct Cursor {
+    /// The identifier of the current subject.
+    s: usize,
+    /// The position of the current predicate in `seq_y`.
+    y: usize,
+    /// The position of the current object in `seq_z`.
+    z: usize,
+    /// The position in `seq_z` where this cursor stops.
+    z_end: usize,
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::triple::stream::TripleSource;
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::ns::{rdf, xsd};
+    use sophia_api::term::matcher::ANY;
+    use sophia_api::triple::Triple;
+    use sophia_term::StaticTerm;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    // a minimal HDT encoder, for testing purposes
+
+    fn vbyte(out: &mut Vec<u8>, mut n: usize) {
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                out.push(byte | 0x80);
+                return;
+            }
+            out.push(byte);
+        }
+    }
+
+    fn control(out: &mut Vec<u8>, kind: u8, format: &str, properties: &str) {
+        out.extend_from_slice(b"$HDT");
+        out.push(kind);
+        for txt in &[format, properties] {
+            out.extend_from_slice(txt.as_bytes());
+            out.push(0);
+        }
+        out.extend_from_slice(&[0, 0]);
+    }
+
+    fn sequence(out: &mut Vec<u8>, values: &[usize]) {
+        let max = values.iter().copied().max().unwrap_or(0);
+        let bits = (0usize.leading_zeros() - max.leading_zeros()) as usize;
+        let mut bytes = vec![0; (bits * values.len()).div_ceil(8)];
+        for (i, value) in values.iter().enumerate() {
+            for b in 0..bits {
+                if value >> b & 1 == 1 {
+                    let pos = i * bits + b;
+                    bytes[pos / 8] |= 1 << (pos % 8);
+                }
+            }
+        }
+        out.extend_from_slice(&[1, bits as u8]);
+        vbyte(out, values.len());
+        out.push(0);
+        out.extend_from_slice(&bytes);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    fn bitmap(out: &mut Vec<u8>, bits: &[bool]) {
+        let mut bytes = vec![0; bits.len().div_ceil(8)];
+        for (i, bit) in bits.iter().enumerate() {
+            if *bit {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        out.push(1);
+        vbyte(out, bits.len());
+        out.push(0);
+        out.extend_from_slice(&bytes);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    fn pfc(out: &mut Vec<u8>, strings: &[&str], block_size: usize) {
+        let (mut data, mut blocks) = (vec![], vec![]);
+        for (i, string) in strings.iter().enumerate() {
+            if i.is_multiple_of(block_size) {
+                blocks.push(data.len());
+                data.extend_from_slice(string.as_bytes());
+            } else {
+                let shared = strings[i - 1]
+                    .bytes()
+                    .zip(string.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                vbyte(&mut data, shared);
+                data.extend_from_slice(&string.as_bytes()[shared..]);
+            }
+            data.push(0);
+        }
+        blocks.push(data.len());
+        out.push(2);
+        vbyte(out, strings.len());
+        vbyte(out, data.len());
+        vbyte(out, block_size);
+        out.push(0);
+        sequence(out, &blocks);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    /// Encode triples of terms, written as in HDT dictionaries.
+    fn encode(header: &str, triples: &[[&str; 3]]) -> Vec<u8> {
+        let subjects: BTreeSet<&str> = triples.iter().map(|t| t[0]).collect();
+        let predicates: BTreeSet<&str> = triples.iter().map(|t| t[1]).collect();
+        let objects: BTreeSet<&str> = triples.iter().map(|t| t[2]).collect();
+        let shared: Vec<&str> = subjects.intersection(&objects).copied().collect();
+        let subject_only: Vec<&str> = subjects.difference(&objects).copied().collect();
+        let object_only: Vec<&str> = objects.difference(&subjects).copied().collect();
+        let predicates: Vec<&str> = predicates.into_iter().collect();
+        let id = |section: &[&str], offset: usize, term: &str| {
+            section.binary_search(&term).ok().map(|i| i + offset + 1)
+        };
+        let ids: BTreeSet<[usize; 3]> = triples
+            .iter()
+            .map(|[s, p, o]| {
+                [
+                    id(&shared, 0, s)
+                        .or_else(|| id(&subject_only, shared.len(), s))
+                        .unwrap(),
+                    id(&predicates, 0, p).unwrap(),
+                    id(&shared, 0, o)
+                        .or_else(|| id(&object_only, shared.len(), o))
+                        .unwrap(),
+                ]
+            })
+            .collect();
+        let ids: Vec<[usize; 3]> = ids.into_iter().collect();
+
+        let (mut seq_y, mut seq_z, mut bitmap_y, mut bitmap_z) = (vec![], vec![], vec![], vec![]);
+        for (i, [s, p, o]) in ids.iter().enumerate() {
+            if i == 0 || ids[i - 1][..2] != [*s, *p] {
+                seq_y.push(*p);
+                // is this the last pair of subject s?
+                let next_pair = ids[i..].iter().find(|t| t[..2] != [*s, *p]);
+                bitmap_y.push(next_pair.is_none_or(|t| t[0] != *s));
+            }
+            seq_z.push(*o);
+            bitmap_z.push(ids.get(i + 1).is_none_or(|t| t[..2] != [*s, *p]));
+        }
+
+        let mut out = vec![];
+        control(&mut out, 1, "<http://purl.org/HDT/hdt#HDTv1>", "");
+        control(
+            &mut out,
+            2,
+            "ntriples",
+            &format!("length={};", header.len()),
+        );
+        out.extend_from_slice(header.as_bytes());
+        control(&mut out, 3, DICTIONARY_FOUR, "mapping=1;");
+        for section in &[&shared, &subject_only, &predicates, &object_only] {
+            pfc(&mut out, section, 2);
+        }
+        control(&mut out, 4, TRIPLES_BITMAP, "order=1;");
+        bitmap(&mut out, &bitmap_y);
+        bitmap(&mut out, &bitmap_z);
+        sequence(&mut out, &seq_y);
+        sequence(&mut out, &seq_z);
+        out
+    }
+
+    const HEADER: &str = "<http://example.org/dump> <http://rdfs.org/ns/void#triples> \"8\" .\n";
+
+    const TRIPLES: &[[&str; 3]] = &[
+        [
+            "http://example.org/alice",
+            "http://example.org/knows",
+            "http://example.org/bob",
+        ],
+        [
+            "http://example.org/alice",
+            "http://example.org/knows",
+            "_:charlie",
+        ],
+        [
+            "http://example.org/alice",
+            "http://example.org/name",
+            "\"Alice\"@en",
+        ],
+        [
+            "http://example.org/alice",
+            "http://example.org/name",
+            "\"Alice\"",
+        ],
+        [
+            "http://example.org/bob",
+            "http://example.org/name",
+            "\"Bob\"",
+        ],
+        [
+            "http://example.org/bob",
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+            "http://example.org/Person",
+        ],
+        [
+            "_:charlie",
+            "http://example.org/age",
+            "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+        ],
+        [
+            "_:charlie",
+            "http://example.org/knows",
+            "http://example.org/alice",
+        ],
+    ];
+
+    const TURTLE: &str = r#"
+        @prefix : <http://example.org/> .
+        :alice :knows :bob, _:charlie ; :name "Alice"@en, "Alice" .
+        :bob :name "Bob" ; a :Person .
+        _:charlie :age 42 ; :knows :alice .
+    "#;
+
+    #[test]
+    fn parse_hdt() -> Result<(), Box<dyn std::error::Error>> {
+        let data = encode(HEADER, TRIPLES);
+        let g: FastGraph = parse_bufread(&data[..]).collect_triples()?;
+        let expected: FastGraph = crate::parser::turtle::parse_str(TURTLE).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_dictionary_section() -> Result<(), Box<dyn std::error::Error>> {
+        let mut data = vec![];
+        pfc(&mut data, &[], 0);
+        assert!(PfcSection::read(&mut &data[..]).is_err());
+
+        let mut data = vec![];
+        pfc(&mut data, &[], 2);
+        let section = PfcSection::read(&mut &data[..])?;
+        assert_eq!(section.len(), 0);
+        assert_eq!(section.locate(b"http://example.org/alice"), None);
+        assert_eq!(section.extract(1), None);
+        Ok(())
+    }
+
+    #[test]
+    fn hdt_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let hdt = Hdt::read(&encode(HEADER, TRIPLES)[..])?;
+        assert_eq!(hdt.len(), 8);
+        assert_eq!(hdt.header().len(), 1);
+        let expected: FastGraph = crate::parser::turtle::parse_str(TURTLE).collect_triples()?;
+        assert!(isomorphic_graphs(&hdt, &expected)?);
+
+        let alice = StaticTerm::new_iri("http://example.org/alice")?;
+        let bob = StaticTerm::new_iri("http://example.org/bob")?;
+        let charlie = StaticTerm::new_bnode("charlie")?;
+        let name = StaticTerm::new_iri("http://example.org/name")?;
+        let knows = StaticTerm::new_iri("http://example.org/knows")?;
+        assert_eq!(hdt.triples_with_s(&alice).count(), 4);
+        assert_eq!(hdt.triples_with_s(&bob).count(), 2);
+        assert_eq!(hdt.triples_with_s(&charlie).count(), 2);
+        assert_eq!(hdt.triples_with_s(&name).count(), 0);
+        assert_eq!(hdt.triples_with_sp(&alice, &name).count(), 2);
+        assert_eq!(hdt.triples_with_sp(&bob, &rdf::type_).count(), 1);
+        assert_eq!(hdt.triples_with_sp(&bob, &knows).count(), 0);
+        assert_eq!(hdt.triples_matching(&ANY, &knows, &ANY).count(), 3);
+        assert!(hdt.contains(&charlie, &knows, &alice)?);
+        assert!(hdt.contains(
+            &alice,
+            &name,
+            &StaticTerm::new_literal_dt("Alice", xsd::string)?
+        )?);
+        assert!(!hdt.contains(
+            &alice,
+            &name,
+            &StaticTerm::new_literal_dt("Alice", xsd::token)?
+        )?);
+        assert!(hdt.contains(
+            &charlie,
+            &StaticTerm::new_iri("http://example.org/age")?,
+            &StaticTerm::new_literal_dt("42", xsd::integer)?
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn many_triples() -> Result<(), Box<dyn std::error::Error>> {
+        // enough triples to span several samples of the bitmaps
+        let terms: Vec<String> = (0..200)
+            .map(|i| format!("http://example.org/{:03}", i))
+            .collect();
+        let mut triples = vec![];
+        for i in 0..200 {
+            for j in 0..(i % 7) {
+                for k in 0..(i % 5) {
+                    triples.push([&terms[i][..], &terms[j][..], &terms[(i + k) % 200][..]]);
+                }
+            }
+        }
+        let hdt = Hdt::read(&encode("", &triples)[..])?;
+        assert_eq!(hdt.len(), triples.len());
+        let mut expected: BTreeMap<&str, usize> = BTreeMap::new();
+        for [s, _, _] in &triples {
+            *expected.entry(*s).or_default() += 1;
+        }
+        for term in &terms {
+            let s = BoxTerm::new_iri(&term[..])?;
+            let count = hdt.triples_with_s(&s).count();
+            assert_eq!(
+                count,
+                expected.get(&term[..]).copied().unwrap_or(0),
+                "{}",
+                term
+            );
+            for t in hdt.triples_with_s(&s) {
+                let t = t?;
+                assert_eq!(&t.s().value()[..], &term[..]);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let data = encode(HEADER, TRIPLES);
+        assert!(Hdt::read(&data[..data.len() - 10]).is_err());
+        assert!(Hdt::read(&data[1..]).is_err());
+        assert!(parse_bufread(&data[..data.len() - 10])
+            .next()
+            .unwrap()
+            .is_err());
+
+        let mut bad = vec![];
+        control(&mut bad, 1, "<http://purl.org/HDT/hdt#HDTv1>", "");
+        control(&mut bad, 2, "turtle", "length=0;");
+        assert!(matches!(Hdt::read(&bad[..]), Err(HdtError::Unsupported(_))));
+
+        let mut other = data.clone();
+        let pos = other.windows(8).position(|w| w == b"order=1;").unwrap();
+        other[pos + 6] = b'2';
+        assert!(matches!(
+            Hdt::read(&other[..]),
+            Err(HdtError::Unsupported(_))
+        ));
+
+        for triple in &[
+            ["not an IRI", "http://example.org/p", "\"x\""],
+            [
+                "http://example.org/s",
+                "http://example.org/p",
+                "_:not a label",
+            ],
+            [
+                "http://example.org/s",
+                "http://example.org/p",
+                "\"x\"@not a tag",
+            ],
+            [
+                "http://example.org/s",
+                "http://example.org/p",
+                "\"x\"^^<not an IRI>",
+            ],
+        ] {
+            let data = encode(HEADER, &[*triple]);
+            let mut triples = parse_bufread(&data[..]);
+            assert!(
+                matches!(triples.next(), Some(Err(HdtError::Format(_)))),
+                "{:?}",
+                triple
+            );
+        }
+    }
+}
@@ -0,0 +1,250 @@
+// this module is transparently re-exported by its parent `hdt`
+// It defines the low-level structures of HDT files: integers, strings, bitmaps and sequences.
+
+use std::convert::TryFrom;
+use std::io::{BufRead, Read};
+
+use super::HdtError;
+
+pub(super) fn read_u8<R: BufRead>(r: &mut R) -> Result<u8, HdtError> {
+    let mut buf = [0];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+/// Read exactly `n` bytes, without allocating them upfront
+/// (so that a corrupted length does not exhaust memory).
+pub(super) fn read_bytes<R: BufRead>(r: &mut R, n: usize) -> Result<Vec<u8>, HdtError> {
+    let mut buf = Vec::new();
+    r.take(n as u64).read_to_end(&mut buf)?;
+    if buf.len() < n {
+        return Err(HdtError::Format("unexpected end of file".to_string()));
+    }
+    Ok(buf)
+}
+
+/// Read a variable-length integer.
+///
+/// NB: unlike most variable-length encodings, HDT flags the *last* byte with its high bit.
+pub(super) fn read_vbyte<R: BufRead>(r: &mut R) -> Result<usize, HdtError> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = read_u8(r)?;
+        if shift > 63 || (shift > 0 && u64::from(byte & 0x7f) >> (64 - shift) != 0) {
+            return Err(HdtError::Format("integer overflow".to_string()));
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 != 0 {
+            break;
+        }
+        shift += 7;
+    }
+    usize::try_from(value).map_err(|_| HdtError::Unsupported(format!("{} is too big", value)))
+}
+
+/// Decode a variable-length integer at `pos` in `data`,
+/// and return it with the position following it.
+pub(super) fn vbyte_at(data: &[u8], mut pos: usize) -> Option<(usize, usize)> {
+    let mut value: usize = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(pos)?;
+        pos += 1;
+        value = value.checked_add(((byte & 0x7f) as usize).checked_shl(shift)?)?;
+        if byte & 0x80 != 0 {
+            return Some((value, pos));
+        }
+        shift += 7;
+    }
+}
+
+/// Read a NUL-terminated UTF-8 string.
+pub(super) fn read_cstring<R: BufRead>(r: &mut R) -> Result<String, HdtError> {
+    let mut buf = vec![];
+    r.read_until(0, &mut buf)?;
+    if buf.pop() != Some(0) {
+        return Err(HdtError::Format("unexpected end of file".to_string()));
+    }
+    String::from_utf8(buf).map_err(|_| HdtError::Format("invalid UTF-8".to_string()))
+}
+
+/// Pack little-endian bytes into 64-bit words.
+fn words(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .rev()
+                .fold(0, |word, byte| (word << 8) | u64::from(*byte))
+        })
+        .collect()
+}
+
+/// A sequence of integers, each encoded with the same number of bits ("log array").
+#[derive(Clone, Debug)]
+pub(super) struct Sequence {
+    words: Vec<u64>,
+    bits: usize,
+    len: usize,
+}
+
+impl Sequence {
+    pub fn read<R: BufRead>(r: &mut R) -> Result<Self, HdtError> {
+        let kind = read_u8(r)?;
+        if kind != 1 {
+            return Err(HdtError::Unsupported(format!("sequence type {}", kind)));
+        }
+        let bits = read_u8(r)? as usize;
+        if bits > 64 {
+            return Err(HdtError::Format(format!("{} bits per entry", bits)));
+        }
+        let len = read_vbyte(r)?;
+        read_u8(r)?; // CRC8
+        let size = bits
+            .checked_mul(len)
+            .ok_or_else(|| HdtError::Format("sequence too long".to_string()))?;
+        let words = words(&read_bytes(r, size.div_ceil(8))?);
+        read_bytes(r, 4)?; // CRC32
+        Ok(Sequence { words, bits, len })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The `i`-th entry of this sequence (starting at 0).
+    ///
+    /// # Panics
+    /// If `i` is out of bounds.
+    pub fn get(&self, i: usize) -> usize {
+        assert!(i < self.len, "index out of bounds");
+        if self.bits == 0 {
+            return 0;
+        }
+        let bit = i * self.bits;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mut value = self.words[word] >> offset;
+        if offset + self.bits > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+        if self.bits < 64 {
+            value &= (1 << self.bits) - 1;
+        }
+        value as usize
+    }
+
+    /// The position of `value` in the sorted range `start..end` of this sequence, if any.
+    pub fn search(&self, start: usize, end: usize, value: usize) -> Option<usize> {
+        let (mut lo, mut hi) = (start, end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.get(mid).cmp(&value) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+/// One every `SAMPLE` set bits of a bitmap has its position recorded,
+/// to speed up `select1`.
+const SAMPLE: usize = 256;
+
+/// A sequence of bits, supporting `select1` queries.
+#[derive(Clone, Debug)]
+pub(super) struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+    ones: usize,
+    samples: Vec<usize>,
+}
+
+impl Bitmap {
+    pub fn read<R: BufRead>(r: &mut R) -> Result<Self, HdtError> {
+        let kind = read_u8(r)?;
+        if kind != 1 {
+            return Err(HdtError::Unsupported(format!("bitmap type {}", kind)));
+        }
+        let len = read_vbyte(r)?;
+        read_u8(r)?; // CRC8
+        let mut words = words(&read_bytes(r, len.div_ceil(8))?);
+        read_bytes(r, 4)?; // CRC32
+        if len % 64 != 0 {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (len % 64)) - 1;
+            }
+        }
+        Ok(Bitmap::new(words, len))
+    }
+
+    fn new(words: Vec<u64>, len: usize) -> Self {
+        let mut ones = 0;
+        let mut samples = vec![];
+        for (i, word) in words.iter().enumerate() {
+            let count = word.count_ones() as usize;
+            // does this word contain the next one to sample?
+            let next = samples.len() * SAMPLE;
+            if ones + count > next {
+                samples.push(i * 64 + nth_one(*word, next - ones));
+            }
+            ones += count;
+        }
+        Bitmap {
+            words,
+            len,
+            ones,
+            samples,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of set bits in this bitmap.
+    pub fn ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Whether the `i`-th bit (starting at 0) is set.
+    ///
+    /// # Panics
+    /// If `i` is out of bounds.
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "index out of bounds");
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// The position of the `n`-th set bit (starting at 0), if any.
+    pub fn select1(&self, n: usize) -> Option<usize> {
+        if n >= self.ones {
+            return None;
+        }
+        let sample = n / SAMPLE;
+        let pos = self.samples[sample];
+        let mut word = pos / 64;
+        // number of set bits before `word`
+        let below = self.words[word] & ((1 << (pos % 64)) - 1);
+        let mut remaining = n - (sample * SAMPLE - below.count_ones() as usize);
+        loop {
+            let count = self.words[word].count_ones() as usize;
+            if remaining < count {
+                return Some(word * 64 + nth_one(self.words[word], remaining));
+            }
+            remaining -= count;
+            word += 1;
+        }
+    }
+}
+
+/// The position of the `n`-th set bit (starting at 0) of `word`, which must have more than `n`.
+fn nth_one(mut word: u64, n: usize) -> usize {
+    for _ in 0..n {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
@@ -0,0 +1,240 @@
+// this module is transparently re-exported by its parent `hdt`
+// It defines the dictionary of HDT files, mapping terms to integer identifiers.
+
+use std::io::BufRead;
+
+use sophia_api::ns::xsd;
+use sophia_api::term::{term_eq, TTerm, TermKind};
+use sophia_term::{BoxTerm, TermError};
+
+use super::_bits::*;
+use super::HdtError;
+
+/// A dictionary section, encoded with Plain Front Coding:
+/// strings are sorted and grouped in blocks,
+/// where each string but the first one is encoded as a suffix of the previous one.
+#[derive(Clone, Debug)]
+pub(super) struct PfcSection {
+    len: usize,
+    block_size: usize,
+    blocks: Sequence,
+    data: Vec<u8>,
+}
+
+impl PfcSection {
+    pub fn read<R: BufRead>(r: &mut R) -> Result<Self, HdtError> {
+        let kind = read_u8(r)?;
+        if kind != 2 {
+            return Err(HdtError::Unsupported(format!(
+                "dictionary section type {}",
+                kind
+            )));
+        }
+        let len = read_vbyte(r)?;
+        let size = read_vbyte(r)?;
+        let block_size = read_vbyte(r)?;
+        read_u8(r)?; // CRC8
+        let blocks = Sequence::read(r)?;
+        let data = read_bytes(r, size)?;
+        read_bytes(r, 4)?; // CRC32
+        if block_size == 0 || (len > 0 && blocks.len() <= (len - 1) / block_size) {
+            return Err(HdtError::Format(
+                "inconsistent dictionary section".to_string(),
+            ));
+        }
+        Ok(PfcSection {
+            len,
+            block_size,
+            blocks,
+            data,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The string with identifier `id` (starting at 1), if any.
+    pub fn extract(&self, id: usize) -> Option<Vec<u8>> {
+        if id == 0 || id > self.len {
+            return None;
+        }
+        let (block, index) = ((id - 1) / self.block_size, (id - 1) % self.block_size);
+        let (mut string, mut pos) = self.first(block)?;
+        for _ in 0..index {
+            pos = self.next(&mut string, pos)?;
+        }
+        Some(string)
+    }
+
+    /// The identifier (starting at 1) of `string`, if any.
+    pub fn locate(&self, string: &[u8]) -> Option<usize> {
+        // find the last block starting with a string lower or equal to `string`
+        let (mut lo, mut hi) = (0, self.len.div_ceil(self.block_size));
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if &self.first(mid)?.0[..] <= string {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let block = lo.checked_sub(1)?;
+        let (mut current, mut pos) = self.first(block)?;
+        let mut id = block * self.block_size + 1;
+        loop {
+            match current[..].cmp(string) {
+                std::cmp::Ordering::Equal => return Some(id),
+                std::cmp::Ordering::Greater => return None,
+                std::cmp::Ordering::Less => {}
+            }
+            id += 1;
+            if id > self.len || (id - 1).is_multiple_of(self.block_size) {
+                return None;
+            }
+            pos = self.next(&mut current, pos)?;
+        }
+    }
+
+    /// The first string of `block`, and the position following it.
+    fn first(&self, block: usize) -> Option<(Vec<u8>, usize)> {
+        let start = self.blocks.get(block);
+        let (string, pos) = self.cstring(start)?;
+        Some((string.to_vec(), pos))
+    }
+
+    /// Replace `string` by the string encoded at `pos`, and return the position following it.
+    fn next(&self, string: &mut Vec<u8>, pos: usize) -> Option<usize> {
+        let (shared, pos) = vbyte_at(&self.data, pos)?;
+        if shared > string.len() {
+            return None;
+        }
+        let (suffix, pos) = self.cstring(pos)?;
+        string.truncate(shared);
+        string.extend_from_slice(suffix);
+        Some(pos)
+    }
+
+    /// The NUL-terminated string at `pos`, and the position following it.
+    fn cstring(&self, pos: usize) -> Option<(&[u8], usize)> {
+        let rest = self.data.get(pos..)?;
+        let len = rest.iter().position(|b| *b == 0)?;
+        Some((&rest[..len], pos + len + 1))
+    }
+}
+
+/// A dictionary with four sections:
+/// terms that are both subjects and objects (shared), other subjects, predicates, other objects.
+///
+/// Shared terms have identifiers `1..=n`,
+/// and other subjects and objects have identifiers starting at `n+1`.
+#[derive(Clone, Debug)]
+pub(super) struct Dictionary {
+    shared: PfcSection,
+    subjects: PfcSection,
+    predicates: PfcSection,
+    objects: PfcSection,
+}
+
+impl Dictionary {
+    pub fn read<R: BufRead>(r: &mut R) -> Result<Self, HdtError> {
+        Ok(Dictionary {
+            shared: PfcSection::read(r)?,
+            subjects: PfcSection::read(r)?,
+            predicates: PfcSection::read(r)?,
+            objects: PfcSection::read(r)?,
+        })
+    }
+
+    pub fn subject(&self, id: usize) -> Result<BoxTerm, HdtError> {
+        let string = if id <= self.shared.len() {
+            self.shared.extract(id)
+        } else {
+            self.subjects.extract(id - self.shared.len())
+        };
+        to_term(string, "subject", id)
+    }
+
+    pub fn predicate(&self, id: usize) -> Result<BoxTerm, HdtError> {
+        to_term(self.predicates.extract(id), "predicate", id)
+    }
+
+    pub fn object(&self, id: usize) -> Result<BoxTerm, HdtError> {
+        let string = if id <= self.shared.len() {
+            self.shared.extract(id)
+        } else {
+            self.objects.extract(id - self.shared.len())
+        };
+        to_term(string, "object", id)
+    }
+
+    pub fn subject_id<T: TTerm + ?Sized>(&self, t: &T) -> Option<usize> {
+        self.locate(t, &self.subjects)
+    }
+
+    pub fn predicate_id<T: TTerm + ?Sized>(&self, t: &T) -> Option<usize> {
+        to_strings(t)
+            .iter()
+            .find_map(|string| self.predicates.locate(string.as_bytes()))
+    }
+
+    fn locate<T: TTerm + ?Sized>(&self, t: &T, section: &PfcSection) -> Option<usize> {
+        to_strings(t).iter().find_map(|string| {
+            let string = string.as_bytes();
+            self.shared
+                .locate(string)
+                .or_else(|| Some(section.locate(string)? + self.shared.len()))
+        })
+    }
+}
+
+fn to_term(string: Option<Vec<u8>>, role: &str, id: usize) -> Result<BoxTerm, HdtError> {
+    let string = string.ok_or_else(|| HdtError::Format(format!("unknown {} {}", role, id)))?;
+    let string = String::from_utf8(string)
+        .map_err(|_| HdtError::Format(format!("invalid UTF-8 in {} {}", role, id)))?;
+    let invalid = |err: TermError| HdtError::Format(format!("invalid {} {}: {}", role, id, err));
+    if let Some(rest) = string.strip_prefix('"') {
+        let quote = rest
+            .rfind('"')
+            .ok_or_else(|| HdtError::Format(format!("invalid literal {}", string)))?;
+        let (lex, suffix) = (&rest[..quote], &rest[quote + 1..]);
+        if suffix.is_empty() {
+            Ok(BoxTerm::new_literal_dt_unchecked(lex, xsd::string))
+        } else if let Some(tag) = suffix.strip_prefix('@') {
+            BoxTerm::new_literal_lang(lex, tag).map_err(invalid)
+        } else if suffix.starts_with("^^<") && suffix.ends_with('>') {
+            let datatype = BoxTerm::new_iri(&suffix[3..suffix.len() - 1]).map_err(invalid)?;
+            BoxTerm::new_literal_dt(lex, datatype).map_err(invalid)
+        } else {
+            Err(HdtError::Format(format!("invalid literal {}", string)))
+        }
+    } else if let Some(label) = string.strip_prefix("_:") {
+        BoxTerm::new_bnode(label).map_err(invalid)
+    } else {
+        BoxTerm::new_iri(string).map_err(invalid)
+    }
+}
+
+/// The possible encodings of `t` in the dictionary.
+///
+/// Simple literals may be encoded with or without their `xsd:string` datatype.
+fn to_strings<T: TTerm + ?Sized>(t: &T) -> Vec<String> {
+    let value = t.value();
+    match t.kind() {
+        TermKind::Iri => vec![value.to_string()],
+        TermKind::BlankNode => vec![format!("_:{}", value)],
+        TermKind::Literal => match (t.language(), t.datatype()) {
+            (Some(tag), _) => vec![format!("\"{}\"@{}", value, tag)],
+            (None, Some(datatype)) => {
+                let typed = format!("\"{}\"^^<{}>", value, datatype.value());
+                if term_eq(&datatype, &xsd::string) {
+                    vec![format!("\"{}\"", value), typed]
+                } else {
+                    vec![typed]
+                }
+            }
+            (None, None) => vec![format!("\"{}\"", value)],
+        },
+        TermKind::Variable => vec![],
+    }
+}
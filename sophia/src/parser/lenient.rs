@@ -0,0 +1,257 @@
+//! Error recovery for line-based parsers.
+//!
+//! In line-based formats such as [N-Triples](../nt/index.html) and [N-Quads](../nq/index.html),
+//! a syntax error only affects the line where it occurs,
+//! and parsing can resume at the next line.
+//! A [`LenientParser`] wraps such a parser,
+//! and reports the errors of malformed lines to a callback instead of aborting the stream.
+//! I/O errors, on the other hand, are still returned as usual.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::parser::nt::NTriplesParser;
+//! # use sophia::parser::TripleParser;
+//! # use sophia::triple::stream::TripleSource;
+//! # use std::cell::RefCell;
+//! let dump = r#"
+//!     <tag:a> <tag:p> "ok".
+//!     <tag:b> <tag:p> "unterminated.
+//!     <tag:c> <tag:p> "ok".
+//! "#;
+//! let errors = RefCell::new(vec![]);
+//! let parser = NTriplesParser {}.lenient(|err| errors.borrow_mut().push(err));
+//! let g: FastGraph = parser.parse_str(dump).collect_triples()?;
+//! assert_eq!(g.triples().count(), 2);
+//! assert_eq!(errors.borrow().len(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`LenientParser`]: struct.LenientParser.html
+
+use std::error::Error;
+use std::io;
+
+use rio_turtle::TurtleError;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::streaming_mode::StreamedQuad;
+use sophia_api::triple::stream::{SourceError, StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::StreamedTriple;
+
+/// An error after which a parser can resume.
+///
+/// The position of such an error, if any, uses the same convention as
+/// [`Location`](../../../sophia_api/parser/enum.Location.html):
+/// both lines and columns are counted from 1.
+/// This is also the convention of the errors of the parsers based on Rio,
+/// such as [`TurtleError`](../../../rio_turtle/struct.TurtleError.html).
+pub trait RecoverableError: Error + 'static {
+    /// Whether parsing can resume after this error,
+    /// skipping only the data where it occurred.
+    fn is_recoverable(&self) -> bool;
+}
+
+impl RecoverableError for TurtleError {
+    fn is_recoverable(&self) -> bool {
+        // only I/O errors have an io::Error as their source
+        self.source()
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .is_none()
+    }
+}
+
+/// A parser skipping the data affected by recoverable errors,
+/// and reporting those errors to a callback.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct LenientParser<P, F> {
+    parser: P,
+    on_error: F,
+}
+
+impl<P, F> LenientParser<P, F> {
+    /// Wrap `parser`, reporting recoverable errors to `on_error`.
+    ///
+    /// `on_error` is cloned for every parsed document.
+    pub fn new(parser: P, on_error: F) -> Self {
+        LenientParser { parser, on_error }
+    }
+
+    /// The wrapped parser.
+    pub fn inner(&self) -> &P {
+        &self.parser
+    }
+}
+
+impl<T, P, F> TripleParser<T> for LenientParser<P, F>
+where
+    P: TripleParser<T>,
+    <P::Source as TripleSource>::Error: RecoverableError,
+    F: FnMut(<P::Source as TripleSource>::Error) + Clone,
+{
+    type Source = LenientSource<P::Source, F>;
+    fn parse(&self, data: T) -> Self::Source {
+        LenientSource {
+            source: self.parser.parse(data),
+            on_error: self.on_error.clone(),
+        }
+    }
+}
+
+impl<T, P, F> QuadParser<T> for LenientParser<P, F>
+where
+    P: QuadParser<T>,
+    <P::Source as QuadSource>::Error: RecoverableError,
+    F: FnMut(<P::Source as QuadSource>::Error) + Clone,
+{
+    type Source = LenientSource<P::Source, F>;
+    fn parse(&self, data: T) -> Self::Source {
+        LenientSource {
+            source: self.parser.parse(data),
+            on_error: self.on_error.clone(),
+        }
+    }
+}
+
+/// The source produced by a [`LenientParser`](struct.LenientParser.html).
+pub struct LenientSource<S, F> {
+    source: S,
+    on_error: F,
+}
+
+impl<S, F> TripleSource for LenientSource<S, F>
+where
+    S: TripleSource,
+    S::Error: RecoverableError,
+    F: FnMut(S::Error),
+{
+    type Error = S::Error;
+    type Triple = S::Triple;
+
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, S::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        match self.source.try_for_some_triple(f) {
+            Err(SourceError(err)) if err.is_recoverable() => {
+                (self.on_error)(err);
+                Ok(true)
+            }
+            other => other,
+        }
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        self.source.size_hint_triples()
+    }
+}
+
+impl<S, F> QuadSource for LenientSource<S, F>
+where
+    S: QuadSource,
+    S::Error: RecoverableError,
+    F: FnMut(S::Error),
+{
+    type Error = S::Error;
+    type Quad = S::Quad;
+
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, S::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        match self.source.try_for_some_quad(f) {
+            Err(SourceError(err)) if err.is_recoverable() => {
+                (self.on_error)(err);
+                Ok(true)
+            }
+            other => other,
+        }
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        self.source.size_hint_quads()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::parser::nq::NQuadsParser;
+    use crate::parser::nt::NTriplesParser;
+    use rio_api::parser::ParseError;
+    use sophia_api::parser::WithLocation;
+    use std::cell::{Cell, RefCell};
+
+    const NT: &str = r#"<tag:a> <tag:p> <tag:b>.
+<tag:a> <tag:p> <tag:b> <tag:c>.
+<tag:a> <tag:p> "c".
+<tag:a> <tag:p> "d
+<tag:a> <tag:p> "e"."#;
+
+    #[test]
+    fn strict_nt_stops_at_first_error() {
+        let res: Result<FastGraph, _> = NTriplesParser {}.parse_str(NT).collect_triples();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn lenient_nt() -> Result<(), Box<dyn std::error::Error>> {
+        let errors = RefCell::new(vec![]);
+        let parser = NTriplesParser {}.lenient(|err: TurtleError| {
+            let position = err.textual_position().unwrap();
+            errors.borrow_mut().push(position.line_number())
+        });
+        let g: FastGraph = parser.parse_str(NT).collect_triples()?;
+        assert_eq!(g.triples().count(), 3);
+        let errors = errors.into_inner();
+        // lines are counted from 1
+        assert_eq!(errors, [2, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_nq() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = "<tag:a> <tag:p> <tag:b>.\n\
+                  <tag:a> <tag:p> <tag:b> \"g\".\n\
+                  <tag:a> <tag:p> <tag:b> <tag:g>.\n";
+        let errors = RefCell::new(vec![]);
+        let parser = NQuadsParser {}.lenient(|err| errors.borrow_mut().push(err));
+        let d: FastDataset = parser.parse_str(nq).collect_quads()?;
+        assert_eq!(d.quads().count(), 2);
+        let errors = errors.into_inner();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location().to_string(), "2:25");
+        Ok(())
+    }
+
+    /// A reader failing after some data
+    struct Failing<'a>(&'a [u8]);
+
+    impl<'a> io::Read for Failing<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                Err(io::Error::other("broken pipe"))
+            } else {
+                self.0.read(buf)
+            }
+        }
+    }
+
+    #[test]
+    fn io_errors_are_not_recovered() {
+        let data = io::BufReader::new(Failing(b"<tag:a> <tag:p> <tag:b>.\n"));
+        let reported = Cell::new(0);
+        let parser = NQuadsParser {}.lenient(|_| reported.set(reported.get() + 1));
+        let res: Result<FastDataset, _> = parser.parse(data).collect_quads();
+        assert!(res.is_err());
+        assert_eq!(reported.get(), 0);
+    }
+}
@@ -0,0 +1,848 @@
+//! Parser for [Notation3] (N3).
+//!
+//! N3 extends Turtle with *formulas* (`{ ... }`), implications (`=>` and `<=`),
+//! universally quantified variables (`?x`) and paths (`x!p` and `x^p`),
+//! which makes it a convenient syntax for rule systems.
+//!
+//! RDF has no notion of formula, so this parser produces a [`QuadSource`]
+//! where each formula is represented by a fresh blank node,
+//! used in place of the formula in the enclosing statement,
+//! and as the graph name of the statements contained in the formula.
+//! Statements outside any formula are in the default graph.
+//! For example, the rule
+//! ```text
+//! { ?x :parent ?y } => { ?y :child ?x } .
+//! ```
+//! produces the following quads:
+//! ```text
+//! ?x :parent ?y _:n3-1 .
+//! ?y :child ?x _:n3-2 .
+//! _:n3-1 log:implies _:n3-2 .
+//! ```
+//! Variables are represented by [variable terms],
+//! so the produced quads are *generalized* RDF.
+//!
+//! The N3 keywords are mapped as follows:
+//! `a` to `rdf:type`, `=` to `owl:sameAs`, `=>` to `log:implies`,
+//! `<=` to `log:implies` with subject and object swapped,
+//! and `is p of` or `<- p` to `p` with subject and object swapped.
+//!
+//! Blank nodes and formulas created by the parser are labelled `n3-1`, `n3-2`...
+//!
+//! NB: `@forAll`, `@forSome` and `@keywords` are not supported,
+//! and blank node labels are scoped to the whole document
+//! (rather than to the formula where they appear).
+//!
+//! [Notation3]: https://w3c.github.io/N3/spec/
+//! [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+//! [variable terms]: ../../term/enum.Term.html#variant.Variable
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use sophia_api::ns::{owl, rdf, xsd};
+use sophia_api::parser::{Location, QuadParser, WithLocation};
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::syntax::escape::{
+    is_pn_chars, is_pn_chars_u, unescape_iri, unescape_local, unescape_string,
+};
+
+/// The IRI of `log:implies`, used for `=>` and `<=`.
+pub const LOG_IMPLIES: &str = "http://www.w3.org/2000/10/swap/log#implies";
+
+/// N3 parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct N3Parser {
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    pub base: Option<String>,
+    /// Whether relative IRI-references are errors when no base IRI is available
+    /// (neither `base` nor a base declared in the data).
+    /// Otherwise (the default), they are kept as is.
+    pub reject_relative: bool,
+    /// Prefixes available before any declaration in the data, as (prefix, namespace) pairs.
+    pub prefixes: Vec<(String, String)>,
+}
+
+impl<B: BufRead> QuadParser<B> for N3Parser {
+    type Source = std::vec::IntoIter<Result<TupleQuad<BoxTerm>, N3Error>>;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        if let Err(err) = data.read_to_string(&mut txt) {
+            return vec![Err(N3Error::Io(err))].into_iter();
+        }
+        let mut parser = Parser::new(&txt);
+        parser.reject_relative = self.reject_relative;
+        parser.prefixes.extend(self.prefixes.iter().cloned());
+        let res = match &self.base {
+            None => Ok(()),
+            Some(base) => parser.set_base(base.clone()),
+        }
+        .and_then(|_| parser.document());
+        if let Err(err) = res {
+            parser.quads.push(Err(err));
+        }
+        parser.quads.into_iter()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(N3Parser, QuadParser);
+
+/// Errors raised by the [`N3Parser`](struct.N3Parser.html).
+#[derive(Debug, Error)]
+pub enum N3Error {
+    /// The data could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data is not valid N3 (or uses an unsupported feature of N3).
+    #[error("Invalid N3 at {line}:{column}: {message}")]
+    Syntax {
+        /// A description of the error.
+        message: String,
+        /// The line of the error (starting at 1).
+        line: usize,
+        /// The column of the error (starting at 1).
+        column: usize,
+        /// The offset of the error from the start of the data, in bytes.
+        byte_offset: usize,
+    },
+}
+
+impl WithLocation for N3Error {
+    fn location(&self) -> Location {
+        match self {
+            N3Error::Io(_) => Location::Unknown,
+            N3Error::Syntax { line, column, .. } => Location::from_lico(*line, *column),
+        }
+    }
+
+    fn byte_offset(&self) -> Option<usize> {
+        match self {
+            N3Error::Io(_) => None,
+            N3Error::Syntax { byte_offset, .. } => Some(*byte_offset),
+        }
+    }
+}
+
+type PResult<T> = Result<T, N3Error>;
+
+/// A recursive descent parser, producing all its quads in memory.
+struct Parser<'a> {
+    txt: &'a str,
+    pos: usize,
+    base: Option<oxiri::Iri<String>>,
+    reject_relative: bool,
+    prefixes: HashMap<String, String>,
+    bnodes: usize,
+    graph: Option<BoxTerm>,
+    quads: Vec<PResult<TupleQuad<BoxTerm>>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(txt: &'a str) -> Self {
+        Parser {
+            txt,
+            pos: 0,
+            base: None,
+            reject_relative: false,
+            prefixes: HashMap::new(),
+            bnodes: 0,
+            graph: None,
+            quads: vec![],
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+    // grammar rules
+
+    fn document(&mut self) -> PResult<()> {
+        loop {
+            self.skip_ws();
+            if self.rest().is_empty() {
+                return Ok(());
+            }
+            let needs_dot = self.statement()?;
+            self.skip_ws();
+            if needs_dot && !self.eat(".") {
+                return Err(self.error("expected '.'"));
+            }
+        }
+    }
+
+    /// Parse a directive or a statement,
+    /// and return whether it must be terminated by a '.'.
+    fn statement(&mut self) -> PResult<bool> {
+        if self.eat("@prefix") {
+            self.prefix_decl()?;
+            return Ok(true);
+        }
+        if self.eat("@base") {
+            self.base_decl()?;
+            return Ok(true);
+        }
+        if self.eat_sparql_keyword("PREFIX") {
+            self.prefix_decl()?;
+            return Ok(false);
+        }
+        if self.eat_sparql_keyword("BASE") {
+            self.base_decl()?;
+            return Ok(false);
+        }
+        for unsupported in &["@forAll", "@forSome", "@keywords"] {
+            if self.rest().starts_with(unsupported) {
+                return Err(self.error(&format!("{} is not supported", unsupported)));
+            }
+        }
+        let subject = self.expression()?;
+        self.skip_ws();
+        if !matches!(self.peek(), Some('.') | Some('}') | None) {
+            self.predicate_object_list(&subject)?;
+        }
+        Ok(true)
+    }
+
+    fn prefix_decl(&mut self) -> PResult<()> {
+        self.skip_ws();
+        let start = self.pos;
+        self.eat_while(|c| is_pn_chars(c) || c == '.');
+        let prefix = self.txt[start..self.pos].to_string();
+        if !self.eat(":") {
+            return Err(self.error("expected ':'"));
+        }
+        self.skip_ws();
+        let ns = self.iriref()?;
+        self.prefixes.insert(prefix, ns);
+        Ok(())
+    }
+
+    fn base_decl(&mut self) -> PResult<()> {
+        self.skip_ws();
+        let base = self.iriref()?;
+        self.set_base(base)
+    }
+
+    fn predicate_object_list(&mut self, subject: &BoxTerm) -> PResult<()> {
+        loop {
+            let (verb, inverse) = self.verb()?;
+            loop {
+                let object = self.expression()?;
+                if inverse {
+                    self.emit(&object, &verb, subject);
+                } else {
+                    self.emit(subject, &verb, &object);
+                }
+                self.skip_ws();
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            if !self.eat(";") {
+                return Ok(());
+            }
+            loop {
+                self.skip_ws();
+                if !self.eat(";") {
+                    break;
+                }
+            }
+            if matches!(self.peek(), Some('.') | Some(']') | Some('}') | None) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse a verb, and return it with a flag indicating whether it is inverted.
+    fn verb(&mut self) -> PResult<(BoxTerm, bool)> {
+        self.skip_ws();
+        if self.eat("=>") {
+            Ok((self.iri(LOG_IMPLIES.to_string())?, false))
+        } else if self.eat("<=") {
+            Ok((self.iri(LOG_IMPLIES.to_string())?, true))
+        } else if self.eat_keyword("<-") {
+            Ok((self.expression()?, true))
+        } else if self.eat("=") {
+            Ok((BoxTerm::copy(&owl::sameAs), false))
+        } else if self.eat_keyword("a") {
+            Ok((BoxTerm::copy(&rdf::type_), false))
+        } else if self.eat_keyword("has") {
+            Ok((self.expression()?, false))
+        } else if self.eat_keyword("is") {
+            let verb = self.expression()?;
+            self.skip_ws();
+            if !self.eat_keyword("of") {
+                return Err(self.error("expected 'of'"));
+            }
+            Ok((verb, true))
+        } else {
+            Ok((self.expression()?, false))
+        }
+    }
+
+    /// A path item, optionally followed by `!` or `^` paths.
+    fn expression(&mut self) -> PResult<BoxTerm> {
+        let mut node = self.path_item()?;
+        loop {
+            self.skip_ws();
+            let forward = if self.eat("!") {
+                true
+            } else if self.eat("^") {
+                false
+            } else {
+                return Ok(node);
+            };
+            let predicate = self.path_item()?;
+            let next = self.new_bnode();
+            if forward {
+                self.emit(&node, &predicate, &next);
+            } else {
+                self.emit(&next, &predicate, &node);
+            }
+            node = next;
+        }
+    }
+
+    fn path_item(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some('<') => {
+                let iri = self.iriref()?;
+                self.iri(iri)
+            }
+            Some('[') => self.blank_node_property_list(),
+            Some('(') => self.collection(),
+            Some('{') => self.formula(),
+            Some('"') | Some('\'') => self.literal(),
+            Some('?') => {
+                self.pos += 1;
+                let name = self.name();
+                BoxTerm::new_variable(name).map_err(|e| self.error(&e.to_string()))
+            }
+            Some('_') if self.rest().starts_with("_:") => {
+                self.pos += 2;
+                let label = self.name();
+                BoxTerm::new_bnode(label).map_err(|e| self.error(&e.to_string()))
+            }
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' || c == '.' => self.number(),
+            _ => {
+                if self.eat_keyword("true") {
+                    Ok(BoxTerm::new_literal_dt_unchecked("true", xsd::boolean))
+                } else if self.eat_keyword("false") {
+                    Ok(BoxTerm::new_literal_dt_unchecked("false", xsd::boolean))
+                } else {
+                    self.prefixed_name()
+                }
+            }
+        }
+    }
+
+    fn blank_node_property_list(&mut self) -> PResult<BoxTerm> {
+        self.pos += 1; // '['
+        let bnode = self.new_bnode();
+        self.skip_ws();
+        if !self.eat("]") {
+            self.predicate_object_list(&bnode)?;
+            self.skip_ws();
+            if !self.eat("]") {
+                return Err(self.error("expected ']'"));
+            }
+        }
+        Ok(bnode)
+    }
+
+    fn collection(&mut self) -> PResult<BoxTerm> {
+        self.pos += 1; // '('
+        let mut items = vec![];
+        loop {
+            self.skip_ws();
+            if self.eat(")") {
+                break;
+            }
+            items.push(self.expression()?);
+        }
+        let nodes: Vec<_> = items.iter().map(|_| self.new_bnode()).collect();
+        let nil = BoxTerm::copy(&rdf::nil);
+        let first = BoxTerm::copy(&rdf::first);
+        let rest = BoxTerm::copy(&rdf::rest);
+        for (i, item) in items.iter().enumerate() {
+            self.emit(&nodes[i], &first, item);
+            self.emit(&nodes[i], &rest, nodes.get(i + 1).unwrap_or(&nil));
+        }
+        Ok(nodes.into_iter().next().unwrap_or(nil))
+    }
+
+    fn formula(&mut self) -> PResult<BoxTerm> {
+        self.pos += 1; // '{'
+        let formula = self.new_bnode();
+        let outer = self.graph.replace(formula.clone());
+        loop {
+            self.skip_ws();
+            if self.eat("}") {
+                break;
+            }
+            if self.rest().is_empty() {
+                return Err(self.error("expected '}'"));
+            }
+            self.statement()?;
+            self.skip_ws();
+            if !self.eat(".") && self.peek() != Some('}') {
+                return Err(self.error("expected '.' or '}'"));
+            }
+        }
+        self.graph = outer;
+        Ok(formula)
+    }
+
+    fn literal(&mut self) -> PResult<BoxTerm> {
+        let lex = self.string()?;
+        if self.eat("@") {
+            let start = self.pos;
+            self.eat_while(|c| c.is_ascii_alphanumeric() || c == '-');
+            let tag = &self.txt[start..self.pos];
+            BoxTerm::new_literal_lang(lex, tag).map_err(|e| self.error(&e.to_string()))
+        } else if self.eat("^^") {
+            let datatype = self.path_item()?;
+            if datatype.kind() != TermKind::Iri {
+                return Err(self.error("datatype must be an IRI"));
+            }
+            BoxTerm::new_literal_dt(lex, datatype).map_err(|e| self.error(&e.to_string()))
+        } else {
+            Ok(BoxTerm::new_literal_dt_unchecked(lex, xsd::string))
+        }
+    }
+
+    fn string(&mut self) -> PResult<String> {
+        let quote = self.peek().unwrap();
+        let long = quote.to_string().repeat(3);
+        let is_long = self.rest().starts_with(&long);
+        self.pos += if is_long { 3 } else { 1 };
+        let start = self.pos;
+        loop {
+            let rest = self.rest();
+            match rest.chars().next() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => {
+                    self.pos += 1;
+                    self.pos += self.peek().map_or(0, char::len_utf8);
+                }
+                Some(c) if is_long && rest.starts_with(&long) => {
+                    // a long string may end with quotes
+                    if rest[3..].starts_with(c) {
+                        self.pos += 1;
+                        continue;
+                    }
+                    break;
+                }
+                Some(c) if !is_long && c == quote => break,
+                Some('\n') | Some('\r') if !is_long => {
+                    return Err(self.error("line break in short string"))
+                }
+                Some(c) => self.pos += c.len_utf8(),
+            }
+        }
+        let raw = &self.txt[start..self.pos];
+        self.pos += if is_long { 3 } else { 1 };
+        unescape_string(raw).map_err(|e| self.error(&e.to_string()))
+    }
+
+    fn number(&mut self) -> PResult<BoxTerm> {
+        let start = self.pos;
+        let _ = self.eat("+") || self.eat("-");
+        let int_digits = self.eat_while(|c| c.is_ascii_digit());
+        let mut datatype = xsd::integer;
+        if self.rest().starts_with('.')
+            && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.pos += 1;
+            self.eat_while(|c| c.is_ascii_digit());
+            datatype = xsd::decimal;
+        } else if int_digits == 0 {
+            return Err(self.error("invalid number"));
+        }
+        if self.rest().starts_with(&['e', 'E'][..]) {
+            let mark = self.pos;
+            self.pos += 1;
+            let _ = self.eat("+") || self.eat("-");
+            if self.eat_while(|c| c.is_ascii_digit()) == 0 {
+                self.pos = mark;
+                return Err(self.error("invalid exponent"));
+            }
+            datatype = xsd::double;
+        }
+        let lex = &self.txt[start..self.pos];
+        Ok(BoxTerm::new_literal_dt_unchecked(lex, datatype))
+    }
+
+    fn prefixed_name(&mut self) -> PResult<BoxTerm> {
+        let start = self.pos;
+        self.eat_while(|c| is_pn_chars(c) || c == '.');
+        let prefix = &self.txt[start..self.pos];
+        if !self.eat(":") {
+            self.pos = start;
+            return Err(match self.peek() {
+                Some(c) if prefix.is_empty() => self.error(&format!("unexpected '{}'", c)),
+                _ => self.error(&format!("unknown keyword '{}'", prefix)),
+            });
+        }
+        let ns = match self.prefixes.get(prefix) {
+            Some(ns) => ns.clone(),
+            None => {
+                self.pos = start;
+                return Err(self.error(&format!("undeclared prefix '{}'", prefix)));
+            }
+        };
+        let local_start = self.pos;
+        loop {
+            match self.peek() {
+                Some('\\') => {
+                    self.pos += 1;
+                    self.pos += self.peek().map_or(0, char::len_utf8);
+                }
+                Some(c) if is_pn_chars(c) || c == ':' || c == '.' || c == '%' => {
+                    self.pos += c.len_utf8()
+                }
+                _ => break,
+            }
+        }
+        // trailing (unescaped) dots terminate the statement
+        while self.txt[local_start..self.pos].ends_with('.')
+            && !self.txt[local_start..self.pos - 1].ends_with('\\')
+        {
+            self.pos -= 1;
+        }
+        let local = unescape_local(&self.txt[local_start..self.pos])
+            .map_err(|e| self.error(&e.to_string()))?;
+        self.iri(ns + &local)
+    }
+
+    /// Parse an IRI reference, and return it resolved against the current base.
+    fn iriref(&mut self) -> PResult<String> {
+        if !self.eat("<") {
+            return Err(self.error("expected '<'"));
+        }
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| c == '>' || c.is_whitespace() || matches!(c, '<' | '"' | '{' | '}'))
+            .unwrap_or_else(|| self.rest().len());
+        self.pos += len;
+        if !self.eat(">") {
+            return Err(self.error("invalid IRI reference"));
+        }
+        let iri =
+            unescape_iri(&self.txt[start..start + len]).map_err(|e| self.error(&e.to_string()))?;
+        match &self.base {
+            None if self.reject_relative && oxiri::Iri::parse(iri.as_str()).is_err() => {
+                Err(self.error(&format!("relative IRI <{}> with no base IRI", iri)))
+            }
+            None => Ok(iri),
+            Some(base) => base
+                .resolve(&iri)
+                .map(oxiri::Iri::into_inner)
+                .map_err(|e| self.error(&e.to_string())),
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+    // helpers
+
+    fn set_base(&mut self, base: String) -> PResult<()> {
+        let base = oxiri::Iri::parse(base).map_err(|e| self.error(&e.to_string()))?;
+        self.base = Some(base);
+        Ok(())
+    }
+
+    fn iri(&self, iri: String) -> PResult<BoxTerm> {
+        BoxTerm::new_iri(iri).map_err(|e| self.error(&e.to_string()))
+    }
+
+    fn new_bnode(&mut self) -> BoxTerm {
+        self.bnodes += 1;
+        BoxTerm::new_bnode_unchecked(format!("n3-{}", self.bnodes))
+    }
+
+    fn emit(&mut self, s: &BoxTerm, p: &BoxTerm, o: &BoxTerm) {
+        let quad = ([s.clone(), p.clone(), o.clone()], self.graph.clone());
+        self.quads.push(Ok(quad));
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.txt[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Like `eat`, but only if `keyword` is not followed by a name character.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let rest = self.rest();
+        if !rest.starts_with(keyword)
+            || rest[keyword.len()..].starts_with(|c: char| is_pn_chars(c) || c == ':')
+        {
+            return false;
+        }
+        self.pos += keyword.len();
+        true
+    }
+
+    /// Like `eat_keyword`, but case insensitive.
+    fn eat_sparql_keyword(&mut self, keyword: &str) -> bool {
+        let rest = self.rest();
+        let matches = rest
+            .get(..keyword.len())
+            .is_some_and(|k| k.eq_ignore_ascii_case(keyword))
+            && rest[keyword.len()..].starts_with(char::is_whitespace);
+        if matches {
+            self.pos += keyword.len();
+        }
+        matches
+    }
+
+    /// Consume the characters matching `predicate`, and return how many were consumed.
+    fn eat_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> usize {
+        let len = self
+            .rest()
+            .find(|c: char| !predicate(c))
+            .unwrap_or_else(|| self.rest().len());
+        self.pos += len;
+        self.txt[self.pos - len..self.pos].chars().count()
+    }
+
+    /// Consume a variable name or blank node label.
+    fn name(&mut self) -> &'a str {
+        let start = self.pos;
+        if self
+            .peek()
+            .is_some_and(|c| is_pn_chars_u(c) || c.is_ascii_digit())
+        {
+            self.eat_while(|c| is_pn_chars(c) || c == '.');
+            while self.txt[start..self.pos].ends_with('.') {
+                self.pos -= 1;
+            }
+        }
+        &self.txt[start..self.pos]
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_ws(&mut self) {
+        loop {
+            self.eat_while(char::is_whitespace);
+            if !self.rest().starts_with('#') {
+                return;
+            }
+            self.eat_while(|c| c != '\n');
+        }
+    }
+
+    fn error(&self, message: &str) -> N3Error {
+        let before = &self.txt[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        N3Error::Syntax {
+            message: message.to_string(),
+            line,
+            column,
+            byte_offset: self.pos,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::quad::stream::QuadSource;
+    use crate::quad::Quad;
+    use sophia_api::term::matcher::ANY;
+    use sophia_term::StaticTerm;
+
+    fn parse(n3: &str) -> Result<FastDataset, Box<dyn std::error::Error>> {
+        let p = N3Parser {
+            base: Some("http://example.org/".into()),
+            ..N3Parser::default()
+        };
+        Ok(p.parse_str(n3).collect_quads()?)
+    }
+
+    fn ex(suffix: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    #[test]
+    fn turtle_subset() -> Result<(), Box<dyn std::error::Error>> {
+        let d = parse(
+            r#"
+            @prefix : <http://example.org/> .
+            PREFIX ex: <http://example.org/>
+            # a comment
+            <alice> a :Person ;
+                :name "Alice", 'Alicia'@es ;
+                :age 42 ; :height 1.65 ; :weight 5.5e1 ; :cool true ;
+                :knows [ :name """Bob "the builder\"""" ] ;
+                :likes ( ex:a ex:b\.c ) .
+            "#,
+        )?;
+        assert_eq!(d.quads().count(), 14);
+        assert_eq!(d.quads_with_g(None as Option<&BoxTerm>).count(), 14);
+        let alice = ex("alice");
+        assert!(d
+            .quads_matching(
+                &alice,
+                &ex("age"),
+                &StaticTerm::new_literal_dt("42", xsd::integer)?,
+                &ANY
+            )
+            .next()
+            .is_some());
+        assert!(d
+            .quads_matching(
+                &ANY,
+                &ex("name"),
+                &StaticTerm::new_literal_dt("Bob \"the builder\"", xsd::string)?,
+                &ANY
+            )
+            .next()
+            .is_some());
+        assert!(d
+            .quads_matching(&ANY, &rdf::first, &ex("b.c"), &ANY)
+            .next()
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn formulas_and_implication() -> Result<(), Box<dyn std::error::Error>> {
+        let d = parse(
+            r#"
+            @prefix : <http://example.org/> .
+            { ?x :parent ?y } => { ?y :child ?x. ?y a :Parent } .
+            { ?x :child ?y } <= { ?y :parent ?x } .
+            "#,
+        )?;
+        // 2 log:implies triples, 2 premises (1 triple each), and conclusions of 2 and 1 triples
+        assert_eq!(d.quads().count(), 7);
+        let implies = StaticTerm::new_iri(LOG_IMPLIES)?;
+        let rules: Vec<_> = d
+            .quads_with_p(&implies)
+            .map(|q| {
+                let q = q.unwrap();
+                assert!(q.g().is_none());
+                (BoxTerm::copy(q.s()), BoxTerm::copy(q.o()))
+            })
+            .collect();
+        assert_eq!(rules.len(), 2);
+        let mut conclusion_sizes = vec![];
+        for (premise, conclusion) in &rules {
+            assert_eq!(premise.kind(), TermKind::BlankNode);
+            assert_eq!(conclusion.kind(), TermKind::BlankNode);
+            // both premises are { ?_ :parent ?_ }, since '<=' swaps subject and object
+            let premise: Vec<_> = d.quads_with_g(Some(premise)).collect();
+            assert_eq!(premise.len(), 1);
+            let premise = premise[0].as_ref().unwrap();
+            assert_eq!(premise.s().kind(), TermKind::Variable);
+            assert_eq!(&premise.p().value()[..], "http://example.org/parent");
+            conclusion_sizes.push(d.quads_with_g(Some(conclusion)).count());
+        }
+        conclusion_sizes.sort_unstable();
+        assert_eq!(conclusion_sizes, vec![1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn paths_and_inverses() -> Result<(), Box<dyn std::error::Error>> {
+        let d = parse(
+            r#"
+            @prefix : <http://example.org/> .
+            :joe!:mother :age 60 .
+            :joe^:child :name "Jack" .
+            :sue is :parent of :joe ; <- :sibling :ann ; = :susan ; has :age 30 .
+            "#,
+        )?;
+        assert_eq!(d.quads().count(), 8);
+        let (joe, sue) = (ex("joe"), ex("sue"));
+        let mother = ex("mother");
+        let mother = d.quads_with_sp(&joe, &mother).next().unwrap()?;
+        assert_eq!(mother.o().kind(), TermKind::BlankNode);
+        assert_eq!(d.quads_with_s(mother.o()).count(), 1);
+        assert_eq!(d.quads_with_po(&ex("child"), &joe).count(), 1);
+        assert_eq!(d.quads_with_spo(&joe, &ex("parent"), &sue).count(), 1);
+        assert_eq!(
+            d.quads_with_spo(&ex("ann"), &ex("sibling"), &sue).count(),
+            1
+        );
+        assert_eq!(
+            d.quads_with_spo(&sue, &owl::sameAs, &ex("susan")).count(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for (n3, line, column) in &[
+            ("<a> <b> <c>", 1, 12),
+            ("<a> <b>\n  foo:c .", 2, 3),
+            ("<a> <b> { <c> <d> <e> ", 1, 23),
+            ("<a> <b> \"c\n\" .", 1, 11),
+            ("@forAll <x> .", 1, 1),
+        ] {
+            let res: Result<FastDataset, _> = N3Parser::default().parse_str(n3).collect_quads();
+            match res {
+                Err(crate::triple::stream::StreamError::SourceError(err)) => {
+                    let expected = format!("{}:{}", line, column);
+                    assert_eq!(err.location().to_string(), expected, "{:?}: {}", n3, err);
+                }
+                _ => panic!("{:?} should fail", n3),
+            }
+        }
+    }
+
+    #[test]
+    fn relative_iris() -> Result<(), Box<dyn std::error::Error>> {
+        let n3 = "<a> <b> <../c> .";
+        let d: FastDataset = N3Parser::default().parse_str(n3).collect_quads()?;
+        assert_eq!(d.quads_with_o(&BoxTerm::new_iri("../c")?).count(), 1);
+
+        let strict = N3Parser {
+            reject_relative: true,
+            ..N3Parser::default()
+        };
+        assert!(strict.parse_str(n3).collect_quads::<FastDataset>().is_err());
+        let with_base = format!("@base <http://example.org/x/>. {}", n3);
+        let d: FastDataset = strict.parse_str(&with_base).collect_quads()?;
+        assert_eq!(d.quads_with_o(&ex("c")).count(), 1);
+        let d: FastDataset = N3Parser {
+            base: Some("http://example.org/x/y".into()),
+            reject_relative: true,
+            ..N3Parser::default()
+        }
+        .parse_str(n3)
+        .collect_quads()?;
+        assert_eq!(d.quads_with_o(&ex("c")).count(), 1);
+        Ok(())
+    }
+}
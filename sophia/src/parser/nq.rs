@@ -1,25 +1,778 @@
-//! Adapter for the [N-Quads] parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/ntriples.rs)
+//! Streaming parser for [N-Quads].
+//!
+//! This parser reads its input one line at a time,
+//! and the quads it produces borrow their term data from internal buffers,
+//! which are reused from one line to the next
+//! (see [scoped streaming mode](../../quad/streaming_mode/index.html)).
+//! Its memory usage is therefore bounded by the size of the longest line,
+//! regardless of the size of the input.
+//!
+//! Syntax errors are reported once per invalid line:
+//! after an error, parsing can resume at the next line.
 //!
 //! [N-Quads]: https://www.w3.org/TR/n-quads/
 
-use crate::parser::rio_common::*;
-use rio_turtle::{NQuadsParser as RioNQParser, TurtleError};
-use sophia_api::parser::QuadParser;
-use std::io::BufRead;
+use std::error::Error;
+use std::io::{self, BufRead};
+
+use crate::parser::lenient::{LenientParser, RecoverableError};
+#[cfg(feature = "memchr")]
+use memchr::{memchr, memchr2, memchr_iter, memrchr};
+use sophia_api::ns::xsd;
+use sophia_api::parser::{Location, QuadParser, WithLocation};
+use sophia_api::quad::stream::{QuadSource, SinkError, SourceError, StreamResult};
+use sophia_api::quad::streaming_mode::StreamedQuad;
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::MownStr;
+use sophia_term::{MownTerm, RefTerm, TermError};
+use thiserror::Error;
 
-/// N-Quads parser based on RIO.
+/// N-Quads parser.
+///
+/// See [module documentation](index.html).
 #[derive(Clone, Debug, Default)]
 pub struct NQuadsParser {}
 
 impl<B: BufRead> QuadParser<B> for NQuadsParser {
-    type Source = StrictRioSource<RioNQParser<B>, TurtleError>;
+    type Source = NQuadsSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        StrictRioSource::Parser(RioNQParser::new(data))
+        self.parse_from(data, 0, 0)
+    }
+}
+
+impl NQuadsParser {
+    /// Parse `data`, which starts at the given line number and byte offset of a larger document,
+    /// so that the locations of errors are relative to that document.
+    ///
+    /// This is used to resume parsing where it stopped
+    /// (see [`checkpoint`](../checkpoint/index.html)).
+    pub fn parse_from<B: BufRead>(
+        &self,
+        data: B,
+        line_number: usize,
+        offset: usize,
+    ) -> NQuadsSource<B> {
+        NQuadsSource {
+            data,
+            line: String::new(),
+            scratch: String::new(),
+            line_number,
+            offset,
+        }
+    }
+
+    /// Make this parser lenient:
+    /// malformed lines are skipped, and their errors are passed to `on_error`
+    /// instead of aborting the stream.
+    ///
+    /// See [`lenient`](../lenient/index.html) for more details.
+    pub fn lenient<F>(self, on_error: F) -> LenientParser<Self, F>
+    where
+        F: FnMut(NQuadsError) + Clone,
+    {
+        LenientParser::new(self, on_error)
     }
 }
 
 sophia_api::def_mod_functions_for_bufread_parser!(NQuadsParser, QuadParser);
 
+/// A quad produced by [`NQuadsSource`](struct.NQuadsSource.html).
+pub type NQuadsSourceQuad<'a> = ([RefTerm<'a>; 3], Option<RefTerm<'a>>);
+sophia_api::make_scoped_quad_streaming_mode!(
+    /// A scoped N-Quads source quad.
+    ScopedNQuadsSourceQuad,
+    NQuadsSourceQuad
+);
+
+/// The quad source produced by [`NQuadsParser`](struct.NQuadsParser.html).
+pub struct NQuadsSource<B> {
+    data: B,
+    /// The line being parsed.
+    line: String,
+    /// Unescaped term data, when the line contains escape sequences.
+    scratch: String,
+    line_number: usize,
+    /// The byte offset of the next line in the data.
+    offset: usize,
+}
+
+impl<B> NQuadsSource<B> {
+    /// The number of lines read so far.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The byte offset, in the data, of the next line to be read.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<B: BufRead> QuadSource for NQuadsSource<B> {
+    type Error = NQuadsError;
+    type Quad = ScopedNQuadsSourceQuad;
+
+    fn try_for_some_quad<F, E>(&mut self, f: &mut F) -> StreamResult<bool, NQuadsError, E>
+    where
+        F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        loop {
+            self.line.clear();
+            self.scratch.clear();
+            self.line_number += 1;
+            let line = self.line_number;
+            let read = self
+                .data
+                .read_line(&mut self.line)
+                .map_err(|source| NQuadsError::Io { source, line })
+                .map_err(SourceError)?;
+            if read == 0 {
+                return Ok(false);
+            }
+            let line_offset = self.offset;
+            self.offset += read;
+            let txt = self.line.trim_end_matches(&['\n', '\r'][..]);
+            let quad = parse_line(txt, line, line_offset, &mut self.scratch, false)
+                .map_err(SourceError)?;
+            if let Some(quad) = quad {
+                f(StreamedQuad::scoped(quad)).map_err(SinkError)?;
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Parse a single line of N-Quads (without its end-of-line characters),
+/// using `scratch` to store unescaped term data.
+///
+/// `line` and `line_offset` are the line number and byte offset of `txt` in the data,
+/// used to locate errors.
+/// If `triples_only` is true, quads with a graph name are rejected.
+pub(crate) fn parse_line<'a>(
+    txt: &'a str,
+    line: usize,
+    line_offset: usize,
+    scratch: &'a mut String,
+    triples_only: bool,
+) -> Result<Option<NQuadsSourceQuad<'a>>, NQuadsError> {
+    let ctx = LineContext {
+        txt,
+        line,
+        line_offset,
+    };
+    let located = match ctx.locate(scratch, triples_only)? {
+        None => return Ok(None),
+        Some(located) => located,
+    };
+    let scratch: &'a str = scratch;
+    ctx.build(located, |span| span.term(txt, scratch)).map(Some)
+}
+
+/// Same as [`parse_line`], but the terms of the quad only borrow their data from `txt`;
+/// data that had to be unescaped is copied.
+fn parse_line_mown<'a>(
+    txt: &'a str,
+    line: usize,
+    line_offset: usize,
+    scratch: &mut String,
+    triples_only: bool,
+) -> Result<Option<BorrowedQuad<'a>>, NQuadsError> {
+    let ctx = LineContext {
+        txt,
+        line,
+        line_offset,
+    };
+    let located = match ctx.locate(scratch, triples_only)? {
+        None => return Ok(None),
+        Some(located) => located,
+    };
+    let scratch: &str = scratch;
+    ctx.build(located, |span| span.mown_term(txt, scratch))
+        .map(Some)
+}
+
+/// A line being parsed, with its location in the data.
+struct LineContext<'a> {
+    txt: &'a str,
+    line: usize,
+    line_offset: usize,
+}
+
+impl<'a> LineContext<'a> {
+    fn error(&self, message: String, pos: usize) -> NQuadsError {
+        NQuadsError::Syntax {
+            message,
+            line: self.line,
+            column: self.txt[..pos].chars().count() + 1,
+            byte_offset: self.line_offset + pos,
+            token: token_at(self.txt, pos).to_string(),
+        }
+    }
+
+    /// Locate the terms of the quad in this line, if any.
+    fn locate(
+        &self,
+        scratch: &mut String,
+        triples_only: bool,
+    ) -> Result<Option<Located>, NQuadsError> {
+        let located = LineParser {
+            txt: self.txt,
+            pos: 0,
+            scratch,
+        }
+        .parse()
+        .map_err(|(message, pos)| self.error(message, pos))?;
+        if let (true, Some((_, Some((pos, _))))) = (triples_only, located) {
+            return Err(self.error("unexpected graph name".to_string(), pos));
+        }
+        Ok(located)
+    }
+
+    /// Build the quad located by [`locate`](#method.locate), using `term` to build each term.
+    fn build<T, F>(
+        &self,
+        ([s, p, o], g): Located,
+        make_term: F,
+    ) -> Result<TupleQuad<T>, NQuadsError>
+    where
+        F: Fn(&Span) -> Result<T, TermError>,
+    {
+        let term = |(pos, span): &(usize, Span)| {
+            make_term(span).map_err(|err| self.error(err.to_string(), *pos))
+        };
+        let quad = (
+            [term(&s), term(&p), term(&o)],
+            g.as_ref().map(term).transpose(),
+        );
+        match quad {
+            ([Ok(s), Ok(p), Ok(o)], Ok(g)) => Ok(([s, p, o], g)),
+            ([Err(err), _, _], _)
+            | ([_, Err(err), _], _)
+            | ([_, _, Err(err)], _)
+            | (_, Err(err)) => Err(err),
+        }
+    }
+}
+
+/// Parse the N-Quads in `txt`, without copying term data whenever possible.
+///
+/// Unlike the quads produced by [`NQuadsParser`](struct.NQuadsParser.html),
+/// the quads produced by the returned source borrow their term data directly from `txt`
+/// (except for terms containing escape sequences, whose unescaped data is owned),
+/// so they can outlive the source, and be collected without any further copy.
+pub fn parse_borrowed(txt: &str) -> BorrowedQuads<'_> {
+    BorrowedQuads::new(txt, false)
+}
+
+/// Parse the N-Quads in `data`, without copying term data whenever possible.
+///
+/// This is the same as [`parse_borrowed`](fn.parse_borrowed.html),
+/// for data that is not known to be valid UTF-8,
+/// e.g. the content of a memory-mapped file.
+/// Invalid UTF-8 is reported as an I/O error, after the quads of the preceding lines.
+pub fn parse_bytes(data: &[u8]) -> BorrowedQuads<'_> {
+    BorrowedQuads::from_bytes(data, false)
+}
+
+/// A quad produced by [`BorrowedQuads`](struct.BorrowedQuads.html).
+pub type BorrowedQuad<'a> = TupleQuad<MownTerm<'a>>;
+
+/// The quad source returned by [`parse_borrowed`](fn.parse_borrowed.html)
+/// and [`parse_bytes`](fn.parse_bytes.html).
+///
+/// It is an iterator of quads, hence a [`QuadSource`](../../quad/stream/trait.QuadSource.html).
+/// Syntax errors are reported once per invalid line,
+/// after which parsing resumes at the next line.
+pub struct BorrowedQuads<'a> {
+    /// The data not parsed yet.
+    rest: &'a str,
+    /// An error to report once `rest` is consumed.
+    error: Option<NQuadsError>,
+    /// Unescaped term data, when the line contains escape sequences.
+    scratch: String,
+    line_number: usize,
+    /// The byte offset of `rest` in the data.
+    offset: usize,
+    triples_only: bool,
+}
+
+impl<'a> BorrowedQuads<'a> {
+    pub(crate) fn new(txt: &'a str, triples_only: bool) -> Self {
+        BorrowedQuads {
+            rest: txt,
+            error: None,
+            scratch: String::new(),
+            line_number: 0,
+            offset: 0,
+            triples_only,
+        }
+    }
+
+    pub(crate) fn from_bytes(data: &'a [u8], triples_only: bool) -> Self {
+        match std::str::from_utf8(data) {
+            Ok(txt) => Self::new(txt, triples_only),
+            Err(err) => {
+                // parse the lines before the one containing the invalid data
+                let valid = &data[..err.valid_up_to()];
+                let end = memrchr(b'\n', valid).map_or(0, |i| i + 1);
+                let line = memchr_iter(b'\n', &valid[..end]).count() + 1;
+                let txt = std::str::from_utf8(&data[..end]).unwrap();
+                let mut quads = Self::new(txt, triples_only);
+                quads.error = Some(NQuadsError::Io {
+                    source: io::Error::new(io::ErrorKind::InvalidData, err),
+                    line,
+                });
+                quads
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for BorrowedQuads<'a> {
+    type Item = Result<BorrowedQuad<'a>, NQuadsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.rest.is_empty() {
+            let len = memchr(b'\n', self.rest.as_bytes()).map_or(self.rest.len(), |i| i + 1);
+            let (line, rest) = self.rest.split_at(len);
+            self.rest = rest;
+            self.line_number += 1;
+            let line_offset = self.offset;
+            self.offset += len;
+            let txt = line.trim_end_matches(&['\n', '\r'][..]);
+            self.scratch.clear();
+            let res = parse_line_mown(
+                txt,
+                self.line_number,
+                line_offset,
+                &mut self.scratch,
+                self.triples_only,
+            );
+            match res {
+                Ok(None) => continue,
+                Ok(Some(quad)) => return Some(Ok(quad)),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.error.take().map(Err)
+    }
+}
+
+/// Errors raised by the [`NQuadsParser`](struct.NQuadsParser.html).
+#[derive(Debug, Error)]
+pub enum NQuadsError {
+    /// The data could not be read (or was not valid UTF-8).
+    #[error("I/O error at line {line}: {source}")]
+    Io {
+        /// The underlying error.
+        source: io::Error,
+        /// The line where the error occurred (starting at 1).
+        line: usize,
+    },
+    /// The data is not valid N-Quads.
+    #[error("Invalid N-Quads at line {line}, column {column}: {message}")]
+    Syntax {
+        /// A description of the error.
+        message: String,
+        /// The line where the error occurred (starting at 1).
+        line: usize,
+        /// The column where the error occurred (starting at 1).
+        column: usize,
+        /// The byte offset where the error occurred (starting at 0).
+        byte_offset: usize,
+        /// The offending token (empty at the end of a line).
+        token: String,
+    },
+}
+
+impl RecoverableError for NQuadsError {
+    fn is_recoverable(&self) -> bool {
+        matches!(self, NQuadsError::Syntax { .. })
+    }
+}
+
+impl WithLocation for NQuadsError {
+    fn location(&self) -> Location {
+        match self {
+            NQuadsError::Io { line, .. } => Location::from_lico(*line, 1),
+            NQuadsError::Syntax { line, column, .. } => Location::from_lico(*line, *column),
+        }
+    }
+
+    fn byte_offset(&self) -> Option<usize> {
+        match self {
+            NQuadsError::Io { .. } => None,
+            NQuadsError::Syntax { byte_offset, .. } => Some(*byte_offset),
+        }
+    }
+
+    fn token(&self) -> Option<&str> {
+        match self {
+            NQuadsError::Io { .. } => None,
+            NQuadsError::Syntax { token, .. } => Some(token),
+        }
+    }
+}
+
+/// A piece of text, either in the line being parsed (when it contains no escape sequence)
+/// or in the scratch buffer (when it had to be unescaped).
+#[derive(Clone, Copy, Debug)]
+enum Text {
+    Line(usize, usize),
+    Scratch(usize, usize),
+}
+
+impl Text {
+    fn get<'a>(self, line: &'a str, scratch: &'a str) -> &'a str {
+        match self {
+            Text::Line(start, end) => &line[start..end],
+            Text::Scratch(start, end) => &scratch[start..end],
+        }
+    }
+
+    /// Same as [`get`](#method.get), but only borrowing from `line`.
+    fn mown<'a>(self, line: &'a str, scratch: &str) -> MownStr<'a> {
+        match self {
+            Text::Line(start, end) => line[start..end].into(),
+            Text::Scratch(start, end) => scratch[start..end].to_string().into(),
+        }
+    }
+}
+
+/// The location of a term in the line being parsed.
+#[derive(Clone, Copy, Debug)]
+enum Span {
+    Iri(Text),
+    BlankNode(usize, usize),
+    Literal(Text),
+    LiteralLang(Text, usize, usize),
+    LiteralDt(Text, Text),
+}
+
+impl Span {
+    fn term<'a>(&self, line: &'a str, scratch: &'a str) -> Result<RefTerm<'a>, TermError> {
+        match *self {
+            Span::Iri(iri) => RefTerm::new_iri(iri.get(line, scratch)),
+            Span::BlankNode(start, end) => RefTerm::new_bnode(&line[start..end]),
+            Span::Literal(value) => RefTerm::new_literal_dt(value.get(line, scratch), xsd::string),
+            Span::LiteralLang(value, start, end) => {
+                RefTerm::new_literal_lang(value.get(line, scratch), &line[start..end])
+            }
+            Span::LiteralDt(value, datatype) => RefTerm::new_literal_dt(
+                value.get(line, scratch),
+                RefTerm::new_iri(datatype.get(line, scratch))?,
+            ),
+        }
+    }
+
+    /// Same as [`term`](#method.term), but only borrowing from `line`.
+    fn mown_term<'a>(&self, line: &'a str, scratch: &str) -> Result<MownTerm<'a>, TermError> {
+        match *self {
+            Span::Iri(iri) => MownTerm::new_iri(iri.mown(line, scratch)),
+            Span::BlankNode(start, end) => MownTerm::new_bnode(&line[start..end]),
+            Span::Literal(value) => {
+                MownTerm::new_literal_dt(value.mown(line, scratch), xsd::string)
+            }
+            Span::LiteralLang(value, start, end) => {
+                MownTerm::new_literal_lang(value.mown(line, scratch), &line[start..end])
+            }
+            Span::LiteralDt(value, datatype) => MownTerm::new_literal_dt(
+                value.mown(line, scratch),
+                MownTerm::new_iri(datatype.mown(line, scratch))?,
+            ),
+        }
+    }
+}
+
+/// An error message, with the byte offset where it occurred in the line.
+type PResult<T> = Result<T, (String, usize)>;
+
+/// The terms of a quad, located in the line being parsed, with their byte offset in the line.
+type Located = ([(usize, Span); 3], Option<(usize, Span)>);
+
+/// The token starting at byte offset `pos` of `txt`,
+/// i.e. the character at that offset and the non-whitespace characters following it
+/// (at most 32 characters).
+fn token_at(txt: &str, pos: usize) -> &str {
+    let rest = &txt[pos..];
+    let mut end = 0;
+    for (n, (i, c)) in rest.char_indices().enumerate() {
+        if n == 32 || (n > 0 && c.is_whitespace()) {
+            break;
+        }
+        end = i + c.len_utf8();
+    }
+    &rest[..end]
+}
+
+/// Whether `iri` starts with a scheme (`ALPHA *( ALPHA / DIGIT / "+" / "-" / "." ) ":"`),
+/// as required for the IRIs of N-Triples and N-Quads.
+fn has_scheme(iri: &str) -> bool {
+    match iri.find(':') {
+        Some(end) => {
+            let scheme = &iri.as_bytes()[..end];
+            matches!(scheme.first(), Some(b) if b.is_ascii_alphabetic())
+                && scheme
+                    .iter()
+                    .all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(b))
+        }
+        None => false,
+    }
+}
+
+/// A parser for a single line.
+///
+/// It does not build terms, but locates them in the line (or in the scratch buffer),
+/// so that the quad can borrow its data from the buffers owned by the source.
+struct LineParser<'a> {
+    txt: &'a str,
+    pos: usize,
+    scratch: &'a mut String,
+}
+
+impl<'a> LineParser<'a> {
+    /// Locate the terms of the quad in this line, if any,
+    /// together with their byte offset in the line.
+    fn parse(&mut self) -> PResult<Option<Located>> {
+        self.skip_whitespace();
+        if self.at_end_of_statement() {
+            return Ok(None);
+        }
+        let s_pos = self.pos;
+        let s = match self.peek() {
+            Some(b'<') => Span::Iri(self.iri()?),
+            Some(b'_') => self.blank_node()?,
+            _ => return self.error("expected IRI or blank node"),
+        };
+        self.skip_whitespace();
+        let p_pos = self.pos;
+        let p = match self.peek() {
+            Some(b'<') => Span::Iri(self.iri()?),
+            _ => return self.error("expected IRI"),
+        };
+        self.skip_whitespace();
+        let o_pos = self.pos;
+        let o = match self.peek() {
+            Some(b'<') => Span::Iri(self.iri()?),
+            Some(b'_') => self.blank_node()?,
+            Some(b'"') => self.literal()?,
+            _ => return self.error("expected IRI, blank node or literal"),
+        };
+        self.skip_whitespace();
+        let g_pos = self.pos;
+        let g = match self.peek() {
+            Some(b'<') => Some((g_pos, Span::Iri(self.iri()?))),
+            Some(b'_') => Some((g_pos, self.blank_node()?)),
+            _ => None,
+        };
+        self.skip_whitespace();
+        if self.peek() != Some(b'.') {
+            return self.error("expected '.'");
+        }
+        self.pos += 1;
+        self.skip_whitespace();
+        if !self.at_end_of_statement() {
+            return self.error("unexpected data after '.'");
+        }
+        Ok(Some(([(s_pos, s), (p_pos, p), (o_pos, o)], g)))
+    }
+
+    fn error<T>(&self, message: &str) -> PResult<T> {
+        Err((message.to_string(), self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.txt.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end_of_statement(&self) -> bool {
+        matches!(self.peek(), None | Some(b'#'))
+    }
+
+    /// Read an IRI, which must be absolute (i.e. start with a scheme).
+    fn iri(&mut self) -> PResult<Text> {
+        let start = self.pos;
+        self.pos += 1; // '<'
+        let iri = self.until(b'>', false, |b| b <= b' ' || b"<\"{}|^`".contains(&b))?;
+        if !has_scheme(iri.get(self.txt, self.scratch)) {
+            return Err(("expected absolute IRI".to_string(), start));
+        }
+        Ok(iri)
+    }
+
+    fn blank_node(&mut self) -> PResult<Span> {
+        if !self.txt[self.pos..].starts_with("_:") {
+            return self.error("expected '_:'");
+        }
+        self.pos += 2;
+        let start = self.pos;
+        let bytes = self.txt.as_bytes();
+        while let Some(b) = bytes.get(self.pos) {
+            if b.is_ascii_whitespace() || b"<\"#".contains(b) {
+                break;
+            }
+            self.pos += 1;
+        }
+        // a blank node label can not end with '.', which is then the end of the statement
+        while self.pos > start && bytes[self.pos - 1] == b'.' {
+            self.pos -= 1;
+        }
+        if self.pos == start {
+            return self.error("empty blank node label");
+        }
+        Ok(Span::BlankNode(start, self.pos))
+    }
+
+    fn literal(&mut self) -> PResult<Span> {
+        self.pos += 1; // '"'
+        let value = self.until(b'"', true, |b| b == b'\n' || b == b'\r')?;
+        match self.peek() {
+            Some(b'@') => {
+                self.pos += 1;
+                let start = self.pos;
+                while let Some(b) = self.peek() {
+                    if !(b.is_ascii_alphanumeric() || b == b'-') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                Ok(Span::LiteralLang(value, start, self.pos))
+            }
+            Some(b'^') => {
+                if !self.txt[self.pos..].starts_with("^^<") {
+                    return self.error("expected '^^<'");
+                }
+                self.pos += 2;
+                Ok(Span::LiteralDt(value, self.iri()?))
+            }
+            _ => Ok(Span::Literal(value)),
+        }
+    }
+
+    /// Read text until the `end` delimiter (excluded), which is consumed.
+    ///
+    /// The delimiter and escape sequences are searched with `memchr`
+    /// (vectorized if the `memchr` feature is enabled),
+    /// rather than one character at a time, as this is where most of the parsing time is spent.
+    /// Escape sequences are decoded in the scratch buffer;
+    /// ECHARs (`\n`, `\"`...) are only allowed if `echar` is true.
+    fn until<F>(&mut self, end: u8, echar: bool, forbidden: F) -> PResult<Text>
+    where
+        F: Fn(u8) -> bool,
+    {
+        let txt = self.txt;
+        let bytes = txt.as_bytes();
+        let start = self.pos;
+        // the start of the unescaped text in the scratch buffer, if any
+        let mut unescaped: Option<usize> = None;
+        // the start of the text not yet copied to the scratch buffer
+        let mut chunk = start;
+        loop {
+            // jump to the next delimiter or escape sequence,
+            // then check the bytes in between
+            let next = memchr2(end, b'\\', &bytes[self.pos..]).map(|i| self.pos + i);
+            let stop = next.unwrap_or(bytes.len());
+            if let Some(i) = bytes[self.pos..stop].iter().position(|b| forbidden(*b)) {
+                self.pos += i;
+                return self.error("invalid character");
+            }
+            self.pos = stop;
+            match next.map(|i| bytes[i]) {
+                None => return self.error("unexpected end of line"),
+                Some(b'\\') => {
+                    if unescaped.is_none() {
+                        unescaped = Some(self.scratch.len());
+                    }
+                    self.scratch.push_str(&txt[chunk..self.pos]);
+                    let c = self.escape(echar)?;
+                    self.scratch.push(c);
+                    chunk = self.pos;
+                }
+                Some(_) => {
+                    let text = match unescaped {
+                        None => Text::Line(start, self.pos),
+                        Some(scratch_start) => {
+                            self.scratch.push_str(&txt[chunk..self.pos]);
+                            Text::Scratch(scratch_start, self.scratch.len())
+                        }
+                    };
+                    self.pos += 1;
+                    return Ok(text);
+                }
+            }
+        }
+    }
+
+    /// Decode the escape sequence at the current position.
+    fn escape(&mut self, echar: bool) -> PResult<char> {
+        let c = match self.txt.as_bytes().get(self.pos + 1) {
+            Some(b'u') => return self.hex(4),
+            Some(b'U') => return self.hex(8),
+            Some(b't') if echar => '\t',
+            Some(b'b') if echar => '\u{8}',
+            Some(b'n') if echar => '\n',
+            Some(b'r') if echar => '\r',
+            Some(b'f') if echar => '\u{c}',
+            Some(b'"') if echar => '"',
+            Some(b'\'') if echar => '\'',
+            Some(b'\\') if echar => '\\',
+            _ => return self.error("invalid escape sequence"),
+        };
+        self.pos += 2;
+        Ok(c)
+    }
+
+    /// Decode a `\u` or `\U` escape sequence with `len` hexadecimal digits.
+    fn hex(&mut self, len: usize) -> PResult<char> {
+        let digits = self.txt.get(self.pos + 2..self.pos + 2 + len);
+        let c = digits
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .and_then(std::char::from_u32);
+        match c {
+            Some(c) => {
+                self.pos += 2 + len;
+                Ok(c)
+            }
+            None => self.error("invalid escape sequence"),
+        }
+    }
+}
+
+/// Plain implementations of the functions of the `memchr` crate used above,
+/// for when the `memchr` feature is not enabled.
+#[cfg(not(feature = "memchr"))]
+mod fallback {
+    pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+        haystack.iter().position(|b| *b == needle)
+    }
+
+    pub fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
+        haystack.iter().position(|b| *b == needle1 || *b == needle2)
+    }
+
+    pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+        haystack.iter().rposition(|b| *b == needle)
+    }
+
+    pub fn memchr_iter(needle: u8, haystack: &[u8]) -> impl Iterator<Item = usize> + '_ {
+        haystack
+            .iter()
+            .enumerate()
+            .filter(move |(_, b)| **b == needle)
+            .map(|(i, _)| i)
+    }
+}
+#[cfg(not(feature = "memchr"))]
+use fallback::{memchr, memchr2, memchr_iter, memrchr};
+
 // ---------------------------------------------------------------------------------
 //                                      tests
 // ---------------------------------------------------------------------------------
@@ -31,8 +784,10 @@ mod test {
     use crate::dataset::Dataset;
     use crate::quad::stream::QuadSource;
     use sophia_api::ns::{rdf, xsd};
+    use sophia_api::quad::Quad;
     use sophia_api::term::matcher::ANY;
-    use sophia_term::StaticTerm;
+    use sophia_api::term::TTerm;
+    use sophia_term::{StaticTerm, Term};
 
     #[test]
     fn test_simple_nq_string() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -75,4 +830,191 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn literals_and_escapes() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let nq = concat!(
+            "# a comment\n",
+            "\n",
+            "<tag:s> <tag:p> \"chat\"@fr <tag:g> . # another comment\n",
+            "<tag:s> <tag:p> \"42\"^^<http://www.w3.org/2001/XMLSchema#integer> .\r\n",
+            "<tag:s> <tag:\\u00E9> \"a\\tb\\\"c\\U0001F600\" .\n",
+            "_:b.1 <tag:p> _:b.2.",
+        );
+        let mut values = vec![];
+        parse_str(nq).for_each_quad(|q| {
+            values.push((
+                q.p().value().to_string(),
+                q.o().value().to_string(),
+                q.o().language().map(str::to_string),
+                q.o().datatype().map(|dt| dt.value().to_string()),
+                q.g().map(|g| g.value().to_string()),
+            ))
+        })?;
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0].1, "chat");
+        assert_eq!(values[0].2.as_deref(), Some("fr"));
+        assert_eq!(values[0].4.as_deref(), Some("tag:g"));
+        assert_eq!(values[1].3.as_deref(), Some(&*xsd::integer.value()));
+        assert_eq!(values[2].0, "tag:\u{e9}");
+        assert_eq!(values[2].1, "a\tb\"c\u{1F600}");
+        assert_eq!(values[2].3.as_deref(), Some(&*xsd::string.value()));
+        assert_eq!(values[3].0, "tag:p");
+        assert_eq!(values[3].1, "b.2");
+        Ok(())
+    }
+
+    #[test]
+    fn errors_do_not_stop_parsing() {
+        let nq = concat!(
+            "<tag:s> <tag:p> <tag:o> .\n",
+            "<tag:s> <tag:p> \"unterminated .\n",
+            "<tag:s> \"not a predicate\" <tag:o> .\n",
+            "<tag:s> <tag:p> <tag:o> <tag:g> <tag:h> .\n",
+            "<tag:s> <tag:p> \"bad\\escape\" .\n",
+            "<tag:s> <tag:p> <tag:o> .\n",
+        );
+        let mut source = parse_str(nq);
+        let mut results = vec![];
+        loop {
+            match source.for_some_quad(&mut |_| ()) {
+                Ok(true) => results.push(Ok(())),
+                Ok(false) => break,
+                Err(err) => results.push(Err(err.location().to_string())),
+            }
+        }
+        assert_eq!(
+            results,
+            vec![
+                Ok(()),
+                Err("2:32".to_string()),
+                Err("3:9".to_string()),
+                Err("4:33".to_string()),
+                Err("5:21".to_string()),
+                Ok(()),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_details() {
+        let nq = concat!(
+            "<tag:s> <tag:p> <tag:o> .\n",
+            "<tag:s> <tag:p> <tag:o> <tag:g> <tag:h> .\n",
+            "<tag:s> <tag:p> \"bad\\escape\" .\n",
+            "<tag:s> <tag:p> \"unterminated .\n",
+            "<tag:s> <tag:p> <tag:{o}> .\n",
+        );
+        let mut source = parse_str(nq);
+        let mut errors = vec![];
+        loop {
+            match source.for_some_quad(&mut |_| ()) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) => {
+                    let loc = err.location();
+                    let pos = (loc.line(), loc.column(), err.byte_offset());
+                    errors.push((pos, err.token().map(str::to_string)));
+                }
+            }
+        }
+        let expected = vec![
+            ((Some(2), Some(33), Some(58)), Some("<tag:h>")),
+            ((Some(3), Some(21), Some(88)), Some("\\escape\"")),
+            ((Some(4), Some(32), Some(130)), Some("")),
+            ((Some(5), Some(22), Some(152)), Some("{o}>")),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(pos, token)| (pos, token.map(str::to_string)))
+            .collect();
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn relative_iris() {
+        let nq = concat!(
+            "<s> <tag:p> <tag:o> .\n",
+            "<tag:s> <tag:p> \"x\"^^<rel> .\n",
+            "<tag:s> <tag:p> <tag:o> <g> .\n",
+            "<tag:s> <tag:p> \"x\"^^<tag:dt> .\n",
+        );
+        let results: Vec<_> = parse_borrowed(nq)
+            .map(|res| {
+                res.map_err(|err| (err.location().to_string(), err.token().map(str::to_string)))
+            })
+            .map(|res| res.map(|_| ()))
+            .collect();
+        let expected = vec![
+            Err(("1:1".to_string(), Some("<s>".to_string()))),
+            Err(("2:22".to_string(), Some("<rel>".to_string()))),
+            Err(("3:25".to_string(), Some("<g>".to_string()))),
+            Ok(()),
+        ];
+        assert_eq!(results, expected);
+        assert!(parse_str(nq).collect_quads::<FastDataset>().is_err());
+    }
+
+    #[test]
+    fn long_terms() {
+        let long = "x".repeat(100);
+        let nq = format!(
+            "<tag:{0}> <tag:p> \"{0}\\\"{0}\\u00e9{0}\" .\n<tag:{0} > <tag:p> <tag:o> .\n",
+            long
+        );
+        let results: Vec<_> = parse_borrowed(&nq).collect();
+        assert_eq!(results.len(), 2);
+        let ([s, _, o], _) = results[0].as_ref().unwrap();
+        assert_eq!(s.value().len(), 104);
+        assert_eq!(&*o.value(), format!("{0}\"{0}\u{e9}{0}", long));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.location().to_string(), "2:106");
+    }
+
+    #[test]
+    fn borrowed() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let nq = concat!(
+            "<tag:s> <tag:p> \"a\\tb\" <tag:g>.\n",
+            "# a comment\n",
+            "_:b <tag:p> \"chat\"@fr .\r\n",
+            "<tag:s> <tag:p> <tag:o>\n",
+        );
+        let quads: Vec<_> = parse_borrowed(nq).collect();
+        assert_eq!(quads.len(), 3);
+        let ([s, _, o], g) = quads[0].as_ref().unwrap();
+        match (s, o) {
+            (Term::Iri(s), Term::Literal(o)) => {
+                assert!(s.ns().is_borrowed());
+                assert!(o.txt().is_owned());
+                assert_eq!(o.txt().as_ref(), "a\tb");
+            }
+            _ => panic!("unexpected terms"),
+        }
+        assert_eq!(g.as_ref().unwrap().value(), "tag:g");
+        assert_eq!(quads[1].as_ref().unwrap().o().language(), Some("fr"));
+        let err = quads[2].as_ref().unwrap_err();
+        assert_eq!(err.location().to_string(), "4:24");
+
+        // same quads as NQuadsParser
+        let valid = &nq[..nq.rfind("<tag:s>").unwrap()];
+        let d1: FastDataset = parse_borrowed(valid).collect_quads()?;
+        let d2: FastDataset = parse_str(valid).collect_quads()?;
+        assert_eq!(d1.quads().count(), 2);
+        assert!(crate::dataset::isomorphic_datasets(&d1, &d2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn bytes() {
+        let nq =
+            b"<tag:s> <tag:p> <tag:o>.\n<tag:s> <tag:p> \"\xff\" .\n<tag:s> <tag:p> <tag:o>.\n";
+        let results: Vec<_> = parse_bytes(nq).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(NQuadsError::Io { line, .. }) => assert_eq!(*line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(parse_bytes(b"<tag:s> <tag:p> <tag:o>.").count(), 1);
+    }
 }
@@ -2,9 +2,12 @@
 //!
 //! [N-Triples]: https://www.w3.org/TR/n-triples/
 
+use crate::parser::lenient::LenientParser;
+use crate::parser::nq::{BorrowedQuads, NQuadsError};
 use crate::parser::rio_common::*;
 use rio_turtle::{NTriplesParser as RioNTParser, TurtleError};
 use sophia_api::parser::TripleParser;
+use sophia_term::MownTerm;
 use std::io::BufRead;
 
 /// N-Triples parser based on RIO.
@@ -18,8 +21,40 @@ impl<B: BufRead> TripleParser<B> for NTriplesParser {
     }
 }
 
+impl NTriplesParser {
+    /// Make this parser lenient:
+    /// malformed lines are skipped, and their errors are passed to `on_error`
+    /// instead of aborting the stream.
+    ///
+    /// See [`lenient`](../lenient/index.html) for more details.
+    pub fn lenient<F>(self, on_error: F) -> LenientParser<Self, F>
+    where
+        F: FnMut(TurtleError) + Clone,
+    {
+        LenientParser::new(self, on_error)
+    }
+}
+
 sophia_api::def_mod_functions_for_bufread_parser!(NTriplesParser, TripleParser);
 
+/// Parse the N-Triples in `txt`, without copying term data whenever possible.
+///
+/// See [`nq::parse_borrowed`](../nq/fn.parse_borrowed.html);
+/// errors are reported as [`NQuadsError`](../nq/enum.NQuadsError.html)s,
+/// and lines with a graph name are rejected.
+pub fn parse_borrowed(txt: &str) -> impl Iterator<Item = Result<[MownTerm<'_>; 3], NQuadsError>> {
+    BorrowedQuads::new(txt, true).map(|res| res.map(|(triple, _)| triple))
+}
+
+/// Parse the N-Triples in `data`, without copying term data whenever possible.
+///
+/// See [`nq::parse_bytes`](../nq/fn.parse_bytes.html);
+/// errors are reported as [`NQuadsError`](../nq/enum.NQuadsError.html)s,
+/// and lines with a graph name are rejected.
+pub fn parse_bytes(data: &[u8]) -> impl Iterator<Item = Result<[MownTerm<'_>; 3], NQuadsError>> {
+    BorrowedQuads::from_bytes(data, true).map(|res| res.map(|(triple, _)| triple))
+}
+
 // ---------------------------------------------------------------------------------
 //                                      tests
 // ---------------------------------------------------------------------------------
@@ -32,6 +67,7 @@ mod test {
     use crate::triple::stream::TripleSource;
     use sophia_api::ns::{rdf, xsd};
     use sophia_api::term::matcher::ANY;
+    use sophia_api::term::TTerm;
     use sophia_term::StaticTerm;
 
     #[test]
@@ -72,4 +108,24 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn borrowed() {
+        let nt = "<tag:s> <tag:p> \"o\" .\n<tag:s> <tag:p> <tag:o> <tag:g> .\n";
+        let mut triples = parse_borrowed(nt);
+        let [s, _, o] = triples.next().unwrap().unwrap();
+        assert_eq!(s.value(), "tag:s");
+        assert_eq!(o.value(), "o");
+        assert!(triples.next().unwrap().is_err());
+        assert!(triples.next().is_none());
+        assert_eq!(parse_bytes(nt.as_bytes()).count(), 2);
+    }
+
+    #[test]
+    fn borrowed_relative_iris() {
+        let nt = "<s> <tag:p> <tag:o> .\n<tag:s> <tag:p> \"o\"^^<dt> .\n";
+        assert!(parse_borrowed(nt).all(|res| res.is_err()));
+        assert!(parse_bytes(nt.as_bytes()).all(|res| res.is_err()));
+        assert_eq!(parse_borrowed(nt).count(), 2);
+    }
 }
@@ -0,0 +1,1214 @@
+//! Parser for the [OWL 2 Functional-Style Syntax] (OFN).
+//!
+//! Ontologies are lowered to RDF according to the [mapping to RDF graphs],
+//! so this parser produces a [`TripleSource`] like any other RDF parser,
+//! and the result can be loaded into any graph.
+//! For example,
+//! ```text
+//! Prefix(:=<http://example.org/>)
+//! Ontology(<http://example.org/pets>
+//!   SubClassOf(:Dog ObjectSomeValuesFrom(:hasOwner :Person))
+//! )
+//! ```
+//! produces the following triples:
+//! ```text
+//! <http://example.org/pets> rdf:type owl:Ontology .
+//! _:ofn-1 rdf:type owl:Restriction .
+//! _:ofn-1 owl:onProperty :hasOwner .
+//! _:ofn-1 owl:someValuesFrom :Person .
+//! :Dog rdfs:subClassOf _:ofn-1 .
+//! ```
+//! Annotated axioms are reified with `owl:Axiom`,
+//! and annotated annotations with `owl:Annotation`.
+//!
+//! The prefixes `rdf:`, `rdfs:`, `xsd:` and `owl:` are predeclared.
+//! Blank nodes created by the parser are labelled `ofn-1`, `ofn-2`...
+//!
+//! NB: the whole document is parsed before any triple is produced.
+//! Entities are not checked against their declarations
+//! (e.g. any IRI is accepted where a class is expected).
+//! Only the ontology document is parsed: imports are not followed.
+//!
+//! [OWL 2 Functional-Style Syntax]: https://www.w3.org/TR/owl2-syntax/
+//! [mapping to RDF graphs]: https://www.w3.org/TR/owl2-mapping-to-rdf/
+//! [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use sophia_api::ns::{owl, rdf, rdfs, xsd};
+use sophia_api::parser::{Location, TripleParser, WithLocation};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::syntax::escape::{is_pn_chars, unescape_local};
+
+/// OWL 2 Functional-Style Syntax parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct OfnParser {}
+
+impl<B: BufRead> TripleParser<B> for OfnParser {
+    type Source = std::vec::IntoIter<Result<[BoxTerm; 3], OfnError>>;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        if let Err(err) = data.read_to_string(&mut txt) {
+            return vec![Err(OfnError::Io(err))].into_iter();
+        }
+        let mut parser = Parser::new(&txt);
+        if let Err(err) = parser.document() {
+            parser.triples.push(Err(err));
+        }
+        parser.triples.into_iter()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(OfnParser, TripleParser);
+
+/// Errors raised by the [`OfnParser`](struct.OfnParser.html).
+#[derive(Debug, Error)]
+pub enum OfnError {
+    /// The data could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data is not a valid OWL 2 Functional-Style Syntax document.
+    #[error("Invalid OWL functional syntax at {line}:{column}: {message}")]
+    Syntax {
+        /// A description of the error.
+        message: String,
+        /// The line of the error (starting at 1).
+        line: usize,
+        /// The column of the error (starting at 1).
+        column: usize,
+        /// The offset of the error from the start of the data, in bytes.
+        byte_offset: usize,
+    },
+}
+
+impl WithLocation for OfnError {
+    fn location(&self) -> Location {
+        match self {
+            OfnError::Io(_) => Location::Unknown,
+            OfnError::Syntax { line, column, .. } => Location::from_lico(*line, *column),
+        }
+    }
+
+    fn byte_offset(&self) -> Option<usize> {
+        match self {
+            OfnError::Io(_) => None,
+            OfnError::Syntax { byte_offset, .. } => Some(*byte_offset),
+        }
+    }
+}
+
+type PResult<T> = Result<T, OfnError>;
+
+/// An annotation, possibly annotated itself.
+struct Annotation {
+    property: BoxTerm,
+    value: BoxTerm,
+    annotations: Vec<Annotation>,
+}
+
+/// What the annotations of an axiom apply to.
+enum Main {
+    /// The main triples of the axiom, which are reified to be annotated.
+    Triples(Vec<[BoxTerm; 3]>),
+    /// A blank node standing for the whole axiom, which is annotated directly.
+    Node(BoxTerm),
+}
+
+impl Main {
+    fn triple<P: TTerm + ?Sized>(s: BoxTerm, p: &P, o: BoxTerm) -> Self {
+        Main::Triples(vec![[s, BoxTerm::copy(p), o]])
+    }
+
+    /// A triple for each pair of consecutive items.
+    fn chain<P: TTerm + ?Sized>(items: &[BoxTerm], p: &P) -> Self {
+        let p = BoxTerm::copy(p);
+        Main::Triples(
+            items
+                .windows(2)
+                .map(|pair| [pair[0].clone(), p.clone(), pair[1].clone()])
+                .collect(),
+        )
+    }
+}
+
+/// A recursive descent parser, producing all its triples in memory.
+struct Parser<'a> {
+    txt: &'a str,
+    pos: usize,
+    prefixes: HashMap<String, String>,
+    bnodes: usize,
+    triples: Vec<PResult<[BoxTerm; 3]>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(txt: &'a str) -> Self {
+        let prefixes = [
+            ("rdf", rdf::PREFIX),
+            ("rdfs", rdfs::PREFIX),
+            ("xsd", xsd::PREFIX),
+            ("owl", owl::PREFIX),
+        ];
+        Parser {
+            txt,
+            pos: 0,
+            prefixes: prefixes
+                .iter()
+                .map(|(prefix, ns)| (prefix.to_string(), ns.to_string()))
+                .collect(),
+            bnodes: 0,
+            triples: vec![],
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+    // document structure
+
+    fn document(&mut self) -> PResult<()> {
+        while self.eat_constructor("Prefix") {
+            self.prefix_decl()?;
+        }
+        if !self.eat_constructor("Ontology") {
+            return Err(self.error("expected 'Ontology('"));
+        }
+        self.ontology()?;
+        self.skip_ws();
+        if !self.rest().is_empty() {
+            return Err(self.error("unexpected data after the ontology"));
+        }
+        Ok(())
+    }
+
+    fn prefix_decl(&mut self) -> PResult<()> {
+        self.skip_ws();
+        let start = self.pos;
+        self.eat_while(|c| is_pn_chars(c) || c == '.');
+        let prefix = self.txt[start..self.pos].to_string();
+        if !self.eat(":") {
+            return Err(self.error("expected ':'"));
+        }
+        self.skip_ws();
+        if !self.eat("=") {
+            return Err(self.error("expected '='"));
+        }
+        let ns = self.full_iri()?;
+        self.prefixes.insert(prefix, ns);
+        self.close()
+    }
+
+    fn ontology(&mut self) -> PResult<()> {
+        let ontology = if self.at_iri() {
+            let ontology = self.iri()?;
+            self.emit(&ontology, &rdf::type_, &owl::Ontology);
+            if self.at_iri() {
+                let version = self.iri()?;
+                self.emit(&ontology, &owl::versionIRI, &version);
+            }
+            ontology
+        } else {
+            let ontology = self.new_bnode();
+            self.emit(&ontology, &rdf::type_, &owl::Ontology);
+            ontology
+        };
+        while self.eat_constructor("Import") {
+            let import = self.iri()?;
+            self.emit(&ontology, &owl::imports, &import);
+            self.close()?;
+        }
+        let annotations = self.annotations()?;
+        self.annotate(&ontology, &annotations);
+        while !self.at_close() {
+            self.axiom()?;
+        }
+        self.close()
+    }
+
+    /// Parse zero or more annotations.
+    fn annotations(&mut self) -> PResult<Vec<Annotation>> {
+        let mut annotations = vec![];
+        while self.eat_constructor("Annotation") {
+            let nested = self.annotations()?;
+            let property = self.iri()?;
+            let value = self.annotation_value()?;
+            self.close()?;
+            annotations.push(Annotation {
+                property,
+                value,
+                annotations: nested,
+            });
+        }
+        Ok(annotations)
+    }
+
+    fn axiom(&mut self) -> PResult<()> {
+        self.skip_ws();
+        let start = self.pos;
+        let keyword = match self.constructor() {
+            Some(keyword) => keyword,
+            None => return Err(self.error("expected an axiom")),
+        };
+        let annotations = self.annotations()?;
+        let main = match keyword {
+            "Declaration" => self.declaration()?,
+            // class expression axioms
+            "SubClassOf" => {
+                let sub = self.class_expression()?;
+                let sup = self.class_expression()?;
+                Main::triple(sub, &rdfs::subClassOf, sup)
+            }
+            "EquivalentClasses" => {
+                let classes = self.items(2, Self::class_expression)?;
+                Main::chain(&classes, &owl::equivalentClass)
+            }
+            "DisjointClasses" => {
+                let classes = self.items(2, Self::class_expression)?;
+                self.disjoint(classes, &owl::disjointWith, &owl::AllDisjointClasses)
+            }
+            "DisjointUnion" => {
+                let class = self.iri()?;
+                let classes = self.items(2, Self::class_expression)?;
+                let list = self.list(classes);
+                Main::triple(class, &owl::disjointUnionOf, list)
+            }
+            // object property axioms
+            "SubObjectPropertyOf" => {
+                if self.eat_constructor("ObjectPropertyChain") {
+                    let chain = self.items(2, Self::object_property)?;
+                    let list = self.list(chain);
+                    let sup = self.object_property()?;
+                    Main::triple(sup, &owl::propertyChainAxiom, list)
+                } else {
+                    let sub = self.object_property()?;
+                    let sup = self.object_property()?;
+                    Main::triple(sub, &rdfs::subPropertyOf, sup)
+                }
+            }
+            "EquivalentObjectProperties" => {
+                let properties = self.items(2, Self::object_property)?;
+                Main::chain(&properties, &owl::equivalentProperty)
+            }
+            "DisjointObjectProperties" => {
+                let properties = self.items(2, Self::object_property)?;
+                self.disjoint(
+                    properties,
+                    &owl::propertyDisjointWith,
+                    &owl::AllDisjointProperties,
+                )
+            }
+            "InverseObjectProperties" => {
+                let p1 = self.object_property()?;
+                let p2 = self.object_property()?;
+                Main::triple(p1, &owl::inverseOf, p2)
+            }
+            "ObjectPropertyDomain" => {
+                let property = self.object_property()?;
+                let class = self.class_expression()?;
+                Main::triple(property, &rdfs::domain, class)
+            }
+            "ObjectPropertyRange" => {
+                let property = self.object_property()?;
+                let class = self.class_expression()?;
+                Main::triple(property, &rdfs::range, class)
+            }
+            "FunctionalObjectProperty" => self.characteristic(&owl::FunctionalProperty)?,
+            "InverseFunctionalObjectProperty" => {
+                self.characteristic(&owl::InverseFunctionalProperty)?
+            }
+            "ReflexiveObjectProperty" => self.characteristic(&owl::ReflexiveProperty)?,
+            "IrreflexiveObjectProperty" => self.characteristic(&owl::IrreflexiveProperty)?,
+            "SymmetricObjectProperty" => self.characteristic(&owl::SymmetricProperty)?,
+            "AsymmetricObjectProperty" => self.characteristic(&owl::AsymmetricProperty)?,
+            "TransitiveObjectProperty" => self.characteristic(&owl::TransitiveProperty)?,
+            // data property axioms
+            "SubDataPropertyOf" => {
+                let sub = self.iri()?;
+                let sup = self.iri()?;
+                Main::triple(sub, &rdfs::subPropertyOf, sup)
+            }
+            "EquivalentDataProperties" => {
+                let properties = self.items(2, Self::iri)?;
+                Main::chain(&properties, &owl::equivalentProperty)
+            }
+            "DisjointDataProperties" => {
+                let properties = self.items(2, Self::iri)?;
+                self.disjoint(
+                    properties,
+                    &owl::propertyDisjointWith,
+                    &owl::AllDisjointProperties,
+                )
+            }
+            "DataPropertyDomain" => {
+                let property = self.iri()?;
+                let class = self.class_expression()?;
+                Main::triple(property, &rdfs::domain, class)
+            }
+            "DataPropertyRange" => {
+                let property = self.iri()?;
+                let range = self.data_range()?;
+                Main::triple(property, &rdfs::range, range)
+            }
+            "FunctionalDataProperty" => {
+                let property = self.iri()?;
+                Main::triple(
+                    property,
+                    &rdf::type_,
+                    BoxTerm::copy(&owl::FunctionalProperty),
+                )
+            }
+            "DatatypeDefinition" => {
+                let datatype = self.iri()?;
+                let range = self.data_range()?;
+                Main::triple(datatype, &owl::equivalentClass, range)
+            }
+            "HasKey" => {
+                let class = self.class_expression()?;
+                self.open()?;
+                let mut keys = self.items(0, Self::object_property)?;
+                self.close()?;
+                self.open()?;
+                keys.extend(self.items(0, Self::iri)?);
+                self.close()?;
+                let list = self.list(keys);
+                Main::triple(class, &owl::hasKey, list)
+            }
+            // assertions
+            "SameIndividual" => {
+                let individuals = self.items(2, Self::individual)?;
+                Main::chain(&individuals, &owl::sameAs)
+            }
+            "DifferentIndividuals" => {
+                let individuals = self.items(2, Self::individual)?;
+                self.disjoint(individuals, &owl::differentFrom, &owl::AllDifferent)
+            }
+            "ClassAssertion" => {
+                let class = self.class_expression()?;
+                let individual = self.individual()?;
+                Main::triple(individual, &rdf::type_, class)
+            }
+            "ObjectPropertyAssertion" => {
+                let (property, inverse) = self.object_property_expression()?;
+                let source = self.individual()?;
+                let target = self.individual()?;
+                if inverse {
+                    Main::triple(target, &property, source)
+                } else {
+                    Main::triple(source, &property, target)
+                }
+            }
+            "NegativeObjectPropertyAssertion" => {
+                let property = self.object_property()?;
+                let source = self.individual()?;
+                let target = self.individual()?;
+                self.negative_assertion(property, source, &owl::targetIndividual, target)
+            }
+            "DataPropertyAssertion" => {
+                let property = self.iri()?;
+                let source = self.individual()?;
+                let target = self.literal()?;
+                Main::triple(source, &property, target)
+            }
+            "NegativeDataPropertyAssertion" => {
+                let property = self.iri()?;
+                let source = self.individual()?;
+                let target = self.literal()?;
+                self.negative_assertion(property, source, &owl::targetValue, target)
+            }
+            // annotation axioms
+            "AnnotationAssertion" => {
+                let property = self.iri()?;
+                let subject = self.individual()?;
+                let value = self.annotation_value()?;
+                Main::triple(subject, &property, value)
+            }
+            "SubAnnotationPropertyOf" => {
+                let sub = self.iri()?;
+                let sup = self.iri()?;
+                Main::triple(sub, &rdfs::subPropertyOf, sup)
+            }
+            "AnnotationPropertyDomain" => {
+                let property = self.iri()?;
+                let domain = self.iri()?;
+                Main::triple(property, &rdfs::domain, domain)
+            }
+            "AnnotationPropertyRange" => {
+                let property = self.iri()?;
+                let range = self.iri()?;
+                Main::triple(property, &rdfs::range, range)
+            }
+            _ => {
+                return Err(self.error_at(start, &format!("unknown axiom '{}'", keyword)));
+            }
+        };
+        self.close()?;
+        match main {
+            Main::Triples(triples) => {
+                for [s, p, o] in triples {
+                    self.emit(&s, &p, &o);
+                    if !annotations.is_empty() {
+                        let axiom = self.reify(&s, &p, &o, &owl::Axiom);
+                        self.annotate(&axiom, &annotations);
+                    }
+                }
+            }
+            Main::Node(node) => self.annotate(&node, &annotations),
+        }
+        Ok(())
+    }
+
+    fn declaration(&mut self) -> PResult<Main> {
+        self.skip_ws();
+        let start = self.pos;
+        let kind = match self.constructor() {
+            Some("Class") => owl::Class,
+            Some("Datatype") => rdfs::Datatype,
+            Some("ObjectProperty") => owl::ObjectProperty,
+            Some("DataProperty") => owl::DatatypeProperty,
+            Some("AnnotationProperty") => owl::AnnotationProperty,
+            Some("NamedIndividual") => owl::NamedIndividual,
+            _ => return Err(self.error_at(start, "expected an entity")),
+        };
+        let entity = self.iri()?;
+        self.close()?;
+        Ok(Main::triple(entity, &rdf::type_, BoxTerm::copy(&kind)))
+    }
+
+    // ------------------------------------------------------------------------------
+    // expressions
+
+    fn class_expression(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        let start = self.pos;
+        let keyword = match self.constructor() {
+            Some(keyword) => keyword,
+            None => return self.iri(),
+        };
+        let node = self.new_bnode();
+        match keyword {
+            "ObjectIntersectionOf" | "ObjectUnionOf" => {
+                let classes = self.items(2, Self::class_expression)?;
+                let list = self.list(classes);
+                self.emit(&node, &rdf::type_, &owl::Class);
+                if keyword == "ObjectIntersectionOf" {
+                    self.emit(&node, &owl::intersectionOf, &list);
+                } else {
+                    self.emit(&node, &owl::unionOf, &list);
+                }
+            }
+            "ObjectComplementOf" => {
+                let class = self.class_expression()?;
+                self.emit(&node, &rdf::type_, &owl::Class);
+                self.emit(&node, &owl::complementOf, &class);
+            }
+            "ObjectOneOf" => {
+                let individuals = self.items(1, Self::individual)?;
+                let list = self.list(individuals);
+                self.emit(&node, &rdf::type_, &owl::Class);
+                self.emit(&node, &owl::oneOf, &list);
+            }
+            "ObjectSomeValuesFrom" | "ObjectAllValuesFrom" => {
+                let property = self.object_property()?;
+                let class = self.class_expression()?;
+                self.restriction(&node, &property);
+                if keyword == "ObjectSomeValuesFrom" {
+                    self.emit(&node, &owl::someValuesFrom, &class);
+                } else {
+                    self.emit(&node, &owl::allValuesFrom, &class);
+                }
+            }
+            "ObjectHasValue" => {
+                let property = self.object_property()?;
+                let individual = self.individual()?;
+                self.restriction(&node, &property);
+                self.emit(&node, &owl::hasValue, &individual);
+            }
+            "ObjectHasSelf" => {
+                let property = self.object_property()?;
+                self.restriction(&node, &property);
+                let value = BoxTerm::new_literal_dt_unchecked("true", xsd::boolean);
+                self.emit(&node, &owl::hasSelf, &value);
+            }
+            "ObjectMinCardinality" | "ObjectMaxCardinality" | "ObjectExactCardinality" => {
+                let cardinality = self.cardinality()?;
+                let property = self.object_property()?;
+                let class = if self.at_close() {
+                    None
+                } else {
+                    Some(self.class_expression()?)
+                };
+                self.restriction(&node, &property);
+                self.cardinality_restriction(&node, keyword, cardinality, &owl::onClass, class);
+            }
+            "DataSomeValuesFrom" | "DataAllValuesFrom" => {
+                // the last item is a data range, the others are data properties
+                let mut items = self.items(2, Self::data_range)?;
+                let range = items.pop().unwrap();
+                self.emit(&node, &rdf::type_, &owl::Restriction);
+                if items.len() == 1 {
+                    self.emit(&node, &owl::onProperty, &items[0]);
+                } else {
+                    let list = self.list(items);
+                    self.emit(&node, &owl::onProperties, &list);
+                }
+                if keyword == "DataSomeValuesFrom" {
+                    self.emit(&node, &owl::someValuesFrom, &range);
+                } else {
+                    self.emit(&node, &owl::allValuesFrom, &range);
+                }
+            }
+            "DataHasValue" => {
+                let property = self.iri()?;
+                let value = self.literal()?;
+                self.restriction(&node, &property);
+                self.emit(&node, &owl::hasValue, &value);
+            }
+            "DataMinCardinality" | "DataMaxCardinality" | "DataExactCardinality" => {
+                let cardinality = self.cardinality()?;
+                let property = self.iri()?;
+                let range = if self.at_close() {
+                    None
+                } else {
+                    Some(self.data_range()?)
+                };
+                self.restriction(&node, &property);
+                self.cardinality_restriction(&node, keyword, cardinality, &owl::onDataRange, range);
+            }
+            _ => {
+                let message = format!("unknown class expression '{}'", keyword);
+                return Err(self.error_at(start, &message));
+            }
+        }
+        self.close()?;
+        Ok(node)
+    }
+
+    fn data_range(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        let start = self.pos;
+        let keyword = match self.constructor() {
+            Some(keyword) => keyword,
+            None => return self.iri(),
+        };
+        let node = self.new_bnode();
+        match keyword {
+            "DataIntersectionOf" | "DataUnionOf" => {
+                let ranges = self.items(2, Self::data_range)?;
+                let list = self.list(ranges);
+                self.emit(&node, &rdf::type_, &rdfs::Datatype);
+                if keyword == "DataIntersectionOf" {
+                    self.emit(&node, &owl::intersectionOf, &list);
+                } else {
+                    self.emit(&node, &owl::unionOf, &list);
+                }
+            }
+            "DataComplementOf" => {
+                let range = self.data_range()?;
+                self.emit(&node, &rdf::type_, &rdfs::Datatype);
+                self.emit(&node, &owl::datatypeComplementOf, &range);
+            }
+            "DataOneOf" => {
+                let literals = self.items(1, Self::literal)?;
+                let list = self.list(literals);
+                self.emit(&node, &rdf::type_, &rdfs::Datatype);
+                self.emit(&node, &owl::oneOf, &list);
+            }
+            "DatatypeRestriction" => {
+                let datatype = self.iri()?;
+                let mut restrictions = vec![];
+                while restrictions.is_empty() || !self.at_close() {
+                    let facet = self.iri()?;
+                    let value = self.literal()?;
+                    let restriction = self.new_bnode();
+                    self.emit(&restriction, &facet, &value);
+                    restrictions.push(restriction);
+                }
+                let list = self.list(restrictions);
+                self.emit(&node, &rdf::type_, &rdfs::Datatype);
+                self.emit(&node, &owl::onDatatype, &datatype);
+                self.emit(&node, &owl::withRestrictions, &list);
+            }
+            _ => {
+                let message = format!("unknown data range '{}'", keyword);
+                return Err(self.error_at(start, &message));
+            }
+        }
+        self.close()?;
+        Ok(node)
+    }
+
+    /// Parse an object property expression,
+    /// and return the property with a flag indicating whether it is inverted.
+    fn object_property_expression(&mut self) -> PResult<(BoxTerm, bool)> {
+        if self.eat_constructor("ObjectInverseOf") {
+            let property = self.iri()?;
+            self.close()?;
+            Ok((property, true))
+        } else {
+            Ok((self.iri()?, false))
+        }
+    }
+
+    /// Parse an object property expression,
+    /// represented by a blank node if it is inverted.
+    fn object_property(&mut self) -> PResult<BoxTerm> {
+        let (property, inverse) = self.object_property_expression()?;
+        if !inverse {
+            return Ok(property);
+        }
+        let node = self.new_bnode();
+        self.emit(&node, &owl::inverseOf, &property);
+        Ok(node)
+    }
+
+    fn characteristic<T: TTerm + ?Sized>(&mut self, kind: &T) -> PResult<Main> {
+        let property = self.object_property()?;
+        Ok(Main::triple(property, &rdf::type_, BoxTerm::copy(kind)))
+    }
+
+    /// A pairwise triple for two items, or a blank node of type `all` otherwise.
+    fn disjoint<P, A>(&mut self, items: Vec<BoxTerm>, pairwise: &P, all: &A) -> Main
+    where
+        P: TTerm + ?Sized,
+        A: TTerm + ?Sized,
+    {
+        if items.len() == 2 {
+            return Main::chain(&items, pairwise);
+        }
+        let node = self.new_bnode();
+        let list = self.list(items);
+        self.emit(&node, &rdf::type_, all);
+        self.emit(&node, &owl::members, &list);
+        Main::Node(node)
+    }
+
+    fn negative_assertion<T: TTerm + ?Sized>(
+        &mut self,
+        property: BoxTerm,
+        source: BoxTerm,
+        target_property: &T,
+        target: BoxTerm,
+    ) -> Main {
+        let node = self.new_bnode();
+        self.emit(&node, &rdf::type_, &owl::NegativePropertyAssertion);
+        self.emit(&node, &owl::sourceIndividual, &source);
+        self.emit(&node, &owl::assertionProperty, &property);
+        self.emit(&node, target_property, &target);
+        Main::Node(node)
+    }
+
+    fn restriction(&mut self, node: &BoxTerm, property: &BoxTerm) {
+        self.emit(node, &rdf::type_, &owl::Restriction);
+        self.emit(node, &owl::onProperty, property);
+    }
+
+    /// Emit the cardinality of a restriction,
+    /// qualified by `filler` (through `on_filler`) if any.
+    fn cardinality_restriction<T: TTerm + ?Sized>(
+        &mut self,
+        node: &BoxTerm,
+        keyword: &str,
+        cardinality: BoxTerm,
+        on_filler: &T,
+        filler: Option<BoxTerm>,
+    ) {
+        let qualified = filler.is_some();
+        let predicate = if keyword.ends_with("MinCardinality") {
+            [owl::minCardinality, owl::minQualifiedCardinality]
+        } else if keyword.ends_with("MaxCardinality") {
+            [owl::maxCardinality, owl::maxQualifiedCardinality]
+        } else {
+            [owl::cardinality, owl::qualifiedCardinality]
+        }[qualified as usize];
+        self.emit(node, &predicate, &cardinality);
+        if let Some(filler) = filler {
+            self.emit(node, on_filler, &filler);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+    // terms
+
+    /// Parse a full IRI (`<...>`) or an abbreviated IRI (`prefix:local`).
+    fn iri(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        if self.peek() == Some('<') {
+            let iri = self.full_iri()?;
+            return BoxTerm::new_iri(iri).map_err(|e| self.error(&e.to_string()));
+        }
+        let start = self.pos;
+        let name = self.name();
+        let colon = match name.find(':') {
+            Some(colon) if !name.starts_with("_:") => colon,
+            _ => {
+                self.pos = start;
+                return Err(self.error("expected an IRI"));
+            }
+        };
+        let ns = match self.prefixes.get(&name[..colon]) {
+            Some(ns) => ns.clone(),
+            None => {
+                self.pos = start;
+                let message = format!("undeclared prefix '{}'", &name[..colon]);
+                return Err(self.error(&message));
+            }
+        };
+        let local = unescape_local(&name[colon + 1..]).map_err(|e| self.error(&e.to_string()))?;
+        BoxTerm::new_iri(ns + &local).map_err(|e| self.error_at(start, &e.to_string()))
+    }
+
+    fn full_iri(&mut self) -> PResult<String> {
+        self.skip_ws();
+        if !self.eat("<") {
+            return Err(self.error("expected '<'"));
+        }
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| c == '>' || c.is_whitespace() || matches!(c, '<' | '"'))
+            .unwrap_or_else(|| self.rest().len());
+        self.pos += len;
+        if !self.eat(">") {
+            return Err(self.error("invalid IRI"));
+        }
+        Ok(self.txt[start..start + len].to_string())
+    }
+
+    /// Parse a named individual (an IRI) or an anonymous individual (`_:label`).
+    fn individual(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        if !self.rest().starts_with("_:") {
+            return self.iri();
+        }
+        self.pos += 2;
+        let start = self.pos;
+        let label = self.name();
+        BoxTerm::new_bnode(label).map_err(|e| self.error_at(start, &e.to_string()))
+    }
+
+    fn annotation_value(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        if self.peek() == Some('"') {
+            self.literal()
+        } else {
+            self.individual()
+        }
+    }
+
+    fn literal(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        if !self.eat("\"") {
+            return Err(self.error("expected a literal"));
+        }
+        let mut lex = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => break,
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c) if c == '"' || c == '\\' => {
+                            lex.push(c);
+                            self.pos += 1;
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    }
+                }
+                Some(c) => {
+                    lex.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        self.pos += 1; // closing quote
+        if self.eat("^^") {
+            let datatype = self.iri()?;
+            BoxTerm::new_literal_dt(lex, datatype).map_err(|e| self.error(&e.to_string()))
+        } else if self.eat("@") {
+            let start = self.pos;
+            self.eat_while(|c| c.is_ascii_alphanumeric() || c == '-');
+            let tag = &self.txt[start..self.pos];
+            BoxTerm::new_literal_lang(lex, tag).map_err(|e| self.error_at(start, &e.to_string()))
+        } else {
+            Ok(BoxTerm::new_literal_dt_unchecked(lex, xsd::string))
+        }
+    }
+
+    fn cardinality(&mut self) -> PResult<BoxTerm> {
+        self.skip_ws();
+        let start = self.pos;
+        if self.eat_while(|c| c.is_ascii_digit()) == 0 {
+            return Err(self.error("expected a cardinality"));
+        }
+        let lex = &self.txt[start..self.pos];
+        Ok(BoxTerm::new_literal_dt_unchecked(
+            lex,
+            xsd::nonNegativeInteger,
+        ))
+    }
+
+    /// Build an RDF list containing `items`.
+    fn list(&mut self, items: Vec<BoxTerm>) -> BoxTerm {
+        let nodes: Vec<_> = items.iter().map(|_| self.new_bnode()).collect();
+        let nil = BoxTerm::copy(&rdf::nil);
+        for (i, item) in items.iter().enumerate() {
+            self.emit(&nodes[i], &rdf::first, item);
+            self.emit(&nodes[i], &rdf::rest, nodes.get(i + 1).unwrap_or(&nil));
+        }
+        nodes.into_iter().next().unwrap_or(nil)
+    }
+
+    // ------------------------------------------------------------------------------
+    // helpers
+
+    /// Parse items with `item` until the next ')', requiring at least `min` of them.
+    fn items(
+        &mut self,
+        min: usize,
+        item: fn(&mut Self) -> PResult<BoxTerm>,
+    ) -> PResult<Vec<BoxTerm>> {
+        let mut items = vec![];
+        while !self.at_close() {
+            items.push(item(self)?);
+        }
+        if items.len() < min {
+            return Err(self.error(&format!("expected at least {} items", min)));
+        }
+        Ok(items)
+    }
+
+    /// Emit `s p o`, and a blank node of type `kind` reifying it.
+    fn reify<T: TTerm + ?Sized>(
+        &mut self,
+        s: &BoxTerm,
+        p: &BoxTerm,
+        o: &BoxTerm,
+        kind: &T,
+    ) -> BoxTerm {
+        let node = self.new_bnode();
+        self.emit(&node, &rdf::type_, kind);
+        self.emit(&node, &owl::annotatedSource, s);
+        self.emit(&node, &owl::annotatedProperty, p);
+        self.emit(&node, &owl::annotatedTarget, o);
+        node
+    }
+
+    fn annotate(&mut self, subject: &BoxTerm, annotations: &[Annotation]) {
+        for annotation in annotations {
+            self.emit(subject, &annotation.property, &annotation.value);
+            if !annotation.annotations.is_empty() {
+                let node = self.reify(
+                    subject,
+                    &annotation.property,
+                    &annotation.value,
+                    &owl::Annotation,
+                );
+                self.annotate(&node, &annotation.annotations);
+            }
+        }
+    }
+
+    fn new_bnode(&mut self) -> BoxTerm {
+        self.bnodes += 1;
+        BoxTerm::new_bnode_unchecked(format!("ofn-{}", self.bnodes))
+    }
+
+    fn emit<P, O>(&mut self, s: &BoxTerm, p: &P, o: &O)
+    where
+        P: TTerm + ?Sized,
+        O: TTerm + ?Sized,
+    {
+        let triple = [s.clone(), BoxTerm::copy(p), BoxTerm::copy(o)];
+        self.triples.push(Ok(triple));
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.txt[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume the characters matching `predicate`, and return how many were consumed.
+    fn eat_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> usize {
+        let len = self
+            .rest()
+            .find(|c: char| !predicate(c))
+            .unwrap_or_else(|| self.rest().len());
+        self.pos += len;
+        self.txt[self.pos - len..self.pos].chars().count()
+    }
+
+    /// Consume a keyword, abbreviated IRI or blank node label.
+    fn name(&mut self) -> &'a str {
+        let start = self.pos;
+        self.eat_while(|c| is_pn_chars(c) || matches!(c, ':' | '.' | '%' | '\\'));
+        &self.txt[start..self.pos]
+    }
+
+    /// Consume a keyword followed by '(', and return the keyword.
+    ///
+    /// Nothing is consumed if the next token is not a keyword.
+    fn constructor(&mut self) -> Option<&'a str> {
+        self.skip_ws();
+        let start = self.pos;
+        let keyword = self.name();
+        self.skip_ws();
+        if keyword.is_empty() || keyword.contains(':') || !self.eat("(") {
+            self.pos = start;
+            return None;
+        }
+        Some(keyword)
+    }
+
+    /// Consume `keyword` followed by '(', if they are next.
+    fn eat_constructor(&mut self, keyword: &str) -> bool {
+        let start = self.pos;
+        match self.constructor() {
+            Some(k) if k == keyword => true,
+            _ => {
+                self.pos = start;
+                false
+            }
+        }
+    }
+
+    fn at_iri(&mut self) -> bool {
+        self.skip_ws();
+        self.peek() == Some('<') || {
+            let start = self.pos;
+            let name = self.name();
+            self.pos = start;
+            name.contains(':') && !name.starts_with("_:")
+        }
+    }
+
+    fn at_close(&mut self) -> bool {
+        self.skip_ws();
+        self.peek() == Some(')')
+    }
+
+    fn open(&mut self) -> PResult<()> {
+        self.skip_ws();
+        if self.eat("(") {
+            Ok(())
+        } else {
+            Err(self.error("expected '('"))
+        }
+    }
+
+    fn close(&mut self) -> PResult<()> {
+        if self.at_close() {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error("expected ')'"))
+        }
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_ws(&mut self) {
+        loop {
+            self.eat_while(char::is_whitespace);
+            if !self.rest().starts_with('#') {
+                return;
+            }
+            self.eat_while(|c| c != '\n');
+        }
+    }
+
+    fn error(&self, message: &str) -> OfnError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, pos: usize, message: &str) -> OfnError {
+        let before = &self.txt[..pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        OfnError::Syntax {
+            message: message.to_string(),
+            line,
+            column,
+            byte_offset: pos,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::triple::stream::{StreamError, TripleSource};
+    use crate::triple::Triple;
+    use sophia_api::term::matcher::ANY;
+    use sophia_api::term::{SimpleIri, TermKind};
+    use sophia_term::StaticTerm;
+
+    fn ex(suffix: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    /// The only subject of type `kind` in `g`.
+    fn typed(g: &FastGraph, kind: &SimpleIri) -> BoxTerm {
+        let mut subjects: Vec<_> = g
+            .triples_matching(&ANY, &rdf::type_, kind)
+            .map(|t| BoxTerm::copy(t.unwrap().s()))
+            .collect();
+        assert_eq!(subjects.len(), 1);
+        subjects.pop().unwrap()
+    }
+
+    #[test]
+    fn axioms() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = parse_str(
+            r#"
+            Prefix(:=<http://example.org/>)
+            Prefix(foaf:=<http://xmlns.com/foaf/0.1/>)
+            # a comment
+            Ontology(<http://example.org/pets> <http://example.org/pets/1.0>
+              Import(<http://example.org/animals>)
+              Annotation(rdfs:label "Pets"@en)
+              Declaration(Class(:Dog))
+              Declaration(ObjectProperty(:hasOwner))
+              SubClassOf(:Dog ObjectSomeValuesFrom(:hasOwner foaf:Person))
+              SubClassOf(Annotation(rdfs:comment "obvious") :Puppy :Dog)
+              DisjointClasses(:Dog :Cat :Fish)
+              ObjectPropertyAssertion(ObjectInverseOf(:hasOwner) :alice :rex)
+              DataPropertyAssertion(:age :rex "3"^^xsd:integer)
+              ClassAssertion(ObjectMinCardinality(2 :hasOwner :Person) :rex)
+            )
+            "#,
+        )
+        .collect_triples()?;
+        assert_eq!(g.triples().count(), 31);
+        let pets = ex("pets");
+        assert_eq!(typed(&g, &owl::Ontology), pets);
+        assert_eq!(
+            g.triples_matching(&pets, &owl::imports, &ex("animals"))
+                .count(),
+            1
+        );
+        assert_eq!(
+            g.triples_matching(&ex("rex"), &ex("hasOwner"), &ex("alice"))
+                .count(),
+            1
+        );
+        let axiom = typed(&g, &owl::Axiom);
+        assert_eq!(
+            g.triples_matching(&axiom, &owl::annotatedSource, &ex("Puppy"))
+                .count(),
+            1
+        );
+        let comment = StaticTerm::new_literal_dt("obvious", xsd::string)?;
+        assert_eq!(
+            g.triples_matching(&axiom, &rdfs::comment, &comment).count(),
+            1
+        );
+        assert_eq!(
+            typed(&g, &owl::AllDisjointClasses).kind(),
+            TermKind::BlankNode
+        );
+        let two = StaticTerm::new_literal_dt("2", xsd::nonNegativeInteger)?;
+        let restriction: Vec<_> = g
+            .triples_matching(&ANY, &owl::minQualifiedCardinality, &two)
+            .collect();
+        assert_eq!(restriction.len(), 1);
+        let restriction = BoxTerm::copy(restriction[0].as_ref().unwrap().s());
+        assert_eq!(
+            g.triples_matching(&ex("rex"), &rdf::type_, &restriction)
+                .count(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn data_ranges_and_nested_annotations() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = parse_str(
+            r#"
+            Prefix(:=<http://example.org/>)
+            Ontology(
+              DatatypeDefinition(:Age
+                DatatypeRestriction(xsd:integer xsd:minInclusive "0"^^xsd:integer))
+              SubClassOf(:Adult DataSomeValuesFrom(:age DataUnionOf(xsd:integer xsd:decimal)))
+              AnnotationAssertion(
+                Annotation(Annotation(rdfs:comment "meta") :source "wiki")
+                rdfs:label :Dog "Dog")
+              NegativeDataPropertyAssertion(:age :rex "4"^^xsd:integer)
+            )
+            "#,
+        )
+        .collect_triples()?;
+        assert_eq!(g.triples().count(), 33);
+        assert_eq!(typed(&g, &owl::Ontology).kind(), TermKind::BlankNode);
+        assert_eq!(
+            g.triples_matching(&ANY, &owl::withRestrictions, &ANY)
+                .count(),
+            1
+        );
+        let axiom = typed(&g, &owl::Axiom);
+        let annotation = typed(&g, &owl::Annotation);
+        assert_eq!(
+            g.triples_matching(&annotation, &owl::annotatedSource, &axiom)
+                .count(),
+            1
+        );
+        let meta = StaticTerm::new_literal_dt("meta", xsd::string)?;
+        assert_eq!(
+            g.triples_matching(&annotation, &rdfs::comment, &meta)
+                .count(),
+            1
+        );
+        let negative = typed(&g, &owl::NegativePropertyAssertion);
+        let four = StaticTerm::new_literal_dt("4", xsd::integer)?;
+        assert_eq!(
+            g.triples_matching(&negative, &owl::targetValue, &four)
+                .count(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for (ofn, line, column) in &[
+            ("Ontology(", 1, 10),
+            ("Ontology(SubClassOf(:A :B))", 1, 21),
+            ("Prefix(:=<http://ex/>)\nOntology(\n  Foo(:a)\n)", 3, 3),
+            ("Ontology(ClassAssertion(ObjectFoo(<a>) <b>))", 1, 25),
+            ("Ontology(DisjointClasses(<http://ex/a>))", 1, 39),
+            ("Ontology() extra", 1, 12),
+        ] {
+            let res: Result<FastGraph, _> = parse_str(ofn).collect_triples();
+            match res {
+                Err(StreamError::SourceError(err)) => {
+                    let expected = format!("{}:{}", line, column);
+                    assert_eq!(err.location().to_string(), expected, "{:?}: {}", ofn, err);
+                }
+                _ => panic!("{:?} should fail", ofn),
+            }
+        }
+    }
+}
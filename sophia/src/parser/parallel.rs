@@ -0,0 +1,347 @@
+//! Parallel parser for [N-Triples] and [N-Quads], using [rayon].
+//!
+//! As these formats are line-based, the input can be split into chunks of whole lines,
+//! which are parsed independently on rayon's thread pool.
+//! The data is read one batch of chunks at a time (one chunk per thread),
+//! so memory usage is bounded by the size of a batch, regardless of the size of the input.
+//! Triples and quads are produced in the order of the input,
+//! and errors are located as precisely as with the [sequential N-Quads parser](../nq/index.html).
+//!
+//! This module requires the `parallel` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::parser::parallel::ParallelParser;
+//! # use sophia::parser::QuadParser;
+//! # use sophia::quad::stream::QuadSource;
+//! let data = "<tag:s> <tag:p> <tag:o>.\n<tag:s> <tag:p> <tag:o> <tag:g>.\n";
+//! let parser = ParallelParser::default();
+//! let d: FastDataset = parser.parse_str(data).collect_quads()?;
+//! assert_eq!(d.quads().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [N-Triples]: https://www.w3.org/TR/n-triples/
+//! [N-Quads]: https://www.w3.org/TR/n-quads/
+//! [rayon]: https://docs.rs/rayon/
+
+use std::io::BufRead;
+
+use rayon::prelude::*;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::CopyTerm;
+use sophia_term::BoxTerm;
+
+use crate::dataset::indexed::IndexedDataset;
+use crate::parser::nq::{parse_line, NQuadsError, NQuadsSourceQuad};
+
+/// The default value of [`ParallelParser::chunk_size`](struct.ParallelParser.html#structfield.chunk_size).
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Parallel N-Triples and N-Quads parser.
+///
+/// As a [`TripleParser`], it parses N-Triples, and rejects lines with a graph name.
+/// As a [`QuadParser`], it parses N-Quads.
+/// In both cases, errors are reported as [`NQuadsError`]s.
+///
+/// See [module documentation](index.html).
+///
+/// [`TripleParser`]: ../trait.TripleParser.html
+/// [`QuadParser`]: ../trait.QuadParser.html
+/// [`NQuadsError`]: ../nq/enum.NQuadsError.html
+#[derive(Clone, Debug)]
+pub struct ParallelParser {
+    /// The minimum size (in bytes) of the chunks parsed by each thread
+    /// (chunks are extended to the end of their last line).
+    pub chunk_size: usize,
+}
+
+impl Default for ParallelParser {
+    fn default() -> Self {
+        ParallelParser {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl ParallelParser {
+    /// Parse the N-Quads `data` directly into `dataset`.
+    ///
+    /// Return the number of quads actually inserted
+    /// (i.e. not already present in `dataset`),
+    /// or the first error encountered.
+    pub fn load_into<B, D>(&self, data: B, dataset: &mut D) -> Result<usize, NQuadsError>
+    where
+        B: BufRead,
+        D: IndexedDataset,
+    {
+        let mut inserted = 0;
+        for quad in QuadParser::parse(self, data) {
+            let ([s, p, o], g) = quad?;
+            if dataset.insert_indexed(&s, &p, &o, g.as_ref()).is_some() {
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    fn source<B, T>(
+        &self,
+        data: B,
+        triples_only: bool,
+        convert: Convert<T>,
+    ) -> ParallelSource<B, T> {
+        ParallelSource {
+            data,
+            chunk_size: self.chunk_size.max(1),
+            triples_only,
+            convert,
+            line_number: 0,
+            offset: 0,
+            parsed: vec![].into_iter(),
+            error: None,
+            eof: false,
+        }
+    }
+}
+
+impl<B: BufRead> TripleParser<B> for ParallelParser {
+    type Source = ParallelSource<B, [BoxTerm; 3]>;
+    fn parse(&self, data: B) -> Self::Source {
+        self.source(data, true, |(spo, _)| {
+            let [s, p, o] = spo;
+            [BoxTerm::copy(&s), BoxTerm::copy(&p), BoxTerm::copy(&o)]
+        })
+    }
+}
+
+impl<B: BufRead> QuadParser<B> for ParallelParser {
+    type Source = ParallelSource<B, TupleQuad<BoxTerm>>;
+    fn parse(&self, data: B) -> Self::Source {
+        self.source(data, false, |(spo, g)| {
+            let [s, p, o] = spo;
+            (
+                [BoxTerm::copy(&s), BoxTerm::copy(&p), BoxTerm::copy(&o)],
+                g.as_ref().map(BoxTerm::copy),
+            )
+        })
+    }
+}
+
+/// The conversion of a parsed line into a triple or a quad.
+type Convert<T> = fn(NQuadsSourceQuad) -> T;
+
+/// The source produced by [`ParallelParser`](struct.ParallelParser.html),
+/// yielding either triples or quads.
+pub struct ParallelSource<B, T> {
+    data: B,
+    chunk_size: usize,
+    triples_only: bool,
+    convert: Convert<T>,
+    /// The number of lines read so far.
+    line_number: usize,
+    /// The byte offset of the next line in the data.
+    offset: usize,
+    /// The results of the last parsed batch, not yet consumed.
+    parsed: std::vec::IntoIter<Result<T, NQuadsError>>,
+    /// An I/O error, to be reported after the last parsed batch.
+    error: Option<NQuadsError>,
+    eof: bool,
+}
+
+/// A chunk of whole lines.
+struct Chunk {
+    txt: String,
+    /// The number of the first line of this chunk (starting at 1).
+    line: usize,
+    /// The byte offset of this chunk in the data.
+    offset: usize,
+}
+
+impl<B: BufRead, T: Send> ParallelSource<B, T> {
+    /// Read the next chunk of lines, if any.
+    ///
+    /// I/O errors are stored in `self.error`, and end the input.
+    fn read_chunk(&mut self) -> Option<Chunk> {
+        let mut chunk = Chunk {
+            txt: String::new(),
+            line: self.line_number + 1,
+            offset: self.offset,
+        };
+        while !self.eof && chunk.txt.len() < self.chunk_size {
+            match self.data.read_line(&mut chunk.txt) {
+                Ok(0) => self.eof = true,
+                Ok(read) => {
+                    self.line_number += 1;
+                    self.offset += read;
+                }
+                Err(source) => {
+                    self.error = Some(NQuadsError::Io {
+                        source,
+                        line: self.line_number + 1,
+                    });
+                    self.eof = true;
+                }
+            }
+        }
+        if chunk.txt.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+
+    /// Parse the next batch of chunks.
+    ///
+    /// Return false if there is nothing left to parse.
+    fn refill(&mut self) -> bool {
+        let mut chunks = vec![];
+        while chunks.len() < rayon::current_num_threads() {
+            match self.read_chunk() {
+                Some(chunk) => chunks.push(chunk),
+                None => break,
+            }
+        }
+        let (triples_only, convert) = (self.triples_only, self.convert);
+        let mut parsed: Vec<_> = chunks
+            .par_iter()
+            .map(|chunk| parse_chunk(chunk, triples_only, convert))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+            .collect();
+        parsed.extend(self.error.take().map(Err));
+        // NB: a batch may contain no triple/quad (e.g. only comments)
+        let more = !chunks.is_empty() || !parsed.is_empty();
+        self.parsed = parsed.into_iter();
+        more
+    }
+}
+
+impl<B: BufRead, T: Send> Iterator for ParallelSource<B, T> {
+    type Item = Result<T, NQuadsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(res) = self.parsed.next() {
+                return Some(res);
+            }
+            if !self.refill() {
+                return None;
+            }
+        }
+    }
+}
+
+fn parse_chunk<T>(
+    chunk: &Chunk,
+    triples_only: bool,
+    convert: Convert<T>,
+) -> Vec<Result<T, NQuadsError>> {
+    let mut results = vec![];
+    let mut scratch = String::new();
+    let mut offset = chunk.offset;
+    for (i, line) in chunk.txt.split('\n').enumerate() {
+        scratch.clear();
+        let txt = line.trim_end_matches('\r');
+        match parse_line(txt, chunk.line + i, offset, &mut scratch, triples_only) {
+            Ok(Some(quad)) => results.push(Ok(convert(quad))),
+            Ok(None) => {}
+            Err(err) => results.push(Err(err)),
+        }
+        offset += line.len() + 1;
+    }
+    results
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::parser::nq;
+    use crate::quad::stream::QuadSource;
+    use crate::triple::stream::TripleSource;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_api::parser::WithLocation;
+
+    fn data(n: usize) -> String {
+        (0..n)
+            .map(|i| {
+                format!(
+                    "<tag:s{}> <tag:p> \"o\\u00e9{}\"@en <tag:g{}> .\r\n",
+                    i,
+                    i,
+                    i % 3
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_as_sequential() -> Result<(), Box<dyn std::error::Error>> {
+        let data = data(1000);
+        let expected: FastDataset = nq::parse_str(&data).collect_quads()?;
+        for chunk_size in &[1, 100, 10_000, DEFAULT_CHUNK_SIZE] {
+            let parser = ParallelParser {
+                chunk_size: *chunk_size,
+            };
+            let quads: Vec<_> = QuadParser::parse_str(&parser, &data).collect::<Result<_, _>>()?;
+            assert_eq!(quads.len(), 1000);
+            assert_eq!(quads[42].0[0], BoxTerm::new_iri("tag:s42")?);
+            let d: FastDataset = QuadParser::parse_str(&parser, &data).collect_quads()?;
+            assert!(isomorphic_datasets(&d, &expected)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let data = format!(
+            "{}<tag:s> <tag:p> \"unterminated .\n{}<tag:s> <tag:p> <tag:{{o}}> .",
+            data(10),
+            data(10),
+        );
+        let parser = ParallelParser { chunk_size: 200 };
+        let errors: Vec<_> = QuadParser::parse_str(&parser, &data)
+            .filter_map(Result::err)
+            .map(|err| err.location().to_string())
+            .collect();
+        assert_eq!(errors, ["11:32", "22:22"]);
+    }
+
+    #[test]
+    fn triples() -> Result<(), Box<dyn std::error::Error>> {
+        let parser = ParallelParser { chunk_size: 10 };
+        let nt = "<tag:a> <tag:p> <tag:b>.\n<tag:b> <tag:p> <tag:c>.\n";
+        let g: FastGraph = TripleParser::parse_str(&parser, nt).collect_triples()?;
+        assert_eq!(g.triples().count(), 2);
+
+        let nq = "<tag:a> <tag:p> <tag:b>.\n<tag:b> <tag:p> <tag:c> <tag:g>.\n";
+        let res: Result<FastGraph, _> = TripleParser::parse_str(&parser, nq).collect_triples();
+        match res {
+            Ok(_) => panic!("N-Quads should not be accepted as N-Triples"),
+            Err(err) => assert_eq!(err.unwrap_source_error().location().to_string(), "2:25"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn load_into() -> Result<(), Box<dyn std::error::Error>> {
+        let data = data(100);
+        let mut d = FastDataset::new();
+        let parser = ParallelParser { chunk_size: 500 };
+        assert_eq!(parser.load_into(data.as_bytes(), &mut d)?, 100);
+        assert_eq!(parser.load_into(data.as_bytes(), &mut d)?, 0);
+        assert_eq!(d.quads().count(), 100);
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+//! Parser for the legacy [RDF/JSON] format (a.k.a. Talis RDF/JSON).
+//!
+//! An RDF/JSON document is a JSON object mapping subjects
+//! (IRIs, or blank node labels prefixed with `_:`)
+//! to objects mapping predicates to arrays of values;
+//! each value is an object with a `type` (`uri`, `bnode` or `literal`), a `value`,
+//! and optionally a `lang` or a `datatype` for literals.
+//!
+//! NB: the whole document is parsed before any triple is produced.
+//!
+//! This module requires the `json` feature.
+//!
+//! [RDF/JSON]: https://www.w3.org/TR/rdf-json/
+
+use std::io::{self, BufRead};
+
+use json::JsonValue;
+use sophia_api::ns::xsd;
+use sophia_api::parser::TripleParser;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// RDF/JSON parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct RdfJsonParser {}
+
+impl<B: BufRead> TripleParser<B> for RdfJsonParser {
+    type Source = std::vec::IntoIter<Result<[BoxTerm; 3], RdfJsonError>>;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        if let Err(err) = data.read_to_string(&mut txt) {
+            return vec![Err(RdfJsonError::Io(err))].into_iter();
+        }
+        let mut triples = vec![];
+        if let Err(err) = json::parse(&txt)
+            .map_err(RdfJsonError::Json)
+            .and_then(|doc| parse_document(&doc, &mut triples))
+        {
+            triples.push(Err(err));
+        }
+        triples.into_iter()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(RdfJsonParser, TripleParser);
+
+/// Errors raised by the [`RdfJsonParser`](struct.RdfJsonParser.html).
+#[derive(Debug, Error)]
+pub enum RdfJsonError {
+    /// The data could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data is not valid JSON.
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] json::Error),
+    /// The data is valid JSON, but not valid RDF/JSON.
+    #[error("Invalid RDF/JSON: {0}")]
+    Syntax(String),
+}
+
+type RResult<T> = Result<T, RdfJsonError>;
+
+fn syntax_error<T>(message: String) -> RResult<T> {
+    Err(RdfJsonError::Syntax(message))
+}
+
+/// Push the triples of `doc` into `triples`, stopping at the first error.
+fn parse_document(doc: &JsonValue, triples: &mut Vec<RResult<[BoxTerm; 3]>>) -> RResult<()> {
+    if !doc.is_object() {
+        return syntax_error("document must be an object".to_string());
+    }
+    for (subject, predicates) in doc.entries() {
+        let s = match subject.strip_prefix("_:") {
+            Some(label) => BoxTerm::new_bnode(label),
+            None => BoxTerm::new_iri(subject),
+        }
+        .map_err(|e| RdfJsonError::Syntax(e.to_string()))?;
+        if !predicates.is_object() {
+            return syntax_error(format!("value of subject {} must be an object", subject));
+        }
+        for (predicate, objects) in predicates.entries() {
+            let p = BoxTerm::new_iri(predicate).map_err(|e| RdfJsonError::Syntax(e.to_string()))?;
+            if !objects.is_array() {
+                return syntax_error(format!("value of predicate {} must be an array", predicate));
+            }
+            for o in objects.members() {
+                triples.push(Ok([s.clone(), p.clone(), parse_object(o)?]));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_object(o: &JsonValue) -> RResult<BoxTerm> {
+    let value = match o["value"].as_str() {
+        Some(value) => value,
+        None => return syntax_error(format!("object without a string value: {}", o)),
+    };
+    let term = match o["type"].as_str() {
+        Some("uri") => BoxTerm::new_iri(value),
+        Some("bnode") => match value.strip_prefix("_:") {
+            Some(label) => BoxTerm::new_bnode(label),
+            None => return syntax_error(format!("blank node without _: prefix: {}", value)),
+        },
+        Some("literal") => match (o["lang"].as_str(), o["datatype"].as_str()) {
+            (Some(lang), _) => BoxTerm::new_literal_lang(value, lang),
+            (None, Some(datatype)) => {
+                BoxTerm::new_iri(datatype).and_then(|dt| BoxTerm::new_literal_dt(value, dt))
+            }
+            (None, None) => BoxTerm::new_literal_dt(value, xsd::string),
+        },
+        _ => return syntax_error(format!("object with invalid type: {}", o)),
+    };
+    term.map_err(|e| RdfJsonError::Syntax(e.to_string()))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::triple::stream::TripleSource;
+    use sophia_api::term::matcher::ANY;
+    use sophia_term::StaticTerm;
+
+    #[test]
+    fn test_simple_rdf_json_string() -> Result<(), Box<dyn std::error::Error>> {
+        let rdf_json = r#"{
+            "http://example.org/alice": {
+                "http://example.org/knows": [
+                    { "type": "uri", "value": "http://example.org/bob" },
+                    { "type": "bnode", "value": "_:charlie" }
+                ],
+                "http://example.org/name": [
+                    { "type": "literal", "value": "Alice", "lang": "en" },
+                    { "type": "literal", "value": "Alice" }
+                ]
+            },
+            "_:charlie": {
+                "http://example.org/age": [
+                    {
+                        "type": "literal",
+                        "value": "42",
+                        "datatype": "http://www.w3.org/2001/XMLSchema#integer"
+                    }
+                ]
+            }
+        }"#;
+        let g: FastGraph = parse_str(rdf_json).collect_triples()?;
+        assert_eq!(g.triples().count(), 5);
+        let alice = StaticTerm::new_iri("http://example.org/alice")?;
+        let charlie = BoxTerm::new_bnode("charlie")?;
+        let knows = StaticTerm::new_iri("http://example.org/knows")?;
+        let name = StaticTerm::new_iri("http://example.org/name")?;
+        let age = StaticTerm::new_iri("http://example.org/age")?;
+        assert_eq!(g.triples_matching(&alice, &knows, &charlie).count(), 1);
+        assert_eq!(
+            g.triples_matching(&alice, &name, &StaticTerm::new_literal_lang("Alice", "en")?)
+                .count(),
+            1
+        );
+        assert_eq!(
+            g.triples_matching(
+                &alice,
+                &name,
+                &StaticTerm::new_literal_dt("Alice", xsd::string)?
+            )
+            .count(),
+            1
+        );
+        assert_eq!(
+            g.triples_matching(
+                &charlie,
+                &age,
+                &StaticTerm::new_literal_dt("42", xsd::integer)?
+            )
+            .count(),
+            1
+        );
+        assert_eq!(g.triples_matching(&ANY, &ANY, &ANY).count(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for rdf_json in &[
+            "{",
+            "[]",
+            r#"{"http://ex.org/s": []}"#,
+            r#"{"http://ex.org/s": {"http://ex.org/p": {}}}"#,
+            r#"{"http://ex.org/s": {"http://ex.org/p": [{"type": "uri"}]}}"#,
+            r#"{"http://ex.org/s": {"http://ex.org/p": [{"type": "foo", "value": "x"}]}}"#,
+            r#"{"http://ex.org/s": {"http://ex.org/p": [{"type": "bnode", "value": "x"}]}}"#,
+            r#"{"http://ex.org/s": {"not an IRI": [{"type": "uri", "value": "http://ex.org/o"}]}}"#,
+        ] {
+            let res: Result<FastGraph, _> = parse_str(rdf_json).collect_triples();
+            assert!(res.is_err(), "{:?} should fail", rdf_json);
+        }
+    }
+
+    #[test]
+    fn triples_before_error() {
+        let rdf_json = r#"{"http://ex.org/s": {"http://ex.org/p": [
+            {"type": "uri", "value": "http://ex.org/o"},
+            {"type": "uri"}
+        ]}}"#;
+        let results: Vec<_> = parse_str(rdf_json).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+}
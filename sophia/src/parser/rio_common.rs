@@ -7,6 +7,7 @@
 //! which makes it impossible for Sophia itself to rely on that feature.
 
 use std::error::Error;
+use std::io;
 use std::result::Result as StdResult;
 
 use rio_api::model::*;
@@ -226,6 +227,81 @@ where
     }
 }
 
+/// A generalized triple produced by a RIO source.
+pub type GRioSourceTriple<'a> = [GRioTermWrapper<'a>; 3];
+sophia_api::make_scoped_triple_streaming_mode!(
+    /// A scoped generalized RIO source triple.
+    ScopedGRioSourceTriple,
+    GRioSourceTriple
+);
+
+/// TripleSource adapter producing generalized triples from a RIO GeneralizedQuadParser.
+///
+/// Quads in a named graph are errors.
+pub struct GeneralizedRioTripleSource<T, E>(pub GeneralizedRioSource<T, E>);
+
+impl<T, E> From<StdResult<T, E>> for GeneralizedRioTripleSource<T, E> {
+    fn from(res: StdResult<T, E>) -> Self {
+        GeneralizedRioTripleSource(res.into())
+    }
+}
+
+impl<T, E> TripleSource for GeneralizedRioTripleSource<T, E>
+where
+    T: GeneralizedQuadsParser<Error = E>,
+    E: Error + From<io::Error> + 'static,
+{
+    type Error = E;
+    type Triple = ScopedGRioSourceTriple;
+
+    fn try_for_some_triple<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, E, EF>
+    where
+        F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), EF>,
+        EF: Error,
+    {
+        match &mut self.0 {
+            GeneralizedRioSource::Error(opt) => Err(SourceError(consume_err(opt))),
+            GeneralizedRioSource::Parser(parser) => {
+                if parser.is_end() {
+                    return Ok(false);
+                }
+                parser
+                    .parse_step(&mut |q| -> StdResult<(), MyStreamError<E, EF>> {
+                        if let Some(g) = q.graph_name {
+                            let msg = format!("unexpected graph name {}", g);
+                            let err = io::Error::new(io::ErrorKind::InvalidData, msg);
+                            return Err(MyStreamError::Source(err.into()));
+                        }
+                        f(StreamedTriple::scoped([
+                            GRioTermWrapper(q.subject),
+                            GRioTermWrapper(q.predicate),
+                            GRioTermWrapper(q.object),
+                        ]))
+                        .map_err(MyStreamError::from_sink_error)
+                    })
+                    .map_err(|e| e.into_stream_error())
+                    .and(Ok(true))
+            }
+        }
+    }
+}
+
+/// Parse the base IRI given to a RIO parser, if any.
+///
+/// As RIO errors can only be built from I/O errors,
+/// an invalid base IRI is reported as an I/O error of kind `InvalidInput`.
+pub(crate) fn parse_base(base: &Option<String>) -> io::Result<Option<oxiri::Iri<String>>> {
+    match base {
+        None => Ok(None),
+        Some(base) => oxiri::Iri::parse(base.clone()).map(Some).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid base IRI <{}>: {}", base, e),
+            )
+        }),
+    }
+}
+
 /// Consume inner error and convert it to Error
 fn consume_err<E>(opt: &mut Option<E>) -> E {
     opt.take().unwrap_or_else(|| {
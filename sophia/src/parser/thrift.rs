@@ -0,0 +1,654 @@
+//! Parser for [RDF Thrift], the binary encoding of RDF used by Apache Jena and RDF Delta.
+//!
+//! RDF Thrift data is a sequence of rows (prefix declarations, triples and quads),
+//! each encoded as a [Thrift] struct with the compact protocol.
+//! Rows are decoded one at a time, as the data is read,
+//! so the [`ThriftQuads`] source works in constant memory.
+//! Triples are produced as quads in the default graph.
+//!
+//! All the term encodings of RDF Thrift are supported:
+//! prefixed names are expanded,
+//! repeated terms are copied from the previous triple or quad,
+//! and values (integers, doubles and decimals) are converted to literals
+//! (with datatype `xsd:integer`, `xsd:double` and `xsd:decimal`, respectively).
+//! Variables are produced as [variable terms].
+//!
+//! NB: since RDF Thrift is a binary format, there is no way to recover from an error,
+//! so the source ends after the first error.
+//! Base IRI rows are ignored, as IRIs in RDF Thrift are always absolute.
+//! RDF-star triple terms are not supported.
+//!
+//! [RDF Thrift]: https://jena.apache.org/documentation/io/rdf-binary.html
+//! [Thrift]: https://thrift.apache.org/
+//! [`ThriftQuads`]: struct.ThriftQuads.html
+//! [variable terms]: ../../term/enum.Term.html#variant.Variable
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+
+use sophia_api::ns::xsd;
+use sophia_api::parser::QuadParser;
+use sophia_api::quad::TupleQuad;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The media type of RDF Thrift.
+pub const MEDIA_TYPE: &str = "application/rdf+thrift";
+
+// the types of the compact protocol
+const STOP: u8 = 0;
+const BOOLEAN_TRUE: u8 = 1;
+const BOOLEAN_FALSE: u8 = 2;
+const BYTE: u8 = 3;
+const I16: u8 = 4;
+const I32: u8 = 5;
+const I64: u8 = 6;
+const DOUBLE: u8 = 7;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const SET: u8 = 10;
+const MAP: u8 = 11;
+const STRUCT: u8 = 12;
+
+/// The maximum nesting of structs and containers, when skipping unknown fields.
+const MAX_DEPTH: usize = 64;
+
+/// RDF Thrift parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct ThriftParser {}
+
+impl<B: BufRead> QuadParser<B> for ThriftParser {
+    type Source = ThriftQuads<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        ThriftQuads {
+            data,
+            prefixes: HashMap::new(),
+            previous: Default::default(),
+            done: false,
+        }
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(ThriftParser, QuadParser);
+
+/// Errors raised by the [`ThriftParser`](struct.ThriftParser.html).
+#[derive(Debug, Error)]
+pub enum ThriftError {
+    /// The data could not be read (or ends in the middle of a row).
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data is not valid RDF Thrift.
+    #[error("Invalid RDF Thrift: {0}")]
+    Format(String),
+    /// The data uses a feature of RDF Thrift that is not supported.
+    #[error("Unsupported RDF Thrift: {0}")]
+    Unsupported(String),
+}
+
+type TResult<T> = Result<T, ThriftError>;
+
+fn format_error<T>(message: String) -> TResult<T> {
+    Err(ThriftError::Format(message))
+}
+
+/// The quad source produced by [`ThriftParser`](struct.ThriftParser.html).
+pub struct ThriftQuads<B> {
+    data: B,
+    prefixes: HashMap<String, String>,
+    /// The terms of the previous triple or quad, for repeated terms.
+    previous: [Option<BoxTerm>; 4],
+    done: bool,
+}
+
+impl<B: BufRead> Iterator for ThriftQuads<B> {
+    type Item = TResult<TupleQuad<BoxTerm>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let res = match self.data.fill_buf() {
+                Ok([]) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(_) => self.row(),
+                Err(err) => Err(err.into()),
+            };
+            match res {
+                Ok(None) => continue,
+                Ok(Some(quad)) => return Some(Ok(quad)),
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<B: BufRead> ThriftQuads<B> {
+    /// Read a row, and return its triple or quad, if any.
+    fn row(&mut self) -> TResult<Option<TupleQuad<BoxTerm>>> {
+        let mut quad = None;
+        let mut last_id = 0;
+        while let Some((id, ty)) = read_field_header(&mut self.data, &mut last_id)? {
+            match (id, ty) {
+                (1, STRUCT) => {
+                    let mut fields = read_strings(&mut self.data, 2)?.into_iter();
+                    match (fields.next().unwrap(), fields.next().unwrap()) {
+                        (Some(prefix), Some(ns)) => {
+                            self.prefixes.insert(prefix, ns);
+                        }
+                        _ => return format_error("incomplete prefix declaration".into()),
+                    }
+                }
+                (2, STRUCT) | (3, STRUCT) => quad = Some(self.statement()?),
+                (4, STRUCT) => {
+                    read_strings(&mut self.data, 1)?;
+                }
+                _ => skip(&mut self.data, ty, 0)?,
+            }
+        }
+        Ok(quad)
+    }
+
+    /// Read a triple or a quad.
+    fn statement(&mut self) -> TResult<TupleQuad<BoxTerm>> {
+        let mut terms: [Option<BoxTerm>; 4] = Default::default();
+        let mut last_id = 0;
+        while let Some((id, ty)) = read_field_header(&mut self.data, &mut last_id)? {
+            match (id, ty) {
+                (1..=4, STRUCT) => {
+                    let position = (id - 1) as usize;
+                    terms[position] = Some(self.term(position)?);
+                }
+                _ => skip(&mut self.data, ty, 0)?,
+            }
+        }
+        self.previous = terms.clone();
+        let [s, p, o, g] = terms;
+        match (s, p, o) {
+            (Some(s), Some(p), Some(o)) => Ok(([s, p, o], g)),
+            _ => format_error("incomplete triple or quad".into()),
+        }
+    }
+
+    /// Read the term at the given position (0 to 3) of a triple or quad.
+    fn term(&mut self, position: usize) -> TResult<BoxTerm> {
+        let mut term = None;
+        let mut last_id = 0;
+        while let Some((id, ty)) = read_field_header(&mut self.data, &mut last_id)? {
+            term = Some(match (id, ty) {
+                (1, STRUCT) => {
+                    let iri = read_string_field(&mut self.data, "IRI")?;
+                    BoxTerm::new_iri(iri).map_err(term_error)?
+                }
+                (2, STRUCT) => {
+                    let label = read_string_field(&mut self.data, "blank node")?;
+                    BoxTerm::new_bnode(label).map_err(term_error)?
+                }
+                (3, STRUCT) => self.literal()?,
+                (4, STRUCT) => {
+                    let iri = self.prefixed_name()?;
+                    BoxTerm::new_iri(iri).map_err(term_error)?
+                }
+                (5, STRUCT) => {
+                    let name = read_string_field(&mut self.data, "variable")?;
+                    BoxTerm::new_variable(name).map_err(term_error)?
+                }
+                (6, STRUCT) | (7, STRUCT) => {
+                    return format_error("wildcard in triple or quad".into());
+                }
+                (8, STRUCT) => {
+                    skip(&mut self.data, STRUCT, 0)?;
+                    match &self.previous[position] {
+                        Some(previous) => previous.clone(),
+                        None => return format_error("no term to repeat".into()),
+                    }
+                }
+                (9, STRUCT) => {
+                    return Err(ThriftError::Unsupported("triple term".into()));
+                }
+                (10, I64) => {
+                    let value = read_zigzag(&mut self.data)?;
+                    BoxTerm::new_literal_dt_unchecked(value.to_string(), xsd::integer)
+                }
+                (11, DOUBLE) => {
+                    let value = read_double(&mut self.data)?;
+                    BoxTerm::new_literal_dt_unchecked(double_lexical(value), xsd::double)
+                }
+                (12, STRUCT) => self.decimal()?,
+                _ => {
+                    skip(&mut self.data, ty, 0)?;
+                    continue;
+                }
+            });
+        }
+        term.ok_or_else(|| ThriftError::Format("empty term".into()))
+    }
+
+    fn literal(&mut self) -> TResult<BoxTerm> {
+        let mut lex = None;
+        let mut lang = None;
+        let mut datatype = None;
+        let mut last_id = 0;
+        while let Some((id, ty)) = read_field_header(&mut self.data, &mut last_id)? {
+            match (id, ty) {
+                (1, BINARY) => lex = Some(read_string(&mut self.data)?),
+                (2, BINARY) => lang = Some(read_string(&mut self.data)?),
+                (3, BINARY) => datatype = Some(read_string(&mut self.data)?),
+                (4, STRUCT) => datatype = Some(self.prefixed_name()?),
+                _ => skip(&mut self.data, ty, 0)?,
+            }
+        }
+        let lex = match lex {
+            Some(lex) => lex,
+            None => return format_error("literal without lexical form".into()),
+        };
+        // empty optional fields are considered absent
+        match (lang, datatype) {
+            (Some(lang), _) if !lang.is_empty() => {
+                BoxTerm::new_literal_lang(lex, lang).map_err(term_error)
+            }
+            (_, Some(datatype)) if !datatype.is_empty() => {
+                let datatype = BoxTerm::new_iri(datatype).map_err(term_error)?;
+                BoxTerm::new_literal_dt(lex, datatype).map_err(term_error)
+            }
+            _ => Ok(BoxTerm::new_literal_dt_unchecked(lex, xsd::string)),
+        }
+    }
+
+    /// Read a prefixed name, and return the IRI it stands for.
+    fn prefixed_name(&mut self) -> TResult<String> {
+        let mut fields = read_strings(&mut self.data, 2)?.into_iter();
+        let (prefix, local) = match (fields.next().unwrap(), fields.next().unwrap()) {
+            (Some(prefix), Some(local)) => (prefix, local),
+            _ => return format_error("incomplete prefixed name".into()),
+        };
+        match self.prefixes.get(&prefix) {
+            Some(ns) => Ok(format!("{}{}", ns, local)),
+            None => format_error(format!("undeclared prefix '{}'", prefix)),
+        }
+    }
+
+    fn decimal(&mut self) -> TResult<BoxTerm> {
+        let mut value = None;
+        let mut scale = None;
+        let mut last_id = 0;
+        while let Some((id, ty)) = read_field_header(&mut self.data, &mut last_id)? {
+            match (id, ty) {
+                (1, I64) => value = Some(read_zigzag(&mut self.data)?),
+                (2, I32) => scale = Some(read_zigzag(&mut self.data)?),
+                _ => skip(&mut self.data, ty, 0)?,
+            }
+        }
+        match (value, scale) {
+            (Some(value), Some(scale)) if scale.abs() <= 1000 => Ok(
+                BoxTerm::new_literal_dt_unchecked(decimal_lexical(value, scale), xsd::decimal),
+            ),
+            _ => format_error("invalid decimal".into()),
+        }
+    }
+}
+
+fn term_error(err: sophia_term::TermError) -> ThriftError {
+    ThriftError::Format(err.to_string())
+}
+
+/// The lexical form of the decimal `value × 10^-scale`.
+fn decimal_lexical(value: i64, scale: i64) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let digits = (value as i128).abs().to_string();
+    if scale <= 0 {
+        let zeros = if value == 0 { 0 } else { -scale as usize };
+        return format!("{}{}{}.0", sign, digits, "0".repeat(zeros));
+    }
+    let scale = scale as usize;
+    let digits = if digits.len() <= scale {
+        format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)
+    } else {
+        digits
+    };
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, int, frac)
+}
+
+/// The lexical form of a double.
+fn double_lexical(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "INF" } else { "-INF" }.to_string()
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+// compact protocol
+
+fn read_byte<R: Read>(r: &mut R) -> TResult<u8> {
+    let mut buf = [0];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_varint<R: Read>(r: &mut R) -> TResult<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(r)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    format_error("varint too long".into())
+}
+
+/// Read a zigzag-encoded integer (i16, i32 or i64).
+fn read_zigzag<R: Read>(r: &mut R) -> TResult<i64> {
+    let n = read_varint(r)?;
+    Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+}
+
+fn read_double<R: Read>(r: &mut R) -> TResult<f64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+fn read_binary<R: Read>(r: &mut R) -> TResult<Vec<u8>> {
+    let len = read_varint(r)?;
+    let mut buf = Vec::new();
+    // do not trust `len` to pre-allocate the buffer
+    r.take(len).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(buf)
+}
+
+fn read_string<R: Read>(r: &mut R) -> TResult<String> {
+    String::from_utf8(read_binary(r)?).map_err(|e| ThriftError::Format(e.to_string()))
+}
+
+/// Read the header of the next field of a struct, and return its id and type,
+/// or `None` at the end of the struct.
+///
+/// `last_id` is the id of the previous field (0 at the start of the struct).
+fn read_field_header<R: Read>(r: &mut R, last_id: &mut i64) -> TResult<Option<(i64, u8)>> {
+    let byte = read_byte(r)?;
+    if byte == STOP {
+        return Ok(None);
+    }
+    let delta = i64::from(byte >> 4);
+    let id = if delta == 0 {
+        read_zigzag(r)?
+    } else {
+        *last_id + delta
+    };
+    *last_id = id;
+    Ok(Some((id, byte & 0x0f)))
+}
+
+/// Read a struct whose fields 1 to `n` are strings.
+fn read_strings<R: Read>(r: &mut R, n: usize) -> TResult<Vec<Option<String>>> {
+    let mut fields = vec![None; n];
+    let mut last_id = 0;
+    while let Some((id, ty)) = read_field_header(r, &mut last_id)? {
+        if ty == BINARY && id >= 1 && id as usize <= n {
+            fields[id as usize - 1] = Some(read_string(r)?);
+        } else {
+            skip(r, ty, 0)?;
+        }
+    }
+    Ok(fields)
+}
+
+/// Read a struct with a single required string field.
+fn read_string_field<R: Read>(r: &mut R, what: &str) -> TResult<String> {
+    match read_strings(r, 1)?.pop().unwrap() {
+        Some(value) => Ok(value),
+        None => format_error(format!("empty {}", what)),
+    }
+}
+
+/// Skip a value of type `ty`.
+fn skip<R: Read>(r: &mut R, ty: u8, depth: usize) -> TResult<()> {
+    if depth > MAX_DEPTH {
+        return format_error("data nested too deeply".into());
+    }
+    match ty {
+        BOOLEAN_TRUE | BOOLEAN_FALSE => {}
+        BYTE => {
+            read_byte(r)?;
+        }
+        I16 | I32 | I64 => {
+            read_varint(r)?;
+        }
+        DOUBLE => {
+            read_double(r)?;
+        }
+        BINARY => {
+            read_binary(r)?;
+        }
+        LIST | SET => {
+            let header = read_byte(r)?;
+            let size = match header >> 4 {
+                15 => read_varint(r)?,
+                size => u64::from(size),
+            };
+            let item_type = header & 0x0f;
+            for _ in 0..size {
+                skip_item(r, item_type, depth + 1)?;
+            }
+        }
+        MAP => {
+            let size = read_varint(r)?;
+            if size > 0 {
+                let types = read_byte(r)?;
+                for _ in 0..size {
+                    skip_item(r, types >> 4, depth + 1)?;
+                    skip_item(r, types & 0x0f, depth + 1)?;
+                }
+            }
+        }
+        STRUCT => {
+            let mut last_id = 0;
+            while let Some((_, ty)) = read_field_header(r, &mut last_id)? {
+                skip(r, ty, depth + 1)?;
+            }
+        }
+        _ => return format_error(format!("unknown type {}", ty)),
+    }
+    Ok(())
+}
+
+/// Skip an item of a list, set or map.
+fn skip_item<R: Read>(r: &mut R, ty: u8, depth: usize) -> TResult<()> {
+    match ty {
+        // unlike struct fields, booleans items are encoded as a byte
+        BOOLEAN_TRUE | BOOLEAN_FALSE => read_byte(r).map(|_| ()),
+        _ => skip(r, ty, depth),
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::quad::Quad;
+    use sophia_api::term::{TTerm, TermKind};
+
+    /// A minimal encoder for the compact protocol.
+    #[derive(Default)]
+    struct Encoder(Vec<u8>);
+
+    impl Encoder {
+        fn field(&mut self, delta: u8, ty: u8) -> &mut Self {
+            self.0.push((delta << 4) | ty);
+            self
+        }
+        fn stop(&mut self) -> &mut Self {
+            self.0.push(STOP);
+            self
+        }
+        fn varint(&mut self, mut n: u64) -> &mut Self {
+            while n >= 0x80 {
+                self.0.push(n as u8 | 0x80);
+                n >>= 7;
+            }
+            self.0.push(n as u8);
+            self
+        }
+        fn zigzag(&mut self, n: i64) -> &mut Self {
+            self.varint(((n << 1) ^ (n >> 63)) as u64)
+        }
+        fn string(&mut self, s: &str) -> &mut Self {
+            self.varint(s.len() as u64);
+            self.0.extend_from_slice(s.as_bytes());
+            self
+        }
+        /// A struct whose fields 1, 2... are the given strings.
+        fn strings(&mut self, strings: &[&str]) -> &mut Self {
+            for s in strings {
+                self.field(1, BINARY).string(s);
+            }
+            self.stop()
+        }
+        /// An RDF_Term with field `id` set to a struct of strings.
+        fn term(&mut self, id: u8, strings: &[&str]) -> &mut Self {
+            self.field(id, STRUCT).strings(strings).stop()
+        }
+    }
+
+    #[test]
+    fn rows() -> Result<(), ThriftError> {
+        let mut e = Encoder::default();
+        // prefix declaration
+        e.field(1, STRUCT)
+            .strings(&["ex", "http://example.org/"])
+            .stop();
+        // triple
+        e.field(2, STRUCT);
+        e.field(1, STRUCT).term(1, &["http://example.org/s"]);
+        e.field(1, STRUCT).term(4, &["ex", "p"]);
+        e.field(1, STRUCT).term(3, &["chat", "fr"]);
+        e.stop().stop();
+        // quad, with an unknown field in the row
+        e.field(3, STRUCT);
+        e.field(1, STRUCT).term(8, &[]);
+        e.field(1, STRUCT).term(1, &["http://example.org/q"]);
+        e.field(1, STRUCT).field(10, I64).zigzag(-42).stop();
+        e.field(1, STRUCT).term(2, &["g"]);
+        e.stop();
+        e.field(12, LIST).0.extend_from_slice(&[0x21, 1, 2]); // [true, false]
+        e.stop();
+        // base (ignored), then quad with values and a typed literal
+        e.field(4, STRUCT).strings(&["http://example.org/"]).stop();
+        e.field(3, STRUCT);
+        e.field(1, STRUCT).field(11, DOUBLE);
+        e.0.extend_from_slice(&1.5f64.to_le_bytes());
+        e.stop();
+        e.field(1, STRUCT).term(8, &[]);
+        e.field(1, STRUCT).field(12, STRUCT);
+        e.field(1, I64)
+            .zigzag(-5)
+            .field(1, I32)
+            .zigzag(3)
+            .stop()
+            .stop();
+        e.field(1, STRUCT).field(3, STRUCT);
+        e.field(1, BINARY)
+            .string("42")
+            .field(3, STRUCT)
+            .strings(&["ex", "Answer"])
+            .stop()
+            .stop();
+        e.stop().stop();
+
+        let quads: Vec<_> = ThriftParser {}.parse(&e.0[..]).collect::<Result<_, _>>()?;
+        assert_eq!(quads.len(), 3);
+
+        let q = &quads[0];
+        assert_eq!(&q.s().value()[..], "http://example.org/s");
+        assert_eq!(&q.p().value()[..], "http://example.org/p");
+        assert_eq!(q.o().language(), Some("fr"));
+        assert!(q.g().is_none());
+
+        let q = &quads[1];
+        assert_eq!(q.s(), quads[0].s());
+        assert_eq!(
+            q.o(),
+            &BoxTerm::new_literal_dt_unchecked("-42", xsd::integer)
+        );
+        assert_eq!(q.g().unwrap().kind(), TermKind::BlankNode);
+
+        let q = &quads[2];
+        assert_eq!(
+            q.s(),
+            &BoxTerm::new_literal_dt_unchecked("1.5e0", xsd::double)
+        );
+        assert_eq!(q.p(), quads[1].p());
+        assert_eq!(
+            q.o(),
+            &BoxTerm::new_literal_dt_unchecked("-0.005", xsd::decimal)
+        );
+        let answer = BoxTerm::new_iri("http://example.org/Answer").unwrap();
+        assert_eq!(q.g(), Some(&BoxTerm::new_literal_dt("42", answer).unwrap()));
+        Ok(())
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(decimal_lexical(12345, 2), "123.45");
+        assert_eq!(decimal_lexical(-12345, 7), "-0.0012345");
+        assert_eq!(decimal_lexical(12, -2), "1200.0");
+        assert_eq!(decimal_lexical(0, 0), "0.0");
+        assert_eq!(double_lexical(-0.25), "-2.5e-1");
+        assert_eq!(double_lexical(f64::NEG_INFINITY), "-INF");
+    }
+
+    #[test]
+    fn errors() {
+        let parse = |e: &Encoder| -> Vec<_> { ThriftParser {}.parse(&e.0[..]).collect() };
+
+        // truncated data
+        let mut e = Encoder::default();
+        e.field(2, STRUCT).field(1, STRUCT).field(1, STRUCT);
+        e.field(1, BINARY).string("http://example.org/s");
+        e.0.truncate(e.0.len() - 3);
+        let res = parse(&e);
+        assert_eq!(res.len(), 1);
+        assert!(matches!(res[0], Err(ThriftError::Io(_))));
+
+        // repeated term in the first triple, followed by a valid triple
+        let mut e = Encoder::default();
+        for _ in 0..2 {
+            e.field(2, STRUCT);
+            e.field(1, STRUCT).term(8, &[]);
+            e.field(1, STRUCT).term(1, &["http://example.org/p"]);
+            e.field(1, STRUCT).term(1, &["http://example.org/o"]);
+            e.stop().stop();
+        }
+        let res = parse(&e);
+        assert_eq!(res.len(), 1);
+        assert!(matches!(&res[0], Err(ThriftError::Format(msg)) if msg == "no term to repeat"));
+
+        // undeclared prefix
+        let mut e = Encoder::default();
+        e.field(2, STRUCT).field(1, STRUCT).term(4, &["ex", "s"]);
+        let res = parse(&e);
+        assert!(matches!(&res[0], Err(ThriftError::Format(msg)) if msg.contains("'ex'")));
+
+        // triple term
+        let mut e = Encoder::default();
+        e.field(2, STRUCT).field(1, STRUCT).field(9, STRUCT);
+        let res = parse(&e);
+        assert!(matches!(res[0], Err(ThriftError::Unsupported(_))));
+    }
+}
@@ -1,23 +1,64 @@
 //! Adapter for the TriG parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
 
+use crate::dataset::metadata::DatasetMetadata;
 use crate::parser::rio_common::*;
+use crate::parser::turtle::events::EventParser;
 use rio_turtle::{TriGParser as RioTriGParser, TurtleError};
+use sophia_api::dataset::MutableDataset;
 use sophia_api::parser::QuadParser;
+use sophia_api::quad::stream::StreamResult;
+use sophia_term::BoxTerm;
 use std::io::BufRead;
 
 /// TriG parser based on RIO.
 #[derive(Clone, Debug, Default)]
 pub struct TriGParser {
-    /// The base IRI used by this parser to resolve relative IRI-references.
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    ///
+    /// If it is not a valid absolute IRI, the source produced by this parser fails immediately.
+    /// If it is `None`, relative IRI-references are errors,
+    /// unless a base IRI is declared in the data.
     pub base: Option<String>,
 }
 
 impl<B: BufRead> QuadParser<B> for TriGParser {
     type Source = StrictRioSource<RioTriGParser<B>, TurtleError>;
     fn parse(&self, data: B) -> Self::Source {
-        // TODO issue TurtleError if base can not be parsed
-        let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        StrictRioSource::Parser(RioTriGParser::new(data, base))
+        parse_base(&self.base)
+            .map(|base| RioTriGParser::new(data, base))
+            .map_err(TurtleError::from)
+            .into()
+    }
+}
+
+impl TriGParser {
+    /// Parse the TriG document `txt` into `dataset`,
+    /// and record its base IRI and prefixes in the [metadata]
+    /// of every graph receiving quads from `txt`.
+    ///
+    /// Prefix declarations are added to those already recorded for each graph.
+    /// The source and last modification time are left unchanged,
+    /// as they can not be known from `txt`.
+    ///
+    /// Return the number of quads actually inserted
+    /// (i.e. not already present in `dataset`).
+    ///
+    /// [metadata]: ../../dataset/metadata/index.html
+    pub fn load_into<D>(
+        &self,
+        txt: &str,
+        dataset: &mut D,
+        metadata: &mut DatasetMetadata,
+    ) -> StreamResult<usize, TurtleError, D::MutationError>
+    where
+        D: MutableDataset,
+    {
+        let parser = EventParser {
+            base: self.base.clone(),
+            trig: true,
+        };
+        parser.load_into(txt, None as Option<&BoxTerm>, dataset, metadata)
     }
 }
 
@@ -33,8 +74,10 @@ mod test {
     use crate::dataset::inmem::FastDataset;
     use crate::dataset::Dataset;
     use crate::quad::stream::QuadSource;
+    use crate::quad::Quad;
     use sophia_api::ns::{rdf, xsd};
     use sophia_api::term::matcher::ANY;
+    use sophia_api::term::TTerm;
     use sophia_term::StaticTerm;
 
     #[test]
@@ -85,4 +128,68 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_graph_blocks_and_default_graph() -> std::result::Result<(), Box<dyn std::error::Error>>
+    {
+        let trig = r#"
+            PREFIX : <http://example.org/ns/>
+
+            :alice :knows :bob .
+            {
+                :bob :knows :alice .
+            }
+            GRAPH :g1 {
+                :alice :name "Alice" .
+            }
+            GRAPH _:g2 {
+                :bob :name "Bob" .
+            }
+            :g1 { :alice a :Person . }
+        "#;
+
+        let d: FastDataset = TriGParser::default().parse_str(trig).collect_quads()?;
+        assert_eq!(d.quads().count(), 5);
+        let no_graph: Option<&StaticTerm> = None;
+        assert_eq!(d.quads_with_g(no_graph).count(), 2);
+        let g1 = StaticTerm::new_iri("http://example.org/ns/g1").unwrap();
+        assert_eq!(d.quads_with_g(Some(&g1)).count(), 2);
+        let g2: Vec<_> = d
+            .quads_matching(
+                &ANY,
+                &StaticTerm::new_iri("http://example.org/ns/name").unwrap(),
+                &StaticTerm::new_literal_dt("Bob", xsd::string).unwrap(),
+                &ANY,
+            )
+            .map(|q| q.unwrap().g().unwrap().kind())
+            .collect();
+        assert_eq!(g2, vec![sophia_api::term::TermKind::BlankNode]);
+        Ok(())
+    }
+
+    #[test]
+    fn load_into_with_metadata() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let trig = r#"
+            PREFIX ex: <http://example.org/ns#>
+            ex:g1 { ex:s ex:p ex:o . }
+            ex:g2 { }
+        "#;
+        let mut d = FastDataset::new();
+        let mut md = DatasetMetadata::new();
+        let n = TriGParser::default().load_into(trig, &mut d, &mut md)?;
+        assert_eq!(n, 1);
+        let g1 = StaticTerm::new_iri("http://example.org/ns#g1")?;
+        let g2 = StaticTerm::new_iri("http://example.org/ns#g2")?;
+        assert_eq!(d.quads_with_g(Some(&g1)).count(), 1);
+        // only the graphs receiving quads get metadata
+        assert_eq!(md.len(), 1);
+        assert!(md.get(Some(&g2)).is_none());
+        let gmd = md.get(Some(&g1)).unwrap();
+        assert_eq!(gmd.base, None);
+        assert_eq!(
+            gmd.prefixes,
+            vec![("ex".to_string(), "http://example.org/ns#".to_string())]
+        );
+        Ok(())
+    }
 }
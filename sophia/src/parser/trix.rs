@@ -0,0 +1,453 @@
+//! Parser for [TriX], an XML serialization of RDF datasets.
+//!
+//! A TriX document is a sequence of `<graph>` elements,
+//! each having an optional name (`<uri>` or `<id>`) followed by `<triple>` elements.
+//! Graphs without a name are parsed into the default graph.
+//!
+//! The document is parsed in a streaming fashion,
+//! so that large dumps can be loaded without being held in memory.
+//! Namespaces are not checked: elements are recognized by their local name.
+//!
+//! [TriX]: https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html
+
+use std::io::BufRead;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::{Location, QuadParser, WithLocation};
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::term_eq;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The namespace of TriX elements.
+pub const TRIX_NS: &str = "http://www.w3.org/2004/03/trix/trix-1/";
+
+/// TriX parser.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct TrixParser {}
+
+impl<B: BufRead> QuadParser<B> for TrixParser {
+    type Source = TrixSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        TrixSource::new(data)
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(TrixParser, QuadParser);
+
+/// Errors raised by the [`TrixParser`](struct.TrixParser.html).
+#[derive(Debug, Error)]
+pub enum TrixError {
+    /// The data is not well-formed XML (or could not be read).
+    #[error("Invalid XML at byte {offset}: {source}")]
+    Xml {
+        /// The underlying error.
+        source: quick_xml::Error,
+        /// The byte offset of the error.
+        offset: usize,
+    },
+    /// The data is well-formed XML, but not valid TriX.
+    #[error("Invalid TriX at byte {offset}: {message}")]
+    Syntax {
+        /// A description of the error.
+        message: String,
+        /// The byte offset of the error.
+        offset: usize,
+    },
+}
+
+impl WithLocation for TrixError {
+    fn location(&self) -> Location {
+        match self {
+            TrixError::Xml { offset, .. } => Location::from_offset(*offset),
+            TrixError::Syntax { offset, .. } => Location::from_offset(*offset),
+        }
+    }
+}
+
+type TResult<T> = Result<T, TrixError>;
+
+/// The [`QuadSource`](../../quad/stream/trait.QuadSource.html)
+/// returned by [`TrixParser`](struct.TrixParser.html).
+///
+/// Parsing stops after the first error.
+pub struct TrixSource<B: BufRead> {
+    reader: Reader<B>,
+    buf: Vec<u8>,
+    state: State,
+    graph: Option<BoxTerm>,
+    terms: Vec<BoxTerm>,
+}
+
+/// Where the parser is in the TriX document.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Before the `<TriX>` element.
+    Start,
+    /// Inside `<TriX>`.
+    Root,
+    /// Inside `<graph>`, before its name and first triple.
+    GraphStart,
+    /// Inside `<graph>`.
+    Graph,
+    /// Inside `<triple>`.
+    Triple,
+    /// After `</TriX>`.
+    End,
+    /// After the end of the document or an error.
+    Done,
+}
+
+/// A simplified XML event.
+enum Node {
+    Start {
+        name: String,
+        /// The content of the tag, between `<` and `>`.
+        raw: String,
+        attributes: Vec<(String, String)>,
+    },
+    End(String),
+    Text {
+        text: String,
+        /// The text as it appears in the document (i.e. escaped).
+        raw: String,
+    },
+    Eof,
+}
+
+impl<B: BufRead> TrixSource<B> {
+    fn new(data: B) -> Self {
+        let mut reader = Reader::from_reader(data);
+        // empty elements are read as a Start and an End event,
+        // so that their depth is tracked like any other element in XML literals
+        reader.expand_empty_elements(true);
+        TrixSource {
+            reader,
+            buf: Vec::new(),
+            state: State::Start,
+            graph: None,
+            terms: Vec::with_capacity(3),
+        }
+    }
+
+    /// Read events until a quad is complete, or the document ends.
+    fn next_quad(&mut self) -> TResult<Option<TupleQuad<BoxTerm>>> {
+        loop {
+            let node = self.next_node()?;
+            match (self.state, node) {
+                (_, Node::Text { text, .. }) => {
+                    if !text.trim().is_empty() {
+                        return Err(self.error("unexpected text"));
+                    }
+                }
+                (State::End, Node::Eof) => {
+                    self.state = State::Done;
+                    return Ok(None);
+                }
+                (_, Node::Eof) => return Err(self.error("unexpected end of document")),
+                (State::Start, Node::Start { name, .. }) if name == "TriX" => {
+                    self.state = State::Root;
+                }
+                (State::Root, Node::Start { name, .. }) if name == "graph" => {
+                    self.graph = None;
+                    self.state = State::GraphStart;
+                }
+                (
+                    State::GraphStart,
+                    Node::Start {
+                        name, attributes, ..
+                    },
+                ) if name == "uri" || name == "id" => {
+                    self.graph = Some(self.term(&name, &attributes)?);
+                    self.state = State::Graph;
+                }
+                (State::GraphStart, Node::Start { name, .. })
+                | (State::Graph, Node::Start { name, .. })
+                    if name == "triple" =>
+                {
+                    self.terms.clear();
+                    self.state = State::Triple;
+                }
+                (
+                    State::Triple,
+                    Node::Start {
+                        name, attributes, ..
+                    },
+                ) => {
+                    if self.terms.len() == 3 {
+                        return Err(self.error("too many terms in triple"));
+                    }
+                    let term = self.term(&name, &attributes)?;
+                    self.terms.push(term);
+                }
+                (State::Triple, Node::End(_)) => {
+                    if self.terms.len() != 3 {
+                        return Err(self.error("not enough terms in triple"));
+                    }
+                    self.state = State::Graph;
+                    let mut terms = self.terms.drain(..);
+                    let spo = [
+                        terms.next().unwrap(),
+                        terms.next().unwrap(),
+                        terms.next().unwrap(),
+                    ];
+                    return Ok(Some((spo, self.graph.clone())));
+                }
+                (State::GraphStart, Node::End(_)) | (State::Graph, Node::End(_)) => {
+                    self.state = State::Root;
+                }
+                (State::Root, Node::End(_)) => {
+                    self.state = State::End;
+                }
+                (_, Node::Start { name, .. }) => {
+                    return Err(self.error(&format!("unexpected element <{}>", name)));
+                }
+                (_, Node::End(name)) => {
+                    return Err(self.error(&format!("unexpected end of element <{}>", name)));
+                }
+            }
+        }
+    }
+
+    /// Parse the content of the term element `name`, whose start tag has just been read.
+    fn term(&mut self, name: &str, attributes: &[(String, String)]) -> TResult<BoxTerm> {
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        let datatype = match name {
+            "uri" | "id" | "plainLiteral" => None,
+            "typedLiteral" => match attribute("datatype") {
+                Some(dt) => Some(BoxTerm::new_iri(dt).map_err(|e| self.error(&e.to_string()))?),
+                None => return Err(self.error("typedLiteral without datatype")),
+            },
+            _ => return Err(self.error(&format!("unexpected element <{}>", name))),
+        };
+        // the content of XML literals is kept as is, including markup
+        let xml_literal = datatype
+            .as_ref()
+            .is_some_and(|dt| term_eq(dt, &rdf::XMLLiteral));
+        let mut content = String::new();
+        let mut depth = 0;
+        loop {
+            match self.next_node()? {
+                Node::Text { text, raw } => {
+                    content.push_str(if xml_literal { &raw } else { &text });
+                }
+                Node::Start { raw, .. } if xml_literal => {
+                    depth += 1;
+                    content.push('<');
+                    content.push_str(&raw);
+                    content.push('>');
+                }
+                Node::End(name) if depth > 0 => {
+                    depth -= 1;
+                    content.push_str("</");
+                    content.push_str(&name);
+                    content.push('>');
+                }
+                Node::End(_) => break,
+                Node::Start { name, .. } => {
+                    return Err(self.error(&format!("unexpected element <{}>", name)));
+                }
+                Node::Eof => return Err(self.error("unexpected end of document")),
+            }
+        }
+        let term = match (name, datatype) {
+            ("uri", _) => BoxTerm::new_iri(content.trim()),
+            ("id", _) => BoxTerm::new_bnode(content.trim()),
+            (_, Some(datatype)) => BoxTerm::new_literal_dt(content, datatype),
+            _ => match attribute("xml:lang") {
+                Some(lang) => BoxTerm::new_literal_lang(content, lang),
+                None => Ok(BoxTerm::new_literal_dt_unchecked(content, xsd::string)),
+            },
+        };
+        term.map_err(|e| self.error(&e.to_string()))
+    }
+
+    /// Read the next relevant XML event.
+    fn next_node(&mut self) -> TResult<Node> {
+        loop {
+            self.buf.clear();
+            let offset = self.reader.buffer_position();
+            let xml_error = |source| TrixError::Xml { source, offset };
+            let node = match self.reader.read_event(&mut self.buf) {
+                Err(err) => return Err(xml_error(err)),
+                Ok(Event::Start(e)) => {
+                    let mut attributes = vec![];
+                    for attr in e.attributes().with_checks(true) {
+                        let attr = attr.map_err(xml_error)?;
+                        let key = utf8(attr.key, offset)?;
+                        let value = attr
+                            .unescape_and_decode_value(&self.reader)
+                            .map_err(xml_error)?;
+                        attributes.push((key, value));
+                    }
+                    Node::Start {
+                        name: utf8(e.local_name(), offset)?,
+                        raw: utf8(&e, offset)?,
+                        attributes,
+                    }
+                }
+                Ok(Event::End(e)) => Node::End(utf8(e.name(), offset)?),
+                Ok(Event::Text(e)) => Node::Text {
+                    text: e.unescape_and_decode(&self.reader).map_err(xml_error)?,
+                    raw: utf8(&e, offset)?,
+                },
+                // the content of CData events is stored escaped
+                Ok(Event::CData(e)) => Node::Text {
+                    text: e.unescape_and_decode(&self.reader).map_err(xml_error)?,
+                    raw: utf8(&e, offset)?,
+                },
+                Ok(Event::Eof) => Node::Eof,
+                // comments, processing instructions, declarations
+                Ok(_) => continue,
+            };
+            return Ok(node);
+        }
+    }
+
+    fn error(&self, message: &str) -> TrixError {
+        TrixError::Syntax {
+            message: message.to_string(),
+            offset: self.reader.buffer_position(),
+        }
+    }
+}
+
+fn utf8(bytes: &[u8], offset: usize) -> TResult<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| TrixError::Syntax {
+        message: "invalid UTF-8".to_string(),
+        offset,
+    })
+}
+
+impl<B: BufRead> Iterator for TrixSource<B> {
+    type Item = TResult<TupleQuad<BoxTerm>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state == State::Done {
+            return None;
+        }
+        match self.next_quad() {
+            Ok(quad) => quad.map(Ok),
+            Err(err) => {
+                self.state = State::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::quad::stream::QuadSource;
+    use sophia_api::term::matcher::ANY;
+    use sophia_term::StaticTerm;
+
+    #[test]
+    fn test_simple_trix_string() -> Result<(), Box<dyn std::error::Error>> {
+        let trix = r#"<?xml version="1.0" encoding="utf-8"?>
+        <TriX xmlns="http://www.w3.org/2004/03/trix/trix-1/">
+          <!-- the default graph -->
+          <graph>
+            <triple>
+              <uri>http://example.org/alice</uri>
+              <uri>http://example.org/knows</uri>
+              <id>bob</id>
+            </triple>
+          </graph>
+          <graph>
+            <uri>http://example.org/g1</uri>
+            <triple>
+              <id>bob</id>
+              <uri>http://example.org/name</uri>
+              <plainLiteral xml:lang="en">Bob &amp; co</plainLiteral>
+            </triple>
+            <triple>
+              <id>bob</id>
+              <uri>http://example.org/age</uri>
+              <typedLiteral datatype="http://www.w3.org/2001/XMLSchema#integer">42</typedLiteral>
+            </triple>
+            <triple>
+              <id>bob</id>
+              <uri>http://example.org/bio</uri>
+              <typedLiteral datatype="http://www.w3.org/1999/02/22-rdf-syntax-ns#XMLLiteral"><p>Born in <b>1970</b> &amp; <br/>alive</p></typedLiteral>
+            </triple>
+            <triple>
+              <id>bob</id>
+              <uri>http://example.org/nick</uri>
+              <plainLiteral><![CDATA[<bobby>]]></plainLiteral>
+            </triple>
+          </graph>
+        </TriX>
+        "#;
+        let d: FastDataset = parse_str(trix).collect_quads()?;
+        assert_eq!(d.quads().count(), 5);
+        let g1 = StaticTerm::new_iri("http://example.org/g1")?;
+        assert_eq!(d.quads_with_g(None as Option<&BoxTerm>).count(), 1);
+        assert_eq!(d.quads_with_g(Some(&g1)).count(), 4);
+        let bob = BoxTerm::new_bnode("bob")?;
+        for (p, o) in &[
+            ("name", BoxTerm::new_literal_lang("Bob & co", "en")?),
+            ("age", BoxTerm::new_literal_dt("42", xsd::integer)?),
+            (
+                "bio",
+                BoxTerm::new_literal_dt(
+                    "<p>Born in <b>1970</b> &amp; <br></br>alive</p>",
+                    rdf::XMLLiteral,
+                )?,
+            ),
+            ("nick", BoxTerm::new_literal_dt("<bobby>", xsd::string)?),
+        ] {
+            let p = BoxTerm::new_iri(format!("http://example.org/{}", p))?;
+            assert_eq!(d.quads_matching(&bob, &p, o, &ANY).count(), 1, "{:?}", o);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for trix in &[
+            "",
+            "<graph/>",
+            "<TriX><graph><triple><uri>a:b</uri><uri>a:c</uri></triple></graph></TriX>",
+            "<TriX><graph><triple><uri>a:b</uri><uri>a:c</uri><uri>a:d</uri><uri>a:e</uri></triple></graph></TriX>",
+            "<TriX><graph><triple><uri>a:b</uri><uri>a:c</uri><foo/></triple></graph></TriX>",
+            "<TriX><graph><triple><uri>a:b</uri><uri>a:c</uri><typedLiteral>x</typedLiteral></triple></graph></TriX>",
+            "<TriX><graph><triple><uri>a:b</uri><uri>a:c</uri><uri>not an IRI</uri></triple></graph></TriX>",
+            "<TriX><graph>text</graph></TriX>",
+            "<TriX><graph></TriX>",
+            "<TriX><graph>",
+        ] {
+            let res: Result<FastDataset, _> = parse_str(trix).collect_quads();
+            assert!(res.is_err(), "{:?} should fail", trix);
+        }
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let trix = "<TriX><graph>
+            <triple><uri>a:b</uri><uri>a:c</uri><uri>a:d</uri></triple>
+            <triple><uri>a:b</uri><uri>a:c</uri></triple>
+            <triple><uri>a:b</uri><uri>a:c</uri><uri>a:e</uri></triple>
+        </graph></TriX>";
+        let results: Vec<_> = parse_str(trix).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+}
@@ -2,25 +2,71 @@
 
 use std::io::BufRead;
 
+use crate::dataset::metadata::DatasetMetadata;
 use crate::parser::rio_common::*;
 use rio_api::parser::ParseError;
 use rio_turtle::{TurtleError, TurtleParser as RioTurtleParser};
+use sophia_api::dataset::MutableDataset;
 use sophia_api::parser::{Location, TripleParser, WithLocation};
+use sophia_api::quad::stream::StreamResult;
+use sophia_api::term::TTerm;
 use thiserror::Error;
 
+pub mod cst;
+pub mod events;
+
+use events::EventParser;
+
 /// Turtle parser based on RIO.
 #[derive(Clone, Debug, Default)]
 pub struct TurtleParser {
-    /// The base IRI used by this parser to resolve relative IRI-references.
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    ///
+    /// If it is not a valid absolute IRI, the source produced by this parser fails immediately.
+    /// If it is `None`, relative IRI-references are errors,
+    /// unless a base IRI is declared in the data.
     pub base: Option<String>,
 }
 
 impl<B: BufRead> TripleParser<B> for TurtleParser {
     type Source = StrictRioSource<RioTurtleParser<B>, TurtleError>;
     fn parse(&self, data: B) -> Self::Source {
-        // TODO issue TurtleError if base can not be parsed
-        let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        StrictRioSource::Parser(RioTurtleParser::new(data, base))
+        parse_base(&self.base)
+            .map(|base| RioTurtleParser::new(data, base))
+            .map_err(TurtleError::from)
+            .into()
+    }
+}
+
+impl TurtleParser {
+    /// Parse the Turtle document `txt` into the graph `graph_name` of `dataset`,
+    /// and record its base IRI and prefixes in the [metadata] of that graph.
+    ///
+    /// Prefix declarations are added to those already recorded for that graph.
+    /// The source and last modification time are left unchanged,
+    /// as they can not be known from `txt`.
+    ///
+    /// Return the number of quads actually inserted
+    /// (i.e. not already present in `dataset`).
+    ///
+    /// [metadata]: ../../dataset/metadata/index.html
+    pub fn load_into<D, T>(
+        &self,
+        txt: &str,
+        graph_name: Option<&T>,
+        dataset: &mut D,
+        metadata: &mut DatasetMetadata,
+    ) -> StreamResult<usize, TurtleError, D::MutationError>
+    where
+        D: MutableDataset,
+        T: TTerm + ?Sized,
+    {
+        let parser = EventParser {
+            base: self.base.clone(),
+            trig: false,
+        };
+        parser.load_into(txt, graph_name, dataset, metadata)
     }
 }
 
@@ -36,10 +82,10 @@ impl WithLocation for SophiaTurtleError {
     fn location(&self) -> Location {
         match self.0.textual_position() {
             None => Location::Unknown,
-            Some(pos) => Location::from_lico(
-                pos.line_number() as usize + 1,
-                pos.byte_number() as usize + 1,
-            ),
+            // Rio already counts lines and columns from 1
+            Some(pos) => {
+                Location::from_lico(pos.line_number() as usize, pos.byte_number() as usize)
+            }
         }
     }
 }
@@ -53,12 +99,14 @@ sophia_api::def_mod_functions_for_bufread_parser!(TurtleParser, TripleParser);
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
     use crate::graph::inmem::FastGraph;
     use crate::graph::Graph;
     use crate::triple::stream::TripleSource;
     use sophia_api::ns::{rdf, xsd};
     use sophia_api::term::matcher::ANY;
-    use sophia_term::StaticTerm;
+    use sophia_term::{BoxTerm, StaticTerm};
 
     #[test]
     fn test_simple_turtle_string() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -100,4 +148,59 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn base_iri() {
+        let ttl = "<a> <b> <../c> .";
+        let p = TurtleParser {
+            base: Some("http://example.org/x/y".into()),
+        };
+        let g: FastGraph = p.parse_str(ttl).collect_triples().unwrap();
+        let c = StaticTerm::new_iri("http://example.org/c").unwrap();
+        assert_eq!(g.triples_with_o(&c).count(), 1);
+
+        // relative IRIs without base
+        let res: Result<FastGraph, _> = TurtleParser::default().parse_str(ttl).collect_triples();
+        assert!(res.is_err());
+
+        // invalid base IRI
+        let p = TurtleParser {
+            base: Some("not an IRI".into()),
+        };
+        let res: Result<FastGraph, _> = p.parse_str("<tag:a> <tag:b> <tag:c>.").collect_triples();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn error_location() {
+        let turtle = "<tag:s> <tag:p> <tag:o>.\n<tag:s> <tag:p> .\n";
+        let res: Result<Vec<[sophia_term::BoxTerm; 3]>, _> =
+            TurtleParser::default().parse_str(turtle).collect_triples();
+        let err = SophiaTurtleError(res.unwrap_err().unwrap_source_error());
+        assert_eq!(err.location().to_string(), "2:17");
+    }
+
+    #[test]
+    fn load_into_with_metadata() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let ttl = "@base <http://example.org/> .\n@prefix ex: <ns#> .\nex:s ex:p <o> .\n";
+        let g = BoxTerm::new_iri("http://example.org/g")?;
+        let mut d = FastDataset::new();
+        let mut md = DatasetMetadata::new();
+        md.get_or_default(Some(&g))
+            .add_prefix("old", "http://example.org/old#");
+        let n = TurtleParser::default().load_into(ttl, Some(&g), &mut d, &mut md)?;
+        assert_eq!(n, 1);
+        assert_eq!(d.quads_with_g(Some(&g)).count(), 1);
+        assert_eq!(md.len(), 1);
+        let gmd = md.get(Some(&g)).unwrap();
+        assert_eq!(gmd.base.as_deref(), Some("http://example.org/"));
+        assert_eq!(
+            gmd.prefixes,
+            vec![
+                ("old".to_string(), "http://example.org/old#".to_string()),
+                ("ex".to_string(), "http://example.org/ns#".to_string()),
+            ]
+        );
+        Ok(())
+    }
 }
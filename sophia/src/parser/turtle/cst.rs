@@ -0,0 +1,443 @@
+//! **Experimental** round-trip mode for Turtle documents.
+//!
+//! A [`TurtleDocument`] keeps the original text of a Turtle document,
+//! split into *chunks* (directives, statements, and the whitespace and comments between them),
+//! together with the triples produced by each statement.
+//! Triples can then be inserted or removed,
+//! and the document can be written back:
+//! all the chunks that were not affected by the changes are output verbatim
+//! (preserving comments, prefix declarations and formatting),
+//! while modified statements are regenerated,
+//! and inserted triples are appended at the end of the document.
+//!
+//! This enables minimal diffs when programmatically editing hand-authored Turtle files.
+//!
+//! Note however that regenerated statements are written in a flat form
+//! (one triple per line, with full IRIs),
+//! and that blank nodes shared between a modified statement and other statements
+//! are only preserved if they are labelled in the original document.
+//!
+//! [`TurtleDocument`]: struct.TurtleDocument.html
+
+use std::io;
+
+use rio_turtle::TurtleError;
+use sophia_api::parser::TripleParser;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::TripleSource;
+use sophia_term::BoxTerm;
+
+use super::TurtleParser;
+use crate::serializer::nt::write_term;
+
+/// A Turtle document that can be edited and written back
+/// with as few changes as possible to its original text.
+///
+/// See [module documentation](index.html) for more details.
+#[derive(Clone, Debug)]
+pub struct TurtleDocument {
+    base: Option<String>,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChunkKind {
+    /// Whitespace and comments
+    Trivia,
+    /// `@prefix`, `@base`, `PREFIX` or `BASE` directive
+    Directive,
+    /// Statement producing triples
+    Statement,
+}
+
+#[derive(Clone, Debug)]
+struct Chunk {
+    kind: ChunkKind,
+    text: String,
+    triples: Vec<[BoxTerm; 3]>,
+    modified: bool,
+}
+
+impl TurtleDocument {
+    /// Parse the given Turtle document, using `base` to resolve relative IRI-references.
+    pub fn parse_str(txt: &str, base: Option<String>) -> Result<TurtleDocument, TurtleError> {
+        let parser = TurtleParser { base: base.clone() };
+        let mut context = String::new();
+        let mut chunks = vec![];
+        for (i, (kind, text)) in split_chunks(txt).into_iter().enumerate() {
+            let triples = match kind {
+                ChunkKind::Trivia => vec![],
+                ChunkKind::Directive => {
+                    context.push_str(text);
+                    context.push('\n');
+                    vec![]
+                }
+                ChunkKind::Statement => {
+                    let statement = format!("{}{}", context, text);
+                    let triples: Vec<[BoxTerm; 3]> = parser
+                        .parse_str(&statement)
+                        .collect_triples()
+                        .map_err(|e| e.unwrap_source_error())?;
+                    triples
+                        .into_iter()
+                        .map(|t| rename_anonymous_bnodes(t, text, i))
+                        .collect()
+                }
+            };
+            chunks.push(Chunk {
+                kind,
+                text: text.to_string(),
+                triples,
+                modified: false,
+            });
+        }
+        Ok(TurtleDocument { base, chunks })
+    }
+
+    /// The base IRI used to parse this document.
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    /// Iterate over all the triples of this document.
+    pub fn triples(&self) -> impl Iterator<Item = &[BoxTerm; 3]> {
+        self.chunks.iter().flat_map(|c| c.triples.iter())
+    }
+
+    /// Whether this document contains the given triple.
+    pub fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.triples().any(|t| matches(t, s, p, o))
+    }
+
+    /// Insert the given triple at the end of this document.
+    ///
+    /// Return `false` if the triple was already present (in which case the document is unchanged).
+    pub fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        if self.contains(s, p, o) {
+            return false;
+        }
+        let triple = [BoxTerm::copy(s), BoxTerm::copy(p), BoxTerm::copy(o)];
+        match self.chunks.last_mut() {
+            Some(c) if c.kind == ChunkKind::Statement && c.modified && c.text.is_empty() => {
+                c.triples.push(triple);
+            }
+            _ => self.chunks.push(Chunk {
+                kind: ChunkKind::Statement,
+                text: String::new(),
+                triples: vec![triple],
+                modified: true,
+            }),
+        }
+        true
+    }
+
+    /// Remove the given triple from this document.
+    ///
+    /// Return `false` if the triple was not present (in which case the document is unchanged).
+    pub fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        for chunk in self.chunks.iter_mut() {
+            if let Some(i) = chunk.triples.iter().position(|t| matches(t, s, p, o)) {
+                chunk.triples.remove(i);
+                chunk.modified = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Write this document to `w`,
+    /// preserving the original text of all unmodified chunks.
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut ends_with_newline = true;
+        for chunk in &self.chunks {
+            if !chunk.modified {
+                w.write_all(chunk.text.as_bytes())?;
+                if !chunk.text.is_empty() {
+                    ends_with_newline = chunk.text.ends_with('\n');
+                }
+                continue;
+            }
+            for (i, [s, p, o]) in chunk.triples.iter().enumerate() {
+                if i == 0 && chunk.text.is_empty() && !ends_with_newline {
+                    // appended statement: make sure it starts on a new line
+                    w.write_all(b"\n")?;
+                } else if i > 0 {
+                    w.write_all(b"\n")?;
+                }
+                write_term(w, s)?;
+                w.write_all(b" ")?;
+                write_term(w, p)?;
+                w.write_all(b" ")?;
+                write_term(w, o)?;
+                w.write_all(b" .")?;
+                ends_with_newline = false;
+            }
+            if chunk.text.is_empty() && !chunk.triples.is_empty() {
+                w.write_all(b"\n")?;
+                ends_with_newline = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write this document into a `String`.
+    pub fn to_turtle_string(&self) -> String {
+        let mut v = Vec::new();
+        self.write_to(&mut v).unwrap(); // writing into a Vec can not fail
+        String::from_utf8(v).unwrap() // original text and terms are UTF-8
+    }
+}
+
+fn matches<TS, TP, TO>(t: &[BoxTerm; 3], s: &TS, p: &TP, o: &TO) -> bool
+where
+    TS: TTerm + ?Sized,
+    TP: TTerm + ?Sized,
+    TO: TTerm + ?Sized,
+{
+    term_eq(&t[0], s) && term_eq(&t[1], p) && term_eq(&t[2], o)
+}
+
+/// Each statement being parsed separately,
+/// the labels generated by the parser for anonymous blank nodes (`[]`)
+/// may collide between statements,
+/// so they are made specific to the chunk they come from.
+pub(super) fn rename_anonymous_bnodes(
+    t: [BoxTerm; 3],
+    text: &str,
+    chunk_index: usize,
+) -> [BoxTerm; 3] {
+    let [s, p, o] = t;
+    let rename = |t: BoxTerm| {
+        if t.kind() == TermKind::BlankNode && !text.contains(&format!("_:{}", t.value())) {
+            BoxTerm::new_bnode_unchecked(format!("{}_{}", t.value(), chunk_index))
+        } else {
+            t
+        }
+    };
+    [rename(s), p, rename(o)]
+}
+
+/// Split `txt` into trivia, directives and statements.
+fn split_chunks(txt: &str) -> Vec<(ChunkKind, &str)> {
+    let bytes = txt.as_bytes();
+    let mut chunks = vec![];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        pos += skip_trivia(&bytes[pos..]);
+        if pos > start {
+            chunks.push((ChunkKind::Trivia, &txt[start..pos]));
+            continue;
+        }
+        let rest = &bytes[pos..];
+        let (kind, len) = match directive_len(rest) {
+            Some(len) => (ChunkKind::Directive, len),
+            None => (ChunkKind::Statement, statement_len(rest)),
+        };
+        chunks.push((kind, &txt[pos..pos + len]));
+        pos += len;
+    }
+    chunks
+}
+
+/// If `bytes` starts with a directive, return its length.
+pub(super) fn directive_len(bytes: &[u8]) -> Option<usize> {
+    if starts_with_ignore_case(bytes, b"prefix") || starts_with_ignore_case(bytes, b"base") {
+        // SPARQL-style directives have no final '.'
+        Some(
+            bytes
+                .iter()
+                .position(|b| *b == b'>')
+                .map(|i| i + 1)
+                .unwrap_or(bytes.len()),
+        )
+    } else if bytes.starts_with(b"@prefix") || bytes.starts_with(b"@base") {
+        Some(statement_len(bytes))
+    } else {
+        None
+    }
+}
+
+/// Return the length of the whitespace and comments at the start of `bytes`.
+pub(super) fn skip_trivia(bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' => i += 1,
+            b'#' => i += line_len(&bytes[i..]),
+            _ => break,
+        }
+    }
+    i
+}
+
+/// Return the length of the statement at the start of `bytes`, including its final '.'.
+///
+/// If the statement is not terminated, the length of `bytes` is returned.
+pub(super) fn statement_len(bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' => {
+                i += bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'>')
+                    .map(|j| j + 1)
+                    .unwrap_or(bytes.len() - i)
+            }
+            q @ b'"' | q @ b'\'' => i += string_len(&bytes[i..], q),
+            // escaped character in a local name
+            b'\\' => i = (i + 2).min(bytes.len()),
+            b'#' => i += line_len(&bytes[i..]),
+            b'.' => {
+                let prev = if i > 0 { Some(bytes[i - 1]) } else { None };
+                let next = bytes.get(i + 1).copied();
+                if !(prev.map(is_name_byte).unwrap_or(false)
+                    && next.map(is_name_byte).unwrap_or(false))
+                {
+                    return i + 1;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Return the length of the string literal at the start of `bytes`,
+/// delimited by `quote` (either simple or triple).
+pub(super) fn string_len(bytes: &[u8], quote: u8) -> usize {
+    let triple = bytes.len() >= 3 && bytes[1] == quote && bytes[2] == quote;
+    let mut i = if triple { 3 } else { 1 };
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if !triple {
+                return i + 1;
+            } else if bytes.len() >= i + 3 && bytes[i + 1] == quote && bytes[i + 2] == quote {
+                return i + 3;
+            }
+            i += 1;
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Return the length of the line at the start of `bytes`, including the final newline.
+pub(super) fn line_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|b| *b == b'\n')
+        .map(|i| i + 1)
+        .unwrap_or(bytes.len())
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'-' || b == b':' || b == b'%' || b >= 0x80
+}
+
+pub(super) fn starts_with_ignore_case(bytes: &[u8], keyword: &[u8]) -> bool {
+    bytes.len() > keyword.len()
+        && bytes[..keyword.len()].eq_ignore_ascii_case(keyword)
+        && bytes[keyword.len()].is_ascii_whitespace()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::xsd;
+    use sophia_term::StaticTerm;
+    use std::collections::HashSet;
+
+    const DOC: &str = r#"# A hand-written document
+@prefix : <http://example.org/ns/> .
+PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+
+# Alice
+:alice a foaf:Person ;   # she is a person
+    foaf:name "Alice. Really." ;
+    foaf:knows [ foaf:name "Bob" ] .
+
+:carol   foaf:age 42.5 ; foaf:knows [] .
+"#;
+
+    #[test]
+    fn unchanged_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let doc = TurtleDocument::parse_str(DOC, None)?;
+        assert_eq!(doc.triples().count(), 6);
+        assert_eq!(doc.to_turtle_string(), DOC);
+        Ok(())
+    }
+
+    #[test]
+    fn anonymous_bnodes_are_not_merged() -> Result<(), Box<dyn std::error::Error>> {
+        let doc = TurtleDocument::parse_str(DOC, None)?;
+        let bnodes: HashSet<_> = doc
+            .triples()
+            .flat_map(|t| t.iter())
+            .filter(|t| t.kind() == TermKind::BlankNode)
+            .map(|t| t.value().to_string())
+            .collect();
+        assert_eq!(bnodes.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_changes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = TurtleDocument::parse_str(DOC, None)?;
+        let carol = StaticTerm::new_iri("http://example.org/ns/carol")?;
+        let age = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/age")?;
+        let name = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/name")?;
+        let carol_name = StaticTerm::new_literal_dt("Carol", xsd::string)?;
+        let age_val = StaticTerm::new_literal_dt("42.5", xsd::decimal)?;
+
+        assert!(doc.remove(&carol, &age, &age_val));
+        assert!(!doc.remove(&carol, &age, &age_val));
+        assert!(doc.insert(&carol, &name, &carol_name));
+        assert!(!doc.insert(&carol, &name, &carol_name));
+
+        let out = doc.to_turtle_string();
+        let alice_part = &DOC[..DOC.find(":carol").unwrap()];
+        assert!(out.starts_with(alice_part));
+        assert!(!out.contains("42.5"));
+        assert!(out.ends_with(
+            "<http://example.org/ns/carol> <http://xmlns.com/foaf/0.1/name> \"Carol\" .\n"
+        ));
+        assert_eq!(doc.triples().count(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn escaped_local_names() -> Result<(), Box<dyn std::error::Error>> {
+        let txt =
+            "@prefix ex: <http://ex.org/> .\nex:s ex:p ex:a\\.b .\nex:s ex:p ex:a\\#b, ex:c\\. .\n";
+        let doc = TurtleDocument::parse_str(txt, None)?;
+        let objects: HashSet<_> = doc.triples().map(|t| t[2].value().to_string()).collect();
+        let expected: HashSet<_> = ["http://ex.org/a.b", "http://ex.org/a#b", "http://ex.org/c."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(objects, expected);
+        assert_eq!(doc.to_turtle_string(), txt);
+        Ok(())
+    }
+}
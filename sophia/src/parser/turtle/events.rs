@@ -0,0 +1,612 @@
+//! Event-level parsing of Turtle and TriG documents.
+//!
+//! The [`TurtleParser`] and [`TriGParser`] only produce triples or quads.
+//! An [`EventParser`] additionally reports the structure of the document,
+//! as a stream of [`Event`]s:
+//! prefix and base declarations, comments, graph blocks (in TriG),
+//! and the triples of each statement, in the order in which they appear.
+//! This is useful for tools that need to preserve this structure,
+//! such as pretty-printers or linters.
+//!
+//! Comments that appear *inside* a statement (or graph block)
+//! are reported after the triples of that statement.
+//!
+//! # Example
+//! ```
+//! # use sophia::parser::turtle::events::{Event, EventParser};
+//! let ttl = "# my data\n@prefix : <http://example.org/> .\n:s :p :o .\n";
+//! let events: Vec<Event> = EventParser::default()
+//!     .parse_str(ttl)
+//!     .collect::<Result<_, _>>()?;
+//! assert_eq!(events.len(), 3);
+//! assert_eq!(events[0], Event::Comment(" my data".into()));
+//! assert_eq!(
+//!     events[1],
+//!     Event::PrefixDecl {
+//!         prefix: "".into(),
+//!         namespace: "http://example.org/".into()
+//!     }
+//! );
+//! assert!(matches!(events[2], Event::Triple(_)));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TurtleParser`]: ../struct.TurtleParser.html
+//! [`TriGParser`]: ../../trig/struct.TriGParser.html
+//! [`EventParser`]: struct.EventParser.html
+//! [`Event`]: enum.Event.html
+
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use rio_turtle::TurtleError;
+use sophia_api::dataset::MutableDataset;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::stream::{QuadSource, SinkError, SourceError, StreamResult};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::stream::TripleSource;
+use sophia_term::BoxTerm;
+
+use super::cst::{
+    directive_len, line_len, rename_anonymous_bnodes, skip_trivia, starts_with_ignore_case,
+    statement_len, string_len,
+};
+use super::TurtleParser;
+use crate::dataset::metadata::{DatasetMetadata, GraphMetadata};
+use crate::parser::trig::TriGParser;
+
+/// A triple with its graph name (if any).
+type BoxQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// An event produced by an [`EventParser`](struct.EventParser.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A prefix declaration (`@prefix` or `PREFIX`).
+    ///
+    /// The namespace is given as written in the document,
+    /// i.e. it is not resolved against the base IRI.
+    PrefixDecl {
+        /// The declared prefix, without the final `:`.
+        prefix: String,
+        /// The namespace associated to the prefix.
+        namespace: String,
+    },
+    /// A base declaration (`@base` or `BASE`),
+    /// with the IRI as written in the document.
+    BaseDecl(String),
+    /// A comment, without the leading `#` nor the final line break.
+    Comment(String),
+    /// The start of a TriG graph block,
+    /// with the name of the graph (`None` for the default graph).
+    GraphStart(Option<BoxTerm>),
+    /// The end of a TriG graph block.
+    GraphEnd,
+    /// A triple, in the graph of the enclosing graph block (if any).
+    Triple([BoxTerm; 3]),
+}
+
+/// A parser producing the [events](enum.Event.html) of a Turtle or TriG document.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct EventParser {
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (see [`TurtleParser::base`](../struct.TurtleParser.html#structfield.base)).
+    pub base: Option<String>,
+    /// Whether the document is in TriG (otherwise, in Turtle).
+    pub trig: bool,
+}
+
+impl EventParser {
+    /// Parse `txt` into a stream of events.
+    ///
+    /// The stream stops after the first error.
+    pub fn parse_str<'a>(&self, txt: &'a str) -> Events<'a> {
+        Events {
+            parser: self.clone(),
+            txt,
+            pos: 0,
+            context: String::new(),
+            chunk_index: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Parse all the triples of `txt`, with their graph name (if any).
+    fn parse_all(&self, txt: &str) -> Result<Vec<BoxQuad>, TurtleError> {
+        if self.trig {
+            TriGParser {
+                base: self.base.clone(),
+            }
+            .parse_str(txt)
+            .collect_quads()
+            .map_err(|e| e.unwrap_source_error())
+        } else {
+            let triples: Vec<[BoxTerm; 3]> = TurtleParser {
+                base: self.base.clone(),
+            }
+            .parse_str(txt)
+            .collect_triples()
+            .map_err(|e| e.unwrap_source_error())?;
+            Ok(triples.into_iter().map(|t| (t, None)).collect())
+        }
+    }
+
+    /// Parse `txt` into `dataset`, and record its base and prefix declarations in `metadata`,
+    /// for every graph receiving triples (and for `graph_name` in Turtle).
+    ///
+    /// Triples outside any graph block go to `graph_name`.
+    /// Namespaces are resolved against the base IRI in effect, if any.
+    /// Return the number of quads actually inserted in `dataset`.
+    pub(crate) fn load_into<D, T>(
+        &self,
+        txt: &str,
+        graph_name: Option<&T>,
+        dataset: &mut D,
+        metadata: &mut DatasetMetadata,
+    ) -> StreamResult<usize, TurtleError, D::MutationError>
+    where
+        D: MutableDataset,
+        T: TTerm + ?Sized,
+    {
+        let default = graph_name.map(BoxTerm::copy);
+        let mut current = default.clone();
+        let mut graphs = if self.trig {
+            vec![]
+        } else {
+            vec![default.clone()]
+        };
+        let mut declared = GraphMetadata {
+            base: self.base.clone(),
+            ..GraphMetadata::default()
+        };
+        let mut inserted = 0;
+        for event in self.parse_str(txt) {
+            match event.map_err(SourceError)? {
+                Event::PrefixDecl { prefix, namespace } => {
+                    let namespace = resolve(declared.base.as_deref(), namespace);
+                    declared.add_prefix(prefix, namespace);
+                }
+                Event::BaseDecl(iri) => {
+                    declared.base = Some(resolve(declared.base.as_deref(), iri));
+                }
+                Event::Comment(_) => (),
+                Event::GraphStart(name) => current = name,
+                Event::GraphEnd => current = default.clone(),
+                Event::Triple([s, p, o]) => {
+                    if !graphs.contains(&current) {
+                        graphs.push(current.clone());
+                    }
+                    if dataset
+                        .insert(&s, &p, &o, current.as_ref())
+                        .map_err(SinkError)?
+                    {
+                        inserted += 1;
+                    }
+                }
+            }
+        }
+        for g in graphs {
+            let gmd = metadata.get_or_default(g.as_ref());
+            gmd.base = declared.base.clone();
+            for (prefix, namespace) in &declared.prefixes {
+                gmd.add_prefix(prefix.clone(), namespace.clone());
+            }
+        }
+        Ok(inserted)
+    }
+}
+
+/// Resolve `iri` against `base`, if possible; otherwise return `iri` unchanged.
+fn resolve(base: Option<&str>, iri: String) -> String {
+    match base.map(|base| oxiri::Iri::parse(base).and_then(|base| base.resolve(&iri))) {
+        Some(Ok(resolved)) => resolved.into_inner(),
+        _ => iri,
+    }
+}
+
+/// The iterator of events returned by
+/// [`EventParser::parse_str`](struct.EventParser.html#method.parse_str).
+#[derive(Debug)]
+pub struct Events<'a> {
+    parser: EventParser,
+    txt: &'a str,
+    pos: usize,
+    /// The directives encountered so far, prepended to each statement before parsing it.
+    context: String,
+    chunk_index: usize,
+    pending: VecDeque<Event>,
+}
+
+impl<'a> Events<'a> {
+    /// Consume the next chunk of the document, pushing its events to `self.pending`.
+    fn next_chunk(&mut self) -> Result<(), TurtleError> {
+        let txt = self.txt;
+        let start = self.pos;
+        let rest = &txt.as_bytes()[start..];
+        let trivia = skip_trivia(rest);
+        if trivia > 0 {
+            self.push_comments(&txt[start..start + trivia]);
+            self.pos += trivia;
+            return Ok(());
+        }
+        self.chunk_index += 1;
+        if let Some(len) = directive_len(rest) {
+            self.pos += len;
+            return self.directive(&txt[start..start + len]);
+        }
+        if self.parser.trig {
+            if let Some((label, len)) = graph_block(rest) {
+                self.pos += len;
+                let label = &txt[start + label.start..start + label.end];
+                return self.graph_block(label, &txt[start..start + len]);
+            }
+        }
+        let len = statement_len(rest);
+        self.pos += len;
+        let text = &txt[start..start + len];
+        self.push_triples(text)?;
+        self.push_comments(text);
+        Ok(())
+    }
+
+    fn directive(&mut self, text: &str) -> Result<(), TurtleError> {
+        self.context.push_str(text);
+        self.context.push('\n');
+        // check that the directive is valid
+        self.parser.parse_all(&self.context)?;
+
+        let keyword = text.trim_start_matches('@');
+        let iri = match (text.find('<'), text.rfind('>')) {
+            (Some(i), Some(j)) if i < j => &text[i + 1..j],
+            _ => "",
+        };
+        let event = if starts_with_ignore_case(keyword.as_bytes(), b"prefix") {
+            let colon = keyword.find(':').unwrap_or(6);
+            Event::PrefixDecl {
+                prefix: keyword[6..colon].trim().to_string(),
+                namespace: iri.to_string(),
+            }
+        } else {
+            Event::BaseDecl(iri.to_string())
+        };
+        self.pending.push_back(event);
+        Ok(())
+    }
+
+    fn graph_block(&mut self, label: &str, text: &str) -> Result<(), TurtleError> {
+        let name = if label.is_empty() {
+            None
+        } else {
+            // parse the label as the subject of a dummy triple
+            let dummy = format!("{}{} <tag:p> <tag:o> .", self.context, label);
+            let triples: Vec<[BoxTerm; 3]> = TurtleParser {
+                base: self.parser.base.clone(),
+            }
+            .parse_str(&dummy)
+            .collect_triples()
+            .map_err(|e| e.unwrap_source_error())?;
+            triples
+                .into_iter()
+                .next()
+                .map(|t| rename_anonymous_bnodes(t, text, self.chunk_index))
+                .map(|[s, _, _]| s)
+        };
+        self.pending.push_back(Event::GraphStart(name));
+        self.push_triples(text)?;
+        self.push_comments(text);
+        self.pending.push_back(Event::GraphEnd);
+        Ok(())
+    }
+
+    fn push_triples(&mut self, text: &str) -> Result<(), TurtleError> {
+        let statement = format!("{}{}", self.context, text);
+        for (t, _) in self.parser.parse_all(&statement)? {
+            let t = rename_anonymous_bnodes(t, text, self.chunk_index);
+            self.pending.push_back(Event::Triple(t));
+        }
+        Ok(())
+    }
+
+    fn push_comments(&mut self, text: &str) {
+        for range in comments(text.as_bytes()) {
+            let comment = text[range].trim_end_matches(&['\n', '\r'][..]);
+            self.pending.push_back(Event::Comment(comment.to_string()));
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event, TurtleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.pos >= self.txt.len() {
+                return None;
+            }
+            if let Err(err) = self.next_chunk() {
+                self.pos = self.txt.len();
+                self.pending.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// If `bytes` starts with a TriG graph block (`[GRAPH] [label] { ... }`),
+/// return the position of its label (possibly empty) and the length of the whole block.
+fn graph_block(bytes: &[u8]) -> Option<(Range<usize>, usize)> {
+    let mut i = 0;
+    if starts_with_ignore_case(bytes, b"graph") {
+        i += 5;
+        i += skip_trivia(&bytes[i..]);
+    }
+    let label_start = i;
+    match bytes.get(i) {
+        Some(b'{') => (),
+        Some(b'<') => i += bytes[i..].iter().position(|b| *b == b'>')? + 1,
+        Some(b'[') => i += bytes[i..].iter().position(|b| *b == b']')? + 1,
+        Some(_) => {
+            while let Some(b) = bytes.get(i) {
+                match b {
+                    // escaped character in a local name
+                    b'\\' => i = (i + 2).min(bytes.len()),
+                    b'{' | b'#' => break,
+                    b if b.is_ascii_whitespace() => break,
+                    _ => i += 1,
+                }
+            }
+        }
+        None => return None,
+    }
+    let label = label_start..i;
+    i += skip_trivia(&bytes[i..]);
+    if bytes.get(i) != Some(&b'{') {
+        return None;
+    }
+    Some((label, i + block_len(&bytes[i..])))
+}
+
+/// Return the length of the block starting with `{` at the start of `bytes`,
+/// including the matching `}`.
+///
+/// If the block is not terminated, the length of `bytes` is returned.
+fn block_len(bytes: &[u8]) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' => {
+                i += bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'>')
+                    .map(|j| j + 1)
+                    .unwrap_or(bytes.len() - i)
+            }
+            q @ b'"' | q @ b'\'' => i += string_len(&bytes[i..], q),
+            // escaped character in a local name
+            b'\\' => i = (i + 2).min(bytes.len()),
+            b'#' => i += line_len(&bytes[i..]),
+            b'{' => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                i += 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Return the position of the comments in `bytes` (without the leading `#`),
+/// skipping IRIs and strings.
+fn comments(bytes: &[u8]) -> Vec<Range<usize>> {
+    let mut comments = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' => {
+                i += bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'>')
+                    .map(|j| j + 1)
+                    .unwrap_or(bytes.len() - i)
+            }
+            q @ b'"' | q @ b'\'' => i += string_len(&bytes[i..], q),
+            // escaped character in a local name
+            b'\\' => i = (i + 2).min(bytes.len()),
+            b'#' => {
+                let len = line_len(&bytes[i..]);
+                comments.push(i + 1..i + len);
+                i += len;
+            }
+            _ => i += 1,
+        }
+    }
+    comments
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::term::{TTerm, TermKind};
+
+    fn events(parser: &EventParser, txt: &str) -> Vec<Event> {
+        parser.parse_str(txt).collect::<Result<_, _>>().unwrap()
+    }
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(txt).unwrap()
+    }
+
+    #[test]
+    fn turtle() {
+        let ttl = r##"# header
+@prefix : <http://example.org/> .
+BASE <http://example.org/base/>
+PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+
+<a> foaf:name "#not a comment" ; # a comment
+    foaf:knows [ foaf:name "Bob" ] .
+:c foaf:knows [] .
+"##;
+        let events = events(&EventParser::default(), ttl);
+        assert_eq!(events.len(), 9, "{:#?}", events);
+        assert_eq!(events[0], Event::Comment(" header".into()));
+        assert_eq!(
+            events[1],
+            Event::PrefixDecl {
+                prefix: "".into(),
+                namespace: "http://example.org/".into(),
+            }
+        );
+        assert_eq!(
+            events[2],
+            Event::BaseDecl("http://example.org/base/".into())
+        );
+        assert_eq!(
+            events[3],
+            Event::PrefixDecl {
+                prefix: "foaf".into(),
+                namespace: "http://xmlns.com/foaf/0.1/".into(),
+            }
+        );
+        let name = iri("http://xmlns.com/foaf/0.1/name");
+        match &events[4] {
+            Event::Triple([s, p, o]) => {
+                assert_eq!(s, &iri("http://example.org/base/a"));
+                assert_eq!(p, &name);
+                assert_eq!(&*o.value(), "#not a comment");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(events[5], Event::Triple(_)));
+        assert!(matches!(events[6], Event::Triple(_)));
+        assert_eq!(events[7], Event::Comment(" a comment".into()));
+        assert!(matches!(events[8], Event::Triple(_)));
+
+        // anonymous blank nodes of different statements are distinct
+        let bnodes: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Triple([_, _, o]) if o.kind() == TermKind::BlankNode => Some(o.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bnodes.len(), 2);
+        assert_ne!(bnodes[0], bnodes[1]);
+    }
+
+    #[test]
+    fn trig() {
+        let trig = r#"@prefix : <http://example.org/> .
+:s :p :o .
+:g1 { :s :p :o1 . :s :p :o2 }
+GRAPH _:g2 {
+    # inside
+    :s :p "}" .
+}
+{ :s :p :o3 }
+"#;
+        let parser = EventParser {
+            trig: true,
+            ..EventParser::default()
+        };
+        let events = events(&parser, trig);
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|e| match e {
+                Event::PrefixDecl { .. } => "prefix",
+                Event::BaseDecl(_) => "base",
+                Event::Comment(_) => "comment",
+                Event::GraphStart(_) => "start",
+                Event::GraphEnd => "end",
+                Event::Triple(_) => "triple",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "prefix", "triple", "start", "triple", "triple", "end", "start", "triple",
+                "comment", "end", "start", "triple", "end",
+            ]
+        );
+        assert_eq!(
+            events[2],
+            Event::GraphStart(Some(iri("http://example.org/g1")))
+        );
+        assert_eq!(
+            events[6],
+            Event::GraphStart(Some(BoxTerm::new_bnode("g2").unwrap()))
+        );
+        assert_eq!(events[8], Event::Comment(" inside".into()));
+        assert_eq!(events[10], Event::GraphStart(None));
+    }
+
+    #[test]
+    fn escaped_local_names() {
+        let ttl = "@prefix ex: <http://ex.org/> .\nex:s ex:p ex:a\\.b .\nex:s ex:p ex:a\\#b .\nex:s ex:p ex:c . # c\n";
+        let ttl_events = events(&EventParser::default(), ttl);
+        assert_eq!(ttl_events.len(), 5, "{:#?}", ttl_events);
+        let objects: Vec<_> = ttl_events[1..4]
+            .iter()
+            .map(|e| match e {
+                Event::Triple([_, _, o]) => o.value().to_string(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            objects,
+            vec!["http://ex.org/a.b", "http://ex.org/a#b", "http://ex.org/c"]
+        );
+        assert_eq!(ttl_events[4], Event::Comment(" c".into()));
+
+        let trig = "@prefix ex: <http://ex.org/> .\nex:g\\#1 { ex:s ex:p ex:a\\#b }\n";
+        let parser = EventParser {
+            trig: true,
+            ..EventParser::default()
+        };
+        let graph_events = events(&parser, trig);
+        assert_eq!(graph_events.len(), 4, "{:#?}", graph_events);
+        assert_eq!(
+            graph_events[1],
+            Event::GraphStart(Some(iri("http://ex.org/g#1")))
+        );
+        assert!(
+            matches!(&graph_events[2], Event::Triple([_, _, o]) if &*o.value() == "http://ex.org/a#b")
+        );
+        assert_eq!(graph_events[3], Event::GraphEnd);
+    }
+
+    #[test]
+    fn errors() {
+        let parser = EventParser::default();
+        let results: Vec<_> = parser
+            .parse_str("@prefix : <tag:> .\n:s :p .\n:s :p :o .")
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+
+        assert!(parser
+            .parse_str("@prefix : <not an iri> .")
+            .any(|r| r.is_err()));
+        // graphs are only recognized in TriG
+        assert!(parser
+            .parse_str("<tag:g> { <tag:s> <tag:p> <tag:o> }")
+            .any(|r| r.is_err()));
+    }
+}
@@ -9,19 +9,25 @@ use rio_xml::{RdfXmlError, RdfXmlParser as RioRdfXmlParser};
 use crate::parser::rio_common::*;
 use crate::parser::TripleParser;
 
-/// N-Triples parser based on RIO.
+/// RDF/XML parser based on RIO.
 #[derive(Clone, Debug, Default)]
 pub struct RdfXmlParser {
-    /// The base IRI used by this parser to resolve relative IRI-references.
+    /// The base IRI used by this parser to resolve relative IRI-references
+    /// (according to [RFC 3986](https://tools.ietf.org/html/rfc3986#section-5)).
+    ///
+    /// If it is not a valid absolute IRI, the source produced by this parser fails immediately.
+    /// If it is `None`, relative IRI-references are errors,
+    /// unless a base IRI is declared in the data.
     pub base: Option<String>,
 }
 
 impl<B: BufRead> TripleParser<B> for RdfXmlParser {
     type Source = StrictRioSource<RioRdfXmlParser<B>, RdfXmlError>;
     fn parse(&self, data: B) -> Self::Source {
-        // TODO issue RdfXmlError if base can not be parsed
-        let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        StrictRioSource::Parser(RioRdfXmlParser::new(data, base))
+        parse_base(&self.base)
+            .map(|base| RioRdfXmlParser::new(data, base))
+            .map_err(|e| RdfXmlError::from(quick_xml::Error::Io(e)))
+            .into()
     }
 }
 
@@ -38,6 +44,8 @@ mod test {
     use crate::graph::Graph;
     use crate::ns::{rdf, xsd};
     use crate::triple::stream::TripleSource;
+    use crate::triple::Triple;
+    use sophia_api::term::TTerm;
     use sophia_term::matcher::ANY;
     use sophia_term::StaticTerm;
 
@@ -88,4 +96,56 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_full_syntax() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let xml = r##"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns:ex="http://example.org/ns/"
+                 xml:base="http://example.org/doc">
+          <rdf:Description rdf:about="#me" ex:name="Pierre">
+            <ex:homepage rdf:resource="/home"/>
+            <ex:friends>
+              <rdf:Bag>
+                <rdf:li rdf:resource="#alice"/>
+                <rdf:li rdf:resource="#bob"/>
+              </rdf:Bag>
+            </ex:friends>
+            <ex:list rdf:parseType="Collection">
+              <rdf:Description rdf:about="#a"/>
+              <rdf:Description rdf:about="#b"/>
+            </ex:list>
+            <ex:bio rdf:parseType="Literal"><b>hi</b></ex:bio>
+          </rdf:Description>
+        </rdf:RDF>
+        "##;
+
+        let mut g = FastGraph::new();
+        let c = RdfXmlParser::default()
+            .parse_str(xml)
+            .add_to_graph(&mut g)?;
+        assert_eq!(c, 12);
+
+        let me = StaticTerm::new_iri("http://example.org/doc#me").unwrap();
+        let ex = |suffix: &'static str| {
+            StaticTerm::new_iri_suffixed("http://example.org/ns/", suffix).unwrap()
+        };
+        assert!(g.contains(
+            &me,
+            &ex("name"),
+            &StaticTerm::new_literal_dt("Pierre", xsd::string).unwrap()
+        )?);
+        assert!(g.contains(
+            &me,
+            &ex("homepage"),
+            &StaticTerm::new_iri("http://example.org/home").unwrap()
+        )?);
+        assert_eq!(g.triples_matching(&ANY, &rdf::type_, &rdf::Bag).count(), 1);
+        assert_eq!(g.triples_matching(&ANY, &rdf::first, &ANY).count(), 2);
+        assert_eq!(g.triples_matching(&ANY, &rdf::rest, &rdf::nil).count(), 1);
+        let bio = ex("bio");
+        let bio = g.triples_matching(&me, &bio, &ANY).next().unwrap()?;
+        assert_eq!(bio.o().datatype().unwrap().value(), rdf::XMLLiteral.value());
+        Ok(())
+    }
 }
@@ -0,0 +1,835 @@
+//! Import pipelines: source → transforms → dataset.
+//!
+//! Importing data usually involves more than [`insert_all`]:
+//! quads are parsed from some files, transformed (e.g. IRIs renamed, blank nodes skolemized),
+//! validated, and only then inserted into a dataset;
+//! and each of those steps needs to decide what to do with errors.
+//! A [`Pipeline`] describes these steps declaratively,
+//! each [`Transform`] with its own [`ErrorPolicy`],
+//! and [runs](struct.Pipeline.html#method.run) them on any source of quads,
+//! returning a [`Report`] with the metrics of each step.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::parser::any::{self, Format};
+//! # use sophia::pipeline::*;
+//! let ttl = "@prefix old: <http://old.example.org/>. _:b old:p old:o, 42.";
+//! let mut pipeline = Pipeline::new();
+//! pipeline
+//!     .add_step(Rename::new("http://old.example.org/", "http://example.org/"), ErrorPolicy::Abort)
+//!     .add_step(Skolemize::new("http://example.org/.well-known/genid/"), ErrorPolicy::Abort)
+//!     .add_step(Validate::default(), ErrorPolicy::Skip);
+//! let mut d = FastDataset::new();
+//! let report = pipeline.run(any::parse_str(ttl, Format::Turtle)?, &mut d)?;
+//! assert_eq!(report.sink.output, 2);
+//! assert_eq!(report.steps[0].name, "rename");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`insert_all`]: ../dataset/trait.MutableDataset.html#method.insert_all
+//! [`Pipeline`]: struct.Pipeline.html
+//! [`Transform`]: trait.Transform.html
+//! [`ErrorPolicy`]: enum.ErrorPolicy.html
+//! [`Report`]: struct.Report.html
+
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use sophia_api::dataset::MutableDataset;
+use sophia_api::quad::TupleQuad;
+use sophia_api::term::{TTerm, TermKind};
+use sophia_term::iri::is_absolute_iri_ref;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::any;
+
+/// A step of a [`Pipeline`](struct.Pipeline.html), transforming quads one at a time.
+pub trait Transform {
+    /// The name of this step, used in [metrics](struct.StepMetrics.html) and errors.
+    fn name(&self) -> &str;
+
+    /// Transform `quad`, or return `Ok(None)` to drop it.
+    fn apply(&mut self, quad: TupleQuad<BoxTerm>) -> TransformResult;
+}
+
+/// The result of [`Transform::apply`](trait.Transform.html#tymethod.apply).
+pub type TransformResult = Result<Option<TupleQuad<BoxTerm>>, Box<dyn Error>>;
+
+/// What a [`Pipeline`](struct.Pipeline.html) does when a step fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Stop the pipeline.
+    #[default]
+    Abort,
+    /// Drop the quad, and go on with the next one.
+    Skip,
+    /// Keep the quad as it was before the failing step, and go on.
+    ///
+    /// For the source, this is the same as `Skip`.
+    Pass,
+}
+
+/// Rename IRIs starting with a given namespace.
+#[derive(Clone, Debug)]
+pub struct Rename {
+    /// The namespace to replace.
+    pub from: String,
+    /// The namespace replacing `from`.
+    pub to: String,
+}
+
+impl Rename {
+    /// Replace namespace `from` by namespace `to`.
+    pub fn new<T: Into<String>, U: Into<String>>(from: T, to: U) -> Self {
+        Rename {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+impl Transform for Rename {
+    fn name(&self) -> &str {
+        "rename"
+    }
+
+    fn apply(&mut self, quad: TupleQuad<BoxTerm>) -> TransformResult {
+        map_terms(quad, |t| {
+            if t.kind() == TermKind::Iri {
+                let iri = t.value();
+                if let Some(suffix) = iri.strip_prefix(self.from.as_str()) {
+                    return Ok(BoxTerm::new_iri(format!("{}{}", self.to, suffix))?);
+                }
+            }
+            Ok(t)
+        })
+    }
+}
+
+/// Replace blank nodes by IRIs ([skolemization](https://www.w3.org/TR/rdf11-concepts/#section-skolemization)).
+///
+/// The IRI of a blank node is its label, appended to a base IRI;
+/// so two blank nodes with the same label are replaced by the same IRI,
+/// even if they come from different documents.
+/// Use a [blank node prefix](../parser/config/struct.ParserConfig.html#method.set_bnode_prefix)
+/// to prevent that.
+#[derive(Clone, Debug)]
+pub struct Skolemize {
+    /// The IRI prepended to blank node labels,
+    /// typically ending with `/.well-known/genid/`.
+    pub base: String,
+}
+
+impl Skolemize {
+    /// Skolemize blank nodes with the given base IRI.
+    pub fn new<T: Into<String>>(base: T) -> Self {
+        Skolemize { base: base.into() }
+    }
+}
+
+impl Transform for Skolemize {
+    fn name(&self) -> &str {
+        "skolemize"
+    }
+
+    fn apply(&mut self, quad: TupleQuad<BoxTerm>) -> TransformResult {
+        map_terms(quad, |t| {
+            if t.kind() == TermKind::BlankNode {
+                Ok(BoxTerm::new_iri(format!("{}{}", self.base, t.value()))?)
+            } else {
+                Ok(t)
+            }
+        })
+    }
+}
+
+/// Check that quads are valid RDF quads
+/// (e.g. no literal as subject, no relative IRI, no variable).
+///
+/// Invalid quads raise a [`ValidationError`](struct.ValidationError.html).
+#[derive(Clone, Debug, Default)]
+pub struct Validate {}
+
+impl Transform for Validate {
+    fn name(&self) -> &str {
+        "validate"
+    }
+
+    fn apply(&mut self, quad: TupleQuad<BoxTerm>) -> TransformResult {
+        let ([s, p, o], g) = &quad;
+        let resource = |t: &BoxTerm| match t.kind() {
+            TermKind::Iri => is_absolute_iri_ref(&t.value()),
+            TermKind::BlankNode => true,
+            _ => false,
+        };
+        let valid = resource(s)
+            && p.kind() == TermKind::Iri
+            && resource(p)
+            && (o.kind() == TermKind::Literal || resource(o))
+            && g.as_ref().map(resource).unwrap_or(true);
+        if valid {
+            Ok(Some(quad))
+        } else {
+            Err(Box::new(ValidationError(Box::new(quad))))
+        }
+    }
+}
+
+/// The error raised by [`Validate`](struct.Validate.html) for invalid quads.
+#[derive(Clone, Debug, Error)]
+#[error("Invalid quad {:?}", .0)]
+pub struct ValidationError(pub Box<TupleQuad<BoxTerm>>);
+
+/// Normalize the language tags of literals, so that equivalent tags become identical.
+///
+/// Tags are lowercased (they are case-insensitive),
+/// and deprecated, grandfathered and redundant tags or subtags
+/// are replaced by their preferred value from the [IANA language subtag registry].
+/// See [`normalize_language_tag`](fn.normalize_language_tag.html).
+///
+/// [IANA language subtag registry]: https://www.iana.org/assignments/language-subtag-registry
+#[derive(Clone, Debug, Default)]
+pub struct NormalizeLang {}
+
+impl Transform for NormalizeLang {
+    fn name(&self) -> &str {
+        "normalize_lang"
+    }
+
+    fn apply(&mut self, quad: TupleQuad<BoxTerm>) -> TransformResult {
+        map_terms(quad, |t| {
+            if let Some(tag) = t.language() {
+                let normalized = normalize_language_tag(tag);
+                if normalized != tag {
+                    return Ok(BoxTerm::new_literal_lang(
+                        t.value().to_string(),
+                        normalized,
+                    )?);
+                }
+            }
+            Ok(t)
+        })
+    }
+}
+
+/// Lowercase `tag`, and replace deprecated, grandfathered and redundant tags or subtags
+/// by their preferred value in the [IANA language subtag registry].
+///
+/// Only the registry entries with a preferred value are considered,
+/// and only the primary language and region subtags are mapped individually.
+///
+/// [IANA language subtag registry]: https://www.iana.org/assignments/language-subtag-registry
+pub fn normalize_language_tag(tag: &str) -> String {
+    let tag = tag.to_ascii_lowercase();
+    if let Some((_, preferred)) = PREFERRED_TAGS.iter().find(|(t, _)| *t == tag) {
+        return preferred.to_string();
+    }
+    let mut ret = String::with_capacity(tag.len());
+    let mut extension = false;
+    for (i, subtag) in tag.split('-').enumerate() {
+        // singletons introduce extensions and private use subtags, which are left as is
+        extension = extension || subtag.len() == 1;
+        let table = if extension {
+            &[][..]
+        } else if i == 0 {
+            PREFERRED_LANGUAGES
+        } else if subtag.len() == 2 || subtag.bytes().all(|b| b.is_ascii_digit()) {
+            PREFERRED_REGIONS
+        } else {
+            &[][..]
+        };
+        if i > 0 {
+            ret.push('-');
+        }
+        match table.iter().find(|(s, _)| *s == subtag) {
+            Some((_, preferred)) => ret.push_str(preferred),
+            None => ret.push_str(subtag),
+        }
+    }
+    ret
+}
+
+/// Grandfathered and redundant tags with a preferred value.
+const PREFERRED_TAGS: &[(&str, &str)] = &[
+    ("art-lojban", "jbo"),
+    ("en-gb-oed", "en-gb-oxendict"),
+    ("i-ami", "ami"),
+    ("i-bnn", "bnn"),
+    ("i-hak", "hak"),
+    ("i-klingon", "tlh"),
+    ("i-lux", "lb"),
+    ("i-navajo", "nv"),
+    ("i-pwn", "pwn"),
+    ("i-tao", "tao"),
+    ("i-tay", "tay"),
+    ("i-tsu", "tsu"),
+    ("no-bok", "nb"),
+    ("no-nyn", "nn"),
+    ("sgn-be-fr", "sfb"),
+    ("sgn-be-nl", "vgt"),
+    ("sgn-br", "bzs"),
+    ("sgn-ch-de", "sgg"),
+    ("sgn-co", "csn"),
+    ("sgn-de", "gsg"),
+    ("sgn-dk", "dsl"),
+    ("sgn-es", "ssp"),
+    ("sgn-fr", "fsl"),
+    ("sgn-gb", "bfi"),
+    ("sgn-gr", "gss"),
+    ("sgn-ie", "isg"),
+    ("sgn-it", "ise"),
+    ("sgn-jp", "jsl"),
+    ("sgn-mx", "mfs"),
+    ("sgn-ni", "ncs"),
+    ("sgn-nl", "dse"),
+    ("sgn-no", "nsl"),
+    ("sgn-pt", "psr"),
+    ("sgn-se", "swl"),
+    ("sgn-us", "ase"),
+    ("sgn-za", "sfs"),
+    ("zh-cmn", "cmn"),
+    ("zh-cmn-hans", "cmn-hans"),
+    ("zh-cmn-hant", "cmn-hant"),
+    ("zh-gan", "gan"),
+    ("zh-guoyu", "cmn"),
+    ("zh-hakka", "hak"),
+    ("zh-min-nan", "nan"),
+    ("zh-wuu", "wuu"),
+    ("zh-xiang", "hsn"),
+    ("zh-yue", "yue"),
+];
+
+/// Deprecated primary language subtags with a preferred value.
+const PREFERRED_LANGUAGES: &[(&str, &str)] = &[
+    ("in", "id"),
+    ("iw", "he"),
+    ("ji", "yi"),
+    ("jw", "jv"),
+    ("mo", "ro"),
+];
+
+/// Deprecated region subtags with a preferred value.
+const PREFERRED_REGIONS: &[(&str, &str)] = &[
+    ("bu", "mm"),
+    ("dd", "de"),
+    ("fx", "fr"),
+    ("tp", "tl"),
+    ("yd", "ye"),
+    ("zr", "cd"),
+];
+
+/// A step defined by a closure.
+pub struct FnStep<F> {
+    name: String,
+    f: F,
+}
+
+impl<F> FnStep<F>
+where
+    F: FnMut(TupleQuad<BoxTerm>) -> TransformResult,
+{
+    /// Build a step named `name`, applying `f` to each quad.
+    pub fn new<T: Into<String>>(name: T, f: F) -> Self {
+        FnStep {
+            name: name.into(),
+            f,
+        }
+    }
+}
+
+impl<F> Transform for FnStep<F>
+where
+    F: FnMut(TupleQuad<BoxTerm>) -> TransformResult,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, quad: TupleQuad<BoxTerm>) -> TransformResult {
+        (self.f)(quad)
+    }
+}
+
+fn map_terms<F>(quad: TupleQuad<BoxTerm>, mut f: F) -> TransformResult
+where
+    F: FnMut(BoxTerm) -> Result<BoxTerm, Box<dyn Error>>,
+{
+    let ([s, p, o], g) = quad;
+    let g = match g {
+        Some(g) => Some(f(g)?),
+        None => None,
+    };
+    Ok(Some(([f(s)?, f(p)?, f(o)?], g)))
+}
+
+/// The metrics of a step of a [`Pipeline`](struct.Pipeline.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepMetrics {
+    /// The name of the step.
+    pub name: String,
+    /// The number of quads received by the step
+    /// (for the source, the number of items read, including errors).
+    pub input: usize,
+    /// The number of quads passed to the next step
+    /// (for the sink, the number of quads that were not already in the dataset).
+    pub output: usize,
+    /// The number of errors raised by the step.
+    pub errors: usize,
+    /// The time spent in the step.
+    pub elapsed: Duration,
+}
+
+impl StepMetrics {
+    fn new<T: Into<String>>(name: T) -> Self {
+        StepMetrics {
+            name: name.into(),
+            ..StepMetrics::default()
+        }
+    }
+}
+
+impl fmt::Display for StepMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} in, {} out, {} errors, {:?}",
+            self.name, self.input, self.output, self.errors, self.elapsed
+        )
+    }
+}
+
+/// The metrics of a run of a [`Pipeline`](struct.Pipeline.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// The metrics of the source.
+    pub source: StepMetrics,
+    /// The metrics of each step, in order.
+    pub steps: Vec<StepMetrics>,
+    /// The metrics of the sink.
+    pub sink: StepMetrics,
+    /// The duration of the whole run.
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.source)?;
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        writeln!(f, "{}", self.sink)?;
+        write!(f, "total: {:?}", self.elapsed)
+    }
+}
+
+/// The error raised when a [`Pipeline`](struct.Pipeline.html) is aborted.
+#[derive(Debug, Error)]
+#[error("{stage} failed: {source}")]
+pub struct PipelineError {
+    /// The name of the failing stage (`source`, `sink`, or the name of a step).
+    pub stage: String,
+    /// The error raised by the failing stage.
+    pub source: Box<dyn Error>,
+    /// The metrics of the run, until it was aborted.
+    pub report: Box<Report>,
+}
+
+/// A sequence of transforms applied to quads before inserting them in a dataset.
+///
+/// See [module documentation](index.html).
+pub struct Pipeline {
+    source_policy: ErrorPolicy,
+    steps: Vec<(Box<dyn Transform>, ErrorPolicy)>,
+    sink_policy: ErrorPolicy,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline {
+            source_policy: ErrorPolicy::Abort,
+            steps: vec![],
+            sink_policy: ErrorPolicy::Abort,
+        }
+    }
+}
+
+impl Pipeline {
+    /// Build a pipeline with no step, aborting on any error.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a step to this pipeline, with its error policy.
+    pub fn add_step<T: Transform + 'static>(&mut self, step: T, policy: ErrorPolicy) -> &mut Self {
+        self.steps.push((Box::new(step), policy));
+        self
+    }
+
+    /// Set what happens when the source yields an error (abort by default).
+    pub fn set_source_policy(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.source_policy = policy;
+        self
+    }
+
+    /// Set what happens when a quad can not be inserted in the dataset (abort by default).
+    pub fn set_sink_policy(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.sink_policy = policy;
+        self
+    }
+
+    /// The names of the steps of this pipeline, in order.
+    pub fn step_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.steps.iter().map(|(step, _)| step.name())
+    }
+
+    /// Run this pipeline on the quads of `source`, inserting them in `sink`.
+    ///
+    /// NB: whatever the error policies, quads inserted before an error remain in `sink`.
+    pub fn run<I, E, D>(&mut self, source: I, sink: &mut D) -> Result<Report, PipelineError>
+    where
+        I: IntoIterator<Item = Result<TupleQuad<BoxTerm>, E>>,
+        E: Error + 'static,
+        D: MutableDataset,
+    {
+        let start = Instant::now();
+        let mut report = Report {
+            source: StepMetrics::new("source"),
+            steps: self
+                .steps
+                .iter()
+                .map(|(s, _)| StepMetrics::new(s.name()))
+                .collect(),
+            sink: StepMetrics::new("sink"),
+            elapsed: Duration::default(),
+        };
+        let abort = |stage: &str, source: Box<dyn Error>, mut report: Report| {
+            report.elapsed = start.elapsed();
+            PipelineError {
+                stage: stage.to_string(),
+                source,
+                report: Box::new(report),
+            }
+        };
+        let mut source = source.into_iter();
+        loop {
+            let t0 = Instant::now();
+            let item = source.next();
+            report.source.elapsed += t0.elapsed();
+            let quad = match item {
+                None => break,
+                Some(Ok(quad)) => {
+                    report.source.input += 1;
+                    report.source.output += 1;
+                    quad
+                }
+                Some(Err(err)) => {
+                    report.source.input += 1;
+                    report.source.errors += 1;
+                    if self.source_policy == ErrorPolicy::Abort {
+                        return Err(abort("source", Box::new(err), report));
+                    }
+                    continue;
+                }
+            };
+
+            // each step takes the quad out of `current`, and puts its output back in it;
+            // `current` is left empty if the quad is dropped
+            let mut current = Some(quad);
+            for ((step, policy), metrics) in self.steps.iter_mut().zip(report.steps.iter_mut()) {
+                let quad = match current.take() {
+                    Some(quad) => quad,
+                    None => break,
+                };
+                metrics.input += 1;
+                let backup = if *policy == ErrorPolicy::Pass {
+                    Some(quad.clone())
+                } else {
+                    None
+                };
+                let t0 = Instant::now();
+                let res = step.apply(quad);
+                metrics.elapsed += t0.elapsed();
+                match res {
+                    Ok(Some(q)) => {
+                        metrics.output += 1;
+                        current = Some(q);
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        metrics.errors += 1;
+                        match backup {
+                            Some(q) => {
+                                metrics.output += 1;
+                                current = Some(q);
+                            }
+                            None if *policy == ErrorPolicy::Skip => break,
+                            None => {
+                                let stage = step.name().to_string();
+                                return Err(abort(&stage, err, report));
+                            }
+                        }
+                    }
+                }
+            }
+            let quad = match current {
+                Some(quad) => quad,
+                None => continue,
+            };
+
+            report.sink.input += 1;
+            let t0 = Instant::now();
+            let ([s, p, o], g) = &quad;
+            let res = sink.insert(s, p, o, g.as_ref());
+            report.sink.elapsed += t0.elapsed();
+            match res {
+                Ok(true) => report.sink.output += 1,
+                Ok(false) => (),
+                Err(err) => {
+                    report.sink.errors += 1;
+                    if self.sink_policy == ErrorPolicy::Abort {
+                        return Err(abort("sink", Box::new(err), report));
+                    }
+                }
+            }
+        }
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+
+    /// Open the file at `path`, and run this pipeline on its quads.
+    ///
+    /// The format of the file is guessed by [`any::open`](../parser/any/fn.open.html).
+    pub fn run_file<P, D>(&mut self, path: P, sink: &mut D) -> Result<Report, PipelineError>
+    where
+        P: AsRef<Path>,
+        D: MutableDataset,
+    {
+        match any::open(path) {
+            Ok(source) => self.run(source, sink),
+            Err(err) => Err(PipelineError {
+                stage: "source".to_string(),
+                source: Box::new(err),
+                report: Box::default(),
+            }),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::flaky::{Faults, FlakyDataset};
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::parser::any::Format;
+    use sophia_api::quad::Quad;
+    use test_case::test_case;
+
+    const TTL: &str = r#"
+        @prefix old: <http://old.example.org/>.
+        _:b old:p old:o, "lit".
+        old:s old:p _:b.
+    "#;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(txt).unwrap()
+    }
+
+    #[test]
+    fn transforms() -> Result<(), Box<dyn Error>> {
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .add_step(
+                Rename::new("http://old.example.org/", "http://example.org/"),
+                ErrorPolicy::Abort,
+            )
+            .add_step(
+                Skolemize::new("http://example.org/genid/"),
+                ErrorPolicy::Abort,
+            )
+            .add_step(Validate::default(), ErrorPolicy::Abort);
+        assert_eq!(
+            pipeline.step_names().collect::<Vec<_>>(),
+            vec!["rename", "skolemize", "validate"]
+        );
+        let mut d = FastDataset::new();
+        let report = pipeline.run(any::parse_str(TTL, Format::Turtle)?, &mut d)?;
+
+        assert_eq!(d.quads().count(), 3);
+        assert!(d.quads().all(|q| {
+            let q = q.unwrap();
+            q.s().kind() == TermKind::Iri && q.o().kind() != TermKind::BlankNode
+        }));
+        assert!(d.quads().any(|q| q
+            .unwrap()
+            .s()
+            .value()
+            .starts_with("http://example.org/genid/")));
+        assert_eq!(d.quads_with_p(&iri("http://example.org/p")).count(), 3);
+
+        assert_eq!(report.source.output, 3);
+        assert_eq!(report.steps.len(), 3);
+        for step in &report.steps {
+            assert_eq!(
+                (step.input, step.output, step.errors),
+                (3, 3, 0),
+                "{}",
+                step
+            );
+        }
+        assert_eq!((report.sink.input, report.sink.output), (3, 3));
+        assert_eq!(report.to_string().lines().count(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn error_policies() -> Result<(), Box<dyn Error>> {
+        let quads = || {
+            vec![
+                Ok(([iri("tag:a"), iri("tag:p"), iri("tag:o")], None)),
+                Err(ValidationError(Box::new((
+                    [iri("tag:x"), iri("tag:x"), iri("tag:x")],
+                    None,
+                )))),
+                Ok(([iri("tag:b"), iri("tag:p"), iri("tag:o")], None)),
+                Ok(([iri("tag:c"), iri("tag:p"), iri("tag:o")], None)),
+            ]
+        };
+        let reject_b = || {
+            FnStep::new("reject-b", |q: TupleQuad<BoxTerm>| {
+                if q.s() == &iri("tag:b") {
+                    Err("b is rejected".into())
+                } else {
+                    Ok(Some(q))
+                }
+            })
+        };
+
+        // abort on source error
+        let mut pipeline = Pipeline::new();
+        pipeline.add_step(reject_b(), ErrorPolicy::Abort);
+        let err = pipeline.run(quads(), &mut FastDataset::new()).unwrap_err();
+        assert_eq!(err.stage, "source");
+        assert_eq!(err.report.sink.output, 1);
+
+        // abort on step error
+        pipeline.set_source_policy(ErrorPolicy::Skip);
+        let err = pipeline.run(quads(), &mut FastDataset::new()).unwrap_err();
+        assert_eq!(err.stage, "reject-b");
+        assert_eq!(err.to_string(), "reject-b failed: b is rejected");
+        assert_eq!(err.report.source.errors, 1);
+        assert_eq!(err.report.steps[0].errors, 1);
+
+        // skip
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .set_source_policy(ErrorPolicy::Skip)
+            .add_step(reject_b(), ErrorPolicy::Skip);
+        let mut d = FastDataset::new();
+        let report = pipeline.run(quads(), &mut d)?;
+        assert_eq!(d.quads().count(), 2);
+        assert_eq!(report.steps[0].input, 3);
+        assert_eq!(report.steps[0].output, 2);
+
+        // pass
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .set_source_policy(ErrorPolicy::Pass)
+            .add_step(reject_b(), ErrorPolicy::Pass);
+        let mut d = FastDataset::new();
+        let report = pipeline.run(quads(), &mut d)?;
+        assert_eq!(d.quads().count(), 3);
+        assert_eq!((report.steps[0].output, report.steps[0].errors), (3, 1));
+
+        // dropping quads
+        let mut pipeline = Pipeline::new();
+        pipeline.add_step(FnStep::new("drop-all", |_| Ok(None)), ErrorPolicy::Abort);
+        let report = pipeline.run(quads().into_iter().filter(Result::is_ok), &mut d)?;
+        assert_eq!((report.steps[0].input, report.steps[0].output), (3, 0));
+        assert_eq!(report.sink.input, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn sink_errors() {
+        let quads: Vec<Result<_, ValidationError>> = (0..5)
+            .map(|i| {
+                Ok((
+                    [iri(&format!("tag:{}", i)), iri("tag:p"), iri("tag:o")],
+                    None,
+                ))
+            })
+            .collect();
+        let mut pipeline = Pipeline::new();
+        let mut d = FlakyDataset::new(FastDataset::new(), Faults::Never, Faults::At(vec![2]));
+        let err = pipeline.run(quads.clone(), &mut d).unwrap_err();
+        assert_eq!(err.stage, "sink");
+        assert_eq!(err.report.sink.input, 3);
+        assert_eq!(err.report.sink.output, 2);
+
+        pipeline.set_sink_policy(ErrorPolicy::Skip);
+        let mut d = FlakyDataset::new(FastDataset::new(), Faults::Never, Faults::At(vec![2]));
+        let report = pipeline.run(quads, &mut d).unwrap();
+        assert_eq!((report.sink.output, report.sink.errors), (4, 1));
+    }
+
+    #[test_case("en", "en" ; "unchanged")]
+    #[test_case("en-US", "en-us" ; "lowercased")]
+    #[test_case("IW", "he" ; "deprecated language")]
+    #[test_case("de-DD", "de-de" ; "deprecated region")]
+    #[test_case("i-klingon", "tlh" ; "grandfathered")]
+    #[test_case("zh-min-nan", "nan" ; "redundant")]
+    #[test_case("iw-x-in-dd", "he-x-in-dd" ; "private use")]
+    #[test_case("en-GB-oed", "en-gb-oxendict" ; "grandfathered with region")]
+    fn language_tag(tag: &str, expected: &str) {
+        assert_eq!(normalize_language_tag(tag), expected);
+    }
+
+    #[test]
+    fn normalize_lang() -> Result<(), Box<dyn Error>> {
+        let mut normalize = NormalizeLang::default();
+        let p = iri("tag:p");
+        let lit = BoxTerm::new_literal_lang("shalom", "IW-IL")?;
+        let plain = BoxTerm::from("x".to_string());
+        let ([_, _, o], _) = normalize
+            .apply(([p.clone(), p.clone(), lit], None))?
+            .unwrap();
+        assert_eq!(o.language(), Some("he-il"));
+        assert_eq!(o.value().to_string(), "shalom");
+        let ([_, _, o], _) = normalize
+            .apply(([p.clone(), p, plain.clone()], None))?
+            .unwrap();
+        assert!(o == plain);
+        Ok(())
+    }
+
+    #[test]
+    fn validate() {
+        let mut validate = Validate::default();
+        let lit = BoxTerm::from("x".to_string());
+        let rel = BoxTerm::new_iri("x").unwrap();
+        let bn = BoxTerm::new_bnode("b").unwrap();
+        let p = iri("tag:p");
+        assert!(validate
+            .apply(([bn.clone(), p.clone(), lit.clone()], Some(bn.clone())))
+            .is_ok());
+        assert!(validate
+            .apply(([lit.clone(), p.clone(), bn.clone()], None))
+            .is_err());
+        assert!(validate
+            .apply(([bn.clone(), bn.clone(), bn.clone()], None))
+            .is_err());
+        assert!(validate
+            .apply(([rel, p.clone(), bn.clone()], None))
+            .is_err());
+        assert!(validate.apply(([bn.clone(), p, bn], Some(lit))).is_err());
+    }
+}
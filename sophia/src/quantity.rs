@@ -0,0 +1,649 @@
+//! Unit-aware handling of quantities, using [QUDT] or custom conventions.
+//!
+//! A quantity is described in a graph by a node bearing a numeric value and a unit,
+//! e.g. with QUDT:
+//! ```text
+//! :obs1 :temperature [ qudt:numericValue 21.5; qudt:unit unit:DEG_C ].
+//! :obs2 :temperature [ qudt:numericValue 70.2; qudt:unit unit:DEG_F ].
+//! ```
+//! [`Quantities`] reads such descriptions, and converts their values between compatible units,
+//! so that data in mixed units can be [matched](struct.Quantities.html#method.matching)
+//! and [aggregated](struct.Quantities.html#method.summary) coherently.
+//!
+//! Units are described in a [`UnitRegistry`],
+//! which can be populated with the most common QUDT units,
+//! with units described in the QUDT vocabulary itself (loaded in a graph),
+//! or with custom units.
+//! Two units are compatible if they have the same dimension
+//! (for QUDT units, the same `qudt:hasDimensionVector`).
+//!
+//! # Example
+//! ```
+//! # use sophia::quantity::*;
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::triple::stream::TripleSource;
+//! # use sophia::term::BoxTerm;
+//! let g: FastGraph = sophia::parser::turtle::parse_str(r#"
+//!     @prefix qudt: <http://qudt.org/schema/qudt/>.
+//!     @prefix unit: <http://qudt.org/vocab/unit/>.
+//!     @prefix : <http://example.org/>.
+//!     :obs1 :temperature [ qudt:numericValue 21.5; qudt:unit unit:DEG_C ].
+//!     :obs2 :temperature [ qudt:numericValue 70.2; qudt:unit unit:DEG_F ].
+//!     :obs3 :temperature [ qudt:numericValue 300; qudt:unit unit:K ].
+//! "#).collect_triples()?;
+//!
+//! let quantities = Quantities::qudt();
+//! let temperature = BoxTerm::new_iri("http://example.org/temperature")?;
+//! let celsius = "http://qudt.org/vocab/unit/DEG_C";
+//! let warm = quantities.matching(&g, &temperature, celsius, 21.0..25.0)?;
+//! assert_eq!(warm.len(), 2); // obs1 and obs2 (21.2°C)
+//! let summary = quantities.summary(&g, &temperature, celsius)?.unwrap();
+//! assert_eq!(summary.count, 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [QUDT]: http://www.qudt.org/
+//! [`Quantities`]: struct.Quantities.html
+//! [`UnitRegistry`]: struct.UnitRegistry.html
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::ops::RangeBounds;
+
+use crate::description::{invalid, single, DescriptionError};
+use sophia_api::graph::Graph;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The QUDT schema namespace.
+mod qudt {
+    sophia_api::namespace!(
+        "http://qudt.org/schema/qudt/",
+        numericValue,
+        unit,
+        conversionMultiplier,
+        conversionOffset,
+        hasDimensionVector
+    );
+}
+
+/// The prefix of QUDT unit IRIs.
+pub const QUDT_UNIT: &str = "http://qudt.org/vocab/unit/";
+
+/// The prefix of QUDT dimension vector IRIs.
+pub const QUDT_DIMENSION: &str = "http://qudt.org/vocab/dimensionvector/";
+
+/// The definition of a unit, relative to the base unit of its dimension.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit {
+    /// An identifier of the dimension of this unit (e.g. a QUDT dimension vector IRI).
+    pub dimension: String,
+    /// The factor converting values in this unit to the base unit (`qudt:conversionMultiplier`).
+    pub multiplier: f64,
+    /// The offset added to values in this unit before applying `multiplier`
+    /// (`qudt:conversionOffset`), e.g. 273.15 for degrees Celsius.
+    pub offset: f64,
+}
+
+impl Unit {
+    /// Build a unit of the given dimension, with no offset.
+    pub fn new<T: Into<String>>(dimension: T, multiplier: f64) -> Self {
+        Unit {
+            dimension: dimension.into(),
+            multiplier,
+            offset: 0.0,
+        }
+    }
+
+    /// Convert `value`, expressed in this unit, to the base unit.
+    pub fn to_base(&self, value: f64) -> f64 {
+        (value + self.offset) * self.multiplier
+    }
+
+    /// Convert `value`, expressed in the base unit, to this unit.
+    pub fn from_base(&self, value: f64) -> f64 {
+        value / self.multiplier - self.offset
+    }
+}
+
+/// A set of units, identified by IRIs.
+#[derive(Clone, Debug, Default)]
+pub struct UnitRegistry {
+    units: HashMap<String, Unit>,
+}
+
+impl UnitRegistry {
+    /// Build an empty registry.
+    pub fn new() -> Self {
+        UnitRegistry::default()
+    }
+
+    /// Build a registry containing the most common QUDT units
+    /// of length, mass, time, temperature, pressure, speed and ratio.
+    pub fn qudt() -> Self {
+        let mut reg = UnitRegistry::new();
+        let units: &[(&str, &str, f64, f64)] = &[
+            // length
+            ("M", "A0E0L1I0M0H0T0D0", 1.0, 0.0),
+            ("KiloM", "A0E0L1I0M0H0T0D0", 1000.0, 0.0),
+            ("CentiM", "A0E0L1I0M0H0T0D0", 0.01, 0.0),
+            ("MilliM", "A0E0L1I0M0H0T0D0", 0.001, 0.0),
+            ("IN", "A0E0L1I0M0H0T0D0", 0.0254, 0.0),
+            ("FT", "A0E0L1I0M0H0T0D0", 0.3048, 0.0),
+            ("MI", "A0E0L1I0M0H0T0D0", 1609.344, 0.0),
+            // mass
+            ("KiloGM", "A0E0L0I0M1H0T0D0", 1.0, 0.0),
+            ("GM", "A0E0L0I0M1H0T0D0", 0.001, 0.0),
+            ("LB", "A0E0L0I0M1H0T0D0", 0.45359237, 0.0),
+            // time
+            ("SEC", "A0E0L0I0M0H0T1D0", 1.0, 0.0),
+            ("MIN", "A0E0L0I0M0H0T1D0", 60.0, 0.0),
+            ("HR", "A0E0L0I0M0H0T1D0", 3600.0, 0.0),
+            ("DAY", "A0E0L0I0M0H0T1D0", 86400.0, 0.0),
+            // temperature
+            ("K", "A0E0L0I0M0H1T0D0", 1.0, 0.0),
+            ("DEG_C", "A0E0L0I0M0H1T0D0", 1.0, 273.15),
+            ("DEG_F", "A0E0L0I0M0H1T0D0", 5.0 / 9.0, 459.67),
+            // pressure
+            ("PA", "A0E0L-1I0M1H0T-2D0", 1.0, 0.0),
+            ("HectoPA", "A0E0L-1I0M1H0T-2D0", 100.0, 0.0),
+            ("KiloPA", "A0E0L-1I0M1H0T-2D0", 1000.0, 0.0),
+            ("BAR", "A0E0L-1I0M1H0T-2D0", 100_000.0, 0.0),
+            // speed
+            ("M-PER-SEC", "A0E0L1I0M0H0T-1D0", 1.0, 0.0),
+            ("KiloM-PER-HR", "A0E0L1I0M0H0T-1D0", 1000.0 / 3600.0, 0.0),
+            ("MI-PER-HR", "A0E0L1I0M0H0T-1D0", 0.44704, 0.0),
+            // ratio
+            ("UNITLESS", "A0E0L0I0M0H0T0D1", 1.0, 0.0),
+            ("PERCENT", "A0E0L0I0M0H0T0D1", 0.01, 0.0),
+        ];
+        for (unit, dimension, multiplier, offset) in units {
+            reg.register(
+                format!("{}{}", QUDT_UNIT, unit),
+                Unit {
+                    dimension: format!("{}{}", QUDT_DIMENSION, dimension),
+                    multiplier: *multiplier,
+                    offset: *offset,
+                },
+            );
+        }
+        reg
+    }
+
+    /// Add (or replace) the unit identified by `iri`.
+    pub fn register<T: Into<String>>(&mut self, iri: T, unit: Unit) {
+        self.units.insert(iri.into(), unit);
+    }
+
+    /// The unit identified by `iri`, if any.
+    pub fn get(&self, iri: &str) -> Option<&Unit> {
+        self.units.get(iri)
+    }
+
+    /// The number of units in this registry.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Whether this registry is empty.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Add the units described in `graph` with the QUDT vocabulary,
+    /// i.e. all resources with a `qudt:conversionMultiplier` and a `qudt:hasDimensionVector`
+    /// (and optionally a `qudt:conversionOffset`).
+    ///
+    /// Return the number of units added.
+    pub fn load<G: Graph>(&mut self, graph: &G) -> QResult<G, usize> {
+        let mut ids = HashSet::new();
+        for t in graph.triples_with_p(&qudt::conversionMultiplier) {
+            ids.insert(BoxTerm::copy(t.map_err(QuantityError::Graph)?.s()));
+        }
+        let mut added = 0;
+        for id in ids.into_iter().filter(|id| id.kind() == TermKind::Iri) {
+            let dimension = match single(graph, &id, &qudt::hasDimensionVector)? {
+                Some(dimension) => dimension.value().to_string(),
+                None => continue,
+            };
+            let multiplier = single(graph, &id, &qudt::conversionMultiplier)?
+                .map(|m| number(&id, &m))
+                .transpose()?
+                .unwrap_or(1.0);
+            let offset = single(graph, &id, &qudt::conversionOffset)?
+                .map(|o| number(&id, &o))
+                .transpose()?
+                .unwrap_or(0.0);
+            if multiplier == 0.0 {
+                return Err(invalid(&id, "null conversion multiplier").into());
+            }
+            self.register(
+                id.value().to_string(),
+                Unit {
+                    dimension,
+                    multiplier,
+                    offset,
+                },
+            );
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// Convert `value` from unit `from` to unit `to`.
+    pub fn convert(&self, value: f64, from: &str, to: &str) -> Result<f64, ConversionError> {
+        if from == to {
+            return Ok(value);
+        }
+        let unit = |iri: &str| {
+            self.get(iri)
+                .ok_or_else(|| ConversionError::UnknownUnit(iri.to_string()))
+        };
+        let (from_unit, to_unit) = (unit(from)?, unit(to)?);
+        if from_unit.dimension != to_unit.dimension {
+            return Err(ConversionError::Incompatible {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+        Ok(to_unit.from_base(from_unit.to_base(value)))
+    }
+}
+
+/// A numeric value with a unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quantity {
+    /// The numeric value.
+    pub value: f64,
+    /// The IRI of the unit.
+    pub unit: String,
+}
+
+impl Quantity {
+    /// Build a quantity.
+    pub fn new<T: Into<String>>(value: f64, unit: T) -> Self {
+        Quantity {
+            value,
+            unit: unit.into(),
+        }
+    }
+
+    /// This quantity, converted to `unit`.
+    pub fn convert_to(&self, unit: &str, registry: &UnitRegistry) -> Result<Self, ConversionError> {
+        Ok(Quantity {
+            value: registry.convert(self.value, &self.unit, unit)?,
+            unit: unit.to_string(),
+        })
+    }
+}
+
+/// The predicates used to describe quantities.
+#[derive(Clone, Debug)]
+pub struct Convention {
+    /// The predicate linking a quantity to its numeric value.
+    pub value: BoxTerm,
+    /// The predicate linking a quantity to its unit.
+    pub unit: BoxTerm,
+}
+
+impl Convention {
+    /// The QUDT convention (`qudt:numericValue` and `qudt:unit`).
+    pub fn qudt() -> Self {
+        Convention {
+            value: BoxTerm::copy(&qudt::numericValue),
+            unit: BoxTerm::copy(&qudt::unit),
+        }
+    }
+}
+
+impl Default for Convention {
+    fn default() -> Self {
+        Convention::qudt()
+    }
+}
+
+/// Aggregated values of several quantities, expressed in a common unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// The IRI of the unit of all values.
+    pub unit: String,
+    /// The number of aggregated quantities.
+    pub count: usize,
+    /// The sum of all values.
+    pub sum: f64,
+    /// The smallest value.
+    pub min: f64,
+    /// The greatest value.
+    pub max: f64,
+}
+
+impl Summary {
+    /// The average of all values.
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+/// Reads quantities from graphs, according to a [`Convention`](struct.Convention.html),
+/// and converts them with a [`UnitRegistry`](struct.UnitRegistry.html).
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Quantities {
+    /// The predicates used to describe quantities.
+    pub convention: Convention,
+    /// The known units.
+    pub units: UnitRegistry,
+}
+
+impl Quantities {
+    /// Read quantities described with QUDT, knowing the most common QUDT units.
+    pub fn qudt() -> Self {
+        Quantities {
+            convention: Convention::qudt(),
+            units: UnitRegistry::qudt(),
+        }
+    }
+
+    /// Read the quantity described by `node` in `graph`.
+    pub fn read<G, T>(&self, graph: &G, node: &T) -> QResult<G, Quantity>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let node = BoxTerm::copy(node);
+        let value = single(graph, &node, &self.convention.value)?
+            .ok_or_else(|| invalid(&node, "no value"))?;
+        let unit = single(graph, &node, &self.convention.unit)?
+            .ok_or_else(|| invalid(&node, "no unit"))?;
+        if unit.kind() != TermKind::Iri {
+            return Err(invalid(&node, format!("invalid unit {}", unit)).into());
+        }
+        let unit_str = unit.value().to_string();
+        Ok(Quantity {
+            value: number(&node, &value)?,
+            unit: unit_str,
+        })
+    }
+
+    /// Read all the quantities that are values of `predicate` in `graph`,
+    /// together with their subject, converted to `unit`.
+    ///
+    /// Fail if any of them can not be converted to `unit`.
+    pub fn values<G, T>(
+        &self,
+        graph: &G,
+        predicate: &T,
+        unit: &str,
+    ) -> QResult<G, Vec<(BoxTerm, Quantity)>>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let mut values = vec![];
+        for t in graph.triples_with_p(predicate) {
+            let t = t.map_err(QuantityError::Graph)?;
+            let quantity = self.read(graph, t.o())?.convert_to(unit, &self.units)?;
+            values.push((BoxTerm::copy(t.s()), quantity));
+        }
+        values.sort_by(|(a, _), (b, _)| a.value().cmp(&b.value()));
+        Ok(values)
+    }
+
+    /// The subjects having a value of `predicate` in `range`, once converted to `unit`,
+    /// sorted by identifier.
+    pub fn matching<G, T, R>(
+        &self,
+        graph: &G,
+        predicate: &T,
+        unit: &str,
+        range: R,
+    ) -> QResult<G, Vec<BoxTerm>>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+        R: RangeBounds<f64>,
+    {
+        let mut subjects: Vec<_> = self
+            .values(graph, predicate, unit)?
+            .into_iter()
+            .filter(|(_, q)| range.contains(&q.value))
+            .map(|(s, _)| s)
+            .collect();
+        subjects.dedup();
+        Ok(subjects)
+    }
+
+    /// Aggregate all the values of `predicate` in `graph`, converted to `unit`.
+    ///
+    /// Return `None` if there is no such value.
+    pub fn summary<G, T>(&self, graph: &G, predicate: &T, unit: &str) -> QResult<G, Option<Summary>>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let values = self.values(graph, predicate, unit)?;
+        Ok(values.iter().fold(None, |summary, (_, q)| {
+            let summary = summary.unwrap_or(Summary {
+                unit: unit.to_string(),
+                count: 0,
+                sum: 0.0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+            });
+            Some(Summary {
+                count: summary.count + 1,
+                sum: summary.sum + q.value,
+                min: summary.min.min(q.value),
+                max: summary.max.max(q.value),
+                ..summary
+            })
+        }))
+    }
+}
+
+/// The error raised when converting between units.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum ConversionError {
+    /// The unit is not in the registry.
+    #[error("Unknown unit <{0}>")]
+    UnknownUnit(String),
+    /// The units have different dimensions.
+    #[error("Can not convert <{from}> to <{to}>")]
+    Incompatible {
+        /// The unit to convert from.
+        from: String,
+        /// The unit to convert to.
+        to: String,
+    },
+}
+
+/// The error raised when reading quantities or units.
+#[derive(Debug, Error)]
+pub enum QuantityError<E: 'static + Error> {
+    /// The graph raised an error.
+    #[error("Error in the graph: {0}")]
+    Graph(#[source] E),
+    /// A description is not well-formed.
+    #[error("Invalid description of {resource}: {message}")]
+    Invalid {
+        /// The offending resource.
+        resource: String,
+        /// The reason why its description is invalid.
+        message: String,
+    },
+    /// A quantity could not be converted.
+    #[error("{0}")]
+    Conversion(#[from] ConversionError),
+}
+
+type QResult<G, T> = Result<T, QuantityError<<G as Graph>::Error>>;
+
+impl<E: 'static + Error> From<DescriptionError<E>> for QuantityError<E> {
+    fn from(err: DescriptionError<E>) -> Self {
+        match err {
+            DescriptionError::Graph(err) => QuantityError::Graph(err),
+            DescriptionError::Invalid { resource, message } => {
+                QuantityError::Invalid { resource, message }
+            }
+        }
+    }
+}
+
+/// The numeric value of literal `t`.
+fn number<E: 'static + Error>(s: &BoxTerm, t: &BoxTerm) -> Result<f64, QuantityError<E>> {
+    match t.kind() {
+        TermKind::Literal => t.value().trim().parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| invalid(s, format!("invalid number {}", t)).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::triple::stream::TripleSource;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn unit(name: &str) -> String {
+        format!("{}{}", QUDT_UNIT, name)
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn convert() {
+        let reg = UnitRegistry::qudt();
+        let conv = |v, from, to| reg.convert(v, &unit(from), &unit(to)).unwrap();
+        assert_close(conv(100.0, "DEG_C", "DEG_F"), 212.0);
+        assert_close(conv(32.0, "DEG_F", "DEG_C"), 0.0);
+        assert_close(conv(0.0, "DEG_C", "K"), 273.15);
+        assert_close(conv(36.0, "KiloM-PER-HR", "M-PER-SEC"), 10.0);
+        assert_close(conv(1.0, "MI", "KiloM"), 1.609344);
+        assert_close(conv(1013.25, "HectoPA", "BAR"), 1.01325);
+        assert_close(conv(42.0, "FT", "FT"), 42.0);
+
+        assert_eq!(
+            reg.convert(1.0, &unit("M"), &unit("SEC")),
+            Err(ConversionError::Incompatible {
+                from: unit("M"),
+                to: unit("SEC")
+            })
+        );
+        assert_eq!(
+            reg.convert(1.0, "tag:furlong", &unit("M")),
+            Err(ConversionError::UnknownUnit("tag:furlong".to_string()))
+        );
+    }
+
+    #[test]
+    fn load_units() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix qudt: <http://qudt.org/schema/qudt/>.
+            @prefix unit: <http://qudt.org/vocab/unit/>.
+            @prefix qkdv: <http://qudt.org/vocab/dimensionvector/>.
+
+            unit:FUR qudt:conversionMultiplier 201.168;
+                qudt:hasDimensionVector qkdv:A0E0L1I0M0H0T0D0.
+            unit:DEG_R qudt:conversionMultiplier 0.5555555555555556;
+                qudt:conversionOffset 0.0;
+                qudt:hasDimensionVector qkdv:A0E0L0I0M0H1T0D0.
+            unit:NO_DIMENSION qudt:conversionMultiplier 2.0.
+            "#,
+        )
+        .collect_triples()?;
+        let mut reg = UnitRegistry::qudt();
+        let before = reg.len();
+        assert_eq!(reg.load(&g)?, 2);
+        assert_eq!(reg.len(), before + 2);
+        assert_close(reg.convert(1.0, &unit("FUR"), &unit("M"))?, 201.168);
+        assert_close(reg.convert(491.67, &unit("DEG_R"), &unit("DEG_C"))?, 0.0);
+        Ok(())
+    }
+
+    const SENSORS: &str = r#"
+        @prefix qudt: <http://qudt.org/schema/qudt/>.
+        @prefix unit: <http://qudt.org/vocab/unit/>.
+        @prefix : <http://example.org/>.
+
+        :s1 :temperature [ qudt:numericValue 20.0; qudt:unit unit:DEG_C ].
+        :s2 :temperature [ qudt:numericValue 77.0; qudt:unit unit:DEG_F ].
+        :s3 :temperature [ qudt:numericValue 283.15; qudt:unit unit:K ].
+        :s4 :pressure [ qudt:numericValue 1.2; qudt:unit unit:BAR ].
+        :s5 :temperature [ qudt:numericValue "3.0e1"; qudt:unit unit:DEG_C ].
+    "#;
+
+    fn temperature() -> BoxTerm {
+        BoxTerm::new_iri("http://example.org/temperature").unwrap()
+    }
+
+    #[test]
+    fn matching() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(SENSORS).collect_triples()?;
+        let q = Quantities::qudt();
+        let ids = |terms: Vec<BoxTerm>| -> Vec<String> {
+            terms.iter().map(|t| t.value().to_string()).collect()
+        };
+        let warm = q.matching(&g, &temperature(), &unit("DEG_C"), 15.0..24.0)?;
+        assert_eq!(ids(warm), ["http://example.org/s1"]);
+        let hot = q.matching(&g, &temperature(), &unit("K"), 295.0..)?;
+        assert_eq!(ids(hot), ["http://example.org/s2", "http://example.org/s5"]);
+        let pressure = BoxTerm::new_iri("http://example.org/pressure")?;
+        assert!(q.matching(&g, &pressure, &unit("DEG_C"), ..).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn summary() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(SENSORS).collect_triples()?;
+        let q = Quantities::qudt();
+        let summary = q.summary(&g, &temperature(), &unit("DEG_C"))?.unwrap();
+        assert_eq!(summary.count, 4);
+        assert_close(summary.min, 10.0);
+        assert_close(summary.max, 30.0);
+        assert_close(summary.mean(), 21.25);
+        let nothing = BoxTerm::new_iri("http://example.org/nothing")?;
+        assert_eq!(q.summary(&g, &nothing, &unit("DEG_C"))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_convention() -> Res {
+        let mut g: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix : <http://example.org/>.
+            :s1 :height [ :value 180; :unit :cm ].
+            :s2 :height [ :value 6; :unit :ft ].
+            "#,
+        )
+        .collect_triples()?;
+        let mut units = UnitRegistry::new();
+        units.register("http://example.org/cm", Unit::new("length", 0.01));
+        units.register("http://example.org/ft", Unit::new("length", 0.3048));
+        let q = Quantities {
+            convention: Convention {
+                value: BoxTerm::new_iri("http://example.org/value")?,
+                unit: BoxTerm::new_iri("http://example.org/unit")?,
+            },
+            units,
+        };
+        let height = BoxTerm::new_iri("http://example.org/height")?;
+        let values = q.values(&g, &height, "http://example.org/cm")?;
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].1, Quantity::new(180.0, "http://example.org/cm"));
+        assert_close(values[1].1.value, 182.88);
+
+        // a quantity without unit
+        crate::parser::turtle::parse_str("<http://example.org/s3> <http://example.org/height> [ <http://example.org/value> 1.5 ].")
+            .add_to_graph(&mut g)?;
+        assert!(q.values(&g, &height, "http://example.org/cm").is_err());
+        Ok(())
+    }
+}
@@ -12,6 +12,7 @@ use sophia_api::term::{CopyTerm, TTerm};
 use sophia_term::*;
 
 use crate::graph::*;
+use crate::quad::{Quad, TupleQuad};
 use crate::triple::*;
 
 /// A map associating variable names to [`term`](../term/enum.Term.html)s.
@@ -165,6 +166,130 @@ impl BindingExt for Binding {
     }
 }
 
+/// A [CONSTRUCT template](https://www.w3.org/TR/sparql11-query/#construct),
+/// i.e. a list of triple (or quad) patterns, instantiated once for each binding map.
+///
+/// As in SPARQL:
+/// * a pattern containing a variable that is not bound by a binding map
+///   produces nothing for that binding map;
+/// * a pattern producing an invalid triple (e.g. with a literal as subject)
+///   produces nothing;
+/// * blank nodes of the template are renamed for each binding map,
+///   by suffixing their identifier with `_` and the index of the binding map.
+///
+/// # Example
+/// ```
+/// # use sophia::query::{BindingMap, Template};
+/// # use sophia::quad::stream::QuadSource;
+/// # use sophia::dataset::{inmem::FastDataset, Dataset};
+/// # use sophia::term::RcTerm;
+/// # use std::convert::Infallible;
+/// let ex = |suffix: &str| RcTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap();
+/// let template = Template::from_triples(vec![
+///     [RcTerm::new_variable("x")?, ex("name"), RcTerm::new_variable("name")?],
+///     [RcTerm::new_variable("x")?, ex("knows"), RcTerm::new_variable("friend")?],
+/// ]);
+/// let bindings = vec!["alice", "bob"].into_iter().map(|name| {
+///     let mut b = BindingMap::new();
+///     b.insert("x".into(), ex(name));
+///     b.insert("name".into(), RcTerm::new_literal_dt(name, sophia::ns::xsd::string).unwrap());
+///     Ok::<_, Infallible>(b)
+/// });
+/// let d: FastDataset = template.instantiate(bindings).collect_quads()?;
+/// assert_eq!(d.quads().count(), 2); // ?friend is never bound
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Template {
+    quads: Vec<TupleQuad<RcTerm>>,
+}
+
+impl Template {
+    /// Build a template producing triples in the default graph.
+    pub fn from_triples(triples: Vec<[RcTerm; 3]>) -> Self {
+        Template {
+            quads: triples.into_iter().map(|t| (t, None)).collect(),
+        }
+    }
+
+    /// Build a template producing quads.
+    ///
+    /// Graph names may contain variables as well.
+    pub fn from_quads(quads: Vec<TupleQuad<RcTerm>>) -> Self {
+        Template { quads }
+    }
+
+    /// Instantiate this template for each binding map of `bindings`.
+    ///
+    /// The returned iterator is a [`QuadSource`](../quad/stream/trait.QuadSource.html),
+    /// failing if `bindings` fails.
+    /// `bindings` can be, for example,
+    /// the result of [`Query::process`](enum.Query.html#method.process).
+    pub fn instantiate<'a, I, E>(
+        &'a self,
+        bindings: I,
+    ) -> impl Iterator<Item = Result<TupleQuad<RcTerm>, E>> + 'a
+    where
+        I: IntoIterator<Item = Result<BindingMap, E>>,
+        I::IntoIter: 'a,
+        E: 'a,
+    {
+        bindings.into_iter().enumerate().flat_map(
+            move |(i, res)| -> Vec<Result<TupleQuad<RcTerm>, E>> {
+                match res {
+                    Err(err) => vec![Err(err)],
+                    Ok(b) => self.instantiate_one(&b, i).map(Ok).collect(),
+                }
+            },
+        )
+    }
+
+    /// Instantiate this template for the binding map `b`, which has index `i`
+    /// (used to rename blank nodes).
+    pub(crate) fn instantiate_one<'a>(
+        &'a self,
+        b: &'a BindingMap,
+        i: usize,
+    ) -> impl Iterator<Item = TupleQuad<RcTerm>> + 'a {
+        self.quads
+            .iter()
+            .filter_map(move |q| instantiate_quad(q, b, i))
+    }
+}
+
+/// Instantiate quad pattern `q` with binding map `b`, which has index `i`.
+fn instantiate_quad(q: &TupleQuad<RcTerm>, b: &BindingMap, i: usize) -> Option<TupleQuad<RcTerm>> {
+    let s = instantiate_term(q.s(), b, i)?;
+    let p = instantiate_term(q.p(), b, i)?;
+    let o = instantiate_term(q.o(), b, i)?;
+    let g = match q.g() {
+        None => None,
+        Some(g) => Some(instantiate_term(g, b, i)?),
+    };
+    let valid = matches!(s, Term::Iri(_) | Term::BNode(_))
+        && matches!(p, Term::Iri(_))
+        && !matches!(o, Term::Variable(_))
+        && matches!(g, None | Some(Term::Iri(_)) | Some(Term::BNode(_)));
+    if valid {
+        Some(([s, p, o], g))
+    } else {
+        None
+    }
+}
+
+/// Instantiate term `t` with binding map `b`, which has index `i`.
+fn instantiate_term(t: &RcTerm, b: &BindingMap, i: usize) -> Option<RcTerm> {
+    match t {
+        Term::Variable(var) => b.get(var.as_str()).cloned(),
+        Term::BNode(bn) => Some(RcTerm::new_bnode_unchecked(format!(
+            "{}_{}",
+            bn.as_str(),
+            i
+        ))),
+        _ => Some(t.clone()),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -334,6 +459,46 @@ mod test {
         assert_eq!(results[2], "http://example.org/charlie Charlie");
     }
 
+    #[test]
+    fn test_template() -> Result<(), Box<dyn std::error::Error>> {
+        let g = data();
+        let v1 = RcTerm::new_variable("v1").unwrap();
+        let v2 = RcTerm::new_variable("v2").unwrap();
+        let x_label = RcTerm::new_iri("http://example.org/label").unwrap();
+        let x_g = RcTerm::new_iri("http://example.org/g").unwrap();
+        let b = RcTerm::new_bnode("b").unwrap();
+        let schema = Namespace::new("http://schema.org/").unwrap();
+        let s_name = schema.get("name").unwrap();
+
+        let mut q = Query::Triples(vec![[v1.clone(), s_name.copied(), v2.clone()]]);
+        let template = Template::from_quads(vec![
+            ([v1.clone(), x_label.clone(), v2.clone()], Some(x_g.clone())),
+            ([v2.clone(), x_label.clone(), v1.clone()], None), // literal subject
+            ([b.clone(), x_label.clone(), v2.clone()], Some(v1.clone())),
+        ]);
+        let quads: Vec<_> = template
+            .instantiate(q.process(&g))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(quads.len(), 10);
+        let in_g = quads.iter().filter(|q| q.1.as_ref() == Some(&x_g)).count();
+        assert_eq!(in_g, 5);
+        let bnodes: std::collections::HashSet<_> = quads
+            .iter()
+            .filter(|q| q.0[0].kind() == sophia_api::term::TermKind::BlankNode)
+            .map(|q| q.0[0].clone())
+            .collect();
+        assert_eq!(bnodes.len(), 5);
+
+        // errors are propagated
+        let bindings = vec![Ok(BindingMap::new()), Err("failure")];
+        let results: Vec<_> = Template::from_triples(vec![[x_g.clone(), x_label.clone(), x_g]])
+            .instantiate(bindings)
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+        Ok(())
+    }
+
     fn data() -> FastGraph {
         let schema = Namespace::new("http://schema.org/").unwrap();
         let s_person = schema.get("Person").unwrap();
@@ -0,0 +1,572 @@
+//! Rule-based rewriting of datasets.
+//!
+//! A [`Rule`] is made of a *pattern*, i.e. a list of quad patterns containing variables,
+//! and a [`Template`], instantiated for each match of the pattern.
+//! Applying a rule to a [`MutableDataset`] replaces the quads matched by the pattern
+//! with the quads produced by the template,
+//! like the SPARQL update `DELETE { P } INSERT { T } WHERE { P }`;
+//! a rule can also [keep the quads it matches](struct.Rule.html#method.set_keep_matches),
+//! like `INSERT { T } WHERE { P }`.
+//!
+//! A [`Rewriter`] applies a list of rules, either once, or exhaustively
+//! (i.e. until none of them changes the dataset anymore),
+//! which is convenient for data migrations.
+//!
+//! # Example
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset, MutableDataset};
+//! # use sophia::query::Template;
+//! # use sophia::rewrite::{Rewriter, Rule};
+//! # use sophia::term::RcTerm;
+//! let ex = |suffix: &str| RcTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap();
+//! let x = RcTerm::new_variable("x")?;
+//! let name = RcTerm::new_variable("name")?;
+//! let mut d = FastDataset::new();
+//! d.insert(&ex("alice"), &ex("fullName"), &ex("Alice"), None as Option<&RcTerm>)?;
+//!
+//! // rename ex:fullName into ex:name
+//! let rule = Rule::new(
+//!     vec![([x.clone(), ex("fullName"), name.clone()], None)],
+//!     Template::from_triples(vec![[x, ex("name"), name]]),
+//! );
+//! let stats = Rewriter::new().add_rule(rule).apply(&mut d)?;
+//! assert_eq!(stats.matches, 1);
+//! assert_eq!(d.quads().count(), 1);
+//! assert!(d.quads_with_p(&ex("name")).next().is_some());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Rule`]: struct.Rule.html
+//! [`Template`]: ../query/struct.Template.html
+//! [`MutableDataset`]: ../dataset/trait.MutableDataset.html
+//! [`Rewriter`]: struct.Rewriter.html
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use sophia_api::term::matcher::AnyOrExactly;
+use sophia_api::term::{term_eq, CopyTerm, TTerm};
+use sophia_term::{RcTerm, Term};
+use thiserror::Error;
+
+use crate::dataset::{Dataset, MutableDataset};
+use crate::quad::{Quad, TupleQuad};
+use crate::query::{BindingMap, Template};
+
+/// The default value of [`Rewriter::max_rounds`](struct.Rewriter.html#method.max_rounds).
+pub const DEFAULT_MAX_ROUNDS: usize = 100;
+
+/// A rewrite rule: a pattern, and a template instantiated for each of its matches.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pattern: Vec<TupleQuad<RcTerm>>,
+    template: Template,
+    keep_matches: bool,
+}
+
+impl Rule {
+    /// Build a rule replacing the quads matched by `pattern` with the quads produced by `template`.
+    ///
+    /// In `pattern`, variables can appear in any position, including graph names;
+    /// a variable graph name only matches named graphs,
+    /// while a quad pattern without graph name only matches the default graph.
+    /// Blank nodes in `pattern` are not variables: they only match themselves.
+    pub fn new(pattern: Vec<TupleQuad<RcTerm>>, template: Template) -> Self {
+        Rule {
+            pattern,
+            template,
+            keep_matches: false,
+        }
+    }
+
+    /// Whether the quads matched by the pattern are kept (by default, they are removed).
+    pub fn keep_matches(&self) -> bool {
+        self.keep_matches
+    }
+
+    /// Set whether the quads matched by the pattern are kept (by default, they are removed).
+    pub fn set_keep_matches(&mut self, keep_matches: bool) -> &mut Self {
+        self.keep_matches = keep_matches;
+        self
+    }
+
+    /// The binding maps of all the matches of the pattern in `d`.
+    pub fn matches<D: Dataset>(&self, d: &D) -> Result<Vec<BindingMap>, D::Error> {
+        let mut bindings = vec![BindingMap::new()];
+        for qp in &self.pattern {
+            let mut next = vec![];
+            for b in bindings {
+                let ms = matcher(qp.s(), &b);
+                let mp = matcher(qp.p(), &b);
+                let mo = matcher(qp.o(), &b);
+                let mg = graph_matcher(qp.g(), &b);
+                for q in d.quads_matching(&ms, &mp, &mo, &mg) {
+                    let q = q?;
+                    let mut b2 = b.clone();
+                    if bind(&mut b2, qp.s(), q.s())
+                        && bind(&mut b2, qp.p(), q.p())
+                        && bind(&mut b2, qp.o(), q.o())
+                        && bind_graph(&mut b2, qp.g(), q.g())
+                    {
+                        next.push(b2);
+                    }
+                }
+            }
+            bindings = next;
+        }
+        Ok(bindings)
+    }
+
+    /// Apply this rule once per match in `d`.
+    ///
+    /// All matches are computed before `d` is modified;
+    /// then the matched quads are removed (unless they are [kept](#method.keep_matches)),
+    /// and the instantiated template is inserted.
+    pub fn apply_once<D: MutableDataset>(
+        &self,
+        d: &mut D,
+    ) -> Result<RewriteStats, RewriteError<D::Error, D::MutationError>> {
+        let mut renamed = 0;
+        self.apply_round(d, &mut renamed).map(|(stats, _)| stats)
+    }
+
+    /// Apply this rule once per match,
+    /// using (and incrementing) `renamed` to rename the blank nodes of the template.
+    ///
+    /// Also returns whether `d` was changed.
+    fn apply_round<D: MutableDataset>(
+        &self,
+        d: &mut D,
+        renamed: &mut usize,
+    ) -> Result<(RewriteStats, bool), RewriteError<D::Error, D::MutationError>> {
+        let matches = self.matches(d).map_err(RewriteError::Match)?;
+        let mut to_remove = vec![];
+        let mut to_insert = vec![];
+        for b in &matches {
+            if !self.keep_matches {
+                to_remove.extend(self.pattern.iter().map(|q| substitute_quad(q, b)));
+            }
+            to_insert.extend(self.template.instantiate_one(b, *renamed));
+            *renamed += 1;
+        }
+        let mut removed = HashSet::new();
+        for ([s, p, o], g) in to_remove {
+            let was_removed = d.remove(&s, &p, &o, g.as_ref());
+            if was_removed.map_err(RewriteError::Update)? {
+                removed.insert(([s, p, o], g));
+            }
+        }
+        let mut inserted = HashSet::new();
+        for ([s, p, o], g) in to_insert {
+            let was_inserted = d.insert(&s, &p, &o, g.as_ref());
+            if was_inserted.map_err(RewriteError::Update)? {
+                inserted.insert(([s, p, o], g));
+            }
+        }
+        let stats = RewriteStats {
+            rounds: 1,
+            matches: matches.len(),
+            removed: removed.len(),
+            inserted: inserted.len(),
+        };
+        // quads removed then inserted again leave the dataset unchanged
+        Ok((stats, removed != inserted))
+    }
+}
+
+/// Applies a list of [rules](struct.Rule.html) to datasets.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Rewriter {
+    rules: Vec<Rule>,
+    max_rounds: usize,
+}
+
+impl Default for Rewriter {
+    fn default() -> Self {
+        Rewriter {
+            rules: vec![],
+            max_rounds: DEFAULT_MAX_ROUNDS,
+        }
+    }
+}
+
+impl Rewriter {
+    /// Build a rewriter without any rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule, applied after the rules already added.
+    pub fn add_rule(&mut self, rule: Rule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The rules of this rewriter.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The maximum number of rounds of [`apply`](#method.apply).
+    pub fn max_rounds(&self) -> usize {
+        self.max_rounds
+    }
+
+    /// Set the maximum number of rounds of [`apply`](#method.apply).
+    pub fn set_max_rounds(&mut self, max_rounds: usize) -> &mut Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Apply each rule once per match, in order.
+    ///
+    /// Each rule sees the changes made by the previous ones.
+    pub fn apply_once<D: MutableDataset>(
+        &self,
+        d: &mut D,
+    ) -> Result<RewriteStats, RewriteError<D::Error, D::MutationError>> {
+        let mut renamed = 0;
+        self.apply_round(d, &mut renamed).map(|(stats, _)| stats)
+    }
+
+    /// Apply the rules exhaustively,
+    /// i.e. [once](#method.apply_once) per round, until a round leaves `d` unchanged.
+    ///
+    /// Note that a rule that keeps its matches
+    /// and whose template contains blank nodes never leaves `d` unchanged,
+    /// as fresh blank nodes are inserted at every round.
+    ///
+    /// # Errors
+    /// Besides the errors of `d`,
+    /// fails with [`NoFixpoint`](enum.RewriteError.html#variant.NoFixpoint)
+    /// if `d` is still changed by the last of [`max_rounds`](#method.max_rounds) rounds
+    /// (the changes of all rounds are not reverted).
+    pub fn apply<D: MutableDataset>(
+        &self,
+        d: &mut D,
+    ) -> Result<RewriteStats, RewriteError<D::Error, D::MutationError>> {
+        let mut renamed = 0;
+        let mut total = RewriteStats::default();
+        for _ in 0..self.max_rounds {
+            let (stats, changed) = self.apply_round(d, &mut renamed)?;
+            total.add(&stats);
+            if !changed {
+                return Ok(total);
+            }
+        }
+        Err(RewriteError::NoFixpoint(self.max_rounds))
+    }
+
+    fn apply_round<D: MutableDataset>(
+        &self,
+        d: &mut D,
+        renamed: &mut usize,
+    ) -> Result<(RewriteStats, bool), RewriteError<D::Error, D::MutationError>> {
+        let mut total = RewriteStats {
+            rounds: 1,
+            ..RewriteStats::default()
+        };
+        let mut changed = false;
+        for rule in &self.rules {
+            let (stats, rule_changed) = rule.apply_round(d, renamed)?;
+            total.matches += stats.matches;
+            total.removed += stats.removed;
+            total.inserted += stats.inserted;
+            changed |= rule_changed;
+        }
+        Ok((total, changed))
+    }
+}
+
+/// Statistics about the application of rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RewriteStats {
+    /// The number of rounds.
+    pub rounds: usize,
+    /// The number of matches of the rule patterns.
+    pub matches: usize,
+    /// The number of quads removed from the dataset.
+    pub removed: usize,
+    /// The number of quads inserted in the dataset.
+    pub inserted: usize,
+}
+
+impl RewriteStats {
+    fn add(&mut self, other: &RewriteStats) {
+        self.rounds += other.rounds;
+        self.matches += other.matches;
+        self.removed += other.removed;
+        self.inserted += other.inserted;
+    }
+}
+
+impl fmt::Display for RewriteStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} match(es) in {} round(s): {} quad(s) removed, {} quad(s) inserted",
+            self.matches, self.rounds, self.removed, self.inserted
+        )
+    }
+}
+
+/// The error raised when applying [rules](struct.Rule.html).
+#[derive(Debug, Error)]
+pub enum RewriteError<E, M>
+where
+    E: 'static + Error,
+    M: 'static + Error,
+{
+    /// The dataset failed while its quads were matched.
+    #[error("Error while matching: {0}")]
+    Match(#[source] E),
+    /// The dataset failed while it was modified.
+    #[error("Error while updating: {0}")]
+    Update(#[source] M),
+    /// The dataset was still changing after the given number of rounds.
+    #[error("No fixpoint reached after {0} round(s)")]
+    NoFixpoint(usize),
+}
+
+type Matcher = AnyOrExactly<RcTerm>;
+
+/// Make a matcher for pattern term `t`, given binding `b`.
+fn matcher(t: &RcTerm, b: &BindingMap) -> Matcher {
+    match t {
+        Term::Variable(var) => b.get(var.as_str()).cloned().into(),
+        _ => AnyOrExactly::Exactly(t.clone()),
+    }
+}
+
+/// Make a matcher for pattern graph name `g`, given binding `b`.
+fn graph_matcher(g: Option<&RcTerm>, b: &BindingMap) -> AnyOrExactly<Option<RcTerm>> {
+    match g {
+        Some(Term::Variable(var)) => match b.get(var.as_str()) {
+            Some(t) => AnyOrExactly::Exactly(Some(t.clone())),
+            None => AnyOrExactly::Any,
+        },
+        _ => AnyOrExactly::Exactly(g.cloned()),
+    }
+}
+
+/// Bind the variable (if any) of pattern term `pt` to `t` in `b`.
+///
+/// Returns false if the variable is already bound to another term,
+/// which happens when it appears several times in the same quad pattern.
+fn bind<T: TTerm + ?Sized>(b: &mut BindingMap, pt: &RcTerm, t: &T) -> bool {
+    if let Term::Variable(var) = pt {
+        match b.get(var.as_str()) {
+            Some(bound) => return term_eq(bound, t),
+            None => {
+                b.insert(var.as_str().to_string(), RcTerm::copy(t));
+            }
+        }
+    }
+    true
+}
+
+/// Same as [`bind`], for graph names.
+fn bind_graph<T: TTerm + ?Sized>(b: &mut BindingMap, pg: Option<&RcTerm>, g: Option<&T>) -> bool {
+    match (pg, g) {
+        (Some(pg), Some(g)) => bind(b, pg, g),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Replace the variables of quad pattern `q` with their value in `b`.
+fn substitute_quad(q: &TupleQuad<RcTerm>, b: &BindingMap) -> TupleQuad<RcTerm> {
+    let substitute = |t: &RcTerm| match t {
+        Term::Variable(var) => b[var.as_str()].clone(),
+        _ => t.clone(),
+    };
+    (
+        [substitute(q.s()), substitute(q.p()), substitute(q.o())],
+        q.g().map(substitute),
+    )
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::flaky::{Faults, FlakyDataset};
+    use crate::dataset::inmem::FastDataset;
+
+    fn ex(suffix: &str) -> RcTerm {
+        RcTerm::new_iri(format!("http://example.org/{}", suffix)).unwrap()
+    }
+
+    fn var(name: &str) -> RcTerm {
+        RcTerm::new_variable(name).unwrap()
+    }
+
+    fn data() -> FastDataset {
+        let mut d = FastDataset::new();
+        let g1 = ex("g1");
+        for (s, p, o, g) in [
+            (ex("alice"), ex("parent"), ex("bob"), None),
+            (ex("bob"), ex("parent"), ex("charlie"), None),
+            (ex("charlie"), ex("parent"), ex("dan"), None),
+            (ex("alice"), ex("likes"), ex("alice"), None),
+            (ex("alice"), ex("likes"), ex("bob"), Some(&g1)),
+        ] {
+            d.insert(&s, &p, &o, g).unwrap();
+        }
+        d
+    }
+
+    #[test]
+    fn matches() -> Result<(), Box<dyn Error>> {
+        let d = data();
+        let grandparent = Rule::new(
+            vec![
+                ([var("x"), ex("parent"), var("y")], None),
+                ([var("y"), ex("parent"), var("z")], None),
+            ],
+            Template::from_triples(vec![]),
+        );
+        let mut found: Vec<_> = grandparent
+            .matches(&d)?
+            .into_iter()
+            .map(|b| (b["x"].value().to_string(), b["z"].value().to_string()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    ex("alice").value().to_string(),
+                    ex("charlie").value().to_string()
+                ),
+                (ex("bob").value().to_string(), ex("dan").value().to_string()),
+            ]
+        );
+
+        // repeated variables
+        let self_love = Rule::new(
+            vec![([var("x"), ex("likes"), var("x")], None)],
+            Template::from_triples(vec![]),
+        );
+        assert_eq!(self_love.matches(&d)?.len(), 1);
+
+        // graph names
+        let named = Rule::new(
+            vec![([var("x"), ex("likes"), var("y")], Some(var("g")))],
+            Template::from_triples(vec![]),
+        );
+        let found = named.matches(&d)?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(&found[0]["g"], &ex("g1"));
+        Ok(())
+    }
+
+    #[test]
+    fn apply_once() -> Result<(), Box<dyn Error>> {
+        let mut d = data();
+        let rule = Rule::new(
+            vec![([var("x"), ex("parent"), var("y")], None)],
+            Template::from_triples(vec![[var("y"), ex("child"), var("x")]]),
+        );
+        let stats = rule.apply_once(&mut d)?;
+        assert_eq!(stats.matches, 3);
+        assert_eq!(stats.removed, 3);
+        assert_eq!(stats.inserted, 3);
+        assert!(d.quads_with_p(&ex("parent")).next().is_none());
+        assert_eq!(d.quads_with_p(&ex("child")).count(), 3);
+
+        // keeping matches
+        let mut d = data();
+        let mut rule = Rule::new(
+            vec![([var("x"), ex("parent"), var("y")], None)],
+            Template::from_triples(vec![[var("x"), ex("type"), ex("Parent")]]),
+        );
+        rule.set_keep_matches(true);
+        let stats = rule.apply_once(&mut d)?;
+        assert_eq!((stats.removed, stats.inserted), (0, 3));
+        assert_eq!(d.quads().count(), 8);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_exhaustively() -> Result<(), Box<dyn Error>> {
+        // transitive closure
+        let mut d = data();
+        let mut rule = Rule::new(
+            vec![
+                ([var("x"), ex("parent"), var("y")], None),
+                ([var("y"), ex("ancestor"), var("z")], None),
+            ],
+            Template::from_triples(vec![[var("x"), ex("ancestor"), var("z")]]),
+        );
+        rule.set_keep_matches(true);
+        let mut base = Rule::new(
+            vec![([var("x"), ex("parent"), var("y")], None)],
+            Template::from_triples(vec![[var("x"), ex("ancestor"), var("y")]]),
+        );
+        base.set_keep_matches(true);
+        let mut rewriter = Rewriter::new();
+        rewriter.add_rule(base).add_rule(rule);
+        let stats = rewriter.apply(&mut d)?;
+        assert_eq!(d.quads_with_p(&ex("ancestor")).count(), 6);
+        assert_eq!(stats.inserted, 6);
+        assert_eq!(stats.removed, 0);
+        assert_eq!(stats.rounds, 3);
+
+        // no fixpoint
+        let mut d = data();
+        let mut rule = Rule::new(
+            vec![([var("x"), ex("parent"), var("y")], None)],
+            Template::from_triples(vec![[
+                var("x"),
+                ex("parent"),
+                RcTerm::new_bnode("fresh").unwrap(),
+            ]]),
+        );
+        rule.set_keep_matches(true);
+        let mut rewriter = Rewriter::new();
+        rewriter.add_rule(rule).set_max_rounds(3);
+        match rewriter.apply(&mut d) {
+            Err(RewriteError::NoFixpoint(3)) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // removing then inserting the same quads is a fixpoint
+        let mut d = data();
+        let identity = Rule::new(
+            vec![([var("x"), ex("parent"), var("y")], None)],
+            Template::from_triples(vec![[var("x"), ex("parent"), var("y")]]),
+        );
+        let mut rewriter = Rewriter::new();
+        rewriter.add_rule(identity);
+        assert_eq!(rewriter.apply(&mut d)?.rounds, 1);
+        assert_eq!(d.quads().count(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let rule = Rule::new(
+            vec![([var("x"), ex("parent"), var("y")], None)],
+            Template::from_triples(vec![[var("y"), ex("child"), var("x")]]),
+        );
+        let mut d = FlakyDataset::new(data(), Faults::At(vec![0]), Faults::Never);
+        match rule.apply_once(&mut d) {
+            Err(RewriteError::Match(err)) => assert!(err.is_injected()),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        let mut d = FlakyDataset::new(data(), Faults::Never, Faults::At(vec![1]));
+        match rule.apply_once(&mut d) {
+            Err(RewriteError::Update(err)) => assert!(err.is_injected()),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//! Serializing data in any supported format,
+//! selected from a media type or a file extension.
+//!
+//! [`AnySerializer`] dispatches to the serializer of a given [`Format`],
+//! so that content negotiation code does not need to match over formats itself.
+//! It is a [`QuadSerializer`];
+//! triple-based formats (such as Turtle) only serialize the quads of the default graph
+//! (see [`serialize_graph_matching`] for serializing other graphs in these formats).
+//!
+//! It is also a [`QuadSink`], relying on the sink of the selected serializer.
+//! RDF/JSON and RDF/XML serializers are not sinks on their own:
+//! for these formats, pushed triples are held in memory,
+//! and each call to [`flush`] writes them as a separate document.
+//!
+//! # Example
+//! ```
+//! # use sophia::serializer::any;
+//! # use sophia::serializer::{QuadSerializer, Stringifier};
+//! # use sophia::term::BoxTerm;
+//! let s = BoxTerm::new_iri("http://example.org/s")?;
+//! let d = vec![([s.clone(), s.clone(), s.clone()], None)];
+//! let mut serializer = any::for_media_type("application/n-triples", Vec::new()).unwrap();
+//! serializer.serialize_dataset(&d)?;
+//! assert_eq!(
+//!     serializer.as_str(),
+//!     "<http://example.org/s> <http://example.org/s> <http://example.org/s>.\n"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`AnySerializer`]: struct.AnySerializer.html
+//! [`Format`]: ../../parser/any/enum.Format.html
+//! [`QuadSerializer`]: ../trait.QuadSerializer.html
+//! [`serialize_graph_matching`]: ../trait.TripleSerializer.html#method.serialize_graph_matching
+//! [`QuadSink`]: ../trait.QuadSink.html
+//! [`flush`]: ../trait.QuadSink.html#tymethod.flush
+
+use std::io;
+
+use sophia_api::quad::stream::{QuadSource, SinkError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::*;
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::stream::TripleSource;
+use sophia_term::BoxTerm;
+
+pub use crate::parser::any::Format;
+
+use super::config::SerializerConfig;
+use super::nq::{NqConfig, NqSerializer};
+use super::nt::{NtConfig, NtSerializer};
+#[cfg(feature = "json")]
+use super::rdf_json::{RdfJsonConfig, RdfJsonSerializer};
+use super::trig::TrigSerializer;
+#[cfg(feature = "xml")]
+use super::trix::TrixSerializer;
+use super::turtle::{TurtleConfig, TurtleSerializer};
+#[cfg(feature = "xml")]
+use super::xml::{RdfXmlConfig, RdfXmlSerializer};
+
+/// A serializer for any supported [`Format`](../../parser/any/enum.Format.html).
+///
+/// See [module documentation](index.html).
+pub struct AnySerializer<W: io::Write> {
+    format: Format,
+    inner: Inner<W>,
+    /// The triples pushed to a serializer that is not a sink, not flushed yet.
+    pending: Vec<[BoxTerm; 3]>,
+}
+
+enum Inner<W: io::Write> {
+    NTriples(NtSerializer<W>),
+    NQuads(NqSerializer<W>),
+    Turtle(TurtleSerializer<W>),
+    TriG(TrigSerializer<W>),
+    #[cfg(feature = "json")]
+    RdfJson(RdfJsonSerializer<W>),
+    #[cfg(feature = "xml")]
+    RdfXml(RdfXmlSerializer<W>),
+    #[cfg(feature = "xml")]
+    TriX(TrixSerializer<W>),
+}
+
+impl<W: io::Write> AnySerializer<W> {
+    /// Build a serializer for `format` writing to `write`, with the default config.
+    ///
+    /// Return `None` if no serializer is available for `format`.
+    #[inline]
+    pub fn new(format: Format, write: W) -> Option<Self> {
+        Self::new_with_config(format, write, SerializerConfig::default())
+    }
+
+    /// Build a serializer for `format` writing to `write`, with the given config.
+    ///
+    /// Options that are not relevant to `format` are ignored.
+    ///
+    /// Return `None` if no serializer is available for `format`.
+    pub fn new_with_config(format: Format, write: W, config: SerializerConfig) -> Option<Self> {
+        let inner = match format {
+            Format::NTriples => {
+                Inner::NTriples(NtSerializer::new_with_config(write, NtConfig::from(config)))
+            }
+            Format::NQuads => {
+                Inner::NQuads(NqSerializer::new_with_config(write, NqConfig::from(config)))
+            }
+            Format::Turtle => Inner::Turtle(TurtleSerializer::new_with_config(
+                write,
+                TurtleConfig::from(config),
+            )),
+            Format::TriG => Inner::TriG(TrigSerializer::new_with_config(write, config)),
+            #[cfg(feature = "json")]
+            Format::RdfJson => Inner::RdfJson(RdfJsonSerializer::new_with_config(
+                write,
+                RdfJsonConfig::from(config),
+            )),
+            #[cfg(feature = "xml")]
+            Format::RdfXml => Inner::RdfXml(RdfXmlSerializer::new_with_config(
+                write,
+                RdfXmlConfig::from(config),
+            )),
+            #[cfg(feature = "xml")]
+            Format::TriX => Inner::TriX(TrixSerializer::new_with_config(write, config)),
+            _ => return None,
+        };
+        Some(AnySerializer {
+            format,
+            inner,
+            pending: vec![],
+        })
+    }
+
+    /// The format of this serializer.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Write the pushed triples held in memory, if any.
+    fn write_pending(&mut self) -> io::Result<()> {
+        let triples = std::mem::take(&mut self.pending);
+        if triples.is_empty() {
+            return Ok(());
+        }
+        match &mut self.inner {
+            #[cfg(feature = "json")]
+            Inner::RdfJson(s) => {
+                s.serialize_graph(&triples)
+                    .map_err(|e| e.unwrap_sink_error())?;
+            }
+            #[cfg(feature = "xml")]
+            Inner::RdfXml(s) => {
+                s.serialize_graph(&triples)
+                    .map_err(|e| e.unwrap_sink_error())?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// Build a serializer writing to `write`, for the format having the given media type.
+///
+/// Return `None` if the media type is unknown,
+/// or if no serializer is available for the corresponding format.
+/// See [`Format::from_media_type`](../../parser/any/enum.Format.html#method.from_media_type).
+pub fn for_media_type<W: io::Write>(media_type: &str, write: W) -> Option<AnySerializer<W>> {
+    AnySerializer::new(Format::from_media_type(media_type)?, write)
+}
+
+/// Build a serializer writing to `write`, for the format having the given file extension.
+///
+/// Return `None` if the extension is unknown,
+/// or if no serializer is available for the corresponding format.
+/// See [`Format::from_extension`](../../parser/any/enum.Format.html#method.from_extension).
+pub fn for_extension<W: io::Write>(extension: &str, write: W) -> Option<AnySerializer<W>> {
+    AnySerializer::new(Format::from_extension(extension)?, write)
+}
+
+/// Whether a serializer is available for `format`.
+pub fn is_supported(format: Format) -> bool {
+    match format {
+        Format::RdfXml | Format::TriX => cfg!(feature = "xml"),
+        Format::RdfJson => cfg!(feature = "json"),
+        _ => matches!(
+            format,
+            Format::NTriples | Format::NQuads | Format::Turtle | Format::TriG
+        ),
+    }
+}
+
+impl<W: io::Write> QuadSerializer for AnySerializer<W> {
+    type Error = io::Error;
+
+    fn serialize_quads<QS>(&mut self, source: QS) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+    {
+        self.write_pending().map_err(SinkError)?;
+        match &mut self.inner {
+            Inner::NTriples(s) => {
+                s.serialize_triples(default_graph(source))?;
+            }
+            Inner::NQuads(s) => {
+                s.serialize_quads(source)?;
+            }
+            Inner::Turtle(s) => {
+                s.serialize_triples(default_graph(source))?;
+            }
+            Inner::TriG(s) => {
+                s.serialize_quads(source)?.flush().map_err(SinkError)?;
+            }
+            #[cfg(feature = "json")]
+            Inner::RdfJson(s) => {
+                s.serialize_triples(default_graph(source))?;
+            }
+            #[cfg(feature = "xml")]
+            Inner::RdfXml(s) => {
+                s.serialize_triples(default_graph(source))?;
+            }
+            #[cfg(feature = "xml")]
+            Inner::TriX(s) => {
+                s.serialize_quads(source)?;
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// As with [`serialize_quads`](#method.serialize_quads),
+/// only the quads of the default graph are written in triple-based formats.
+impl<W: io::Write> QuadSink for AnySerializer<W> {
+    fn push_quad<Q>(&mut self, quad: &Q) -> Result<(), Self::Error>
+    where
+        Q: Quad,
+    {
+        let triple = (quad.s(), quad.p(), quad.o());
+        match &mut self.inner {
+            Inner::NQuads(s) => s.push_quad(quad),
+            Inner::TriG(s) => s.push_quad(quad),
+            #[cfg(feature = "xml")]
+            Inner::TriX(s) => s.push_quad(quad),
+            _ if quad.g().is_some() => Ok(()),
+            Inner::NTriples(s) => s.push_triple(&triple),
+            Inner::Turtle(s) => s.push_triple(&triple),
+            #[allow(unreachable_patterns)]
+            _ => {
+                self.pending.push([
+                    BoxTerm::copy(quad.s()),
+                    BoxTerm::copy(quad.p()),
+                    BoxTerm::copy(quad.o()),
+                ]);
+                Ok(())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        match &mut self.inner {
+            Inner::NTriples(s) => TripleSink::flush(s),
+            Inner::NQuads(s) => QuadSink::flush(s),
+            Inner::Turtle(s) => TripleSink::flush(s),
+            Inner::TriG(s) => QuadSink::flush(s),
+            #[cfg(feature = "json")]
+            Inner::RdfJson(s) => s.flush(),
+            #[cfg(feature = "xml")]
+            Inner::RdfXml(s) => s.flush(),
+            #[cfg(feature = "xml")]
+            Inner::TriX(s) => QuadSink::flush(s),
+        }
+    }
+}
+
+impl AnySerializer<Vec<u8>> {
+    /// Create a new serializer for `format` which targets a `String`.
+    ///
+    /// Return `None` if no serializer is available for `format`.
+    #[inline]
+    pub fn new_stringifier(format: Format) -> Option<Self> {
+        AnySerializer::new(format, Vec::new())
+    }
+}
+
+impl Stringifier for AnySerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        match &self.inner {
+            Inner::NTriples(s) => s.as_utf8(),
+            Inner::NQuads(s) => s.as_utf8(),
+            Inner::Turtle(s) => s.as_utf8(),
+            Inner::TriG(s) => s.as_utf8(),
+            #[cfg(feature = "json")]
+            Inner::RdfJson(s) => s.as_utf8(),
+            #[cfg(feature = "xml")]
+            Inner::RdfXml(s) => s.as_utf8(),
+            #[cfg(feature = "xml")]
+            Inner::TriX(s) => s.as_utf8(),
+        }
+    }
+}
+
+/// The triples of the default graph of `source`.
+fn default_graph<QS: QuadSource>(source: QS) -> impl TripleSource<Error = QS::Error> {
+    source.filter_map_quads(|q| {
+        if q.g().is_some() {
+            return None;
+        }
+        Some([
+            BoxTerm::copy(q.s()),
+            BoxTerm::copy(q.p()),
+            BoxTerm::copy(q.o()),
+        ])
+    })
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_term::StaticTerm;
+
+    fn dataset() -> Vec<([StaticTerm; 3], Option<StaticTerm>)> {
+        let s = StaticTerm::new_iri("tag:s").unwrap();
+        let g = StaticTerm::new_iri("tag:g").unwrap();
+        vec![([s, s, s], None), ([s, s, g], Some(g))]
+    }
+
+    #[test]
+    fn media_type() {
+        let mut ser = for_media_type("application/n-triples; charset=utf-8", Vec::new()).unwrap();
+        assert_eq!(ser.format(), Format::NTriples);
+        let s = ser.serialize_dataset(&dataset()).unwrap().to_string();
+        assert_eq!(s, "<tag:s> <tag:s> <tag:s>.\n");
+
+        let mut ser = for_media_type("application/trig", Vec::new()).unwrap();
+        let s = ser.serialize_dataset(&dataset()).unwrap().to_string();
+        assert!(s.contains("<tag:g>"));
+
+        assert!(for_media_type("text/n3", Vec::new()).is_none());
+        assert!(for_media_type("text/plain", Vec::new()).is_none());
+    }
+
+    #[test]
+    fn extension() {
+        let mut ser = for_extension("nq", Vec::new()).unwrap();
+        let s = ser.serialize_dataset(&dataset()).unwrap().to_string();
+        assert_eq!(
+            s,
+            "<tag:s> <tag:s> <tag:s>.\n<tag:s> <tag:s> <tag:g> <tag:g>.\n"
+        );
+
+        let mut ser = for_extension(".TTL", Vec::new()).unwrap();
+        assert_eq!(ser.format(), Format::Turtle);
+        let s = ser.serialize_dataset(&dataset()).unwrap().to_string();
+        assert!(s.contains("<tag:s>"));
+        assert!(!s.contains("<tag:g>"));
+
+        assert!(for_extension("hdt", Vec::new()).is_none());
+    }
+
+    #[test]
+    fn sink() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::any::parse_str;
+        let count = |txt: &str, format| -> Result<usize, Box<dyn std::error::Error>> {
+            Ok(parse_str(txt, format)?
+                .collect::<Result<Vec<_>, _>>()?
+                .len())
+        };
+
+        let quads = dataset();
+        for format in Format::ALL.iter().copied() {
+            let mut ser = match AnySerializer::new_stringifier(format) {
+                Some(ser) => ser,
+                None => continue,
+            };
+            ser.push_quad(&quads[0])?;
+            QuadSink::flush(&mut ser)?;
+            let first = ser.as_str().to_string();
+            assert_eq!(count(&first, format)?, 1, "{:?}", format);
+            ser.push_quad(&quads[1])?;
+            QuadSink::flush(&mut ser)?;
+            let out = ser.as_str();
+            match format {
+                Format::NQuads | Format::TriG => assert_eq!(count(out, format)?, 2),
+                // one document per flush
+                Format::TriX => assert_eq!(count(&out[first.len()..], format)?, 1),
+                // only the default graph is written
+                _ => assert_eq!(out, first, "{:?}", format),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn supported() {
+        for format in Format::ALL.iter().copied() {
+            assert_eq!(
+                AnySerializer::new_stringifier(format).is_some(),
+                is_supported(format),
+                "{:?}",
+                format
+            );
+        }
+    }
+}
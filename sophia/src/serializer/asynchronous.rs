@@ -0,0 +1,406 @@
+//! Asynchronous serializers, writing to [tokio]'s [`AsyncWrite`].
+//!
+//! [`AsyncNqSerializer`] and [`AsyncTurtleSerializer`] encode quads (resp. triples)
+//! into an internal buffer as they are read from their source,
+//! and write the buffer to the underlying writer whenever it is full;
+//! the executor is never blocked waiting for the writer.
+//! This allows, for example, an HTTP handler to stream a large response
+//! without spawning a blocking task.
+//! Reading the source, on the other hand, is synchronous.
+//!
+//! Each call to `serialize_*` writes all its output before returning,
+//! so nothing remains in the buffer between calls.
+//! [`flush`] only flushes the underlying writer.
+//!
+//! This module requires the `async` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::parser::nq::parse_borrowed;
+//! # use sophia::serializer::asynchronous::AsyncNqSerializer;
+//! # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+//! let data = "<tag:s> <tag:p> <tag:o> <tag:g>.\n";
+//! let mut serializer = AsyncNqSerializer::new(Vec::new());
+//! serializer.serialize_quads(parse_borrowed(data)).await?;
+//! let out = serializer.finish().await?;
+//! assert_eq!(&out[..], b"<tag:s> <tag:p> <tag:o> <tag:g>.\n");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! # })?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [tokio]: https://docs.rs/tokio/
+//! [`AsyncWrite`]: https://docs.rs/tokio/1/tokio/io/trait.AsyncWrite.html
+//! [`AsyncNqSerializer`]: struct.AsyncNqSerializer.html
+//! [`AsyncTurtleSerializer`]: struct.AsyncTurtleSerializer.html
+//! [`flush`]: struct.AsyncNqSerializer.html#method.flush
+
+use std::io;
+
+use sophia_api::dataset::Dataset;
+use sophia_api::graph::Graph;
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_term::BoxTerm;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use super::config::{BnodeLabeling, SerializerConfig};
+use super::labeling::BnodeLabeler;
+use super::nq::{write_quad, NqConfig};
+use super::turtle::{TurtleConfig, TurtleWriter};
+
+/// Default capacity of the buffer of asynchronous serializers.
+pub const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Asynchronous N-Quads serializer.
+///
+/// The [`threads`](../nq/struct.NqConfig.html#method.set_threads) option is ignored.
+///
+/// See [module documentation](index.html).
+pub struct AsyncNqSerializer<W> {
+    config: NqConfig,
+    write: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+    labeler: BnodeLabeler,
+}
+
+impl<W> AsyncNqSerializer<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Build a new N-Quads serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> Self {
+        Self::new_with_config(write, NqConfig::default())
+    }
+
+    /// Build a new N-Quads serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: NqConfig) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, write, config)
+    }
+
+    /// Build a new N-Quads serializer writing to `write` by chunks of about `capacity` bytes.
+    pub fn with_capacity(capacity: usize, write: W, config: NqConfig) -> Self {
+        let labeler = BnodeLabeler::new(config.common().bnode_labeling());
+        AsyncNqSerializer {
+            config,
+            write,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            labeler,
+        }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &NqConfig {
+        &self.config
+    }
+
+    /// Serialize all quads from the given [`QuadSource`].
+    ///
+    /// Like [`NqSerializer::serialize_quads`],
+    /// this method can be called several times,
+    /// and blank nodes are relabeled consistently across calls,
+    /// except with [`BnodeLabeling::Canonical`].
+    ///
+    /// [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+    /// [`NqSerializer::serialize_quads`]: ../nq/struct.NqSerializer.html#method.serialize_quads
+    /// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+    pub async fn serialize_quads<QS>(
+        &mut self,
+        source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, io::Error>
+    where
+        QS: QuadSource,
+    {
+        let config = self.config.common();
+        let capacity = self.capacity;
+        let (write, buffer) = (&mut self.write, &mut self.buffer);
+        if config.bnode_labeling() == BnodeLabeling::Canonical {
+            let quads: Vec<([BoxTerm; 3], Option<BoxTerm>)> = source
+                .collect_quads()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            let mut labeler =
+                BnodeLabeler::canonical(quads.iter().map(|([s, p, o], g)| ([s, p, o], g.as_ref())));
+            write_quads(write, buffer, capacity, config, &mut labeler, quads.quads())
+                .await
+                .map_err(|e| SinkError(e.unwrap_sink_error()))?;
+        } else {
+            let labeler = &mut self.labeler;
+            write_quads(write, buffer, capacity, config, labeler, source).await?;
+        }
+        Ok(self)
+    }
+
+    /// Serialize a whole [`Dataset`](../../dataset/trait.Dataset.html).
+    pub async fn serialize_dataset<D>(
+        &mut self,
+        dataset: &D,
+    ) -> StreamResult<&mut Self, D::Error, io::Error>
+    where
+        D: Dataset,
+    {
+        self.serialize_quads(dataset.quads()).await
+    }
+
+    /// Flush the underlying writer.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.write.flush().await
+    }
+
+    /// Flush the underlying writer, and return it.
+    pub async fn finish(mut self) -> io::Result<W> {
+        self.flush().await?;
+        Ok(self.write)
+    }
+}
+
+async fn write_quads<W, QS>(
+    write: &mut W,
+    buffer: &mut Vec<u8>,
+    capacity: usize,
+    config: &SerializerConfig,
+    labeler: &mut BnodeLabeler,
+    mut source: QS,
+) -> StreamResult<(), QS::Error, io::Error>
+where
+    W: AsyncWrite + Unpin,
+    QS: QuadSource,
+{
+    loop {
+        let more = source.try_for_some_quad(&mut |q| write_quad(buffer, config, labeler, &q))?;
+        if !more || buffer.len() >= capacity {
+            write_buffer(write, buffer).await.map_err(SinkError)?;
+        }
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+/// Asynchronous Turtle serializer.
+///
+/// See [module documentation](index.html).
+pub struct AsyncTurtleSerializer<W> {
+    config: TurtleConfig,
+    write: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W> AsyncTurtleSerializer<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Build a new Turtle serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> Self {
+        Self::new_with_config(write, TurtleConfig::default())
+    }
+
+    /// Build a new Turtle serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: TurtleConfig) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, write, config)
+    }
+
+    /// Build a new Turtle serializer writing to `write` by chunks of about `capacity` bytes.
+    ///
+    /// In pretty mode, all the triples held by the serializer
+    /// (see [`TurtleConfig::set_max_buffered`]) are written at once.
+    ///
+    /// [`TurtleConfig::set_max_buffered`]: ../turtle/struct.TurtleConfig.html#method.set_max_buffered
+    pub fn with_capacity(capacity: usize, write: W, config: TurtleConfig) -> Self {
+        AsyncTurtleSerializer {
+            config,
+            write,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &TurtleConfig {
+        &self.config
+    }
+
+    /// Serialize all triples from the given [`TripleSource`].
+    ///
+    /// Like [`TurtleSerializer`], each call writes a complete Turtle document.
+    ///
+    /// [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+    /// [`TurtleSerializer`]: ../turtle/struct.TurtleSerializer.html
+    pub async fn serialize_triples<TS>(
+        &mut self,
+        source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, io::Error>
+    where
+        TS: TripleSource,
+    {
+        let config = &self.config;
+        let capacity = self.capacity;
+        let (write, buffer) = (&mut self.write, &mut self.buffer);
+        let labeling = config.common().bnode_labeling();
+        if labeling == BnodeLabeling::Canonical {
+            let triples: Vec<[BoxTerm; 3]> = source
+                .collect_triples()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            let mut labeler =
+                BnodeLabeler::canonical(triples.iter().map(|[s, p, o]| ([s, p, o], None)));
+            write_triples(
+                write,
+                buffer,
+                capacity,
+                config,
+                &mut labeler,
+                triples.triples(),
+            )
+            .await
+            .map_err(|e| SinkError(e.unwrap_sink_error()))?;
+        } else {
+            let mut labeler = BnodeLabeler::new(labeling);
+            write_triples(write, buffer, capacity, config, &mut labeler, source).await?;
+        }
+        Ok(self)
+    }
+
+    /// Serialize a whole [`Graph`](../../graph/trait.Graph.html).
+    pub async fn serialize_graph<G>(
+        &mut self,
+        graph: &G,
+    ) -> StreamResult<&mut Self, G::Error, io::Error>
+    where
+        G: Graph,
+    {
+        self.serialize_triples(graph.triples()).await
+    }
+
+    /// Flush the underlying writer.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.write.flush().await
+    }
+
+    /// Flush the underlying writer, and return it.
+    pub async fn finish(mut self) -> io::Result<W> {
+        self.flush().await?;
+        Ok(self.write)
+    }
+}
+
+async fn write_triples<W, TS>(
+    write: &mut W,
+    buffer: &mut Vec<u8>,
+    capacity: usize,
+    config: &TurtleConfig,
+    labeler: &mut BnodeLabeler,
+    mut source: TS,
+) -> StreamResult<(), TS::Error, io::Error>
+where
+    W: AsyncWrite + Unpin,
+    TS: TripleSource,
+{
+    let mut tw = TurtleWriter::new(config);
+    tw.start(buffer).map_err(SinkError)?;
+    loop {
+        let more = source.try_for_some_triple(&mut |t| tw.write_triple(buffer, labeler, &t))?;
+        if !more {
+            tw.finish(buffer, labeler).map_err(SinkError)?;
+        }
+        if !more || buffer.len() >= capacity {
+            write_buffer(write, buffer).await.map_err(SinkError)?;
+        }
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+/// Write the content of `buffer` to `write`, and clear it.
+async fn write_buffer<W>(write: &mut W, buffer: &mut Vec<u8>) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    write.write_all(buffer).await?;
+    buffer.clear();
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::serializer::nq::NqSerializer;
+    use crate::serializer::turtle::TurtleSerializer;
+    use sophia_api::serializer::{QuadSerializer, Stringifier, TripleSerializer};
+    use sophia_term::StaticTerm;
+
+    type StaticQuad = ([StaticTerm; 3], Option<StaticTerm>);
+
+    fn some_quads() -> Vec<([BoxTerm; 3], Option<BoxTerm>)> {
+        (0..100)
+            .map(|i| {
+                (
+                    [
+                        BoxTerm::new_iri(format!("tag:s{}", i % 7)).unwrap(),
+                        BoxTerm::new_iri("tag:p").unwrap(),
+                        BoxTerm::new_bnode(format!("b{}", i % 3)).unwrap(),
+                    ],
+                    if i % 2 == 0 {
+                        Some(BoxTerm::new_iri("tag:g").unwrap())
+                    } else {
+                        None
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn nq() -> Result<(), Box<dyn std::error::Error>> {
+        let quads = some_quads();
+        let expected = NqSerializer::new_stringifier()
+            .serialize_quads(quads.quads())?
+            .to_string();
+
+        // a tiny buffer, so that the output is written in several chunks
+        let mut serializer = AsyncNqSerializer::with_capacity(16, Vec::new(), NqConfig::default());
+        serializer.serialize_quads(quads.quads()).await?;
+        assert!(serializer.buffer.is_empty());
+        let out = serializer.finish().await?;
+        assert_eq!(String::from_utf8(out)?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn turtle() -> Result<(), Box<dyn std::error::Error>> {
+        let triples: Vec<[BoxTerm; 3]> = some_quads().into_iter().map(|(t, _)| t).collect();
+        let mut config = TurtleConfig::default();
+        config.add_prefix("t", "tag:");
+        for pretty in &[false, true] {
+            config.set_pretty(*pretty).set_max_buffered(10);
+            let expected = TurtleSerializer::new_stringifier_with_config(config.clone())
+                .serialize_graph(&triples)?
+                .to_string();
+            let mut serializer =
+                AsyncTurtleSerializer::with_capacity(16, Vec::new(), config.clone());
+            serializer.serialize_graph(&triples).await?;
+            let out = serializer.finish().await?;
+            assert_eq!(String::from_utf8(out)?, expected);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn source_error() {
+        let err = io::Error::other("oops");
+        let quads: Vec<Result<StaticQuad, io::Error>> = vec![Err(err)];
+        let mut serializer = AsyncNqSerializer::new(Vec::new());
+        assert!(matches!(
+            serializer.serialize_quads(quads.into_iter()).await,
+            Err(SourceError(_))
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+//! Configuration shared by the text-based serializers.
+//!
+//! Each serializer has its own configuration type
+//! (e.g. [`NtConfig`], [`TurtleConfig`]),
+//! which embeds a [`SerializerConfig`] for the options that are common to all text formats.
+//! Options that make no sense for a given format are ignored by the corresponding serializer
+//! (e.g. N-Triples only allows double quotes, and has no numeric shorthand).
+//!
+//! [`NtConfig`]: ../nt/struct.NtConfig.html
+//! [`TurtleConfig`]: ../turtle/struct.TurtleConfig.html
+//! [`SerializerConfig`]: struct.SerializerConfig.html
+
+use crate::dataset::metadata::GraphMetadata;
+
+/// The quotes used to delimit literals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum QuoteStyle {
+    /// Always use double quotes (`"`).
+    #[default]
+    Double,
+    /// Always use single quotes (`'`), where the format allows it.
+    Single,
+    /// Use double quotes, unless the literal contains double quotes but no single quotes.
+    Auto,
+}
+
+impl QuoteStyle {
+    /// Choose the quote character to use for the given literal value.
+    pub fn choose(&self, value: &str) -> char {
+        match self {
+            QuoteStyle::Double => '"',
+            QuoteStyle::Single => '\'',
+            QuoteStyle::Auto => {
+                if value.contains('"') && !value.contains('\'') {
+                    '\''
+                } else {
+                    '"'
+                }
+            }
+        }
+    }
+}
+
+/// How blank nodes are labeled by serializers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BnodeLabeling {
+    /// Keep the labels of the input.
+    #[default]
+    Preserve,
+    /// Relabel blank nodes `b0`, `b1`... in the order in which they are encountered.
+    Sequential,
+    /// Relabel blank nodes `c0`, `c1`... according to the structure of the data,
+    /// so that isomorphic inputs produce the same labels
+    /// (see [`BnodeLabeler::canonical`] for the limitations of this strategy).
+    ///
+    /// NB: this strategy requires serializers to hold the whole data in memory.
+    /// Labels are computed from the data passed to a single `serialize_*` call,
+    /// so calling it several times on the same serializer would give
+    /// the same labels to unrelated blank nodes;
+    /// [`NqSerializer`] rejects such calls, other serializers should only be called once.
+    ///
+    /// [`BnodeLabeler::canonical`]: ../labeling/struct.BnodeLabeler.html#method.canonical
+    /// [`NqSerializer`]: ../nq/struct.NqSerializer.html
+    Canonical,
+}
+
+/// Configuration options common to all text-based serializers.
+#[derive(Clone, Debug)]
+pub struct SerializerConfig {
+    indent: usize,
+    max_line_length: Option<usize>,
+    quote_style: QuoteStyle,
+    ascii: bool,
+    numeric_shorthand: bool,
+    boolean_shorthand: bool,
+    explicit_xsd_string: bool,
+    bnode_labeling: BnodeLabeling,
+    prefixes: Vec<(String, String)>,
+    sort_subjects: bool,
+}
+
+impl Default for SerializerConfig {
+    fn default() -> Self {
+        SerializerConfig {
+            indent: 4,
+            max_line_length: None,
+            quote_style: QuoteStyle::default(),
+            ascii: false,
+            numeric_shorthand: false,
+            boolean_shorthand: false,
+            explicit_xsd_string: false,
+            bnode_labeling: BnodeLabeling::default(),
+            prefixes: vec![],
+            sort_subjects: false,
+        }
+    }
+}
+
+impl SerializerConfig {
+    /// Build a new default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of spaces used for each level of indentation.
+    pub fn indent(&self) -> usize {
+        self.indent
+    }
+    /// Set the number of spaces used for each level of indentation (default: 4).
+    pub fn set_indent(&mut self, indent: usize) -> &mut Self {
+        self.indent = indent;
+        self
+    }
+
+    /// The maximum line length, if any.
+    pub fn max_line_length(&self) -> Option<usize> {
+        self.max_line_length
+    }
+    /// Set the maximum line length (default: none).
+    ///
+    /// This is a soft limit: serializers break lines where the syntax allows it,
+    /// but a single term longer than the limit will still be written on a single line.
+    pub fn set_max_line_length(&mut self, max_line_length: Option<usize>) -> &mut Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// The quotes used to delimit literals.
+    pub fn quote_style(&self) -> QuoteStyle {
+        self.quote_style
+    }
+    /// Set the quotes used to delimit literals (default: [`QuoteStyle::Double`]).
+    ///
+    /// [`QuoteStyle::Double`]: enum.QuoteStyle.html#variant.Double
+    pub fn set_quote_style(&mut self, quote_style: QuoteStyle) -> &mut Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    /// Whether the output is restricted to ASCII characters.
+    pub fn ascii(&self) -> bool {
+        self.ascii
+    }
+    /// Set the ascii configuration (default: false).
+    ///
+    /// If true, all non-ASCII characters in IRIs and literals are escaped.
+    pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Whether numeric literals are written in their shorthand form, where the format allows it.
+    pub fn numeric_shorthand(&self) -> bool {
+        self.numeric_shorthand
+    }
+    /// Set the numeric shorthand configuration (default: false).
+    ///
+    /// If true, `xsd:integer`, `xsd:decimal` and `xsd:double` literals
+    /// are written without quotes nor datatype,
+    /// provided that their lexical form allows it.
+    pub fn set_numeric_shorthand(&mut self, numeric_shorthand: bool) -> &mut Self {
+        self.numeric_shorthand = numeric_shorthand;
+        self
+    }
+
+    /// Whether boolean literals are written in their shorthand form, where the format allows it.
+    pub fn boolean_shorthand(&self) -> bool {
+        self.boolean_shorthand
+    }
+    /// Set the boolean shorthand configuration (default: false).
+    ///
+    /// If true, the `xsd:boolean` literals `"true"` and `"false"`
+    /// are written without quotes nor datatype.
+    pub fn set_boolean_shorthand(&mut self, boolean_shorthand: bool) -> &mut Self {
+        self.boolean_shorthand = boolean_shorthand;
+        self
+    }
+
+    /// Whether the `xsd:string` datatype is written explicitly.
+    pub fn explicit_xsd_string(&self) -> bool {
+        self.explicit_xsd_string
+    }
+    /// Set the explicit xsd:string configuration (default: false).
+    ///
+    /// If true, literals of type `xsd:string` are written with an explicit datatype
+    /// (e.g. `"foo"^^<http://www.w3.org/2001/XMLSchema#string>`);
+    /// otherwise they are written as simple literals (e.g. `"foo"`).
+    /// Both forms are equivalent in RDF 1.1,
+    /// but some consumers expect one or the other.
+    pub fn set_explicit_xsd_string(&mut self, explicit_xsd_string: bool) -> &mut Self {
+        self.explicit_xsd_string = explicit_xsd_string;
+        self
+    }
+
+    /// How blank nodes are labeled.
+    pub fn bnode_labeling(&self) -> BnodeLabeling {
+        self.bnode_labeling
+    }
+    /// Set how blank nodes are labeled (default: [`BnodeLabeling::Preserve`]).
+    ///
+    /// [`BnodeLabeling::Preserve`]: enum.BnodeLabeling.html#variant.Preserve
+    pub fn set_bnode_labeling(&mut self, bnode_labeling: BnodeLabeling) -> &mut Self {
+        self.bnode_labeling = bnode_labeling;
+        self
+    }
+
+    /// The prefixes declared in the output, as (prefix, namespace) pairs.
+    pub fn prefixes(&self) -> &[(String, String)] {
+        &self.prefixes
+    }
+    /// Set the prefixes declared in the output, as (prefix, namespace) pairs (default: none).
+    ///
+    /// In Turtle and TriG,
+    /// every prefix is declared with `@prefix` at the start of the output,
+    /// and IRIs (including datatypes) starting with a declared namespace
+    /// are written as prefixed names (e.g. `foaf:name`),
+    /// unless their local part can not be expressed in the format.
+    /// Prefixes must be valid Turtle prefix names (`PN_PREFIX`).
+    /// RDF/XML uses them for the names of elements
+    /// (see [`RdfXmlConfig::set_prefixes`](../xml/struct.RdfXmlConfig.html#method.set_prefixes)).
+    ///
+    /// See also [`add_prefixes_from`](#method.add_prefixes_from),
+    /// to reuse the prefixes of a parsed file.
+    pub fn set_prefixes(&mut self, prefixes: Vec<(String, String)>) -> &mut Self {
+        self.prefixes = prefixes;
+        self
+    }
+    /// Add a prefix declaration, replacing any previous declaration of the same prefix.
+    ///
+    /// See [`set_prefixes`](#method.set_prefixes).
+    pub fn add_prefix<P, N>(&mut self, prefix: P, namespace: N) -> &mut Self
+    where
+        P: Into<String>,
+        N: Into<String>,
+    {
+        let prefix = prefix.into();
+        let namespace = namespace.into();
+        match self.prefixes.iter_mut().find(|(p, _)| *p == prefix) {
+            Some(pair) => pair.1 = namespace,
+            None => self.prefixes.push((prefix, namespace)),
+        }
+        self
+    }
+
+    /// Add the prefix declarations recorded in `metadata`
+    /// (typically, the prefixes of the file from which a graph was loaded),
+    /// replacing any previous declaration of the same prefixes.
+    ///
+    /// See [`set_prefixes`](#method.set_prefixes).
+    pub fn add_prefixes_from(&mut self, metadata: &GraphMetadata) -> &mut Self {
+        for (prefix, namespace) in &metadata.prefixes {
+            self.add_prefix(prefix.as_str(), namespace.as_str());
+        }
+        self
+    }
+
+    /// Whether subjects are written in order.
+    pub fn sort_subjects(&self) -> bool {
+        self.sort_subjects
+    }
+    /// Set the sort subjects configuration (default: false).
+    ///
+    /// If true, serializers grouping triples by subject
+    /// (Turtle in [pretty mode](../turtle/struct.TurtleConfig.html#method.set_pretty))
+    /// write the groups in the order of their subjects,
+    /// rather than in the order in which subjects are first encountered.
+    /// This makes the output independent of the order of the input,
+    /// at least when blank nodes are [labeled canonically](enum.BnodeLabeling.html#variant.Canonical).
+    pub fn set_sort_subjects(&mut self, sort_subjects: bool) -> &mut Self {
+        self.sort_subjects = sort_subjects;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn auto_quote_style() {
+        assert_eq!(QuoteStyle::Auto.choose("abc"), '"');
+        assert_eq!(QuoteStyle::Auto.choose("a \"b\" c"), '\'');
+        assert_eq!(QuoteStyle::Auto.choose("a \"b\" c'"), '"');
+        assert_eq!(QuoteStyle::Single.choose("abc"), '\'');
+    }
+}
@@ -0,0 +1,232 @@
+//! Blank node labeling, as used by serializers.
+//!
+//! Serializers relabel blank nodes according to the [`BnodeLabeling`] strategy
+//! of their [`SerializerConfig`].
+//!
+//! [`BnodeLabeling`]: ../config/enum.BnodeLabeling.html
+//! [`SerializerConfig`]: ../config/struct.SerializerConfig.html
+
+use std::collections::HashMap;
+
+use sophia_api::term::{TTerm, TermKind};
+use sophia_term::BoxTerm;
+
+use super::config::BnodeLabeling;
+use super::nt::write_term;
+
+/// Assigns labels to blank nodes, according to a [`BnodeLabeling`] strategy.
+///
+/// [`BnodeLabeling`]: ../config/enum.BnodeLabeling.html
+#[derive(Clone, Debug)]
+pub struct BnodeLabeler {
+    strategy: BnodeLabeling,
+    labels: HashMap<String, BoxTerm>,
+    fallback: usize,
+}
+
+impl BnodeLabeler {
+    /// Build a labeler for the given strategy.
+    ///
+    /// NB: for [`BnodeLabeling::Canonical`],
+    /// labels must be computed beforehand from the whole data,
+    /// using [`canonical`](#method.canonical) instead;
+    /// a labeler built with this method will then behave as
+    /// [`BnodeLabeling::Sequential`].
+    ///
+    /// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+    /// [`BnodeLabeling::Sequential`]: ../config/enum.BnodeLabeling.html#variant.Sequential
+    pub fn new(strategy: BnodeLabeling) -> Self {
+        BnodeLabeler {
+            strategy,
+            labels: HashMap::new(),
+            fallback: 0,
+        }
+    }
+
+    /// Build a labeler assigning canonical labels (`c0`, `c1`...)
+    /// to the blank nodes of the given quads.
+    ///
+    /// Blank nodes are ordered according to a signature
+    /// computed from the quads they are involved in,
+    /// iteratively refined with the signatures of their neighbouring blank nodes.
+    /// As a consequence, isomorphic inputs get the same labels,
+    /// except for blank nodes that can not be distinguished by their signature
+    /// (e.g. in highly symmetrical graphs),
+    /// which are ordered by their original label.
+    pub fn canonical<'a, I>(quads: I) -> Self
+    where
+        I: IntoIterator<Item = ([&'a BoxTerm; 3], Option<&'a BoxTerm>)>,
+    {
+        let mut bnodes: Vec<&str> = vec![];
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut encoded: Vec<[Enc; 4]> = vec![];
+        let mut buffer = vec![];
+        for ([s, p, o], g) in quads {
+            let mut enc = [Enc::None; 4];
+            let terms = [Some(s), Some(p), Some(o), g];
+            for (i, t) in terms.iter().copied().enumerate() {
+                enc[i] = match t {
+                    None => Enc::None,
+                    Some(t) if t.kind() == TermKind::BlankNode => {
+                        let label = t.value_raw().0;
+                        let next = bnodes.len();
+                        let id = *index.entry(label).or_insert(next);
+                        if id == next {
+                            bnodes.push(label);
+                        }
+                        Enc::Bnode(id)
+                    }
+                    Some(t) => {
+                        buffer.clear();
+                        write_term(&mut buffer, t).unwrap(); // writing into a Vec can not fail
+                        Enc::Other(fnv(FNV_INIT, &buffer))
+                    }
+                };
+            }
+            encoded.push(enc);
+        }
+
+        let mut colors = vec![FNV_INIT; bnodes.len()];
+        let mut distinct = 1;
+        for _ in 0..bnodes.len() {
+            let mut signatures = vec![vec![]; bnodes.len()];
+            for enc in &encoded {
+                for (i, e) in enc.iter().enumerate() {
+                    if let Enc::Bnode(b) = e {
+                        let mut h = fnv(FNV_INIT, &[i as u8]);
+                        for other in enc.iter() {
+                            h = match other {
+                                Enc::Bnode(c) if c == b => fnv(h, b"self"),
+                                Enc::Bnode(c) => fnv(fnv(h, b"bnode"), &colors[*c].to_be_bytes()),
+                                Enc::Other(x) => fnv(h, &x.to_be_bytes()),
+                                Enc::None => fnv(h, b"none"),
+                            };
+                        }
+                        signatures[*b].push(h);
+                    }
+                }
+            }
+            let new_colors: Vec<u64> = signatures
+                .iter_mut()
+                .zip(colors.iter())
+                .map(|(sig, color)| {
+                    sig.sort_unstable();
+                    sig.iter()
+                        .fold(fnv(FNV_INIT, &color.to_be_bytes()), |h, x| {
+                            fnv(h, &x.to_be_bytes())
+                        })
+                })
+                .collect();
+            let mut sorted = new_colors.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            colors = new_colors;
+            if sorted.len() <= distinct {
+                break;
+            }
+            distinct = sorted.len();
+        }
+
+        let mut order: Vec<usize> = (0..bnodes.len()).collect();
+        order.sort_by(|a, b| {
+            colors[*a]
+                .cmp(&colors[*b])
+                .then_with(|| bnodes[*a].cmp(bnodes[*b]))
+        });
+        let labels = order
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| {
+                (
+                    bnodes[b].to_string(),
+                    BoxTerm::new_bnode_unchecked(format!("c{}", i)),
+                )
+            })
+            .collect();
+        BnodeLabeler {
+            strategy: BnodeLabeling::Canonical,
+            labels,
+            fallback: 0,
+        }
+    }
+
+    /// Call `f` with `t`, or with its relabeled version if `t` is a blank node.
+    pub fn with_label<R, F>(&mut self, t: &dyn TTerm, f: F) -> R
+    where
+        F: FnOnce(&dyn TTerm) -> R,
+    {
+        if self.strategy == BnodeLabeling::Preserve || t.kind() != TermKind::BlankNode {
+            return f(t);
+        }
+        let label = t.value_raw().0;
+        if let Some(new) = self.labels.get(label) {
+            return f(new);
+        }
+        let new = match self.strategy {
+            BnodeLabeling::Canonical => {
+                // should not happen, unless the labeler is used on unexpected data
+                self.fallback += 1;
+                BoxTerm::new_bnode_unchecked(format!("x{}", self.fallback - 1))
+            }
+            _ => BoxTerm::new_bnode_unchecked(format!("b{}", self.labels.len())),
+        };
+        let ret = f(&new);
+        self.labels.insert(label.to_string(), new);
+        ret
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Enc {
+    None,
+    Bnode(usize),
+    Other(u64),
+}
+
+const FNV_INIT: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64-bit FNV-1a hash, used instead of std's hashers for its stability.
+fn fnv(mut h: u64, bytes: &[u8]) -> u64 {
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn relabel(labeler: &mut BnodeLabeler, t: &BoxTerm) -> String {
+        labeler.with_label(t, |t| t.value().to_string())
+    }
+
+    #[test]
+    fn sequential() {
+        let mut labeler = BnodeLabeler::new(BnodeLabeling::Sequential);
+        let x = BoxTerm::new_bnode_unchecked("x");
+        let y = BoxTerm::new_bnode_unchecked("y");
+        let i = BoxTerm::new_iri_unchecked("http://example.org/");
+        assert_eq!(relabel(&mut labeler, &y), "b0");
+        assert_eq!(relabel(&mut labeler, &x), "b1");
+        assert_eq!(relabel(&mut labeler, &y), "b0");
+        assert_eq!(relabel(&mut labeler, &i), "http://example.org/");
+    }
+
+    #[test]
+    fn canonical() {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let q = BoxTerm::new_iri_unchecked("http://example.org/q");
+        let labels = |a: &str, b: &str| {
+            let a = BoxTerm::new_bnode_unchecked(a);
+            let b = BoxTerm::new_bnode_unchecked(b);
+            // a p b ; b q a ; b q b
+            let quads = vec![[&a, &p, &b], [&b, &q, &a], [&b, &q, &b]];
+            let mut labeler = BnodeLabeler::canonical(quads.into_iter().map(|t| (t, None)));
+            (relabel(&mut labeler, &a), relabel(&mut labeler, &b))
+        };
+        assert_eq!(labels("x", "y"), labels("y", "x"));
+        assert_eq!(labels("x", "y"), labels("foo", "bar"));
+    }
+}
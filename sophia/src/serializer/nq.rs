@@ -1,37 +1,87 @@
 //! Serializer for the [N-Quads] concrete syntax of RDF.
 //!
-//! **Important**:
-//! the methods in this module accepting a [`Write`]
-//! make no effort to minimize the number of write operations.
-//! Hence, in most cased, they should be passed a [`BufWriter`].
+//! [`NqSerializer`] writes through an internal [`BufWriter`],
+//! so it can be given an unbuffered [`Write`] (such as a `File`).
+//! Quads are written as they are read from their source,
+//! and [`serialize_quads`] can be called several times to write a large export piecewise;
+//! quads can also be written one at a time, through the [`QuadSink`] trait.
+//! Buffered data is written at the end of each call to [`serialize_quads`],
+//! when [`flush`] or [`finish`] is called,
+//! or (ignoring errors) when the serializer is dropped.
 //!
 //! [N-Quads]: https://www.w3.org/TR/n-quads/
+//! [`NqSerializer`]: struct.NqSerializer.html
+//! [`serialize_quads`]: struct.NqSerializer.html#method.serialize_quads
+//! [`flush`]: struct.NqSerializer.html#method.flush
+//! [`finish`]: struct.NqSerializer.html#method.finish
+//! [`QuadSink`]: ../trait.QuadSink.html
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use super::nt::write_term;
+use super::config::{BnodeLabeling, SerializerConfig};
+use super::labeling::BnodeLabeler;
+use super::nt::{write_term, write_term_with_config};
+use super::parallel::ParallelEncoder;
+use sophia_api::dataset::Dataset;
 use sophia_api::quad::{stream::*, Quad};
 use sophia_api::serializer::*;
-use std::io;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use std::io::{self, BufWriter, Write};
 
 /// N-Quads serializer configuration.
 #[derive(Clone, Debug, Default)]
 pub struct NqConfig {
-    ascii: bool,
+    common: SerializerConfig,
+    threads: usize,
 }
 
 impl NqConfig {
     /// Set the ascii configuration.
     pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
-        self.ascii = ascii;
+        self.common.set_ascii(ascii);
+        self
+    }
+
+    /// Borrow the options common to all text serializers.
+    ///
+    /// Only the `ascii` option is relevant to N-Quads.
+    pub fn common(&self) -> &SerializerConfig {
+        &self.common
+    }
+
+    /// Mutably borrow the options common to all text serializers.
+    pub fn common_mut(&mut self) -> &mut SerializerConfig {
+        &mut self.common
+    }
+
+    /// Set the number of worker threads used to encode quads (default: none).
+    ///
+    /// With more than one thread, quads are copied by the calling thread,
+    /// encoded by the worker threads, and written back in order.
+    /// This is mostly useful for large in-memory datasets,
+    /// where encoding and escaping dominate the serialization time.
+    pub fn set_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads;
         self
     }
 }
 
+impl From<SerializerConfig> for NqConfig {
+    fn from(common: SerializerConfig) -> Self {
+        NqConfig { common, threads: 0 }
+    }
+}
+
 /// N-Quads serializer.
-pub struct NqSerializer<W> {
+///
+/// See [module documentation](index.html).
+pub struct NqSerializer<W: io::Write> {
     config: NqConfig,
-    write: W,
+    write: BufWriter<W>,
+    labeler: BnodeLabeler,
+    // whether quads have already been labeled canonically
+    canonical_done: bool,
 }
 
 impl<W> NqSerializer<W>
@@ -46,13 +96,33 @@ where
 
     /// Build a new N-Quads serializer writing to `write`, with the given config.
     pub fn new_with_config(write: W, config: NqConfig) -> NqSerializer<W> {
-        NqSerializer { write, config }
+        Self::with_buffer(BufWriter::new(write), config)
+    }
+
+    fn with_buffer(write: BufWriter<W>, config: NqConfig) -> NqSerializer<W> {
+        let labeler = BnodeLabeler::new(config.common.bnode_labeling());
+        NqSerializer {
+            write,
+            config,
+            labeler,
+            canonical_done: false,
+        }
     }
 
     /// Borrow this serializer's configuration.
     pub fn config(&self) -> &NqConfig {
         &self.config
     }
+
+    /// Write all buffered data to the underlying writer, and flush it.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+
+    /// Write all buffered data, and return the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        Ok(self.write.into_inner()?)
+    }
 }
 
 impl<W> QuadSerializer for NqSerializer<W>
@@ -61,53 +131,206 @@ where
 {
     type Error = io::Error;
 
-    fn serialize_quads<QS>(
-        &mut self,
-        mut source: QS,
-    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    /// Serialize all quads from the given [`QuadSource`],
+    /// as they are read from it.
+    ///
+    /// This method can be called several times:
+    /// blank nodes are relabeled consistently across calls.
+    /// The exception is [`BnodeLabeling::Canonical`],
+    /// which requires to hold all the quads in memory,
+    /// and labels the quads of a single call:
+    /// a second call would reuse the same labels for unrelated blank nodes,
+    /// so it fails with an [`InvalidInput`] error instead.
+    ///
+    /// All the serialized data is written to the underlying writer
+    /// (and flushed) before this method returns.
+    ///
+    /// [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+    /// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+    /// [`InvalidInput`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+    fn serialize_quads<QS>(&mut self, source: QS) -> StreamResult<&mut Self, QS::Error, Self::Error>
     where
         QS: QuadSource,
     {
-        if self.config.ascii {
-            todo!("Pure-ASCII N-Quads is not implemented yet")
+        let config = &self.config.common;
+        let threads = self.config.threads;
+        let w = &mut self.write;
+        if config.bnode_labeling() == BnodeLabeling::Canonical {
+            if self.canonical_done {
+                return Err(SinkError(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "canonical blank node labels can only be computed for a single call",
+                )));
+            }
+            self.canonical_done = true;
+            let quads: Vec<([BoxTerm; 3], Option<BoxTerm>)> = source
+                .collect_quads()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            let mut labeler =
+                BnodeLabeler::canonical(quads.iter().map(|([s, p, o], g)| ([s, p, o], g.as_ref())));
+            write_quads(w, config, threads, &mut labeler, quads.quads())
+                .map_err(|e| SinkError(e.unwrap_sink_error()))?;
+        } else {
+            write_quads(w, config, threads, &mut self.labeler, source)?;
         }
-        source
-            .try_for_each_quad(|q| {
-                {
-                    let w = &mut self.write;
-                    write_term(w, q.s())?;
-                    w.write_all(b" ")?;
-                    write_term(w, q.p())?;
-                    w.write_all(b" ")?;
-                    write_term(w, q.o())?;
-                    if let Some(n) = q.g() {
-                        w.write_all(b" ")?;
-                        write_term(w, n)?;
-                    }
-                    w.write_all(b".\n")
-                }
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
-            })
-            .map(|_| self)
+        self.write.flush().map_err(SinkError)?;
+        Ok(self)
     }
 }
 
+/// Quads pushed one at a time are relabeled consistently with all other quads,
+/// but can not be labeled canonically:
+/// with [`BnodeLabeling::Canonical`], they are labeled as with [`BnodeLabeling::Sequential`].
+///
+/// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+/// [`BnodeLabeling::Sequential`]: ../config/enum.BnodeLabeling.html#variant.Sequential
+impl<W> QuadSink for NqSerializer<W>
+where
+    W: io::Write,
+{
+    fn push_quad<Q>(&mut self, quad: &Q) -> Result<(), Self::Error>
+    where
+        Q: Quad,
+    {
+        write_quad(
+            &mut self.write,
+            &self.config.common,
+            &mut self.labeler,
+            quad,
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
+
+fn write_quads<W, QS>(
+    w: &mut W,
+    config: &SerializerConfig,
+    threads: usize,
+    labeler: &mut BnodeLabeler,
+    mut source: QS,
+) -> StreamResult<(), QS::Error, io::Error>
+where
+    W: io::Write,
+    QS: QuadSource,
+{
+    if threads > 1 {
+        let mut encoder = ParallelEncoder::new(config, threads);
+        source.try_for_each_quad(|q| encoder.push(w, labeler, [q.s(), q.p(), q.o()], q.g()))?;
+        return encoder.finish(w).map_err(SinkError);
+    }
+    source.try_for_each_quad(|q| write_quad(w, config, labeler, &q))
+}
+
+/// Write one quad, relabeling its blank nodes with `labeler`.
+pub(crate) fn write_quad<W, Q>(
+    w: &mut W,
+    config: &SerializerConfig,
+    labeler: &mut BnodeLabeler,
+    q: &Q,
+) -> io::Result<()>
+where
+    W: io::Write,
+    Q: Quad,
+{
+    labeler.with_label(q.s().as_dyn(), |s| write_term_with_config(w, s, config))?;
+    w.write_all(b" ")?;
+    write_term_with_config(w, q.p(), config)?;
+    w.write_all(b" ")?;
+    labeler.with_label(q.o().as_dyn(), |o| write_term_with_config(w, o, config))?;
+    if let Some(n) = q.g() {
+        w.write_all(b" ")?;
+        labeler.with_label(n.as_dyn(), |n| write_term_with_config(w, n, config))?;
+    }
+    w.write_all(b".\n")
+}
+
+pub(crate) type BoxQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// The quads of `dataset`, with canonical blank node labels.
+pub(crate) fn canonical_quads<D: Dataset>(dataset: &D) -> Result<Vec<BoxQuad>, D::Error> {
+    let quads = dataset
+        .quads()
+        .map(|q| {
+            q.map(|q| {
+                (
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut labeler =
+        BnodeLabeler::canonical(quads.iter().map(|([s, p, o], g)| ([s, p, o], g.as_ref())));
+    let mut relabel = |t: &BoxTerm| labeler.with_label(t.as_dyn(), |t| BoxTerm::copy(t));
+    Ok(quads
+        .iter()
+        .map(|([s, p, o], g)| {
+            (
+                [relabel(s), relabel(p), relabel(o)],
+                g.as_ref().map(&mut relabel),
+            )
+        })
+        .collect())
+}
+
+/// The canonical lines of `quads`: N-Quads, sorted and deduplicated.
+pub(crate) fn canonical_lines(quads: &[BoxQuad]) -> Vec<u8> {
+    let mut lines: Vec<Vec<u8>> = quads
+        .iter()
+        .map(|([s, p, o], g)| {
+            let mut line = vec![];
+            // writing into a Vec can not fail
+            for t in [Some(s), Some(p), Some(o), g.as_ref()].iter().flatten() {
+                write_term(&mut line, *t).unwrap();
+                line.push(b' ');
+            }
+            line.extend_from_slice(b".\n");
+            line
+        })
+        .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    lines.concat()
+}
+
+/// The canonical N-Quads serialization of `dataset`,
+/// where blank nodes are labeled canonically (`c0`, `c1`...),
+/// and lines are sorted in lexicographic order (without duplicates).
+pub fn canonical_nquads<D: Dataset>(dataset: &D) -> Result<String, D::Error> {
+    let lines = canonical_lines(&canonical_quads(dataset)?);
+    Ok(String::from_utf8(lines).unwrap()) // N-Quads are written in UTF-8
+}
+
 impl NqSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
     pub fn new_stringifier() -> Self {
-        NqSerializer::new(Vec::new())
+        NqSerializer::new_stringifier_with_config(NqConfig::default())
     }
     /// Create a new serializer which targets a `String` with a custom config.
     #[inline]
     pub fn new_stringifier_with_config(config: NqConfig) -> Self {
-        NqSerializer::new_with_config(Vec::new(), config)
+        // no need to buffer writes to a Vec
+        NqSerializer::with_buffer(BufWriter::with_capacity(0, Vec::new()), config)
     }
 }
 
+/// NB: only the data written to the underlying `Vec` is visible;
+/// serializers built with [`new_stringifier`](struct.NqSerializer.html#method.new_stringifier)
+/// do not buffer their output,
+/// but with those built with [`new`](struct.NqSerializer.html#method.new),
+/// quads pushed through [`QuadSink`](../trait.QuadSink.html) are only visible
+/// once the serializer is [flushed](struct.NqSerializer.html#method.flush).
 impl Stringifier for NqSerializer<Vec<u8>> {
     fn as_utf8(&self) -> &[u8] {
-        &self.write[..]
+        &self.write.get_ref()[..]
     }
 }
 
@@ -154,4 +377,176 @@ pub(crate) mod test {
 "#
         );
     }
+
+    #[test]
+    fn dataset_matching() {
+        let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
+        let g1 = StaticTerm::new_iri("http://champin.net/").unwrap();
+        let g2 = StaticTerm::new_iri("http://example.org/").unwrap();
+        let d = vec![
+            ([me, rdf::type_.into(), rdfs::Resource.into()], None),
+            ([me, rdf::type_.into(), rdfs::Resource.into()], Some(g1)),
+            ([me, rdfs::label.into(), "PA".as_literal().into()], Some(g2)),
+        ];
+        let s = NqSerializer::new_stringifier()
+            .serialize_dataset_matching(&d, Some(&g1))
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            r#"<http://champin.net/#pa> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/2000/01/rdf-schema#Resource> <http://champin.net/>.
+"#
+        );
+    }
+
+    #[test]
+    fn parallel() {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let g = BoxTerm::new_iri_unchecked("http://example.org/g");
+        let d: Vec<([BoxTerm; 3], Option<BoxTerm>)> = (0..5000)
+            .map(|i| {
+                let s = BoxTerm::new_bnode_unchecked(format!("n{}", i % 7));
+                let o = BoxTerm::new_literal_lang_unchecked(format!("value {}\n", i), "en");
+                (
+                    [s, p.clone(), o],
+                    if i % 3 == 0 { Some(g.clone()) } else { None },
+                )
+            })
+            .collect();
+        let mut config = NqConfig::default();
+        config
+            .common_mut()
+            .set_bnode_labeling(BnodeLabeling::Sequential);
+        let expected = NqSerializer::new_stringifier_with_config(config.clone())
+            .serialize_dataset(&d)
+            .unwrap()
+            .to_string();
+        config.set_threads(4);
+        let got = NqSerializer::new_stringifier_with_config(config)
+            .serialize_dataset(&d)
+            .unwrap()
+            .to_string();
+        assert_eq!(got.lines().count(), 5000);
+        assert_eq!(got, expected);
+    }
+
+    /// A writer counting the write operations it receives.
+    struct CountingWriter(Vec<u8>, usize);
+
+    impl io::Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.1 += 1;
+            self.0.write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn buffered() -> Result<(), Box<dyn std::error::Error>> {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let d: Vec<([BoxTerm; 3], Option<BoxTerm>)> = (0..1000)
+            .map(|i| ([p.clone(), p.clone(), BoxTerm::from(i.to_string())], None))
+            .collect();
+        let mut ser = NqSerializer::new(CountingWriter(vec![], 0));
+        ser.serialize_dataset(&d)?;
+        assert!(ser.write.get_ref().1 < 100);
+        ser.flush()?;
+        assert_eq!(ser.write.buffer().len(), 0);
+        let CountingWriter(bytes, writes) = ser.finish()?;
+        assert!(writes < 100);
+        assert_eq!(String::from_utf8(bytes)?.lines().count(), 1000);
+        Ok(())
+    }
+
+    #[test]
+    fn incremental() -> Result<(), Box<dyn std::error::Error>> {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let x = BoxTerm::new_bnode_unchecked("x");
+        let y = BoxTerm::new_bnode_unchecked("y");
+        let mut config = NqConfig::default();
+        config
+            .common_mut()
+            .set_bnode_labeling(BnodeLabeling::Sequential);
+        let mut ser = NqSerializer::new_with_config(Vec::new(), config);
+        ser.serialize_quads(vec![([x.clone(), p.clone(), y.clone()], None::<BoxTerm>)].quads())?;
+        ser.flush()?;
+        assert_eq!(
+            std::str::from_utf8(ser.as_utf8())?,
+            "_:b0 <http://example.org/p> _:b1.\n"
+        );
+        ser.serialize_quads(vec![([y, p.clone(), x], None::<BoxTerm>)].quads())?;
+        let got = String::from_utf8(ser.finish()?)?;
+        assert_eq!(
+            got,
+            "_:b0 <http://example.org/p> _:b1.\n_:b1 <http://example.org/p> _:b0.\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_once() -> Result<(), Box<dyn std::error::Error>> {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let x = BoxTerm::new_bnode_unchecked("x");
+        let y = BoxTerm::new_bnode_unchecked("y");
+        let mut config = NqConfig::default();
+        config
+            .common_mut()
+            .set_bnode_labeling(BnodeLabeling::Canonical);
+        let mut ser = NqSerializer::new_stringifier_with_config(config);
+        ser.serialize_quads(vec![([x, p.clone(), p.clone()], None::<BoxTerm>)].quads())?;
+        assert_eq!(
+            ser.as_str(),
+            "_:c0 <http://example.org/p> <http://example.org/p>.\n"
+        );
+        let err = ser
+            .serialize_quads(vec![([y, p.clone(), p], None::<BoxTerm>)].quads())
+            .err()
+            .unwrap()
+            .unwrap_sink_error();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        // nothing was written by the failed call
+        assert_eq!(
+            ser.as_str(),
+            "_:c0 <http://example.org/p> <http://example.org/p>.\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stringify_buffered() -> Result<(), Box<dyn std::error::Error>> {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let d = vec![([p.clone(), p.clone(), p], None::<BoxTerm>)];
+        let got = NqSerializer::new(Vec::new())
+            .serialize_dataset(&d)?
+            .to_string();
+        assert_eq!(
+            got,
+            "<http://example.org/p> <http://example.org/p> <http://example.org/p>.\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sink() -> Result<(), Box<dyn std::error::Error>> {
+        let p = BoxTerm::new_iri_unchecked("http://example.org/p");
+        let x = BoxTerm::new_bnode_unchecked("x");
+        let mut config = NqConfig::default();
+        config
+            .common_mut()
+            .set_bnode_labeling(BnodeLabeling::Sequential);
+        let mut ser = NqSerializer::new_with_config(Vec::new(), config);
+        ser.push_quad(&([x.clone(), p.clone(), p.clone()], None::<BoxTerm>))?;
+        ser.serialize_quads(vec![([p.clone(), p.clone(), x.clone()], Some(x))].quads())?;
+        QuadSink::flush(&mut ser)?;
+        assert_eq!(
+            std::str::from_utf8(ser.as_utf8())?,
+            concat!(
+                "_:b0 <http://example.org/p> <http://example.org/p>.\n",
+                "<http://example.org/p> <http://example.org/p> _:b0 _:b0.\n",
+            )
+        );
+        Ok(())
+    }
 }
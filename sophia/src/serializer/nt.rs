@@ -1,39 +1,123 @@
 //! Serializer for the [N-Triples] concrete syntax of RDF.
 //!
+//! [`NtSerializer`] writes triples as they are read from their source;
+//! [`serialize_triples`] can be called several times,
+//! and triples can also be written one at a time, through the [`TripleSink`] trait.
+//!
 //! **Important**:
 //! the methods in this module accepting a [`Write`]
-//! make no effort to minimize the number of write operations.
+//! stream escaped text directly into it, without intermediate allocations,
+//! but make no effort to minimize the number of write operations.
 //! Hence, in most cased, they should be passed a [`BufWriter`].
 //!
 //! [N-Triples]: https://www.w3.org/TR/n-triples/
+//! [`NtSerializer`]: struct.NtSerializer.html
+//! [`serialize_triples`]: struct.NtSerializer.html#method.serialize_triples
+//! [`TripleSink`]: ../trait.TripleSink.html
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
+use super::config::{BnodeLabeling, SerializerConfig};
+use super::labeling::BnodeLabeler;
+use super::parallel::ParallelEncoder;
+use crate::syntax::escape::{write_canonical_string, write_escaped_iri, write_escaped_string};
+use sophia_api::graph::Graph;
 use sophia_api::ns::xsd;
 use sophia_api::serializer::*;
 use sophia_api::term::{TTerm, TermKind};
-use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
 use sophia_api::triple::Triple;
+use sophia_term::literal::canonical::canonical_form;
+use sophia_term::BoxTerm;
 use std::io;
 
 /// N-Triples serializer configuration.
 #[derive(Clone, Debug, Default)]
 pub struct NtConfig {
-    ascii: bool,
+    common: SerializerConfig,
+    threads: usize,
+    canonical: bool,
 }
 
 impl NtConfig {
     /// Set the ascii configuration.
     pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
-        self.ascii = ascii;
+        self.common.set_ascii(ascii);
+        self
+    }
+
+    /// Borrow the options common to all text serializers.
+    ///
+    /// Only the `ascii` option is relevant to N-Triples.
+    pub fn common(&self) -> &SerializerConfig {
+        &self.common
+    }
+
+    /// Mutably borrow the options common to all text serializers.
+    pub fn common_mut(&mut self) -> &mut SerializerConfig {
+        &mut self.common
+    }
+
+    /// Set the number of worker threads used to encode triples (default: none).
+    ///
+    /// With more than one thread, triples are copied by the calling thread,
+    /// encoded by the worker threads, and written back in order.
+    /// This is mostly useful for large in-memory graphs,
+    /// where encoding and escaping dominate the serialization time.
+    pub fn set_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads;
         self
     }
+
+    /// Whether the output is canonical N-Triples.
+    pub fn canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Set the canonical configuration (default: false).
+    ///
+    /// If true, the output is [canonical N-Triples],
+    /// suitable for comparing, hashing or signing graphs:
+    /// * blank nodes are labeled canonically
+    ///   (see [`BnodeLabeling::Canonical`](../config/enum.BnodeLabeling.html#variant.Canonical)),
+    /// * literals are written in their [canonical lexical form] when their datatype is supported,
+    ///   with lowercase language tags,
+    ///   and escaped with [`write_canonical_string`],
+    /// * lines are sorted (by code point), and duplicate lines are removed.
+    ///
+    /// NB: as literals are rewritten, the output is only *value-equivalent* to the input,
+    /// not isomorphic to it.
+    /// Triples differing only by the lexical form of a literal with the same value
+    /// (e.g. `"+007"^^xsd:integer` and `"7"^^xsd:integer`) are written as the same line,
+    /// and therefore merged, so the output may contain fewer triples than the input.
+    ///
+    /// All other options are ignored in canonical mode,
+    /// and the whole graph is held in memory.
+    ///
+    /// [canonical N-Triples]: https://www.w3.org/TR/rdf12-n-triples/#canonical-ntriples
+    /// [canonical lexical form]: https://docs.rs/sophia_term/0.6/sophia_term/literal/canonical/fn.canonical_form.html
+    /// [`write_canonical_string`]: ../../syntax/escape/fn.write_canonical_string.html
+    pub fn set_canonical(&mut self, canonical: bool) -> &mut Self {
+        self.canonical = canonical;
+        self
+    }
+}
+
+impl From<SerializerConfig> for NtConfig {
+    fn from(common: SerializerConfig) -> Self {
+        NtConfig {
+            common,
+            threads: 0,
+            canonical: false,
+        }
+    }
 }
 
 /// N-Triples serializer.
 pub struct NtSerializer<W> {
     config: NtConfig,
     write: W,
+    labeler: BnodeLabeler,
 }
 
 impl<W> NtSerializer<W>
@@ -48,7 +132,12 @@ where
 
     /// Build a new N-Triples serializer writing to `write`, with the given config.
     pub fn new_with_config(write: W, config: NtConfig) -> NtSerializer<W> {
-        NtSerializer { write, config }
+        let labeler = BnodeLabeler::new(config.common.bnode_labeling());
+        NtSerializer {
+            write,
+            config,
+            labeler,
+        }
     }
 
     /// Borrow this serializer's configuration.
@@ -63,33 +152,145 @@ where
 {
     type Error = io::Error;
 
+    /// Serialize all triples from the given [`TripleSource`],
+    /// as they are read from it.
+    ///
+    /// This method can be called several times:
+    /// blank nodes are relabeled consistently across calls,
+    /// except with [`BnodeLabeling::Canonical`] or in [canonical mode],
+    /// where the triples of each call are labeled on their own.
+    ///
+    /// [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+    /// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+    /// [canonical mode]: struct.NtConfig.html#method.set_canonical
     fn serialize_triples<TS>(
         &mut self,
-        mut source: TS,
+        source: TS,
     ) -> StreamResult<&mut Self, TS::Error, Self::Error>
     where
         TS: TripleSource,
     {
-        if self.config.ascii {
-            todo!("Pure-ASCII N-Triples is not implemented yet")
+        let config = &self.config.common;
+        let threads = self.config.threads;
+        let w = &mut self.write;
+        if self.config.canonical {
+            let triples: Vec<[BoxTerm; 3]> = source
+                .collect_triples()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            write_canonical_triples(w, &triples).map_err(SinkError)?;
+        } else if config.bnode_labeling() == BnodeLabeling::Canonical {
+            let triples: Vec<[BoxTerm; 3]> = source
+                .collect_triples()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            let mut labeler =
+                BnodeLabeler::canonical(triples.iter().map(|[s, p, o]| ([s, p, o], None)));
+            write_triples(w, config, threads, &mut labeler, triples.triples())
+                .map_err(|e| SinkError(e.unwrap_sink_error()))?;
+        } else {
+            write_triples(w, config, threads, &mut self.labeler, source)?;
         }
-        source
-            .try_for_each_triple(|t| {
-                {
-                    let w = &mut self.write;
-                    write_term(w, t.s())?;
-                    w.write_all(b" ")?;
-                    write_term(w, t.p())?;
-                    w.write_all(b" ")?;
-                    write_term(w, t.o())?;
-                    w.write_all(b".\n")
-                }
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
-            })
-            .map(|_| self)
+        Ok(self)
     }
 }
 
+/// Triples pushed one at a time are relabeled consistently with all other triples,
+/// but can not be labeled canonically:
+/// with [`BnodeLabeling::Canonical`], they are labeled as with [`BnodeLabeling::Sequential`].
+/// In [canonical mode], which requires sorting the whole graph,
+/// [`push_triple`] fails with an [`InvalidInput`] error.
+///
+/// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+/// [`BnodeLabeling::Sequential`]: ../config/enum.BnodeLabeling.html#variant.Sequential
+/// [canonical mode]: struct.NtConfig.html#method.set_canonical
+/// [`push_triple`]: ../trait.TripleSink.html#tymethod.push_triple
+/// [`InvalidInput`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+impl<W> TripleSink for NtSerializer<W>
+where
+    W: io::Write,
+{
+    fn push_triple<T>(&mut self, triple: &T) -> Result<(), Self::Error>
+    where
+        T: Triple,
+    {
+        if self.config.canonical {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "canonical N-Triples can not be written one triple at a time",
+            ));
+        }
+        write_triple(
+            &mut self.write,
+            &self.config.common,
+            &mut self.labeler,
+            triple,
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
+
+fn write_triples<W, TS>(
+    w: &mut W,
+    config: &SerializerConfig,
+    threads: usize,
+    labeler: &mut BnodeLabeler,
+    mut source: TS,
+) -> StreamResult<(), TS::Error, io::Error>
+where
+    W: io::Write,
+    TS: TripleSource,
+{
+    if threads > 1 {
+        let mut encoder = ParallelEncoder::new(config, threads);
+        source.try_for_each_triple(|t| encoder.push(w, labeler, [t.s(), t.p(), t.o()], None))?;
+        return encoder.finish(w).map_err(SinkError);
+    }
+    source.try_for_each_triple(|t| write_triple(w, config, labeler, &t))
+}
+
+/// Write one triple, relabeling its blank nodes with `labeler`.
+fn write_triple<W, T>(
+    w: &mut W,
+    config: &SerializerConfig,
+    labeler: &mut BnodeLabeler,
+    t: &T,
+) -> io::Result<()>
+where
+    W: io::Write,
+    T: Triple,
+{
+    labeler.with_label(t.s().as_dyn(), |s| write_term_with_config(w, s, config))?;
+    w.write_all(b" ")?;
+    write_term_with_config(w, t.p(), config)?;
+    w.write_all(b" ")?;
+    labeler.with_label(t.o().as_dyn(), |o| write_term_with_config(w, o, config))?;
+    w.write_all(b".\n")
+}
+
+fn write_canonical_triples<W: io::Write>(w: &mut W, triples: &[[BoxTerm; 3]]) -> io::Result<()> {
+    let mut labeler = BnodeLabeler::canonical(triples.iter().map(|[s, p, o]| ([s, p, o], None)));
+    let mut lines = Vec::with_capacity(triples.len());
+    for [s, p, o] in triples {
+        let mut line = Vec::new();
+        labeler.with_label(s, |s| write_canonical_term(&mut line, s))?;
+        line.push(b' ');
+        write_canonical_term(&mut line, p)?;
+        line.push(b' ');
+        labeler.with_label(o, |o| write_canonical_term(&mut line, o))?;
+        line.extend_from_slice(b" .\n");
+        lines.push(line);
+    }
+    // sorting UTF-8 bytes is the same as sorting by code point
+    lines.sort_unstable();
+    lines.dedup();
+    for line in lines {
+        w.write_all(&line)?;
+    }
+    Ok(())
+}
+
 impl NtSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -111,6 +312,16 @@ impl Stringifier for NtSerializer<Vec<u8>> {
 
 /// Write the given term into the given write in the N-Triples format.
 pub fn write_term<W, T>(w: &mut W, t: &T) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    write_term_with_config(w, t, &SerializerConfig::default())
+}
+
+/// Write the given term into the given write in the N-Triples format,
+/// honouring the relevant options of `config`.
+pub fn write_term_with_config<W, T>(w: &mut W, t: &T, config: &SerializerConfig) -> io::Result<()>
 where
     W: io::Write,
     T: TTerm + ?Sized,
@@ -120,15 +331,15 @@ where
         Iri => {
             w.write_all(b"<")?;
             let v = t.value_raw();
-            w.write_all(v.0.as_bytes())?;
+            write_escaped_iri(w, v.0, config.ascii())?;
             if let Some(suffix) = v.1 {
-                w.write_all(suffix.as_bytes())?;
+                write_escaped_iri(w, suffix, config.ascii())?;
             }
             w.write_all(b">")
         }
         Literal => {
             w.write_all(b"\"")?;
-            quoted_string(w, t.value_raw().0.as_bytes())?;
+            write_escaped_string(w, t.value_raw().0, '"', config.ascii())?;
             match t.language() {
                 Some(tag) => {
                     w.write_all(b"\"@")?;
@@ -136,9 +347,9 @@ where
                 }
                 None => {
                     let dt = t.datatype().unwrap();
-                    if xsd::string != dt {
+                    if config.explicit_xsd_string() || xsd::string != dt {
                         w.write_all(b"\"^^")?;
-                        write_term(w, &dt)
+                        write_term_with_config(w, &dt, config)
                     } else {
                         w.write_all(b"\"")
                     }
@@ -156,42 +367,53 @@ where
     }
 }
 
-fn quoted_string<W: io::Write>(w: &mut W, txt: &[u8]) -> io::Result<()> {
-    let mut cut = txt.len();
-    let mut cutchar = b'\0';
-    for (pos, chr) in txt.iter().enumerate() {
-        let chr = *chr;
-        if chr <= b'\\' && (chr == b'\n' || chr == b'\r' || chr == b'\\' || chr == b'"') {
-            cut = pos;
-            cutchar = chr;
-            break;
-        }
+/// Write the given term into the given write in the canonical N-Triples format.
+///
+/// See [`NtConfig::set_canonical`](struct.NtConfig.html#method.set_canonical).
+/// NB: blank node labels are written as is,
+/// and literals with the same value but different lexical forms are written identically.
+pub fn write_canonical_term<W, T>(w: &mut W, t: &T) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    if t.kind() != TermKind::Literal {
+        return write_term(w, t);
     }
-    w.write_all(&txt[..cut])?;
-    if cut < txt.len() {
-        match cutchar {
-            b'\n' => {
-                w.write_all(b"\\n")?;
-            }
-            b'\r' => {
-                w.write_all(b"\\r")?;
-            }
-            b'"' => {
-                w.write_all(b"\\\"")?;
-            }
-            b'\\' => {
-                w.write_all(b"\\\\")?;
+    w.write_all(b"\"")?;
+    match canonical_form(t) {
+        Some(lex) => write_canonical_string(w, &lex)?,
+        None => write_canonical_string(w, t.value_raw().0)?,
+    }
+    match t.language() {
+        Some(tag) => {
+            w.write_all(b"\"@")?;
+            write_lowercase(w, tag)
+        }
+        None => {
+            let dt = t.datatype().unwrap();
+            if xsd::string != dt {
+                w.write_all(b"\"^^")?;
+                write_term(w, &dt)
+            } else {
+                w.write_all(b"\"")
             }
-            _ => unreachable!(),
         }
-    };
-    if cut + 1 >= txt.len() {
-        Ok(())
-    } else {
-        quoted_string(w, &txt[cut + 1..])
     }
 }
 
+/// Write `txt` lowercased, without allocating a new string.
+fn write_lowercase<W: io::Write>(w: &mut W, txt: &str) -> io::Result<()> {
+    let mut buffer = [0; 32];
+    for chunk in txt.as_bytes().chunks(buffer.len()) {
+        let lower = &mut buffer[..chunk.len()];
+        lower.copy_from_slice(chunk);
+        lower.make_ascii_lowercase();
+        w.write_all(lower)?;
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------------
 //                                      tests
 // ---------------------------------------------------------------------------------
@@ -229,4 +451,137 @@ pub(crate) mod test {
 "#
         );
     }
+
+    #[test]
+    fn graph_matching() {
+        let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
+        let g1 = StaticTerm::new_iri("http://champin.net/").unwrap();
+        let g2 = StaticTerm::new_iri("http://example.org/").unwrap();
+        let d = vec![
+            ([me, rdf::type_.into(), rdfs::Resource.into()], None),
+            ([me, rdfs::label.into(), "PA".as_literal().into()], Some(g1)),
+            (
+                [me, rdfs::comment.into(), "hidden".as_literal().into()],
+                Some(g2),
+            ),
+        ];
+        let s = NtSerializer::new_stringifier()
+            .serialize_graph_matching(&d, [None, Some(&g1)])
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            r#"<http://champin.net/#pa> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/2000/01/rdf-schema#Resource>.
+<http://champin.net/#pa> <http://www.w3.org/2000/01/rdf-schema#label> "PA".
+"#
+        );
+    }
+
+    #[test]
+    fn ascii() {
+        let me = StaticTerm::new_iri("http://example.org/é").unwrap();
+        let g = vec![[
+            me,
+            rdfs::label.into(),
+            "Çà \"et\" là 😀\n".as_literal().into(),
+        ]];
+        let mut config = NtConfig::default();
+        config.set_ascii(true);
+        let s = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            r#"<http://example.org/\u00E9> <http://www.w3.org/2000/01/rdf-schema#label> "\u00C7\u00E0 \"et\" l\u00E0 \U0001F600\n".
+"#
+        );
+    }
+
+    #[test]
+    fn bnode_labeling() {
+        let b1 = StaticTerm::new_bnode("x").unwrap();
+        let b2 = StaticTerm::new_bnode("y").unwrap();
+        let g = vec![
+            [b1, rdf::value.into(), b2],
+            [b2, rdf::type_.into(), rdf::Property.into()],
+        ];
+        let ser = |labeling| {
+            let mut config = NtConfig::default();
+            config.common_mut().set_bnode_labeling(labeling);
+            NtSerializer::new_stringifier_with_config(config)
+                .serialize_graph(&g)
+                .unwrap()
+                .to_string()
+        };
+        assert!(ser(BnodeLabeling::Preserve).starts_with("_:x "));
+        assert!(ser(BnodeLabeling::Sequential).starts_with("_:b0 "));
+        assert!(ser(BnodeLabeling::Sequential).ends_with("_:b1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#Property>.\n"));
+        let canonical = ser(BnodeLabeling::Canonical);
+        assert!(!canonical.contains("_:x"));
+        assert!(!canonical.contains("_:y"));
+    }
+
+    #[test]
+    fn sink() -> Result<(), Box<dyn std::error::Error>> {
+        let p = StaticTerm::new_iri("http://example.org/p")?;
+        let x = StaticTerm::new_bnode("x")?;
+        let mut config = NtConfig::default();
+        config
+            .common_mut()
+            .set_bnode_labeling(BnodeLabeling::Sequential);
+        let mut ser = NtSerializer::new_stringifier_with_config(config.clone());
+        ser.push_triple(&[x, p, p])?;
+        ser.serialize_triples(vec![[p, p, x]].triples())?;
+        TripleSink::flush(&mut ser)?;
+        assert_eq!(
+            ser.as_str(),
+            concat!(
+                "_:b0 <http://example.org/p> <http://example.org/p>.\n",
+                "<http://example.org/p> <http://example.org/p> _:b0.\n",
+            )
+        );
+
+        config.set_canonical(true);
+        let mut ser = NtSerializer::new_stringifier_with_config(config);
+        let err = ser.push_triple(&[x, p, p]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn canonical() -> Result<(), Box<dyn std::error::Error>> {
+        let b = StaticTerm::new_bnode("x")?;
+        let s = StaticTerm::new_iri("http://example.org/s")?;
+        let p = StaticTerm::new_iri("http://example.org/p")?;
+        let g = vec![
+            [s, p, StaticTerm::new_literal_dt("+007", xsd::integer)?],
+            [s, p, StaticTerm::new_literal_dt("1e2", xsd::double)?],
+            [s, p, StaticTerm::new_literal_dt("1", xsd::boolean)?],
+            // same value as "+007", hence merged with it
+            [s, p, StaticTerm::new_literal_dt("7", xsd::integer)?],
+            [s, p, StaticTerm::new_literal_lang("colour", "EN-gb")?],
+            [s, p, "tab\tbell\u{7}é\"".as_literal().into()],
+            [b, p, StaticTerm::new_literal_dt("not a date", xsd::date)?],
+            [s, p, b],
+        ];
+        let mut config = NtConfig::default();
+        config.set_canonical(true).set_ascii(true);
+        assert!(config.canonical());
+        let out = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"<http://example.org/s> <http://example.org/p> "1.0E2"^^<http://www.w3.org/2001/XMLSchema#double> .
+<http://example.org/s> <http://example.org/p> "7"^^<http://www.w3.org/2001/XMLSchema#integer> .
+<http://example.org/s> <http://example.org/p> "colour"@en-gb .
+<http://example.org/s> <http://example.org/p> "tab\tbell\u0007é\"" .
+<http://example.org/s> <http://example.org/p> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+<http://example.org/s> <http://example.org/p> _:c0 .
+_:c0 <http://example.org/p> "not a date"^^<http://www.w3.org/2001/XMLSchema#date> .
+"#
+        );
+        Ok(())
+    }
 }
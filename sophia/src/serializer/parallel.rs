@@ -0,0 +1,186 @@
+//! Multithreaded encoding for the N-Triples and N-Quads serializers.
+//!
+//! The main thread reads the source,
+//! copies (and possibly relabels) the terms of the quads,
+//! and sends them by chunks to worker threads,
+//! which encode them into byte buffers.
+//! The buffers are then written by the main thread in the original order.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::mem::replace;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+
+use super::config::SerializerConfig;
+use super::labeling::BnodeLabeler;
+use super::nt::write_term_with_config;
+
+pub(crate) type OwnedQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// Number of quads sent to a worker at once.
+const CHUNK_SIZE: usize = 1024;
+
+/// Encodes quads in N-Quads (or N-Triples if they are all in the default graph)
+/// using worker threads, and writes them in order.
+pub(crate) struct ParallelEncoder {
+    task_tx: mpsc::Sender<(usize, Vec<OwnedQuad>)>,
+    res_rx: mpsc::Receiver<(usize, io::Result<Vec<u8>>)>,
+    workers: Vec<thread::JoinHandle<()>>,
+    chunk: Vec<OwnedQuad>,
+    sent: usize,
+    reorder: Reorder,
+}
+
+impl ParallelEncoder {
+    /// Start `threads` worker threads, encoding terms according to `config`.
+    pub fn new(config: &SerializerConfig, threads: usize) -> Self {
+        let (task_tx, task_rx) = mpsc::channel::<(usize, Vec<OwnedQuad>)>();
+        let task_rx = Arc::new(Mutex::new(task_rx));
+        let (res_tx, res_rx) = mpsc::channel();
+        let workers = (0..threads)
+            .map(|_| {
+                let task_rx = task_rx.clone();
+                let res_tx = res_tx.clone();
+                let config = config.clone();
+                thread::spawn(move || loop {
+                    let task = task_rx.lock().unwrap().recv();
+                    let (i, quads) = match task {
+                        Ok(task) => task,
+                        Err(_) => break, // no more tasks
+                    };
+                    let mut buffer = Vec::with_capacity(quads.len() * 100);
+                    let res = encode(&mut buffer, &quads, &config).map(|_| buffer);
+                    if res_tx.send((i, res)).is_err() {
+                        break; // encoder was dropped
+                    }
+                })
+            })
+            .collect();
+        ParallelEncoder {
+            task_tx,
+            res_rx,
+            workers,
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+            sent: 0,
+            reorder: Reorder::default(),
+        }
+    }
+
+    /// Copy the given terms, relabeling blank nodes with `labeler`, and queue them for encoding.
+    ///
+    /// Already encoded chunks are written to `w`.
+    pub fn push<W, T>(
+        &mut self,
+        w: &mut W,
+        labeler: &mut BnodeLabeler,
+        [s, p, o]: [&T; 3],
+        g: Option<&T>,
+    ) -> io::Result<()>
+    where
+        W: io::Write,
+        T: TTerm + ?Sized,
+    {
+        let copy = |t: &dyn TTerm| BoxTerm::copy(t);
+        self.chunk.push((
+            [
+                labeler.with_label(s.as_dyn(), copy),
+                BoxTerm::copy(p),
+                labeler.with_label(o.as_dyn(), copy),
+            ],
+            g.map(|g| labeler.with_label(g.as_dyn(), copy)),
+        ));
+        if self.chunk.len() < CHUNK_SIZE {
+            return Ok(());
+        }
+        self.send_chunk()?;
+        while let Ok(res) = self.res_rx.try_recv() {
+            self.reorder.receive(w, res)?;
+        }
+        // bound the memory used by pending chunks
+        let max_in_flight = 2 * self.workers.len();
+        while self.sent - self.reorder.next > max_in_flight {
+            let res = self.res_rx.recv().map_err(|_| worker_failed())?;
+            self.reorder.receive(w, res)?;
+        }
+        Ok(())
+    }
+
+    /// Encode the remaining quads, write everything to `w`, and stop the worker threads.
+    pub fn finish<W: io::Write>(mut self, w: &mut W) -> io::Result<()> {
+        if !self.chunk.is_empty() {
+            self.send_chunk()?;
+        }
+        let ParallelEncoder {
+            task_tx,
+            res_rx,
+            workers,
+            sent,
+            mut reorder,
+            ..
+        } = self;
+        drop(task_tx);
+        while reorder.next < sent {
+            let res = res_rx.recv().map_err(|_| worker_failed())?;
+            reorder.receive(w, res)?;
+        }
+        for worker in workers {
+            worker.join().map_err(|_| worker_failed())?;
+        }
+        Ok(())
+    }
+
+    fn send_chunk(&mut self) -> io::Result<()> {
+        let chunk = replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE));
+        self.task_tx
+            .send((self.sent, chunk))
+            .map_err(|_| worker_failed())?;
+        self.sent += 1;
+        Ok(())
+    }
+}
+
+fn encode(w: &mut Vec<u8>, quads: &[OwnedQuad], config: &SerializerConfig) -> io::Result<()> {
+    for ([s, p, o], g) in quads {
+        write_term_with_config(w, s, config)?;
+        w.push(b' ');
+        write_term_with_config(w, p, config)?;
+        w.push(b' ');
+        write_term_with_config(w, o, config)?;
+        if let Some(g) = g {
+            w.push(b' ');
+            write_term_with_config(w, g, config)?;
+        }
+        w.extend_from_slice(b".\n");
+    }
+    Ok(())
+}
+
+fn worker_failed() -> io::Error {
+    io::Error::other("serializer worker thread failed")
+}
+
+/// Writes the encoded chunks in order, as they are received.
+#[derive(Default)]
+struct Reorder {
+    pending: BTreeMap<usize, Vec<u8>>,
+    next: usize,
+}
+
+impl Reorder {
+    fn receive<W: io::Write>(
+        &mut self,
+        w: &mut W,
+        (i, res): (usize, io::Result<Vec<u8>>),
+    ) -> io::Result<()> {
+        self.pending.insert(i, res?);
+        while let Some(buffer) = self.pending.remove(&self.next) {
+            w.write_all(&buffer)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+//! Serializer for the legacy [RDF/JSON] format (a.k.a. Talis RDF/JSON).
+//!
+//! The output can be read back with the [RDF/JSON parser](../../parser/rdf_json/index.html).
+//! Subjects are written as IRIs or blank node labels prefixed with `_:`,
+//! each with an object mapping predicates to arrays of values.
+//! Literals with the `xsd:string` datatype are written without a `datatype` entry.
+//!
+//! Literals and variables can not be written as subjects or predicates,
+//! nor blank nodes as predicates: such triples cause an error.
+//!
+//! Of the options common to all text serializers (see [`RdfJsonConfig::common`]),
+//! only `indent` (in [pretty mode]) and `ascii` are relevant to RDF/JSON.
+//!
+//! **Important**:
+//! this serializer needs to hold the whole graph in memory,
+//! as the triples of a given subject and predicate must be grouped together.
+//!
+//! This module requires the `json` feature.
+//!
+//! [RDF/JSON]: https://www.w3.org/TR/rdf-json/
+//! [`RdfJsonConfig::common`]: struct.RdfJsonConfig.html#method.common
+//! [pretty mode]: struct.RdfJsonConfig.html#method.set_pretty
+
+use super::config::SerializerConfig;
+use json::JsonValue;
+use sophia_api::ns::xsd;
+use sophia_api::serializer::*;
+use sophia_api::term::{TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use std::convert::TryFrom;
+use std::io;
+
+/// RDF/JSON serializer configuration.
+#[derive(Clone, Debug)]
+pub struct RdfJsonConfig {
+    common: SerializerConfig,
+    pretty: bool,
+}
+
+impl Default for RdfJsonConfig {
+    fn default() -> Self {
+        SerializerConfig::default().into()
+    }
+}
+
+impl From<SerializerConfig> for RdfJsonConfig {
+    fn from(common: SerializerConfig) -> Self {
+        RdfJsonConfig {
+            common,
+            pretty: false,
+        }
+    }
+}
+
+impl RdfJsonConfig {
+    /// Borrow the options common to all text serializers.
+    pub fn common(&self) -> &SerializerConfig {
+        &self.common
+    }
+
+    /// Mutably borrow the options common to all text serializers.
+    pub fn common_mut(&mut self) -> &mut SerializerConfig {
+        &mut self.common
+    }
+
+    /// Whether the output is pretty-printed.
+    pub fn pretty(&self) -> bool {
+        self.pretty
+    }
+
+    /// Set the pretty configuration (default: false, which produces compact output on a single line).
+    ///
+    /// In pretty mode, each entry is written on its own line,
+    /// and new blocks are indented with [`indent`] spaces.
+    ///
+    /// [`indent`]: ../config/struct.SerializerConfig.html#method.set_indent
+    pub fn set_pretty(&mut self, pretty: bool) -> &mut Self {
+        self.pretty = pretty;
+        self
+    }
+}
+
+/// RDF/JSON serializer.
+///
+/// See [module documentation](index.html).
+pub struct RdfJsonSerializer<W> {
+    config: RdfJsonConfig,
+    write: W,
+}
+
+impl<W> RdfJsonSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new RDF/JSON serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> RdfJsonSerializer<W> {
+        Self::new_with_config(write, RdfJsonConfig::default())
+    }
+
+    /// Build a new RDF/JSON serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: RdfJsonConfig) -> RdfJsonSerializer<W> {
+        RdfJsonSerializer { write, config }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &RdfJsonConfig {
+        &self.config
+    }
+
+    /// Flush the underlying writer.
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
+
+impl<W> TripleSerializer for RdfJsonSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_triples<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: TripleSource,
+    {
+        let mut doc = JsonValue::new_object();
+        source.try_for_each_triple(|t| -> io::Result<()> {
+            let s = node_key(t.s(), "subject")?;
+            if t.p().kind() != TermKind::Iri {
+                return Err(invalid(t.p(), "predicate"));
+            }
+            let values = &mut doc[s][t.p().value().to_string()];
+            if values.is_null() {
+                *values = JsonValue::new_array();
+            }
+            values.push(object(t.o())?).unwrap(); // values is an array
+            Ok(())
+        })?;
+        let common = &self.config.common;
+        let txt = if self.config.pretty {
+            let indent = u16::try_from(common.indent()).unwrap_or(u16::MAX);
+            json::stringify_pretty(doc, indent)
+        } else {
+            json::stringify(doc)
+        };
+        let txt = if common.ascii() {
+            escape_non_ascii(&txt)
+        } else {
+            txt
+        };
+        self.write.write_all(txt.as_bytes()).map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
+/// Replace all non-ASCII characters of the JSON text `txt` by `\uXXXX` escape sequences
+/// (as surrogate pairs for characters outside the Basic Multilingual Plane).
+///
+/// This is correct because non-ASCII characters can only occur inside JSON strings.
+fn escape_non_ascii(txt: &str) -> String {
+    let mut escaped = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        if c.is_ascii() {
+            escaped.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                escaped.push_str(&format!("\\u{:04X}", unit));
+            }
+        }
+    }
+    escaped
+}
+
+/// The key of the subject `t`, i.e. its IRI, or its label prefixed with `_:`.
+fn node_key<T: TTerm + ?Sized>(t: &T, position: &str) -> io::Result<String> {
+    match t.kind() {
+        TermKind::Iri => Ok(t.value().to_string()),
+        TermKind::BlankNode => Ok(format!("_:{}", t.value())),
+        _ => Err(invalid(t, position)),
+    }
+}
+
+/// The RDF/JSON representation of the object `t`.
+fn object<T: TTerm + ?Sized>(t: &T) -> io::Result<JsonValue> {
+    let mut o = JsonValue::new_object();
+    match t.kind() {
+        TermKind::Iri => {
+            o["type"] = "uri".into();
+            o["value"] = t.value().to_string().into();
+        }
+        TermKind::BlankNode => {
+            o["type"] = "bnode".into();
+            o["value"] = node_key(t, "object")?.into();
+        }
+        TermKind::Literal => {
+            o["type"] = "literal".into();
+            o["value"] = t.value().to_string().into();
+            match t.language() {
+                Some(tag) => o["lang"] = tag.into(),
+                None => {
+                    let dt = t.datatype().unwrap();
+                    if xsd::string != dt {
+                        o["datatype"] = dt.value().to_string().into();
+                    }
+                }
+            }
+        }
+        TermKind::Variable => return Err(invalid(t, "object")),
+    }
+    Ok(o)
+}
+
+fn invalid<T: TTerm + ?Sized>(t: &T, position: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "can not write {:?} {} as {} in RDF/JSON",
+            t.kind(),
+            t.value(),
+            position
+        ),
+    )
+}
+
+impl RdfJsonSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        RdfJsonSerializer::new(Vec::new())
+    }
+    /// Create a new serializer which targets a `String` with a custom config.
+    #[inline]
+    pub fn new_stringifier_with_config(config: RdfJsonConfig) -> Self {
+        RdfJsonSerializer::new_with_config(Vec::new(), config)
+    }
+}
+
+impl Stringifier for RdfJsonSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::MutableGraph;
+    use crate::parser::rdf_json;
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::ns::Namespace;
+    use sophia_api::term::CopiableTerm;
+    use sophia_term::BoxTerm;
+
+    #[test]
+    fn graph() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let alice: BoxTerm = ex.get("alice")?.copied();
+        let charlie = BoxTerm::new_bnode("charlie")?;
+        let g = vec![
+            [
+                alice.clone(),
+                ex.get("knows")?.copied(),
+                ex.get("bob")?.copied(),
+            ],
+            [alice.clone(), ex.get("knows")?.copied(), charlie.clone()],
+            [
+                alice.clone(),
+                ex.get("name")?.copied(),
+                BoxTerm::new_literal_lang("Alice", "en")?,
+            ],
+            [
+                charlie,
+                ex.get("age")?.copied(),
+                BoxTerm::new_literal_dt("42", xsd::integer)?,
+            ],
+            [
+                alice,
+                ex.get("name")?.copied(),
+                BoxTerm::from("\"Al\"".to_string()),
+            ],
+        ];
+        let mut ser = RdfJsonSerializer::new_stringifier();
+        let txt = ser.serialize_graph(&g)?.as_str();
+        assert_eq!(
+            json::parse(txt)?,
+            json::object! {
+                "http://example.org/alice": {
+                    "http://example.org/knows": [
+                        { "type": "uri", "value": "http://example.org/bob" },
+                        { "type": "bnode", "value": "_:charlie" }
+                    ],
+                    "http://example.org/name": [
+                        { "type": "literal", "value": "Alice", "lang": "en" },
+                        { "type": "literal", "value": "\"Al\"" }
+                    ]
+                },
+                "_:charlie": {
+                    "http://example.org/age": [
+                        {
+                            "type": "literal",
+                            "value": "42",
+                            "datatype": "http://www.w3.org/2001/XMLSchema#integer"
+                        }
+                    ]
+                },
+            }
+        );
+        assert!(!txt.contains('\n'));
+
+        let g2: FastGraph = rdf_json::parse_str(txt).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let g = vec![[ex.get("s")?, ex.get("p")?, ex.get("o")?]];
+        let mut config = RdfJsonConfig::default();
+        config.set_pretty(true).common_mut().set_indent(2);
+        let mut ser = RdfJsonSerializer::new_stringifier_with_config(config);
+        let txt = ser.serialize_graph(&g)?.as_str();
+        assert_eq!(
+            txt,
+            r#"{
+  "http://example.org/s": {
+    "http://example.org/p": [
+      {
+        "type": "uri",
+        "value": "http://example.org/o"
+      }
+    ]
+  }
+}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ascii() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let g = vec![[
+            ex.get("s")?.copied(),
+            ex.get("p")?.copied(),
+            BoxTerm::from("caf\u{e9} \u{1F600}".to_string()),
+        ]];
+        let mut config = RdfJsonConfig::default();
+        config.common_mut().set_ascii(true);
+        let mut ser = RdfJsonSerializer::new_stringifier_with_config(config);
+        let txt = ser.serialize_graph(&g)?.as_str();
+        assert!(txt.is_ascii());
+        assert!(txt.contains(r#""caf\u00E9 \uD83D\uDE00""#));
+        let g2: FastGraph = rdf_json::parse_str(txt).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_terms() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let lit = BoxTerm::from("lit".to_string());
+        let bnode = BoxTerm::new_bnode("b")?;
+        let var = BoxTerm::new_variable("v")?;
+        for t in &[
+            [lit.clone(), ex.get("p")?.copied(), ex.get("o")?.copied()],
+            [ex.get("s")?.copied(), lit, ex.get("o")?.copied()],
+            [ex.get("s")?.copied(), bnode, ex.get("o")?.copied()],
+            [ex.get("s")?.copied(), ex.get("p")?.copied(), var],
+        ] {
+            let mut g = FastGraph::new();
+            g.insert(&t[0], &t[1], &t[2])?;
+            let mut ser = RdfJsonSerializer::new_stringifier();
+            assert!(ser.serialize_graph(&g).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,504 @@
+//! Serializer for the [TriG] concrete syntax of RDF.
+//!
+//! [`TrigSerializer`] writes quads as they are received,
+//! grouping consecutive quads of the same named graph in a graph block,
+//! and writing the quads of the default graph outside any block.
+//! It does not reorder quads, so a graph may span several blocks
+//! if its quads are not grouped by graph.
+//!
+//! This makes it suitable for continuously appending data to a file.
+//! Besides [`serialize_quads`], which can be called several times,
+//! quads can be written one at a time through the [`QuadSink`] trait.
+//! Every call to [`serialize_quads`] or [`flush`] closes the current graph block,
+//! so that the data written so far is a complete TriG document.
+//!
+//! The prefixes of the [configuration] are declared at the start of the output.
+//! Other prefixes can be declared at any time with [`add_prefix`].
+//! The declaration is written immediately (after closing the current graph block, if any),
+//! and only applies to the quads written after it.
+//! Redeclaring a prefix replaces its namespace for the following quads.
+//!
+//! [`TrigSerializer`] writes through an internal [`BufWriter`],
+//! so it can be given an unbuffered [`Write`] (such as a `File`).
+//! Buffered data is written when [`flush`] or [`finish`] is called;
+//! if the serializer is dropped instead, the last graph block may be left open.
+//!
+//! [TriG]: https://www.w3.org/TR/trig/
+//! [`TrigSerializer`]: struct.TrigSerializer.html
+//! [`serialize_quads`]: struct.TrigSerializer.html#method.serialize_quads
+//! [`QuadSink`]: ../trait.QuadSink.html
+//! [`flush`]: struct.TrigSerializer.html#method.flush
+//! [`finish`]: struct.TrigSerializer.html#method.finish
+//! [configuration]: ../config/struct.SerializerConfig.html#method.set_prefixes
+//! [`add_prefix`]: struct.TrigSerializer.html#method.add_prefix
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use super::config::{BnodeLabeling, SerializerConfig};
+use super::labeling::BnodeLabeler;
+use super::nt::write_term_with_config as write_nt_term;
+use super::turtle::{write_prefixed, write_prefixes};
+use crate::dataset::metadata::DatasetMetadata;
+use sophia_api::dataset::Dataset;
+use sophia_api::prefix::PrefixMap;
+use sophia_api::quad::stream::{QuadSource, SinkError, SourceError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::*;
+use sophia_api::term::{same_graph_name, CopyTerm, MownStr, TTerm, TermKind};
+use sophia_term::BoxTerm;
+use std::io::{self, BufWriter, Write};
+
+/// TriG serializer.
+///
+/// See [module documentation](index.html).
+pub struct TrigSerializer<W: io::Write> {
+    config: SerializerConfig,
+    write: BufWriter<W>,
+    labeler: BnodeLabeler,
+    prefixes: Declared,
+    /// Whether the prefixes of the configuration have been written.
+    started: bool,
+    /// The name of the open graph block, if any.
+    current: Option<BoxTerm>,
+}
+
+impl<W> TrigSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new TriG serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> TrigSerializer<W> {
+        Self::new_with_config(write, SerializerConfig::default())
+    }
+
+    /// Build a new TriG serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: SerializerConfig) -> TrigSerializer<W> {
+        Self::with_buffer(BufWriter::new(write), config)
+    }
+
+    fn with_buffer(write: BufWriter<W>, config: SerializerConfig) -> TrigSerializer<W> {
+        let labeler = BnodeLabeler::new(config.bnode_labeling());
+        let prefixes = Declared(
+            config
+                .prefixes()
+                .iter()
+                .map(|(p, ns)| (p.clone(), BoxTerm::new_iri_unchecked(ns.as_str())))
+                .collect(),
+        );
+        TrigSerializer {
+            config,
+            write,
+            labeler,
+            prefixes,
+            started: false,
+            current: None,
+        }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &SerializerConfig {
+        &self.config
+    }
+
+    /// Declare a prefix for the quads written from now on.
+    ///
+    /// `prefix` must be a valid Turtle prefix name (`PN_PREFIX`).
+    pub fn add_prefix<P, N>(&mut self, prefix: P, namespace: N) -> io::Result<&mut Self>
+    where
+        P: Into<String>,
+        N: Into<String>,
+    {
+        let prefix = prefix.into();
+        let namespace = BoxTerm::new_iri_unchecked(namespace.into());
+        self.start()?;
+        self.close()?;
+        write!(self.write, "@prefix {}: ", prefix)?;
+        write_nt_term(&mut self.write, &namespace, &self.config)?;
+        self.write.write_all(b" .\n")?;
+        match self.prefixes.0.iter_mut().find(|(p, _)| *p == prefix) {
+            Some(pair) => pair.1 = namespace,
+            None => self.prefixes.0.push((prefix, namespace)),
+        }
+        Ok(self)
+    }
+
+    /// Serialize all quads of `dataset`, grouped by graph,
+    /// declaring before each graph the prefixes recorded for it in `metadata`
+    /// (unless they are already declared with the same namespace).
+    ///
+    /// This preserves the prefixes of each graph
+    /// when the graphs of `dataset` were loaded from different files.
+    /// The quads of the default graph are written first,
+    /// then the named graphs in the order of their names.
+    ///
+    /// NB: all the quads of `dataset` are collected in memory before being written,
+    /// and the serialized data may be buffered until [`flush`](#method.flush) is called.
+    pub fn serialize_dataset_with_metadata<D>(
+        &mut self,
+        dataset: &D,
+        metadata: &DatasetMetadata,
+    ) -> StreamResult<&mut Self, D::Error, io::Error>
+    where
+        D: Dataset,
+    {
+        let mut quads: Vec<([BoxTerm; 3], Option<BoxTerm>)> = dataset
+            .quads()
+            .collect_quads()
+            .map_err(|e| SourceError(e.unwrap_source_error()))?;
+        // stable sort, preserving the order of the dataset inside each graph
+        quads.sort_by(|q1, q2| q1.1.cmp(&q2.1));
+        let saved = if self.config.bnode_labeling() == BnodeLabeling::Canonical {
+            let labeler =
+                BnodeLabeler::canonical(quads.iter().map(|([s, p, o], g)| ([s, p, o], g.as_ref())));
+            Some(std::mem::replace(&mut self.labeler, labeler))
+        } else {
+            None
+        };
+        let res = self.write_with_metadata(&quads, metadata);
+        if let Some(saved) = saved {
+            self.labeler = saved;
+        }
+        res.map_err(SinkError)?;
+        Ok(self)
+    }
+
+    fn write_with_metadata(
+        &mut self,
+        quads: &[([BoxTerm; 3], Option<BoxTerm>)],
+        metadata: &DatasetMetadata,
+    ) -> io::Result<()> {
+        self.start()?;
+        let mut previous = None;
+        for q in quads {
+            if previous != Some(&q.1) {
+                previous = Some(&q.1);
+                if let Some(gmd) = metadata.get(q.1.as_ref()) {
+                    for (prefix, namespace) in &gmd.prefixes {
+                        if !self.prefixes.declares(prefix, namespace) {
+                            self.add_prefix(prefix.as_str(), namespace.as_str())?;
+                        }
+                    }
+                }
+            }
+            self.write_quad(q)?;
+        }
+        self.close()
+    }
+
+    /// Close the current graph block, write all buffered data, and flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.close()?;
+        self.write.flush()
+    }
+
+    /// Close the current graph block, write all buffered data, and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.close()?;
+        Ok(self.write.into_inner()?)
+    }
+
+    /// Write the prefixes of the configuration, if not done yet.
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            write_prefixes(&mut self.write, &self.config)?;
+        }
+        Ok(())
+    }
+
+    /// Close the current graph block, if any.
+    fn close(&mut self) -> io::Result<()> {
+        if self.current.take().is_some() {
+            self.write.write_all(b"}\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_quad<Q: Quad>(&mut self, q: &Q) -> io::Result<()> {
+        self.start()?;
+        if !same_graph_name(q.g(), self.current.as_ref()) {
+            self.close()?;
+            if let Some(g) = q.g() {
+                let TrigSerializer {
+                    config,
+                    write,
+                    labeler,
+                    prefixes,
+                    ..
+                } = &mut *self;
+                labeler.with_label(g.as_dyn(), |g| write_prefixed(write, g, config, prefixes))?;
+                write.write_all(b" {\n")?;
+                self.current = Some(BoxTerm::copy(g));
+            }
+        }
+        let TrigSerializer {
+            config,
+            write,
+            labeler,
+            prefixes,
+            current,
+            ..
+        } = &mut *self;
+        if current.is_some() {
+            write.write_all(&b" ".repeat(config.indent()))?;
+        }
+        labeler.with_label(q.s().as_dyn(), |s| {
+            write_prefixed(write, s, config, prefixes)
+        })?;
+        write.write_all(b" ")?;
+        write_prefixed(write, q.p(), config, prefixes)?;
+        write.write_all(b" ")?;
+        labeler.with_label(q.o().as_dyn(), |o| {
+            write_prefixed(write, o, config, prefixes)
+        })?;
+        write.write_all(b" .\n")
+    }
+}
+
+impl<W> QuadSerializer for TrigSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    /// Serialize all quads from the given [`QuadSource`],
+    /// as they are read from it,
+    /// then close the current graph block.
+    ///
+    /// Like [`NqSerializer::serialize_quads`],
+    /// this method can be called several times,
+    /// and blank nodes are relabeled consistently across calls,
+    /// except with [`BnodeLabeling::Canonical`].
+    ///
+    /// NB: the serialized data may be buffered until [`flush`](#method.flush) is called.
+    ///
+    /// [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+    /// [`NqSerializer::serialize_quads`]: ../nq/struct.NqSerializer.html#method.serialize_quads
+    /// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+    fn serialize_quads<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+    {
+        self.start().map_err(SinkError)?;
+        if self.config.bnode_labeling() == BnodeLabeling::Canonical {
+            let quads: Vec<([BoxTerm; 3], Option<BoxTerm>)> = source
+                .collect_quads()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            let labeler =
+                BnodeLabeler::canonical(quads.iter().map(|([s, p, o], g)| ([s, p, o], g.as_ref())));
+            let saved = std::mem::replace(&mut self.labeler, labeler);
+            let res = quads.iter().try_for_each(|q| self.write_quad(q));
+            self.labeler = saved;
+            res.map_err(SinkError)?;
+        } else {
+            source.try_for_each_quad(|q| self.write_quad(&q))?;
+        }
+        self.close().map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
+/// Quads pushed one at a time are relabeled consistently with all other quads,
+/// but can not be labeled canonically:
+/// with [`BnodeLabeling::Canonical`], they are labeled as with [`BnodeLabeling::Sequential`].
+///
+/// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+/// [`BnodeLabeling::Sequential`]: ../config/enum.BnodeLabeling.html#variant.Sequential
+impl<W> QuadSink for TrigSerializer<W>
+where
+    W: io::Write,
+{
+    fn push_quad<Q>(&mut self, quad: &Q) -> Result<(), Self::Error>
+    where
+        Q: Quad,
+    {
+        self.write_quad(quad)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        TrigSerializer::flush(self)
+    }
+}
+
+impl TrigSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        TrigSerializer::new_stringifier_with_config(SerializerConfig::default())
+    }
+    /// Create a new serializer which targets a `String` with a custom config.
+    #[inline]
+    pub fn new_stringifier_with_config(config: SerializerConfig) -> Self {
+        // no need to buffer writes to a Vec
+        TrigSerializer::with_buffer(BufWriter::with_capacity(0, Vec::new()), config)
+    }
+}
+
+/// NB: quads pushed through [`QuadSink`](../trait.QuadSink.html)
+/// may leave a graph block open until [`flush`](struct.TrigSerializer.html#method.flush) is called.
+impl Stringifier for TrigSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write.get_ref()[..]
+    }
+}
+
+/// The prefixes declared so far, with their namespace.
+#[derive(Default)]
+struct Declared(Vec<(String, BoxTerm)>);
+
+impl Declared {
+    /// Whether `prefix` is currently declared with the given `namespace`.
+    fn declares(&self, prefix: &str, namespace: &str) -> bool {
+        self.0
+            .iter()
+            .any(|(p, ns)| p == prefix && ns.value() == namespace)
+    }
+}
+
+impl<'a> PrefixMap<'a> for Declared {
+    type Term = BoxTerm;
+
+    fn get_namespace(&self, prefix: &str) -> Option<&Self::Term> {
+        self.0.iter().find(|(p, _)| p == prefix).map(|(_, ns)| ns)
+    }
+
+    fn get_prefixed_pair<'s, T: TTerm>(&'s self, iri: &'s T) -> Option<(&'s str, MownStr<'s>)> {
+        if iri.kind() != TermKind::Iri {
+            return None;
+        }
+        let raw_value = iri.value_raw();
+        let len = raw_value.len();
+        self.0
+            .iter()
+            .filter_map(|(prefix, ns)| {
+                let ns = ns.value_raw();
+                if raw_value.starts_with(ns.bytes()) {
+                    Some((prefix.as_str(), raw_value.slice(ns.len()..)))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(_, suffix)| len - suffix.len())
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::parser::turtle::TurtleParser;
+    use sophia_api::dataset::{isomorphic_datasets, Dataset};
+    use sophia_term::StaticTerm;
+
+    fn some_quads() -> Vec<([StaticTerm; 3], Option<StaticTerm>)> {
+        let s = StaticTerm::new_iri("http://example.org/s").unwrap();
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let b = StaticTerm::new_bnode("b").unwrap();
+        let g = StaticTerm::new_iri("http://example.org/g").unwrap();
+        vec![
+            ([s, p, b], None),
+            ([b, p, s], Some(g)),
+            ([s, p, s], Some(g)),
+            ([s, p, b], Some(b)),
+            ([s, p, s], None),
+            ([s, p, b], Some(g)),
+        ]
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let quads = some_quads();
+        let trig = TrigSerializer::new_stringifier()
+            .serialize_quads(quads.quads())?
+            .to_string();
+        assert_eq!(trig.matches('{').count(), 3, "{}", trig);
+        let d: FastDataset = crate::parser::trig::parse_str(&trig).collect_quads()?;
+        assert!(isomorphic_datasets(&quads, &d)?, "{}", trig);
+        Ok(())
+    }
+
+    #[test]
+    fn sink() -> Result<(), Box<dyn std::error::Error>> {
+        let quads = some_quads();
+        let mut ser = TrigSerializer::new_stringifier();
+        for q in &quads[..3] {
+            ser.push_quad(q)?;
+        }
+        QuadSink::flush(&mut ser)?;
+        let first = ser.to_string();
+        assert!(first.ends_with("}\n"), "{}", first);
+        crate::parser::trig::parse_str(&first).collect_quads::<FastDataset>()?;
+
+        ser.add_prefix("ex", "http://example.org/")?;
+        for q in &quads[3..] {
+            ser.push_quad(q)?;
+        }
+        let out = ser.finish()?;
+        let trig = String::from_utf8(out)?;
+        // prefixes only apply to quads written after their declaration
+        assert!(trig.starts_with(&first));
+        assert!(!first.contains("ex:"));
+        assert!(trig[first.len()..].contains("ex:g {"), "{}", trig);
+        let d: FastDataset = crate::parser::trig::parse_str(&trig).collect_quads()?;
+        assert!(isomorphic_datasets(&quads, &d)?, "{}", trig);
+        Ok(())
+    }
+
+    #[test]
+    fn config_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let quads = some_quads();
+        let mut config = SerializerConfig::new();
+        config.add_prefix("ex", "http://example.org/");
+        let trig = TrigSerializer::new_stringifier_with_config(config)
+            .serialize_quads(quads.quads())?
+            .to_string();
+        assert!(
+            trig.starts_with("@prefix ex: <http://example.org/> .\n\nex:s ex:p "),
+            "{}",
+            trig
+        );
+        assert!(trig.contains("ex:g {"), "{}", trig);
+        let d: FastDataset = crate::parser::trig::parse_str(&trig).collect_quads()?;
+        assert!(isomorphic_datasets(&quads, &d)?, "{}", trig);
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let g1 = StaticTerm::new_iri("http://example.org/g1")?;
+        let g2 = StaticTerm::new_iri("http://example.org/g2")?;
+        let mut d = FastDataset::new();
+        let mut md = DatasetMetadata::new();
+        let parser = TurtleParser::default();
+        let ttl1 = "@prefix ex: <http://example.org/one#> .\nex:s ex:p ex:o .\n";
+        let ttl2 = "@prefix ex: <http://example.org/two#> .\nex:s ex:p ex:o .\n";
+        parser.load_into(ttl2, Some(&g2), &mut d, &mut md)?;
+        parser.load_into(ttl1, Some(&g1), &mut d, &mut md)?;
+        let trig = TrigSerializer::new_stringifier()
+            .serialize_dataset_with_metadata(&d, &md)?
+            .to_string();
+        assert_eq!(
+            trig,
+            r#"@prefix ex: <http://example.org/one#> .
+<http://example.org/g1> {
+    ex:s ex:p ex:o .
+}
+@prefix ex: <http://example.org/two#> .
+<http://example.org/g2> {
+    ex:s ex:p ex:o .
+}
+"#
+        );
+        let d2: FastDataset = crate::parser::trig::parse_str(&trig).collect_quads()?;
+        assert!(isomorphic_datasets(&d, &d2)?, "{}", trig);
+        Ok(())
+    }
+}
@@ -0,0 +1,561 @@
+//! Serializer for [TriX], an XML serialization of RDF datasets.
+//!
+//! The output can be read back with the [TriX parser](../../parser/trix/index.html).
+//! Each graph of the dataset is written as a `<graph>` element
+//! (without name for the default graph), containing its `<triple>` elements.
+//! Literals with the `xsd:string` datatype or a language tag are written as `<plainLiteral>`,
+//! others as `<typedLiteral>`.
+//! The lexical form of `rdf:XMLLiteral`s is written as is,
+//! as expected by the parser, so it must be well-formed XML.
+//!
+//! [`serialize_dataset`] writes each graph only once.
+//! [`serialize_quads`], on the other hand, streams its quads,
+//! starting a new `<graph>` element each time the graph name changes;
+//! so a graph may span several `<graph>` elements
+//! if the quads are not grouped by graph.
+//!
+//! Quads can also be written one at a time through the [`QuadSink`] trait,
+//! in the same way as with [`serialize_quads`].
+//! As an XML document can not be appended to,
+//! [`flush`] ends the current `<TriX>` document,
+//! and the next quads are written in a new one
+//! (as are the quads of each call to [`serialize_quads`]).
+//!
+//! Of the options common to all text serializers (see [`new_with_config`]),
+//! only `indent` and `ascii` are relevant to TriX.
+//! With `ascii`, non-ASCII characters are written as character references,
+//! except in `rdf:XMLLiteral`s, which are written as is.
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [TriX]: https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html
+//! [`serialize_dataset`]: struct.TrixSerializer.html#method.serialize_dataset
+//! [`serialize_quads`]: struct.TrixSerializer.html#method.serialize_quads
+//! [`new_with_config`]: struct.TrixSerializer.html#method.new_with_config
+//! [`QuadSink`]: ../trait.QuadSink.html
+//! [`flush`]: ../trait.QuadSink.html#tymethod.flush
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use super::config::SerializerConfig;
+use super::xml::write_escaped;
+use crate::parser::trix::TRIX_NS;
+use sophia_api::dataset::Dataset;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::quad::stream::{QuadSource, SinkError, SourceError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::*;
+use sophia_api::term::{same_graph_name, CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+use std::collections::HashSet;
+use std::io;
+
+/// TriX serializer.
+///
+/// See [module documentation](index.html).
+pub struct TrixSerializer<W> {
+    config: SerializerConfig,
+    write: W,
+    /// Whether a `<TriX>` element is open.
+    open: bool,
+    /// The name of the open `<graph>` element, if any.
+    current: Option<Option<BoxTerm>>,
+}
+
+impl<W> TrixSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new TriX serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> TrixSerializer<W> {
+        Self::new_with_config(write, SerializerConfig::default())
+    }
+
+    /// Build a new TriX serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: SerializerConfig) -> TrixSerializer<W> {
+        TrixSerializer {
+            config,
+            write,
+            open: false,
+            current: None,
+        }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &SerializerConfig {
+        &self.config
+    }
+
+    /// Open a `<TriX>` element, if none is open.
+    fn start(&mut self) -> io::Result<()> {
+        if !self.open {
+            self.open = true;
+            Writer::new(&mut self.write, &self.config).write_start()?;
+        }
+        Ok(())
+    }
+
+    /// Write `q`, starting a new `<graph>` element if its graph name changes.
+    fn write_quad<Q: Quad>(&mut self, q: &Q) -> io::Result<()> {
+        self.start()?;
+        let mut w = Writer::new(&mut self.write, &self.config);
+        let same = match &self.current {
+            Some(g) => same_graph_name(q.g(), g.as_ref()),
+            None => false,
+        };
+        if !same {
+            if self.current.is_some() {
+                w.write_graph_end()?;
+            }
+            w.write_graph_start(q.g())?;
+            self.current = Some(q.g().map(BoxTerm::copy));
+        }
+        w.write_triple(q.s(), q.p(), q.o())
+    }
+
+    /// Close the open `<graph>` and `<TriX>` elements, if any.
+    fn close(&mut self) -> io::Result<()> {
+        if self.open {
+            self.open = false;
+            let mut w = Writer::new(&mut self.write, &self.config);
+            if self.current.take().is_some() {
+                w.write_graph_end()?;
+            }
+            w.write_end()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W> QuadSerializer for TrixSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_quads<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+    {
+        // end the document of the quads pushed so far, if any
+        self.close().map_err(SinkError)?;
+        self.start().map_err(SinkError)?;
+        source.try_for_each_quad(|q| self.write_quad(&q))?;
+        self.close().map_err(SinkError)?;
+        Ok(self)
+    }
+
+    /// Serialize a whole [`Dataset`], writing each of its graphs in a single `<graph>` element.
+    ///
+    /// [`Dataset`]: ../../dataset/trait.Dataset.html
+    fn serialize_dataset<D>(
+        &mut self,
+        dataset: &D,
+    ) -> StreamResult<&mut Self, D::Error, Self::Error>
+    where
+        D: Dataset,
+    {
+        // graph names, in the order of their first occurrence
+        let mut names: Vec<Option<BoxTerm>> = vec![];
+        let mut seen = HashSet::new();
+        for q in dataset.quads() {
+            let q = q.map_err(SourceError)?;
+            let g = q.g().map(BoxTerm::copy);
+            if seen.insert(g.clone()) {
+                names.push(g);
+            }
+        }
+        self.close().map_err(SinkError)?;
+        let mut w = Writer::new(&mut self.write, &self.config);
+        w.write_start().map_err(SinkError)?;
+        for g in &names {
+            w.write_graph_start(g.as_ref()).map_err(SinkError)?;
+            for q in dataset.quads_with_g(g.as_ref()) {
+                let q = q.map_err(SourceError)?;
+                w.write_triple(q.s(), q.p(), q.o()).map_err(SinkError)?;
+            }
+            w.write_graph_end().map_err(SinkError)?;
+        }
+        w.write_end().map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
+impl<W> QuadSink for TrixSerializer<W>
+where
+    W: io::Write,
+{
+    fn push_quad<Q>(&mut self, quad: &Q) -> Result<(), Self::Error>
+    where
+        Q: Quad,
+    {
+        self.write_quad(quad)
+    }
+
+    /// End the current `<TriX>` document, if any, and flush the underlying writer.
+    fn flush(&mut self) -> io::Result<()> {
+        self.close()?;
+        self.write.flush()
+    }
+}
+
+/// Writes TriX elements according to a configuration.
+struct Writer<'a, W> {
+    w: &'a mut W,
+    ascii: bool,
+    indent: usize,
+}
+
+impl<'a, W: io::Write> Writer<'a, W> {
+    fn new(w: &'a mut W, config: &SerializerConfig) -> Self {
+        Writer {
+            w,
+            ascii: config.ascii(),
+            indent: config.indent(),
+        }
+    }
+
+    /// Write the indentation of an element at the given nesting `level`.
+    fn write_indent(&mut self, level: usize) -> io::Result<()> {
+        write!(self.w, "{:1$}", "", level * self.indent)
+    }
+
+    fn write_start(&mut self) -> io::Result<()> {
+        writeln!(
+            self.w,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<TriX xmlns=\"{}\">",
+            TRIX_NS
+        )
+    }
+
+    fn write_end(&mut self) -> io::Result<()> {
+        self.w.write_all(b"</TriX>\n")
+    }
+
+    fn write_graph_start<T>(&mut self, g: Option<&T>) -> io::Result<()>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.write_indent(1)?;
+        self.w.write_all(b"<graph>\n")?;
+        if let Some(g) = g {
+            if g.kind() == TermKind::Literal {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "can not write literal {} as a graph name in TriX",
+                        g.value()
+                    ),
+                ));
+            }
+            self.write_indent(2)?;
+            self.write_term(g)?;
+            self.w.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_graph_end(&mut self) -> io::Result<()> {
+        self.write_indent(1)?;
+        self.w.write_all(b"</graph>\n")
+    }
+
+    fn write_triple<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> io::Result<()>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.write_indent(2)?;
+        self.w.write_all(b"<triple>\n")?;
+        for t in &[s.as_dyn(), p.as_dyn(), o.as_dyn()] {
+            self.write_indent(3)?;
+            self.write_term(*t)?;
+            self.w.write_all(b"\n")?;
+        }
+        self.write_indent(2)?;
+        self.w.write_all(b"</triple>\n")
+    }
+
+    /// Write the TriX element representing `t`.
+    fn write_term<T>(&mut self, t: &T) -> io::Result<()>
+    where
+        T: TTerm + ?Sized,
+    {
+        let (w, ascii) = (&mut *self.w, self.ascii);
+        let name = match t.kind() {
+            TermKind::Iri => {
+                w.write_all(b"<uri>")?;
+                "uri"
+            }
+            TermKind::BlankNode => {
+                w.write_all(b"<id>")?;
+                "id"
+            }
+            TermKind::Literal => {
+                let dt = t.datatype().unwrap();
+                match t.language() {
+                    Some(tag) => {
+                        w.write_all(b"<plainLiteral xml:lang=\"")?;
+                        write_escaped(w, tag, ascii)?;
+                        w.write_all(b"\">")?;
+                        "plainLiteral"
+                    }
+                    None if xsd::string == dt => {
+                        w.write_all(b"<plainLiteral>")?;
+                        "plainLiteral"
+                    }
+                    None => {
+                        w.write_all(b"<typedLiteral datatype=\"")?;
+                        write_escaped(w, &dt.value(), ascii)?;
+                        w.write_all(b"\">")?;
+                        if rdf::XMLLiteral == dt {
+                            w.write_all(t.value().as_bytes())?;
+                            return w.write_all(b"</typedLiteral>");
+                        }
+                        "typedLiteral"
+                    }
+                }
+            }
+            TermKind::Variable => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("can not write variable ?{} in TriX", t.value()),
+                ))
+            }
+        };
+        write_escaped(w, &t.value(), ascii)?;
+        write!(w, "</{}>", name)
+    }
+}
+
+impl TrixSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        TrixSerializer::new(Vec::new())
+    }
+    /// Create a new serializer which targets a `String` with a custom config.
+    #[inline]
+    pub fn new_stringifier_with_config(config: SerializerConfig) -> Self {
+        TrixSerializer::new_with_config(Vec::new(), config)
+    }
+}
+
+/// NB: quads pushed through [`QuadSink`](../trait.QuadSink.html)
+/// leave the document open until the serializer is
+/// [flushed](../trait.QuadSink.html#tymethod.flush).
+impl Stringifier for TrixSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::MutableDataset;
+    use crate::parser::trix;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_api::ns::Namespace;
+    use sophia_api::term::CopiableTerm;
+
+    fn some_dataset() -> Result<FastDataset, Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let bob = BoxTerm::new_bnode("bob")?;
+        let g1 = Some(ex.get("g1")?);
+        let mut d = FastDataset::new();
+        d.insert(
+            &ex.get("alice")?,
+            &ex.get("knows")?,
+            &bob,
+            None as Option<&BoxTerm>,
+        )?;
+        d.insert(
+            &bob,
+            &ex.get("name")?,
+            &BoxTerm::new_literal_lang("Bob & <co>", "en")?,
+            g1.as_ref(),
+        )?;
+        d.insert(
+            &bob,
+            &ex.get("age")?,
+            &BoxTerm::new_literal_dt("42", xsd::integer)?,
+            g1.as_ref(),
+        )?;
+        d.insert(
+            &bob,
+            &ex.get("bio")?,
+            &BoxTerm::new_literal_dt("<p>Born in <b>1970</b> &amp; alive</p>", rdf::XMLLiteral)?,
+            g1.as_ref(),
+        )?;
+        d.insert(
+            &bob,
+            &ex.get("nick")?,
+            &BoxTerm::from("\"bobby\"\n".to_string()),
+            Some(&BoxTerm::new_bnode("g2")?),
+        )?;
+        Ok(d)
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let d = some_dataset()?;
+        let mut ser = TrixSerializer::new_stringifier();
+        let trix = ser.serialize_dataset(&d)?.as_str();
+        assert_eq!(trix.matches("<graph>").count(), 3);
+        let d2: FastDataset = trix::parse_str(trix).collect_quads()?;
+        assert!(isomorphic_datasets(&d, &d2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn streaming() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let s: BoxTerm = ex.get("s")?.copied();
+        let p: BoxTerm = ex.get("p")?.copied();
+        let g: Option<BoxTerm> = Some(ex.get("g")?.copied());
+        let quads = vec![
+            ([s.clone(), p.clone(), ex.get("o1")?.copied()], g.clone()),
+            ([s.clone(), p.clone(), ex.get("o2")?.copied()], g.clone()),
+            ([s.clone(), p.clone(), ex.get("o3")?.copied()], None),
+            ([s.clone(), p.clone(), ex.get("o4")?.copied()], g),
+        ];
+        let mut config = SerializerConfig::new();
+        config.set_indent(2);
+        let mut ser = TrixSerializer::new_stringifier_with_config(config);
+        let trix = ser.serialize_quads(quads.quads())?.as_str();
+        assert_eq!(
+            trix,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<TriX xmlns="http://www.w3.org/2004/03/trix/trix-1/">
+  <graph>
+    <uri>http://example.org/g</uri>
+    <triple>
+      <uri>http://example.org/s</uri>
+      <uri>http://example.org/p</uri>
+      <uri>http://example.org/o1</uri>
+    </triple>
+    <triple>
+      <uri>http://example.org/s</uri>
+      <uri>http://example.org/p</uri>
+      <uri>http://example.org/o2</uri>
+    </triple>
+  </graph>
+  <graph>
+    <triple>
+      <uri>http://example.org/s</uri>
+      <uri>http://example.org/p</uri>
+      <uri>http://example.org/o3</uri>
+    </triple>
+  </graph>
+  <graph>
+    <uri>http://example.org/g</uri>
+    <triple>
+      <uri>http://example.org/s</uri>
+      <uri>http://example.org/p</uri>
+      <uri>http://example.org/o4</uri>
+    </triple>
+  </graph>
+</TriX>
+"#
+        );
+        let d: FastDataset = trix::parse_str(trix).collect_quads()?;
+        assert!(isomorphic_datasets(&d, &quads)?);
+        Ok(())
+    }
+
+    #[test]
+    fn ascii() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let quads = vec![(
+            [
+                ex.get("caf\u{e9}")?.copied(),
+                ex.get("name")?.copied(),
+                BoxTerm::new_literal_lang("caf\u{e9} \u{1F600}", "fr")?,
+            ],
+            None,
+        )];
+        let mut config = SerializerConfig::new();
+        config.set_ascii(true);
+        let mut ser = TrixSerializer::new_stringifier_with_config(config);
+        let trix = ser.serialize_quads(quads.quads())?.as_str();
+        assert!(trix.is_ascii());
+        assert!(trix.contains("<uri>http://example.org/caf&#xE9;</uri>"));
+        assert!(trix.contains(">caf&#xE9; &#x1F600;</plainLiteral>"));
+        let d: FastDataset = trix::parse_str(trix).collect_quads()?;
+        assert!(isomorphic_datasets(&d, &quads)?);
+        Ok(())
+    }
+
+    #[test]
+    fn empty() -> Result<(), Box<dyn std::error::Error>> {
+        let d = FastDataset::new();
+        let mut ser = TrixSerializer::new_stringifier();
+        let trix = ser.serialize_dataset(&d)?.as_str();
+        let d2: FastDataset = trix::parse_str(trix).collect_quads()?;
+        assert_eq!(d2.quads().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn sink() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let s: BoxTerm = ex.get("s")?.copied();
+        let p: BoxTerm = ex.get("p")?.copied();
+        let g: Option<BoxTerm> = Some(ex.get("g")?.copied());
+        let quads = vec![
+            ([s.clone(), p.clone(), ex.get("o1")?.copied()], g.clone()),
+            ([s.clone(), p.clone(), ex.get("o2")?.copied()], None),
+        ];
+        let mut ser = TrixSerializer::new_stringifier();
+        for q in &quads {
+            ser.push_quad(q)?;
+        }
+        assert!(!ser.as_str().contains("</TriX>"));
+        QuadSink::flush(&mut ser)?;
+        let first = ser.as_str().to_string();
+        let d: FastDataset = trix::parse_str(&first).collect_quads()?;
+        assert!(isomorphic_datasets(&d, &quads)?);
+        assert_eq!(first.matches("<graph>").count(), 2);
+
+        // each flush ends a document, and the next quads start a new one
+        ser.push_quad(&([s.clone(), p.clone(), ex.get("o3")?.copied()], g))?;
+        QuadSink::flush(&mut ser)?;
+        QuadSink::flush(&mut ser)?;
+        let second = &ser.as_str()[first.len()..];
+        assert!(second.starts_with("<?xml"));
+        assert_eq!(second.matches("</TriX>").count(), 1);
+        let d: FastDataset = trix::parse_str(second).collect_quads()?;
+        assert_eq!(d.quads().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn literal_graph_name() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new("http://example.org/")?;
+        let quads = vec![(
+            [
+                ex.get("s")?.copied(),
+                ex.get("p")?.copied(),
+                ex.get("o")?.copied(),
+            ],
+            Some(BoxTerm::from("g".to_string())),
+        )];
+        let mut ser = TrixSerializer::new_stringifier();
+        assert!(ser.serialize_quads(quads.quads()).is_err());
+        Ok(())
+    }
+}
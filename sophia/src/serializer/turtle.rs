@@ -1,59 +1,235 @@
 //! Serializer for the [Turtle] concrete syntax of RDF.
 //!
+//! Besides [`serialize_triples`], which can be called several times,
+//! triples can be written one at a time through the [`TripleSink`] trait.
+//! The prefixes of the configuration are declared before the first triple.
+//! In pretty mode, pushed triples are buffered (up to [`max_buffered`])
+//! until [`flush`] is called.
+//!
 //! **Important**:
 //! the methods in this module accepting a [`Write`]
 //! make no effort to minimize the number of write operations.
 //! Hence, in most cased, they should be passed a [`BufWriter`].
 //!
-//! [RDF/XML]: https://www.w3.org/TR/turtle/
+//! [Turtle]: https://www.w3.org/TR/turtle/
+//! [`serialize_triples`]: struct.TurtleSerializer.html#method.serialize_triples
+//! [`TripleSink`]: ../trait.TripleSink.html
+//! [`max_buffered`]: struct.TurtleConfig.html#method.set_max_buffered
+//! [`flush`]: ../trait.TripleSink.html#tymethod.flush
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use super::rio_common::rio_format_triples;
-use rio_turtle::TurtleFormatter;
+use super::config::{BnodeLabeling, SerializerConfig};
+use super::labeling::BnodeLabeler;
+use super::nt::write_term_with_config as write_nt_term;
+use crate::dataset::metadata::GraphMetadata;
+use crate::syntax::escape::{escape_local, write_escaped_string};
+use sophia_api::graph::Graph;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::prefix::PrefixMap;
 use sophia_api::serializer::*;
-use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
+use sophia_api::term::{CopyTerm, SimpleIri, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::mem::MaybeUninit;
 
-/// RDF/XML serializer configuration.
-#[derive(Clone, Debug, Default)]
-pub struct TurtleConfig {}
+/// Default value for [`TurtleConfig::set_max_buffered`](struct.TurtleConfig.html#method.set_max_buffered).
+pub const DEFAULT_MAX_BUFFERED: usize = 100_000;
+
+/// Turtle serializer configuration.
+#[derive(Clone, Debug)]
+pub struct TurtleConfig {
+    common: SerializerConfig,
+    pretty: bool,
+    max_buffered: usize,
+    inline_bnodes: bool,
+}
+
+impl Default for TurtleConfig {
+    fn default() -> Self {
+        SerializerConfig::default().into()
+    }
+}
+
+impl From<SerializerConfig> for TurtleConfig {
+    fn from(common: SerializerConfig) -> Self {
+        TurtleConfig {
+            common,
+            pretty: false,
+            max_buffered: DEFAULT_MAX_BUFFERED,
+            inline_bnodes: false,
+        }
+    }
+}
 
 impl TurtleConfig {
-    // TODO add ways to customize prefixes
+    /// Borrow the options common to all text serializers.
+    pub fn common(&self) -> &SerializerConfig {
+        &self.common
+    }
+
+    /// Mutably borrow the options common to all text serializers.
+    pub fn common_mut(&mut self) -> &mut SerializerConfig {
+        &mut self.common
+    }
+
+    /// Set the pretty configuration.
+    ///
+    /// In pretty mode, triples are grouped by subject,
+    /// and triples sharing the same subject and predicate are written as object lists.
+    /// Lists (`rdf:first`/`rdf:rest` chains of blank nodes that are not referenced elsewhere)
+    /// are written with the collection syntax `( ... )`,
+    /// unless the graph exceeds [`max_buffered`](#method.set_max_buffered) triples.
+    /// Only the first call to [`serialize_triples`] writes collections;
+    /// a later call referring to one of their nodes fails.
+    ///
+    /// [`serialize_triples`]: struct.TurtleSerializer.html#method.serialize_triples
+    pub fn set_pretty(&mut self, pretty: bool) -> &mut Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Set the maximum number of triples buffered in pretty mode.
+    ///
+    /// Grouping triples by subject requires to hold them in memory.
+    /// When more than `max_buffered` triples are held,
+    /// all buffered groups are written, and grouping starts over;
+    /// the subject of a flushed group may therefore appear in several groups.
+    /// This bounds the memory used to pretty-print arbitrarily large graphs.
+    pub fn set_max_buffered(&mut self, max_buffered: usize) -> &mut Self {
+        self.max_buffered = max_buffered.max(1);
+        self
+    }
+
+    /// Set whether blank nodes are written inline in pretty mode (default: false).
+    ///
+    /// A blank node referenced exactly once as an object
+    /// is then written in place of that reference, as `[ ... ]` (or `[]` if it has no triple),
+    /// instead of being written with a label.
+    /// As for collections, this only happens when the graph fits in
+    /// [`max_buffered`](#method.set_max_buffered) triples,
+    /// and only during the first call to [`serialize_triples`];
+    /// a later call referring to a blank node written inline fails,
+    /// since that node has no label to refer to it.
+    ///
+    /// Combined with [`BnodeLabeling::Sequential`] for the remaining blank nodes,
+    /// this avoids echoing long generated labels in the output.
+    ///
+    /// [`BnodeLabeling::Sequential`]: ../config/enum.BnodeLabeling.html#variant.Sequential
+    /// [`serialize_triples`]: struct.TurtleSerializer.html#method.serialize_triples
+    pub fn set_inline_bnodes(&mut self, inline_bnodes: bool) -> &mut Self {
+        self.inline_bnodes = inline_bnodes;
+        self
+    }
+
+    /// The prefixes declared in the output, as (prefix, namespace) pairs.
+    ///
+    /// Shorthand for `self.common().prefixes()`.
+    pub fn prefixes(&self) -> &[(String, String)] {
+        self.common.prefixes()
+    }
+
+    /// Set the prefixes declared in the output, as (prefix, namespace) pairs (default: none).
+    ///
+    /// Shorthand for `self.common_mut().set_prefixes(prefixes)`,
+    /// see [`SerializerConfig::set_prefixes`].
+    ///
+    /// [`SerializerConfig::set_prefixes`]: ../config/struct.SerializerConfig.html#method.set_prefixes
+    pub fn set_prefixes(&mut self, prefixes: Vec<(String, String)>) -> &mut Self {
+        self.common.set_prefixes(prefixes);
+        self
+    }
+
+    /// Add a prefix declaration, replacing any previous declaration of the same prefix.
+    ///
+    /// Shorthand for `self.common_mut().add_prefix(prefix, namespace)`,
+    /// see [`SerializerConfig::set_prefixes`].
+    ///
+    /// [`SerializerConfig::set_prefixes`]: ../config/struct.SerializerConfig.html#method.set_prefixes
+    pub fn add_prefix<P, N>(&mut self, prefix: P, namespace: N) -> &mut Self
+    where
+        P: Into<String>,
+        N: Into<String>,
+    {
+        self.common.add_prefix(prefix, namespace);
+        self
+    }
+
+    /// Add the prefix declarations recorded in `metadata`.
+    ///
+    /// Shorthand for `self.common_mut().add_prefixes_from(metadata)`,
+    /// see [`SerializerConfig::add_prefixes_from`].
+    ///
+    /// [`SerializerConfig::add_prefixes_from`]: ../config/struct.SerializerConfig.html#method.add_prefixes_from
+    pub fn add_prefixes_from(&mut self, metadata: &GraphMetadata) -> &mut Self {
+        self.common.add_prefixes_from(metadata);
+        self
+    }
+}
+
+pub(crate) type Prefixes<'a> = HashMap<&'a str, SimpleIri<'a>>;
+
+/// The prefixes declared by `config`, as a [`PrefixMap`](../../prefix/trait.PrefixMap.html).
+pub(crate) fn prefix_map(config: &SerializerConfig) -> Prefixes<'_> {
+    config
+        .prefixes()
+        .iter()
+        .map(|(p, ns)| (p.as_str(), SimpleIri::new_unchecked(ns, None)))
+        .collect()
+}
+
+/// Write the `@prefix` declarations of the prefixes declared by `config`, if any.
+pub(crate) fn write_prefixes<W: io::Write>(w: &mut W, config: &SerializerConfig) -> io::Result<()> {
+    if !config.prefixes().is_empty() {
+        for (prefix, ns) in config.prefixes() {
+            write!(w, "@prefix {}: ", prefix)?;
+            write_nt_term(w, &SimpleIri::new_unchecked(ns, None), config)?;
+            w.write_all(b" .\n")?;
+        }
+        w.write_all(b"\n")?;
+    }
+    Ok(())
 }
 
-/// RDF/XML serializer.
+/// Turtle serializer.
 pub struct TurtleSerializer<W> {
     config: TurtleConfig,
-    write: MaybeUninit<W>,
+    write: W,
+    labeler: BnodeLabeler,
+    /// Whether the prefixes of the configuration have been written.
+    started: bool,
+    /// The triples pushed in pretty mode, not flushed yet.
+    pending: SubjectGroups,
 }
 
 impl<W> TurtleSerializer<W>
 where
     W: io::Write,
 {
-    /// Build a new N-Triples serializer writing to `write`, with the default config.
+    /// Build a new Turtle serializer writing to `write`, with the default config.
     #[inline]
     pub fn new(write: W) -> TurtleSerializer<W> {
         Self::new_with_config(write, TurtleConfig::default())
     }
 
-    /// Build a new N-Triples serializer writing to `write`, with the given config.
+    /// Build a new Turtle serializer writing to `write`, with the given config.
     pub fn new_with_config(write: W, config: TurtleConfig) -> TurtleSerializer<W> {
-        let write = MaybeUninit::new(write);
-        TurtleSerializer { write, config }
+        let labeler = BnodeLabeler::new(config.common.bnode_labeling());
+        TurtleSerializer {
+            write,
+            config,
+            labeler,
+            started: false,
+            pending: SubjectGroups::default(),
+        }
     }
 
     /// Borrow this serializer's configuration.
     pub fn config(&self) -> &TurtleConfig {
         &self.config
     }
-
-    fn write(&self) -> &W {
-        unsafe { &*self.write.as_ptr() }
-    }
 }
 
 impl<W> TripleSerializer for TurtleSerializer<W>
@@ -62,6 +238,19 @@ where
 {
     type Error = io::Error;
 
+    /// Serialize all triples from the given [`TripleSource`].
+    ///
+    /// This method can be called several times:
+    /// blank nodes are relabeled consistently across calls,
+    /// except with [`BnodeLabeling::Canonical`],
+    /// where the triples of each call are labeled on their own.
+    /// Collections and blank nodes are only written inline during the first call,
+    /// and later calls fail if they refer to one of them.
+    /// Triples pushed through [`TripleSink`] and not flushed yet are written first.
+    ///
+    /// [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+    /// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+    /// [`TripleSink`]: ../trait.TripleSink.html
     fn serialize_triples<TS>(
         &mut self,
         source: TS,
@@ -69,22 +258,689 @@ where
     where
         TS: TripleSource,
     {
-        // temporarily move out self.write
-        let mut buffer = MaybeUninit::uninit();
-        std::mem::swap(&mut self.write, &mut buffer);
-        let write = unsafe { buffer.assume_init() };
-
-        let mut tf = TurtleFormatter::new(write);
-        rio_format_triples(&mut tf, source)?;
-        let write = tf.finish().map_err(SinkError)?;
-
-        // restore self.write
-        buffer = MaybeUninit::new(write);
-        std::mem::swap(&mut self.write, &mut buffer);
+        self.write_pending().map_err(SinkError)?;
+        // the prefixes are only declared once
+        let start = !self.started;
+        self.started = true;
+        // the groups of previous calls tell which blank nodes can still be written inline
+        let res = if self.config.common.bnode_labeling() == BnodeLabeling::Canonical {
+            let triples: Vec<[BoxTerm; 3]> = source
+                .collect_triples()
+                .map_err(|e| SourceError(e.unwrap_source_error()))?;
+            let mut labeler =
+                BnodeLabeler::canonical(triples.iter().map(|[s, p, o]| ([s, p, o], None)));
+            let groups = std::mem::take(&mut self.pending);
+            let mut tw = TurtleWriter::with_groups(&self.config, groups);
+            let res = write_triples(
+                &mut self.write,
+                &mut tw,
+                &mut labeler,
+                triples.triples(),
+                start,
+            )
+            .map_err(|e| SinkError(e.unwrap_sink_error()));
+            self.pending = tw.into_groups();
+            res
+        } else {
+            let groups = std::mem::take(&mut self.pending);
+            let mut tw = TurtleWriter::with_groups(&self.config, groups);
+            let res = write_triples(&mut self.write, &mut tw, &mut self.labeler, source, start);
+            self.pending = tw.into_groups();
+            res
+        };
+        res?;
         Ok(self)
     }
 }
 
+impl<W> TurtleSerializer<W>
+where
+    W: io::Write,
+{
+    /// Write the triples pushed in pretty mode, if any.
+    fn write_pending(&mut self) -> io::Result<()> {
+        let prefixes = prefix_map(&self.config.common);
+        self.pending.flush(
+            &mut self.write,
+            &self.config,
+            &prefixes,
+            &mut self.labeler,
+            false,
+        )
+    }
+}
+
+/// Triples pushed one at a time are relabeled consistently with all other triples,
+/// but can not be labeled canonically:
+/// with [`BnodeLabeling::Canonical`], they are labeled as with [`BnodeLabeling::Sequential`].
+/// In pretty mode, they are grouped by subject,
+/// but lists and blank nodes are never written inline,
+/// as later triples may refer to them.
+///
+/// [`BnodeLabeling::Canonical`]: ../config/enum.BnodeLabeling.html#variant.Canonical
+/// [`BnodeLabeling::Sequential`]: ../config/enum.BnodeLabeling.html#variant.Sequential
+impl<W> TripleSink for TurtleSerializer<W>
+where
+    W: io::Write,
+{
+    fn push_triple<T>(&mut self, triple: &T) -> Result<(), Self::Error>
+    where
+        T: Triple,
+    {
+        if !self.started {
+            self.started = true;
+            write_prefixes(&mut self.write, &self.config.common)?;
+        }
+        if self.config.pretty {
+            self.pending.add(triple.s(), triple.p(), triple.o());
+            if self.pending.len >= self.config.max_buffered {
+                self.write_pending()?;
+            }
+            Ok(())
+        } else {
+            let prefixes = prefix_map(&self.config.common);
+            write_plain_triple(
+                &mut self.write,
+                &self.config.common,
+                &prefixes,
+                &mut self.labeler,
+                triple,
+            )
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.write.flush()
+    }
+}
+
+fn write_triples<W, TS>(
+    w: &mut W,
+    tw: &mut TurtleWriter,
+    labeler: &mut BnodeLabeler,
+    mut source: TS,
+    start: bool,
+) -> StreamResult<(), TS::Error, io::Error>
+where
+    W: io::Write,
+    TS: TripleSource,
+{
+    if start {
+        tw.start(w).map_err(SinkError)?;
+    }
+    source.try_for_each_triple(|t| tw.write_triple(w, labeler, &t))?;
+    tw.finish(w, labeler).map_err(SinkError)
+}
+
+/// A Turtle serialization in progress,
+/// written piecewise by [`start`], [`write_triple`] and [`finish`].
+///
+/// [`start`]: #method.start
+/// [`write_triple`]: #method.write_triple
+/// [`finish`]: #method.finish
+pub(crate) struct TurtleWriter<'a> {
+    config: &'a TurtleConfig,
+    prefixes: Prefixes<'a>,
+    groups: SubjectGroups,
+}
+
+impl<'a> TurtleWriter<'a> {
+    #[cfg(feature = "async")]
+    pub(crate) fn new(config: &'a TurtleConfig) -> Self {
+        Self::with_groups(config, SubjectGroups::default())
+    }
+
+    /// Continue a serialization, with the groups left by a previous one,
+    /// so that nothing written by the latter is written inline.
+    fn with_groups(config: &'a TurtleConfig, groups: SubjectGroups) -> Self {
+        TurtleWriter {
+            config,
+            prefixes: prefix_map(&config.common),
+            groups,
+        }
+    }
+
+    /// Stop this serialization, returning its groups for the next one.
+    fn into_groups(self) -> SubjectGroups {
+        self.groups
+    }
+
+    /// Write the prefix declarations.
+    pub(crate) fn start<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write_prefixes(w, &self.config.common)
+    }
+
+    /// Write `t`, or buffer it in pretty mode.
+    pub(crate) fn write_triple<W, T>(
+        &mut self,
+        w: &mut W,
+        labeler: &mut BnodeLabeler,
+        t: &T,
+    ) -> io::Result<()>
+    where
+        W: io::Write,
+        T: Triple,
+    {
+        let prefixes = &self.prefixes;
+        if self.config.pretty {
+            self.groups.add(t.s(), t.p(), t.o());
+            if self.groups.len >= self.config.max_buffered {
+                self.groups
+                    .flush(w, self.config, prefixes, labeler, false)?;
+            }
+            Ok(())
+        } else {
+            write_plain_triple(w, &self.config.common, prefixes, labeler, t)
+        }
+    }
+
+    /// Write the triples still buffered in pretty mode.
+    pub(crate) fn finish<W: io::Write>(
+        &mut self,
+        w: &mut W,
+        labeler: &mut BnodeLabeler,
+    ) -> io::Result<()> {
+        self.groups
+            .flush(w, self.config, &self.prefixes, labeler, true)
+    }
+}
+
+/// Write `t` as a single statement, relabeling its blank nodes with `labeler`.
+fn write_plain_triple<W, T>(
+    w: &mut W,
+    config: &SerializerConfig,
+    prefixes: &Prefixes,
+    labeler: &mut BnodeLabeler,
+    t: &T,
+) -> io::Result<()>
+where
+    W: io::Write,
+    T: Triple,
+{
+    labeler.with_label(t.s().as_dyn(), |s| write_prefixed(w, s, config, prefixes))?;
+    w.write_all(b" ")?;
+    write_prefixed(w, t.p(), config, prefixes)?;
+    w.write_all(b" ")?;
+    labeler.with_label(t.o().as_dyn(), |o| write_prefixed(w, o, config, prefixes))?;
+    w.write_all(b" .\n")
+}
+
+/// Write the given term into the given write in the Turtle format,
+/// honouring the relevant options of `config`.
+pub fn write_term<W, T>(w: &mut W, t: &T, config: &SerializerConfig) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    write_prefixed(w, t, config, &Prefixes::new())
+}
+
+/// Write the given term like [`write_term`](fn.write_term.html),
+/// abbreviating IRIs with `prefixes` when possible.
+pub(crate) fn write_prefixed<'p, W, T, P>(
+    w: &mut W,
+    t: &T,
+    config: &SerializerConfig,
+    prefixes: &P,
+) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+    P: PrefixMap<'p>,
+{
+    if t.kind() == TermKind::Iri {
+        return write_iri(w, t, config, prefixes);
+    }
+    if t.kind() != TermKind::Literal {
+        return write_nt_term(w, t, config);
+    }
+    let value = t.value_raw().0;
+    match t.language() {
+        Some(tag) => {
+            write_quoted(w, value, config)?;
+            w.write_all(b"@")?;
+            w.write_all(tag.as_bytes())
+        }
+        None => {
+            let dt = t.datatype().unwrap();
+            if (config.numeric_shorthand() && is_numeric_shorthand(&dt, value))
+                || (config.boolean_shorthand()
+                    && xsd::boolean == dt
+                    && (value == "true" || value == "false"))
+            {
+                return w.write_all(value.as_bytes());
+            }
+            write_quoted(w, value, config)?;
+            if config.explicit_xsd_string() || xsd::string != dt {
+                w.write_all(b"^^")?;
+                write_iri(w, &dt, config, prefixes)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write `iri` as a prefixed name if possible, or in full otherwise.
+fn write_iri<'p, W, T, P>(
+    w: &mut W,
+    iri: &T,
+    config: &SerializerConfig,
+    prefixes: &P,
+) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+    P: PrefixMap<'p>,
+{
+    let raw = iri.value_raw();
+    let iri = SimpleIri::new_unchecked(raw.0, raw.1);
+    if let Some((prefix, suffix)) = prefixes.get_prefixed_pair(&iri) {
+        if let Some(local) = escape_local(&suffix) {
+            return write!(w, "{}:{}", prefix, local);
+        }
+    }
+    write_nt_term(w, &iri, config)
+}
+
+fn write_quoted<W: io::Write>(w: &mut W, value: &str, config: &SerializerConfig) -> io::Result<()> {
+    let quote = config.quote_style().choose(value);
+    write!(w, "{}", quote)?;
+    write_escaped_string(w, value, quote, config.ascii())?;
+    write!(w, "{}", quote)
+}
+
+/// Whether a literal with the given datatype and lexical value
+/// can be written as a Turtle numeric shorthand.
+fn is_numeric_shorthand(dt: &SimpleIri, value: &str) -> bool {
+    let value = unsigned(value);
+    if xsd::integer == *dt {
+        all_digits(value)
+    } else if xsd::decimal == *dt {
+        match value.find('.') {
+            Some(i) => (i == 0 || all_digits(&value[..i])) && all_digits(&value[i + 1..]),
+            None => false,
+        }
+    } else if xsd::double == *dt {
+        match value.find(&['e', 'E'][..]) {
+            Some(i) => {
+                let mantissa = &value[..i];
+                let mantissa_ok = match mantissa.find('.') {
+                    Some(j) => {
+                        mantissa.len() > 1
+                            && (j == 0 || all_digits(&mantissa[..j]))
+                            && (j + 1 == mantissa.len() || all_digits(&mantissa[j + 1..]))
+                    }
+                    None => all_digits(mantissa),
+                };
+                mantissa_ok && all_digits(unsigned(&value[i + 1..]))
+            }
+            None => false,
+        }
+    } else {
+        false
+    }
+}
+
+fn unsigned(value: &str) -> &str {
+    if value.starts_with('+') || value.starts_with('-') {
+        &value[1..]
+    } else {
+        value
+    }
+}
+
+fn all_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Triples buffered by subject, for pretty output.
+#[derive(Default)]
+struct SubjectGroups {
+    groups: Vec<(BoxTerm, Vec<[BoxTerm; 2]>)>,
+    index: HashMap<BoxTerm, usize>,
+    len: usize,
+    written: bool,
+    /// The blank nodes already written inline, which have no label to refer to them.
+    inlined: HashSet<BoxTerm>,
+}
+
+impl SubjectGroups {
+    fn add<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO)
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let s = BoxTerm::copy(s);
+        let i = match self.index.get(&s) {
+            Some(i) => *i,
+            None => {
+                let i = self.groups.len();
+                self.index.insert(s.clone(), i);
+                self.groups.push((s, vec![]));
+                i
+            }
+        };
+        self.groups[i].1.push([BoxTerm::copy(p), BoxTerm::copy(o)]);
+        self.len += 1;
+    }
+
+    /// Write all buffered groups.
+    ///
+    /// If `last` is true, and all the triples are buffered (i.e. nothing was flushed before),
+    /// lists are written with the collection syntax,
+    /// and blank nodes may be written inline (see [`inlined`](fn.inlined.html)).
+    fn flush<W: io::Write>(
+        &mut self,
+        w: &mut W,
+        tconfig: &TurtleConfig,
+        prefixes: &Prefixes,
+        labeler: &mut BnodeLabeler,
+        last: bool,
+    ) -> io::Result<()> {
+        if !self.inlined.is_empty() {
+            let terms = self
+                .groups
+                .iter()
+                .flat_map(|(s, pos)| std::iter::once(s).chain(pos.iter().flatten()));
+            for t in terms {
+                if self.inlined.contains(t) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "blank node _:{} was written inline, and can not be referred to again",
+                            t.value()
+                        ),
+                    ));
+                }
+            }
+        }
+        let mut groups = std::mem::take(&mut self.groups);
+        if tconfig.common.sort_subjects() {
+            groups.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        for (_, pos) in groups.iter_mut() {
+            // rdf:type first, then other predicates (stable sort keeps object order)
+            pos.sort_by(|a, b| {
+                let ta = rdf::type_ == a[0];
+                let tb = rdf::type_ == b[0];
+                tb.cmp(&ta).then_with(|| a[0].cmp(&b[0]))
+            });
+        }
+        let inlined = if last && !self.written {
+            inlined(&groups, tconfig.inline_bnodes)
+        } else {
+            Inlined::default()
+        };
+        self.inlined
+            .extend(inlined.nodes.iter().map(|n| (*n).clone()));
+        let config = &tconfig.common;
+        let indent = config.indent();
+        let mut out = Vec::new();
+        let mut obj = Vec::new();
+        for (s, pos) in &groups {
+            if inlined.nodes.contains(s) {
+                continue;
+            }
+            out.clear();
+            if self.written {
+                out.push(b'\n');
+            }
+            let mut line_start = out.len();
+            labeler.with_label(s, |s| write_prefixed(&mut out, s, config, prefixes))?;
+            let mut prev: Option<&BoxTerm> = None;
+            for [p, o] in pos {
+                obj.clear();
+                inlined.write_object(&mut obj, o, config, prefixes, labeler)?;
+                match prev {
+                    Some(prev_p) if prev_p == p => {
+                        out.push(b',');
+                        let col = out.len() - line_start;
+                        match config.max_line_length() {
+                            Some(max) if col + 1 + obj.len() > max => {
+                                out.push(b'\n');
+                                line_start = out.len();
+                                out.resize(out.len() + 2 * indent, b' ');
+                            }
+                            _ => out.push(b' '),
+                        }
+                    }
+                    _ => {
+                        if prev.is_some() {
+                            out.extend_from_slice(b" ;\n");
+                            line_start = out.len();
+                            out.resize(out.len() + indent, b' ');
+                        } else {
+                            out.push(b' ');
+                        }
+                        if rdf::type_ == *p {
+                            out.push(b'a');
+                        } else {
+                            write_prefixed(&mut out, p, config, prefixes)?;
+                        }
+                        out.push(b' ');
+                    }
+                }
+                out.extend_from_slice(&obj);
+                prev = Some(p);
+            }
+            out.extend_from_slice(b" .\n");
+            w.write_all(&out)?;
+            self.written = true;
+        }
+        self.index.clear();
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// The blank nodes written inline, as collections `( ... )` or as `[ ... ]`.
+#[derive(Default)]
+struct Inlined<'a> {
+    /// The items of each list written as a collection, indexed by its first node.
+    items: HashMap<&'a BoxTerm, Vec<&'a BoxTerm>>,
+    /// The predicate-object pairs of each blank node written as `[ ... ]`.
+    bnodes: HashMap<&'a BoxTerm, &'a [[BoxTerm; 2]]>,
+    /// All the nodes written inline, whose triples are not written separately.
+    nodes: HashSet<&'a BoxTerm>,
+}
+
+impl<'a> Inlined<'a> {
+    /// Write `o`, inline if possible.
+    fn write_object<W: io::Write>(
+        &self,
+        w: &mut W,
+        o: &BoxTerm,
+        config: &SerializerConfig,
+        prefixes: &Prefixes,
+        labeler: &mut BnodeLabeler,
+    ) -> io::Result<()> {
+        if let Some(items) = self.items.get(o) {
+            w.write_all(b"(")?;
+            for item in items {
+                w.write_all(b" ")?;
+                self.write_object(w, item, config, prefixes, labeler)?;
+            }
+            return w.write_all(b" )");
+        }
+        match self.bnodes.get(o) {
+            Some([]) => w.write_all(b"[]"),
+            Some(pos) => {
+                w.write_all(b"[")?;
+                let mut prev: Option<&BoxTerm> = None;
+                for [p, o] in pos.iter() {
+                    if prev == Some(p) {
+                        w.write_all(b",")?;
+                    } else {
+                        if prev.is_some() {
+                            w.write_all(b" ;")?;
+                        }
+                        w.write_all(b" ")?;
+                        if rdf::type_ == *p {
+                            w.write_all(b"a")?;
+                        } else {
+                            write_prefixed(w, p, config, prefixes)?;
+                        }
+                    }
+                    w.write_all(b" ")?;
+                    self.write_object(w, o, config, prefixes, labeler)?;
+                    prev = Some(p);
+                }
+                w.write_all(b" ]")
+            }
+            None => labeler.with_label(o, |o| write_prefixed(w, o, config, prefixes)),
+        }
+    }
+}
+
+/// Find the blank nodes in `groups` that can be written inline.
+///
+/// These are the lists made of blank nodes described only by `rdf:first` and `rdf:rest`,
+/// ending with `rdf:nil`, and whose nodes are referenced exactly once
+/// (the first one by a triple that is itself written, the others by the previous node),
+/// which are written with the collection syntax.
+/// If `bnodes` is true, these are also the other blank nodes referenced exactly once,
+/// by a triple that is itself written, which are written as `[ ... ]`.
+/// Writing them inline thus produces an isomorphic graph.
+fn inlined(groups: &[(BoxTerm, Vec<[BoxTerm; 2]>)], bnodes: bool) -> Inlined<'_> {
+    // the object of rdf:first and rdf:rest of each candidate list node
+    let mut cells: HashMap<&BoxTerm, (&BoxTerm, &BoxTerm)> = HashMap::new();
+    for (s, pos) in groups {
+        if s.kind() != TermKind::BlankNode || pos.len() != 2 {
+            continue;
+        }
+        let first = pos.iter().find(|[p, _]| rdf::first == *p);
+        let rest = pos.iter().find(|[p, _]| rdf::rest == *p);
+        if let (Some([_, first]), Some([_, rest])) = (first, rest) {
+            cells.insert(s, (first, rest));
+        }
+    }
+    // the subject referencing each blank node, if it is referenced exactly once as an object
+    let mut referrers: HashMap<&BoxTerm, Option<&BoxTerm>> = HashMap::new();
+    for (s, pos) in groups {
+        for [p, o] in pos {
+            if p.kind() == TermKind::BlankNode {
+                referrers.insert(p, None);
+            }
+            if o.kind() == TermKind::BlankNode {
+                referrers
+                    .entry(o)
+                    .and_modify(|r| *r = None)
+                    .or_insert(Some(s));
+            }
+        }
+    }
+    let referrer_of = |t: &BoxTerm| referrers.get(t).copied().flatten();
+
+    // (first node, referrer of the first node, nodes, items) of each well-formed list
+    let mut lists = vec![];
+    for &head in cells.keys() {
+        let referrer = match referrer_of(head) {
+            Some(r) => r,
+            None => continue,
+        };
+        if matches!(cells.get(referrer), Some((_, rest)) if *rest == head) {
+            // not the first node of its list
+            continue;
+        }
+        let mut nodes = vec![head];
+        let mut items = vec![];
+        let mut current = head;
+        let well_formed = loop {
+            let (first, rest) = cells[current];
+            items.push(first);
+            if rdf::nil == *rest {
+                break true;
+            }
+            match cells.get_key_value(rest) {
+                Some((&next, _))
+                    if referrer_of(next) == Some(current) && !nodes.contains(&next) =>
+                {
+                    nodes.push(next);
+                    current = next;
+                }
+                _ => break false,
+            }
+        };
+        if well_formed {
+            lists.push((head, referrer, nodes, items));
+        }
+    }
+
+    // (node, referrer) of each other blank node that could be written as [ ... ]
+    let anonymous: Vec<(&BoxTerm, &BoxTerm)> = if bnodes {
+        let list_nodes: HashSet<&BoxTerm> = lists
+            .iter()
+            .flat_map(|(_, _, nodes, _)| nodes.iter().copied())
+            .collect();
+        referrers
+            .iter()
+            .filter_map(|(&b, r)| match r {
+                Some(r) if !list_nodes.contains(b) => Some((b, *r)),
+                _ => None,
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    // lists come first, then anonymous nodes
+    let referrer_of_inlined = |i: usize| {
+        if i < lists.len() {
+            lists[i].1
+        } else {
+            anonymous[i - lists.len()].1
+        }
+    };
+    let mut owner: HashMap<&BoxTerm, usize> = lists
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, _, nodes, _))| nodes.iter().map(move |n| (*n, i)))
+        .collect();
+    owner.extend(
+        anonymous
+            .iter()
+            .enumerate()
+            .map(|(i, (b, _))| (*b, lists.len() + i)),
+    );
+    let pos_of: HashMap<&BoxTerm, &[[BoxTerm; 2]]> =
+        groups.iter().map(|(s, pos)| (s, &pos[..])).collect();
+
+    // a node can only be written inline if its referrer is eventually written,
+    // i.e. if it is not nested in a cycle of inlined nodes
+    let mut inlined = Inlined::default();
+    for i in 0..lists.len() + anonymous.len() {
+        let mut visited = vec![i];
+        let mut referrer = referrer_of_inlined(i);
+        let written = loop {
+            match owner.get(referrer) {
+                None => break true,
+                Some(j) if visited.contains(j) => break false,
+                Some(&j) => {
+                    visited.push(j);
+                    referrer = referrer_of_inlined(j);
+                }
+            }
+        };
+        if !written {
+            continue;
+        }
+        if i < lists.len() {
+            let (head, _, nodes, items) = &lists[i];
+            inlined.items.insert(*head, items.clone());
+            inlined.nodes.extend(nodes);
+        } else {
+            let b = anonymous[i - lists.len()].0;
+            inlined
+                .bnodes
+                .insert(b, pos_of.get(b).copied().unwrap_or(&[]));
+            inlined.nodes.insert(b);
+        }
+    }
+    inlined
+}
+
 impl TurtleSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -98,9 +954,11 @@ impl TurtleSerializer<Vec<u8>> {
     }
 }
 
+/// NB: in pretty mode, triples pushed through [`TripleSink`](../trait.TripleSink.html)
+/// are only visible once the serializer is [flushed](../trait.TripleSink.html#tymethod.flush).
 impl Stringifier for TurtleSerializer<Vec<u8>> {
     fn as_utf8(&self) -> &[u8] {
-        &self.write()[..]
+        &self.write[..]
     }
 }
 
@@ -111,8 +969,11 @@ impl Stringifier for TurtleSerializer<Vec<u8>> {
 #[cfg(test)]
 pub(crate) mod test {
     use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::metadata::DatasetMetadata;
+    use crate::serializer::config::QuoteStyle;
+    use sophia_api::dataset::Dataset;
     use sophia_api::graph::isomorphic_graphs;
-    use sophia_api::ns::*;
     use sophia_term::literal::convert::AsLiteral;
     use sophia_term::*;
 
@@ -138,4 +999,447 @@ pub(crate) mod test {
         assert!(isomorphic_graphs(&g, &g2)?);
         Ok(())
     }
+
+    fn some_graph() -> Result<Vec<[StaticTerm; 3]>, Box<dyn std::error::Error>> {
+        let alice = StaticTerm::new_iri("http://example.org/alice")?;
+        let bob = StaticTerm::new_iri("http://example.org/bob")?;
+        let knows = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/knows")?;
+        let person = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/Person")?;
+        Ok(vec![
+            [alice, knows, bob],
+            [bob, rdf::type_.into(), person],
+            [alice, rdf::type_.into(), person],
+            [alice, knows, alice],
+        ])
+    }
+
+    #[test]
+    fn pretty() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = TurtleConfig::default();
+        config.set_pretty(true);
+        let s = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            s,
+            r#"<http://example.org/alice> a <http://xmlns.com/foaf/0.1/Person> ;
+    <http://xmlns.com/foaf/0.1/knows> <http://example.org/bob>, <http://example.org/alice> .
+
+<http://example.org/bob> a <http://xmlns.com/foaf/0.1/Person> .
+"#
+        );
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = TurtleConfig::default();
+        config
+            .set_pretty(true)
+            .add_prefix("ex", "http://example.org/")
+            .add_prefix("foaf", "http://example.org/wrong#")
+            .add_prefix("foaf", "http://xmlns.com/foaf/0.1/");
+        let s = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            s,
+            r#"@prefix ex: <http://example.org/> .
+@prefix foaf: <http://xmlns.com/foaf/0.1/> .
+
+ex:alice a foaf:Person ;
+    foaf:knows ex:bob, ex:alice .
+
+ex:bob a foaf:Person .
+"#
+        );
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn prefixed_names() -> Result<(), Box<dyn std::error::Error>> {
+        let s = StaticTerm::new_iri("http://example.org/a/b")?;
+        let p = StaticTerm::new_iri("http://example.org/ns#p.")?;
+        let g = vec![
+            [s, p, StaticTerm::new_literal_dt("1.", xsd::decimal)?],
+            [s, p, StaticTerm::new_iri("http://example.org/ns#")?],
+            [s, p, StaticTerm::new_iri("http://example.org/ns#x%20y")?],
+            [s, p, StaticTerm::new_iri("http://example.org/ns#x\u{A1}y")?],
+        ];
+        let mut config = TurtleConfig::default();
+        config.set_prefixes(vec![
+            ("".into(), "http://example.org/".into()),
+            ("ns".into(), "http://example.org/ns#".into()),
+            ("xsd".into(), xsd::PREFIX.into()),
+        ]);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(
+            out.contains(r#":a\/b ns:p\. "1."^^xsd:decimal ."#),
+            "{}",
+            out
+        );
+        assert!(out.contains(r#":a\/b ns:p\. ns: ."#), "{}", out);
+        assert!(out.contains(r#":a\/b ns:p\. ns:x%20y ."#), "{}", out);
+        // U+00A1 is allowed in IRIs, but not in local names, even escaped
+        assert!(
+            out.contains(":a\\/b ns:p\\. <http://example.org/ns#x\u{A1}y> ."),
+            "{}",
+            out
+        );
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_bounded() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = TurtleConfig::default();
+        config.set_pretty(true).set_max_buffered(2);
+        let s = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(s.matches("<http://example.org/alice> ").count(), 3);
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn prefixes_declared_once() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = TurtleConfig::default();
+        config.add_prefix("ex", "http://example.org/");
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config);
+        ser.push_triple(&g[0])?;
+        ser.serialize_graph(&g)?;
+        ser.serialize_graph(&g)?;
+        let s = ser.as_str();
+        assert_eq!(s.matches("@prefix").count(), 1);
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(s).collect_triples()?;
+        assert_eq!(g2.len(), 2 * g.len() + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn shared_bnode_across_calls() -> Result<(), Box<dyn std::error::Error>> {
+        let a = StaticTerm::new_iri("http://example.org/a")?;
+        let p = StaticTerm::new_iri("http://example.org/p")?;
+        let q = StaticTerm::new_iri("http://example.org/q")?;
+        let x = StaticTerm::new_bnode("x")?;
+        let g1 = vec![[a, p, x]];
+        let g2 = vec![[x, q, a]];
+        let mut config = TurtleConfig::default();
+        config.set_pretty(true).set_inline_bnodes(true);
+
+        // the blank node is labeled by the first call, and not inlined by the second
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config.clone());
+        ser.serialize_triples(g2.triples())?;
+        ser.serialize_triples(g1.triples())?;
+        let g: Vec<[BoxTerm; 3]> =
+            crate::parser::turtle::parse_str(ser.as_str()).collect_triples()?;
+        assert_eq!(g.len(), 2);
+        assert_eq!(g[0][0], g[1][2]);
+
+        // the blank node is inlined by the first call, and can not be referred to any more
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config);
+        ser.serialize_triples(g1.triples())?;
+        assert!(ser.as_str().contains("[]"));
+        assert!(ser.serialize_triples(g2.triples()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_list_across_calls() -> Result<(), Box<dyn std::error::Error>> {
+        let a = StaticTerm::new_iri("http://example.org/a")?;
+        let p = StaticTerm::new_iri("http://example.org/p")?;
+        let q = StaticTerm::new_iri("http://example.org/q")?;
+        let l1 = StaticTerm::new_bnode("l1")?;
+        let l2 = StaticTerm::new_bnode("l2")?;
+        let g1 = vec![
+            [a, p, l1],
+            [l1, rdf::first.into(), a],
+            [l1, rdf::rest.into(), l2],
+            [l2, rdf::first.into(), p],
+            [l2, rdf::rest.into(), rdf::nil.into()],
+        ];
+        let g2 = vec![[l2, q, a]];
+        let mut config = TurtleConfig::default();
+        config.set_pretty(true);
+
+        // the list is folded by the first call, and its nodes can not be referred to any more
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config.clone());
+        ser.serialize_triples(g1.triples())?;
+        assert!(ser.as_str().contains('('));
+        assert!(ser.serialize_triples(g2.triples()).is_err());
+
+        // the list is not folded by the second call, so its nodes remain shared
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config);
+        ser.serialize_triples(g2.triples())?;
+        ser.serialize_triples(g1.triples())?;
+        assert!(!ser.as_str().contains('('));
+        let g: Vec<[BoxTerm; 3]> =
+            crate::parser::turtle::parse_str(ser.as_str()).collect_triples()?;
+        let mut expected = g1.clone();
+        expected.extend(g2);
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn sink() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = TurtleConfig::default();
+        config.add_prefix("ex", "http://example.org/");
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config.clone());
+        for t in &g {
+            ser.push_triple(t)?;
+        }
+        TripleSink::flush(&mut ser)?;
+        let s = ser.as_str();
+        assert_eq!(s.matches("@prefix").count(), 1);
+        assert!(s.starts_with("@prefix ex: <http://example.org/> .\n\nex:alice "));
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+
+        config.set_pretty(true);
+        let mut ser = TurtleSerializer::new_stringifier_with_config(config);
+        let (first, rest) = g.split_at(1);
+        ser.push_triple(&first[0])?;
+        assert_eq!(ser.as_str(), "@prefix ex: <http://example.org/> .\n\n");
+        TripleSink::flush(&mut ser)?;
+        let flushed = ser.as_str().len();
+        let g2: Vec<[BoxTerm; 3]> =
+            crate::parser::turtle::parse_str(ser.as_str()).collect_triples()?;
+        assert!(isomorphic_graphs(&first.to_vec(), &g2)?);
+        for t in rest {
+            ser.push_triple(t)?;
+        }
+        assert_eq!(ser.as_str().len(), flushed);
+        TripleSink::flush(&mut ser)?;
+        let g2: Vec<[BoxTerm; 3]> =
+            crate::parser::turtle::parse_str(ser.as_str()).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_collections() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = r#"
+            @prefix : <http://example.org/>.
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+            :s :p ( :a ( :b :c ) "d" ).
+            # referenced twice
+            :t :q _:shared. :u :q _:shared. _:shared rdf:first :a; rdf:rest rdf:nil.
+            # not only a list node
+            :t :q _:x. _:x rdf:first :a; rdf:rest rdf:nil; :r :b.
+            # not terminated
+            :t :q _:y. _:y rdf:first :a; rdf:rest :z.
+            # nested in itself
+            _:c1 rdf:first :a; rdf:rest _:c2. _:c2 rdf:first _:c1; rdf:rest rdf:nil.
+        "#;
+        let g: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let mut config = TurtleConfig::default();
+        config
+            .set_pretty(true)
+            .add_prefix("ex", "http://example.org/");
+        let out = TurtleSerializer::new_stringifier_with_config(config.clone())
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(
+            out.contains(r#"ex:s ex:p ( ex:a ( ex:b ex:c ) "d" ) ."#),
+            "{}",
+            out
+        );
+        assert_eq!(out.matches("rdf-syntax-ns#first>").count(), 5, "{}", out);
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+
+        // lists are not detected when the graph is written piecewise
+        config.set_max_buffered(4);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(!out.contains('('), "{}", out);
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_sorted() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = r#"
+            @prefix : <http://example.org/>.
+            :c :p 3. :a :p 1. :b :p 2. :a :q 1.
+        "#;
+        let g: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let mut config = TurtleConfig::default();
+        config
+            .set_pretty(true)
+            .common_mut()
+            .add_prefix("", "http://example.org/")
+            .set_numeric_shorthand(true)
+            .set_sort_subjects(true);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"@prefix : <http://example.org/> .
+
+:a :p 1 ;
+    :q 1 .
+
+:b :p 2 .
+
+:c :p 3 .
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_inline_bnodes() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = r#"
+            @prefix : <http://example.org/>.
+            :alice :knows [ a :Person; :name "Bob", "Robert"; :knows [] ].
+            :alice :knows _:carol. _:carol :knows _:carol.
+            :alice :likes ( [ :p :o ] ).
+            :alice :address _:a. _:a :next _:b. _:b :next _:a.
+            _:x :p _:y. _:y :p _:x.
+        "#;
+        let g: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let mut config = TurtleConfig::default();
+        config
+            .set_pretty(true)
+            .set_inline_bnodes(true)
+            .add_prefix("ex", "http://example.org/")
+            .common_mut()
+            .set_bnode_labeling(BnodeLabeling::Sequential);
+        let out = TurtleSerializer::new_stringifier_with_config(config.clone())
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(
+            out.contains(r#"[ a ex:Person ; ex:knows [] ; ex:name "Bob", "Robert" ]"#),
+            "{}",
+            out
+        );
+        assert!(out.contains("ex:likes ( [ ex:p ex:o ] )"), "{}", out);
+        assert!(out.contains("ex:next [ ex:next _:b"), "{}", out);
+        assert!(!out.contains("carol"), "{}", out);
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+
+        config.set_inline_bnodes(false);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(!out.contains('['), "{}", out);
+        Ok(())
+    }
+
+    #[test]
+    fn common_config() -> Result<(), Box<dyn std::error::Error>> {
+        let s = StaticTerm::new_iri("http://example.org/s")?;
+        let p = StaticTerm::new_iri("http://example.org/p")?;
+        let g = vec![
+            [s, p, StaticTerm::new_literal_dt("42", xsd::integer)?],
+            [s, p, StaticTerm::new_literal_dt("-.5", xsd::decimal)?],
+            [s, p, StaticTerm::new_literal_dt("1.0E3", xsd::double)?],
+            [s, p, StaticTerm::new_literal_dt("1.", xsd::decimal)?],
+            [s, p, StaticTerm::new_literal_dt("say \"é\"", xsd::string)?],
+        ];
+        let mut common = SerializerConfig::new();
+        common
+            .set_indent(2)
+            .set_max_line_length(Some(40))
+            .set_quote_style(QuoteStyle::Auto)
+            .set_ascii(true)
+            .set_numeric_shorthand(true);
+        let mut config = TurtleConfig::from(common);
+        config.set_pretty(true);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"<http://example.org/s> <http://example.org/p> 42,
+    -.5, 1.0E3,
+    "1."^^<http://www.w3.org/2001/XMLSchema#decimal>,
+    'say "\u00E9"' .
+"#
+        );
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn literal_shorthands() -> Result<(), Box<dyn std::error::Error>> {
+        let s = StaticTerm::new_iri("http://example.org/s")?;
+        let p = StaticTerm::new_iri("http://example.org/p")?;
+        let g = vec![
+            [s, p, StaticTerm::new_literal_dt("true", xsd::boolean)?],
+            [s, p, StaticTerm::new_literal_dt("1", xsd::boolean)?],
+            [s, p, StaticTerm::new_literal_dt("12", xsd::integer)?],
+            [s, p, StaticTerm::new_literal_dt("foo", xsd::string)?],
+        ];
+        let ser = |common: &SerializerConfig| -> Result<String, Box<dyn std::error::Error>> {
+            Ok(
+                TurtleSerializer::new_stringifier_with_config(common.clone().into())
+                    .serialize_graph(&g)?
+                    .to_string(),
+            )
+        };
+
+        let mut common = SerializerConfig::new();
+        let out = ser(&common)?;
+        assert!(out.contains(r#" "true"^^<http://www.w3.org/2001/XMLSchema#boolean> ."#));
+        assert!(out.contains(r#" "12"^^<http://www.w3.org/2001/XMLSchema#integer> ."#));
+        assert!(out.contains(r#" "foo" ."#));
+
+        common
+            .set_boolean_shorthand(true)
+            .set_numeric_shorthand(true)
+            .set_explicit_xsd_string(true);
+        let out = ser(&common)?;
+        assert!(out.contains(" true ."));
+        assert!(out.contains(r#" "1"^^<http://www.w3.org/2001/XMLSchema#boolean> ."#));
+        assert!(out.contains(" 12 ."));
+        assert!(out.contains(r#" "foo"^^<http://www.w3.org/2001/XMLSchema#string> ."#));
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::turtle::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = "@prefix ex: <http://example.org/> .\nex:s ex:p ex:o .\n";
+        let mut d = FastDataset::new();
+        let mut md = DatasetMetadata::new();
+        crate::parser::turtle::TurtleParser::default().load_into(
+            ttl,
+            None as Option<&BoxTerm>,
+            &mut d,
+            &mut md,
+        )?;
+        let mut config = TurtleConfig::default();
+        config.add_prefixes_from(md.get(None as Option<&BoxTerm>).unwrap());
+        let s = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&d.graph(None as Option<&BoxTerm>))?
+            .to_string();
+        assert_eq!(
+            s,
+            "@prefix ex: <http://example.org/> .\n\nex:s ex:p ex:o .\n"
+        );
+        Ok(())
+    }
 }
@@ -1,48 +1,165 @@
 //! Serializer for the [RDF/XML] concrete syntax of RDF.
 //!
+//! Triples are grouped by subject, each subject being described by a single node element.
+//! Language-tagged literals are written with an `xml:lang` attribute,
+//! other literals with an `rdf:datatype` attribute (unless their datatype is `xsd:string`).
+//! Optionally, the first `rdf:type` of each subject can be used as the name of its node element
+//! ([typed node] abbreviation, see [`RdfXmlConfig::set_typed_nodes`]).
+//!
+//! Blank nodes are always relabeled (`b0`, `b1`...),
+//! as their labels must be valid XML names.
+//! Predicates are written as XML qualified names,
+//! using the declared prefixes (see [`RdfXmlConfig::set_prefixes`]),
+//! or generated ones (`ns0`, `ns1`...);
+//! predicates that can not be split into a namespace and a local name
+//! (e.g. `http://example.org/123`) cause an error.
+//!
+//! Of the options common to all text serializers (see [`RdfXmlConfig::common`]),
+//! only `indent`, `ascii` and `prefixes` are relevant to RDF/XML.
+//! With `ascii`, non-ASCII characters are escaped in text and attribute values,
+//! but not in qualified names.
+//!
 //! **Important**:
-//! the methods in this module accepting a [`Write`]
+//! this serializer needs to hold the whole graph in memory,
+//! as all namespaces must be declared before the first triple is written.
+//! Also, the methods in this module accepting a [`Write`]
 //! make no effort to minimize the number of write operations.
 //! Hence, in most cased, they should be passed a [`BufWriter`].
 //!
 //! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
+//! [typed node]: https://www.w3.org/TR/rdf-syntax-grammar/#section-Syntax-typed-nodes
+//! [`RdfXmlConfig::set_typed_nodes`]: struct.RdfXmlConfig.html#method.set_typed_nodes
+//! [`RdfXmlConfig::set_prefixes`]: struct.RdfXmlConfig.html#method.set_prefixes
+//! [`RdfXmlConfig::common`]: struct.RdfXmlConfig.html#method.common
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use super::rio_common::rio_format_triples;
-use rio_xml::RdfXmlFormatter;
+use super::config::{BnodeLabeling, SerializerConfig};
+use super::labeling::BnodeLabeler;
+use sophia_api::ns::{rdf, xsd};
 use sophia_api::serializer::*;
-use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
+use sophia_api::term::{TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_term::BoxTerm;
+use std::collections::HashMap;
 use std::io;
-use std::mem::MaybeUninit;
+
+/// Names of the RDF namespace that can not be used as property or node element names.
+const RESERVED_RDF_NAMES: [&str; 12] = [
+    "RDF",
+    "ID",
+    "about",
+    "parseType",
+    "resource",
+    "nodeID",
+    "datatype",
+    "Description",
+    "li",
+    "aboutEach",
+    "aboutEachPrefix",
+    "bagID",
+];
 
 /// RDF/XML serializer configuration.
-#[derive(Clone, Debug, Default)]
-pub struct RdfXmlConfig {}
+#[derive(Clone, Debug)]
+pub struct RdfXmlConfig {
+    common: SerializerConfig,
+    typed_nodes: bool,
+}
+
+impl Default for RdfXmlConfig {
+    fn default() -> Self {
+        SerializerConfig::default().into()
+    }
+}
+
+impl From<SerializerConfig> for RdfXmlConfig {
+    fn from(common: SerializerConfig) -> Self {
+        RdfXmlConfig {
+            common,
+            typed_nodes: false,
+        }
+    }
+}
 
 impl RdfXmlConfig {
-    // TODO add ways to customize namespaces
+    /// Borrow the options common to all text serializers.
+    pub fn common(&self) -> &SerializerConfig {
+        &self.common
+    }
+
+    /// Mutably borrow the options common to all text serializers.
+    pub fn common_mut(&mut self) -> &mut SerializerConfig {
+        &mut self.common
+    }
+
+    /// Whether typed node elements are used.
+    pub fn typed_nodes(&self) -> bool {
+        self.typed_nodes
+    }
+
+    /// Set whether typed node elements are used (default: false).
+    ///
+    /// When enabled, the first `rdf:type` of each subject (that can be written as an XML name)
+    /// is used as the name of its node element, instead of `rdf:Description`.
+    pub fn set_typed_nodes(&mut self, typed_nodes: bool) -> &mut Self {
+        self.typed_nodes = typed_nodes;
+        self
+    }
+
+    /// The prefixes declared in the output, as (prefix, namespace) pairs.
+    ///
+    /// Shorthand for `self.common().prefixes()`.
+    pub fn prefixes(&self) -> &[(String, String)] {
+        self.common.prefixes()
+    }
+
+    /// Set the prefixes declared in the output, as (prefix, namespace) pairs (default: none).
+    ///
+    /// Shorthand for `self.common_mut().set_prefixes(prefixes)`.
+    ///
+    /// In RDF/XML, every prefix is declared with an `xmlns` attribute on the root element,
+    /// and used for the IRIs of predicates and types starting with its namespace.
+    /// The `rdf` prefix is reserved for the RDF namespace, and ignored in this list.
+    /// Prefixes that are not valid XML names without a colon (`NCName`),
+    /// or that start with `xml`, are ignored as well.
+    pub fn set_prefixes(&mut self, prefixes: Vec<(String, String)>) -> &mut Self {
+        self.common.set_prefixes(prefixes);
+        self
+    }
+
+    /// Add a prefix declaration, replacing any previous declaration of the same prefix.
+    ///
+    /// Shorthand for `self.common_mut().add_prefix(prefix, namespace)`,
+    /// see [`set_prefixes`](#method.set_prefixes).
+    pub fn add_prefix<P, N>(&mut self, prefix: P, namespace: N) -> &mut Self
+    where
+        P: Into<String>,
+        N: Into<String>,
+    {
+        self.common.add_prefix(prefix, namespace);
+        self
+    }
 }
 
 /// RDF/XML serializer.
 pub struct RdfXmlSerializer<W> {
     config: RdfXmlConfig,
-    write: MaybeUninit<W>,
+    write: W,
 }
 
 impl<W> RdfXmlSerializer<W>
 where
     W: io::Write,
 {
-    /// Build a new N-Triples serializer writing to `write`, with the default config.
+    /// Build a new RDF/XML serializer writing to `write`, with the default config.
     #[inline]
     pub fn new(write: W) -> RdfXmlSerializer<W> {
         Self::new_with_config(write, RdfXmlConfig::default())
     }
 
-    /// Build a new N-Triples serializer writing to `write`, with the given config.
+    /// Build a new RDF/XML serializer writing to `write`, with the given config.
     pub fn new_with_config(write: W, config: RdfXmlConfig) -> RdfXmlSerializer<W> {
-        let write = MaybeUninit::new(write);
         RdfXmlSerializer { write, config }
     }
 
@@ -51,8 +168,9 @@ where
         &self.config
     }
 
-    fn write(&self) -> &W {
-        unsafe { &*self.write.as_ptr() }
+    /// Flush the underlying writer.
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
     }
 }
 
@@ -69,22 +187,268 @@ where
     where
         TS: TripleSource,
     {
-        // temporarily move out self.write
-        let mut buffer = MaybeUninit::uninit();
-        std::mem::swap(&mut self.write, &mut buffer);
-        let write = unsafe { buffer.assume_init() };
-
-        let mut tf = RdfXmlFormatter::new(write).map_err(SinkError)?;
-        rio_format_triples(&mut tf, source)?;
-        let write = tf.finish().map_err(SinkError)?;
-
-        // restore self.write
-        buffer = MaybeUninit::new(write);
-        std::mem::swap(&mut self.write, &mut buffer);
+        let triples: Vec<[BoxTerm; 3]> = source
+            .collect_triples()
+            .map_err(|e| SourceError(e.unwrap_source_error()))?;
+        write_triples(&mut self.write, &self.config, &triples).map_err(SinkError)?;
         Ok(self)
     }
 }
 
+/// The description of a subject: its node element name, and its (property name, object) pairs.
+struct Node<'a> {
+    subject: &'a BoxTerm,
+    name: String,
+    properties: Vec<(String, &'a BoxTerm)>,
+}
+
+fn write_triples<W: io::Write>(
+    w: &mut W,
+    config: &RdfXmlConfig,
+    triples: &[[BoxTerm; 3]],
+) -> io::Result<()> {
+    // group triples by subject, in the order of their first occurrence
+    let mut groups: Vec<(&BoxTerm, Vec<&[BoxTerm; 3]>)> = vec![];
+    let mut index = HashMap::new();
+    for t in triples {
+        let i = *index.entry(&t[0]).or_insert_with(|| {
+            groups.push((&t[0], vec![]));
+            groups.len() - 1
+        });
+        groups[i].1.push(t);
+    }
+
+    // compute all names first, so that all namespaces are known
+    let mut namespaces = Namespaces::new(config);
+    let mut nodes = Vec::with_capacity(groups.len());
+    for (subject, triples) in groups {
+        let mut name = None;
+        let mut properties = Vec::with_capacity(triples.len());
+        for [_, p, o] in triples {
+            if name.is_none() && config.typed_nodes && rdf::type_ == *p && o.kind() == TermKind::Iri
+            {
+                name = namespaces.qname(&o.value());
+                if name.is_some() {
+                    continue;
+                }
+            }
+            let qname = match p.kind() {
+                TermKind::Iri => namespaces.qname(&p.value()),
+                _ => None,
+            };
+            match qname {
+                Some(qname) => properties.push((qname, o)),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("can not write predicate {} as an XML name", p),
+                    ))
+                }
+            }
+        }
+        nodes.push(Node {
+            subject,
+            name: name.unwrap_or_else(|| "rdf:Description".to_string()),
+            properties,
+        });
+    }
+
+    let ascii = config.common.ascii();
+    let indent = " ".repeat(config.common.indent());
+    w.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rdf:RDF")?;
+    for (prefix, ns) in &namespaces.decls {
+        write!(w, "\n{}xmlns:{}=\"", indent, prefix)?;
+        write_escaped(w, ns, ascii)?;
+        w.write_all(b"\"")?;
+    }
+    w.write_all(b">\n")?;
+    let mut labeler = BnodeLabeler::new(BnodeLabeling::Sequential);
+    for node in nodes {
+        write!(w, "{}<{}", indent, node.name)?;
+        write_reference(w, node.subject, "about", &mut labeler, ascii)?;
+        if node.properties.is_empty() {
+            w.write_all(b"/>\n")?;
+            continue;
+        }
+        w.write_all(b">\n")?;
+        for (name, o) in node.properties {
+            write!(w, "{}{}<{}", indent, indent, name)?;
+            if o.kind() != TermKind::Literal {
+                write_reference(w, o, "resource", &mut labeler, ascii)?;
+                w.write_all(b"/>\n")?;
+                continue;
+            }
+            match o.language() {
+                Some(tag) => {
+                    w.write_all(b" xml:lang=\"")?;
+                    write_escaped(w, tag, ascii)?;
+                    w.write_all(b"\"")?;
+                }
+                None => {
+                    let dt = o.datatype().unwrap();
+                    if xsd::string != dt {
+                        w.write_all(b" rdf:datatype=\"")?;
+                        write_escaped(w, &dt.value(), ascii)?;
+                        w.write_all(b"\"")?;
+                    }
+                }
+            }
+            w.write_all(b">")?;
+            write_escaped(w, &o.value(), ascii)?;
+            writeln!(w, "</{}>", name)?;
+        }
+        writeln!(w, "{}</{}>", indent, node.name)?;
+    }
+    w.write_all(b"</rdf:RDF>\n")
+}
+
+/// Write the attribute identifying `t`,
+/// i.e. `rdf:nodeID` for blank nodes, and `rdf:{iri_attr}` for IRIs.
+fn write_reference<W: io::Write>(
+    w: &mut W,
+    t: &BoxTerm,
+    iri_attr: &str,
+    labeler: &mut BnodeLabeler,
+    ascii: bool,
+) -> io::Result<()> {
+    match t.kind() {
+        TermKind::Iri => {
+            write!(w, " rdf:{}=\"", iri_attr)?;
+            write_escaped(w, &t.value(), ascii)?;
+            w.write_all(b"\"")
+        }
+        TermKind::BlankNode => {
+            labeler.with_label(t, |t| write!(w, " rdf:nodeID=\"{}\"", t.value()))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("can not write {} as a node in RDF/XML", t),
+        )),
+    }
+}
+
+/// Write `txt`, escaped for use in XML text or attribute values,
+/// with all non-ASCII characters written as character references if `ascii` is true.
+pub(crate) fn write_escaped<W: io::Write>(w: &mut W, txt: &str, ascii: bool) -> io::Result<()> {
+    let mut start = 0;
+    for (i, c) in txt.char_indices() {
+        let escaped = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            '\t' => "&#9;",
+            '\n' => "&#10;",
+            '\r' => "&#13;",
+            _ if ascii && !c.is_ascii() => "", // written below as &#xXXXX;
+            _ => continue,
+        };
+        w.write_all(&txt.as_bytes()[start..i])?;
+        if escaped.is_empty() {
+            write!(w, "&#x{:X};", c as u32)?;
+        } else {
+            w.write_all(escaped.as_bytes())?;
+        }
+        start = i + c.len_utf8();
+    }
+    w.write_all(&txt.as_bytes()[start..])
+}
+
+/// The namespaces declared in the output.
+struct Namespaces {
+    /// (prefix, namespace) pairs, `rdf` first
+    decls: Vec<(String, String)>,
+    /// the number of generated prefixes
+    generated: usize,
+}
+
+impl Namespaces {
+    fn new(config: &RdfXmlConfig) -> Self {
+        let mut decls = vec![("rdf".to_string(), rdf::PREFIX.to_string())];
+        decls.extend(
+            config
+                .prefixes()
+                .iter()
+                .filter(|(p, _)| p != "rdf" && is_ncname(p) && !is_reserved_prefix(p))
+                .cloned(),
+        );
+        Namespaces {
+            decls,
+            generated: 0,
+        }
+    }
+
+    /// The qualified name of `iri`, declaring a new namespace if required.
+    ///
+    /// Return `None` if `iri` can not be written as a qualified name.
+    fn qname(&mut self, iri: &str) -> Option<String> {
+        if let Some(local) = iri.strip_prefix(rdf::PREFIX) {
+            if RESERVED_RDF_NAMES.contains(&local) {
+                return None;
+            }
+        }
+        let declared = self
+            .decls
+            .iter()
+            .filter(|(_, ns)| iri.starts_with(ns.as_str()) && is_ncname(&iri[ns.len()..]))
+            .max_by_key(|(_, ns)| ns.len());
+        if let Some((prefix, ns)) = declared {
+            return Some(format!("{}:{}", prefix, &iri[ns.len()..]));
+        }
+        let (ns, local) = split_iri(iri)?;
+        let prefix = loop {
+            let prefix = format!("ns{}", self.generated);
+            self.generated += 1;
+            if self.decls.iter().all(|(p, _)| *p != prefix) {
+                break prefix;
+            }
+        };
+        let qname = format!("{}:{}", prefix, local);
+        self.decls.push((prefix, ns.to_string()));
+        Some(qname)
+    }
+}
+
+/// Split `iri` into a namespace and the longest suffix that is a valid `NCName`.
+fn split_iri(iri: &str) -> Option<(&str, &str)> {
+    let mut start = iri.len();
+    for (i, c) in iri.char_indices().rev() {
+        if !is_name_char(c) {
+            break;
+        }
+        start = i;
+    }
+    start += iri[start..].find(is_name_start_char)?;
+    if start == 0 {
+        None
+    } else {
+        Some((&iri[..start], &iri[start..]))
+    }
+}
+
+fn is_ncname(txt: &str) -> bool {
+    let mut chars = txt.chars();
+    match chars.next() {
+        Some(c) if is_name_start_char(c) => chars.all(is_name_char),
+        _ => false,
+    }
+}
+
+/// Whether `prefix` starts with `xml`, in any case, which is reserved by [Namespaces in XML].
+///
+/// [Namespaces in XML]: https://www.w3.org/TR/xml-names/#xmlReserved
+fn is_reserved_prefix(prefix: &str) -> bool {
+    prefix.len() >= 3 && prefix.as_bytes()[..3].eq_ignore_ascii_case(b"xml")
+}
+
+fn is_name_start_char(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || c.is_numeric() || c == '-' || c == '.' || c == '\u{B7}'
+}
+
 impl RdfXmlSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -100,7 +464,7 @@ impl RdfXmlSerializer<Vec<u8>> {
 
 impl Stringifier for RdfXmlSerializer<Vec<u8>> {
     fn as_utf8(&self) -> &[u8] {
-        &self.write()[..]
+        &self.write[..]
     }
 }
 
@@ -112,9 +476,9 @@ impl Stringifier for RdfXmlSerializer<Vec<u8>> {
 pub(crate) mod test {
     use super::*;
     use sophia_api::graph::isomorphic_graphs;
-    use sophia_api::ns::*;
     use sophia_term::literal::convert::AsLiteral;
     use sophia_term::*;
+    use test_case::test_case;
 
     #[test]
     fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
@@ -138,4 +502,165 @@ pub(crate) mod test {
         assert!(isomorphic_graphs(&g, &g2)?);
         Ok(())
     }
+
+    fn some_graph() -> Result<Vec<[StaticTerm; 3]>, Box<dyn std::error::Error>> {
+        let alice = StaticTerm::new_iri("http://example.org/alice")?;
+        let bob = StaticTerm::new_bnode("x")?;
+        let name = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/name")?;
+        let knows = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/knows")?;
+        let person = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/Person")?;
+        Ok(vec![
+            [alice, rdf::type_.into(), person],
+            [alice, name, StaticTerm::new_literal_lang("Alice", "en")?],
+            [alice, knows, bob],
+            [
+                alice,
+                rdf::type_.into(),
+                StaticTerm::new_iri("http://example.org/Agent")?,
+            ],
+            [bob, rdf::type_.into(), person],
+            [
+                bob,
+                name,
+                StaticTerm::new_literal_dt("<Bob & co>", xsd::token)?,
+            ],
+            [
+                bob,
+                StaticTerm::new_iri("http://example.org/ns#motto")?,
+                "\"hi\"".as_literal().into(),
+            ],
+        ])
+    }
+
+    #[test]
+    fn typed_nodes() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = RdfXmlConfig::default();
+        config
+            .set_typed_nodes(true)
+            .add_prefix("foaf", "http://xmlns.com/foaf/0.1/")
+            .common_mut()
+            .set_indent(2);
+        assert!(config.typed_nodes());
+        let s = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            s,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rdf:RDF
+  xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+  xmlns:foaf="http://xmlns.com/foaf/0.1/"
+  xmlns:ns0="http://example.org/ns#">
+  <foaf:Person rdf:about="http://example.org/alice">
+    <foaf:name xml:lang="en">Alice</foaf:name>
+    <foaf:knows rdf:nodeID="b0"/>
+    <rdf:type rdf:resource="http://example.org/Agent"/>
+  </foaf:Person>
+  <foaf:Person rdf:nodeID="b0">
+    <foaf:name rdf:datatype="http://www.w3.org/2001/XMLSchema#token">&lt;Bob &amp; co&gt;</foaf:name>
+    <ns0:motto>&quot;hi&quot;</ns0:motto>
+  </foaf:Person>
+</rdf:RDF>
+"#
+        );
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::xml::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn descriptions() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let s = RdfXmlSerializer::new_stringifier()
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(s.contains("    <rdf:Description rdf:about=\"http://example.org/alice\">\n"));
+        assert!(
+            s.contains("        <rdf:type rdf:resource=\"http://xmlns.com/foaf/0.1/Person\"/>\n")
+        );
+        assert!(s.contains("        <ns0:name xml:lang=\"en\">Alice</ns0:name>\n"));
+        assert!(!s.contains("foaf:"));
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::xml::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn type_only() -> Result<(), Box<dyn std::error::Error>> {
+        let g = vec![[
+            StaticTerm::new_iri("http://example.org/alice")?,
+            rdf::type_.into(),
+            StaticTerm::new_iri("http://example.org/Person")?,
+        ]];
+        let mut config = RdfXmlConfig::default();
+        config.set_typed_nodes(true);
+        let s = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(s.contains("    <ns0:Person rdf:about=\"http://example.org/alice\"/>\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn ascii() -> Result<(), Box<dyn std::error::Error>> {
+        let g = vec![[
+            StaticTerm::new_iri("http://example.org/caf\u{e9}")?,
+            StaticTerm::new_iri("http://example.org/name")?,
+            "caf\u{e9} \u{1F600}".as_literal().into(),
+        ]];
+        let mut config = RdfXmlConfig::default();
+        config.common_mut().set_ascii(true);
+        let s = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(s.is_ascii());
+        assert!(s.contains("rdf:about=\"http://example.org/caf&#xE9;\""));
+        assert!(s.contains(">caf&#xE9; &#x1F600;</ns0:name>"));
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::xml::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let g = some_graph()?;
+        let mut config = RdfXmlConfig::default();
+        config
+            .add_prefix("", "http://example.org/")
+            .add_prefix("xmlfoaf", "http://xmlns.com/foaf/0.1/")
+            .add_prefix("1ex", "http://example.org/ns#");
+        let s = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(!s.contains("xmlns:="));
+        assert!(!s.contains("xmlns:xmlfoaf="));
+        assert!(!s.contains("xmlns:1ex="));
+        assert!(s.contains("xmlns:ns0="));
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::xml::parse_str(&s).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_predicate() -> Result<(), Box<dyn std::error::Error>> {
+        let g = vec![[
+            StaticTerm::new_iri("http://example.org/alice")?,
+            StaticTerm::new_iri("http://example.org/123")?,
+            StaticTerm::new_iri("http://example.org/bob")?,
+        ]];
+        let mut stringifier = RdfXmlSerializer::new_stringifier();
+        assert!(stringifier.serialize_graph(&g).is_err());
+        Ok(())
+    }
+
+    #[test_case("http://example.org/name", Some(("http://example.org/", "name")) ; "slash")]
+    #[test_case("http://example.org/ns#a-b.c", Some(("http://example.org/ns#", "a-b.c")) ; "hash")]
+    #[test_case("http://example.org/1a", Some(("http://example.org/1", "a")) ; "leading digit")]
+    #[test_case("http://example.org/123", None ; "digits only")]
+    #[test_case("http://example.org/", None ; "empty local name")]
+    #[test_case("urn", None ; "empty namespace")]
+    fn split(iri: &str, expected: Option<(&str, &str)>) {
+        assert_eq!(split_iri(iri), expected);
+    }
 }
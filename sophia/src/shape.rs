@@ -0,0 +1,635 @@
+//! Describe the data expected by [SHACL] shapes.
+//!
+//! This module does not validate data against shapes;
+//! it reads the node shapes of a shapes graph into [`ShapeDescription`]s,
+//! listing the properties expected by each shape,
+//! with their name, datatype and cardinality.
+//! Such descriptions can be used to generate forms,
+//! or be exported as [JSON Schema] to drive validation on the client side
+//! (this requires the `json` feature).
+//!
+//! Only the constraints relevant to this purpose are considered
+//! (`sh:datatype`, `sh:class`, `sh:nodeKind`, `sh:node`, `sh:in`, `sh:minCount` and `sh:maxCount`),
+//! together with the non-validating properties `sh:name`, `sh:description` and `sh:order`.
+//! Property paths other than predicates and inverse predicates are not supported.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::shape::describe_shapes;
+//! # use sophia::triple::stream::TripleSource;
+//! let shapes: FastGraph = sophia::parser::turtle::parse_str(r#"
+//!     @prefix sh: <http://www.w3.org/ns/shacl#>.
+//!     @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+//!     @prefix ex: <http://example.org/>.
+//!
+//!     ex:PersonShape a sh:NodeShape;
+//!         sh:targetClass ex:Person;
+//!         sh:property [
+//!             sh:path ex:name; sh:name "name";
+//!             sh:datatype xsd:string; sh:minCount 1; sh:maxCount 1
+//!         ], [
+//!             sh:path ex:nick; sh:name "nicknames"; sh:datatype xsd:string
+//!         ].
+//! "#).collect_triples()?;
+//! let descriptions = describe_shapes(&shapes)?;
+//! let person = &descriptions[0];
+//! assert!(person.properties[0].is_required());
+//! assert!(person.properties[1].is_multivalued());
+//!
+//! # #[cfg(feature = "json")] {
+//! let schema = person.to_json_schema();
+//! assert_eq!(schema["required"][0], "name");
+//! assert_eq!(schema["properties"]["nicknames"]["type"], "array");
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [SHACL]: https://www.w3.org/TR/shacl/
+//! [JSON Schema]: https://json-schema.org/
+//! [`ShapeDescription`]: struct.ShapeDescription.html
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::description::{self, invalid, objects, text, DescriptionError};
+#[cfg(feature = "json")]
+use json::JsonValue;
+use sophia_api::graph::Graph;
+#[cfg(feature = "json")]
+use sophia_api::ns::xsd;
+use sophia_api::ns::{rdf, rdfs};
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The SHACL namespace.
+mod sh {
+    sophia_api::namespace!(
+        "http://www.w3.org/ns/shacl#",
+        NodeShape,
+        targetClass,
+        property,
+        path,
+        inversePath,
+        name,
+        description,
+        order,
+        datatype,
+        class,
+        nodeKind,
+        node,
+        minCount,
+        maxCount,
+        IRI;
+        in_, "in"
+    );
+}
+
+/// The description of a SHACL node shape.
+#[derive(Clone, Debug)]
+pub struct ShapeDescription {
+    /// The node shape.
+    pub id: BoxTerm,
+    /// The name of the shape (`sh:name` or `rdfs:label`), if any.
+    pub name: Option<String>,
+    /// The description of the shape (`sh:description` or `rdfs:comment`), if any.
+    pub description: Option<String>,
+    /// The classes targeted by the shape (`sh:targetClass`).
+    pub target_classes: Vec<BoxTerm>,
+    /// The properties expected by the shape,
+    /// sorted by `sh:order` (unordered properties last), then by key.
+    pub properties: Vec<PropertyDescription>,
+}
+
+impl ShapeDescription {
+    /// Export this description as a JSON Schema (draft 07),
+    /// describing a JSON object with one member per property.
+    ///
+    /// See [`PropertyDescription::to_json_schema`](struct.PropertyDescription.html#method.to_json_schema)
+    /// for the schema of each member.
+    #[cfg(feature = "json")]
+    pub fn to_json_schema(&self) -> JsonValue {
+        let mut schema = JsonValue::new_object();
+        schema["$schema"] = "http://json-schema.org/draft-07/schema#".into();
+        if self.id.kind() == TermKind::Iri {
+            schema["$id"] = self.id.value().to_string().into();
+        }
+        if let Some(name) = &self.name {
+            schema["title"] = name.as_str().into();
+        }
+        if let Some(description) = &self.description {
+            schema["description"] = description.as_str().into();
+        }
+        schema["type"] = "object".into();
+        let mut properties = JsonValue::new_object();
+        let mut required = vec![];
+        for property in &self.properties {
+            let key = property.key();
+            properties[key.as_str()] = property.to_json_schema();
+            if property.is_required() {
+                required.push(key);
+            }
+        }
+        schema["properties"] = properties;
+        if !required.is_empty() {
+            schema["required"] = required.into();
+        }
+        schema
+    }
+}
+
+/// A property path, as supported by [`PropertyDescription`](struct.PropertyDescription.html).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PropertyPath {
+    /// A predicate path, linking a node to the values of the predicate.
+    Predicate(BoxTerm),
+    /// An inverse path (`sh:inversePath`), linking a node to the subjects of the predicate.
+    Inverse(BoxTerm),
+}
+
+/// The description of a property expected by a node shape.
+#[derive(Clone, Debug)]
+pub struct PropertyDescription {
+    /// The path of the property (`sh:path`).
+    pub path: PropertyPath,
+    /// The name of the property (`sh:name`), if any.
+    pub name: Option<String>,
+    /// The description of the property (`sh:description`), if any.
+    pub description: Option<String>,
+    /// The datatype of the values (`sh:datatype`), if constrained.
+    pub datatype: Option<BoxTerm>,
+    /// The class of the values (`sh:class`), if constrained.
+    pub class: Option<BoxTerm>,
+    /// The node kind of the values (`sh:nodeKind`), if constrained.
+    pub node_kind: Option<BoxTerm>,
+    /// The shape that the values must conform to (`sh:node`), if any.
+    pub node: Option<BoxTerm>,
+    /// The allowed values (`sh:in`), if constrained.
+    pub values: Option<Vec<BoxTerm>>,
+    /// The minimum number of values (`sh:minCount`), 0 if unconstrained.
+    pub min_count: usize,
+    /// The maximum number of values (`sh:maxCount`), if constrained.
+    pub max_count: Option<usize>,
+    /// The relative order of the property in forms (`sh:order`), if any.
+    pub order: Option<f64>,
+}
+
+impl PropertyDescription {
+    /// The key identifying this property in generated forms or schemas.
+    ///
+    /// This is the name of the property if it has one,
+    /// otherwise the last segment of the predicate IRI
+    /// (prefixed with `^` for inverse paths).
+    pub fn key(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let (predicate, prefix) = match &self.path {
+            PropertyPath::Predicate(p) => (p, ""),
+            PropertyPath::Inverse(p) => (p, "^"),
+        };
+        let iri = predicate.value();
+        let local = match iri.rsplit(&['#', '/'][..]).next() {
+            Some(local) if !local.is_empty() => local,
+            _ => &*iri,
+        };
+        format!("{}{}", prefix, local)
+    }
+
+    /// Whether at least one value is required.
+    pub fn is_required(&self) -> bool {
+        self.min_count > 0
+    }
+
+    /// Whether several values are allowed.
+    pub fn is_multivalued(&self) -> bool {
+        self.max_count.map(|max| max > 1).unwrap_or(true)
+    }
+
+    /// Export this description as a JSON Schema (draft 07).
+    ///
+    /// Values are described according to their datatype
+    /// (e.g. `xsd:integer` becomes `"integer"`, `xsd:dateTime` becomes a `"date-time"` string),
+    /// and values that are expected to be IRIs become `"iri"` strings.
+    /// Multi-valued properties are described as arrays of such values.
+    #[cfg(feature = "json")]
+    pub fn to_json_schema(&self) -> JsonValue {
+        let mut value = JsonValue::new_object();
+        match &self.datatype {
+            Some(datatype) => {
+                let (typ, format) = json_type(&datatype.value());
+                value["type"] = typ.into();
+                if let Some(format) = format {
+                    value["format"] = format.into();
+                }
+            }
+            None if self.expects_iri() => {
+                value["type"] = "string".into();
+                value["format"] = "iri".into();
+            }
+            None => {}
+        }
+        if let Some(values) = &self.values {
+            let values: Vec<_> = values.iter().map(|v| v.value().to_string()).collect();
+            value["enum"] = values.into();
+        }
+        let mut schema = if self.is_multivalued() {
+            let mut array = JsonValue::new_object();
+            array["type"] = "array".into();
+            array["items"] = value;
+            if self.min_count > 0 {
+                array["minItems"] = self.min_count.into();
+            }
+            if let Some(max) = self.max_count {
+                array["maxItems"] = max.into();
+            }
+            array
+        } else {
+            value
+        };
+        if let Some(name) = &self.name {
+            schema["title"] = name.as_str().into();
+        }
+        if let Some(description) = &self.description {
+            schema["description"] = description.as_str().into();
+        }
+        schema
+    }
+
+    #[cfg(feature = "json")]
+    fn expects_iri(&self) -> bool {
+        self.class.is_some()
+            || self.node.is_some()
+            || self
+                .node_kind
+                .as_ref()
+                .map(|kind| term_eq(kind, &sh::IRI))
+                .unwrap_or(false)
+    }
+}
+
+/// The error raised when describing shapes.
+#[derive(Debug, Error)]
+pub enum ShapeError<E: 'static + Error> {
+    /// The shapes graph raised an error.
+    #[error("Error in the shapes graph: {0}")]
+    Graph(#[source] E),
+    /// A shape is not well-formed, or uses unsupported features.
+    #[error("Invalid shape {shape}: {message}")]
+    Invalid {
+        /// The offending shape.
+        shape: String,
+        /// The reason why it is invalid.
+        message: String,
+    },
+}
+
+type SResult<G, T> = Result<T, ShapeError<<G as Graph>::Error>>;
+
+impl<E: 'static + Error> From<DescriptionError<E>> for ShapeError<E> {
+    fn from(err: DescriptionError<E>) -> Self {
+        match err {
+            DescriptionError::Graph(err) => ShapeError::Graph(err),
+            DescriptionError::Invalid { resource, message } => ShapeError::Invalid {
+                shape: resource,
+                message,
+            },
+        }
+    }
+}
+
+/// Describe all the node shapes of the given shapes graph.
+///
+/// Node shapes are the instances of `sh:NodeShape`
+/// and the subjects of `sh:targetClass`.
+/// They are sorted by IRI.
+pub fn describe_shapes<G: Graph>(shapes: &G) -> SResult<G, Vec<ShapeDescription>> {
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    let typed = shapes.triples_with_po(&rdf::type_, &sh::NodeShape);
+    let targeting = shapes.triples_with_p(&sh::targetClass);
+    for t in typed.chain(targeting) {
+        let id = BoxTerm::copy(t.map_err(ShapeError::Graph)?.s());
+        if seen.insert(id.clone()) {
+            ids.push(id);
+        }
+    }
+    ids.sort_by(|a, b| a.value().cmp(&b.value()));
+    ids.iter().map(|id| describe_shape(shapes, id)).collect()
+}
+
+/// Describe the node shape `shape` of the given shapes graph.
+pub fn describe_shape<G, T>(shapes: &G, shape: &T) -> SResult<G, ShapeDescription>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    let id = BoxTerm::copy(shape);
+    let mut name = text(shapes, &id, &sh::name)?;
+    if name.is_none() {
+        name = text(shapes, &id, &rdfs::label)?;
+    }
+    let mut description = text(shapes, &id, &sh::description)?;
+    if description.is_none() {
+        description = text(shapes, &id, &rdfs::comment)?;
+    }
+    let target_classes = objects(shapes, &id, &sh::targetClass)?;
+    let mut properties = objects(shapes, &id, &sh::property)?
+        .iter()
+        .map(|property| describe_property(shapes, &id, property))
+        .collect::<Result<Vec<_>, _>>()?;
+    properties.sort_by(|a, b| {
+        match (a.order, b.order) {
+            (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a.key().cmp(&b.key()))
+    });
+    Ok(ShapeDescription {
+        id,
+        name,
+        description,
+        target_classes,
+        properties,
+    })
+}
+
+/// Describe the property shape `property` of the node shape `shape`.
+fn describe_property<G: Graph>(
+    g: &G,
+    shape: &BoxTerm,
+    property: &BoxTerm,
+) -> SResult<G, PropertyDescription> {
+    let path = match single(g, shape, property, &sh::path)? {
+        None => return Err(invalid(shape, "property shape without sh:path").into()),
+        Some(p) if p.kind() == TermKind::Iri => PropertyPath::Predicate(p),
+        Some(p) => match single(g, shape, &p, &sh::inversePath)? {
+            Some(i) if i.kind() == TermKind::Iri => PropertyPath::Inverse(i),
+            _ => return Err(invalid(shape, "unsupported property path").into()),
+        },
+    };
+    let values = match single(g, shape, property, &sh::in_)? {
+        Some(head) => Some(list(g, shape, head)?),
+        None => None,
+    };
+    let order = match single(g, shape, property, &sh::order)? {
+        Some(order) => Some(
+            order
+                .value()
+                .parse()
+                .map_err(|_| invalid(shape, format!("invalid sh:order {}", order)))?,
+        ),
+        None => None,
+    };
+    Ok(PropertyDescription {
+        path,
+        name: text(g, property, &sh::name)?,
+        description: text(g, property, &sh::description)?,
+        datatype: single(g, shape, property, &sh::datatype)?,
+        class: single(g, shape, property, &sh::class)?,
+        node_kind: single(g, shape, property, &sh::nodeKind)?,
+        node: single(g, shape, property, &sh::node)?,
+        values,
+        min_count: count(g, shape, property, &sh::minCount)?.unwrap_or(0),
+        max_count: count(g, shape, property, &sh::maxCount)?,
+        order,
+    })
+}
+
+/// The JSON type (and format, if any) corresponding to the given datatype.
+#[cfg(feature = "json")]
+fn json_type(datatype: &str) -> (&'static str, Option<&'static str>) {
+    match datatype.strip_prefix(xsd::PREFIX) {
+        Some("boolean") => ("boolean", None),
+        Some("integer")
+        | Some("int")
+        | Some("long")
+        | Some("short")
+        | Some("byte")
+        | Some("nonNegativeInteger")
+        | Some("positiveInteger")
+        | Some("negativeInteger")
+        | Some("nonPositiveInteger")
+        | Some("unsignedLong")
+        | Some("unsignedInt")
+        | Some("unsignedShort")
+        | Some("unsignedByte") => ("integer", None),
+        Some("decimal") | Some("double") | Some("float") => ("number", None),
+        Some("dateTime") | Some("dateTimeStamp") => ("string", Some("date-time")),
+        Some("date") => ("string", Some("date")),
+        Some("time") => ("string", Some("time")),
+        Some("anyURI") => ("string", Some("uri")),
+        _ => ("string", None),
+    }
+}
+
+/// The value of `p` for `s`, which must be unique if present.
+///
+/// Errors are reported about `shape`, rather than about `s`.
+fn single<G, T>(g: &G, shape: &BoxTerm, s: &BoxTerm, p: &T) -> SResult<G, Option<BoxTerm>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    description::single(g, s, p).map_err(|err| match err {
+        DescriptionError::Invalid { message, .. } => invalid(shape, message).into(),
+        err => err.into(),
+    })
+}
+
+/// The integer value of `p` for `s`, if any.
+fn count<G, T>(g: &G, shape: &BoxTerm, s: &BoxTerm, p: &T) -> SResult<G, Option<usize>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    match single(g, shape, s, p)? {
+        Some(n) if n.kind() == TermKind::Literal => match n.value().parse() {
+            Ok(n) => Ok(Some(n)),
+            Err(_) => Err(invalid(shape, format!("invalid count {}", n)).into()),
+        },
+        Some(n) => Err(invalid(shape, format!("invalid count {}", n)).into()),
+        None => Ok(None),
+    }
+}
+
+/// The items of the RDF list starting at `head`.
+fn list<G: Graph>(g: &G, shape: &BoxTerm, head: BoxTerm) -> SResult<G, Vec<BoxTerm>> {
+    let mut items = vec![];
+    let mut visited = HashSet::new();
+    let mut node = head;
+    while !term_eq(&node, &rdf::nil) {
+        if !visited.insert(node.clone()) {
+            return Err(invalid(shape, "cyclic list").into());
+        }
+        let first = single(g, shape, &node, &rdf::first)?;
+        let rest = single(g, shape, &node, &rdf::rest)?;
+        match (first, rest) {
+            (Some(first), Some(rest)) => {
+                items.push(first);
+                node = rest;
+            }
+            _ => return Err(invalid(shape, "malformed list").into()),
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::ns::xsd;
+    use sophia_api::triple::stream::TripleSource;
+
+    const SHAPES: &str = r#"
+        @prefix sh: <http://www.w3.org/ns/shacl#>.
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+        @prefix ex: <http://example.org/>.
+
+        ex:PersonShape a sh:NodeShape;
+            rdfs:label "Person"@en, "Personne"@fr;
+            sh:targetClass ex:Person;
+            sh:property [
+                sh:path ex:name; sh:name "name"; sh:order 1;
+                sh:datatype xsd:string; sh:minCount 1; sh:maxCount 1
+            ], [
+                sh:path ex:birthDate; sh:order 2;
+                sh:datatype xsd:date; sh:maxCount 1
+            ], [
+                sh:path ex:knows; sh:class ex:Person
+            ], [
+                sh:path [ sh:inversePath ex:member ]; sh:node ex:GroupShape
+            ], [
+                sh:path ex:gender; sh:in ("female" "male" "other"); sh:maxCount 1
+            ], [
+                sh:path ex:age; sh:datatype xsd:nonNegativeInteger;
+                sh:minCount 2; sh:maxCount 3
+            ].
+
+        ex:GroupShape sh:targetClass ex:Group;
+            sh:description "A group of people";
+            sh:property [ sh:path ex:member; sh:nodeKind sh:IRI ].
+    "#;
+
+    fn describe(ttl: &str) -> Result<Vec<ShapeDescription>, ShapeError<std::convert::Infallible>> {
+        let g: FastGraph = crate::parser::turtle::parse_str(ttl)
+            .collect_triples()
+            .unwrap();
+        describe_shapes(&g)
+    }
+
+    #[test]
+    fn descriptions() -> Result<(), Box<dyn std::error::Error>> {
+        let shapes = describe(SHAPES)?;
+        assert_eq!(shapes.len(), 2);
+        let group = &shapes[0];
+        assert_eq!(group.id.value(), "http://example.org/GroupShape");
+        assert_eq!(group.name, None);
+        assert_eq!(group.description.as_deref(), Some("A group of people"));
+        assert_eq!(group.properties.len(), 1);
+
+        let person = &shapes[1];
+        assert_eq!(person.name.as_deref(), Some("Person"));
+        assert_eq!(person.target_classes.len(), 1);
+        assert_eq!(
+            person.target_classes[0].value(),
+            "http://example.org/Person"
+        );
+        let keys: Vec<_> = person.properties.iter().map(|p| p.key()).collect();
+        assert_eq!(
+            keys,
+            ["name", "birthDate", "^member", "age", "gender", "knows"]
+        );
+
+        let name = &person.properties[0];
+        assert!(name.is_required());
+        assert!(!name.is_multivalued());
+        assert_eq!(name.datatype.as_ref().unwrap().value(), xsd::string.value());
+        let member = &person.properties[2];
+        assert!(
+            matches!(&member.path, PropertyPath::Inverse(p) if p.value() == "http://example.org/member")
+        );
+        assert_eq!(
+            member.node.as_ref().unwrap().value(),
+            "http://example.org/GroupShape"
+        );
+        let gender = &person.properties[4];
+        let values: Vec<_> = gender
+            .values
+            .iter()
+            .flatten()
+            .map(|v| v.value().to_string())
+            .collect();
+        assert_eq!(values, ["female", "male", "other"]);
+        let knows = &person.properties[5];
+        assert!(!knows.is_required());
+        assert!(knows.is_multivalued());
+        assert_eq!(knows.max_count, None);
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_schema() -> Result<(), Box<dyn std::error::Error>> {
+        let shapes = describe(SHAPES)?;
+        let schema = shapes[1].to_json_schema();
+        let expected = json::parse(
+            r#"{
+                "$schema": "http://json-schema.org/draft-07/schema#",
+                "$id": "http://example.org/PersonShape",
+                "title": "Person",
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "title": "name"},
+                    "birthDate": {"type": "string", "format": "date"},
+                    "^member": {"type": "array", "items": {"type": "string", "format": "iri"}},
+                    "age": {"type": "array", "items": {"type": "integer"}, "minItems": 2, "maxItems": 3},
+                    "gender": {"enum": ["female", "male", "other"]},
+                    "knows": {"type": "array", "items": {"type": "string", "format": "iri"}}
+                },
+                "required": ["name", "age"]
+            }"#,
+        )?;
+        assert_eq!(schema, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_shapes() {
+        let prefixes =
+            "@prefix sh: <http://www.w3.org/ns/shacl#>. @prefix ex: <http://example.org/>.";
+        for (ttl, message) in &[
+            (
+                "ex:S a sh:NodeShape; sh:property [ sh:name \"x\" ].",
+                "property shape without sh:path",
+            ),
+            (
+                "ex:S a sh:NodeShape; sh:property [ sh:path (ex:p ex:q) ].",
+                "unsupported property path",
+            ),
+            (
+                "ex:S a sh:NodeShape; sh:property [ sh:path ex:p; sh:minCount \"many\" ].",
+                "invalid count \"many\"",
+            ),
+            (
+                "ex:S a sh:NodeShape; sh:property [ sh:path ex:p; sh:maxCount 1, 2 ].",
+                "several values for <http://www.w3.org/ns/shacl#maxCount>",
+            ),
+        ] {
+            match describe(&format!("{} {}", prefixes, ttl)) {
+                Err(ShapeError::Invalid { shape, message: m }) => {
+                    assert_eq!(shape, "<http://example.org/S>");
+                    assert_eq!(&m, message);
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
+}
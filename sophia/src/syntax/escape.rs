@@ -0,0 +1,499 @@
+//! Escaping and unescaping of strings, IRIs and local names,
+//! as specified by the [N-Triples] and [Turtle] concrete syntaxes.
+//!
+//! These functions are used by the serializers of this crate,
+//! and are exposed for those who write their own emitters or tokenizers.
+//!
+//! * [`write_escaped_string`] and [`unescape_string`] handle the content of quoted literals
+//!   (`ECHAR` and `UCHAR` escape sequences);
+//! * [`write_canonical_string`] escapes the content of quoted literals in canonical N-Triples;
+//! * [`write_escaped_iri`] and [`unescape_iri`] handle the content of `<...>` IRI references
+//!   (`UCHAR` escape sequences);
+//! * [`percent_encode`] and [`percent_decode`] convert characters that are not allowed in IRIs
+//!   to and from their percent-encoded form (which, unlike the escapes above, changes the IRI);
+//! * [`escape_local`] and [`unescape_local`] handle the local part of prefixed names
+//!   (`PN_LOCAL_ESC` escape sequences).
+//!
+//! [N-Triples]: https://www.w3.org/TR/n-triples/
+//! [Turtle]: https://www.w3.org/TR/turtle/
+//! [`write_escaped_string`]: fn.write_escaped_string.html
+//! [`unescape_string`]: fn.unescape_string.html
+//! [`write_canonical_string`]: fn.write_canonical_string.html
+//! [`write_escaped_iri`]: fn.write_escaped_iri.html
+//! [`unescape_iri`]: fn.unescape_iri.html
+//! [`percent_encode`]: fn.percent_encode.html
+//! [`percent_decode`]: fn.percent_decode.html
+//! [`escape_local`]: fn.escape_local.html
+//! [`unescape_local`]: fn.unescape_local.html
+
+use std::io;
+use thiserror::Error;
+
+/// Errors raised when unescaping malformed input.
+///
+/// Positions are byte offsets in the input string.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum UnescapeError {
+    /// A backslash is not followed by a valid escape sequence.
+    #[error("Invalid escape sequence at {0}")]
+    InvalidEscape(usize),
+    /// A `\u` or `\U` escape sequence does not denote a valid character.
+    #[error("Invalid code point at {0}")]
+    InvalidCodePoint(usize),
+    /// A `%` is not followed by two hexadecimal digits,
+    /// or the decoded bytes are not valid UTF-8.
+    #[error("Invalid percent-encoding at {0}")]
+    InvalidPercentEncoding(usize),
+}
+
+/// Write `txt` as the content of a string delimited by `quote`,
+/// escaping the quote itself, backslashes and line breaks,
+/// as well as all non-ASCII characters if `ascii` is true.
+///
+/// `quote` is expected to be `'"'` or `'\''`.
+pub fn write_escaped_string<W: io::Write>(
+    w: &mut W,
+    txt: &str,
+    quote: char,
+    ascii: bool,
+) -> io::Result<()> {
+    // all characters to escape (but non-ASCII ones) are single bytes,
+    // so scanning bytes is both correct and faster than decoding chars
+    let bytes = txt.as_bytes();
+    let mut start = 0;
+    for (pos, b) in bytes.iter().enumerate() {
+        let escaped: &[u8] = match b {
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\\' => b"\\\\",
+            b'"' if quote == '"' => b"\\\"",
+            b'\'' if quote == '\'' => b"\\'",
+            0xC0..=0xFF if ascii => b"", // first byte of a non-ASCII char, escaped below
+            _ => continue,
+        };
+        w.write_all(&bytes[start..pos])?;
+        if escaped.is_empty() {
+            start = pos + write_uchar_at(w, txt, pos)?;
+        } else {
+            w.write_all(escaped)?;
+            start = pos + 1;
+        }
+    }
+    w.write_all(&bytes[start..])
+}
+
+/// Write `txt` as the content of a double-quoted string,
+/// escaped as required by [canonical N-Triples]:
+/// `\b`, `\t`, `\n`, `\f`, `\r`, `\"` and `\\` for the corresponding characters,
+/// `\uXXXX` for the other control characters,
+/// and all other characters unescaped.
+///
+/// [canonical N-Triples]: https://www.w3.org/TR/rdf12-n-triples/#canonical-ntriples
+pub fn write_canonical_string<W: io::Write>(w: &mut W, txt: &str) -> io::Result<()> {
+    let bytes = txt.as_bytes();
+    let mut start = 0;
+    for (pos, b) in bytes.iter().enumerate() {
+        let escaped: &[u8] = match b {
+            0x08 => b"\\b",
+            b'\t' => b"\\t",
+            b'\n' => b"\\n",
+            0x0C => b"\\f",
+            b'\r' => b"\\r",
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            0x00..=0x1F | 0x7F => b"", // escaped below as \uXXXX
+            _ => continue,
+        };
+        w.write_all(&bytes[start..pos])?;
+        if escaped.is_empty() {
+            write_uchar(w, *b as char)?;
+        } else {
+            w.write_all(escaped)?;
+        }
+        start = pos + 1;
+    }
+    w.write_all(&bytes[start..])
+}
+
+/// Return `txt` escaped as the content of a string delimited by `quote`.
+///
+/// See [`write_escaped_string`](fn.write_escaped_string.html).
+pub fn escape_string(txt: &str, quote: char, ascii: bool) -> String {
+    let mut buffer = Vec::with_capacity(txt.len());
+    write_escaped_string(&mut buffer, txt, quote, ascii).unwrap(); // writing into a Vec can not fail
+    String::from_utf8(buffer).unwrap()
+}
+
+/// Write `txt` as (part of) an IRI reference, escaping all non-ASCII characters if `ascii` is true.
+///
+/// NB: `txt` is expected to be a valid IRI;
+/// characters that are not allowed in IRIs (such as spaces)
+/// should be [percent-encoded](fn.percent_encode.html) beforehand.
+pub fn write_escaped_iri<W: io::Write>(w: &mut W, txt: &str, ascii: bool) -> io::Result<()> {
+    if !ascii {
+        return w.write_all(txt.as_bytes());
+    }
+    let bytes = txt.as_bytes();
+    let mut start = 0;
+    for (pos, b) in bytes.iter().enumerate() {
+        if *b >= 0xC0 {
+            // first byte of a non-ASCII char
+            w.write_all(&bytes[start..pos])?;
+            start = pos + write_uchar_at(w, txt, pos)?;
+        }
+    }
+    w.write_all(&bytes[start..])
+}
+
+/// Return `txt` escaped as (part of) an IRI reference.
+///
+/// See [`write_escaped_iri`](fn.write_escaped_iri.html).
+pub fn escape_iri(txt: &str, ascii: bool) -> String {
+    let mut buffer = Vec::with_capacity(txt.len());
+    write_escaped_iri(&mut buffer, txt, ascii).unwrap(); // writing into a Vec can not fail
+    String::from_utf8(buffer).unwrap()
+}
+
+/// Percent-encode the characters of `txt` that are not allowed in IRIs
+/// (control characters, spaces, and any of ``<>"{}|\^` ``),
+/// as well as all non-ASCII characters if `ascii` is true
+/// (which converts an IRI into a URI).
+///
+/// Existing `%` characters are left untouched,
+/// so that already encoded sequences are not encoded twice.
+pub fn percent_encode(txt: &str, ascii: bool) -> String {
+    let mut ret = String::with_capacity(txt.len());
+    let mut buffer = [0; 4];
+    for chr in txt.chars() {
+        let encode = match chr {
+            '\u{0}'..='\u{20}' | '\u{7F}' => true,
+            '<' | '>' | '"' | '{' | '}' | '|' | '\\' | '^' | '`' => true,
+            c => ascii && !c.is_ascii(),
+        };
+        if encode {
+            for b in chr.encode_utf8(&mut buffer).as_bytes() {
+                ret.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            ret.push(chr);
+        }
+    }
+    ret
+}
+
+/// Decode all percent-encoded sequences in `txt`.
+///
+/// Fails if a `%` is not followed by two hexadecimal digits,
+/// or if the decoded bytes are not valid UTF-8.
+pub fn percent_decode(txt: &str) -> Result<String, UnescapeError> {
+    let bytes = txt.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = txt
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(UnescapeError::InvalidPercentEncoding(i))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|e| {
+        // find the position of the faulty sequence in the original text
+        let mut valid = e.utf8_error().valid_up_to();
+        let mut pos = 0;
+        while valid > 0 {
+            pos += if bytes[pos] == b'%' { 3 } else { 1 };
+            valid -= 1;
+        }
+        UnescapeError::InvalidPercentEncoding(pos)
+    })
+}
+
+/// Replace `ECHAR` (e.g. `\n`) and `UCHAR` (e.g. `\u00E9`) escape sequences in `txt`
+/// by the characters they represent,
+/// as in the content of a quoted literal.
+pub fn unescape_string(txt: &str) -> Result<String, UnescapeError> {
+    unescape(txt, |c| {
+        Some(match c {
+            't' => '\t',
+            'b' => '\u{8}',
+            'n' => '\n',
+            'r' => '\r',
+            'f' => '\u{C}',
+            '"' | '\'' | '\\' => c,
+            _ => return None,
+        })
+    })
+}
+
+/// Replace `UCHAR` escape sequences (e.g. `\u00E9`) in `txt`
+/// by the characters they represent,
+/// as in the content of an IRI reference.
+pub fn unescape_iri(txt: &str) -> Result<String, UnescapeError> {
+    unescape(txt, |_| None)
+}
+
+/// Escape `txt` so that it can be used as the local part of a prefixed name
+/// (`PN_LOCAL` in Turtle and SPARQL).
+///
+/// Characters that are not allowed at their position
+/// are escaped with a backslash where the grammar allows it
+/// (e.g. `a.` becomes `a\.`);
+/// percent-encoded sequences are kept as is.
+///
+/// Returns `None` if some characters (e.g. spaces) can not appear in a local name,
+/// even escaped; the IRI must then be written in full.
+pub fn escape_local(txt: &str) -> Option<String> {
+    let mut ret = String::with_capacity(txt.len());
+    let mut chars = txt.char_indices().peekable();
+    while let Some((pos, chr)) = chars.next() {
+        let last = chars.peek().is_none();
+        let allowed = match chr {
+            ':' => true,
+            '.' => pos > 0 && !last,
+            '%' => {
+                let hex = txt.get(pos + 1..pos + 3);
+                if hex.is_some_and(|h| h.bytes().all(|b| b.is_ascii_hexdigit())) {
+                    ret.push_str(&txt[pos..pos + 3]);
+                    chars.next();
+                    chars.next();
+                    continue;
+                }
+                false
+            }
+            c if pos == 0 => is_pn_chars_u(c) || c.is_ascii_digit(),
+            c => is_pn_chars(c),
+        };
+        if allowed {
+            ret.push(chr);
+        } else if is_pn_local_esc(chr) {
+            ret.push('\\');
+            ret.push(chr);
+        } else {
+            return None;
+        }
+    }
+    Some(ret)
+}
+
+/// Replace `PN_LOCAL_ESC` escape sequences (e.g. `\.`) in `txt`
+/// by the characters they represent,
+/// as in the local part of a prefixed name.
+///
+/// NB: percent-encoded sequences are part of the IRI, and are therefore kept as is.
+pub fn unescape_local(txt: &str) -> Result<String, UnescapeError> {
+    let mut ret = String::with_capacity(txt.len());
+    let mut chars = txt.char_indices();
+    while let Some((pos, chr)) = chars.next() {
+        if chr == '\\' {
+            match chars.next() {
+                Some((_, c)) if is_pn_local_esc(c) => ret.push(c),
+                _ => return Err(UnescapeError::InvalidEscape(pos)),
+            }
+        } else {
+            ret.push(chr);
+        }
+    }
+    Ok(ret)
+}
+
+fn write_uchar<W: io::Write>(w: &mut W, chr: char) -> io::Result<()> {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let code = chr as u32;
+    let mut buffer = *b"\\U00000000";
+    let digits = if code <= 0xFFFF {
+        buffer[1] = b'u';
+        4
+    } else {
+        8
+    };
+    for i in 0..digits {
+        buffer[1 + digits - i] = HEX[(code >> (4 * i)) as usize & 0xF];
+    }
+    w.write_all(&buffer[..2 + digits])
+}
+
+/// Write the char starting at byte `pos` of `txt` as a `UCHAR`, and return its length in bytes.
+fn write_uchar_at<W: io::Write>(w: &mut W, txt: &str, pos: usize) -> io::Result<usize> {
+    let chr = txt[pos..].chars().next().unwrap();
+    write_uchar(w, chr)?;
+    Ok(chr.len_utf8())
+}
+
+/// Replace `UCHAR` escape sequences in `txt`,
+/// as well as the other escape sequences recognized by `echar`.
+fn unescape<F>(txt: &str, echar: F) -> Result<String, UnescapeError>
+where
+    F: Fn(char) -> Option<char>,
+{
+    let mut ret = String::with_capacity(txt.len());
+    let mut chars = txt.char_indices();
+    while let Some((pos, chr)) = chars.next() {
+        if chr != '\\' {
+            ret.push(chr);
+            continue;
+        }
+        let len = match chars.next() {
+            Some((_, 'u')) => 4,
+            Some((_, 'U')) => 8,
+            Some((_, c)) => {
+                ret.push(echar(c).ok_or(UnescapeError::InvalidEscape(pos))?);
+                continue;
+            }
+            None => return Err(UnescapeError::InvalidEscape(pos)),
+        };
+        let hex = txt
+            .get(pos + 2..pos + 2 + len)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or(UnescapeError::InvalidEscape(pos))?;
+        let code = u32::from_str_radix(hex, 16).unwrap();
+        ret.push(std::char::from_u32(code).ok_or(UnescapeError::InvalidCodePoint(pos))?);
+        for _ in 0..len {
+            chars.next();
+        }
+    }
+    Ok(ret)
+}
+
+/// `PN_CHARS_BASE | '_'`
+pub(crate) fn is_pn_chars_u(c: char) -> bool {
+    matches!(
+        c,
+        'A'..='Z'
+        | 'a'..='z'
+        | '_'
+        | '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+/// `PN_CHARS_U | '-' | [0-9] | #x00B7 | [#x0300-#x036F] | [#x203F-#x2040]`
+pub(crate) fn is_pn_chars(c: char) -> bool {
+    matches!(c, '-' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+        || is_pn_chars_u(c)
+}
+
+/// Characters that can be escaped with a backslash in local names.
+fn is_pn_local_esc(c: char) -> bool {
+    "_~.-!$&'()*+,;=/?#@%".contains(c)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("abc", '"', false, "abc" ; "plain")]
+    #[test_case("a\"b'c", '"', false, "a\\\"b'c" ; "double quote")]
+    #[test_case("a\"b'c", '\'', false, "a\"b\\'c" ; "single quote")]
+    #[test_case("a\\b\nc\rd", '"', false, "a\\\\b\\nc\\rd" ; "special chars")]
+    #[test_case("é😀", '"', false, "é😀" ; "unicode")]
+    #[test_case("é😀", '"', true, "\\u00E9\\U0001F600" ; "ascii")]
+    #[test_case("aé\"b😀\nc", '"', true, "a\\u00E9\\\"b\\U0001F600\\nc" ; "ascii mixed")]
+    fn string(txt: &str, quote: char, ascii: bool, expected: &str) {
+        let escaped = escape_string(txt, quote, ascii);
+        assert_eq!(escaped, expected);
+        assert_eq!(unescape_string(&escaped).unwrap(), txt);
+    }
+
+    #[test_case("abc", "abc" ; "plain")]
+    #[test_case("a\"b\\c", "a\\\"b\\\\c" ; "quote and backslash")]
+    #[test_case("\u{8}\t\n\u{c}\r", "\\b\\t\\n\\f\\r" ; "echar")]
+    #[test_case("a\u{0}b\u{1f}c\u{7f}", "a\\u0000b\\u001Fc\\u007F" ; "control")]
+    #[test_case("é😀", "é😀" ; "unicode")]
+    fn canonical_string(txt: &str, expected: &str) {
+        let mut buffer = Vec::new();
+        write_canonical_string(&mut buffer, txt).unwrap();
+        assert_eq!(std::str::from_utf8(&buffer).unwrap(), expected);
+    }
+
+    #[test]
+    fn unescape_string_echar() {
+        assert_eq!(
+            unescape_string(r#"\t\b\n\r\f\"\'\\"#).unwrap(),
+            "\t\u{8}\n\r\u{C}\"'\\"
+        );
+        assert_eq!(unescape_string(r"A\U0001F600").unwrap(), "A😀");
+    }
+
+    #[test_case(r"a\x" => UnescapeError::InvalidEscape(1) ; "unknown escape")]
+    #[test_case(r"ab\" => UnescapeError::InvalidEscape(2) ; "trailing backslash")]
+    #[test_case(r"\u00" => UnescapeError::InvalidEscape(0) ; "truncated uchar")]
+    #[test_case(r"\u00G0" => UnescapeError::InvalidEscape(0) ; "non hex uchar")]
+    #[test_case(r"\uD800" => UnescapeError::InvalidCodePoint(0) ; "surrogate")]
+    #[test_case(r"\U00110000" => UnescapeError::InvalidCodePoint(0) ; "out of range")]
+    fn unescape_string_err(txt: &str) -> UnescapeError {
+        unescape_string(txt).unwrap_err()
+    }
+
+    #[test]
+    fn iri() {
+        let iri = "http://example.org/é";
+        assert_eq!(escape_iri(iri, false), iri);
+        assert_eq!(escape_iri(iri, true), "http://example.org/\\u00E9");
+        assert_eq!(unescape_iri("http://example.org/\\u00E9").unwrap(), iri);
+        assert_eq!(
+            unescape_iri("http://example.org/\\n"),
+            Err(UnescapeError::InvalidEscape(19))
+        );
+    }
+
+    #[test_case("http://example.org/a b", false, "http://example.org/a%20b" ; "space")]
+    #[test_case("http://example.org/<{|}>", false, "http://example.org/%3C%7B%7C%7D%3E" ; "delimiters")]
+    #[test_case("http://example.org/é", false, "http://example.org/é" ; "iri")]
+    #[test_case("http://example.org/é", true, "http://example.org/%C3%A9" ; "uri")]
+    #[test_case("http://example.org/%20", true, "http://example.org/%20" ; "already encoded")]
+    fn percent(txt: &str, ascii: bool, expected: &str) {
+        assert_eq!(percent_encode(txt, ascii), expected);
+        assert_eq!(percent_decode(expected).unwrap(), txt.replace("%20", " "));
+    }
+
+    #[test_case("%" => UnescapeError::InvalidPercentEncoding(0) ; "truncated")]
+    #[test_case("a%2G" => UnescapeError::InvalidPercentEncoding(1) ; "non hex")]
+    #[test_case("ab%C3" => UnescapeError::InvalidPercentEncoding(2) ; "invalid utf8")]
+    fn percent_decode_err(txt: &str) -> UnescapeError {
+        percent_decode(txt).unwrap_err()
+    }
+
+    #[test_case("", Some("") ; "empty")]
+    #[test_case("abc", Some("abc") ; "plain")]
+    #[test_case("a.b:c", Some("a.b:c") ; "inner dot and colon")]
+    #[test_case("123", Some("123") ; "leading digit")]
+    #[test_case(".a.", Some("\\.a\\.") ; "leading and trailing dots")]
+    #[test_case("-a-", Some("\\-a-") ; "leading dash")]
+    #[test_case("a/b?c#d", Some("a\\/b\\?c\\#d") ; "punctuation")]
+    #[test_case("a%20b", Some("a%20b") ; "percent-encoded")]
+    #[test_case("100%", Some("100\\%") ; "percent")]
+    #[test_case("é·", Some("é·") ; "unicode")]
+    #[test_case("a b", None ; "space")]
+    #[test_case("a\"b", None ; "quote")]
+    fn local(txt: &str, expected: Option<&str>) {
+        let escaped = escape_local(txt);
+        assert_eq!(escaped.as_deref(), expected);
+        if let Some(escaped) = escaped {
+            assert_eq!(unescape_local(&escaped).unwrap(), txt);
+        }
+    }
+
+    #[test]
+    fn unescape_local_err() {
+        assert_eq!(unescape_local(r"a\b"), Err(UnescapeError::InvalidEscape(1)));
+        assert_eq!(unescape_local(r"a\"), Err(UnescapeError::InvalidEscape(1)));
+    }
+}
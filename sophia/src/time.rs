@@ -0,0 +1,546 @@
+//! Build and query temporal descriptions using [OWL-Time].
+//!
+//! [`Instant`]s and [`Interval`]s are built from [chrono] date-times,
+//! can be written into any [`MutableGraph`], and read back from any [`Graph`].
+//! The [Allen relation] between two intervals can be computed with [`Interval::relation_to`],
+//! and [`related_intervals`] retrieves the intervals of a graph
+//! that are in a given relation with a given interval.
+//!
+//! Instants are described by their `time:inXSDDateTimeStamp` or `time:inXSDDateTime`;
+//! other descriptions (e.g. `time:inDateTime`) are not supported.
+//! Date-times without a timezone are assumed to be in UTC.
+//!
+//! This module requires the `time` feature.
+//!
+//! # Example
+//! ```
+//! # use sophia::time::*;
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::term::BoxTerm;
+//! # use chrono::DateTime;
+//! let iri = |suffix: &str| BoxTerm::new_iri(format!("http://example.org/{}", suffix));
+//! let date = DateTime::parse_from_rfc3339;
+//! let war = Interval::new(
+//!     iri("war")?,
+//!     Instant::new(iri("war-start")?, date("1914-07-28T00:00:00Z")?),
+//!     Instant::new(iri("war-end")?, date("1918-11-11T11:00:00+01:00")?),
+//! );
+//! let treaty = Interval::new(
+//!     iri("treaty")?,
+//!     Instant::new(iri("treaty-start")?, date("1919-01-18T00:00:00+01:00")?),
+//!     Instant::new(iri("treaty-end")?, date("1919-06-28T00:00:00+01:00")?),
+//! );
+//! assert_eq!(war.relation_to(&treaty), AllenRelation::Before);
+//!
+//! let mut g = FastGraph::new();
+//! war.add_to(&mut g)?;
+//! treaty.add_to(&mut g)?;
+//! let after_war = related_intervals(&g, &war, AllenRelation::Before)?;
+//! assert_eq!(after_war, vec![treaty]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [OWL-Time]: https://www.w3.org/TR/owl-time/
+//! [chrono]: https://docs.rs/chrono/
+//! [Allen relation]: https://www.w3.org/TR/owl-time/#time:TemporalRelations
+//! [`Instant`]: struct.Instant.html
+//! [`Interval`]: struct.Interval.html
+//! [`Interval::relation_to`]: struct.Interval.html#method.relation_to
+//! [`related_intervals`]: fn.related_intervals.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::description::{invalid, single, DescriptionError};
+use chrono::{
+    DateTime, Duration, FixedOffset, NaiveDateTime, Offset, SecondsFormat, TimeZone, Utc,
+};
+use sophia_api::graph::{Graph, MgResult, MutableGraph};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{term_eq, CopyTerm, SimpleIri, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The OWL-Time namespace.
+mod time_ns {
+    sophia_api::namespace!(
+        "http://www.w3.org/2006/time#",
+        Instant,
+        ProperInterval,
+        hasBeginning,
+        hasEnd,
+        inXSDDateTime,
+        inXSDDateTimeStamp,
+        // Allen relations
+        intervalBefore,
+        intervalAfter,
+        intervalMeets,
+        intervalMetBy,
+        intervalOverlaps,
+        intervalOverlappedBy,
+        intervalStarts,
+        intervalStartedBy,
+        intervalDuring,
+        intervalContains,
+        intervalFinishes,
+        intervalFinishedBy,
+        intervalEquals
+    );
+}
+
+/// An instant (`time:Instant`), i.e. a temporal entity with zero extent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instant {
+    /// The resource describing the instant.
+    pub id: BoxTerm,
+    /// The position of the instant on the time line (`time:inXSDDateTime`).
+    pub at: DateTime<FixedOffset>,
+}
+
+impl Instant {
+    /// Build the instant `at`, described by `id`.
+    pub fn new<Tz: TimeZone>(id: BoxTerm, at: DateTime<Tz>) -> Self {
+        let offset = at.offset().fix();
+        Instant {
+            id,
+            at: at.with_timezone(&offset),
+        }
+    }
+
+    /// Insert the description of this instant in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        let at = self.at.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        let at = BoxTerm::new_literal_dt_unchecked(at, xsd::dateTime);
+        graph.insert(&self.id, &rdf::type_, &time_ns::Instant)?;
+        graph.insert(&self.id, &time_ns::inXSDDateTime, &at)?;
+        Ok(())
+    }
+
+    /// Read the description of instant `id` from `graph`.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> TResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let value = match single(graph, &id, &time_ns::inXSDDateTimeStamp)? {
+            Some(value) => value,
+            None => single(graph, &id, &time_ns::inXSDDateTime)?
+                .ok_or_else(|| invalid(&id, "no date-time"))?,
+        };
+        let at = match value.kind() {
+            TermKind::Literal => parse_date_time(&value.value()),
+            _ => None,
+        }
+        .ok_or_else(|| invalid(&id, format!("invalid date-time {}", value)))?;
+        Ok(Instant { id, at })
+    }
+}
+
+/// A proper interval (`time:ProperInterval`), i.e. a temporal entity
+/// with distinct beginning and end.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interval {
+    /// The resource describing the interval.
+    pub id: BoxTerm,
+    /// The beginning of the interval (`time:hasBeginning`).
+    pub beginning: Instant,
+    /// The end of the interval (`time:hasEnd`).
+    pub end: Instant,
+}
+
+impl Interval {
+    /// Build the interval from `beginning` to `end`, described by `id`.
+    ///
+    /// # Panics
+    /// If `end` is not after `beginning`.
+    pub fn new(id: BoxTerm, beginning: Instant, end: Instant) -> Self {
+        assert!(
+            beginning.at < end.at,
+            "the end of an interval must be after its beginning"
+        );
+        Interval { id, beginning, end }
+    }
+
+    /// The duration of this interval.
+    pub fn duration(&self) -> Duration {
+        self.end.at.signed_duration_since(self.beginning.at)
+    }
+
+    /// Whether this interval contains `at` (including its beginning and end).
+    pub fn contains<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+        self.beginning.at <= *at && *at <= self.end.at
+    }
+
+    /// The Allen relation between this interval and `other`.
+    pub fn relation_to(&self, other: &Interval) -> AllenRelation {
+        use AllenRelation::*;
+        let (b1, e1) = (self.beginning.at, self.end.at);
+        let (b2, e2) = (other.beginning.at, other.end.at);
+        if e1 < b2 {
+            Before
+        } else if e2 < b1 {
+            After
+        } else if e1 == b2 {
+            Meets
+        } else if e2 == b1 {
+            MetBy
+        } else {
+            match (b1.cmp(&b2), e1.cmp(&e2)) {
+                (Ordering::Equal, Ordering::Equal) => Equals,
+                (Ordering::Equal, Ordering::Less) => Starts,
+                (Ordering::Equal, Ordering::Greater) => StartedBy,
+                (Ordering::Greater, Ordering::Equal) => Finishes,
+                (Ordering::Less, Ordering::Equal) => FinishedBy,
+                (Ordering::Greater, Ordering::Less) => During,
+                (Ordering::Less, Ordering::Greater) => Contains,
+                (Ordering::Less, Ordering::Less) => Overlaps,
+                (Ordering::Greater, Ordering::Greater) => OverlappedBy,
+            }
+        }
+    }
+
+    /// Insert the description of this interval, and of its beginning and end, in `graph`.
+    pub fn add_to<G: MutableGraph>(&self, graph: &mut G) -> MgResult<G, ()> {
+        graph.insert(&self.id, &rdf::type_, &time_ns::ProperInterval)?;
+        graph.insert(&self.id, &time_ns::hasBeginning, &self.beginning.id)?;
+        graph.insert(&self.id, &time_ns::hasEnd, &self.end.id)?;
+        self.beginning.add_to(graph)?;
+        self.end.add_to(graph)
+    }
+
+    /// Read the description of interval `id` from `graph`.
+    pub fn from_graph<G, T>(graph: &G, id: &T) -> TResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let id = BoxTerm::copy(id);
+        let beginning = single(graph, &id, &time_ns::hasBeginning)?
+            .ok_or_else(|| invalid(&id, "no beginning"))?;
+        let end = single(graph, &id, &time_ns::hasEnd)?.ok_or_else(|| invalid(&id, "no end"))?;
+        let beginning = Instant::from_graph(graph, &beginning)?;
+        let end = Instant::from_graph(graph, &end)?;
+        if beginning.at >= end.at {
+            return Err(invalid(&id, "end is not after beginning").into());
+        }
+        Ok(Interval { id, beginning, end })
+    }
+}
+
+/// The thirteen relations that can hold between two proper intervals,
+/// as defined by James F. Allen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AllenRelation {
+    /// `time:intervalBefore`: the first interval ends before the second one begins.
+    Before,
+    /// `time:intervalAfter`: the first interval begins after the second one ends.
+    After,
+    /// `time:intervalMeets`: the first interval ends when the second one begins.
+    Meets,
+    /// `time:intervalMetBy`: the first interval begins when the second one ends.
+    MetBy,
+    /// `time:intervalOverlaps`: the first interval begins first, and ends during the second one.
+    Overlaps,
+    /// `time:intervalOverlappedBy`: the first interval begins during the second one,
+    /// and ends last.
+    OverlappedBy,
+    /// `time:intervalStarts`: both intervals begin together, and the first one ends first.
+    Starts,
+    /// `time:intervalStartedBy`: both intervals begin together, and the second one ends first.
+    StartedBy,
+    /// `time:intervalDuring`: the first interval is strictly inside the second one.
+    During,
+    /// `time:intervalContains`: the second interval is strictly inside the first one.
+    Contains,
+    /// `time:intervalFinishes`: both intervals end together, and the first one begins last.
+    Finishes,
+    /// `time:intervalFinishedBy`: both intervals end together, and the second one begins last.
+    FinishedBy,
+    /// `time:intervalEquals`: both intervals begin and end together.
+    Equals,
+}
+
+impl AllenRelation {
+    /// All the Allen relations.
+    pub const ALL: &'static [AllenRelation] = &[
+        AllenRelation::Before,
+        AllenRelation::After,
+        AllenRelation::Meets,
+        AllenRelation::MetBy,
+        AllenRelation::Overlaps,
+        AllenRelation::OverlappedBy,
+        AllenRelation::Starts,
+        AllenRelation::StartedBy,
+        AllenRelation::During,
+        AllenRelation::Contains,
+        AllenRelation::Finishes,
+        AllenRelation::FinishedBy,
+        AllenRelation::Equals,
+    ];
+
+    /// The IRI of the OWL-Time property representing this relation.
+    pub fn iri(self) -> &'static SimpleIri<'static> {
+        use AllenRelation::*;
+        match self {
+            Before => &time_ns::intervalBefore,
+            After => &time_ns::intervalAfter,
+            Meets => &time_ns::intervalMeets,
+            MetBy => &time_ns::intervalMetBy,
+            Overlaps => &time_ns::intervalOverlaps,
+            OverlappedBy => &time_ns::intervalOverlappedBy,
+            Starts => &time_ns::intervalStarts,
+            StartedBy => &time_ns::intervalStartedBy,
+            During => &time_ns::intervalDuring,
+            Contains => &time_ns::intervalContains,
+            Finishes => &time_ns::intervalFinishes,
+            FinishedBy => &time_ns::intervalFinishedBy,
+            Equals => &time_ns::intervalEquals,
+        }
+    }
+
+    /// The relation represented by the OWL-Time property `iri`, if any.
+    pub fn from_iri<T: TTerm + ?Sized>(iri: &T) -> Option<Self> {
+        Self::ALL.iter().copied().find(|r| term_eq(r.iri(), iri))
+    }
+
+    /// The inverse of this relation,
+    /// i.e. the relation between `y` and `x` when this relation holds between `x` and `y`.
+    pub fn inverse(self) -> Self {
+        use AllenRelation::*;
+        match self {
+            Before => After,
+            After => Before,
+            Meets => MetBy,
+            MetBy => Meets,
+            Overlaps => OverlappedBy,
+            OverlappedBy => Overlaps,
+            Starts => StartedBy,
+            StartedBy => Starts,
+            During => Contains,
+            Contains => During,
+            Finishes => FinishedBy,
+            FinishedBy => Finishes,
+            Equals => Equals,
+        }
+    }
+}
+
+/// The error raised when reading temporal descriptions.
+#[derive(Debug, Error)]
+pub enum TimeError<E: 'static + Error> {
+    /// The graph raised an error.
+    #[error("Error in the graph: {0}")]
+    Graph(#[source] E),
+    /// A description is not well-formed, or not supported.
+    #[error("Invalid description of {resource}: {message}")]
+    Invalid {
+        /// The offending resource.
+        resource: String,
+        /// The reason why its description is invalid.
+        message: String,
+    },
+}
+
+type TResult<G, T> = Result<T, TimeError<<G as Graph>::Error>>;
+
+impl<E: 'static + Error> From<DescriptionError<E>> for TimeError<E> {
+    fn from(err: DescriptionError<E>) -> Self {
+        match err {
+            DescriptionError::Graph(err) => TimeError::Graph(err),
+            DescriptionError::Invalid { resource, message } => {
+                TimeError::Invalid { resource, message }
+            }
+        }
+    }
+}
+
+/// Read all the intervals (resources with a `time:hasBeginning` and a `time:hasEnd`)
+/// described in `graph`, sorted by identifier.
+pub fn intervals<G: Graph>(graph: &G) -> TResult<G, Vec<Interval>> {
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    for t in graph.triples_with_p(&time_ns::hasBeginning) {
+        let id = BoxTerm::copy(t.map_err(TimeError::Graph)?.s());
+        if seen.insert(id.clone()) {
+            ids.push(id);
+        }
+    }
+    ids.sort_by(|a, b| a.value().cmp(&b.value()));
+    ids.iter()
+        .map(|id| Interval::from_graph(graph, id))
+        .collect()
+}
+
+/// Read all the intervals described in `graph`
+/// such that `relation` holds between `interval` and them,
+/// sorted by identifier.
+///
+/// Relations are computed from the beginning and end of the intervals;
+/// `time:intervalBefore`, `time:intervalMeets`... statements in `graph` are ignored.
+/// `interval` itself is never part of the result.
+pub fn related_intervals<G: Graph>(
+    graph: &G,
+    interval: &Interval,
+    relation: AllenRelation,
+) -> TResult<G, Vec<Interval>> {
+    let mut related = intervals(graph)?;
+    related.retain(|i| !term_eq(&i.id, &interval.id) && interval.relation_to(i) == relation);
+    Ok(related)
+}
+
+/// Parse an `xsd:dateTime` or `xsd:dateTimeStamp` (assuming UTC if it has no timezone).
+fn parse_date_time(txt: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(txt).ok().or_else(|| {
+        let naive = NaiveDateTime::parse_from_str(txt, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+        Some(Utc.from_utc_datetime(&naive).into())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::triple::stream::TripleSource;
+    use sophia_api::graph::isomorphic_graphs;
+
+    type Res = Result<(), Box<dyn std::error::Error>>;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("http://example.org/{}", txt)).unwrap()
+    }
+
+    /// Hour `h` of 2020-01-01 (UTC).
+    fn at(h: u32) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(&format!("2020-01-01T{:02}:00:00Z", h)).unwrap()
+    }
+
+    /// An interval from hour `b` to hour `e` of 2020-01-01 (UTC).
+    fn interval(name: &str, b: u32, e: u32) -> Interval {
+        Interval::new(
+            iri(name),
+            Instant::new(iri(&format!("{}-b", name)), at(b)),
+            Instant::new(iri(&format!("{}-e", name)), at(e)),
+        )
+    }
+
+    #[test]
+    fn relations() {
+        use AllenRelation::*;
+        let x = interval("x", 4, 8);
+        let cases = [
+            (0, 2, After),
+            (0, 4, MetBy),
+            (0, 6, OverlappedBy),
+            (0, 8, Finishes),
+            (0, 10, During),
+            (4, 6, StartedBy),
+            (4, 8, Equals),
+            (4, 10, Starts),
+            (5, 7, Contains),
+            (5, 8, FinishedBy),
+            (5, 10, Overlaps),
+            (8, 10, Meets),
+            (9, 10, Before),
+        ];
+        for (b, e, expected) in cases.iter().copied() {
+            let y = interval("y", b, e);
+            assert_eq!(x.relation_to(&y), expected, "{}-{}", b, e);
+            assert_eq!(y.relation_to(&x), expected.inverse(), "{}-{}", b, e);
+            assert_eq!(AllenRelation::from_iri(expected.iri()), Some(expected));
+        }
+    }
+
+    #[test]
+    fn duration_and_contains() {
+        let x = interval("x", 4, 8);
+        assert_eq!(x.duration(), Duration::hours(4));
+        assert!(x.contains(&at(4)));
+        assert!(x.contains(&DateTime::parse_from_rfc3339("2020-01-01T07:00:00+02:00").unwrap()));
+        assert!(!x.contains(&at(9)));
+    }
+
+    #[test]
+    fn write() -> Res {
+        let mut g = FastGraph::new();
+        interval("x", 4, 8).add_to(&mut g)?;
+        let expected: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix time: <http://www.w3.org/2006/time#>.
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            @prefix : <http://example.org/>.
+
+            :x a time:ProperInterval;
+                time:hasBeginning :x-b;
+                time:hasEnd :x-e.
+            :x-b a time:Instant;
+                time:inXSDDateTime "2020-01-01T04:00:00Z"^^xsd:dateTime.
+            :x-e a time:Instant;
+                time:inXSDDateTime "2020-01-01T08:00:00Z"^^xsd:dateTime.
+            "#,
+        )
+        .collect_triples()?;
+        assert!(isomorphic_graphs(&g, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn read() -> Res {
+        let g: FastGraph = crate::parser::turtle::parse_str(
+            r#"
+            @prefix time: <http://www.w3.org/2006/time#>.
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            @prefix : <http://example.org/>.
+
+            :x time:hasBeginning [
+                    time:inXSDDateTimeStamp "2020-01-01T06:00:00+02:00"^^xsd:dateTimeStamp
+                ];
+                time:hasEnd [ time:inXSDDateTime "2020-01-01T08:00:00"^^xsd:dateTime ].
+            :y time:hasBeginning :y-b; time:hasEnd :y-e.
+            :y-b time:inXSDDateTime "2020-01-01T06:00:00Z"^^xsd:dateTime.
+            :y-e time:inXSDDateTime "2020-01-01T07:00:00Z"^^xsd:dateTime.
+            :z time:hasBeginning :y-e; time:hasEnd :y-b.
+            "#,
+        )
+        .collect_triples()?;
+        let x = Interval::from_graph(&g, &iri("x"))?;
+        assert_eq!(x.duration(), Duration::hours(4));
+        let y = Interval::from_graph(&g, &iri("y"))?;
+        assert_eq!(x.relation_to(&y), AllenRelation::Contains);
+        assert!(Interval::from_graph(&g, &iri("z")).is_err());
+        assert!(intervals(&g).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn query() -> Res {
+        let mut g = FastGraph::new();
+        let x = interval("x", 4, 8);
+        for i in &[
+            interval("a", 0, 2),
+            interval("b", 5, 7),
+            interval("c", 6, 7),
+            interval("d", 9, 10),
+            x.clone(),
+        ] {
+            i.add_to(&mut g)?;
+        }
+        assert_eq!(intervals(&g)?.len(), 5);
+        let ids = |intervals: Vec<Interval>| -> Vec<String> {
+            intervals.iter().map(|i| i.id.value().to_string()).collect()
+        };
+        let contained = related_intervals(&g, &x, AllenRelation::Contains)?;
+        assert_eq!(
+            ids(contained),
+            ["http://example.org/b", "http://example.org/c"]
+        );
+        let before_x = related_intervals(&g, &x, AllenRelation::After)?;
+        assert_eq!(ids(before_x), ["http://example.org/a"]);
+        assert!(related_intervals(&g, &x, AllenRelation::Equals)?.is_empty());
+        Ok(())
+    }
+}
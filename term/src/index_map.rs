@@ -2,6 +2,7 @@
 
 use crate::factory::{FTerm, TermFactory};
 use crate::RefTerm;
+use sophia_api::term::TTerm;
 
 /// A bidirectional mapping between [`Term`]s and *indexes* of a smaller type.
 ///
@@ -35,6 +36,27 @@ pub trait TermIndexMap: Default {
     fn dec_ref(&mut self, i: Self::Index);
     /// Shrinks the capacity of the TermIndexMap as much as possible.
     fn shrink_to_fit(&mut self);
+    /// Renumber the used indexes, so that they are contiguous,
+    /// and release the memory held by the freed ones.
+    ///
+    /// Return the pairs `(old_index, new_index)` of the indexes that were changed,
+    /// so that the caller can update the structures using them.
+    fn compact(&mut self) -> Vec<(Self::Index, Self::Index)>;
+    /// Replace, in place, every term for which `rewrite` returns `Some(new_term)`.
+    ///
+    /// `rewrite` is called once per indexed term, always with the original terms,
+    /// so that terms can be swapped.
+    /// A rewritten term keeps its index,
+    /// unless the new term is already indexed (or is the rewriting of another term).
+    /// In that case, the two indexes are merged:
+    /// the ref count of the old index is transferred to the surviving one,
+    /// the old index is freed,
+    /// and the pair `(old_index, surviving_index)` is returned,
+    /// so that the caller can update the structures using the old index.
+    fn rewrite_terms<U, R>(&mut self, rewrite: R) -> Vec<(Self::Index, Self::Index)>
+    where
+        U: TTerm,
+        R: FnMut(&FTerm<Self::Factory>) -> Option<U>;
 
     // The following methods have a default impl, and would generally not be overridden
 
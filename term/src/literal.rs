@@ -13,7 +13,15 @@ use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::io;
 
+pub mod canonical;
 pub mod convert;
+pub mod datetime;
+pub mod html;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod registry;
+#[cfg(feature = "text")]
+pub mod text;
 
 /// Internal distinction of literals.
 ///
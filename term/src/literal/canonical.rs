@@ -0,0 +1,186 @@
+//! Canonical lexical forms of literals.
+//!
+//! Several lexical values may denote the same value (e.g. `"01"`, `"+1"` and `"1"` as `xsd:integer`).
+//! [`canonical_form`] returns the [canonical representation] defined by XML Schema 1.1
+//! for the most common XSD datatypes, which is useful to compare, hash or sign RDF data:
+//! * `xsd:boolean`: `true` or `false`;
+//! * `xsd:integer` and its sub-types: no sign unless negative, no leading zeros (e.g. `-12`);
+//! * `xsd:decimal`: no sign unless negative, no leading or trailing zeros,
+//!   no decimal point for integer values (e.g. `1.5`, `-12`);
+//! * `xsd:double` and `xsd:float`: a mantissa with a single non-zero digit before the decimal point
+//!   (which is mandatory), followed by an exponent (e.g. `1.5E2`, `0.0E0`, `INF`).
+//!
+//! [`canonical_form`]: fn.canonical_form.html
+//! [canonical representation]: https://www.w3.org/TR/xmlschema11-2/#canonical-lexical-representation
+
+use super::registry::{is_decimal, is_double, is_integer, unsigned};
+use sophia_api::ns::xsd;
+use sophia_api::term::{TTerm, TermKind};
+
+/// The canonical lexical form of literal `lit`.
+///
+/// Return `None` if `lit` is not a literal,
+/// if its datatype is not supported (see [module documentation](index.html)),
+/// or if its lexical value is not valid for its datatype.
+///
+/// # Example
+/// ```
+/// # use sophia_term::literal::canonical::canonical_form;
+/// # use sophia_term::StaticTerm;
+/// # use sophia_api::ns::xsd;
+/// let lit = StaticTerm::new_literal_dt_unchecked("+007", xsd::int);
+/// assert_eq!(canonical_form(&lit), Some("7".to_string()));
+/// ```
+pub fn canonical_form<T>(lit: &T) -> Option<String>
+where
+    T: TTerm + ?Sized,
+{
+    if lit.kind() != TermKind::Literal {
+        return None;
+    }
+    let dt = lit.datatype()?;
+    let lex = lit.value_raw().0;
+    let integers = [
+        &xsd::integer,
+        &xsd::nonNegativeInteger,
+        &xsd::positiveInteger,
+        &xsd::nonPositiveInteger,
+        &xsd::negativeInteger,
+        &xsd::long,
+        &xsd::int,
+        &xsd::short,
+        &xsd::byte,
+        &xsd::unsignedLong,
+        &xsd::unsignedInt,
+        &xsd::unsignedShort,
+        &xsd::unsignedByte,
+    ];
+    if xsd::boolean == dt {
+        match lex {
+            "true" | "1" => Some("true".to_string()),
+            "false" | "0" => Some("false".to_string()),
+            _ => None,
+        }
+    } else if integers.iter().any(|i| **i == dt) {
+        canonical_integer(lex)
+    } else if xsd::decimal == dt {
+        canonical_decimal(lex)
+    } else if xsd::double == dt {
+        canonical_double(lex, false)
+    } else if xsd::float == dt {
+        canonical_double(lex, true)
+    } else {
+        None
+    }
+}
+
+fn canonical_integer(lex: &str) -> Option<String> {
+    if !is_integer(lex) {
+        return None;
+    }
+    let digits = unsigned(lex).trim_start_matches('0');
+    Some(if digits.is_empty() {
+        "0".to_string()
+    } else if lex.starts_with('-') {
+        format!("-{}", digits)
+    } else {
+        digits.to_string()
+    })
+}
+
+fn canonical_decimal(lex: &str) -> Option<String> {
+    if !is_decimal(lex) {
+        return None;
+    }
+    let mut parts = unsigned(lex).splitn(2, '.');
+    let int = parts.next().unwrap().trim_start_matches('0');
+    let frac = parts.next().unwrap_or("").trim_end_matches('0');
+    if int.is_empty() && frac.is_empty() {
+        return Some("0".to_string());
+    }
+    let mut ret = String::with_capacity(lex.len());
+    if lex.starts_with('-') {
+        ret.push('-');
+    }
+    ret.push_str(if int.is_empty() { "0" } else { int });
+    if !frac.is_empty() {
+        ret.push('.');
+        ret.push_str(frac);
+    }
+    Some(ret)
+}
+
+/// Canonical form of `xsd:double`, or `xsd:float` if `single` is true.
+fn canonical_double(lex: &str, single: bool) -> Option<String> {
+    if !is_double(lex) {
+        return None;
+    }
+    let (infinite, negative, formatted) = match lex {
+        "NaN" => return Some("NaN".to_string()),
+        "INF" | "+INF" => (true, false, String::new()),
+        "-INF" => (true, true, String::new()),
+        _ if single => {
+            let v: f32 = lex.parse().ok()?;
+            (v.is_infinite(), v.is_sign_negative(), format!("{:E}", v))
+        }
+        _ => {
+            let v: f64 = lex.parse().ok()?;
+            (v.is_infinite(), v.is_sign_negative(), format!("{:E}", v))
+        }
+    };
+    if infinite {
+        return Some(if negative { "-INF" } else { "INF" }.to_string());
+    }
+    // the mantissa of Rust's exponent notation has no decimal point for integers (e.g. 1E2)
+    let e = formatted.find('E').unwrap();
+    if formatted[..e].contains('.') {
+        Some(formatted)
+    } else {
+        Some(format!("{}.0{}", &formatted[..e], &formatted[e..]))
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StaticTerm;
+    use sophia_api::term::SimpleIri;
+    use test_case::test_case;
+
+    #[test_case("1", xsd::boolean, Some("true") ; "boolean 1")]
+    #[test_case("false", xsd::boolean, Some("false") ; "boolean false")]
+    #[test_case("True", xsd::boolean, None ; "boolean invalid")]
+    #[test_case("+007", xsd::integer, Some("7") ; "integer plus")]
+    #[test_case("-0", xsd::integer, Some("0") ; "integer minus zero")]
+    #[test_case("-0012", xsd::short, Some("-12") ; "short")]
+    #[test_case("12a", xsd::integer, None ; "integer invalid")]
+    #[test_case("01.50", xsd::decimal, Some("1.5") ; "decimal")]
+    #[test_case("-.5", xsd::decimal, Some("-0.5") ; "decimal no int")]
+    #[test_case("+12.", xsd::decimal, Some("12") ; "decimal no frac")]
+    #[test_case("-0.00", xsd::decimal, Some("0") ; "decimal zero")]
+    #[test_case("1e2", xsd::double, Some("1.0E2") ; "double")]
+    #[test_case("0.00125", xsd::double, Some("1.25E-3") ; "double small")]
+    #[test_case("-0", xsd::double, Some("-0.0E0") ; "double minus zero")]
+    #[test_case("+INF", xsd::double, Some("INF") ; "double inf")]
+    #[test_case("1e400", xsd::double, Some("INF") ; "double overflow")]
+    #[test_case("inf", xsd::double, None ; "double invalid")]
+    #[test_case("0.1", xsd::float, Some("1.0E-1") ; "float")]
+    #[test_case("NaN", xsd::float, Some("NaN") ; "float nan")]
+    #[test_case("01", xsd::string, None ; "unsupported")]
+    fn canonical(lex: &'static str, dt: SimpleIri<'static>, expected: Option<&str>) {
+        let lit = StaticTerm::new_literal_dt_unchecked(lex, dt);
+        assert_eq!(canonical_form(&lit).as_deref(), expected);
+    }
+
+    #[test]
+    fn not_a_literal() {
+        let iri = StaticTerm::new_iri_unchecked("http://example.org/1");
+        assert_eq!(canonical_form(&iri), None);
+        let lang = StaticTerm::new_literal_lang_unchecked("1", "en");
+        assert_eq!(canonical_form(&lang), None);
+    }
+}
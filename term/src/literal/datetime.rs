@@ -0,0 +1,485 @@
+//! Timezone-aware comparison of `xsd:dateTime` literals.
+//!
+//! Comparing `xsd:dateTime` literals by their lexical value gives a wrong ordering
+//! as soon as they use different timezone offsets
+//! (e.g. `2020-01-01T12:30:00+02:00` comes *before* `2020-01-01T11:00:00Z`).
+//! This module provides [`DateTime`], which converts such literals to instants on the UTC time-line,
+//! as well as a [`DateTimeRange`] matcher and a sorted [`DateTimeIndex`] built upon it.
+//!
+//! [`DateTime`]: struct.DateTime.html
+//! [`DateTimeRange`]: struct.DateTimeRange.html
+//! [`DateTimeIndex`]: struct.DateTimeIndex.html
+
+use super::convert::{DataType, TryConvertTerm};
+use sophia_api::ns::xsd;
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{SimpleIri, TTerm};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::ops::{Bound, RangeBounds};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The value of an `xsd:dateTime` literal, as an instant on the UTC time-line.
+///
+/// Equality, ordering and hashing only consider the instant,
+/// so `2020-01-01T12:00:00+02:00` and `2020-01-01T10:00:00Z` are equal.
+///
+/// NB: values without a timezone are considered to be in UTC.
+/// Strictly speaking, XML Schema considers them incomparable
+/// with timezoned values that are less than 14 hours away.
+#[derive(Clone, Copy, Debug)]
+pub struct DateTime {
+    seconds: i64,
+    nanos: u32,
+    offset: Option<i16>,
+}
+
+impl DateTime {
+    /// Build a `DateTime` from the number of seconds since 1970-01-01T00:00:00Z,
+    /// and a number of additional nanoseconds.
+    pub fn from_timestamp(seconds: i64, nanos: u32) -> Self {
+        DateTime {
+            seconds: seconds + (nanos / 1_000_000_000) as i64,
+            nanos: nanos % 1_000_000_000,
+            offset: Some(0),
+        }
+    }
+
+    /// The number of seconds since 1970-01-01T00:00:00Z.
+    pub fn timestamp(&self) -> i64 {
+        self.seconds
+    }
+
+    /// The number of nanoseconds since the last whole second.
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// The timezone offset of the original value, in minutes, if any.
+    pub fn offset(&self) -> Option<i16> {
+        self.offset
+    }
+}
+
+impl PartialEq for DateTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.seconds == other.seconds && self.nanos == other.nanos
+    }
+}
+
+impl Eq for DateTime {}
+
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DateTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.seconds
+            .cmp(&other.seconds)
+            .then_with(|| self.nanos.cmp(&other.nanos))
+    }
+}
+
+impl Hash for DateTime {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.seconds.hash(state);
+        self.nanos.hash(state);
+    }
+}
+
+impl DataType for DateTime {
+    fn iri() -> SimpleIri<'static> {
+        xsd::dateTime
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = InvalidDateTime;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        parse(txt).ok_or_else(|| InvalidDateTime(txt.to_string()))
+    }
+}
+
+impl TryConvertTerm for DateTime {}
+
+/// The error raised when parsing an invalid `xsd:dateTime` lexical value.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("Invalid xsd:dateTime {0:?}")]
+pub struct InvalidDateTime(pub String);
+
+/// A matcher for `xsd:dateTime` literals denoting an instant in the given range.
+///
+/// Other terms, including literals with an invalid lexical value, never match.
+///
+/// ```
+/// # use sophia_term::literal::datetime::*;
+/// # use sophia_term::StaticTerm;
+/// # use sophia_api::ns::xsd;
+/// # use sophia_api::term::matcher::TermMatcher;
+/// let start: DateTime = "2020-01-01T00:00:00Z".parse()?;
+/// let m = DateTimeRange::new(start..);
+/// let t = StaticTerm::new_literal_dt_unchecked("2020-01-01T01:30:00+02:00", xsd::dateTime);
+/// assert!(!m.matches(&t)); // 2019-12-31T23:30:00Z
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct DateTimeRange {
+    start: Bound<DateTime>,
+    end: Bound<DateTime>,
+}
+
+impl DateTimeRange {
+    /// Build a matcher for the given range of instants.
+    pub fn new<R: RangeBounds<DateTime>>(range: R) -> Self {
+        DateTimeRange {
+            start: cloned(range.start_bound()),
+            end: cloned(range.end_bound()),
+        }
+    }
+}
+
+impl RangeBounds<DateTime> for DateTimeRange {
+    fn start_bound(&self) -> Bound<&DateTime> {
+        as_ref(&self.start)
+    }
+    fn end_bound(&self) -> Bound<&DateTime> {
+        as_ref(&self.end)
+    }
+}
+
+impl TermMatcher for DateTimeRange {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        DateTime::try_convert(t)
+            .map(|d| self.contains(&d))
+            .unwrap_or(false)
+    }
+}
+
+/// A sorted index of `xsd:dateTime` literals,
+/// associating the instants they denote to arbitrary values.
+///
+/// It is meant to be maintained alongside a graph or a dataset
+/// (e.g. with the triples, or term indexes, where each literal occurs),
+/// in order to retrieve those values in chronological order,
+/// or for a given range of instants.
+#[derive(Clone, Debug)]
+pub struct DateTimeIndex<V> {
+    map: BTreeMap<DateTime, Vec<V>>,
+    len: usize,
+}
+
+impl<V> Default for DateTimeIndex<V> {
+    fn default() -> Self {
+        DateTimeIndex {
+            map: BTreeMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> DateTimeIndex<V> {
+    /// Build an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of values in this index.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Associate `value` to the instant denoted by `term`.
+    ///
+    /// Return `false`, and leave the index unchanged,
+    /// if `term` is not a valid `xsd:dateTime` literal.
+    pub fn insert<T>(&mut self, term: &T, value: V) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        match DateTime::try_convert(term) {
+            Ok(d) => {
+                self.map.entry(d).or_default().push(value);
+                self.len += 1;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Remove one occurrence of `value` from the instant denoted by `term`.
+    ///
+    /// Return `false` if it was not found.
+    pub fn remove<T>(&mut self, term: &T, value: &V) -> bool
+    where
+        T: TTerm + ?Sized,
+        V: PartialEq,
+    {
+        let d = match DateTime::try_convert(term) {
+            Ok(d) => d,
+            Err(_) => return false,
+        };
+        let values = match self.map.get_mut(&d) {
+            Some(values) => values,
+            None => return false,
+        };
+        let pos = match values.iter().position(|v| v == value) {
+            Some(pos) => pos,
+            None => return false,
+        };
+        values.remove(pos);
+        if values.is_empty() {
+            self.map.remove(&d);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Iterate, in chronological order, over the values associated to instants in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end.
+    pub fn range<R>(&self, range: R) -> impl Iterator<Item = (&DateTime, &V)>
+    where
+        R: RangeBounds<DateTime>,
+    {
+        self.map
+            .range(range)
+            .flat_map(|(d, values)| values.iter().map(move |v| (d, v)))
+    }
+}
+
+fn cloned(b: Bound<&DateTime>) -> Bound<DateTime> {
+    match b {
+        Bound::Included(d) => Bound::Included(*d),
+        Bound::Excluded(d) => Bound::Excluded(*d),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn as_ref(b: &Bound<DateTime>) -> Bound<&DateTime> {
+    match b {
+        Bound::Included(d) => Bound::Included(d),
+        Bound::Excluded(d) => Bound::Excluded(d),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Parse `-?YYYY-MM-DDThh:mm:ss(.s+)?(Z|(+|-)hh:mm)?`
+fn parse(txt: &str) -> Option<DateTime> {
+    let (negative, rest) = match txt.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, txt),
+    };
+    let t = rest.find('T')?;
+    let (date, time) = (&rest[..t], &rest[t + 1..]);
+
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?;
+    let month = two_digits(parts.next()?)?;
+    let day = two_digits(parts.next()?)?;
+    if year.len() < 4
+        || year.len() > 11 // prevent overflows
+        || (year.len() > 4 && year.starts_with('0'))
+        || !year.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let year: i64 = year.parse().ok()?;
+    let year = if negative { -year } else { year };
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, Some(0))
+    } else if time.len() > 6 && matches!(time.as_bytes()[time.len() - 6], b'+' | b'-') {
+        let (time, tz) = time.split_at(time.len() - 6);
+        let h = two_digits(tz.get(1..3)?)?;
+        let m = two_digits(tz.get(4..6)?)?;
+        if tz.get(3..4)? != ":" || m > 59 || h * 60 + m > 14 * 60 {
+            return None;
+        }
+        let offset = (h * 60 + m) as i16;
+        (
+            time,
+            Some(if tz.starts_with('-') { -offset } else { offset }),
+        )
+    } else {
+        (time, None)
+    };
+
+    let hour = two_digits(time.get(0..2)?)?;
+    let minute = two_digits(time.get(3..5)?)?;
+    let second = two_digits(time.get(6..8)?)?;
+    if time.get(2..3)? != ":" || time.get(5..6)? != ":" {
+        return None;
+    }
+    let nanos = match time.get(8..) {
+        Some("") => 0,
+        Some(frac) => {
+            let digits = frac.strip_prefix('.')?;
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let mut nanos = 0;
+            for i in 0..9 {
+                let digit = digits.as_bytes().get(i).map_or(0, |b| b - b'0');
+                nanos = nanos * 10 + digit as u32;
+            }
+            nanos
+        }
+        None => return None,
+    };
+    let valid_time = hour < 24 && minute < 60 && second < 60;
+    let end_of_day = hour == 24 && minute == 0 && second == 0 && nanos == 0;
+    if !valid_time && !end_of_day {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+        - offset.unwrap_or(0) as i64 * 60;
+    Some(DateTime {
+        seconds,
+        nanos,
+        offset,
+    })
+}
+
+fn two_digits(txt: &str) -> Option<i64> {
+    if txt.len() == 2 && txt.bytes().all(|b| b.is_ascii_digit()) {
+        txt.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 in the proleptic Gregorian calendar
+/// (see <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StaticTerm;
+    use test_case::test_case;
+
+    fn dt(txt: &str) -> DateTime {
+        txt.parse().unwrap()
+    }
+
+    #[test_case("1970-01-01T00:00:00Z", 0, 0 ; "epoch")]
+    #[test_case("1970-01-01T00:00:00", 0, 0 ; "no timezone")]
+    #[test_case("1970-01-01T02:00:00+02:00", 0, 0 ; "positive offset")]
+    #[test_case("1969-12-31T23:30:00-00:30", 0, 0 ; "negative offset")]
+    #[test_case("2000-03-01T00:00:00.5Z", 951_868_800, 500_000_000 ; "fraction")]
+    #[test_case("1969-12-31T24:00:00Z", 0, 0 ; "end of day")]
+    #[test_case("2020-02-29T12:00:00Z", 1_582_977_600, 0 ; "leap day")]
+    #[test_case("-0001-01-01T00:00:00Z", -62_198_755_200, 0 ; "negative year")]
+    fn parse_valid(txt: &str, seconds: i64, nanos: u32) {
+        let d = dt(txt);
+        assert_eq!(d.timestamp(), seconds);
+        assert_eq!(d.subsec_nanos(), nanos);
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("2020-01-01" ; "date only")]
+    #[test_case("20-01-01T00:00:00Z" ; "short year")]
+    #[test_case("02020-01-01T00:00:00Z" ; "leading zero")]
+    #[test_case("2020-13-01T00:00:00Z" ; "bad month")]
+    #[test_case("2019-02-29T00:00:00Z" ; "bad day")]
+    #[test_case("2020-01-01T24:00:01Z" ; "bad hour")]
+    #[test_case("2020-01-01T00:60:00Z" ; "bad minute")]
+    #[test_case("2020-01-01T00:00:00.Z" ; "empty fraction")]
+    #[test_case("2020-01-01T00:00:00+15:00" ; "bad offset")]
+    #[test_case("2020-01-01T00:00:00+0200" ; "malformed offset")]
+    #[test_case("2020-01-01T00:00:00é" ; "non ascii")]
+    fn parse_invalid(txt: &str) {
+        assert!(txt.parse::<DateTime>().is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        let a = dt("2020-01-01T12:30:00+02:00");
+        let b = dt("2020-01-01T11:00:00Z");
+        assert!(a < b);
+        assert_eq!(dt("2020-01-01T12:00:00+02:00"), dt("2020-01-01T10:00:00Z"));
+        assert_eq!(a.offset(), Some(120));
+        assert_eq!(dt("2020-01-01T10:00:00").offset(), None);
+    }
+
+    #[test]
+    fn range_matcher() {
+        let m = DateTimeRange::new(dt("2020-01-01T00:00:00Z")..dt("2020-01-02T00:00:00Z"));
+        let lit = |txt: &'static str| StaticTerm::new_literal_dt_unchecked(txt, xsd::dateTime);
+        assert!(m.matches(&lit("2020-01-01T01:30:00+01:00")));
+        assert!(!m.matches(&lit("2020-01-01T00:30:00+01:00")));
+        assert!(!m.matches(&lit("2020-01-02T01:30:00+01:00")));
+        assert!(!m.matches(&lit("not a date")));
+        assert!(!m.matches(&StaticTerm::new_literal_dt_unchecked(
+            "2020-01-01T12:00:00Z",
+            xsd::string
+        )));
+        assert!(m.constant().is_none());
+    }
+
+    #[test]
+    fn index() {
+        let lit = |txt: &'static str| StaticTerm::new_literal_dt_unchecked(txt, xsd::dateTime);
+        let mut idx = DateTimeIndex::new();
+        assert!(idx.insert(&lit("2020-01-01T12:30:00+02:00"), 1));
+        assert!(idx.insert(&lit("2020-01-01T11:00:00Z"), 2));
+        assert!(idx.insert(&lit("2020-01-01T09:00:00-03:00"), 3));
+        assert!(idx.insert(&lit("2020-01-01T10:30:00Z"), 4));
+        assert!(!idx.insert(&StaticTerm::new_iri_unchecked("http://example.org/"), 5));
+        assert_eq!(idx.len(), 4);
+
+        let all: Vec<_> = idx.range(..).map(|(_, v)| *v).collect();
+        assert_eq!(all, vec![1, 4, 2, 3]);
+        let some: Vec<_> = idx
+            .range(dt("2020-01-01T10:30:00Z")..dt("2020-01-01T12:00:00Z"))
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(some, vec![1, 4, 2]);
+
+        assert!(idx.remove(&lit("2020-01-01T10:30:00Z"), &1));
+        assert!(!idx.remove(&lit("2020-01-01T10:30:00Z"), &1));
+        assert_eq!(idx.len(), 3);
+    }
+}
@@ -0,0 +1,185 @@
+//! Support for the [`rdf:HTML`] datatype.
+//!
+//! Literals of this datatype are compared by means of a canonical form of their markup,
+//! produced by a light-weight tokenizer:
+//! * tag and attribute names are lowercased,
+//! * attributes are sorted by name, and their values are double-quoted,
+//! * the slash of self-closing tags and the whitespace inside tags are removed,
+//! * text, comments and other markup declarations are kept verbatim.
+//!
+//! NB: no DOM tree is built, so this does not implement the full
+//! [HTML fragment parsing algorithm];
+//! in particular, implied tags are not inserted,
+//! and character references are not decoded.
+//!
+//! [`rdf:HTML`]: https://www.w3.org/TR/rdf11-concepts/#section-html
+//! [HTML fragment parsing algorithm]: https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+
+use super::registry::DatatypeHandler;
+use std::any::Any;
+
+/// Return the canonical form of the lexical value of an `rdf:HTML` literal.
+pub fn canonicalize(lex: &str) -> String {
+    let mut out = String::with_capacity(lex.len());
+    let mut rest = lex;
+    while let Some(i) = rest.find('<') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let consumed = if rest.starts_with("<!--") {
+            copy_until(&mut out, rest, "-->")
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            copy_until(&mut out, rest, ">")
+        } else if starts_with_alpha(&rest[1..]) {
+            write_tag(&mut out, rest, false)
+        } else if rest.starts_with("</") && starts_with_alpha(&rest[2..]) {
+            write_tag(&mut out, rest, true)
+        } else {
+            None
+        };
+        match consumed {
+            Some(len) => rest = &rest[len..],
+            None => {
+                // not markup, or unterminated markup: keep it as text
+                out.push('<');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Check whether two lexical values have the same [canonical form](fn.canonicalize.html).
+pub fn html_eq(lex1: &str, lex2: &str) -> bool {
+    lex1 == lex2 || canonicalize(lex1) == canonicalize(lex2)
+}
+
+/// A [`DatatypeHandler`](../registry/struct.DatatypeHandler.html) for `rdf:HTML`,
+/// accepting any lexical value, and parsing it to its canonical form (as a `String`).
+pub fn handler() -> DatatypeHandler {
+    DatatypeHandler::with_parser(
+        |_| true,
+        |lex| Some(Box::new(canonicalize(lex)) as Box<dyn Any>),
+    )
+}
+
+fn starts_with_alpha(txt: &str) -> bool {
+    txt.bytes().next().is_some_and(|b| b.is_ascii_alphabetic())
+}
+
+/// Copy `txt` up to and including `end` into `out`,
+/// and return the number of bytes copied (or `None` if `end` was not found).
+fn copy_until(out: &mut String, txt: &str, end: &str) -> Option<usize> {
+    let len = txt.find(end)? + end.len();
+    out.push_str(&txt[..len]);
+    Some(len)
+}
+
+/// Write the canonical form of the start tag (or end tag) at the beginning of `txt`,
+/// and return its length (or `None` if the tag is not terminated).
+fn write_tag(out: &mut String, txt: &str, end_tag: bool) -> Option<usize> {
+    let start = if end_tag { 2 } else { 1 };
+    let name_len = txt[start..]
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .unwrap_or(txt.len() - start);
+    let name = &txt[start..start + name_len];
+    let mut attributes = vec![];
+    let mut rest = &txt[start + name_len..];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if rest.is_empty() {
+            return None;
+        }
+        if rest.starts_with('>') {
+            break;
+        }
+        let attr_len = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>' || c == '=')
+            .unwrap_or(rest.len())
+            .max(1); // an attribute name may start with '='
+        let attr_name = rest[..attr_len].to_ascii_lowercase();
+        rest = rest[attr_len..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let mut value = None;
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            let (val, remaining) = match after_eq.chars().next() {
+                Some(q) if q == '"' || q == '\'' => {
+                    let end = after_eq[1..].find(q)?;
+                    (&after_eq[1..=end], &after_eq[end + 2..])
+                }
+                _ => {
+                    let end = after_eq
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .unwrap_or(after_eq.len());
+                    after_eq.split_at(end)
+                }
+            };
+            value = Some(val);
+            rest = remaining;
+        }
+        attributes.push((attr_name, value));
+    }
+    attributes.sort_by(|(n1, _), (n2, _)| n1.cmp(n2));
+
+    out.push('<');
+    if end_tag {
+        out.push('/');
+    }
+    out.push_str(&name.to_ascii_lowercase());
+    for (attr_name, value) in attributes {
+        out.push(' ');
+        out.push_str(&attr_name);
+        if let Some(value) = value {
+            out.push_str("=\"");
+            out.push_str(&value.replace('"', "&quot;"));
+            out.push('"');
+        }
+    }
+    out.push('>');
+    // rest starts with the closing '>'
+    Some(txt.len() - rest.len() + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("plain text", "plain text")]
+    #[test_case("<P>Hello <B>world</B></P>", "<p>Hello <b>world</b></p>")]
+    #[test_case(
+        "<a TITLE='x' href=\"y\" Class=z>",
+        "<a class=\"z\" href=\"y\" title=\"x\">"
+    )]
+    #[test_case("<img src = 'a \"b\"' />", "<img src=\"a &quot;b&quot;\">")]
+    #[test_case("<input disabled type=checkbox>", "<input disabled type=\"checkbox\">")]
+    #[test_case("</DIV >", "</div>")]
+    #[test_case("<!-- <B>  kept </B> -->x", "<!-- <B>  kept </B> -->x")]
+    #[test_case("<!DOCTYPE html><BR>", "<!DOCTYPE html><br>")]
+    #[test_case("1 < 2 <3", "1 < 2 <3")]
+    #[test_case("<b>unterminated <i", "<b>unterminated <i")]
+    #[test_case("&amp; &#233;", "&amp; &#233;")]
+    #[test_case("<p>caf\u{E9}</p>", "<p>caf\u{E9}</p>")]
+    fn canonical(lex: &str, expected: &str) {
+        assert_eq!(canonicalize(lex), expected);
+    }
+
+    #[test]
+    fn eq() {
+        assert!(html_eq(
+            "<P CLASS='a' id=b>x</P>",
+            "<p id=\"b\" class=\"a\">x</p>"
+        ));
+        assert!(html_eq("<br/>", "<BR>"));
+        assert!(!html_eq("<p>x</p>", "<p>X</p>"));
+        assert!(!html_eq("<p>x  y</p>", "<p>x y</p>"));
+    }
+
+    #[test]
+    fn handler_parses() {
+        let h = handler();
+        assert!(h.is_valid("<p>unclosed"));
+        let v = h.parse("<P>x</P>").unwrap().downcast::<String>().unwrap();
+        assert_eq!(*v, "<p>x</p>");
+    }
+}
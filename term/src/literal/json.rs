@@ -0,0 +1,228 @@
+//! Support for the [`rdf:JSON`] datatype.
+//!
+//! Literals of this datatype are compared as JSON values,
+//! by means of their canonical form as defined by the
+//! [JSON Canonicalization Scheme] (JCS),
+//! which is also suitable for hashing.
+//!
+//! This module requires the `json` feature.
+//!
+//! [`rdf:JSON`]: https://www.w3.org/TR/json-ld11/#the-rdf-json-datatype
+//! [JSON Canonicalization Scheme]: https://tools.ietf.org/html/rfc8785
+
+use super::registry::DatatypeHandler;
+pub use json::{Error as JsonError, JsonValue};
+use std::any::Any;
+use std::fmt::Write;
+use thiserror::Error;
+
+/// An error raised when computing the canonical form of an `rdf:JSON` value.
+#[derive(Debug, Error)]
+pub enum CanonicalJsonError {
+    /// The lexical value is not valid JSON.
+    #[error(transparent)]
+    Json(#[from] JsonError),
+    /// The value contains a number which is not representable as an IEEE 754 double,
+    /// and therefore has no canonical form.
+    #[error("The number '{0}' is out of the range of IEEE 754 doubles")]
+    NumberOutOfRange(String),
+}
+
+/// Parse the lexical value of an `rdf:JSON` literal.
+pub fn parse(lex: &str) -> Result<JsonValue, JsonError> {
+    json::parse(lex)
+}
+
+/// Return the canonical form of the lexical value of an `rdf:JSON` literal.
+pub fn canonicalize(lex: &str) -> Result<String, CanonicalJsonError> {
+    to_canonical(&parse(lex)?)
+}
+
+/// Serialize `value` according to the JSON Canonicalization Scheme:
+/// no whitespace, object members sorted by key,
+/// and numbers serialized as in ECMAScript.
+///
+/// Fails if `value` contains a number overflowing IEEE 754 doubles (e.g. `1e400`),
+/// as the JSON Canonicalization Scheme does not allow them.
+pub fn to_canonical(value: &JsonValue) -> Result<String, CanonicalJsonError> {
+    let mut out = String::new();
+    write_canonical(&mut out, value)?;
+    Ok(out)
+}
+
+/// Check whether two lexical values represent the same JSON value.
+///
+/// Invalid JSON values are compared lexically.
+pub fn json_eq(lex1: &str, lex2: &str) -> bool {
+    lex1 == lex2
+        || match (canonicalize(lex1), canonicalize(lex2)) {
+            (Ok(c1), Ok(c2)) => c1 == c2,
+            _ => false,
+        }
+}
+
+/// A [`DatatypeHandler`](../registry/struct.DatatypeHandler.html) for `rdf:JSON`,
+/// parsing values as [`JsonValue`]s.
+///
+/// [`JsonValue`]: enum.JsonValue.html
+pub fn handler() -> DatatypeHandler {
+    DatatypeHandler::with_parser(
+        |lex| parse(lex).is_ok(),
+        |lex| parse(lex).ok().map(|v| Box::new(v) as Box<dyn Any>),
+    )
+}
+
+fn write_canonical(out: &mut String, value: &JsonValue) -> Result<(), CanonicalJsonError> {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Number(_) => {
+            let num = value.as_f64().unwrap();
+            if !num.is_finite() {
+                return Err(CanonicalJsonError::NumberOutOfRange(value.dump()));
+            }
+            write_number(out, num)
+        }
+        JsonValue::Short(_) | JsonValue::String(_) => write_string(out, value.as_str().unwrap()),
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, item)?;
+            }
+            out.push(']');
+        }
+        JsonValue::Object(obj) => {
+            let mut members: Vec<_> = obj.iter().collect();
+            // keys are sorted by their UTF-16 code units
+            members.sort_by(|(k1, _), (k2, _)| k1.encode_utf16().cmp(k2.encode_utf16()));
+            out.push('{');
+            for (i, (key, val)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_canonical(out, val)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut String, txt: &str) {
+    out.push('"');
+    for c in txt.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{C}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Serialize `num` as ECMAScript's `Number.prototype.toString` does.
+///
+/// `num` must be finite.
+fn write_number(out: &mut String, num: f64) {
+    if num == 0.0 {
+        // also covers -0
+        out.push('0');
+        return;
+    }
+    if num < 0.0 {
+        out.push('-');
+    }
+    // the shortest representation that round-trips, as d.ddde[-]x
+    let sci = format!("{:e}", num.abs());
+    let mut parts = sci.splitn(2, 'e');
+    let digits = parts.next().unwrap().replace('.', "");
+    let exp: i32 = parts.next().unwrap().parse().unwrap();
+    let k = digits.len() as i32;
+    let n = exp + 1;
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend((0..n - k).map(|_| '0'));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend((0..-n).map(|_| '0'));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        write!(out, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("null", "null")]
+    #[test_case(" [ true , false ] ", "[true,false]")]
+    #[test_case(
+        r#"{"b": 1, "a": {"d": [], "c": null}}"#,
+        r#"{"a":{"c":null,"d":[]},"b":1}"#
+    )]
+    #[test_case(r#"{"€": 1, "\r": 2, "1": 3}"#, r#"{"\r":2,"1":3,"€":1}"#)]
+    #[test_case(r#""é\u001f\/""#, r#""é\u001f/""#)]
+    #[test_case(
+        "[1.0, -0, 1e2, 1.5, 0.001, 1e-7, 1e21, 100000000000000000000]",
+        "[1,0,100,1.5,0.001,1e-7,1e+21,100000000000000000000]"
+    )]
+    #[test_case("[4.50, 2e-3, 1E30, -1.25e-10]", "[4.5,0.002,1e+30,-1.25e-10]")]
+    fn canonical(lex: &str, expected: &str) {
+        assert_eq!(canonicalize(lex).unwrap(), expected);
+    }
+
+    #[test_case("1e400" ; "positive")]
+    #[test_case("-1e400" ; "negative")]
+    #[test_case(r#"{"a": [1, 1E999]}"# ; "nested")]
+    fn out_of_range(lex: &str) {
+        assert!(matches!(
+            canonicalize(lex),
+            Err(CanonicalJsonError::NumberOutOfRange(_))
+        ));
+        assert!(!json_eq(lex, "null"));
+    }
+
+    #[test]
+    fn eq() {
+        assert!(json_eq(r#"{"a": 1, "b": 2}"#, r#"{"b":2.0,"a":1}"#));
+        assert!(!json_eq(r#"{"a": 1}"#, r#"{"a": "1"}"#));
+        assert!(!json_eq("[1, 2]", "[2, 1]"));
+        assert!(!json_eq("{", "{}"));
+        assert!(json_eq("{", "{"));
+    }
+
+    #[test]
+    fn handler_parses() {
+        let h = handler();
+        assert!(h.is_valid("[1, 2]"));
+        assert!(!h.is_valid("[1, 2"));
+        let v = h
+            .parse(r#"{"a": 1}"#)
+            .unwrap()
+            .downcast::<JsonValue>()
+            .unwrap();
+        assert_eq!(v["a"].as_i32(), Some(1));
+    }
+}
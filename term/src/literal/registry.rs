@@ -0,0 +1,458 @@
+//! A registry of datatypes, for validating and parsing the lexical values of literals.
+//!
+//! A [`DatatypeRegistry`] maps datatype IRIs to a [`DatatypeHandler`],
+//! which checks whether a lexical value is valid for the datatype,
+//! and optionally parses it into a native value.
+//! The [default](struct.DatatypeRegistry.html#method.default) registry
+//! knows the most common XSD datatypes, as well as `rdf:JSON` and `rdf:HTML`,
+//! and users can [register](struct.DatatypeRegistry.html#method.register) their own
+//! (e.g. `geo:wktLiteral`).
+//!
+//! ```
+//! # use sophia_term::literal::registry::*;
+//! # use sophia_term::StaticTerm;
+//! # use sophia_api::ns::{rdf, xsd};
+//! # use sophia_api::term::SimpleIri;
+//! let mut registry = DatatypeRegistry::default();
+//! let lit = StaticTerm::new_literal_dt_unchecked("042", xsd::integer);
+//! assert_eq!(registry.is_valid(&lit), Some(true));
+//! assert_eq!(registry.parse_as::<i64, _>(&lit), Some(42));
+//!
+//! let wkt = SimpleIri::new("http://www.opengis.net/ont/geosparql#", Some("wktLiteral"))?;
+//! registry.register(&wkt, DatatypeHandler::new(|txt| txt.trim_start().starts_with("POINT")));
+//! let lit = StaticTerm::new_literal_dt_unchecked("POINT(1 2)", wkt);
+//! assert_eq!(registry.is_valid(&lit), Some(true));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`DatatypeRegistry`]: struct.DatatypeRegistry.html
+//! [`DatatypeHandler`]: struct.DatatypeHandler.html
+
+use super::convert::DataType;
+use super::datetime::DateTime;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{TTerm, TermKind};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Checks whether a lexical value is valid for a given datatype.
+pub type LexicalValidator = fn(&str) -> bool;
+
+/// Parses a (valid) lexical value into a native value.
+///
+/// Returns `None` if the value can not be represented by the native type
+/// (e.g. an `xsd:integer` too big for an `i64`).
+pub type ValueParser = fn(&str) -> Option<Box<dyn Any>>;
+
+/// How the lexical values of a given datatype are validated and parsed.
+#[derive(Clone, Copy)]
+pub struct DatatypeHandler {
+    validate: LexicalValidator,
+    parse: Option<ValueParser>,
+}
+
+impl DatatypeHandler {
+    /// A handler validating lexical values with `validate`, with no value parser.
+    pub fn new(validate: LexicalValidator) -> Self {
+        DatatypeHandler {
+            validate,
+            parse: None,
+        }
+    }
+
+    /// A handler validating lexical values with `validate`, and parsing them with `parse`.
+    pub fn with_parser(validate: LexicalValidator, parse: ValueParser) -> Self {
+        DatatypeHandler {
+            validate,
+            parse: Some(parse),
+        }
+    }
+
+    /// A handler validating and parsing lexical values with the `FromStr` implementation of `T`.
+    pub fn from_native<T>() -> Self
+    where
+        T: FromStr + Any,
+    {
+        DatatypeHandler::with_parser(valid_for::<T>, parse_as::<T>)
+    }
+
+    /// Check whether `lex` is a valid lexical value.
+    pub fn is_valid(&self, lex: &str) -> bool {
+        (self.validate)(lex)
+    }
+
+    /// Parse `lex` into a native value,
+    /// or return `None` if it is not valid, or if this handler has no value parser.
+    pub fn parse(&self, lex: &str) -> Option<Box<dyn Any>> {
+        match self.parse {
+            Some(parse) if self.is_valid(lex) => parse(lex),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for DatatypeHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DatatypeHandler")
+            .field("parse", &self.parse.is_some())
+            .finish()
+    }
+}
+
+/// A registry of [`DatatypeHandler`](struct.DatatypeHandler.html)s,
+/// indexed by datatype IRIs.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct DatatypeRegistry {
+    handlers: HashMap<String, DatatypeHandler>,
+}
+
+impl DatatypeRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        DatatypeRegistry {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Register `handler` for datatype `dt`,
+    /// and return the handler previously registered for `dt`, if any.
+    pub fn register<T>(&mut self, dt: &T, handler: DatatypeHandler) -> Option<DatatypeHandler>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.handlers.insert(dt.value().to_string(), handler)
+    }
+
+    /// Register the native type `T`,
+    /// using its `FromStr` implementation to validate and parse lexical values.
+    pub fn register_native<T>(&mut self) -> Option<DatatypeHandler>
+    where
+        T: DataType + FromStr + Any,
+    {
+        self.register(&T::iri(), DatatypeHandler::from_native::<T>())
+    }
+
+    /// Remove the handler registered for datatype `dt`, and return it.
+    pub fn unregister<T>(&mut self, dt: &T) -> Option<DatatypeHandler>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.handlers.remove(&dt.value()[..])
+    }
+
+    /// The handler registered for datatype `dt`, if any.
+    pub fn get<T>(&self, dt: &T) -> Option<&DatatypeHandler>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.handlers.get(&dt.value()[..])
+    }
+
+    /// Check whether the lexical value of literal `lit` is valid for its datatype.
+    ///
+    /// Returns `None` if `lit` is not a literal, or if its datatype is not registered.
+    /// Language-tagged strings are always valid.
+    pub fn is_valid<T>(&self, lit: &T) -> Option<bool>
+    where
+        T: TTerm + ?Sized,
+    {
+        if lit.kind() != TermKind::Literal {
+            return None;
+        }
+        if lit.language().is_some() {
+            return Some(true);
+        }
+        let handler = self.get(&lit.datatype()?)?;
+        Some(handler.is_valid(lit.value_raw().0))
+    }
+
+    /// Parse the lexical value of literal `lit` according to its datatype.
+    ///
+    /// Returns `None` if `lit` is not a literal, if its datatype is not registered
+    /// or has no value parser, or if its lexical value is not valid.
+    pub fn parse<T>(&self, lit: &T) -> Option<Box<dyn Any>>
+    where
+        T: TTerm + ?Sized,
+    {
+        if lit.kind() != TermKind::Literal {
+            return None;
+        }
+        let handler = self.get(&lit.datatype()?)?;
+        handler.parse(lit.value_raw().0)
+    }
+
+    /// Parse the lexical value of literal `lit` as a `V`.
+    ///
+    /// Returns `None` in the same cases as [`parse`](#method.parse),
+    /// and if the parsed value is not a `V`.
+    pub fn parse_as<V, T>(&self, lit: &T) -> Option<V>
+    where
+        V: Any,
+        T: TTerm + ?Sized,
+    {
+        self.parse(lit)?.downcast().ok().map(|v| *v)
+    }
+}
+
+impl Default for DatatypeRegistry {
+    /// A registry pre-populated with the most common XSD datatypes,
+    /// `rdf:JSON` (with the `json` feature) and `rdf:HTML`.
+    ///
+    /// Their values are parsed as follows:
+    /// * `xsd:string`, `xsd:anyURI`: `String`,
+    /// * `xsd:boolean`: `bool`,
+    /// * `xsd:decimal`, `xsd:double`, `xsd:float`: `f64` (possibly with a loss of precision),
+    /// * `xsd:integer` and its unbounded sub-types: `i64`,
+    /// * bounded integer types: the corresponding Rust type (e.g. `u8` for `xsd:unsignedByte`),
+    /// * `xsd:dateTime`: [`DateTime`](../datetime/struct.DateTime.html),
+    /// * `rdf:JSON`: [`JsonValue`](../json/enum.JsonValue.html),
+    /// * `rdf:HTML`: its [canonical form](../html/fn.canonicalize.html), as a `String`.
+    fn default() -> Self {
+        let mut reg = DatatypeRegistry::new();
+        reg.register(
+            &xsd::string,
+            DatatypeHandler::with_parser(|_| true, |txt| Some(Box::new(txt.to_string()))),
+        );
+        reg.register(
+            &xsd::anyURI,
+            DatatypeHandler::with_parser(sophia_iri::is_valid_iri_ref, |txt| {
+                Some(Box::new(txt.to_string()))
+            }),
+        );
+        reg.register(
+            &xsd::boolean,
+            DatatypeHandler::with_parser(
+                |txt| matches!(txt, "true" | "false" | "1" | "0"),
+                |txt| Some(Box::new(txt == "true" || txt == "1")),
+            ),
+        );
+        reg.register(
+            &xsd::decimal,
+            DatatypeHandler::with_parser(is_decimal, parse_as::<f64>),
+        );
+        let double = DatatypeHandler::with_parser(is_double, parse_double);
+        reg.register(&xsd::double, double);
+        reg.register(&xsd::float, double);
+        reg.register(
+            &xsd::integer,
+            DatatypeHandler::with_parser(is_integer, parse_as::<i64>),
+        );
+        reg.register(
+            &xsd::nonNegativeInteger,
+            DatatypeHandler::with_parser(|txt| is_integer(txt) && sign(txt) >= 0, parse_as::<i64>),
+        );
+        reg.register(
+            &xsd::positiveInteger,
+            DatatypeHandler::with_parser(|txt| is_integer(txt) && sign(txt) > 0, parse_as::<i64>),
+        );
+        reg.register(
+            &xsd::nonPositiveInteger,
+            DatatypeHandler::with_parser(|txt| is_integer(txt) && sign(txt) <= 0, parse_as::<i64>),
+        );
+        reg.register(
+            &xsd::negativeInteger,
+            DatatypeHandler::with_parser(|txt| is_integer(txt) && sign(txt) < 0, parse_as::<i64>),
+        );
+        reg.register(&xsd::long, bounded_integer::<i64>());
+        reg.register(&xsd::int, bounded_integer::<i32>());
+        reg.register(&xsd::short, bounded_integer::<i16>());
+        reg.register(&xsd::byte, bounded_integer::<i8>());
+        reg.register(&xsd::unsignedLong, bounded_integer::<u64>());
+        reg.register(&xsd::unsignedInt, bounded_integer::<u32>());
+        reg.register(&xsd::unsignedShort, bounded_integer::<u16>());
+        reg.register(&xsd::unsignedByte, bounded_integer::<u8>());
+        reg.register_native::<DateTime>();
+        #[cfg(feature = "json")]
+        reg.register(&rdf::JSON, super::json::handler());
+        reg.register(&rdf::HTML, super::html::handler());
+        reg
+    }
+}
+
+fn valid_for<T: FromStr>(txt: &str) -> bool {
+    txt.parse::<T>().is_ok()
+}
+
+fn parse_as<T: FromStr + Any>(txt: &str) -> Option<Box<dyn Any>> {
+    txt.parse::<T>().ok().map(|v| Box::new(v) as Box<dyn Any>)
+}
+
+fn bounded_integer<T: FromStr + Any>() -> DatatypeHandler {
+    DatatypeHandler::with_parser(|txt| is_integer(txt) && valid_for::<T>(txt), parse_as::<T>)
+}
+
+/// Split an optional leading sign.
+pub(crate) fn unsigned(txt: &str) -> &str {
+    txt.strip_prefix(|c: char| c == '+' || c == '-')
+        .unwrap_or(txt)
+}
+
+/// `[+-]?[0-9]+`
+pub(crate) fn is_integer(txt: &str) -> bool {
+    let digits = unsigned(txt);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The sign (-1, 0 or 1) of a valid integer.
+fn sign(txt: &str) -> i8 {
+    if unsigned(txt).bytes().all(|b| b == b'0') {
+        0
+    } else if txt.starts_with('-') {
+        -1
+    } else {
+        1
+    }
+}
+
+/// `[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)`
+pub(crate) fn is_decimal(txt: &str) -> bool {
+    let mut parts = unsigned(txt).splitn(2, '.');
+    let int = parts.next().unwrap();
+    let frac = parts.next().unwrap_or("");
+    !(int.is_empty() && frac.is_empty())
+        && int.bytes().all(|b| b.is_ascii_digit())
+        && frac.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)([Ee][+-]?[0-9]+)?|[+-]?INF|NaN`
+pub(crate) fn is_double(txt: &str) -> bool {
+    if matches!(txt, "INF" | "+INF" | "-INF" | "NaN") {
+        return true;
+    }
+    let mut parts = txt.splitn(2, &['e', 'E'][..]);
+    let mantissa = parts.next().unwrap();
+    is_decimal(mantissa) && parts.next().is_none_or(is_integer)
+}
+
+fn parse_double(txt: &str) -> Option<Box<dyn Any>> {
+    let value = match txt {
+        "INF" | "+INF" => f64::INFINITY,
+        "-INF" => f64::NEG_INFINITY,
+        "NaN" => f64::NAN,
+        _ => txt.parse().ok()?,
+    };
+    Some(Box::new(value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StaticTerm;
+    use sophia_api::term::SimpleIri;
+    use test_case::test_case;
+
+    fn lit(txt: &'static str, dt: SimpleIri<'static>) -> StaticTerm {
+        StaticTerm::new_literal_dt_unchecked(txt, dt)
+    }
+
+    #[test_case("abc", xsd::string, true)]
+    #[test_case("http://example.org/", xsd::anyURI, true)]
+    #[test_case("not an iri", xsd::anyURI, false)]
+    #[test_case("1", xsd::boolean, true)]
+    #[test_case("True", xsd::boolean, false)]
+    #[test_case("-1.5", xsd::decimal, true)]
+    #[test_case(".5", xsd::decimal, true)]
+    #[test_case("1.", xsd::decimal, true)]
+    #[test_case(".", xsd::decimal, false)]
+    #[test_case("1e5", xsd::decimal, false)]
+    #[test_case("1.5E-3", xsd::double, true)]
+    #[test_case("-INF", xsd::double, true)]
+    #[test_case("inf", xsd::double, false)]
+    #[test_case("1e", xsd::float, false)]
+    #[test_case("+0042", xsd::integer, true)]
+    #[test_case("123456789012345678901234567890", xsd::integer, true)]
+    #[test_case("4 2", xsd::integer, false)]
+    #[test_case("-0", xsd::nonNegativeInteger, true)]
+    #[test_case("-1", xsd::nonNegativeInteger, false)]
+    #[test_case("0", xsd::positiveInteger, false)]
+    #[test_case("-3", xsd::negativeInteger, true)]
+    #[test_case("128", xsd::byte, false)]
+    #[test_case("-128", xsd::byte, true)]
+    #[test_case("255", xsd::unsignedByte, true)]
+    #[test_case("-1", xsd::unsignedInt, false)]
+    #[test_case("2020-01-01T00:00:00Z", xsd::dateTime, true)]
+    #[test_case("2020-01-01", xsd::dateTime, false)]
+    #[test_case("<p>unclosed", rdf::HTML, true)]
+    fn validation(txt: &'static str, dt: SimpleIri<'static>, valid: bool) {
+        let reg = DatatypeRegistry::default();
+        assert_eq!(reg.is_valid(&lit(txt, dt)), Some(valid));
+    }
+
+    #[cfg(feature = "json")]
+    #[test_case("{\"a\": [1, 2]}", true)]
+    #[test_case("{a: 1}", false)]
+    fn json_validation(txt: &'static str, valid: bool) {
+        let reg = DatatypeRegistry::default();
+        assert_eq!(reg.is_valid(&lit(txt, rdf::JSON)), Some(valid));
+    }
+
+    #[test]
+    fn parsing() {
+        let reg = DatatypeRegistry::default();
+        assert_eq!(reg.parse_as::<bool, _>(&lit("1", xsd::boolean)), Some(true));
+        assert_eq!(
+            reg.parse_as::<i64, _>(&lit("+0042", xsd::integer)),
+            Some(42)
+        );
+        assert_eq!(
+            reg.parse_as::<u8, _>(&lit("255", xsd::unsignedByte)),
+            Some(255)
+        );
+        assert_eq!(
+            reg.parse_as::<f64, _>(&lit("-INF", xsd::double)),
+            Some(f64::NEG_INFINITY)
+        );
+        assert_eq!(reg.parse_as::<f64, _>(&lit("1.5", xsd::decimal)), Some(1.5));
+        assert_eq!(
+            reg.parse_as::<String, _>(&lit("abc", xsd::string)),
+            Some("abc".to_string())
+        );
+        assert_eq!(
+            reg.parse_as::<DateTime, _>(&lit("2020-01-01T02:00:00+02:00", xsd::dateTime)),
+            "2020-01-01T00:00:00Z".parse().ok()
+        );
+        assert_eq!(
+            reg.parse_as::<String, _>(&lit("<P>x</P>", rdf::HTML)),
+            Some("<p>x</p>".to_string())
+        );
+        #[cfg(feature = "json")]
+        assert!(reg
+            .parse_as::<super::super::json::JsonValue, _>(&lit("[1, 2]", rdf::JSON))
+            .is_some());
+        // invalid lexical value
+        assert_eq!(reg.parse_as::<i64, _>(&lit("4 2", xsd::integer)), None);
+        // valid, but too big for i64
+        assert_eq!(
+            reg.parse_as::<i64, _>(&lit("123456789012345678901234567890", xsd::integer)),
+            None
+        );
+        // wrong native type
+        assert_eq!(reg.parse_as::<i32, _>(&lit("42", xsd::integer)), None);
+    }
+
+    #[test]
+    fn unknown() {
+        let mut reg = DatatypeRegistry::default();
+        let wkt =
+            SimpleIri::new("http://www.opengis.net/ont/geosparql#", Some("wktLiteral")).unwrap();
+        let point = lit("POINT(1 2)", wkt);
+        assert_eq!(reg.is_valid(&point), None);
+        assert_eq!(
+            reg.is_valid(&StaticTerm::new_iri_unchecked("http://example.org/")),
+            None
+        );
+        assert_eq!(
+            reg.is_valid(&StaticTerm::new_literal_lang_unchecked("chat", "fr")),
+            Some(true)
+        );
+
+        reg.register(&wkt, DatatypeHandler::new(|txt| txt.starts_with("POINT")));
+        assert_eq!(reg.is_valid(&point), Some(true));
+        assert!(reg.unregister(&wkt).is_some());
+        assert_eq!(reg.is_valid(&point), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Case-insensitive (and optionally diacritic-insensitive) matching of literal values.
+//!
+//! [`TextMatcher`] compares the lexical value of literals to a given text,
+//! after applying Unicode [case folding] to both,
+//! and optionally removing their diacritics (so that `"Café"` matches `"cafe"`).
+//! It can be used with any method accepting a [`TermMatcher`],
+//! e.g. [`Graph::triples_matching`], for simple search-like lookups.
+//!
+//! NB: the matcher checks every candidate literal,
+//! so it does not benefit from the indexes of graphs and datasets.
+//!
+//! This module requires the `text` feature.
+//!
+//! [`TextMatcher`]: struct.TextMatcher.html
+//! [case folding]: https://www.unicode.org/reports/tr44/#CaseFolding.txt
+//! [`TermMatcher`]: https://docs.rs/sophia_api/0.6/sophia_api/term/matcher/trait.TermMatcher.html
+//! [`Graph::triples_matching`]: https://docs.rs/sophia_api/0.6/sophia_api/graph/trait.Graph.html#method.triples_matching
+
+use caseless::default_case_fold_str;
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{SimpleIri, TTerm, TermKind};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Fold `txt` for case-insensitive comparison.
+///
+/// The result is in [NFC], unless `strip_diacritics` is true,
+/// in which case all combining marks are removed from its [NFD].
+///
+/// [NFC]: https://unicode.org/reports/tr15/#Norm_Forms
+/// [NFD]: https://unicode.org/reports/tr15/#Norm_Forms
+pub fn fold(txt: &str, strip_diacritics: bool) -> String {
+    let folded = default_case_fold_str(&txt.nfd().collect::<String>());
+    if strip_diacritics {
+        folded.nfd().filter(|c| !is_combining_mark(*c)).collect()
+    } else {
+        folded.nfc().collect()
+    }
+}
+
+/// How a [`TextMatcher`](struct.TextMatcher.html) compares literal values to its text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextMatch {
+    /// The value must be equal to the text.
+    Equal,
+    /// The value must start with the text.
+    StartsWith,
+    /// The value must contain the text.
+    Contains,
+}
+
+/// A matcher for literals whose value matches a given text,
+/// ignoring case, and optionally diacritics.
+///
+/// Language tags and datatypes are ignored;
+/// terms other than literals never match.
+///
+/// # Example
+/// ```
+/// # use sophia_term::literal::text::TextMatcher;
+/// # use sophia_term::StaticTerm;
+/// # use sophia_api::term::matcher::TermMatcher;
+/// let mut m = TextMatcher::contains("cafe");
+/// let t = StaticTerm::new_literal_lang_unchecked("Le CAFÉ du coin", "fr");
+/// assert!(!m.matches(&t));
+/// m.set_strip_diacritics(true);
+/// assert!(m.matches(&t));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextMatcher {
+    text: String,
+    folded: String,
+    mode: TextMatch,
+    strip_diacritics: bool,
+}
+
+impl TextMatcher {
+    /// Build a matcher comparing literal values to `text` according to `mode`.
+    ///
+    /// Diacritics are not stripped by default.
+    pub fn new<T: Into<String>>(text: T, mode: TextMatch) -> Self {
+        let text = text.into();
+        TextMatcher {
+            folded: fold(&text, false),
+            text,
+            mode,
+            strip_diacritics: false,
+        }
+    }
+
+    /// Build a matcher for literals whose value is equal to `text`.
+    pub fn equal<T: Into<String>>(text: T) -> Self {
+        Self::new(text, TextMatch::Equal)
+    }
+
+    /// Build a matcher for literals whose value starts with `text`.
+    pub fn starts_with<T: Into<String>>(text: T) -> Self {
+        Self::new(text, TextMatch::StartsWith)
+    }
+
+    /// Build a matcher for literals whose value contains `text`.
+    pub fn contains<T: Into<String>>(text: T) -> Self {
+        Self::new(text, TextMatch::Contains)
+    }
+
+    /// The text of this matcher, as given on creation.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// How this matcher compares literal values to its text.
+    pub fn mode(&self) -> TextMatch {
+        self.mode
+    }
+
+    /// Whether diacritics are ignored by this matcher.
+    pub fn strip_diacritics(&self) -> bool {
+        self.strip_diacritics
+    }
+
+    /// Set whether diacritics are ignored by this matcher (defaults to false).
+    pub fn set_strip_diacritics(&mut self, strip_diacritics: bool) -> &mut Self {
+        self.strip_diacritics = strip_diacritics;
+        self.folded = fold(&self.text, strip_diacritics);
+        self
+    }
+
+    /// Check whether `value` matches the text of this matcher.
+    pub fn matches_str(&self, value: &str) -> bool {
+        let value = fold(value, self.strip_diacritics);
+        match self.mode {
+            TextMatch::Equal => value == self.folded,
+            TextMatch::StartsWith => value.starts_with(&self.folded),
+            TextMatch::Contains => value.contains(&self.folded),
+        }
+    }
+}
+
+impl TermMatcher for TextMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        t.kind() == TermKind::Literal && self.matches_str(&t.value())
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StaticTerm;
+    use sophia_api::ns::xsd;
+    use test_case::test_case;
+
+    #[test_case("Hello", false, "hello" ; "ascii")]
+    #[test_case("STRASSE", false, "strasse" ; "ascii upper")]
+    #[test_case("Straße", false, "strasse" ; "sharp s")]
+    #[test_case("ΣΊΣΥΦΟΣ", false, "σίσυφοσ" ; "final sigma")]
+    #[test_case("Ćafé", false, "ćafé" ; "keep diacritics")]
+    #[test_case("Ćafe\u{301}", false, "ćafé" ; "recomposed")]
+    #[test_case("Ćafé", true, "cafe" ; "strip diacritics")]
+    #[test_case("Ångström", true, "angstrom" ; "strip ring and umlaut")]
+    fn fold_text(txt: &str, strip_diacritics: bool, expected: &str) {
+        assert_eq!(fold(txt, strip_diacritics), expected);
+    }
+
+    #[test]
+    fn modes() {
+        let m = TextMatcher::equal("the  Cafe");
+        assert!(m.matches_str("THE  CAFE"));
+        assert!(!m.matches_str("the cafe"));
+        assert!(!m.matches_str("the  Cafe!"));
+
+        let m = TextMatcher::starts_with("straße");
+        assert!(m.matches_str("STRASSENBAHN"));
+        assert!(!m.matches_str("Hauptstraße"));
+
+        let m = TextMatcher::contains("straße");
+        assert!(m.matches_str("HAUPTSTRASSE 12"));
+        assert!(!m.matches_str("Hauptstrase"));
+    }
+
+    #[test]
+    fn diacritics() {
+        let mut m = TextMatcher::equal("Élève");
+        assert!(m.matches_str("éLÈVE"));
+        assert!(m.matches_str("e\u{301}le\u{300}ve"));
+        assert!(!m.matches_str("eleve"));
+        m.set_strip_diacritics(true);
+        assert!(m.strip_diacritics());
+        assert!(m.matches_str("eleve"));
+        assert!(m.matches_str("ÉLEVE"));
+        assert_eq!(m.text(), "Élève");
+    }
+
+    #[test]
+    fn as_matcher() {
+        let m = TextMatcher::contains("bob");
+        assert!(m.constant().is_none());
+        let lang = StaticTerm::new_literal_lang_unchecked("Bob Marley", "en");
+        let typed = StaticTerm::new_literal_dt_unchecked("BOB", xsd::token);
+        let iri = StaticTerm::new_iri_unchecked("http://example.org/bob");
+        let bnode = StaticTerm::new_bnode_unchecked("bob");
+        assert!(m.matches(&lang));
+        assert!(m.matches(&typed));
+        assert!(!m.matches(&iri));
+        assert!(!m.matches(&bnode));
+    }
+}